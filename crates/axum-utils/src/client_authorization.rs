@@ -4,7 +4,7 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 // Please see LICENSE in the repository root for full details.
 
-use std::collections::HashMap;
+use std::{collections::HashMap, str::FromStr};
 
 use axum::{
     BoxError, Json,
@@ -15,6 +15,7 @@ use axum::{
     response::IntoResponse,
 };
 use axum_extra::typed_header::{TypedHeader, TypedHeaderRejectionReason};
+use base64ct::{Base64UrlUnpadded, Encoding};
 use headers::{Authorization, authorization::Basic};
 use http::{Request, StatusCode};
 use mas_data_model::{Client, JwksOrJwksUri};
@@ -26,7 +27,9 @@ use mas_storage::{RepositoryAccess, oauth2::OAuth2ClientRepository};
 use oauth2_types::errors::{ClientError, ClientErrorCode};
 use serde::{Deserialize, de::DeserializeOwned};
 use serde_json::Value;
+use sha2::{Digest, Sha256};
 use thiserror::Error;
+use x509_cert::{Certificate, der::Decode, name::RdnSequence};
 
 static JWT_BEARER_CLIENT_ASSERTION: &str = "urn:ietf:params:oauth:client-assertion-type:jwt-bearer";
 
@@ -41,6 +44,55 @@ struct AuthorizedForm<F = ()> {
     inner: F,
 }
 
+/// A certificate presented by the client on a mutual-TLS connection, as per
+/// [RFC 8705].
+///
+/// It is added to the request extensions by the listener accepting the
+/// connection.
+///
+/// [RFC 8705]: https://www.rfc-editor.org/rfc/rfc8705
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientCertificate {
+    der: Vec<u8>,
+    trusted: bool,
+}
+
+impl ClientCertificate {
+    /// Create a new [`ClientCertificate`] from its DER encoding.
+    ///
+    /// `trusted` tells whether the certificate was verified by the listener
+    /// against its trusted certificate authorities.
+    #[must_use]
+    pub const fn new(der: Vec<u8>, trusted: bool) -> Self {
+        Self { der, trusted }
+    }
+
+    /// The DER encoding of the certificate
+    #[must_use]
+    pub fn der(&self) -> &[u8] {
+        &self.der
+    }
+
+    /// The SHA-256 thumbprint of the certificate, base64url-encoded, as used
+    /// in the `x5t#S256` confirmation method
+    #[must_use]
+    pub fn thumbprint_sha256(&self) -> String {
+        Base64UrlUnpadded::encode_string(&Sha256::digest(&self.der))
+    }
+
+    /// Check that the subject DN of the certificate matches the expected one
+    fn subject_matches(&self, expected: &str) -> Result<bool, CredentialsVerificationError> {
+        let expected = RdnSequence::from_str(expected)
+            .map_err(|_| CredentialsVerificationError::InvalidClientConfig)?;
+        let certificate = Certificate::from_der(&self.der)
+            .map_err(|_| CredentialsVerificationError::InvalidCertificate)?;
+
+        // Compare the RFC 4514 representations, so that the string types used
+        // in the certificate don't matter
+        Ok(certificate.tbs_certificate.subject.to_string() == expected.to_string())
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum Credentials {
     None {
@@ -58,6 +110,10 @@ pub enum Credentials {
         client_id: String,
         jwt: Box<Jwt<'static, HashMap<String, serde_json::Value>>>,
     },
+    ClientCertificate {
+        client_id: String,
+        certificate: ClientCertificate,
+    },
 }
 
 impl Credentials {
@@ -68,7 +124,17 @@ impl Credentials {
             Credentials::None { client_id }
             | Credentials::ClientSecretBasic { client_id, .. }
             | Credentials::ClientSecretPost { client_id, .. }
-            | Credentials::ClientAssertionJwtBearer { client_id, .. } => client_id,
+            | Credentials::ClientAssertionJwtBearer { client_id, .. }
+            | Credentials::ClientCertificate { client_id, .. } => client_id,
+        }
+    }
+
+    /// Get the certificate the client presented over mutual-TLS, if any
+    #[must_use]
+    pub fn client_certificate(&self) -> Option<&ClientCertificate> {
+        match self {
+            Credentials::ClientCertificate { certificate, .. } => Some(certificate),
+            _ => None,
         }
     }
 
@@ -86,7 +152,8 @@ impl Credentials {
             Credentials::None { client_id }
             | Credentials::ClientSecretBasic { client_id, .. }
             | Credentials::ClientSecretPost { client_id, .. }
-            | Credentials::ClientAssertionJwtBearer { client_id, .. } => client_id,
+            | Credentials::ClientAssertionJwtBearer { client_id, .. }
+            | Credentials::ClientCertificate { client_id, .. } => client_id,
         };
 
         repo.oauth2_client().find_by_client_id(client_id).await
@@ -106,7 +173,10 @@ impl Credentials {
         client: &Client,
     ) -> Result<(), CredentialsVerificationError> {
         match (self, method) {
-            (Credentials::None { .. }, OAuthClientAuthenticationMethod::None) => {}
+            (
+                Credentials::None { .. } | Credentials::ClientCertificate { .. },
+                OAuthClientAuthenticationMethod::None,
+            ) => {}
 
             (
                 Credentials::ClientSecretPost { client_secret, .. },
@@ -168,6 +238,48 @@ impl Credentials {
                     .map_err(|_| CredentialsVerificationError::InvalidAssertionSignature)?;
             }
 
+            (
+                Credentials::ClientCertificate { certificate, .. },
+                OAuthClientAuthenticationMethod::TlsClientAuth,
+            ) => {
+                // The certificate must have been issued by one of the trusted
+                // authorities, and be the one registered for this client
+                if !certificate.trusted {
+                    return Err(CredentialsVerificationError::UntrustedCertificate);
+                }
+
+                let subject_dn = client
+                    .tls_client_auth_subject_dn
+                    .as_deref()
+                    .ok_or(CredentialsVerificationError::InvalidClientConfig)?;
+
+                if !certificate.subject_matches(subject_dn)? {
+                    return Err(CredentialsVerificationError::CertificateMismatch);
+                }
+            }
+
+            (
+                Credentials::ClientCertificate { certificate, .. },
+                OAuthClientAuthenticationMethod::SelfSignedTlsClientAuth,
+            ) => {
+                // Get the client JWKS, which should contain the certificate
+                let jwks = client
+                    .jwks
+                    .as_ref()
+                    .ok_or(CredentialsVerificationError::InvalidClientConfig)?;
+
+                let jwks = fetch_jwks(http_client, jwks)
+                    .await
+                    .map_err(|_| CredentialsVerificationError::JwksFetchFailed)?;
+
+                if !jwks
+                    .iter()
+                    .any(|key| key.certificate() == Some(certificate.der()))
+                {
+                    return Err(CredentialsVerificationError::CertificateMismatch);
+                }
+            }
+
             (_, _) => {
                 return Err(CredentialsVerificationError::AuthenticationMethodMismatch);
             }
@@ -176,7 +288,12 @@ impl Credentials {
     }
 }
 
-async fn fetch_jwks(
+/// Get the JWKS of a client, fetching it from its `jwks_uri` if needed
+///
+/// # Errors
+///
+/// Returns an error if the JWKS could not be fetched
+pub async fn fetch_jwks(
     http_client: &reqwest::Client,
     jwks: &JwksOrJwksUri,
 ) -> Result<PublicJsonWebKeySet, BoxError> {
@@ -215,6 +332,15 @@ pub enum CredentialsVerificationError {
 
     #[error("failed to fetch jwks")]
     JwksFetchFailed,
+
+    #[error("invalid client certificate")]
+    InvalidCertificate,
+
+    #[error("client certificate was not issued by a trusted authority")]
+    UntrustedCertificate,
+
+    #[error("client certificate does not match the one registered")]
+    CertificateMismatch,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -338,6 +464,9 @@ where
         let header =
             TypedHeader::<Authorization<Basic>>::from_request_parts(&mut parts, state).await;
 
+        // Grab the certificate presented over mutual-TLS, if any
+        let certificate = parts.extensions.get::<ClientCertificate>().cloned();
+
         // Take the Authorization header
         let credentials_from_header = match header {
             Ok(header) => Some((header.username().to_owned(), header.password().to_owned())),
@@ -411,8 +540,16 @@ where
             }
 
             (None, Some(client_id), None, None, None) => {
-                // Only got a client_id in the form
-                Credentials::None { client_id }
+                // Only got a client_id in the form, the client may have authenticated with
+                // its certificate
+                if let Some(certificate) = certificate {
+                    Credentials::ClientCertificate {
+                        client_id,
+                        certificate,
+                    }
+                } else {
+                    Credentials::None { client_id }
+                }
             }
 
             (
@@ -501,6 +638,33 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn client_certificate_test() {
+        let certificate = ClientCertificate::new(b"certificate".to_vec(), true);
+        let mut req = Request::builder()
+            .method(Method::POST)
+            .header(
+                http::header::CONTENT_TYPE,
+                mime::APPLICATION_WWW_FORM_URLENCODED.as_ref(),
+            )
+            .body(Body::new("client_id=client-id&foo=bar".to_owned()))
+            .unwrap();
+        req.extensions_mut().insert(certificate.clone());
+
+        assert_eq!(
+            ClientAuthorization::<serde_json::Value>::from_request(req, &())
+                .await
+                .unwrap(),
+            ClientAuthorization {
+                credentials: Credentials::ClientCertificate {
+                    client_id: "client-id".to_owned(),
+                    certificate,
+                },
+                form: Some(serde_json::json!({"foo": "bar"})),
+            }
+        );
+    }
+
     #[tokio::test]
     async fn client_secret_basic_test() {
         let req = Request::builder()
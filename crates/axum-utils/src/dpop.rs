@@ -0,0 +1,190 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+//! Validation of [DPoP] proofs
+//!
+//! [DPoP]: https://www.rfc-editor.org/rfc/rfc9449
+
+use std::collections::HashMap;
+
+use base64ct::{Base64UrlUnpadded, Encoding};
+use chrono::Duration;
+use http::{HeaderMap, Method};
+use mas_iana::jose::JsonWebSignatureAlg;
+use mas_jose::{
+    claims::{self, ClaimError, TimeOptions},
+    jwa::AsymmetricVerifyingKey,
+    jwt::{Jwt, JwtDecodeError},
+};
+use mas_storage::{Clock, RepositoryAccess};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use url::Url;
+
+/// The name of the header carrying the `DPoP` proof
+pub const DPOP_HEADER: &str = "dpop";
+
+/// The JWS algorithms accepted for `DPoP` proofs
+pub const SUPPORTED_ALGORITHMS: [JsonWebSignatureAlg; 9] = [
+    JsonWebSignatureAlg::Rs256,
+    JsonWebSignatureAlg::Rs384,
+    JsonWebSignatureAlg::Rs512,
+    JsonWebSignatureAlg::Ps256,
+    JsonWebSignatureAlg::Ps384,
+    JsonWebSignatureAlg::Ps512,
+    JsonWebSignatureAlg::Es256,
+    JsonWebSignatureAlg::Es384,
+    JsonWebSignatureAlg::Es256K,
+];
+
+/// How long after being issued a `DPoP` proof is accepted
+const PROOF_LIFETIME: Duration = Duration::seconds(5 * 60);
+
+/// How far in the future the `iat` claim of a `DPoP` proof can be, to account
+/// for clock skew
+const PROOF_LEEWAY: Duration = Duration::seconds(30);
+
+#[derive(Debug, Error)]
+pub enum DpopProofError<E> {
+    #[error(transparent)]
+    Internal(E),
+
+    #[error("multiple DPoP proofs in the request")]
+    MultipleProofs,
+
+    #[error("the DPoP header is not valid UTF-8")]
+    InvalidHeader,
+
+    #[error("could not decode the DPoP proof")]
+    Decode(#[from] JwtDecodeError),
+
+    #[error("the DPoP proof has an invalid type")]
+    InvalidType,
+
+    #[error("the DPoP proof uses an unsupported algorithm")]
+    UnsupportedAlgorithm,
+
+    #[error("the DPoP proof does not embed a valid public key")]
+    InvalidKey,
+
+    #[error("the DPoP proof signature is invalid")]
+    InvalidSignature,
+
+    #[error("the DPoP proof has invalid claims")]
+    InvalidClaims(#[from] ClaimError),
+
+    #[error("the DPoP proof has expired")]
+    Expired,
+
+    #[error("the DPoP proof was issued for another request")]
+    RequestMismatch,
+
+    #[error("the DPoP proof was issued for another access token")]
+    AccessTokenMismatch,
+
+    #[error("the DPoP proof was already used")]
+    Replayed,
+}
+
+/// A `DPoP` proof which passed validation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DpopProof {
+    /// The JWK thumbprint of the key which signed the proof
+    pub jkt: String,
+}
+
+/// Validate the `DPoP` proof sent in the `DPoP` header of a request, if any
+///
+/// The `htm` and `htu` claims are checked against the given HTTP method and
+/// URL if `request` is set, and the `ath` claim against `access_token` if set.
+/// Valid proofs are recorded, so that they can't be replayed.
+///
+/// Returns [`None`] if the request has no `DPoP` proof.
+///
+/// # Errors
+///
+/// Returns an error if the proof is invalid, or if it was already used
+pub async fn verify_dpop_proof<E>(
+    clock: &impl Clock,
+    repo: &mut impl RepositoryAccess<Error = E>,
+    headers: &HeaderMap,
+    request: Option<(&Method, &Url)>,
+    access_token: Option<&str>,
+) -> Result<Option<DpopProof>, DpopProofError<E>> {
+    let mut values = headers.get_all(DPOP_HEADER).iter();
+    let Some(value) = values.next() else {
+        return Ok(None);
+    };
+
+    if values.next().is_some() {
+        return Err(DpopProofError::MultipleProofs);
+    }
+
+    let value = value.to_str().map_err(|_| DpopProofError::InvalidHeader)?;
+    let jwt: Jwt<'_, HashMap<String, serde_json::Value>> = Jwt::try_from(value)?;
+
+    if jwt.header().typ() != Some("dpop+jwt") {
+        return Err(DpopProofError::InvalidType);
+    }
+
+    let alg = jwt.header().alg();
+    if !SUPPORTED_ALGORITHMS.contains(alg) {
+        return Err(DpopProofError::UnsupportedAlgorithm);
+    }
+
+    // The proof is signed by the key it embeds
+    let jwk = jwt.header().jwk().ok_or(DpopProofError::InvalidKey)?;
+    let key = AsymmetricVerifyingKey::from_jwk_and_alg(jwk.params(), alg)
+        .map_err(|_| DpopProofError::InvalidKey)?;
+    jwt.verify(&key)
+        .map_err(|_| DpopProofError::InvalidSignature)?;
+
+    let jkt = jwk.params().thumbprint_sha256();
+
+    let (_header, mut claims) = jwt.into_parts();
+
+    let now = clock.now();
+    let jti = claims::JTI.extract_required(&mut claims)?;
+    let htm = claims::HTM.extract_required(&mut claims)?;
+    let htu = claims::HTU.extract_required(&mut claims)?;
+    let iat = claims::IAT
+        .extract_required_with_options(&mut claims, TimeOptions::new(now).leeway(PROOF_LEEWAY))?;
+
+    let expires_at = *iat + PROOF_LIFETIME;
+    if expires_at < now {
+        return Err(DpopProofError::Expired);
+    }
+
+    if let Some((method, uri)) = request {
+        // The query and fragment parts are ignored when comparing the URIs
+        let mut htu = Url::parse(&htu).map_err(|_| DpopProofError::RequestMismatch)?;
+        htu.set_query(None);
+        htu.set_fragment(None);
+
+        if htm != method.as_str() || htu.as_str() != uri.as_str() {
+            return Err(DpopProofError::RequestMismatch);
+        }
+    }
+
+    if let Some(access_token) = access_token {
+        let ath = claims::ATH.extract_required(&mut claims)?;
+        let expected = Base64UrlUnpadded::encode_string(&Sha256::digest(access_token));
+        if ath != expected {
+            return Err(DpopProofError::AccessTokenMismatch);
+        }
+    }
+
+    let first_use = repo
+        .oauth2_dpop_proof()
+        .record(clock, &jkt, &jti, expires_at)
+        .await
+        .map_err(DpopProofError::Internal)?;
+
+    if !first_use {
+        return Err(DpopProofError::Replayed);
+    }
+
+    Ok(Some(DpopProof { jkt }))
+}
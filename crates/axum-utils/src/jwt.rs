@@ -7,7 +7,7 @@
 use axum::response::{IntoResponse, Response};
 use axum_extra::typed_header::TypedHeader;
 use headers::ContentType;
-use mas_jose::jwt::Jwt;
+use mas_jose::{jwe::Jwe, jwt::Jwt};
 use mime::Mime;
 
 pub struct JwtResponse<T>(pub Jwt<'static, T>);
@@ -19,3 +19,15 @@ impl<T> IntoResponse for JwtResponse<T> {
         (TypedHeader(content_type), self.0.into_string()).into_response()
     }
 }
+
+/// A response containing a JWE, which payload is either a JWT or a JSON
+/// document
+pub struct JweResponse(pub Jwe);
+
+impl IntoResponse for JweResponse {
+    fn into_response(self) -> Response {
+        let application_jwt: Mime = "application/jwt".parse().unwrap();
+        let content_type = ContentType::from(application_jwt);
+        (TypedHeader(content_type), self.0.into_string()).into_response()
+    }
+}
@@ -10,6 +10,7 @@
 pub mod client_authorization;
 pub mod cookies;
 pub mod csrf;
+pub mod dpop;
 pub mod error_wrapper;
 pub mod fancy_error;
 pub mod jwt;
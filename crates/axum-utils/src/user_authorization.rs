@@ -8,14 +8,16 @@ use std::{collections::HashMap, error::Error};
 
 use axum::{
     extract::{
-        Form, FromRequest, FromRequestParts,
+        Form, FromRequest,
         rejection::{FailedToDeserializeForm, FormRejection},
     },
     response::{IntoResponse, Response},
 };
-use axum_extra::typed_header::{TypedHeader, TypedHeaderRejectionReason};
-use headers::{Authorization, Header, HeaderMapExt, HeaderName, authorization::Bearer};
-use http::{HeaderMap, HeaderValue, Request, StatusCode, header::WWW_AUTHENTICATE};
+use headers::{Header, HeaderMapExt, HeaderName};
+use http::{
+    HeaderMap, HeaderValue, Method, Request, StatusCode,
+    header::{AUTHORIZATION, WWW_AUTHENTICATE},
+};
 use mas_data_model::Session;
 use mas_storage::{
     Clock, RepositoryAccess,
@@ -23,6 +25,12 @@ use mas_storage::{
 };
 use serde::{Deserialize, de::DeserializeOwned};
 use thiserror::Error;
+use url::Url;
+
+use crate::{
+    client_authorization::ClientCertificate,
+    dpop::{DpopProofError, SUPPORTED_ALGORITHMS, verify_dpop_proof},
+};
 
 #[derive(Debug, Deserialize)]
 struct AuthorizedForm<F> {
@@ -37,6 +45,8 @@ struct AuthorizedForm<F> {
 enum AccessToken {
     Form(String),
     Header(String),
+    /// A token sent with the `DPoP` authentication scheme, as per RFC 9449
+    DPoP(String),
     None,
 }
 
@@ -46,7 +56,7 @@ impl AccessToken {
         repo: &mut impl RepositoryAccess<Error = E>,
     ) -> Result<(mas_data_model::AccessToken, Session), AuthorizationVerificationError<E>> {
         let token = match self {
-            AccessToken::Form(t) | AccessToken::Header(t) => t,
+            AccessToken::Form(t) | AccessToken::Header(t) | AccessToken::DPoP(t) => t,
             AccessToken::None => return Err(AuthorizationVerificationError::MissingToken),
         };
 
@@ -70,51 +80,115 @@ impl AccessToken {
 pub struct UserAuthorization<F = ()> {
     access_token: AccessToken,
     form: Option<F>,
+    method: Method,
+    headers: HeaderMap,
+    certificate: Option<ClientCertificate>,
 }
 
 impl<F: Send> UserAuthorization<F> {
+    /// Fetch the access token and its session, and check that the token is
+    /// valid and presented the way it is bound
+    async fn verify<E>(
+        self,
+        repo: &mut impl RepositoryAccess<Error = E>,
+        clock: &impl Clock,
+        endpoint: &Url,
+    ) -> Result<(mas_data_model::AccessToken, Session, Option<F>), AuthorizationVerificationError<E>>
+    {
+        let (token, session) = self.access_token.fetch(repo).await?;
+
+        if !token.is_valid(clock.now()) || !session.is_valid() {
+            return Err(AuthorizationVerificationError::InvalidToken);
+        }
+
+        // Tokens bound to a DPoP key must be sent with the DPoP scheme, alongside
+        // a proof signed by that key for this request, and only them
+        match (&token.dpop_jkt, &self.access_token) {
+            (Some(jkt), AccessToken::DPoP(access_token)) => {
+                let proof = verify_dpop_proof(
+                    clock,
+                    repo,
+                    &self.headers,
+                    Some((&self.method, endpoint)),
+                    Some(access_token),
+                )
+                .await
+                .map_err(|e| match e {
+                    DpopProofError::Internal(e) => AuthorizationVerificationError::Internal(e),
+                    e => AuthorizationVerificationError::InvalidDpopProof(e),
+                })?
+                .ok_or(AuthorizationVerificationError::MissingDpopProof)?;
+
+                if proof.jkt != *jkt {
+                    return Err(AuthorizationVerificationError::DpopKeyMismatch);
+                }
+            }
+            (Some(_), _) => return Err(AuthorizationVerificationError::DpopRequired),
+            (None, AccessToken::DPoP(_)) => {
+                return Err(AuthorizationVerificationError::InvalidToken);
+            }
+            (None, _) => {}
+        }
+
+        // Tokens bound to a certificate can only be used over a mutual-TLS
+        // connection with that certificate, as per RFC 8705
+        if let Some(x5t_s256) = &token.x5t_s256 {
+            if self
+                .certificate
+                .as_ref()
+                .is_none_or(|certificate| certificate.thumbprint_sha256() != *x5t_s256)
+            {
+                return Err(AuthorizationVerificationError::CertificateMismatch);
+            }
+        }
+
+        Ok((token, session, self.form))
+    }
+
     // TODO: take scopes to validate as parameter
     /// Verify a user authorization and return the session and the protected
     /// form value
     ///
+    /// `endpoint` is the URL of the endpoint being called, against which
+    /// `DPoP` proofs are checked.
+    ///
     /// # Errors
     ///
-    /// Returns an error if the token is invalid, if the user session ended or
-    /// if the form is missing
+    /// Returns an error if the token is invalid, if the user session ended, if
+    /// the token was not presented the way it is bound or if the form is
+    /// missing
     pub async fn protected_form<E>(
         self,
         repo: &mut impl RepositoryAccess<Error = E>,
         clock: &impl Clock,
+        endpoint: &Url,
     ) -> Result<(Session, F), AuthorizationVerificationError<E>> {
-        let Some(form) = self.form else {
+        let (_token, session, form) = self.verify(repo, clock, endpoint).await?;
+
+        let Some(form) = form else {
             return Err(AuthorizationVerificationError::MissingForm);
         };
 
-        let (token, session) = self.access_token.fetch(repo).await?;
-
-        if !token.is_valid(clock.now()) || !session.is_valid() {
-            return Err(AuthorizationVerificationError::InvalidToken);
-        }
-
         Ok((session, form))
     }
 
     // TODO: take scopes to validate as parameter
     /// Verify a user authorization and return the session
     ///
+    /// `endpoint` is the URL of the endpoint being called, against which
+    /// `DPoP` proofs are checked.
+    ///
     /// # Errors
     ///
-    /// Returns an error if the token is invalid or if the user session ended
+    /// Returns an error if the token is invalid, if the user session ended or
+    /// if the token was not presented the way it is bound
     pub async fn protected<E>(
         self,
         repo: &mut impl RepositoryAccess<Error = E>,
         clock: &impl Clock,
+        endpoint: &Url,
     ) -> Result<Session, AuthorizationVerificationError<E>> {
-        let (token, session) = self.access_token.fetch(repo).await?;
-
-        if !token.is_valid(clock.now()) || !session.is_valid() {
-            return Err(AuthorizationVerificationError::InvalidToken);
-        }
+        let (token, session, _form) = self.verify(repo, clock, endpoint).await?;
 
         if !token.is_used() {
             // Mark the token as used
@@ -143,6 +217,21 @@ pub enum AuthorizationVerificationError<E> {
     #[error("missing form")]
     MissingForm,
 
+    #[error("token is bound to a DPoP key but was not sent with the DPoP scheme")]
+    DpopRequired,
+
+    #[error("missing DPoP proof")]
+    MissingDpopProof,
+
+    #[error("invalid DPoP proof")]
+    InvalidDpopProof(#[source] DpopProofError<E>),
+
+    #[error("token is bound to another DPoP key")]
+    DpopKeyMismatch,
+
+    #[error("token is bound to another client certificate")]
+    CertificateMismatch,
+
     #[error(transparent)]
     Internal(#[from] E),
 }
@@ -150,6 +239,7 @@ pub enum AuthorizationVerificationError<E> {
 enum BearerError {
     InvalidRequest,
     InvalidToken,
+    InvalidDpopProof,
     #[allow(dead_code)]
     InsufficientScope {
         scope: Option<HeaderValue>,
@@ -161,6 +251,7 @@ impl BearerError {
         match self {
             BearerError::InvalidRequest => HeaderValue::from_static("invalid_request"),
             BearerError::InvalidToken => HeaderValue::from_static("invalid_token"),
+            BearerError::InvalidDpopProof => HeaderValue::from_static("invalid_dpop_proof"),
             BearerError::InsufficientScope { .. } => HeaderValue::from_static("insufficient_scope"),
         }
     }
@@ -185,6 +276,8 @@ enum WwwAuthenticate {
         error: BearerError,
         error_description: Option<HeaderValue>,
     },
+    /// The `DPoP` scheme, as per RFC 9449
+    DPoP { error: BearerError },
 }
 
 impl Header for WwwAuthenticate {
@@ -225,6 +318,19 @@ impl Header for WwwAuthenticate {
 
                 ("Bearer", params)
             }
+            WwwAuthenticate::DPoP { error } => {
+                let mut params = error.params();
+                params.insert("error", error.error());
+
+                let algs = SUPPORTED_ALGORITHMS
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                params.insert("algs", HeaderValue::from_str(&algs).unwrap());
+
+                ("DPoP", params)
+            }
         };
 
         let params = params.into_iter().map(|(k, v)| format!(" {k}={v:?}"));
@@ -268,7 +374,7 @@ where
                 });
                 (StatusCode::BAD_REQUEST, headers).into_response()
             }
-            Self::InvalidToken => {
+            Self::InvalidToken | Self::CertificateMismatch => {
                 let mut headers = HeaderMap::new();
 
                 headers.typed_insert(WwwAuthenticate::Bearer {
@@ -278,6 +384,22 @@ where
                 });
                 (StatusCode::BAD_REQUEST, headers).into_response()
             }
+            Self::DpopRequired | Self::DpopKeyMismatch => {
+                let mut headers = HeaderMap::new();
+
+                headers.typed_insert(WwwAuthenticate::DPoP {
+                    error: BearerError::InvalidToken,
+                });
+                (StatusCode::UNAUTHORIZED, headers).into_response()
+            }
+            Self::MissingDpopProof | Self::InvalidDpopProof(_) => {
+                let mut headers = HeaderMap::new();
+
+                headers.typed_insert(WwwAuthenticate::DPoP {
+                    error: BearerError::InvalidDpopProof,
+                });
+                (StatusCode::UNAUTHORIZED, headers).into_response()
+            }
             Self::Internal(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
         }
     }
@@ -294,21 +416,20 @@ where
         req: Request<axum::body::Body>,
         state: &S,
     ) -> Result<Self, Self::Rejection> {
-        let (mut parts, body) = req.into_parts();
-        let header =
-            TypedHeader::<Authorization<Bearer>>::from_request_parts(&mut parts, state).await;
-
-        // Take the Authorization header
-        let token_from_header = match header {
-            Ok(header) => Some(header.token().to_owned()),
-            Err(err) => match err.reason() {
-                // If it's missing it is fine
-                TypedHeaderRejectionReason::Missing => None,
-                // If the header could not be parsed, return the error
-                _ => return Err(UserAuthorizationError::InvalidHeader),
-            },
+        let (parts, body) = req.into_parts();
+
+        // Take the Authorization header, which can use either the Bearer or the
+        // DPoP scheme
+        let token_from_header = match parts.headers.get(AUTHORIZATION) {
+            Some(header) => Some(parse_authorization(header)?),
+            // If it's missing it is fine
+            None => None,
         };
 
+        let method = parts.method.clone();
+        let headers = parts.headers.clone();
+        // Grab the certificate presented over mutual-TLS, if any
+        let certificate = parts.extensions.get::<ClientCertificate>().cloned();
         let req = Request::from_parts(parts, body);
 
         // Take the form value
@@ -328,11 +449,42 @@ where
         let access_token = match (token_from_header, token_from_form) {
             // Ensure the token should not be in both the form and the access token
             (Some(_), Some(_)) => return Err(UserAuthorizationError::TokenInFormAndHeader),
-            (Some(t), None) => AccessToken::Header(t),
+            (Some(t), None) => t,
             (None, Some(t)) => AccessToken::Form(t),
             (None, None) => AccessToken::None,
         };
 
-        Ok(UserAuthorization { access_token, form })
+        Ok(UserAuthorization {
+            access_token,
+            form,
+            method,
+            headers,
+            certificate,
+        })
+    }
+}
+
+/// Parse the value of an `Authorization` header using either the `Bearer` or
+/// the `DPoP` scheme
+fn parse_authorization(header: &HeaderValue) -> Result<AccessToken, UserAuthorizationError> {
+    let header = header
+        .to_str()
+        .map_err(|_| UserAuthorizationError::InvalidHeader)?;
+    let (scheme, token) = header
+        .split_once(' ')
+        .ok_or(UserAuthorizationError::InvalidHeader)?;
+    let token = token.trim();
+
+    if token.is_empty() {
+        return Err(UserAuthorizationError::InvalidHeader);
+    }
+
+    // Authentication schemes are case-insensitive
+    if scheme.eq_ignore_ascii_case("bearer") {
+        Ok(AccessToken::Header(token.to_owned()))
+    } else if scheme.eq_ignore_ascii_case("dpop") {
+        Ok(AccessToken::DPoP(token.to_owned()))
+    } else {
+        Err(UserAuthorizationError::InvalidHeader)
     }
 }
@@ -11,10 +11,10 @@ use ipnetwork::IpNetwork;
 use mas_data_model::SiteConfig;
 use mas_handlers::{
     ActivityTracker, BoundActivityTracker, CookieManager, ErrorWrapper, GraphQLSchema, Limiter,
-    MetadataCache, RequesterFingerprint, passwords::PasswordManager,
+    MetadataCache, RequesterFingerprint, ldap::LdapBackend, passwords::PasswordManager,
 };
 use mas_i18n::Translator;
-use mas_keystore::{Encrypter, Keystore};
+use mas_keystore::{Encrypter, Keystore, SharedKeystore};
 use mas_matrix::HomeserverConnection;
 use mas_policy::{Policy, PolicyFactory};
 use mas_router::UrlBuilder;
@@ -32,7 +32,7 @@ use crate::telemetry::METER;
 pub struct AppState {
     pub pool: PgPool,
     pub templates: Templates,
-    pub key_store: Keystore,
+    pub key_store: SharedKeystore,
     pub cookie_manager: CookieManager,
     pub encrypter: Encrypter,
     pub url_builder: UrlBuilder,
@@ -41,6 +41,7 @@ pub struct AppState {
     pub graphql_schema: GraphQLSchema,
     pub http_client: reqwest::Client,
     pub password_manager: PasswordManager,
+    pub ldap: LdapBackend,
     pub metadata_cache: MetadataCache,
     pub site_config: SiteConfig,
     pub activity_tracker: ActivityTracker,
@@ -151,7 +152,7 @@ impl FromRef<AppState> for Arc<Translator> {
 
 impl FromRef<AppState> for Keystore {
     fn from_ref(input: &AppState) -> Self {
-        input.key_store.clone()
+        input.key_store.load()
     }
 }
 
@@ -179,6 +180,12 @@ impl FromRef<AppState> for PasswordManager {
     }
 }
 
+impl FromRef<AppState> for LdapBackend {
+    fn from_ref(input: &AppState) -> Self {
+        input.ldap.clone()
+    }
+}
+
 impl FromRef<AppState> for CookieManager {
     fn from_ref(input: &AppState) -> Self {
         input.cookie_manager.clone()
@@ -18,12 +18,12 @@ use mas_data_model::{Device, TokenType, Ulid, UpstreamOAuthProvider, User};
 use mas_email::Address;
 use mas_matrix::HomeserverConnection;
 use mas_storage::{
-    Clock, RepositoryAccess, SystemClock,
+    Clock, Pagination, RepositoryAccess, SystemClock,
     compat::{CompatAccessTokenRepository, CompatSessionFilter, CompatSessionRepository},
-    oauth2::OAuth2SessionFilter,
+    oauth2::{OAuth2InitialAccessTokenRepository, OAuth2SessionFilter},
     queue::{
         DeactivateUserJob, ProvisionUserJob, QueueJobRepositoryExt as _, ReactivateUserJob,
-        SyncDevicesJob,
+        SendBackchannelLogoutJob, SyncDevicesJob,
     },
     user::{BrowserSessionFilter, UserEmailRepository, UserPasswordRepository, UserRepository},
 };
@@ -94,6 +94,20 @@ enum Subcommand {
         admin: bool,
     },
 
+    /// Issue an initial access token, required to register OAuth 2.0 clients
+    /// when `client_registration.require_initial_access_token` is set
+    IssueInitialAccessToken {
+        /// Number of seconds after which the token expires. If not specified,
+        /// the token never expires.
+        #[arg(long)]
+        expires_in: Option<u32>,
+
+        /// Number of clients the token can be used to register. If not
+        /// specified, the number of uses is not limited.
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+        max_uses: Option<u32>,
+    },
+
     /// Trigger a provisioning job for all users
     ProvisionAllUsers,
 
@@ -323,6 +337,37 @@ impl Options {
                 Ok(ExitCode::SUCCESS)
             }
 
+            SC::IssueInitialAccessToken {
+                expires_in,
+                max_uses,
+            } => {
+                let _span = info_span!("cli.manage.issue_initial_access_token").entered();
+                let database_config = DatabaseConfig::extract_or_default(figment)?;
+                let mut conn = database_connection_from_config(&database_config).await?;
+                let txn = conn.begin().await?;
+                let mut repo = PgRepository::from_conn(txn);
+
+                let expires_after =
+                    expires_in.map(|expires_in| chrono::Duration::seconds(expires_in.into()));
+                let token = TokenType::InitialAccessToken.generate(&mut rng);
+
+                let initial_access_token = repo
+                    .oauth2_initial_access_token()
+                    .add(&mut rng, &clock, token, expires_after, max_uses)
+                    .await?;
+
+                repo.into_inner().commit().await?;
+
+                info!(
+                    %initial_access_token.id,
+                    initial_access_token.expires_at = initial_access_token.expires_at.map(tracing::field::display),
+                    initial_access_token.max_uses,
+                    "Initial access token issued: {}", initial_access_token.token
+                );
+
+                Ok(ExitCode::SUCCESS)
+            }
+
             SC::ProvisionAllUsers => {
                 let _span = info_span!("cli.manage.provision_all_users").entered();
                 let database_config = DatabaseConfig::extract_or_default(figment)?;
@@ -379,6 +424,30 @@ impl Options {
                 let affected = if dry_run {
                     repo.oauth2_session().count(filter).await?
                 } else {
+                    // Notify the clients through the back-channel before ending the
+                    // sessions. This covers the sessions started from the browser
+                    // sessions ended below, as those are ended here as well.
+                    let mut pagination = Pagination::first(100);
+                    loop {
+                        let page = repo.oauth2_session().list(filter, pagination).await?;
+                        for session in &page.edges {
+                            repo.queue_job()
+                                .schedule_job(
+                                    &mut rng,
+                                    &clock,
+                                    SendBackchannelLogoutJob::new(session),
+                                )
+                                .await?;
+                        }
+
+                        match page.edges.last() {
+                            Some(last) if page.has_next_page => {
+                                pagination = pagination.after(last.id);
+                            }
+                            _ => break,
+                        }
+                    }
+
                     repo.oauth2_session().finish_bulk(&clock, filter).await?
                 };
 
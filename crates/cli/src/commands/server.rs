@@ -13,7 +13,7 @@ use itertools::Itertools;
 use mas_config::{
     AppConfig, ClientsConfig, ConfigurationSection, ConfigurationSectionExt, UpstreamOAuth2Config,
 };
-use mas_handlers::{ActivityTracker, CookieManager, Limiter, MetadataCache};
+use mas_handlers::{ActivityTracker, CookieManager, Limiter, MetadataCache, ldap::LdapBackend};
 use mas_listener::server::Server;
 use mas_router::UrlBuilder;
 use mas_storage::SystemClock;
@@ -25,7 +25,7 @@ use crate::{
     app_state::AppState,
     lifecycle::LifecycleManager,
     util::{
-        database_pool_from_config, homeserver_connection_from_config,
+        database_pool_from_config, homeserver_connection_from_config, load_key_store_continuously,
         load_policy_factory_dynamic_data_continuously, mailer_from_config,
         password_manager_from_config, policy_factory_from_config, site_config_from_config,
         templates_from_config, test_mailer_in_background,
@@ -150,10 +150,24 @@ impl Options {
             &config.matrix,
             &config.experimental,
             &config.passwords,
+            &config.ldap,
             &config.account,
             &config.captcha,
+            &config.client_registration,
         )?;
 
+        // Load the signing keys generated in the database, if the automatic key rotation
+        // is enabled
+        let key_store = load_key_store_continuously(
+            key_store,
+            &site_config,
+            &encrypter,
+            &pool,
+            shutdown.soft_shutdown_token(),
+            shutdown.task_tracker(),
+        )
+        .await?;
+
         // Load and compile the templates
         let templates =
             templates_from_config(&config.templates, &site_config, &url_builder).await?;
@@ -175,6 +189,9 @@ impl Options {
                 homeserver_connection.clone(),
                 url_builder.clone(),
                 &site_config,
+                &key_store,
+                &encrypter,
+                &http_client,
                 shutdown.soft_shutdown_token(),
                 shutdown.task_tracker(),
             )
@@ -185,6 +202,9 @@ impl Options {
 
         let password_manager = password_manager_from_config(&config.passwords).await?;
 
+        // The LDAP directory used to check passwords, if any
+        let ldap = LdapBackend::new(&config.ldap);
+
         // The upstream OIDC metadata cache
         let metadata_cache = MetadataCache::new();
 
@@ -220,6 +240,7 @@ impl Options {
             password_manager.clone(),
             url_builder.clone(),
             limiter.clone(),
+            encrypter.clone(),
         );
 
         let state = {
@@ -235,6 +256,7 @@ impl Options {
                 graphql_schema,
                 http_client,
                 password_manager,
+                ldap,
                 metadata_cache,
                 site_config,
                 activity_tracker,
@@ -264,13 +286,21 @@ impl Options {
                 };
 
                 // and build the router
-                let router = crate::server::build_router(
+                let mut router = crate::server::build_router(
                     state.clone(),
                     &config.resources,
                     config.prefix.as_deref(),
                     config.name.as_deref(),
                 );
 
+                // Expose the client certificates if the listener asks for them
+                if let Some(client_auth) = config.tls.as_ref().and_then(|tls| tls.client_auth.as_ref()) {
+                    router = router.layer(axum::middleware::map_request_with_state(
+                        client_auth.has_certificate_authorities(),
+                        crate::server::add_client_certificate,
+                    ));
+                }
+
 
                 // Display some informations about where we'll be serving connections
                 let proto = if config.tls.is_some() { "https" } else { "http" };
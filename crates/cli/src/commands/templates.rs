@@ -9,8 +9,9 @@ use std::process::ExitCode;
 use clap::Parser;
 use figment::Figment;
 use mas_config::{
-    AccountConfig, BrandingConfig, CaptchaConfig, ConfigurationSection, ConfigurationSectionExt,
-    ExperimentalConfig, MatrixConfig, PasswordsConfig, TemplatesConfig,
+    AccountConfig, BrandingConfig, CaptchaConfig, ClientRegistrationConfig, ConfigurationSection,
+    ConfigurationSectionExt, ExperimentalConfig, LdapConfig, MatrixConfig, PasswordsConfig,
+    TemplatesConfig,
 };
 use mas_storage::{Clock, SystemClock};
 use rand::SeedableRng;
@@ -42,8 +43,11 @@ impl Options {
                 let matrix_config = MatrixConfig::extract(figment)?;
                 let experimental_config = ExperimentalConfig::extract_or_default(figment)?;
                 let password_config = PasswordsConfig::extract_or_default(figment)?;
+                let ldap_config = LdapConfig::extract_or_default(figment)?;
                 let account_config = AccountConfig::extract_or_default(figment)?;
                 let captcha_config = CaptchaConfig::extract_or_default(figment)?;
+                let client_registration_config =
+                    ClientRegistrationConfig::extract_or_default(figment)?;
 
                 let clock = SystemClock::default();
                 // XXX: we should disallow SeedableRng::from_entropy
@@ -55,8 +59,10 @@ impl Options {
                     &matrix_config,
                     &experimental_config,
                     &password_config,
+                    &ldap_config,
                     &account_config,
                     &captcha_config,
+                    &client_registration_config,
                 )?;
                 let templates =
                     templates_from_config(&template_config, &site_config, &url_builder).await?;
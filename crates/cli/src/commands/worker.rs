@@ -6,6 +6,7 @@
 
 use std::{process::ExitCode, time::Duration};
 
+use anyhow::Context;
 use clap::Parser;
 use figment::Figment;
 use mas_config::{AppConfig, ConfigurationSection};
@@ -15,8 +16,9 @@ use tracing::{info, info_span};
 use crate::{
     lifecycle::LifecycleManager,
     util::{
-        database_pool_from_config, homeserver_connection_from_config, mailer_from_config,
-        site_config_from_config, templates_from_config, test_mailer_in_background,
+        database_pool_from_config, homeserver_connection_from_config, load_key_store_continuously,
+        mailer_from_config, site_config_from_config, templates_from_config,
+        test_mailer_in_background,
     },
 };
 
@@ -45,8 +47,10 @@ impl Options {
             &config.matrix,
             &config.experimental,
             &config.passwords,
+            &config.ldap,
             &config.account,
             &config.captcha,
+            &config.client_registration,
         )?;
 
         // Load and compile the templates
@@ -56,8 +60,25 @@ impl Options {
         let mailer = mailer_from_config(&config.email, &templates)?;
         test_mailer_in_background(&mailer, Duration::from_secs(30));
 
+        // Initialize the key store
+        let key_store = config
+            .secrets
+            .key_store()
+            .await
+            .context("could not import keys from config")?;
+        let encrypter = config.secrets.encrypter();
+        let key_store = load_key_store_continuously(
+            key_store,
+            &site_config,
+            &encrypter,
+            &pool,
+            shutdown.soft_shutdown_token(),
+            shutdown.task_tracker(),
+        )
+        .await?;
+
         let http_client = mas_http::reqwest_client();
-        let conn = homeserver_connection_from_config(&config.matrix, http_client);
+        let conn = homeserver_connection_from_config(&config.matrix, http_client.clone());
 
         drop(config);
 
@@ -68,6 +89,9 @@ impl Options {
             conn,
             url_builder,
             &site_config,
+            &key_store,
+            &encrypter,
+            &http_client,
             shutdown.soft_shutdown_token(),
             shutdown.task_tracker(),
         )
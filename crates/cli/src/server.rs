@@ -8,19 +8,22 @@ use std::{
     future::ready,
     net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, ToSocketAddrs},
     os::unix::net::UnixListener,
+    sync::Arc,
 };
 
 use anyhow::Context;
 use axum::{
     Extension, Router,
+    body::Body,
     error_handling::HandleErrorLayer,
-    extract::{FromRef, MatchedPath},
+    extract::{FromRef, MatchedPath, State},
 };
 use hyper::{
     Method, Request, Response, StatusCode, Version,
     header::{CACHE_CONTROL, HeaderValue, USER_AGENT},
 };
 use listenfd::ListenFd;
+use mas_axum_utils::client_authorization::ClientCertificate;
 use mas_config::{HttpBindConfig, HttpResource, HttpTlsConfig, UnixOrTcp};
 use mas_listener::{ConnectionInfo, unix_or_tcp::UnixOrTcpListener};
 use mas_router::Route;
@@ -35,7 +38,16 @@ use opentelemetry_semantic_conventions::trace::{
     HTTP_REQUEST_METHOD, HTTP_RESPONSE_STATUS_CODE, HTTP_ROUTE, NETWORK_PROTOCOL_NAME,
     NETWORK_PROTOCOL_VERSION, URL_PATH, URL_QUERY, URL_SCHEME, USER_AGENT_ORIGINAL,
 };
-use rustls::ServerConfig;
+use rustls::{
+    DigitallySignedStruct, DistinguishedName, RootCertStore, ServerConfig, SignatureScheme,
+    client::danger::HandshakeSignatureValid,
+    crypto::{CryptoProvider, WebPkiSupportedAlgorithms},
+    pki_types::{CertificateDer, UnixTime},
+    server::{
+        WebPkiClientVerifier,
+        danger::{ClientCertVerified, ClientCertVerifier},
+    },
+};
 use sentry_tower::{NewSentryLayer, SentryHttpLayer};
 use tower::Layer;
 use tower_http::{services::ServeDir, set_header::SetResponseHeaderLayer};
@@ -282,11 +294,114 @@ pub fn build_router(
         .with_state(state)
 }
 
+/// Make the certificate the client presented over mutual-TLS available to the
+/// handlers, for the `tls_client_auth` and `self_signed_tls_client_auth`
+/// authentication methods
+///
+/// `trusted` tells whether the listener checked the certificates against its
+/// certificate authorities.
+pub async fn add_client_certificate(
+    State(trusted): State<bool>,
+    mut request: Request<Body>,
+) -> Request<Body> {
+    let certificate = request
+        .extensions()
+        .get::<ConnectionInfo>()
+        .and_then(ConnectionInfo::get_tls_ref)
+        .and_then(|tls| tls.peer_certificates.as_ref())
+        .and_then(|certificates| certificates.first())
+        .map(|certificate| ClientCertificate::new(certificate.to_vec(), trusted));
+
+    if let Some(certificate) = certificate {
+        request.extensions_mut().insert(certificate);
+    }
+
+    request
+}
+
+/// A client certificate verifier which accepts any certificate, only checking
+/// that the client has the corresponding private key.
+///
+/// The certificate itself is checked against the client JWKS with the
+/// `self_signed_tls_client_auth` authentication method.
+#[derive(Debug)]
+struct SelfSignedClientCertVerifier {
+    algorithms: WebPkiSupportedAlgorithms,
+}
+
+impl ClientCertVerifier for SelfSignedClientCertVerifier {
+    fn client_auth_mandatory(&self) -> bool {
+        false
+    }
+
+    fn root_hint_subjects(&self) -> &[DistinguishedName] {
+        &[]
+    }
+
+    fn verify_client_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _now: UnixTime,
+    ) -> Result<ClientCertVerified, rustls::Error> {
+        Ok(ClientCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.algorithms.supported_schemes()
+    }
+}
+
 pub fn build_tls_server_config(config: &HttpTlsConfig) -> Result<ServerConfig, anyhow::Error> {
     let (key, chain) = config.load()?;
 
-    let mut config = rustls::ServerConfig::builder()
-        .with_no_client_auth()
+    let builder = rustls::ServerConfig::builder();
+    let builder = if let Some(client_auth) = &config.client_auth {
+        let verifier: Arc<dyn ClientCertVerifier> = if let Some(certificate_authorities) =
+            client_auth.load()?
+        {
+            let mut roots = RootCertStore::empty();
+            for certificate in certificate_authorities {
+                roots
+                    .add(certificate)
+                    .context("invalid client certificate authority")?;
+            }
+
+            WebPkiClientVerifier::builder(Arc::new(roots))
+                .allow_unauthenticated()
+                .build()
+                .context("failed to build the client certificate verifier")?
+        } else {
+            let provider = CryptoProvider::get_default().context("no default crypto provider")?;
+            Arc::new(SelfSignedClientCertVerifier {
+                algorithms: provider.signature_verification_algorithms,
+            })
+        };
+
+        builder.with_client_cert_verifier(verifier)
+    } else {
+        builder.with_no_client_auth()
+    };
+
+    let mut config = builder
         .with_single_cert(chain, key)
         .context("failed to build TLS server config")?;
     config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
@@ -366,6 +366,20 @@ pub async fn config_sync(
                     jwks.cloned(),
                     jwks_uri.cloned(),
                     client.redirect_uris,
+                    client.post_logout_redirect_uris,
+                    client.backchannel_logout_uri,
+                    client.backchannel_logout_session_required,
+                    client.require_pushed_authorization_requests,
+                    client.jwt_bearer_subject,
+                    match client.access_token_format {
+                        mas_config::AccessTokenFormatConfig::Opaque => {
+                            mas_data_model::AccessTokenFormat::Opaque
+                        }
+                        mas_config::AccessTokenFormatConfig::Jwt => {
+                            mas_data_model::AccessTokenFormat::Jwt
+                        }
+                    },
+                    client.tls_client_auth_subject_dn,
                 )
                 .await?;
         }
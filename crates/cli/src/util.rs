@@ -8,13 +8,17 @@ use std::{sync::Arc, time::Duration};
 
 use anyhow::Context;
 use mas_config::{
-    AccountConfig, BrandingConfig, CaptchaConfig, DatabaseConfig, EmailConfig, EmailSmtpMode,
-    EmailTransportKind, ExperimentalConfig, HomeserverKind, MatrixConfig, PasswordsConfig,
-    PolicyConfig, TemplatesConfig,
+    AccountConfig, BrandingConfig, CaptchaConfig, ClientRegistrationConfig, DatabaseConfig,
+    EmailConfig, EmailSmtpMode, EmailTransportKind, ExperimentalConfig, HomeserverKind, LdapConfig,
+    MatrixConfig, PasswordsConfig, PolicyConfig, TemplatesConfig,
+};
+use mas_data_model::{
+    JwksOrJwksUri, SessionExpirationConfig, SigningKeyRotationConfig, SiteConfig,
 };
-use mas_data_model::{SessionExpirationConfig, SiteConfig};
 use mas_email::{MailTransport, Mailer};
 use mas_handlers::passwords::PasswordManager;
+use mas_iana::jose::JsonWebKeyUse;
+use mas_keystore::{Encrypter, JsonWebKey, JsonWebKeySet, Keystore, PrivateKey, SharedKeystore};
 use mas_matrix::{HomeserverConnection, ReadOnlyHomeserverConnection};
 use mas_matrix_synapse::SynapseConnection;
 use mas_policy::PolicyFactory;
@@ -137,6 +141,7 @@ pub async fn policy_factory_from_config(
         register: config.register_entrypoint.clone(),
         client_registration: config.client_registration_entrypoint.clone(),
         authorization_grant: config.authorization_grant_entrypoint.clone(),
+        token_exchange: config.token_exchange_entrypoint.clone(),
         email: config.email_entrypoint.clone(),
     };
 
@@ -176,15 +181,49 @@ pub fn captcha_config_from_config(
     }))
 }
 
+pub fn client_registration_config_from_config(
+    client_registration_config: &ClientRegistrationConfig,
+) -> Result<mas_data_model::ClientRegistrationConfig, anyhow::Error> {
+    let software_statement_issuers = client_registration_config
+        .software_statement_issuers
+        .iter()
+        .map(|issuer| {
+            let jwks = match (&issuer.jwks, &issuer.jwks_uri) {
+                (Some(jwks), None) => JwksOrJwksUri::Jwks(jwks.clone()),
+                (None, Some(jwks_uri)) => JwksOrJwksUri::JwksUri(jwks_uri.clone()),
+                _ => anyhow::bail!(
+                    "exactly one of jwks or jwks_uri must be set for software statement issuer {}",
+                    issuer.issuer
+                ),
+            };
+
+            Ok(mas_data_model::SoftwareStatementIssuer {
+                issuer: issuer.issuer.clone(),
+                jwks,
+            })
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(mas_data_model::ClientRegistrationConfig {
+        require_initial_access_token: client_registration_config.require_initial_access_token,
+        require_software_statement: client_registration_config.require_software_statement,
+        software_statement_issuers,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn site_config_from_config(
     branding_config: &BrandingConfig,
     matrix_config: &MatrixConfig,
     experimental_config: &ExperimentalConfig,
     password_config: &PasswordsConfig,
+    ldap_config: &LdapConfig,
     account_config: &AccountConfig,
     captcha_config: &CaptchaConfig,
+    client_registration_config: &ClientRegistrationConfig,
 ) -> Result<SiteConfig, anyhow::Error> {
     let captcha = captcha_config_from_config(captcha_config)?;
+    let client_registration = client_registration_config_from_config(client_registration_config)?;
     let session_expiration = experimental_config
         .inactive_session_expiration
         .as_ref()
@@ -193,6 +232,15 @@ pub fn site_config_from_config(
             compat_session_inactivity_ttl: c.expire_compat_sessions.then_some(c.ttl),
             user_session_inactivity_ttl: c.expire_user_sessions.then_some(c.ttl),
         });
+    let signing_key_rotation =
+        experimental_config
+            .signing_key_rotation
+            .as_ref()
+            .map(|c| SigningKeyRotationConfig {
+                rotation_interval: c.rotation_interval,
+                publication_delay: c.publication_delay,
+                retention: c.retention,
+            });
 
     Ok(SiteConfig {
         access_token_ttl: experimental_config.access_token_ttl,
@@ -201,7 +249,7 @@ pub fn site_config_from_config(
         policy_uri: branding_config.policy_uri.clone(),
         tos_uri: branding_config.tos_uri.clone(),
         imprint: branding_config.imprint.clone(),
-        password_login_enabled: password_config.enabled(),
+        password_login_enabled: password_config.enabled() || ldap_config.enabled(),
         password_registration_enabled: password_config.enabled()
             && account_config.password_registration_enabled,
         email_change_allowed: account_config.email_change_allowed,
@@ -215,6 +263,12 @@ pub fn site_config_from_config(
         minimum_password_complexity: password_config.minimum_complexity(),
         session_expiration,
         login_with_email_allowed: account_config.login_with_email_allowed,
+        passkeys_enabled: account_config.passkeys_enabled,
+        email_code_login_enabled: account_config.email_code_login_enabled,
+        refresh_token_reuse_notification_enabled: account_config
+            .refresh_token_reuse_notification_enabled,
+        client_registration,
+        signing_key_rotation,
     })
 }
 
@@ -450,6 +504,104 @@ pub async fn load_policy_factory_dynamic_data(
     Ok(())
 }
 
+/// Build the [`SharedKeystore`] out of the keys from the configuration and, if
+/// the automatic signing key rotation is enabled, spawn a task to periodically
+/// load the keys generated in the database
+pub async fn load_key_store_continuously(
+    static_key_store: Keystore,
+    site_config: &SiteConfig,
+    encrypter: &Encrypter,
+    pool: &PgPool,
+    cancellation_token: CancellationToken,
+    task_tracker: &TaskTracker,
+) -> Result<SharedKeystore, anyhow::Error> {
+    let key_store = SharedKeystore::new(static_key_store.clone());
+    if site_config.signing_key_rotation.is_none() {
+        return Ok(key_store);
+    }
+
+    let encrypter = encrypter.clone();
+    let pool = pool.clone();
+
+    load_key_store(&key_store, &static_key_store, &encrypter, &pool).await?;
+
+    task_tracker.spawn({
+        let key_store = key_store.clone();
+        async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(60));
+
+            loop {
+                tokio::select! {
+                    () = cancellation_token.cancelled() => {
+                        return;
+                    }
+                    _ = interval.tick() => {}
+                }
+
+                if let Err(err) =
+                    load_key_store(&key_store, &static_key_store, &encrypter, &pool).await
+                {
+                    tracing::error!(
+                        error = ?err,
+                        "Failed to load the signing keys"
+                    );
+                    cancellation_token.cancel();
+                    return;
+                }
+            }
+        }
+    });
+
+    Ok(key_store)
+}
+
+/// Load the signing keys generated in the database, on top of the keys from
+/// the configuration
+#[tracing::instrument(name = "secrets.load_signing_keys", skip_all, err(Debug))]
+pub async fn load_key_store(
+    key_store: &SharedKeystore,
+    static_key_store: &Keystore,
+    encrypter: &Encrypter,
+    pool: &PgPool,
+) -> Result<(), anyhow::Error> {
+    let mut repo = PgRepository::from_pool(pool)
+        .await
+        .context("Failed to acquire database connection")?;
+
+    let signing_keys = repo.signing_key().all().await?;
+
+    // The generated keys go after the ones from the configuration, so that
+    // they are preferred when signing
+    let mut keys: Vec<_> = static_key_store.iter().cloned().collect();
+    let mut published_keys = Vec::new();
+    for signing_key in signing_keys {
+        let der = encrypter
+            .decrypt_string(&signing_key.encrypted_key)
+            .with_context(|| format!("Failed to decrypt signing key {}", signing_key.kid))?;
+        let key = PrivateKey::load_der(&der)
+            .with_context(|| format!("Failed to load signing key {}", signing_key.kid))?;
+        let key = JsonWebKey::new(key)
+            .with_kid(signing_key.kid.clone())
+            .with_use(JsonWebKeyUse::Sig);
+
+        if signing_key.is_active() {
+            keys.push(key);
+        } else {
+            published_keys.push(key);
+        }
+    }
+
+    tracing::debug!(
+        signing = keys.len(),
+        published = published_keys.len(),
+        "Loaded signing keys"
+    );
+
+    key_store.store(Keystore::new(JsonWebKeySet::new(keys)).with_published_keys(published_keys));
+
+    Ok(())
+}
+
 /// Create a clonable, type-erased [`HomeserverConnection`] from the
 /// configuration
 pub fn homeserver_connection_from_config(
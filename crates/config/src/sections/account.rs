@@ -72,6 +72,21 @@ pub struct AccountConfig {
     /// This has no effect if password login is disabled.
     #[serde(default = "default_false", skip_serializing_if = "is_default_false")]
     pub login_with_email_allowed: bool,
+
+    /// Whether users can register `WebAuthn` passkeys, and use them to log in
+    /// or as a second factor. Defaults to `false`.
+    #[serde(default = "default_false", skip_serializing_if = "is_default_false")]
+    pub passkeys_enabled: bool,
+
+    /// Whether users without a password can log in with a one-time code sent
+    /// to one of their email addresses. Defaults to `false`.
+    #[serde(default = "default_false", skip_serializing_if = "is_default_false")]
+    pub email_code_login_enabled: bool,
+
+    /// Whether to notify users by email when one of their sessions was
+    /// revoked because a refresh token was reused. Defaults to `false`.
+    #[serde(default = "default_false", skip_serializing_if = "is_default_false")]
+    pub refresh_token_reuse_notification_enabled: bool,
 }
 
 impl Default for AccountConfig {
@@ -84,6 +99,9 @@ impl Default for AccountConfig {
             password_recovery_enabled: default_false(),
             account_deactivation_allowed: default_true(),
             login_with_email_allowed: default_false(),
+            passkeys_enabled: default_false(),
+            email_code_login_enabled: default_false(),
+            refresh_token_reuse_notification_enabled: default_false(),
         }
     }
 }
@@ -98,6 +116,9 @@ impl AccountConfig {
             && is_default_false(&self.password_recovery_enabled)
             && is_default_true(&self.account_deactivation_allowed)
             && is_default_false(&self.login_with_email_allowed)
+            && is_default_false(&self.passkeys_enabled)
+            && is_default_false(&self.email_code_login_enabled)
+            && is_default_false(&self.refresh_token_reuse_notification_enabled)
     }
 }
 
@@ -0,0 +1,114 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+use figment::Figment;
+use mas_jose::jwk::PublicJsonWebKeySet;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize, de::Error};
+use url::Url;
+
+use super::ConfigurationSection;
+
+/// A trusted issuer of software statements
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct SoftwareStatementIssuerConfig {
+    /// The issuer, as found in the `iss` claim of the software statements it
+    /// signs
+    pub issuer: String,
+
+    /// The JSON Web Key Set (JWKS) used to verify the software statements
+    /// signed by this issuer. Mutually exclusive with `jwks_uri`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jwks: Option<PublicJsonWebKeySet>,
+
+    /// The URL of the JSON Web Key Set (JWKS) used to verify the software
+    /// statements signed by this issuer. Mutually exclusive with `jwks`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<String>", url)]
+    pub jwks_uri: Option<Url>,
+}
+
+impl SoftwareStatementIssuerConfig {
+    fn validate(&self) -> Result<(), Box<figment::error::Error>> {
+        match (&self.jwks, &self.jwks_uri) {
+            (None, None) => {
+                let error = figment::error::Error::custom("jwks or jwks_uri is required");
+                Err(Box::new(error.with_path("jwks")))
+            }
+
+            (Some(_), Some(_)) => {
+                let error =
+                    figment::error::Error::custom("jwks and jwks_uri are mutually exclusive");
+                Err(Box::new(error.with_path("jwks")))
+            }
+
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Configuration section to control who can use the dynamic client
+/// registration endpoint
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema, Serialize)]
+pub struct ClientRegistrationConfig {
+    /// Whether clients must present an initial access token, minted by an
+    /// administrator, to register. Defaults to `false`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub require_initial_access_token: bool,
+
+    /// Whether clients must present a software statement signed by one of the
+    /// trusted `software_statement_issuers` to register. Defaults to `false`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub require_software_statement: bool,
+
+    /// List of trusted issuers of software statements.
+    ///
+    /// The claims of a verified software statement take precedence over the
+    /// metadata submitted by the client.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub software_statement_issuers: Vec<SoftwareStatementIssuerConfig>,
+}
+
+impl ClientRegistrationConfig {
+    /// Returns true if the configuration is the default one
+    pub(crate) fn is_default(&self) -> bool {
+        !self.require_initial_access_token
+            && !self.require_software_statement
+            && self.software_statement_issuers.is_empty()
+    }
+}
+
+impl ConfigurationSection for ClientRegistrationConfig {
+    const PATH: Option<&'static str> = Some("client_registration");
+
+    fn validate(&self, figment: &Figment) -> Result<(), figment::error::Error> {
+        let annotate = |mut error: figment::Error| {
+            error.metadata = figment.find_metadata(Self::PATH.unwrap()).cloned();
+            error.profile = Some(figment::Profile::Default);
+            error.path.insert(0, Self::PATH.unwrap().to_owned());
+            error
+        };
+
+        if self.require_software_statement && self.software_statement_issuers.is_empty() {
+            return Err(annotate(
+                figment::Error::custom(
+                    "at least one software statement issuer is required when software statements \
+                     are required",
+                )
+                .with_path("software_statement_issuers"),
+            ));
+        }
+
+        for (index, issuer) in self.software_statement_issuers.iter().enumerate() {
+            issuer.validate().map_err(|mut err| {
+                err.path.insert(0, format!("{index}"));
+                err.path.insert(0, "software_statement_issuers".to_owned());
+                annotate(*err)
+            })?;
+        }
+
+        Ok(())
+    }
+}
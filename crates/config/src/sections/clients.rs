@@ -16,6 +16,15 @@ use url::Url;
 
 use super::ConfigurationSection;
 
+const fn default_false() -> bool {
+    false
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+const fn is_default_false(value: &bool) -> bool {
+    *value == default_false()
+}
+
 #[derive(JsonSchema, Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "snake_case")]
 pub enum JwksOrJwksUri {
@@ -51,6 +60,14 @@ pub enum ClientAuthMethodConfig {
     /// `client_secret_basic`: a `client_assertion` sent in the request body and
     /// signed by an asymmetric key
     PrivateKeyJwt,
+
+    /// `tls_client_auth`: a certificate issued by a trusted certificate
+    /// authority presented over mutual-TLS
+    TlsClientAuth,
+
+    /// `self_signed_tls_client_auth`: a self-signed certificate presented over
+    /// mutual-TLS, which is registered in the client JWKS
+    SelfSignedTlsClientAuth,
 }
 
 impl std::fmt::Display for ClientAuthMethodConfig {
@@ -61,10 +78,34 @@ impl std::fmt::Display for ClientAuthMethodConfig {
             ClientAuthMethodConfig::ClientSecretPost => write!(f, "client_secret_post"),
             ClientAuthMethodConfig::ClientSecretJwt => write!(f, "client_secret_jwt"),
             ClientAuthMethodConfig::PrivateKeyJwt => write!(f, "private_key_jwt"),
+            ClientAuthMethodConfig::TlsClientAuth => write!(f, "tls_client_auth"),
+            ClientAuthMethodConfig::SelfSignedTlsClientAuth => {
+                write!(f, "self_signed_tls_client_auth")
+            }
         }
     }
 }
 
+/// Format of the access tokens issued to a client
+#[derive(JsonSchema, Serialize, Deserialize, Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessTokenFormatConfig {
+    /// Opaque access tokens, which resource servers have to introspect
+    #[default]
+    Opaque,
+
+    /// Signed JWT access tokens, as per RFC 9068, which resource servers can
+    /// validate offline using the service JWKS
+    Jwt,
+}
+
+impl AccessTokenFormatConfig {
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    const fn is_default(&self) -> bool {
+        matches!(self, AccessTokenFormatConfig::Opaque)
+    }
+}
+
 /// An OAuth 2.0 client configuration
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ClientConfig {
@@ -84,30 +125,73 @@ pub struct ClientConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub client_secret: Option<String>,
 
-    /// The JSON Web Key Set (JWKS) used by the `private_key_jwt` authentication
-    /// method. Mutually exclusive with `jwks_uri`
+    /// The JSON Web Key Set (JWKS) used by the `private_key_jwt` and
+    /// `self_signed_tls_client_auth` authentication methods. Mutually exclusive
+    /// with `jwks_uri`
     #[serde(skip_serializing_if = "Option::is_none")]
     pub jwks: Option<PublicJsonWebKeySet>,
 
-    /// The URL of the JSON Web Key Set (JWKS) used by the `private_key_jwt`
-    /// authentication method. Mutually exclusive with `jwks`
+    /// The URL of the JSON Web Key Set (JWKS) used by the `private_key_jwt` and
+    /// `self_signed_tls_client_auth` authentication methods. Mutually exclusive
+    /// with `jwks`
     #[serde(skip_serializing_if = "Option::is_none")]
     pub jwks_uri: Option<Url>,
 
+    /// The subject distinguished name of the certificate the client presents
+    /// with the `tls_client_auth` authentication method, as a RFC 4514 string,
+    /// e.g. `CN=client.example.com,O=Example`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls_client_auth_subject_dn: Option<String>,
+
     /// List of allowed redirect URIs
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub redirect_uris: Vec<Url>,
+
+    /// List of URIs the client is allowed to redirect to after an RP-initiated
+    /// logout
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_logout_redirect_uris: Vec<Url>,
+
+    /// URI to which the service sends a Logout Token when a session of this
+    /// client ends, as per the OpenID Connect Back-Channel Logout spec
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backchannel_logout_uri: Option<Url>,
+
+    /// Whether the client requires the `sid` claim to be included in the Logout
+    /// Token. Defaults to `false`.
+    #[serde(default = "default_false", skip_serializing_if = "is_default_false")]
+    pub backchannel_logout_session_required: bool,
+
+    /// Whether the client must use pushed authorization requests to start an
+    /// authorization flow. Defaults to `false`.
+    #[serde(default = "default_false", skip_serializing_if = "is_default_false")]
+    pub require_pushed_authorization_requests: bool,
+
+    /// Username of the user on behalf of which this client can get access
+    /// tokens using the JWT bearer grant. The assertions are verified with the
+    /// client JWKS, so this requires the `private_key_jwt` authentication method
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jwt_bearer_subject: Option<String>,
+
+    /// Format of the access tokens issued to this client. Defaults to
+    /// `opaque`.
+    ///
+    /// JWT access tokens can be validated offline by resource servers, but
+    /// revocation can only be checked through the introspection endpoint.
+    #[serde(default, skip_serializing_if = "AccessTokenFormatConfig::is_default")]
+    pub access_token_format: AccessTokenFormatConfig,
 }
 
 impl ClientConfig {
     fn validate(&self) -> Result<(), figment::error::Error> {
         let auth_method = self.client_auth_method;
         match self.client_auth_method {
-            ClientAuthMethodConfig::PrivateKeyJwt => {
+            ClientAuthMethodConfig::PrivateKeyJwt
+            | ClientAuthMethodConfig::SelfSignedTlsClientAuth => {
                 if self.jwks.is_none() && self.jwks_uri.is_none() {
-                    let error = figment::error::Error::custom(
-                        "jwks or jwks_uri is required for private_key_jwt",
-                    );
+                    let error = figment::error::Error::custom(format!(
+                        "jwks or jwks_uri is required for {auth_method}"
+                    ));
                     return Err(error.with_path("client_auth_method"));
                 }
 
@@ -117,9 +201,25 @@ impl ClientConfig {
                     return Err(error.with_path("jwks"));
                 }
 
+                if self.client_secret.is_some() {
+                    let error = figment::error::Error::custom(format!(
+                        "client_secret is not allowed with {auth_method}"
+                    ));
+                    return Err(error.with_path("client_secret"));
+                }
+            }
+
+            ClientAuthMethodConfig::TlsClientAuth => {
+                if self.tls_client_auth_subject_dn.is_none() {
+                    let error = figment::error::Error::custom(
+                        "tls_client_auth_subject_dn is required for tls_client_auth",
+                    );
+                    return Err(error.with_path("client_auth_method"));
+                }
+
                 if self.client_secret.is_some() {
                     let error = figment::error::Error::custom(
-                        "client_secret is not allowed with private_key_jwt",
+                        "client_secret is not allowed with tls_client_auth",
                     );
                     return Err(error.with_path("client_secret"));
                 }
@@ -174,6 +274,24 @@ impl ClientConfig {
             }
         }
 
+        if self.tls_client_auth_subject_dn.is_some()
+            && !matches!(auth_method, ClientAuthMethodConfig::TlsClientAuth)
+        {
+            let error = figment::error::Error::custom(
+                "tls_client_auth_subject_dn requires the tls_client_auth authentication method",
+            );
+            return Err(error.with_path("tls_client_auth_subject_dn"));
+        }
+
+        if self.jwt_bearer_subject.is_some()
+            && !matches!(auth_method, ClientAuthMethodConfig::PrivateKeyJwt)
+        {
+            let error = figment::error::Error::custom(
+                "jwt_bearer_subject requires the private_key_jwt authentication method",
+            );
+            return Err(error.with_path("jwt_bearer_subject"));
+        }
+
         Ok(())
     }
 
@@ -192,6 +310,10 @@ impl ClientConfig {
                 OAuthClientAuthenticationMethod::ClientSecretJwt
             }
             ClientAuthMethodConfig::PrivateKeyJwt => OAuthClientAuthenticationMethod::PrivateKeyJwt,
+            ClientAuthMethodConfig::TlsClientAuth => OAuthClientAuthenticationMethod::TlsClientAuth,
+            ClientAuthMethodConfig::SelfSignedTlsClientAuth => {
+                OAuthClientAuthenticationMethod::SelfSignedTlsClientAuth
+            }
         }
     }
 }
@@ -274,6 +396,7 @@ mod tests {
                     - client_id: 01GFWR3WHR93Y5HK389H28VHZ9
                       client_auth_method: client_secret_post
                       client_secret: hello
+                      access_token_format: jwt
 
                     - client_id: 01GFWR43R2ZZ8HX9CVBNW9TJWG
                       client_auth_method: client_secret_jwt
@@ -281,6 +404,7 @@ mod tests {
 
                     - client_id: 01GFWR4BNFDCC4QDG6AMSP1VRR
                       client_auth_method: private_key_jwt
+                      jwt_bearer_subject: alice
                       jwks:
                         keys:
                         - kid: "03e84aed4ef4431014e8617567864c4efaaaede9"
@@ -296,6 +420,10 @@ mod tests {
                           use: "sig"
                           e: "AQAB"
                           n: "0hukqytPwrj1RbMYhYoepCi3CN5k7DwYkTe_Cmb7cP9_qv4ok78KdvFXt5AnQxCRwBD7-qTNkkfMWO2RxUMBdQD0ED6tsSb1n5dp0XY8dSWiBDCX8f6Hr-KolOpvMLZKRy01HdAWcM6RoL9ikbjYHUEW1C8IJnw3MzVHkpKFDL354aptdNLaAdTCBvKzU9WpXo10g-5ctzSlWWjQuecLMQ4G1mNdsR1LHhUENEnOvgT8cDkX0fJzLbEbyBYkdMgKggyVPEB1bg6evG4fTKawgnf0IDSPxIU-wdS9wdSP9ZCJJPLi5CEp-6t6rE_sb2dGcnzjCGlembC57VwpkUvyMw"
+
+                    - client_id: 01JSK3F1W6N7Q9R2T4V5X8Y0ZA
+                      client_auth_method: tls_client_auth
+                      tls_client_auth_subject_dn: CN=partner.example.com,O=Partner
                 "#,
             )?;
 
@@ -303,7 +431,7 @@ mod tests {
                 .merge(Yaml::file("config.yaml"))
                 .extract_inner::<ClientsConfig>("clients")?;
 
-            assert_eq!(config.0.len(), 5);
+            assert_eq!(config.0.len(), 6);
 
             assert_eq!(
                 config.0[0].client_id,
@@ -320,6 +448,27 @@ mod tests {
             );
             assert_eq!(config.0[1].redirect_uris, Vec::new());
 
+            assert_eq!(config.0[1].jwt_bearer_subject, None);
+            assert_eq!(config.0[4].jwt_bearer_subject.as_deref(), Some("alice"));
+
+            assert_eq!(
+                config.0[0].access_token_format,
+                AccessTokenFormatConfig::Opaque
+            );
+            assert_eq!(
+                config.0[2].access_token_format,
+                AccessTokenFormatConfig::Jwt
+            );
+
+            assert_eq!(
+                config.0[5].client_auth_method(),
+                OAuthClientAuthenticationMethod::TlsClientAuth
+            );
+            assert_eq!(
+                config.0[5].tls_client_auth_subject_dn.as_deref(),
+                Some("CN=partner.example.com,O=Partner")
+            );
+
             Ok(())
         });
     }
@@ -45,6 +45,70 @@ pub struct InactiveSessionExpirationConfig {
     pub expire_user_sessions: bool,
 }
 
+fn default_signing_key_rotation_interval() -> Duration {
+    Duration::days(30)
+}
+
+fn is_default_signing_key_rotation_interval(value: &Duration) -> bool {
+    *value == default_signing_key_rotation_interval()
+}
+
+fn default_signing_key_publication_delay() -> Duration {
+    Duration::days(1)
+}
+
+fn is_default_signing_key_publication_delay(value: &Duration) -> bool {
+    *value == default_signing_key_publication_delay()
+}
+
+fn default_signing_key_retention() -> Duration {
+    Duration::days(7)
+}
+
+fn is_default_signing_key_retention(value: &Duration) -> bool {
+    *value == default_signing_key_retention()
+}
+
+/// Configuration options for the automatic signing key rotation feature
+///
+/// When enabled, signing keys are generated by the service and stored
+/// encrypted in the database, on top of the ones set in `secrets.keys`.
+#[serde_as]
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct SigningKeyRotationConfig {
+    /// How long a set of keys is used for signing before being replaced, in
+    /// seconds. Defaults to 30 days.
+    #[schemars(with = "u64", range(min = 86_400))]
+    #[serde(
+        default = "default_signing_key_rotation_interval",
+        skip_serializing_if = "is_default_signing_key_rotation_interval"
+    )]
+    #[serde_as(as = "serde_with::DurationSeconds<i64>")]
+    pub rotation_interval: Duration,
+
+    /// How long new keys are published before being used for signing, in
+    /// seconds. This should be longer than the time clients cache the JWKS.
+    /// Defaults to 1 day.
+    #[schemars(with = "u64", range(min = 3600))]
+    #[serde(
+        default = "default_signing_key_publication_delay",
+        skip_serializing_if = "is_default_signing_key_publication_delay"
+    )]
+    #[serde_as(as = "serde_with::DurationSeconds<i64>")]
+    pub publication_delay: Duration,
+
+    /// How long retired keys stay published after they stopped being used for
+    /// signing, in seconds. This should be longer than the lifetime of the
+    /// signed tokens. Defaults to 7 days.
+    #[schemars(with = "u64", range(min = 3600))]
+    #[serde(
+        default = "default_signing_key_retention",
+        skip_serializing_if = "is_default_signing_key_retention"
+    )]
+    #[serde_as(as = "serde_with::DurationSeconds<i64>")]
+    pub retention: Duration,
+}
+
 /// Configuration sections for experimental options
 ///
 /// Do not change these options unless you know what you are doing.
@@ -75,6 +139,12 @@ pub struct ExperimentalConfig {
     /// Disabled by default
     #[serde(skip_serializing_if = "Option::is_none")]
     pub inactive_session_expiration: Option<InactiveSessionExpirationConfig>,
+
+    /// Experimental feature to automatically rotate the signing keys
+    ///
+    /// Disabled by default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signing_key_rotation: Option<SigningKeyRotationConfig>,
 }
 
 impl Default for ExperimentalConfig {
@@ -83,6 +153,7 @@ impl Default for ExperimentalConfig {
             access_token_ttl: default_token_ttl(),
             compat_token_ttl: default_token_ttl(),
             inactive_session_expiration: None,
+            signing_key_rotation: None,
         }
     }
 }
@@ -92,9 +163,35 @@ impl ExperimentalConfig {
         is_default_token_ttl(&self.access_token_ttl)
             && is_default_token_ttl(&self.compat_token_ttl)
             && self.inactive_session_expiration.is_none()
+            && self.signing_key_rotation.is_none()
     }
 }
 
 impl ConfigurationSection for ExperimentalConfig {
     const PATH: Option<&'static str> = Some("experimental");
+
+    fn validate(&self, figment: &figment::Figment) -> Result<(), figment::Error> {
+        if let Some(rotation) = &self.signing_key_rotation {
+            if rotation.publication_delay >= rotation.rotation_interval {
+                let mut error = figment::Error::from(
+                    "`publication_delay` must be shorter than `rotation_interval`".to_owned(),
+                );
+                error.metadata = figment
+                    .find_metadata(&format!(
+                        "{root}.signing_key_rotation",
+                        root = Self::PATH.unwrap()
+                    ))
+                    .cloned();
+                error.profile = Some(figment::Profile::Default);
+                error.path = vec![
+                    Self::PATH.unwrap().to_owned(),
+                    "signing_key_rotation".to_owned(),
+                    "publication_delay".to_owned(),
+                ];
+                return Err(error);
+            }
+        }
+
+        Ok(())
+    }
 }
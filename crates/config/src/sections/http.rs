@@ -188,6 +188,69 @@ pub struct TlsConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[schemars(with = "Option<String>")]
     pub password_file: Option<Utf8PathBuf>,
+
+    /// Mutual-TLS client authentication, as per RFC 8705.
+    ///
+    /// If set, clients connecting to this listener are asked for a certificate,
+    /// which they can use to authenticate with the `tls_client_auth` and
+    /// `self_signed_tls_client_auth` methods.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_auth: Option<TlsClientAuthConfig>,
+}
+
+/// Configuration of mutual-TLS client authentication on a listener
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Default)]
+pub struct TlsClientAuthConfig {
+    /// PEM-encoded X509 certificates of the authorities which issue the
+    /// certificates of clients using the `tls_client_auth` method
+    ///
+    /// If neither `ca` nor `ca_file` is set, client certificates are not
+    /// checked against any authority, and clients can only use the
+    /// `self_signed_tls_client_auth` method. Otherwise, self-signed
+    /// certificates are rejected.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ca: Option<String>,
+
+    /// File containing the PEM-encoded X509 certificates of the authorities
+    /// which issue the certificates of clients using the `tls_client_auth`
+    /// method
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<String>")]
+    pub ca_file: Option<Utf8PathBuf>,
+}
+
+impl TlsClientAuthConfig {
+    /// Whether client certificates are checked against certificate authorities
+    #[must_use]
+    pub fn has_certificate_authorities(&self) -> bool {
+        self.ca.is_some() || self.ca_file.is_some()
+    }
+
+    /// Load the certificates of the authorities which issue client
+    /// certificates, if any
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the certificate file could not be read, or if the
+    /// certificates could not be decoded as PEM
+    pub fn load(&self) -> Result<Option<Vec<CertificateDer<'static>>>, anyhow::Error> {
+        let ca_pem = match (&self.ca, &self.ca_file) {
+            (None, None) => return Ok(None),
+            (Some(_), Some(_)) => bail!("Only one of `ca` or `ca_file` can be set at a time"),
+            (Some(ca), None) => Cow::Borrowed(ca),
+            (None, Some(path)) => Cow::Owned(std::fs::read_to_string(path)?),
+        };
+
+        let mut ca_reader = Cursor::new(ca_pem.as_bytes());
+        let certificates: Result<Vec<_>, _> = rustls_pemfile::certs(&mut ca_reader).collect();
+        let certificates = certificates?;
+
+        if certificates.is_empty() {
+            bail!("Client certificate authorities are empty (or invalid)")
+        }
+
+        Ok(Some(certificates))
+    }
 }
 
 impl TlsConfig {
@@ -0,0 +1,354 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+use chrono::Duration;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize, de::Error};
+use serde_with::serde_as;
+use url::Url;
+
+use super::upstream_oauth2::ImportAction;
+use crate::ConfigurationSection;
+
+fn default_search_filter() -> String {
+    "(uid={username})".to_owned()
+}
+
+fn is_default_search_filter(value: &String) -> bool {
+    *value == default_search_filter()
+}
+
+fn default_timeout() -> Duration {
+    Duration::seconds(10)
+}
+
+fn is_default_timeout(value: &Duration) -> bool {
+    *value == default_timeout()
+}
+
+/// What should be done for the localpart attribute
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default, JsonSchema)]
+#[schemars(rename = "LdapLocalpartImportPreference")]
+pub struct LocalpartImportPreference {
+    /// The Jinja2 template to use for the localpart attribute. The localpart
+    /// is used to find the local user matching the directory entry
+    ///
+    /// If not provided, the default template is `{{ user.uid }}`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+}
+
+impl LocalpartImportPreference {
+    const fn is_default(&self) -> bool {
+        self.template.is_none()
+    }
+}
+
+/// What should be done for the displayname attribute
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default, JsonSchema)]
+#[schemars(rename = "LdapDisplaynameImportPreference")]
+pub struct DisplaynameImportPreference {
+    /// How to handle the attribute
+    #[serde(default, skip_serializing_if = "ImportAction::is_default")]
+    pub action: ImportAction,
+
+    /// The Jinja2 template to use for the displayname attribute
+    ///
+    /// If not provided, the default template is `{{ user.cn }}`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+}
+
+impl DisplaynameImportPreference {
+    const fn is_default(&self) -> bool {
+        self.action.is_default() && self.template.is_none()
+    }
+}
+
+/// What should be done with the email attribute
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default, JsonSchema)]
+#[schemars(rename = "LdapEmailImportPreference")]
+pub struct EmailImportPreference {
+    /// How to handle the attribute
+    #[serde(default, skip_serializing_if = "ImportAction::is_default")]
+    pub action: ImportAction,
+
+    /// The Jinja2 template to use for the email address attribute
+    ///
+    /// If not provided, the default template is `{{ user.mail }}`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+}
+
+impl EmailImportPreference {
+    const fn is_default(&self) -> bool {
+        self.action.is_default() && self.template.is_none()
+    }
+}
+
+/// How attributes of the directory entry should be imported
+///
+/// The templates have access to the attributes of the entry through the
+/// `user` variable. Only the first value of multi-valued attributes is
+/// available.
+///
+/// Attributes are only imported when a user is created on their first login.
+/// As there is no interactive step, the `suggest` action is not supported.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default, JsonSchema)]
+#[schemars(rename = "LdapAttributesImports")]
+pub struct AttributesImports {
+    /// Import the localpart of the MXID
+    #[serde(default, skip_serializing_if = "LocalpartImportPreference::is_default")]
+    pub localpart: LocalpartImportPreference,
+
+    /// Import the displayname of the user
+    #[serde(
+        default,
+        skip_serializing_if = "DisplaynameImportPreference::is_default"
+    )]
+    pub displayname: DisplaynameImportPreference,
+
+    /// Import the email address of the user
+    #[serde(default, skip_serializing_if = "EmailImportPreference::is_default")]
+    pub email: EmailImportPreference,
+}
+
+impl AttributesImports {
+    const fn is_default(&self) -> bool {
+        self.localpart.is_default() && self.displayname.is_default() && self.email.is_default()
+    }
+}
+
+/// Configuration section to check passwords against an LDAP directory
+///
+/// Users are looked up with a search, using the service account if one is
+/// configured, and their password is then checked by binding as the entry
+/// which was found.
+#[serde_as]
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct LdapConfig {
+    /// URL of the LDAP server, like `ldaps://ldap.example.com` or
+    /// `ldap://ldap.example.com:389`
+    ///
+    /// Password checks against the directory are disabled if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<String>", url)]
+    pub url: Option<Url>,
+
+    /// Whether to upgrade `ldap://` connections to TLS with the `StartTLS`
+    /// operation
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub starttls: bool,
+
+    /// Timeout for connecting and for each operation on the server, in
+    /// seconds
+    #[serde(
+        default = "default_timeout",
+        skip_serializing_if = "is_default_timeout"
+    )]
+    #[schemars(with = "u64", range(min = 1, max = 300))]
+    #[serde_as(as = "serde_with::DurationSeconds<i64>")]
+    pub timeout: Duration,
+
+    /// The DN to bind as to search the directory. The search is anonymous if
+    /// not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bind_dn: Option<String>,
+
+    /// The password of the DN used to search the directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bind_password: Option<String>,
+
+    /// The DN under which users are searched
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_dn: Option<String>,
+
+    /// The filter used to find the entry of a user. `{username}` is replaced by
+    /// the username entered, escaped for use in a filter
+    ///
+    /// Defaults to `(uid={username})`. With Active Directory, something like
+    /// `(&(objectClass=user)(sAMAccountName={username}))` is more appropriate
+    #[serde(
+        default = "default_search_filter",
+        skip_serializing_if = "is_default_search_filter"
+    )]
+    pub search_filter: String,
+
+    /// How attributes of the directory entry should be imported
+    #[serde(default, skip_serializing_if = "AttributesImports::is_default")]
+    pub attributes_imports: AttributesImports,
+
+    /// Whether to create the local user on their first successful login, if it
+    /// doesn't exist yet. Defaults to `false`, in which case users need to be
+    /// created beforehand
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub create_users: bool,
+}
+
+impl Default for LdapConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            starttls: false,
+            timeout: default_timeout(),
+            bind_dn: None,
+            bind_password: None,
+            base_dn: None,
+            search_filter: default_search_filter(),
+            attributes_imports: AttributesImports::default(),
+            create_users: false,
+        }
+    }
+}
+
+impl LdapConfig {
+    /// Returns true if the configuration is the default one
+    pub(crate) fn is_default(&self) -> bool {
+        self.url.is_none()
+            && !self.starttls
+            && is_default_timeout(&self.timeout)
+            && self.bind_dn.is_none()
+            && self.bind_password.is_none()
+            && self.base_dn.is_none()
+            && is_default_search_filter(&self.search_filter)
+            && self.attributes_imports.is_default()
+            && !self.create_users
+    }
+
+    /// Returns true if passwords should be checked against the directory
+    #[must_use]
+    pub fn enabled(&self) -> bool {
+        self.url.is_some()
+    }
+}
+
+impl ConfigurationSection for LdapConfig {
+    const PATH: Option<&'static str> = Some("ldap");
+
+    fn validate(&self, figment: &figment::Figment) -> Result<(), figment::Error> {
+        let metadata = figment.find_metadata(Self::PATH.unwrap());
+
+        let error_on_field = |mut error: figment::error::Error, field: &'static str| {
+            error.metadata = metadata.cloned();
+            error.profile = Some(figment::Profile::Default);
+            error.path = vec![Self::PATH.unwrap().to_owned(), field.to_owned()];
+            error
+        };
+
+        let missing_field = |field: &'static str| {
+            error_on_field(figment::error::Error::missing_field(field), field)
+        };
+
+        let Some(url) = &self.url else {
+            return Ok(());
+        };
+
+        match url.scheme() {
+            "ldap" => {}
+            "ldaps" => {
+                if self.starttls {
+                    return Err(error_on_field(
+                        figment::error::Error::custom(
+                            "StartTLS can't be used with an `ldaps://` URL",
+                        ),
+                        "starttls",
+                    ));
+                }
+            }
+            _ => {
+                return Err(error_on_field(
+                    figment::error::Error::custom("The URL must use the `ldap` or `ldaps` scheme"),
+                    "url",
+                ));
+            }
+        }
+
+        if self.base_dn.is_none() {
+            return Err(missing_field("base_dn"));
+        }
+
+        if self.bind_dn.is_some() && self.bind_password.is_none() {
+            return Err(missing_field("bind_password"));
+        }
+
+        if !self.search_filter.contains("{username}") {
+            return Err(error_on_field(
+                figment::error::Error::custom(
+                    "The search filter must contain the `{username}` placeholder",
+                ),
+                "search_filter",
+            ));
+        }
+
+        if matches!(
+            self.attributes_imports.displayname.action,
+            ImportAction::Suggest
+        ) || matches!(self.attributes_imports.email.action, ImportAction::Suggest)
+        {
+            return Err(error_on_field(
+                figment::error::Error::custom(
+                    "The `suggest` action is not supported for LDAP attributes",
+                ),
+                "attributes_imports",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use figment::{
+        Figment,
+        providers::{Format, Yaml},
+    };
+
+    use super::*;
+
+    #[test]
+    fn load_config() {
+        let figment = Figment::new().merge(Yaml::string(
+            r"
+                ldap:
+                  url: ldaps://ldap.example.com
+                  bind_dn: cn=mas,dc=example,dc=com
+                  bind_password: secret
+                  base_dn: ou=people,dc=example,dc=com
+                  attributes_imports:
+                    email:
+                      action: require
+                  create_users: true
+            ",
+        ));
+
+        let config = figment.extract_inner::<LdapConfig>("ldap").unwrap();
+        config.validate(&figment).unwrap();
+
+        assert!(config.enabled());
+        assert_eq!(config.search_filter, "(uid={username})");
+        assert_eq!(config.timeout, Duration::seconds(10));
+        assert_eq!(
+            config.attributes_imports.email.action,
+            ImportAction::Require
+        );
+        assert!(config.create_users);
+    }
+
+    #[test]
+    fn validate_config() {
+        let figment = Figment::new().merge(Yaml::string(
+            r"
+                ldap:
+                  url: ldaps://ldap.example.com
+                  starttls: true
+                  base_dn: ou=people,dc=example,dc=com
+            ",
+        ));
+
+        let config = figment.extract_inner::<LdapConfig>("ldap").unwrap();
+        assert!(config.validate(&figment).is_err());
+    }
+}
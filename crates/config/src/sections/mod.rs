@@ -11,11 +11,13 @@ use serde::{Deserialize, Serialize};
 mod account;
 mod branding;
 mod captcha;
+mod client_registration;
 mod clients;
 mod database;
 mod email;
 mod experimental;
 mod http;
+mod ldap;
 mod matrix;
 mod passwords;
 mod policy;
@@ -29,13 +31,21 @@ pub use self::{
     account::AccountConfig,
     branding::BrandingConfig,
     captcha::{CaptchaConfig, CaptchaServiceKind},
-    clients::{ClientAuthMethodConfig, ClientConfig, ClientsConfig},
+    client_registration::{ClientRegistrationConfig, SoftwareStatementIssuerConfig},
+    clients::{AccessTokenFormatConfig, ClientAuthMethodConfig, ClientConfig, ClientsConfig},
     database::{DatabaseConfig, PgSslMode},
     email::{EmailConfig, EmailSmtpMode, EmailTransportKind},
     experimental::ExperimentalConfig,
     http::{
         BindConfig as HttpBindConfig, HttpConfig, ListenerConfig as HttpListenerConfig,
-        Resource as HttpResource, TlsConfig as HttpTlsConfig, UnixOrTcp,
+        Resource as HttpResource, TlsClientAuthConfig as HttpTlsClientAuthConfig,
+        TlsConfig as HttpTlsConfig, UnixOrTcp,
+    },
+    ldap::{
+        AttributesImports as LdapAttributesImports,
+        DisplaynameImportPreference as LdapDisplaynameImportPreference,
+        EmailImportPreference as LdapEmailImportPreference, LdapConfig,
+        LocalpartImportPreference as LdapLocalpartImportPreference,
     },
     matrix::{HomeserverKind, MatrixConfig},
     passwords::{Algorithm as PasswordAlgorithm, PasswordsConfig},
@@ -64,6 +74,10 @@ pub struct RootConfig {
     #[serde(default, skip_serializing_if = "ClientsConfig::is_default")]
     pub clients: ClientsConfig,
 
+    /// Configuration related to the dynamic client registration endpoint
+    #[serde(default, skip_serializing_if = "ClientRegistrationConfig::is_default")]
+    pub client_registration: ClientRegistrationConfig,
+
     /// Configuration of the HTTP server
     #[serde(default)]
     pub http: HttpConfig,
@@ -91,6 +105,10 @@ pub struct RootConfig {
     #[serde(default)]
     pub passwords: PasswordsConfig,
 
+    /// Configuration related to checking passwords against an LDAP directory
+    #[serde(default, skip_serializing_if = "LdapConfig::is_default")]
+    pub ldap: LdapConfig,
+
     /// Configuration related to the homeserver
     pub matrix: MatrixConfig,
 
@@ -128,12 +146,14 @@ pub struct RootConfig {
 impl ConfigurationSection for RootConfig {
     fn validate(&self, figment: &figment::Figment) -> Result<(), figment::Error> {
         self.clients.validate(figment)?;
+        self.client_registration.validate(figment)?;
         self.http.validate(figment)?;
         self.database.validate(figment)?;
         self.telemetry.validate(figment)?;
         self.templates.validate(figment)?;
         self.email.validate(figment)?;
         self.passwords.validate(figment)?;
+        self.ldap.validate(figment)?;
         self.secrets.validate(figment)?;
         self.matrix.validate(figment)?;
         self.policy.validate(figment)?;
@@ -160,12 +180,14 @@ impl RootConfig {
     {
         Ok(Self {
             clients: ClientsConfig::default(),
+            client_registration: ClientRegistrationConfig::default(),
             http: HttpConfig::default(),
             database: DatabaseConfig::default(),
             telemetry: TelemetryConfig::default(),
             templates: TemplatesConfig::default(),
             email: EmailConfig::default(),
             passwords: PasswordsConfig::default(),
+            ldap: LdapConfig::default(),
             secrets: SecretsConfig::generate(&mut rng).await?,
             matrix: MatrixConfig::generate(&mut rng),
             policy: PolicyConfig::default(),
@@ -183,12 +205,14 @@ impl RootConfig {
     pub fn test() -> Self {
         Self {
             clients: ClientsConfig::default(),
+            client_registration: ClientRegistrationConfig::default(),
             http: HttpConfig::default(),
             database: DatabaseConfig::default(),
             telemetry: TelemetryConfig::default(),
             templates: TemplatesConfig::default(),
             passwords: PasswordsConfig::default(),
             email: EmailConfig::default(),
+            ldap: LdapConfig::default(),
             secrets: SecretsConfig::test(),
             matrix: MatrixConfig::test(),
             policy: PolicyConfig::default(),
@@ -206,6 +230,9 @@ impl RootConfig {
 #[allow(missing_docs)]
 #[derive(Debug, Deserialize)]
 pub struct AppConfig {
+    #[serde(default)]
+    pub client_registration: ClientRegistrationConfig,
+
     #[serde(default)]
     pub http: HttpConfig,
 
@@ -223,6 +250,9 @@ pub struct AppConfig {
     #[serde(default)]
     pub passwords: PasswordsConfig,
 
+    #[serde(default)]
+    pub ldap: LdapConfig,
+
     pub matrix: MatrixConfig,
 
     #[serde(default)]
@@ -246,11 +276,13 @@ pub struct AppConfig {
 
 impl ConfigurationSection for AppConfig {
     fn validate(&self, figment: &figment::Figment) -> Result<(), figment::Error> {
+        self.client_registration.validate(figment)?;
         self.http.validate(figment)?;
         self.database.validate(figment)?;
         self.templates.validate(figment)?;
         self.email.validate(figment)?;
         self.passwords.validate(figment)?;
+        self.ldap.validate(figment)?;
         self.secrets.validate(figment)?;
         self.matrix.validate(figment)?;
         self.policy.validate(figment)?;
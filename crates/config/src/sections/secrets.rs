@@ -245,9 +245,27 @@ impl SecretsConfig {
             key_file: None,
         };
 
+        let span = tracing::info_span!("ed25519");
+        let key_rng = rand_chacha::ChaChaRng::from_rng(&mut rng)?;
+        let ed25519_key = task::spawn_blocking(move || {
+            let _entered = span.enter();
+            let ret = PrivateKey::generate_ed25519(key_rng);
+            info!("Done generating Ed25519 key");
+            ret
+        })
+        .await
+        .context("could not join blocking task")?;
+        let ed25519_key = KeyConfig {
+            kid: Alphanumeric.sample_string(&mut rng, 10),
+            password: None,
+            password_file: None,
+            key: Some(ed25519_key.to_pem(pem_rfc7468::LineEnding::LF)?.to_string()),
+            key_file: None,
+        };
+
         Ok(Self {
             encryption: Standard.sample(&mut rng),
-            keys: vec![rsa_key, ec_p256_key, ec_p384_key, ec_k256_key],
+            keys: vec![rsa_key, ec_p256_key, ec_p384_key, ec_k256_key, ed25519_key],
         })
     }
 
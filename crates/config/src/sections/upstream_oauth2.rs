@@ -177,7 +177,7 @@ pub enum ImportAction {
 
 impl ImportAction {
     #[allow(clippy::trivially_copy_pass_by_ref)]
-    const fn is_default(&self) -> bool {
+    pub(crate) const fn is_default(&self) -> bool {
         matches!(self, ImportAction::Ignore)
     }
 }
@@ -11,6 +11,7 @@ use thiserror::Error;
 pub(crate) mod compat;
 pub mod oauth2;
 pub(crate) mod policy_data;
+pub(crate) mod signing_key;
 mod site_config;
 pub(crate) mod tokens;
 pub(crate) mod upstream_oauth2;
@@ -30,11 +31,17 @@ pub use self::{
         CompatSessionState, CompatSsoLogin, CompatSsoLoginState, Device, ToScopeTokenError,
     },
     oauth2::{
-        AuthorizationCode, AuthorizationGrant, AuthorizationGrantStage, Client, DeviceCodeGrant,
-        DeviceCodeGrantState, InvalidRedirectUriError, JwksOrJwksUri, Pkce, Session, SessionState,
+        AccessTokenFormat, AuthorizationCode, AuthorizationGrant, AuthorizationGrantStage, Client,
+        DeviceCodeGrant, DeviceCodeGrantState, InitialAccessToken, InvalidAccessTokenFormatError,
+        InvalidRedirectUriError, JwksOrJwksUri, PUSHED_AUTHORIZATION_REQUEST_URI_PREFIX, Pkce,
+        PushedAuthorizationRequest, Session, SessionState,
     },
     policy_data::PolicyData,
-    site_config::{CaptchaConfig, CaptchaService, SessionExpirationConfig, SiteConfig},
+    signing_key::SigningKey,
+    site_config::{
+        CaptchaConfig, CaptchaService, ClientRegistrationConfig, SessionExpirationConfig,
+        SigningKeyRotationConfig, SiteConfig, SoftwareStatementIssuer,
+    },
     tokens::{
         AccessToken, AccessTokenState, RefreshToken, RefreshTokenState, TokenFormatError, TokenType,
     },
@@ -49,7 +56,8 @@ pub use self::{
     user_agent::{DeviceType, UserAgent},
     users::{
         Authentication, AuthenticationMethod, BrowserSession, Password, User, UserEmail,
-        UserEmailAuthentication, UserEmailAuthenticationCode, UserRecoverySession,
-        UserRecoveryTicket, UserRegistration, UserRegistrationPassword,
+        UserEmailAuthentication, UserEmailAuthenticationCode, UserPasskey, UserPasskeyChallenge,
+        UserRecoveryCode, UserRecoverySession, UserRecoveryTicket, UserRegistration,
+        UserRegistrationPassword, UserTotpAuthenticator,
     },
 };
@@ -10,7 +10,7 @@ use chrono::{DateTime, Duration, Utc};
 use mas_iana::oauth::PkceCodeChallengeMethod;
 use oauth2_types::{
     pkce::{CodeChallengeError, CodeChallengeMethodExt},
-    requests::ResponseMode,
+    requests::{ClaimsRequest, ResponseMode},
     scope::{OPENID, PROFILE, Scope},
 };
 use rand::{
@@ -164,6 +164,13 @@ pub struct AuthorizationGrant {
     pub created_at: DateTime<Utc>,
     pub requires_consent: bool,
     pub login_hint: Option<String>,
+    pub claims: Option<ClaimsRequest>,
+
+    /// The resources the client requested access to, through [resource
+    /// indicators]
+    ///
+    /// [resource indicators]: https://www.rfc-editor.org/rfc/rfc8707
+    pub resources: Vec<Url>,
 }
 
 impl std::ops::Deref for AuthorizationGrant {
@@ -280,6 +287,8 @@ impl AuthorizationGrant {
             created_at: now,
             requires_consent: false,
             login_hint: Some(String::from("mxid:@example-user:example.com")),
+            claims: None,
+            resources: Vec::new(),
         }
     }
 }
@@ -5,10 +5,13 @@
 // Please see LICENSE in the repository root for full details.
 
 use chrono::{DateTime, Utc};
-use mas_iana::{jose::JsonWebSignatureAlg, oauth::OAuthClientAuthenticationMethod};
+use mas_iana::{
+    jose::{JsonWebEncryptionAlg, JsonWebEncryptionEnc, JsonWebSignatureAlg},
+    oauth::OAuthClientAuthenticationMethod,
+};
 use mas_jose::jwk::PublicJsonWebKeySet;
 use oauth2_types::{
-    oidc::ApplicationType,
+    oidc::{ApplicationType, SubjectType},
     registration::{ClientMetadata, Localized},
     requests::GrantType,
 };
@@ -28,6 +31,50 @@ pub enum JwksOrJwksUri {
     JwksUri(Url),
 }
 
+/// The format of the access tokens issued to a client
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum AccessTokenFormat {
+    /// Random opaque strings, which need to be introspected
+    #[default]
+    Opaque,
+
+    /// Signed JWTs, as defined in RFC 9068
+    Jwt,
+}
+
+#[derive(Debug, Clone, Error)]
+#[error("Invalid access token format {0:?}")]
+pub struct InvalidAccessTokenFormatError(String);
+
+impl std::str::FromStr for AccessTokenFormat {
+    type Err = InvalidAccessTokenFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "opaque" => Ok(Self::Opaque),
+            "jwt" => Ok(Self::Jwt),
+            s => Err(InvalidAccessTokenFormatError(s.to_owned())),
+        }
+    }
+}
+
+impl AccessTokenFormat {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Opaque => "opaque",
+            Self::Jwt => "jwt",
+        }
+    }
+}
+
+impl std::fmt::Display for AccessTokenFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct Client {
     pub id: Ulid,
@@ -86,6 +133,68 @@ pub struct Client {
     /// URI using the https scheme that a third party can use to initiate a
     /// login by the RP
     pub initiate_login_uri: Option<Url>,
+
+    /// Array of URLs to which the RP is allowed to redirect the End-User after
+    /// an RP-initiated logout
+    pub post_logout_redirect_uris: Vec<Url>,
+
+    /// URL to which the OP sends Logout Tokens when a session of this client
+    /// ends
+    pub backchannel_logout_uri: Option<Url>,
+
+    /// Whether the RP requires a `sid` Claim to be included in the Logout Token
+    pub backchannel_logout_session_required: bool,
+
+    /// Whether the client must use pushed authorization requests to start an
+    /// authorization flow
+    pub require_pushed_authorization_requests: bool,
+
+    /// Username of the user on behalf of which the client can get access
+    /// tokens using the JWT bearer grant
+    pub jwt_bearer_subject: Option<String>,
+
+    /// JWS alg algorithm that MUST be used for signing Request Objects sent to
+    /// the OP
+    pub request_object_signing_alg: Option<JsonWebSignatureAlg>,
+
+    /// Array of `request_uri` values that are pre-registered by the RP for use
+    /// at the OP
+    pub request_uris: Vec<Url>,
+
+    /// Subject type requested for responses to this client
+    pub subject_type: Option<SubjectType>,
+
+    /// URL used to determine the sector of pairwise subject identifiers
+    pub sector_identifier_uri: Option<Url>,
+
+    /// Format of the access tokens issued to this client
+    pub access_token_format: AccessTokenFormat,
+
+    /// JWS alg algorithm used to sign JWT introspection responses returned to
+    /// this client
+    pub introspection_signed_response_alg: Option<JsonWebSignatureAlg>,
+
+    /// JWE alg algorithm used to encrypt the ID tokens issued to this client
+    pub id_token_encrypted_response_alg: Option<JsonWebEncryptionAlg>,
+
+    /// JWE enc algorithm used to encrypt the ID tokens issued to this client
+    pub id_token_encrypted_response_enc: Option<JsonWebEncryptionEnc>,
+
+    /// JWE alg algorithm used to encrypt the user info responses returned to
+    /// this client
+    pub userinfo_encrypted_response_alg: Option<JsonWebEncryptionAlg>,
+
+    /// JWE enc algorithm used to encrypt the user info responses returned to
+    /// this client
+    pub userinfo_encrypted_response_enc: Option<JsonWebEncryptionEnc>,
+
+    /// Subject distinguished name of the certificate the client must present
+    /// when using the `tls_client_auth` authentication method
+    pub tls_client_auth_subject_dn: Option<String>,
+
+    /// Encrypted token the client uses to manage its registration, if it was
+    /// dynamically registered
+    pub encrypted_registration_access_token: Option<String>,
 }
 
 #[derive(Debug, Error)]
@@ -123,6 +232,37 @@ impl Client {
         }
     }
 
+    /// Whether the given URI is registered as a `post_logout_redirect_uri` for
+    /// this client.
+    ///
+    /// As per the [RP-Initiated Logout] spec, the URI must exactly match one of
+    /// the registered URIs.
+    ///
+    /// [RP-Initiated Logout]: https://openid.net/specs/openid-connect-rpinitiated-1_0.html
+    #[must_use]
+    pub fn has_post_logout_redirect_uri(&self, uri: &Url) -> bool {
+        self.post_logout_redirect_uris.contains(uri)
+    }
+
+    /// The sector identifier used to derive pairwise subject identifiers for
+    /// this client, or `None` if it uses public subject identifiers.
+    ///
+    /// As per the [OpenID Connect Core] spec, this is the host of the
+    /// `sector_identifier_uri` if set, else the host of the redirect URIs.
+    ///
+    /// [OpenID Connect Core]: https://openid.net/specs/openid-connect-core-1_0.html#PairwiseAlg
+    #[must_use]
+    pub fn sector_identifier(&self) -> Option<&str> {
+        if self.subject_type != Some(SubjectType::Pairwise) {
+            return None;
+        }
+
+        self.sector_identifier_uri
+            .as_ref()
+            .or_else(|| self.redirect_uris.first())
+            .and_then(Url::host_str)
+    }
+
     /// Create a client metadata object for this client
     #[must_use]
     pub fn into_metadata(self) -> ClientMetadata {
@@ -151,25 +291,34 @@ impl Client {
             contacts: None,
             software_id: None,
             software_version: None,
-            sector_identifier_uri: None,
-            subject_type: None,
-            id_token_encrypted_response_alg: None,
-            id_token_encrypted_response_enc: None,
-            userinfo_encrypted_response_alg: None,
-            userinfo_encrypted_response_enc: None,
-            request_object_signing_alg: None,
+            software_statement: None,
+            sector_identifier_uri: self.sector_identifier_uri,
+            subject_type: self.subject_type,
+            id_token_encrypted_response_alg: self.id_token_encrypted_response_alg,
+            id_token_encrypted_response_enc: self.id_token_encrypted_response_enc,
+            userinfo_encrypted_response_alg: self.userinfo_encrypted_response_alg,
+            userinfo_encrypted_response_enc: self.userinfo_encrypted_response_enc,
+            request_object_signing_alg: self.request_object_signing_alg,
             request_object_encryption_alg: None,
             request_object_encryption_enc: None,
             default_max_age: None,
             require_auth_time: None,
             default_acr_values: None,
-            request_uris: None,
+            request_uris: (!self.request_uris.is_empty()).then_some(self.request_uris),
             require_signed_request_object: None,
-            require_pushed_authorization_requests: None,
-            introspection_signed_response_alg: None,
+            require_pushed_authorization_requests: self
+                .require_pushed_authorization_requests
+                .then_some(true),
+            introspection_signed_response_alg: self.introspection_signed_response_alg,
             introspection_encrypted_response_alg: None,
             introspection_encrypted_response_enc: None,
-            post_logout_redirect_uris: None,
+            post_logout_redirect_uris: (!self.post_logout_redirect_uris.is_empty())
+                .then_some(self.post_logout_redirect_uris),
+            backchannel_logout_session_required: self
+                .backchannel_logout_uri
+                .is_some()
+                .then_some(self.backchannel_logout_session_required),
+            backchannel_logout_uri: self.backchannel_logout_uri,
         }
     }
 
@@ -201,6 +350,27 @@ impl Client {
                 id_token_signed_response_alg: None,
                 userinfo_signed_response_alg: None,
                 jwks: None,
+                post_logout_redirect_uris: vec![
+                    Url::parse("https://client1.example.com/logged-out").unwrap(),
+                ],
+                backchannel_logout_uri: Some(
+                    Url::parse("https://client1.example.com/backchannel-logout").unwrap(),
+                ),
+                backchannel_logout_session_required: true,
+                require_pushed_authorization_requests: false,
+                jwt_bearer_subject: None,
+                request_object_signing_alg: None,
+                request_uris: Vec::new(),
+                subject_type: None,
+                sector_identifier_uri: None,
+                access_token_format: AccessTokenFormat::Opaque,
+                introspection_signed_response_alg: None,
+                id_token_encrypted_response_alg: None,
+                id_token_encrypted_response_enc: None,
+                userinfo_encrypted_response_alg: None,
+                userinfo_encrypted_response_enc: None,
+                tls_client_auth_subject_dn: None,
+                encrypted_registration_access_token: None,
             },
             // Another client without any URIs set
             Self {
@@ -222,6 +392,23 @@ impl Client {
                 id_token_signed_response_alg: None,
                 userinfo_signed_response_alg: None,
                 jwks: None,
+                post_logout_redirect_uris: Vec::new(),
+                backchannel_logout_uri: None,
+                backchannel_logout_session_required: false,
+                require_pushed_authorization_requests: false,
+                jwt_bearer_subject: None,
+                request_object_signing_alg: None,
+                request_uris: Vec::new(),
+                subject_type: None,
+                sector_identifier_uri: None,
+                access_token_format: AccessTokenFormat::Opaque,
+                introspection_signed_response_alg: None,
+                id_token_encrypted_response_alg: None,
+                id_token_encrypted_response_enc: None,
+                userinfo_encrypted_response_alg: None,
+                userinfo_encrypted_response_enc: None,
+                tls_client_auth_subject_dn: None,
+                encrypted_registration_access_token: None,
             },
         ]
     }
@@ -283,4 +470,22 @@ mod tests {
             registered_uris
         ));
     }
+
+    #[test]
+    fn test_sector_identifier() {
+        let now = chrono::DateTime::UNIX_EPOCH;
+        let mut rng = rand::rngs::mock::StepRng::new(0, 1);
+        let mut client = Client::samples(now, &mut rng).remove(0);
+
+        // Public subject identifiers have no sector
+        assert_eq!(client.sector_identifier(), None);
+
+        // The sector defaults to the host of the redirect URIs
+        client.subject_type = Some(SubjectType::Pairwise);
+        assert_eq!(client.sector_identifier(), Some("client1.example.com"));
+
+        // The sector identifier URI takes precedence
+        client.sector_identifier_uri = Some(Url::parse("https://example.com/sector.json").unwrap());
+        assert_eq!(client.sector_identifier(), Some("example.com"));
+    }
 }
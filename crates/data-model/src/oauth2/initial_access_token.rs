@@ -0,0 +1,68 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use ulid::Ulid;
+
+use crate::InvalidTransitionError;
+
+/// A token minted by an administrator, which clients must present to the
+/// dynamic client registration endpoint, as defined in [RFC 7591]
+///
+/// [RFC 7591]: https://www.rfc-editor.org/rfc/rfc7591.html#section-3
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InitialAccessToken {
+    pub id: Ulid,
+
+    /// The token itself.
+    pub token: String,
+
+    /// The number of client registrations this token can be used for, if
+    /// limited.
+    pub max_uses: Option<u32>,
+
+    /// The number of client registrations this token was used for.
+    pub use_count: u32,
+
+    /// The time at which this token was created.
+    pub created_at: DateTime<Utc>,
+
+    /// The time at which this token expires, if it does.
+    pub expires_at: Option<DateTime<Utc>>,
+
+    /// The time at which this token was last used to register a client.
+    pub last_used_at: Option<DateTime<Utc>>,
+
+    /// The time at which this token was revoked.
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl InitialAccessToken {
+    /// Returns `true` if this token can still be used to register a client,
+    /// meaning it wasn't revoked, didn't expire and wasn't used too many times.
+    #[must_use]
+    pub fn is_valid(&self, now: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none()
+            && self.expires_at.is_none_or(|expires_at| now < expires_at)
+            && self
+                .max_uses
+                .is_none_or(|max_uses| self.use_count < max_uses)
+    }
+
+    /// Mark this token as revoked.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the token was already revoked.
+    pub fn revoke(mut self, revoked_at: DateTime<Utc>) -> Result<Self, InvalidTransitionError> {
+        if self.revoked_at.is_some() {
+            return Err(InvalidTransitionError);
+        }
+
+        self.revoked_at = Some(revoked_at);
+        Ok(self)
+    }
+}
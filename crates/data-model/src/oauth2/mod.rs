@@ -7,13 +7,22 @@
 mod authorization_grant;
 mod client;
 mod device_code_grant;
+mod initial_access_token;
+mod pushed_authorization_request;
 mod session;
 
 pub use self::{
     authorization_grant::{
         AuthorizationCode, AuthorizationGrant, AuthorizationGrantStage, LoginHint, Pkce,
     },
-    client::{Client, InvalidRedirectUriError, JwksOrJwksUri},
+    client::{
+        AccessTokenFormat, Client, InvalidAccessTokenFormatError, InvalidRedirectUriError,
+        JwksOrJwksUri,
+    },
     device_code_grant::{DeviceCodeGrant, DeviceCodeGrantState},
+    initial_access_token::InitialAccessToken,
+    pushed_authorization_request::{
+        PUSHED_AUTHORIZATION_REQUEST_URI_PREFIX, PushedAuthorizationRequest,
+    },
     session::{Session, SessionState},
 };
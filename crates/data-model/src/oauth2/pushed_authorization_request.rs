@@ -0,0 +1,68 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use ulid::Ulid;
+
+use crate::InvalidTransitionError;
+
+/// The prefix of the `request_uri` values referencing pushed authorization
+/// requests, as defined in [RFC 9126]
+///
+/// [RFC 9126]: https://www.rfc-editor.org/rfc/rfc9126.html#section-2.2
+pub const PUSHED_AUTHORIZATION_REQUEST_URI_PREFIX: &str = "urn:ietf:params:oauth:request_uri:";
+
+/// An authorization request pushed by a client to the pushed authorization
+/// request endpoint, as defined in [RFC 9126]
+///
+/// [RFC 9126]: https://www.rfc-editor.org/rfc/rfc9126.html
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PushedAuthorizationRequest {
+    pub id: Ulid,
+
+    /// The client which pushed this authorization request.
+    pub client_id: Ulid,
+
+    /// The `request_uri` which the client uses to reference this authorization
+    /// request at the authorization endpoint.
+    pub request_uri: String,
+
+    /// The authorization request parameters, as pushed by the client.
+    pub parameters: Vec<(String, String)>,
+
+    /// The time at which this authorization request was pushed.
+    pub created_at: DateTime<Utc>,
+
+    /// The time at which this authorization request expires.
+    pub expires_at: DateTime<Utc>,
+
+    /// The time at which this authorization request was used at the
+    /// authorization endpoint.
+    pub consumed_at: Option<DateTime<Utc>>,
+}
+
+impl PushedAuthorizationRequest {
+    /// Returns `true` if this authorization request can still be used at the
+    /// authorization endpoint, meaning it wasn't used yet and didn't expire.
+    #[must_use]
+    pub fn is_valid(&self, now: DateTime<Utc>) -> bool {
+        self.consumed_at.is_none() && now < self.expires_at
+    }
+
+    /// Mark this authorization request as consumed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the authorization request was already consumed.
+    pub fn consume(mut self, consumed_at: DateTime<Utc>) -> Result<Self, InvalidTransitionError> {
+        if self.consumed_at.is_some() {
+            return Err(InvalidTransitionError);
+        }
+
+        self.consumed_at = Some(consumed_at);
+        Ok(self)
+    }
+}
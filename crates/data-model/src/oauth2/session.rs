@@ -10,6 +10,7 @@ use chrono::{DateTime, Utc};
 use oauth2_types::scope::Scope;
 use serde::Serialize;
 use ulid::Ulid;
+use url::Url;
 
 use crate::{InvalidTransitionError, UserAgent};
 
@@ -83,6 +84,23 @@ pub struct Session {
     pub user_agent: Option<UserAgent>,
     pub last_active_at: Option<DateTime<Utc>>,
     pub last_active_ip: Option<IpAddr>,
+
+    /// The session this one was derived from through a token exchange, if any
+    pub parent_session_id: Option<Ulid>,
+
+    /// The client acting on behalf of the user, if the session was obtained
+    /// through a token exchange with an actor token
+    pub actor_client_id: Option<Ulid>,
+
+    /// The audience the session was restricted to through a token exchange, if
+    /// any
+    pub audience: Option<String>,
+
+    /// The resources the tokens of this session are restricted to, through
+    /// [resource indicators]
+    ///
+    /// [resource indicators]: https://www.rfc-editor.org/rfc/rfc8707
+    pub resources: Vec<Url>,
 }
 
 impl std::ops::Deref for Session {
@@ -107,4 +125,18 @@ impl Session {
         self.state = self.state.finish(finished_at)?;
         Ok(self)
     }
+
+    /// The audiences the tokens of this session are restricted to.
+    ///
+    /// Those are the resources requested through resource indicators, or the
+    /// audience requested through a token exchange. An empty list means the
+    /// tokens are not restricted to any particular audience.
+    #[must_use]
+    pub fn audiences(&self) -> Vec<String> {
+        if self.resources.is_empty() {
+            self.audience.iter().cloned().collect()
+        } else {
+            self.resources.iter().map(ToString::to_string).collect()
+        }
+    }
 }
@@ -0,0 +1,55 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use ulid::Ulid;
+
+/// A signing key generated by the service and stored in the database, as part
+/// of the automatic key rotation
+///
+/// A key goes through three stages: it is first only published in the JWKS,
+/// then it is activated and used for signing, and finally it is retired, and
+/// stays published until it gets removed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SigningKey {
+    pub id: Ulid,
+
+    /// The key ID, as published in the JWKS
+    pub kid: String,
+
+    /// The PKCS#8 DER-encoded private key, encrypted with the site encryption
+    /// key
+    pub encrypted_key: String,
+
+    /// When the key was created and started being published
+    pub created_at: DateTime<Utc>,
+
+    /// When the key started being used for signing
+    pub activated_at: Option<DateTime<Utc>>,
+
+    /// When the key stopped being used for signing
+    pub retired_at: Option<DateTime<Utc>>,
+}
+
+impl SigningKey {
+    /// Whether the key is published but not yet used for signing
+    #[must_use]
+    pub fn is_pending(&self) -> bool {
+        self.activated_at.is_none()
+    }
+
+    /// Whether the key is currently used for signing
+    #[must_use]
+    pub fn is_active(&self) -> bool {
+        self.activated_at.is_some() && self.retired_at.is_none()
+    }
+
+    /// Whether the key is not used for signing anymore
+    #[must_use]
+    pub fn is_retired(&self) -> bool {
+        self.retired_at.is_some()
+    }
+}
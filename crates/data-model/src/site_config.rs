@@ -7,6 +7,8 @@
 use chrono::Duration;
 use url::Url;
 
+use crate::JwksOrJwksUri;
+
 /// Which Captcha service is being used
 #[derive(Debug, Clone, Copy)]
 pub enum CaptchaService {
@@ -36,6 +38,42 @@ pub struct SessionExpirationConfig {
     pub compat_session_inactivity_ttl: Option<Duration>,
 }
 
+/// Automatic signing key rotation configuration
+#[derive(Debug, Clone)]
+pub struct SigningKeyRotationConfig {
+    /// How long a set of keys is used for signing before being replaced
+    pub rotation_interval: Duration,
+
+    /// How long new keys are published before being used for signing
+    pub publication_delay: Duration,
+
+    /// How long retired keys stay published
+    pub retention: Duration,
+}
+
+/// A trusted issuer of software statements
+#[derive(Debug, Clone)]
+pub struct SoftwareStatementIssuer {
+    /// The issuer, as found in the `iss` claim of the software statements
+    pub issuer: String,
+
+    /// The keys used to verify the software statements
+    pub jwks: JwksOrJwksUri,
+}
+
+/// Configuration of the dynamic client registration endpoint
+#[derive(Debug, Clone, Default)]
+pub struct ClientRegistrationConfig {
+    /// Whether clients must present an initial access token to register.
+    pub require_initial_access_token: bool,
+
+    /// Whether clients must present a software statement to register.
+    pub require_software_statement: bool,
+
+    /// The trusted issuers of software statements.
+    pub software_statement_issuers: Vec<SoftwareStatementIssuer>,
+}
+
 /// Random site configuration we want accessible in various places.
 #[allow(clippy::struct_excessive_bools)]
 #[derive(Debug, Clone)]
@@ -90,4 +128,20 @@ pub struct SiteConfig {
 
     /// Whether users can log in with their email address.
     pub login_with_email_allowed: bool,
+
+    /// Whether users can register passkeys and use them to log in.
+    pub passkeys_enabled: bool,
+
+    /// Whether users without a password can log in with a code sent by email.
+    pub email_code_login_enabled: bool,
+
+    /// Whether to notify users by email when one of their sessions was revoked
+    /// because a refresh token was reused.
+    pub refresh_token_reuse_notification_enabled: bool,
+
+    /// Configuration of the dynamic client registration endpoint
+    pub client_registration: ClientRegistrationConfig,
+
+    /// Automatic signing key rotation, if enabled
+    pub signing_key_rotation: Option<SigningKeyRotationConfig>,
 }
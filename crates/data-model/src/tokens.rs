@@ -57,6 +57,13 @@ pub struct AccessToken {
     pub created_at: DateTime<Utc>,
     pub expires_at: Option<DateTime<Utc>>,
     pub first_used_at: Option<DateTime<Utc>>,
+
+    /// The JWK thumbprint of the `DPoP` key this token is bound to, if any
+    pub dpop_jkt: Option<String>,
+
+    /// The SHA-256 thumbprint of the client certificate this token is bound
+    /// to, if any
+    pub x5t_s256: Option<String>,
 }
 
 impl AccessToken {
@@ -185,6 +192,9 @@ pub struct RefreshToken {
     pub session_id: Ulid,
     pub created_at: DateTime<Utc>,
     pub access_token_id: Option<Ulid>,
+
+    /// The JWK thumbprint of the `DPoP` key this token is bound to, if any
+    pub dpop_jkt: Option<String>,
 }
 
 impl std::ops::Deref for RefreshToken {
@@ -240,6 +250,9 @@ pub enum TokenType {
 
     /// A legacy refresh token
     CompatRefreshToken,
+
+    /// An initial access token, used to register clients dynamically
+    InitialAccessToken,
 }
 
 impl std::fmt::Display for TokenType {
@@ -249,6 +262,7 @@ impl std::fmt::Display for TokenType {
             TokenType::RefreshToken => write!(f, "refresh token"),
             TokenType::CompatAccessToken => write!(f, "compat access token"),
             TokenType::CompatRefreshToken => write!(f, "compat refresh token"),
+            TokenType::InitialAccessToken => write!(f, "initial access token"),
         }
     }
 }
@@ -260,6 +274,7 @@ impl TokenType {
             TokenType::RefreshToken => "mar",
             TokenType::CompatAccessToken => "mct",
             TokenType::CompatRefreshToken => "mcr",
+            TokenType::InitialAccessToken => "mit",
         }
     }
 
@@ -269,6 +284,7 @@ impl TokenType {
             "mar" => Some(TokenType::RefreshToken),
             "mct" | "syt" => Some(TokenType::CompatAccessToken),
             "mcr" | "syr" => Some(TokenType::CompatRefreshToken),
+            "mit" => Some(TokenType::InitialAccessToken),
             _ => None,
         }
     }
@@ -302,6 +318,11 @@ impl TokenType {
             return Ok(TokenType::CompatRefreshToken);
         }
 
+        // JWT access tokens are stored as-is, so we just need to recognize them
+        if is_likely_jwt_access_token(token) {
+            return Ok(TokenType::AccessToken);
+        }
+
         let split: Vec<&str> = token.split('_').collect();
         let [prefix, random_part, crc]: [&str; 3] = split
             .try_into()
@@ -359,6 +380,22 @@ fn is_likely_synapse_macaroon(token: &str) -> bool {
     decoded.get(4..13) == Some(b"location ")
 }
 
+/// Returns true if and only if a token looks like a JWT access token, as
+/// defined by RFC 9068.
+///
+/// This only looks at the `typ` header: the signature doesn't need to be
+/// checked, as those tokens are looked up in the database like opaque ones.
+fn is_likely_jwt_access_token(token: &str) -> bool {
+    let Some((header, _)) = token.split_once('.') else {
+        return false;
+    };
+    let Ok(decoded) = Base64UrlUnpadded::decode_vec(header) else {
+        return false;
+    };
+    serde_json::from_slice::<serde_json::Value>(&decoded)
+        .is_ok_and(|header| header.get("typ").and_then(serde_json::Value::as_str) == Some("at+jwt"))
+}
+
 const NUM: [u8; 62] = *b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
 
 fn base62_encode(mut num: u32) -> String {
@@ -456,6 +493,21 @@ mod tests {
         assert!(!is_likely_synapse_macaroon("aaa"));
     }
 
+    #[test]
+    fn test_is_likely_jwt_access_token() {
+        // {"alg":"RS256","typ":"at+jwt"}
+        let token = "eyJhbGciOiJSUzI1NiIsInR5cCI6ImF0K2p3dCJ9.e30.c2lnbmF0dXJl";
+        assert!(is_likely_jwt_access_token(token));
+        assert_eq!(TokenType::check(token).unwrap(), TokenType::AccessToken);
+
+        // {"alg":"RS256","typ":"JWT"}
+        assert!(!is_likely_jwt_access_token(
+            "eyJhbGciOiJSUzI1NiIsInR5cCI6IkpXVCJ9.e30.c2lnbmF0dXJl"
+        ));
+        assert!(!is_likely_jwt_access_token("...."));
+        assert!(!is_likely_jwt_access_token("aaa"));
+    }
+
     #[test]
     fn test_generate_and_check() {
         const COUNT: usize = 500; // Generate 500 of each token type
@@ -468,6 +520,7 @@ mod tests {
             TokenType::CompatRefreshToken,
             TokenType::AccessToken,
             TokenType::RefreshToken,
+            TokenType::InitialAccessToken,
         ] {
             // Generate many tokens
             let tokens: HashSet<String> = (0..COUNT).map(|_| t.generate(&mut rng)).collect();
@@ -23,6 +23,8 @@ pub struct User {
     pub locked_at: Option<DateTime<Utc>>,
     pub deactivated_at: Option<DateTime<Utc>>,
     pub can_request_admin: bool,
+    pub locale: Option<String>,
+    pub profile_updated_at: Option<DateTime<Utc>>,
 }
 
 impl User {
@@ -31,6 +33,13 @@ impl User {
     pub fn is_valid(&self) -> bool {
         self.locked_at.is_none() && self.deactivated_at.is_none()
     }
+
+    /// Returns when the profile of the user was last updated, falling back to
+    /// when the user was created.
+    #[must_use]
+    pub fn profile_updated_at(&self) -> DateTime<Utc> {
+        self.profile_updated_at.unwrap_or(self.created_at)
+    }
 }
 
 impl User {
@@ -45,6 +54,8 @@ impl User {
             locked_at: None,
             deactivated_at: None,
             can_request_admin: false,
+            locale: None,
+            profile_updated_at: None,
         }]
     }
 }
@@ -69,9 +80,124 @@ pub struct Authentication {
 pub enum AuthenticationMethod {
     Password { user_password_id: Ulid },
     UpstreamOAuth2 { upstream_oauth2_session_id: Ulid },
+    Totp { user_totp_authenticator_id: Ulid },
+    Passkey { user_passkey_id: Ulid },
+    RecoveryCode { user_recovery_code_id: Ulid },
+    EmailCode { user_email_authentication_id: Ulid },
+    Ldap { dn: String },
     Unknown,
 }
 
+/// A TOTP authenticator enrolled by a user as a second factor
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UserTotpAuthenticator {
+    pub id: Ulid,
+    pub user_id: Ulid,
+
+    /// The shared secret, encrypted with the site encryption key
+    #[serde(skip)]
+    pub encrypted_secret: String,
+
+    pub created_at: DateTime<Utc>,
+
+    /// When the user proved they set up the authenticator correctly by
+    /// entering a first code. Unverified authenticators are never used to
+    /// authenticate
+    pub verified_at: Option<DateTime<Utc>>,
+
+    /// The last time step for which a code was accepted, to prevent codes
+    /// from being used more than once
+    pub last_used_step: Option<u64>,
+}
+
+impl UserTotpAuthenticator {
+    /// Returns `true` if the authenticator was verified and can be used to
+    /// authenticate
+    #[must_use]
+    pub fn is_verified(&self) -> bool {
+        self.verified_at.is_some()
+    }
+}
+
+/// A `WebAuthn` credential registered by a user, usable as a passwordless
+/// login or as a second factor
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UserPasskey {
+    pub id: Ulid,
+    pub user_id: Ulid,
+
+    /// The credential ID chosen by the authenticator, base64url-encoded
+    pub credential_id: String,
+
+    /// A human-readable name, chosen by the user
+    pub name: String,
+
+    /// The credential public key, as a COSE key
+    #[serde(skip)]
+    pub public_key: Vec<u8>,
+
+    /// The signature counter reported by the authenticator on last use, used
+    /// to detect cloned authenticators
+    pub sign_count: u32,
+
+    /// The transports the authenticator supports, as reported during
+    /// registration
+    pub transports: Vec<String>,
+
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+/// A challenge issued for a `WebAuthn` registration or assertion ceremony
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UserPasskeyChallenge {
+    pub id: Ulid,
+
+    /// The browser session which started the ceremony, if any. Registrations
+    /// always have one, passwordless logins don't
+    pub user_session_id: Option<Ulid>,
+
+    /// The random challenge, base64url-encoded
+    pub challenge: String,
+
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl UserPasskeyChallenge {
+    /// Returns `true` if the challenge was already used
+    #[must_use]
+    pub fn is_completed(&self) -> bool {
+        self.completed_at.is_some()
+    }
+}
+
+/// A single-use code which a user can enter instead of their second factor,
+/// if they lost access to it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UserRecoveryCode {
+    pub id: Ulid,
+    pub user_id: Ulid,
+
+    /// The code, hashed like passwords are
+    #[serde(skip)]
+    pub hashed_code: String,
+
+    /// The version of the hashing scheme used for the code
+    pub version: u16,
+
+    pub created_at: DateTime<Utc>,
+    pub consumed_at: Option<DateTime<Utc>>,
+}
+
+impl UserRecoveryCode {
+    /// Returns `true` if the code was already used
+    #[must_use]
+    pub fn is_consumed(&self) -> bool {
+        self.consumed_at.is_some()
+    }
+}
+
 /// A session to recover a user if they have lost their credentials
 ///
 /// For each session intiated, there may be multiple [`UserRecoveryTicket`]s
@@ -116,6 +242,7 @@ pub struct UserEmailAuthentication {
     pub id: Ulid,
     pub user_session_id: Option<Ulid>,
     pub user_registration_id: Option<Ulid>,
+    pub user_id: Option<Ulid>,
     pub email: String,
     pub created_at: DateTime<Utc>,
     pub completed_at: Option<DateTime<Utc>>,
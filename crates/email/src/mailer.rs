@@ -10,7 +10,10 @@ use lettre::{
     AsyncTransport, Message,
     message::{Mailbox, MessageBuilder, MultiPart},
 };
-use mas_templates::{EmailRecoveryContext, EmailVerificationContext, Templates, WithLanguage};
+use mas_templates::{
+    EmailRecoveryCodeUsedContext, EmailRecoveryContext, EmailSessionRevokedContext,
+    EmailVerificationContext, Templates, WithLanguage,
+};
 use thiserror::Error;
 
 use crate::MailTransport;
@@ -99,6 +102,58 @@ impl Mailer {
         Ok(message)
     }
 
+    fn prepare_recovery_code_used_email(
+        &self,
+        to: Mailbox,
+        context: &WithLanguage<EmailRecoveryCodeUsedContext>,
+    ) -> Result<Message, Error> {
+        let plain = self
+            .templates
+            .render_email_recovery_code_used_txt(context)?;
+
+        let html = self
+            .templates
+            .render_email_recovery_code_used_html(context)?;
+
+        let multipart = MultiPart::alternative_plain_html(plain, html);
+
+        let subject = self
+            .templates
+            .render_email_recovery_code_used_subject(context)?;
+
+        let message = self
+            .base_message()
+            .subject(subject.trim())
+            .to(to)
+            .multipart(multipart)?;
+
+        Ok(message)
+    }
+
+    fn prepare_session_revoked_email(
+        &self,
+        to: Mailbox,
+        context: &WithLanguage<EmailSessionRevokedContext>,
+    ) -> Result<Message, Error> {
+        let plain = self.templates.render_email_session_revoked_txt(context)?;
+
+        let html = self.templates.render_email_session_revoked_html(context)?;
+
+        let multipart = MultiPart::alternative_plain_html(plain, html);
+
+        let subject = self
+            .templates
+            .render_email_session_revoked_subject(context)?;
+
+        let message = self
+            .base_message()
+            .subject(subject.trim())
+            .to(to)
+            .multipart(multipart)?;
+
+        Ok(message)
+    }
+
     /// Send the verification email to a user
     ///
     /// # Errors
@@ -149,6 +204,58 @@ impl Mailer {
         Ok(())
     }
 
+    /// Send the email notifying a user that one of their recovery codes was
+    /// used
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the email failed rendering or failed sending
+    #[tracing::instrument(
+        name = "email.recovery_code_used.send",
+        skip_all,
+        fields(
+            email.to = %to,
+            email.language = %context.language(),
+            user.id = %context.user().id,
+        ),
+        err,
+    )]
+    pub async fn send_recovery_code_used_email(
+        &self,
+        to: Mailbox,
+        context: &WithLanguage<EmailRecoveryCodeUsedContext>,
+    ) -> Result<(), Error> {
+        let message = self.prepare_recovery_code_used_email(to, context)?;
+        self.transport.send(message).await?;
+        Ok(())
+    }
+
+    /// Send the email notifying a user that one of their sessions was revoked
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the email failed rendering or failed sending
+    #[tracing::instrument(
+        name = "email.session_revoked.send",
+        skip_all,
+        fields(
+            email.to = %to,
+            email.language = %context.language(),
+            user.id = %context.user().id,
+            client.id = %context.client().id,
+        ),
+        err,
+    )]
+    pub async fn send_session_revoked_email(
+        &self,
+        to: Mailbox,
+        context: &WithLanguage<EmailSessionRevokedContext>,
+    ) -> Result<(), Error> {
+        let message = self.prepare_session_revoked_email(to, context)?;
+        self.transport.send(message).await?;
+        Ok(())
+    }
+
     /// Test the connetion to the mail server
     ///
     /// # Errors
@@ -58,6 +58,14 @@ fn finish(t: TransformOpenApi) -> TransformOpenApi {
             description: Some("Manage OAuth2 sessions".to_owned()),
             ..Tag::default()
         })
+        .tag(Tag {
+            name: "oauth2-initial-access-token".to_owned(),
+            description: Some(
+                "Manage the initial access tokens required to register OAuth 2.0 clients"
+                    .to_owned(),
+            ),
+            ..Tag::default()
+        })
         .tag(Tag {
             name: "user".to_owned(),
             description: Some("Manage users".to_owned()),
@@ -6,7 +6,7 @@
 
 use std::net::IpAddr;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use mas_data_model::Device;
 use schemars::JsonSchema;
 use serde::Serialize;
@@ -101,6 +101,48 @@ impl Resource for User {
     }
 }
 
+/// The recovery codes of a user, which they can use instead of their second
+/// factor
+#[derive(Serialize, JsonSchema)]
+pub struct UserRecoveryCodes {
+    #[serde(skip)]
+    id: Ulid,
+
+    /// The number of recovery codes the user can still use
+    remaining: usize,
+}
+
+impl UserRecoveryCodes {
+    /// Create the resource for the given user, with the number of unused codes
+    pub fn new(user_id: Ulid, remaining: usize) -> Self {
+        Self {
+            id: user_id,
+            remaining,
+        }
+    }
+
+    /// Samples of recovery codes for examples in the schema
+    pub fn samples() -> [Self; 2] {
+        [
+            Self::new(Ulid::from_bytes([0x01; 16]), 7),
+            Self::new(Ulid::from_bytes([0x02; 16]), 0),
+        ]
+    }
+}
+
+impl Resource for UserRecoveryCodes {
+    const KIND: &'static str = "user-recovery-codes";
+    const PATH: &'static str = "/api/admin/v1/users";
+
+    fn id(&self) -> Ulid {
+        self.id
+    }
+
+    fn path(&self) -> String {
+        format!("{}/{}/recovery-codes", Self::PATH, self.id())
+    }
+}
+
 /// An email address for a user
 #[derive(Serialize, JsonSchema)]
 pub struct UserEmail {
@@ -581,3 +623,83 @@ impl PolicyData {
         }]
     }
 }
+
+/// An initial access token, required to register OAuth 2.0 clients
+#[derive(Serialize, JsonSchema)]
+pub struct OAuth2InitialAccessToken {
+    #[serde(skip)]
+    id: Ulid,
+
+    /// The token itself, to be presented to the client registration endpoint
+    token: String,
+
+    /// The number of clients this token can be used to register, if limited
+    max_uses: Option<u32>,
+
+    /// The number of clients this token was used to register
+    use_count: u32,
+
+    /// When the object was created
+    created_at: DateTime<Utc>,
+
+    /// When the token expires, if it does
+    expires_at: Option<DateTime<Utc>>,
+
+    /// When the token was last used to register a client
+    last_used_at: Option<DateTime<Utc>>,
+
+    /// When the token was revoked, if it was
+    revoked_at: Option<DateTime<Utc>>,
+}
+
+impl Resource for OAuth2InitialAccessToken {
+    const KIND: &'static str = "oauth2-initial-access-token";
+    const PATH: &'static str = "/api/admin/v1/oauth2-initial-access-tokens";
+
+    fn id(&self) -> Ulid {
+        self.id
+    }
+}
+
+impl From<mas_data_model::InitialAccessToken> for OAuth2InitialAccessToken {
+    fn from(value: mas_data_model::InitialAccessToken) -> Self {
+        Self {
+            id: value.id,
+            token: value.token,
+            max_uses: value.max_uses,
+            use_count: value.use_count,
+            created_at: value.created_at,
+            expires_at: value.expires_at,
+            last_used_at: value.last_used_at,
+            revoked_at: value.revoked_at,
+        }
+    }
+}
+
+impl OAuth2InitialAccessToken {
+    /// Samples of initial access tokens
+    pub fn samples() -> [Self; 2] {
+        [
+            Self {
+                id: Ulid::from_bytes([0x01; 16]),
+                token: "mit_FFFFFFFFFFFFFFFFFFFFFFFFFFFFFF_2VwF6A".to_owned(),
+                max_uses: Some(10),
+                use_count: 2,
+                created_at: DateTime::default(),
+                expires_at: Some(DateTime::default() + Duration::days(7)),
+                last_used_at: Some(DateTime::default() + Duration::hours(1)),
+                revoked_at: None,
+            },
+            Self {
+                id: Ulid::from_bytes([0x02; 16]),
+                token: "mit_AAAAAAAAAAAAAAAAAAAAAAAAAAAAAA_0G1Kxu".to_owned(),
+                max_uses: None,
+                use_count: 0,
+                created_at: DateTime::default(),
+                expires_at: None,
+                last_used_at: None,
+                revoked_at: Some(DateTime::default() + Duration::days(1)),
+            },
+        ]
+    }
+}
@@ -19,6 +19,7 @@ use super::call_context::CallContext;
 use crate::passwords::PasswordManager;
 
 mod compat_sessions;
+mod oauth2_initial_access_tokens;
 mod oauth2_sessions;
 mod policy_data;
 mod upstream_oauth_links;
@@ -53,6 +54,31 @@ where
             "/oauth2-sessions/{id}",
             get_with(self::oauth2_sessions::get, self::oauth2_sessions::get_doc),
         )
+        .api_route(
+            "/oauth2-initial-access-tokens",
+            get_with(
+                self::oauth2_initial_access_tokens::list,
+                self::oauth2_initial_access_tokens::list_doc,
+            )
+            .post_with(
+                self::oauth2_initial_access_tokens::add,
+                self::oauth2_initial_access_tokens::add_doc,
+            ),
+        )
+        .api_route(
+            "/oauth2-initial-access-tokens/{id}",
+            get_with(
+                self::oauth2_initial_access_tokens::get,
+                self::oauth2_initial_access_tokens::get_doc,
+            ),
+        )
+        .api_route(
+            "/oauth2-initial-access-tokens/{id}/revoke",
+            post_with(
+                self::oauth2_initial_access_tokens::revoke,
+                self::oauth2_initial_access_tokens::revoke_doc,
+            ),
+        )
         .api_route(
             "/policy-data",
             post_with(self::policy_data::set, self::policy_data::set_doc),
@@ -101,6 +127,17 @@ where
             "/users/{id}/unlock",
             post_with(self::users::unlock, self::users::unlock_doc),
         )
+        .api_route(
+            "/users/{id}/recovery-codes",
+            get_with(self::users::recovery_codes, self::users::recovery_codes_doc),
+        )
+        .api_route(
+            "/users/{id}/reset-second-factor",
+            post_with(
+                self::users::reset_second_factor,
+                self::users::reset_second_factor_doc,
+            ),
+        )
         .api_route(
             "/user-emails",
             get_with(self::user_emails::list, self::user_emails::list_doc)
@@ -0,0 +1,161 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+use aide::{NoApi, OperationIo, transform::TransformOperation};
+use axum::{Json, response::IntoResponse};
+use chrono::Duration;
+use hyper::StatusCode;
+use mas_data_model::TokenType;
+use mas_storage::BoxRng;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::{
+    admin::{
+        call_context::CallContext,
+        model::OAuth2InitialAccessToken,
+        response::{ErrorResponse, SingleResponse},
+    },
+    impl_from_error_for_route,
+};
+
+#[derive(Debug, thiserror::Error, OperationIo)]
+#[aide(output_with = "Json<ErrorResponse>")]
+pub enum RouteError {
+    #[error(transparent)]
+    Internal(Box<dyn std::error::Error + Send + Sync + 'static>),
+
+    #[error("The maximum number of uses must be at least 1")]
+    InvalidMaxUses,
+}
+
+impl_from_error_for_route!(mas_storage::RepositoryError);
+
+impl IntoResponse for RouteError {
+    fn into_response(self) -> axum::response::Response {
+        let error = ErrorResponse::from_error(&self);
+        let status = match self {
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::InvalidMaxUses => StatusCode::BAD_REQUEST,
+        };
+        (status, Json(error)).into_response()
+    }
+}
+
+/// # JSON payload for the `POST /api/admin/v1/oauth2-initial-access-tokens`
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename = "AddOAuth2InitialAccessTokenRequest")]
+pub struct Request {
+    /// The number of seconds after which the token expires. If not set, the
+    /// token never expires.
+    #[schemars(range(min = 1))]
+    expires_in: Option<u32>,
+
+    /// The number of clients the token can be used to register. If not set,
+    /// the number of uses is not limited.
+    #[schemars(range(min = 1))]
+    max_uses: Option<u32>,
+}
+
+pub fn doc(operation: TransformOperation) -> TransformOperation {
+    operation
+        .id("addOAuth2InitialAccessToken")
+        .summary("Create an initial access token")
+        .description(
+            "Create a new initial access token, which can be used to register OAuth 2.0 clients \
+             through the dynamic client registration endpoint.",
+        )
+        .tag("oauth2-initial-access-token")
+        .response_with::<201, Json<SingleResponse<OAuth2InitialAccessToken>>, _>(|t| {
+            let [sample, ..] = OAuth2InitialAccessToken::samples();
+            let response = SingleResponse::new_canonical(sample);
+            t.description("Initial access token was created")
+                .example(response)
+        })
+        .response_with::<400, RouteError, _>(|t| {
+            let response = ErrorResponse::from_error(&RouteError::InvalidMaxUses);
+            t.description("Invalid maximum number of uses")
+                .example(response)
+        })
+}
+
+#[tracing::instrument(
+    name = "handler.admin.v1.oauth2_initial_access_tokens.add",
+    skip_all,
+    err
+)]
+pub async fn handler(
+    CallContext {
+        mut repo, clock, ..
+    }: CallContext,
+    NoApi(mut rng): NoApi<BoxRng>,
+    Json(params): Json<Request>,
+) -> Result<(StatusCode, Json<SingleResponse<OAuth2InitialAccessToken>>), RouteError> {
+    if params.max_uses == Some(0) {
+        return Err(RouteError::InvalidMaxUses);
+    }
+
+    let expires_after = params
+        .expires_in
+        .map(|expires_in| Duration::seconds(expires_in.into()));
+    let token = TokenType::InitialAccessToken.generate(&mut rng);
+
+    let initial_access_token = repo
+        .oauth2_initial_access_token()
+        .add(&mut rng, &clock, token, expires_after, params.max_uses)
+        .await?;
+
+    repo.save().await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(SingleResponse::new_canonical(initial_access_token.into())),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::{Request, StatusCode};
+    use mas_data_model::TokenType;
+    use sqlx::PgPool;
+
+    use crate::test_utils::{RequestBuilderExt, ResponseExt, TestState, setup};
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_create(pool: PgPool) {
+        setup();
+        let mut state = TestState::from_pool(pool).await.unwrap();
+        let token = state.token_with_scope("urn:mas:admin").await;
+
+        let request = Request::post("/api/admin/v1/oauth2-initial-access-tokens")
+            .bearer(&token)
+            .json(serde_json::json!({
+                "expires_in": 3600,
+                "max_uses": 5,
+            }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CREATED);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["data"]["type"], "oauth2-initial-access-token");
+        let attributes = &body["data"]["attributes"];
+        assert_eq!(attributes["max_uses"], 5);
+        assert_eq!(attributes["use_count"], 0);
+        assert_eq!(attributes["created_at"], "2022-01-16T14:40:00Z");
+        assert_eq!(attributes["expires_at"], "2022-01-16T15:40:00Z");
+        assert_eq!(
+            TokenType::check(attributes["token"].as_str().unwrap()).unwrap(),
+            TokenType::InitialAccessToken
+        );
+
+        // The token can't be created with a maximum of zero uses
+        let request = Request::post("/api/admin/v1/oauth2-initial-access-tokens")
+            .bearer(&token)
+            .json(serde_json::json!({
+                "max_uses": 0,
+            }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+    }
+}
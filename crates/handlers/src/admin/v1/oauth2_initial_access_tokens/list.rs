@@ -0,0 +1,216 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+use aide::{OperationIo, transform::TransformOperation};
+use axum::{
+    Json,
+    extract::{Query, rejection::QueryRejection},
+    response::IntoResponse,
+};
+use axum_macros::FromRequestParts;
+use hyper::StatusCode;
+use mas_storage::{Page, oauth2::OAuth2InitialAccessTokenFilter};
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::{
+    admin::{
+        call_context::CallContext,
+        model::{OAuth2InitialAccessToken, Resource},
+        params::Pagination,
+        response::{ErrorResponse, PaginatedResponse},
+    },
+    impl_from_error_for_route,
+};
+
+#[derive(Deserialize, JsonSchema, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum InitialAccessTokenStatus {
+    Active,
+    Revoked,
+}
+
+impl std::fmt::Display for InitialAccessTokenStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Active => write!(f, "active"),
+            Self::Revoked => write!(f, "revoked"),
+        }
+    }
+}
+
+#[derive(FromRequestParts, Deserialize, JsonSchema, OperationIo)]
+#[serde(rename = "OAuth2InitialAccessTokenFilter")]
+#[aide(input_with = "Query<FilterParams>")]
+#[from_request(via(Query), rejection(RouteError))]
+pub struct FilterParams {
+    /// Retrieve the items with the given status
+    ///
+    /// Defaults to retrieve all tokens, including revoked ones.
+    ///
+    /// * `active`: Only retrieve tokens which were not revoked
+    ///
+    /// * `revoked`: Only retrieve revoked tokens
+    #[serde(rename = "filter[status]")]
+    status: Option<InitialAccessTokenStatus>,
+}
+
+impl std::fmt::Display for FilterParams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut sep = '?';
+
+        if let Some(status) = self.status {
+            write!(f, "{sep}filter[status]={status}")?;
+            sep = '&';
+        }
+
+        let _ = sep;
+        Ok(())
+    }
+}
+
+#[derive(Debug, thiserror::Error, OperationIo)]
+#[aide(output_with = "Json<ErrorResponse>")]
+pub enum RouteError {
+    #[error(transparent)]
+    Internal(Box<dyn std::error::Error + Send + Sync + 'static>),
+
+    #[error("Invalid filter parameters")]
+    InvalidFilter(#[from] QueryRejection),
+}
+
+impl_from_error_for_route!(mas_storage::RepositoryError);
+
+impl IntoResponse for RouteError {
+    fn into_response(self) -> axum::response::Response {
+        let error = ErrorResponse::from_error(&self);
+        let status = match self {
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::InvalidFilter(_) => StatusCode::BAD_REQUEST,
+        };
+        (status, Json(error)).into_response()
+    }
+}
+
+pub fn doc(operation: TransformOperation) -> TransformOperation {
+    operation
+        .id("listOAuth2InitialAccessTokens")
+        .summary("List initial access tokens")
+        .description("Retrieve a list of initial access tokens.
+Note that by default, all tokens, including revoked ones are returned, with the oldest first.
+Use the `filter[status]` parameter to filter the tokens by their status and `page[last]` parameter to retrieve the last N tokens.")
+        .tag("oauth2-initial-access-token")
+        .response_with::<200, Json<PaginatedResponse<OAuth2InitialAccessToken>>, _>(|t| {
+            let tokens = OAuth2InitialAccessToken::samples();
+            let pagination = mas_storage::Pagination::first(tokens.len());
+            let page = Page {
+                edges: tokens.into(),
+                has_next_page: true,
+                has_previous_page: false,
+            };
+
+            t.description("Paginated response of initial access tokens")
+                .example(PaginatedResponse::new(
+                    page,
+                    pagination,
+                    42,
+                    OAuth2InitialAccessToken::PATH,
+                ))
+        })
+}
+
+#[tracing::instrument(
+    name = "handler.admin.v1.oauth2_initial_access_tokens.list",
+    skip_all,
+    err
+)]
+pub async fn handler(
+    CallContext { mut repo, .. }: CallContext,
+    Pagination(pagination): Pagination,
+    params: FilterParams,
+) -> Result<Json<PaginatedResponse<OAuth2InitialAccessToken>>, RouteError> {
+    let base = format!("{path}{params}", path = OAuth2InitialAccessToken::PATH);
+    let filter = OAuth2InitialAccessTokenFilter::new();
+
+    let filter = match params.status {
+        Some(InitialAccessTokenStatus::Active) => filter.active_only(),
+        Some(InitialAccessTokenStatus::Revoked) => filter.revoked_only(),
+        None => filter,
+    };
+
+    let page = repo
+        .oauth2_initial_access_token()
+        .list(filter, pagination)
+        .await?;
+    let count = repo.oauth2_initial_access_token().count(filter).await?;
+
+    Ok(Json(PaginatedResponse::new(
+        page.map(OAuth2InitialAccessToken::from),
+        pagination,
+        count,
+        &base,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::{Request, StatusCode};
+    use sqlx::PgPool;
+
+    use crate::test_utils::{RequestBuilderExt, ResponseExt, TestState, setup};
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_list(pool: PgPool) {
+        setup();
+        let mut state = TestState::from_pool(pool).await.unwrap();
+        let token = state.token_with_scope("urn:mas:admin").await;
+        let mut rng = state.rng();
+
+        // Provision two tokens, and revoke one of them
+        let mut repo = state.repository().await.unwrap();
+        repo.oauth2_initial_access_token()
+            .add(&mut rng, &state.clock, "mit_active".to_owned(), None, None)
+            .await
+            .unwrap();
+        let revoked = repo
+            .oauth2_initial_access_token()
+            .add(&mut rng, &state.clock, "mit_revoked".to_owned(), None, None)
+            .await
+            .unwrap();
+        repo.oauth2_initial_access_token()
+            .revoke(&state.clock, revoked)
+            .await
+            .unwrap();
+        repo.save().await.unwrap();
+
+        let request = Request::get("/api/admin/v1/oauth2-initial-access-tokens")
+            .bearer(&token)
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["meta"]["count"], 2);
+
+        let request =
+            Request::get("/api/admin/v1/oauth2-initial-access-tokens?filter[status]=active")
+                .bearer(&token)
+                .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["meta"]["count"], 1);
+        assert_eq!(body["data"][0]["attributes"]["token"], "mit_active");
+
+        let request =
+            Request::get("/api/admin/v1/oauth2-initial-access-tokens?filter[status]=revoked")
+                .bearer(&token)
+                .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["meta"]["count"], 1);
+        assert_eq!(body["data"][0]["attributes"]["token"], "mit_revoked");
+    }
+}
@@ -0,0 +1,166 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+use aide::{OperationIo, transform::TransformOperation};
+use axum::{Json, response::IntoResponse};
+use hyper::StatusCode;
+use ulid::Ulid;
+
+use crate::{
+    admin::{
+        call_context::CallContext,
+        model::{OAuth2InitialAccessToken, Resource},
+        params::UlidPathParam,
+        response::{ErrorResponse, SingleResponse},
+    },
+    impl_from_error_for_route,
+};
+
+#[derive(Debug, thiserror::Error, OperationIo)]
+#[aide(output_with = "Json<ErrorResponse>")]
+pub enum RouteError {
+    #[error(transparent)]
+    Internal(Box<dyn std::error::Error + Send + Sync + 'static>),
+
+    #[error("Initial access token ID {0} not found")]
+    NotFound(Ulid),
+}
+
+impl_from_error_for_route!(mas_storage::RepositoryError);
+
+impl IntoResponse for RouteError {
+    fn into_response(self) -> axum::response::Response {
+        let error = ErrorResponse::from_error(&self);
+        let status = match self {
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+        };
+        (status, Json(error)).into_response()
+    }
+}
+
+pub fn doc(operation: TransformOperation) -> TransformOperation {
+    operation
+        .id("revokeOAuth2InitialAccessToken")
+        .summary("Revoke an initial access token")
+        .description(
+            "Revoke an initial access token, so that it can't be used to register clients anymore. \
+             Clients which were registered with it are not affected.",
+        )
+        .tag("oauth2-initial-access-token")
+        .response_with::<200, Json<SingleResponse<OAuth2InitialAccessToken>>, _>(|t| {
+            // In the samples, the second token is the one revoked
+            let [_active, revoked] = OAuth2InitialAccessToken::samples();
+            let id = revoked.id();
+            let response = SingleResponse::new(
+                revoked,
+                format!("/api/admin/v1/oauth2-initial-access-tokens/{id}/revoke"),
+            );
+            t.description("Initial access token was revoked")
+                .example(response)
+        })
+        .response_with::<404, RouteError, _>(|t| {
+            let response = ErrorResponse::from_error(&RouteError::NotFound(Ulid::nil()));
+            t.description("Initial access token was not found")
+                .example(response)
+        })
+}
+
+#[tracing::instrument(
+    name = "handler.admin.v1.oauth2_initial_access_tokens.revoke",
+    skip_all,
+    err
+)]
+pub async fn handler(
+    CallContext {
+        mut repo, clock, ..
+    }: CallContext,
+    id: UlidPathParam,
+) -> Result<Json<SingleResponse<OAuth2InitialAccessToken>>, RouteError> {
+    let id = *id;
+    let mut initial_access_token = repo
+        .oauth2_initial_access_token()
+        .lookup(id)
+        .await?
+        .ok_or(RouteError::NotFound(id))?;
+
+    if initial_access_token.revoked_at.is_none() {
+        initial_access_token = repo
+            .oauth2_initial_access_token()
+            .revoke(&clock, initial_access_token)
+            .await?;
+    }
+
+    repo.save().await?;
+
+    Ok(Json(SingleResponse::new(
+        OAuth2InitialAccessToken::from(initial_access_token),
+        format!("/api/admin/v1/oauth2-initial-access-tokens/{id}/revoke"),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::{Request, StatusCode};
+    use mas_storage::{Clock, RepositoryAccess};
+    use sqlx::PgPool;
+
+    use crate::test_utils::{RequestBuilderExt, ResponseExt, TestState, setup};
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_revoke(pool: PgPool) {
+        setup();
+        let mut state = TestState::from_pool(pool).await.unwrap();
+        let token = state.token_with_scope("urn:mas:admin").await;
+
+        let mut repo = state.repository().await.unwrap();
+        let initial_access_token = repo
+            .oauth2_initial_access_token()
+            .add(
+                &mut state.rng(),
+                &state.clock,
+                "mit_token".to_owned(),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        repo.save().await.unwrap();
+
+        let request = Request::post(format!(
+            "/api/admin/v1/oauth2-initial-access-tokens/{}/revoke",
+            initial_access_token.id
+        ))
+        .bearer(&token)
+        .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let body: serde_json::Value = response.json();
+        assert_eq!(
+            body["data"]["attributes"]["revoked_at"],
+            serde_json::json!(state.clock.now())
+        );
+
+        // Revoking again is a no-op
+        let request = Request::post(format!(
+            "/api/admin/v1/oauth2-initial-access-tokens/{}/revoke",
+            initial_access_token.id
+        ))
+        .bearer(&token)
+        .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+
+        // The token can't be used anymore
+        let mut repo = state.repository().await.unwrap();
+        let initial_access_token = repo
+            .oauth2_initial_access_token()
+            .lookup(initial_access_token.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!initial_access_token.is_valid(state.clock.now()));
+    }
+}
@@ -343,7 +343,7 @@ mod tests {
                 "finished_at": null,
                 "user_id": null,
                 "user_session_id": null,
-                "client_id": "01FSHN9AG0FAQ50MT1E9FFRPZR",
+                "client_id": "01FSHN9AG0JPM113P1DR80VX1A",
                 "scope": "urn:mas:admin",
                 "user_agent": null,
                 "last_active_at": null,
@@ -10,6 +10,8 @@ mod deactivate;
 mod get;
 mod list;
 mod lock;
+mod recovery_codes;
+mod reset_second_factor;
 mod set_admin;
 mod set_password;
 mod unlock;
@@ -21,6 +23,8 @@ pub use self::{
     get::{doc as get_doc, handler as get},
     list::{doc as list_doc, handler as list},
     lock::{doc as lock_doc, handler as lock},
+    recovery_codes::{doc as recovery_codes_doc, handler as recovery_codes},
+    reset_second_factor::{doc as reset_second_factor_doc, handler as reset_second_factor},
     set_admin::{doc as set_admin_doc, handler as set_admin},
     set_password::{doc as set_password_doc, handler as set_password},
     unlock::{doc as unlock_doc, handler as unlock},
@@ -0,0 +1,193 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+use aide::{OperationIo, transform::TransformOperation};
+use axum::{Json, response::IntoResponse};
+use hyper::StatusCode;
+use ulid::Ulid;
+
+use crate::{
+    admin::{
+        call_context::CallContext,
+        model::{Resource, User},
+        params::UlidPathParam,
+        response::{ErrorResponse, SingleResponse},
+    },
+    impl_from_error_for_route,
+};
+
+#[derive(Debug, thiserror::Error, OperationIo)]
+#[aide(output_with = "Json<ErrorResponse>")]
+pub enum RouteError {
+    #[error(transparent)]
+    Internal(Box<dyn std::error::Error + Send + Sync + 'static>),
+
+    #[error("User ID {0} not found")]
+    NotFound(Ulid),
+}
+
+impl_from_error_for_route!(mas_storage::RepositoryError);
+
+impl IntoResponse for RouteError {
+    fn into_response(self) -> axum::response::Response {
+        let error = ErrorResponse::from_error(&self);
+        let status = match self {
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+        };
+        (status, Json(error)).into_response()
+    }
+}
+
+pub fn doc(operation: TransformOperation) -> TransformOperation {
+    operation
+        .id("resetUserSecondFactor")
+        .summary("Reset the second factor of a user")
+        .description("Calling this endpoint will remove the TOTP authenticator and the recovery codes of the user, if any, so that they can log in with only their password and enroll a new one.
+This is useful when a user lost access to their authenticator app.")
+        .tag("user")
+        .response_with::<200, Json<SingleResponse<User>>, _>(|t| {
+            let [sample, ..] = User::samples();
+            let id = sample.id();
+            let response = SingleResponse::new(
+                sample,
+                format!("/api/admin/v1/users/{id}/reset-second-factor"),
+            );
+            t.description("The second factor of the user was reset")
+                .example(response)
+        })
+        .response_with::<404, RouteError, _>(|t| {
+            let response = ErrorResponse::from_error(&RouteError::NotFound(Ulid::nil()));
+            t.description("User ID not found").example(response)
+        })
+}
+
+#[tracing::instrument(name = "handler.admin.v1.users.reset_second_factor", skip_all, err)]
+pub async fn handler(
+    CallContext { mut repo, .. }: CallContext,
+    id: UlidPathParam,
+) -> Result<Json<SingleResponse<User>>, RouteError> {
+    let id = *id;
+    let user = repo
+        .user()
+        .lookup(id)
+        .await?
+        .ok_or(RouteError::NotFound(id))?;
+
+    let authenticator = repo.user_totp().find(&user).await?;
+    if let Some(authenticator) = authenticator {
+        tracing::info!(
+            user.id = %user.id,
+            user_totp_authenticator.id = %authenticator.id,
+            "Removing TOTP authenticator"
+        );
+        repo.user_totp().remove(authenticator).await?;
+    }
+
+    let removed = repo.user_recovery_code().remove_all(&user).await?;
+    if removed > 0 {
+        tracing::info!(user.id = %user.id, "Removed {removed} recovery codes");
+    }
+
+    repo.save().await?;
+
+    Ok(Json(SingleResponse::new(
+        User::from(user),
+        format!("/api/admin/v1/users/{id}/reset-second-factor"),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::{Request, StatusCode};
+    use mas_storage::{
+        RepositoryAccess,
+        user::{UserRecoveryCodeRepository, UserRepository, UserTotpRepository},
+    };
+    use sqlx::PgPool;
+
+    use crate::test_utils::{RequestBuilderExt, ResponseExt, TestState, setup};
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_reset_second_factor(pool: PgPool) {
+        setup();
+        let mut state = TestState::from_pool(pool).await.unwrap();
+        let token = state.token_with_scope("urn:mas:admin").await;
+
+        let mut repo = state.repository().await.unwrap();
+        let user = repo
+            .user()
+            .add(&mut state.rng(), &state.clock, "alice".to_owned())
+            .await
+            .unwrap();
+        let authenticator = repo
+            .user_totp()
+            .add(
+                &mut state.rng(),
+                &state.clock,
+                &user,
+                "encrypted".to_owned(),
+            )
+            .await
+            .unwrap();
+        repo.user_totp()
+            .verify(&state.clock, authenticator)
+            .await
+            .unwrap();
+        repo.user_recovery_code()
+            .add(&mut state.rng(), &state.clock, &user, 1, "hash".to_owned())
+            .await
+            .unwrap();
+        repo.save().await.unwrap();
+
+        let request = Request::post(format!(
+            "/api/admin/v1/users/{}/reset-second-factor",
+            user.id
+        ))
+        .bearer(&token)
+        .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["data"]["id"], user.id.to_string());
+
+        // The authenticator and the recovery codes are gone
+        let mut repo = state.repository().await.unwrap();
+        assert!(repo.user_totp().find(&user).await.unwrap().is_none());
+        assert_eq!(
+            repo.user_recovery_code().count_unused(&user).await.unwrap(),
+            0
+        );
+
+        // Calling it again is fine
+        let request = Request::post(format!(
+            "/api/admin/v1/users/{}/reset-second-factor",
+            user.id
+        ))
+        .bearer(&token)
+        .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_reset_second_factor_unknown_user(pool: PgPool) {
+        setup();
+        let mut state = TestState::from_pool(pool).await.unwrap();
+        let token = state.token_with_scope("urn:mas:admin").await;
+
+        let request =
+            Request::post("/api/admin/v1/users/01040G2081040G2081040G2081/reset-second-factor")
+                .bearer(&token)
+                .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::NOT_FOUND);
+        let body: serde_json::Value = response.json();
+        assert_eq!(
+            body["errors"][0]["title"],
+            "User ID 01040G2081040G2081040G2081 not found"
+        );
+    }
+}
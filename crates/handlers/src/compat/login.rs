@@ -14,14 +14,14 @@ use mas_axum_utils::sentry::SentryEventID;
 use mas_data_model::{
     CompatSession, CompatSsoLoginState, Device, SiteConfig, TokenType, User, UserAgent,
 };
-use mas_matrix::HomeserverConnection;
+use mas_matrix::{HomeserverConnection, ProvisionRequest};
 use mas_storage::{
     BoxClock, BoxRepository, BoxRng, Clock, RepositoryAccess,
     compat::{
         CompatAccessTokenRepository, CompatRefreshTokenRepository, CompatSessionRepository,
         CompatSsoLoginRepository,
     },
-    user::{UserPasswordRepository, UserRepository},
+    user::{UserPasskeyRepository, UserPasswordRepository, UserRepository, UserTotpRepository},
 };
 use opentelemetry::{Key, KeyValue, metrics::Counter};
 use rand::{CryptoRng, RngCore};
@@ -33,7 +33,9 @@ use zeroize::Zeroizing;
 use super::{MatrixError, MatrixJsonBody};
 use crate::{
     BoundActivityTracker, Limiter, METER, RequesterFingerprint, impl_from_error_for_route,
-    passwords::PasswordManager, rate_limit::PasswordCheckLimitedError,
+    ldap::{LdapBackend, LdapError, LdapLogin},
+    passwords::PasswordManager,
+    rate_limit::PasswordCheckLimitedError,
 };
 
 static LOGIN_COUNTER: LazyLock<Counter<u64>> = LazyLock::new(|| {
@@ -78,8 +80,11 @@ struct LoginTypes {
 }
 
 #[tracing::instrument(name = "handlers.compat.login.get", skip_all)]
-pub(crate) async fn get(State(password_manager): State<PasswordManager>) -> impl IntoResponse {
-    let flows = if password_manager.is_enabled() {
+pub(crate) async fn get(
+    State(password_manager): State<PasswordManager>,
+    State(ldap): State<LdapBackend>,
+) -> impl IntoResponse {
+    let flows = if password_manager.is_enabled() || ldap.is_enabled() {
         vec![
             LoginType::Password,
             LoginType::Sso {
@@ -193,6 +198,12 @@ pub enum RouteError {
     #[error("password verification failed")]
     PasswordVerificationFailed(#[source] anyhow::Error),
 
+    #[error("LDAP password verification failed")]
+    LdapVerificationFailed,
+
+    #[error("user requires a second factor")]
+    SecondFactorRequired,
+
     #[error("request rate limited")]
     RateLimited(#[from] PasswordCheckLimitedError),
 
@@ -238,13 +249,19 @@ impl IntoResponse for RouteError {
                 error: "Missing property 'identifier",
                 status: StatusCode::BAD_REQUEST,
             },
-            Self::UserNotFound | Self::NoPassword | Self::PasswordVerificationFailed(_) => {
-                MatrixError {
-                    errcode: "M_FORBIDDEN",
-                    error: "Invalid username/password",
-                    status: StatusCode::FORBIDDEN,
-                }
-            }
+            Self::UserNotFound
+            | Self::NoPassword
+            | Self::PasswordVerificationFailed(_)
+            | Self::LdapVerificationFailed => MatrixError {
+                errcode: "M_FORBIDDEN",
+                error: "Invalid username/password",
+                status: StatusCode::FORBIDDEN,
+            },
+            Self::SecondFactorRequired => MatrixError {
+                errcode: "M_FORBIDDEN",
+                error: "This account requires a second factor, use single sign-on instead",
+                status: StatusCode::FORBIDDEN,
+            },
             Self::LoginTookTooLong => MatrixError {
                 errcode: "M_FORBIDDEN",
                 error: "Login token expired",
@@ -266,6 +283,7 @@ pub(crate) async fn post(
     mut rng: BoxRng,
     clock: BoxClock,
     State(password_manager): State<PasswordManager>,
+    State(ldap): State<LdapBackend>,
     mut repo: BoxRepository,
     activity_tracker: BoundActivityTracker,
     State(homeserver): State<Arc<dyn HomeserverConnection>>,
@@ -277,7 +295,10 @@ pub(crate) async fn post(
 ) -> Result<impl IntoResponse, RouteError> {
     let user_agent = user_agent.map(|ua| UserAgent::parse(ua.as_str().to_owned()));
     let login_type = input.credentials.login_type();
-    let (mut session, user) = match (password_manager.is_enabled(), input.credentials) {
+    let (mut session, user) = match (
+        password_manager.is_enabled() || ldap.is_enabled(),
+        input.credentials,
+    ) {
         (
             true,
             Credentials::Password {
@@ -303,6 +324,7 @@ pub(crate) async fn post(
                 &mut rng,
                 &clock,
                 &password_manager,
+                &ldap,
                 &limiter,
                 requester,
                 &mut repo,
@@ -310,6 +332,7 @@ pub(crate) async fn post(
                 user,
                 password,
                 input.device_id, // TODO check for validity
+                site_config.passkeys_enabled,
             )
             .await?
         }
@@ -499,6 +522,7 @@ async fn user_password_login(
     mut rng: &mut (impl RngCore + CryptoRng + Send),
     clock: &impl Clock,
     password_manager: &PasswordManager,
+    ldap: &LdapBackend,
     limiter: &Limiter,
     requester: RequesterFingerprint,
     repo: &mut BoxRepository,
@@ -506,53 +530,101 @@ async fn user_password_login(
     username: String,
     password: String,
     requested_device_id: Option<String>,
+    passkeys_enabled: bool,
 ) -> Result<(CompatSession, User), RouteError> {
     // Try getting the localpart out of the MXID
     let username = homeserver.localpart(&username).unwrap_or(&username);
 
-    // Find the user
-    let user = repo
-        .user()
-        .find_by_username(username)
-        .await?
-        .filter(mas_data_model::User::is_valid)
-        .ok_or(RouteError::UserNotFound)?;
+    // Check the password against the LDAP directory first, if one is configured.
+    // Users which are not found in the directory go through the local password
+    // check
+    let ldap_user = if ldap.is_enabled() {
+        limiter.check_ldap_password(requester, username)?;
 
-    // Check the rate limit
-    limiter.check_password(requester, &user)?;
+        match ldap
+            .login(&mut rng, clock, repo, homeserver, username, &password)
+            .await
+        {
+            Ok(LdapLogin::Success { user, created, .. }) => Some((user, created)),
+            Ok(LdapLogin::UnknownUser) => None,
+            Ok(LdapLogin::InvalidCredentials) => {
+                return Err(RouteError::LdapVerificationFailed);
+            }
+            Err(LdapError::Ldap(e)) => {
+                // Don't lock everyone out if the directory is unreachable
+                tracing::error!(
+                    error = &e as &dyn std::error::Error,
+                    "Could not check the password against the LDAP directory"
+                );
+                None
+            }
+            Err(e) => return Err(RouteError::Internal(Box::new(e))),
+        }
+    } else {
+        None
+    };
 
-    // Lookup its password
-    let user_password = repo
-        .user_password()
-        .active(&user)
-        .await?
-        .ok_or(RouteError::NoPassword)?;
+    let (user, created) = if let Some((user, created)) = ldap_user {
+        if !user.is_valid() {
+            return Err(RouteError::UserNotFound);
+        }
 
-    // Verify the password
-    let password = Zeroizing::new(password.into_bytes());
+        (user, created)
+    } else {
+        // Find the user
+        let user = repo
+            .user()
+            .find_by_username(username)
+            .await?
+            .filter(mas_data_model::User::is_valid)
+            .ok_or(RouteError::UserNotFound)?;
 
-    let new_password_hash = password_manager
-        .verify_and_upgrade(
-            &mut rng,
-            user_password.version,
-            password,
-            user_password.hashed_password.clone(),
-        )
-        .await
-        .map_err(RouteError::PasswordVerificationFailed)?;
+        // Check the rate limit
+        limiter.check_password(requester, &user)?;
 
-    if let Some((version, hashed_password)) = new_password_hash {
-        // Save the upgraded password if needed
-        repo.user_password()
-            .add(
+        // Lookup its password
+        let user_password = repo
+            .user_password()
+            .active(&user)
+            .await?
+            .ok_or(RouteError::NoPassword)?;
+
+        // Verify the password
+        let password = Zeroizing::new(password.into_bytes());
+
+        let new_password_hash = password_manager
+            .verify_and_upgrade(
                 &mut rng,
-                clock,
-                &user,
-                version,
-                hashed_password,
-                Some(&user_password),
+                user_password.version,
+                password,
+                user_password.hashed_password.clone(),
             )
-            .await?;
+            .await
+            .map_err(RouteError::PasswordVerificationFailed)?;
+
+        if let Some((version, hashed_password)) = new_password_hash {
+            // Save the upgraded password if needed
+            repo.user_password()
+                .add(
+                    &mut rng,
+                    clock,
+                    &user,
+                    version,
+                    hashed_password,
+                    Some(&user_password),
+                )
+                .await?;
+        }
+
+        (user, false)
+    };
+
+    // This API has no way to ask for a second factor, so users who enrolled one
+    // have to go through the browser
+    let authenticator = repo.user_totp().find(&user).await?;
+    let has_passkeys = passkeys_enabled && !repo.user_passkey().all(&user).await?.is_empty();
+    if authenticator.is_some_and(|a| a.is_verified()) || has_passkeys {
+        return Err(RouteError::SecondFactorRequired);
     }
 
     // Lock the user sync to make sure we don't get into a race condition
@@ -560,6 +632,15 @@ async fn user_password_login(
 
     let mxid = homeserver.mxid(&user.username);
 
+    // A user created from the directory isn't provisioned on the homeserver until
+    // the queued job runs, so do it now to be able to create the device
+    if created {
+        homeserver
+            .provision_user(&ProvisionRequest::new(&mxid, &user.sub))
+            .await
+            .map_err(RouteError::ProvisionDeviceFailed)?;
+    }
+
     // Now that the user credentials have been verified, start a new compat session
     let device = if let Some(requested_device_id) = requested_device_id {
         Device::from(requested_device_id)
@@ -957,6 +1038,134 @@ mod tests {
         "###);
     }
 
+    /// Test that passwords are checked against the LDAP directory, and that
+    /// users are created from it.
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_ldap_password_login(pool: PgPool) {
+        setup();
+        let mut state = TestState::from_pool(pool).await.unwrap();
+        let config = mas_config::LdapConfig {
+            create_users: true,
+            ..mas_config::LdapConfig::default()
+        };
+        let directory = crate::ldap::MockDirectory::default().with_entry("john", "hunter2", &[]);
+        state.ldap = LdapBackend::with_directory(&config, std::sync::Arc::new(directory));
+
+        // This does more password checks than the default rate limits allow
+        let mut rate_limiting = mas_config::RateLimitingConfig::default();
+        rate_limiting.login.per_ip.burst = std::num::NonZeroU32::new(10).unwrap();
+        state.limiter = crate::Limiter::new(&rate_limiting).unwrap();
+
+        // A local user, which isn't in the directory
+        user_with_password(&state, "alice", "password").await;
+
+        let login = |user: &str, password: &str| {
+            Request::post("/_matrix/client/v3/login").json(serde_json::json!({
+                "type": "m.login.password",
+                "identifier": {
+                    "type": "m.id.user",
+                    "user": user,
+                },
+                "password": password,
+            }))
+        };
+
+        // The password login flow is advertised
+        let response = state
+            .request(Request::get("/_matrix/client/v3/login").empty())
+            .await;
+        response.assert_status(StatusCode::OK);
+        assert!(response.body().contains("m.login.password"));
+
+        // A wrong password is rejected
+        let response = state.request(login("john", "wrong")).await;
+        response.assert_status(StatusCode::FORBIDDEN);
+
+        // The right one creates the user, provisions it on the homeserver and
+        // starts a session
+        let response = state.request(login("john", "hunter2")).await;
+        response.assert_status(StatusCode::OK);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["user_id"], "@john:example.com");
+
+        let mxid = state.homeserver_connection.mxid("john");
+        state
+            .homeserver_connection
+            .query_user(&mxid)
+            .await
+            .expect("the user to be provisioned");
+
+        // Logging in again uses the same user
+        let response = state.request(login("john", "hunter2")).await;
+        response.assert_status(StatusCode::OK);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["user_id"], "@john:example.com");
+
+        // Users which aren't in the directory use their local password
+        let response = state.request(login("alice", "password")).await;
+        response.assert_status(StatusCode::OK);
+
+        // Which also works if the directory is unreachable
+        state.ldap = LdapBackend::with_directory(
+            &config,
+            std::sync::Arc::new(crate::ldap::MockDirectory::unreachable()),
+        );
+        let response = state.request(login("alice", "password")).await;
+        response.assert_status(StatusCode::OK);
+    }
+
+    /// Test that users with a second factor can't login with only their
+    /// password.
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_user_password_login_second_factor(pool: PgPool) {
+        setup();
+        let state = TestState::from_pool(pool).await.unwrap();
+
+        user_with_password(&state, "alice", "password").await;
+
+        let mut repo = state.repository().await.unwrap();
+        let user = repo
+            .user()
+            .find_by_username("alice")
+            .await
+            .unwrap()
+            .unwrap();
+        let authenticator = repo
+            .user_totp()
+            .add(
+                &mut state.rng(),
+                &state.clock,
+                &user,
+                "encrypted".to_owned(),
+            )
+            .await
+            .unwrap();
+        repo.user_totp()
+            .verify(&state.clock, authenticator)
+            .await
+            .unwrap();
+        repo.save().await.unwrap();
+
+        let request = Request::post("/_matrix/client/v3/login").json(serde_json::json!({
+            "type": "m.login.password",
+            "identifier": {
+                "type": "m.id.user",
+                "user": "alice",
+            },
+            "password": "password",
+        }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::FORBIDDEN);
+        let body: serde_json::Value = response.json();
+        insta::assert_json_snapshot!(body, @r###"
+        {
+          "errcode": "M_FORBIDDEN",
+          "error": "This account requires a second factor, use single sign-on instead"
+        }
+        "###);
+    }
+
     /// Test that password logins are rate limited.
     #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
     async fn test_password_login_rate_limit(pool: PgPool) {
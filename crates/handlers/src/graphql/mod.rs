@@ -29,6 +29,7 @@ use mas_axum_utils::{
     FancyError, SessionInfo, SessionInfoExt, cookies::CookieJar, sentry::SentryEventID,
 };
 use mas_data_model::{BrowserSession, Session, SiteConfig, User};
+use mas_keystore::Encrypter;
 use mas_matrix::HomeserverConnection;
 use mas_policy::{InstantiateError, Policy, PolicyFactory};
 use mas_router::UrlBuilder;
@@ -75,6 +76,7 @@ struct GraphQLState {
     password_manager: PasswordManager,
     url_builder: UrlBuilder,
     limiter: Limiter,
+    encrypter: Encrypter,
 }
 
 #[async_trait::async_trait]
@@ -111,6 +113,10 @@ impl state::State for GraphQLState {
         &self.limiter
     }
 
+    fn encrypter(&self) -> &Encrypter {
+        &self.encrypter
+    }
+
     fn clock(&self) -> BoxClock {
         let clock = SystemClock::default();
         Box::new(clock)
@@ -134,6 +140,7 @@ pub fn schema(
     password_manager: PasswordManager,
     url_builder: UrlBuilder,
     limiter: Limiter,
+    encrypter: Encrypter,
 ) -> Schema {
     let state = GraphQLState {
         pool: pool.clone(),
@@ -143,6 +150,7 @@ pub fn schema(
         password_manager,
         url_builder,
         limiter,
+        encrypter,
     };
     let state: BoxState = Box::new(state);
 
@@ -494,6 +502,12 @@ impl OwnerId for mas_data_model::UserEmail {
     }
 }
 
+impl OwnerId for mas_data_model::UserPasskey {
+    fn owner_id(&self) -> Option<Ulid> {
+        Some(self.user_id)
+    }
+}
+
 impl OwnerId for Session {
     fn owner_id(&self) -> Option<Ulid> {
         self.user_id
@@ -12,7 +12,7 @@ use ulid::Ulid;
 use super::{
     Anonymous, Authentication, BrowserSession, CompatSession, CompatSsoLogin, OAuth2Client,
     OAuth2Session, SiteConfig, UpstreamOAuth2Link, UpstreamOAuth2Provider, User, UserEmail,
-    UserEmailAuthentication, UserRecoveryTicket,
+    UserEmailAuthentication, UserPasskey, UserRecoveryTicket,
 };
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -28,6 +28,7 @@ pub enum NodeType {
     User,
     UserEmail,
     UserEmailAuthentication,
+    UserPasskey,
     UserRecoveryTicket,
 }
 
@@ -54,6 +55,7 @@ impl NodeType {
             NodeType::User => "user",
             NodeType::UserEmail => "user_email",
             NodeType::UserEmailAuthentication => "user_email_authentication",
+            NodeType::UserPasskey => "user_passkey",
             NodeType::UserRecoveryTicket => "user_recovery_ticket",
         }
     }
@@ -71,6 +73,7 @@ impl NodeType {
             "user" => Some(NodeType::User),
             "user_email" => Some(NodeType::UserEmail),
             "user_email_authentication" => Some(NodeType::UserEmailAuthentication),
+            "user_passkey" => Some(NodeType::UserPasskey),
             "user_recovery_ticket" => Some(NodeType::UserRecoveryTicket),
             _ => None,
         }
@@ -128,5 +131,6 @@ pub enum Node {
     User(Box<User>),
     UserEmail(Box<UserEmail>),
     UserEmailAuthentication(Box<UserEmailAuthentication>),
+    UserPasskey(Box<UserPasskey>),
     UserRecoveryTicket(Box<UserRecoveryTicket>),
 }
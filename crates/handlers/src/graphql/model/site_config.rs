@@ -56,6 +56,9 @@ pub struct SiteConfig {
 
     /// Whether users can log in with their email address.
     login_with_email_allowed: bool,
+
+    /// Whether users can register passkeys and use them to log in.
+    passkeys_enabled: bool,
 }
 
 #[derive(SimpleObject)]
@@ -102,6 +105,7 @@ impl SiteConfig {
             account_deactivation_allowed: data_model.account_deactivation_allowed,
             minimum_password_complexity: data_model.minimum_password_complexity,
             login_with_email_allowed: data_model.login_with_email_allowed,
+            passkeys_enabled: data_model.passkeys_enabled,
         }
     }
 }
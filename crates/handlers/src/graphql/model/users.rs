@@ -17,7 +17,10 @@ use mas_storage::{
     compat::{CompatSessionFilter, CompatSsoLoginFilter, CompatSsoLoginRepository},
     oauth2::{OAuth2SessionFilter, OAuth2SessionRepository},
     upstream_oauth2::{UpstreamOAuthLinkFilter, UpstreamOAuthLinkRepository},
-    user::{BrowserSessionFilter, BrowserSessionRepository, UserEmailFilter, UserEmailRepository},
+    user::{
+        BrowserSessionFilter, BrowserSessionRepository, UserEmailFilter, UserEmailRepository,
+        UserPasskeyRepository,
+    },
 };
 
 use super::{
@@ -715,6 +718,46 @@ impl User {
 
         Ok(password.is_some())
     }
+
+    /// Check if the user has a verified TOTP authenticator, used as a second
+    /// factor on password logins.
+    async fn has_totp_authenticator(
+        &self,
+        ctx: &Context<'_>,
+    ) -> Result<bool, async_graphql::Error> {
+        let state = ctx.state();
+        let mut repo = state.repository().await?;
+
+        let authenticator = repo.user_totp().find(&self.0).await?;
+
+        Ok(authenticator.is_some_and(|a| a.is_verified()))
+    }
+
+    /// Get the list of passkeys registered by the user, oldest first.
+    async fn passkeys(&self, ctx: &Context<'_>) -> Result<Vec<UserPasskey>, async_graphql::Error> {
+        let state = ctx.state();
+        let mut repo = state.repository().await?;
+
+        let passkeys = repo.user_passkey().all(&self.0).await?;
+        repo.cancel().await?;
+
+        Ok(passkeys.into_iter().map(UserPasskey).collect())
+    }
+
+    /// Get the number of recovery codes the user can still use instead of
+    /// their second factor.
+    async fn recovery_codes_remaining(
+        &self,
+        ctx: &Context<'_>,
+    ) -> Result<usize, async_graphql::Error> {
+        let state = ctx.state();
+        let mut repo = state.repository().await?;
+
+        let count = repo.user_recovery_code().count_unused(&self.0).await?;
+        repo.cancel().await?;
+
+        Ok(count)
+    }
 }
 
 /// A session in an application, either a compatibility or an OAuth 2.0 one
@@ -753,6 +796,39 @@ impl UserEmail {
     }
 }
 
+/// A `WebAuthn` passkey registered by a user
+#[derive(Description)]
+pub struct UserPasskey(pub mas_data_model::UserPasskey);
+
+#[Object(use_type_description)]
+impl UserPasskey {
+    /// ID of the object.
+    pub async fn id(&self) -> ID {
+        NodeType::UserPasskey.id(self.0.id)
+    }
+
+    /// The name given to the passkey by the user
+    async fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    /// The transports supported by the authenticator, as reported by the
+    /// browser during registration
+    async fn transports(&self) -> &[String] {
+        &self.0.transports
+    }
+
+    /// When the object was created.
+    pub async fn created_at(&self) -> DateTime<Utc> {
+        self.0.created_at
+    }
+
+    /// When the passkey was last used to authenticate
+    async fn last_used_at(&self) -> Option<DateTime<Utc>> {
+        self.0.last_used_at
+    }
+}
+
 /// The state of a compatibility session.
 #[derive(Enum, Copy, Clone, Eq, PartialEq)]
 pub enum UserEmailState {
@@ -114,6 +114,12 @@ impl MatrixMutations {
                 .context("Failed to unset display name")?;
         }
 
-        Ok(SetDisplayNamePayload::Set(User(user.clone())))
+        // Record the change, so that it is reflected in the `updated_at` claim
+        let clock = state.clock();
+        let mut repo = state.repository().await?;
+        let user = repo.user().touch_profile(&clock, user).await?;
+        repo.save().await?;
+
+        Ok(SetDisplayNamePayload::Set(User(user)))
     }
 }
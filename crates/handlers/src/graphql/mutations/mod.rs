@@ -10,6 +10,9 @@ mod matrix;
 mod oauth2_session;
 mod user;
 mod user_email;
+mod user_passkey;
+mod user_recovery_code;
+mod user_totp;
 
 use anyhow::Context as _;
 use async_graphql::MergedObject;
@@ -24,6 +27,9 @@ use crate::passwords::PasswordManager;
 #[derive(Default, MergedObject)]
 pub struct Mutation(
     user_email::UserEmailMutations,
+    user_totp::UserTotpMutations,
+    user_passkey::UserPasskeyMutations,
+    user_recovery_code::UserRecoveryCodeMutations,
     user::UserMutations,
     oauth2_session::OAuth2SessionMutations,
     compat_session::CompatSessionMutations,
@@ -14,7 +14,7 @@ use mas_storage::{
         OAuth2AccessTokenRepository, OAuth2ClientRepository, OAuth2RefreshTokenRepository,
         OAuth2SessionRepository,
     },
-    queue::{QueueJobRepositoryExt as _, SyncDevicesJob},
+    queue::{QueueJobRepositoryExt as _, SendBackchannelLogoutJob, SyncDevicesJob},
     user::UserRepository,
 };
 use oauth2_types::scope::Scope;
@@ -181,7 +181,7 @@ impl OAuth2SessionMutations {
         };
         let access_token = repo
             .oauth2_access_token()
-            .add(&mut rng, &clock, &session, access_token, ttl)
+            .add(&mut rng, &clock, &session, access_token, ttl, None, None)
             .await?;
 
         let refresh_token = if permanent {
@@ -241,6 +241,10 @@ impl OAuth2SessionMutations {
                 .await?;
         }
 
+        repo.queue_job()
+            .schedule_job(&mut rng, &clock, SendBackchannelLogoutJob::new(&session))
+            .await?;
+
         let session = repo.oauth2_session().finish(&clock, session).await?;
 
         repo.save().await?;
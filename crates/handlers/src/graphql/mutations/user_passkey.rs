@@ -0,0 +1,509 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+use async_graphql::{Context, Description, Enum, ID, InputObject, Object};
+use mas_storage::{
+    RepositoryAccess,
+    user::{BrowserSessionRepository, UserPasskeyRepository},
+};
+use ulid::Ulid;
+
+use super::verify_password_if_needed;
+use crate::{
+    graphql::{
+        model::{NodeType, User, UserPasskey},
+        state::ContextExt,
+    },
+    webauthn::{self, RelyingParty},
+};
+
+/// Maximum length of a passkey name, in characters
+const MAX_NAME_LENGTH: usize = 256;
+
+/// Validate and normalize a passkey name
+fn clean_name(name: &str) -> Option<String> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+        None
+    } else {
+        Some(name.to_owned())
+    }
+}
+
+#[derive(Default)]
+pub struct UserPasskeyMutations {
+    _private: (),
+}
+
+/// The input for the `startRegisterPasskey` mutation
+#[derive(InputObject)]
+struct StartRegisterPasskeyInput {
+    /// The user's current password. This is required if the user has a
+    /// password on its account.
+    password: Option<String>,
+}
+
+/// The status of the `startRegisterPasskey` mutation
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+enum StartRegisterPasskeyStatus {
+    /// The registration started, the options must now be passed to
+    /// `navigator.credentials.create()`
+    Started,
+
+    /// Passkeys are disabled on this server
+    Disabled,
+
+    /// The password provided is incorrect
+    IncorrectPassword,
+}
+
+/// The payload of the `startRegisterPasskey` mutation
+#[derive(Description)]
+enum StartRegisterPasskeyPayload {
+    Started { id: Ulid, options: String },
+    Disabled,
+    IncorrectPassword,
+}
+
+#[Object(use_type_description)]
+impl StartRegisterPasskeyPayload {
+    /// Status of the operation
+    async fn status(&self) -> StartRegisterPasskeyStatus {
+        match self {
+            Self::Started { .. } => StartRegisterPasskeyStatus::Started,
+            Self::Disabled => StartRegisterPasskeyStatus::Disabled,
+            Self::IncorrectPassword => StartRegisterPasskeyStatus::IncorrectPassword,
+        }
+    }
+
+    /// The ID of the registration, to pass to `completeRegisterPasskey`
+    async fn id(&self) -> Option<ID> {
+        match self {
+            Self::Started { id, .. } => Some(ID(id.to_string())),
+            Self::Disabled | Self::IncorrectPassword => None,
+        }
+    }
+
+    /// The options to pass to `navigator.credentials.create()`, as a JSON
+    /// string in the format accepted by
+    /// `PublicKeyCredential.parseCreationOptionsFromJSON()`
+    async fn options(&self) -> Option<&str> {
+        match self {
+            Self::Started { options, .. } => Some(options),
+            Self::Disabled | Self::IncorrectPassword => None,
+        }
+    }
+}
+
+/// The input for the `completeRegisterPasskey` mutation
+#[derive(InputObject)]
+struct CompleteRegisterPasskeyInput {
+    /// The ID of the registration, as returned by `startRegisterPasskey`
+    id: ID,
+
+    /// The name to give to the passkey
+    name: String,
+
+    /// The credential returned by `navigator.credentials.create()`, serialized
+    /// as JSON with `PublicKeyCredential.toJSON()`
+    response: String,
+}
+
+/// The status of the `completeRegisterPasskey` mutation
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+enum CompleteRegisterPasskeyStatus {
+    /// The passkey was added
+    Added,
+
+    /// Passkeys are disabled on this server
+    Disabled,
+
+    /// The registration is unknown, expired or was already completed
+    InvalidChallenge,
+
+    /// The credential is invalid
+    InvalidResponse,
+
+    /// The name is empty or too long
+    InvalidName,
+
+    /// The passkey is already registered
+    Exists,
+}
+
+/// The payload of the `completeRegisterPasskey` mutation
+#[derive(Description)]
+enum CompleteRegisterPasskeyPayload {
+    Added(mas_data_model::UserPasskey),
+    Disabled,
+    InvalidChallenge,
+    InvalidResponse,
+    InvalidName,
+    Exists,
+}
+
+#[Object(use_type_description)]
+impl CompleteRegisterPasskeyPayload {
+    /// Status of the operation
+    async fn status(&self) -> CompleteRegisterPasskeyStatus {
+        match self {
+            Self::Added(_) => CompleteRegisterPasskeyStatus::Added,
+            Self::Disabled => CompleteRegisterPasskeyStatus::Disabled,
+            Self::InvalidChallenge => CompleteRegisterPasskeyStatus::InvalidChallenge,
+            Self::InvalidResponse => CompleteRegisterPasskeyStatus::InvalidResponse,
+            Self::InvalidName => CompleteRegisterPasskeyStatus::InvalidName,
+            Self::Exists => CompleteRegisterPasskeyStatus::Exists,
+        }
+    }
+
+    /// The passkey which was added
+    async fn passkey(&self) -> Option<UserPasskey> {
+        match self {
+            Self::Added(passkey) => Some(UserPasskey(passkey.clone())),
+            _ => None,
+        }
+    }
+}
+
+/// The input for the `renamePasskey` mutation
+#[derive(InputObject)]
+struct RenamePasskeyInput {
+    /// The ID of the passkey to rename
+    id: ID,
+
+    /// The new name
+    name: String,
+}
+
+/// The status of the `renamePasskey` mutation
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+enum RenamePasskeyStatus {
+    /// The passkey was renamed
+    Renamed,
+
+    /// The passkey was not found
+    NotFound,
+
+    /// The name is empty or too long
+    InvalidName,
+}
+
+/// The payload of the `renamePasskey` mutation
+#[derive(Description)]
+enum RenamePasskeyPayload {
+    Renamed(mas_data_model::UserPasskey),
+    NotFound,
+    InvalidName,
+}
+
+#[Object(use_type_description)]
+impl RenamePasskeyPayload {
+    /// Status of the operation
+    async fn status(&self) -> RenamePasskeyStatus {
+        match self {
+            Self::Renamed(_) => RenamePasskeyStatus::Renamed,
+            Self::NotFound => RenamePasskeyStatus::NotFound,
+            Self::InvalidName => RenamePasskeyStatus::InvalidName,
+        }
+    }
+
+    /// The passkey which was renamed
+    async fn passkey(&self) -> Option<UserPasskey> {
+        match self {
+            Self::Renamed(passkey) => Some(UserPasskey(passkey.clone())),
+            Self::NotFound | Self::InvalidName => None,
+        }
+    }
+}
+
+/// The input for the `removePasskey` mutation
+#[derive(InputObject)]
+struct RemovePasskeyInput {
+    /// The ID of the passkey to remove
+    id: ID,
+
+    /// The user's current password. This is required if the user has a
+    /// password on its account.
+    password: Option<String>,
+}
+
+/// The status of the `removePasskey` mutation
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+enum RemovePasskeyStatus {
+    /// The passkey was removed
+    Removed,
+
+    /// The passkey was not found
+    NotFound,
+
+    /// The password provided is incorrect
+    IncorrectPassword,
+}
+
+/// The payload of the `removePasskey` mutation
+#[derive(Description)]
+enum RemovePasskeyPayload {
+    Removed(mas_data_model::User),
+    NotFound,
+    IncorrectPassword,
+}
+
+#[Object(use_type_description)]
+impl RemovePasskeyPayload {
+    /// Status of the operation
+    async fn status(&self) -> RemovePasskeyStatus {
+        match self {
+            Self::Removed(_) => RemovePasskeyStatus::Removed,
+            Self::NotFound => RemovePasskeyStatus::NotFound,
+            Self::IncorrectPassword => RemovePasskeyStatus::IncorrectPassword,
+        }
+    }
+
+    /// The user who owned the passkey
+    async fn user(&self) -> Option<User> {
+        match self {
+            Self::Removed(user) => Some(User(user.clone())),
+            Self::NotFound | Self::IncorrectPassword => None,
+        }
+    }
+}
+
+#[Object]
+impl UserPasskeyMutations {
+    /// Start registering a new passkey for the current user
+    async fn start_register_passkey(
+        &self,
+        ctx: &Context<'_>,
+        input: StartRegisterPasskeyInput,
+    ) -> Result<StartRegisterPasskeyPayload, async_graphql::Error> {
+        let state = ctx.state();
+        let mut rng = state.rng();
+        let clock = state.clock();
+        let requester = ctx.requester();
+
+        // Only allow calling this if the requester is a browser session
+        let Some(browser_session) = requester.browser_session() else {
+            return Err(async_graphql::Error::new("Unauthorized"));
+        };
+        let user = &browser_session.user;
+
+        if !state.site_config().passkeys_enabled {
+            return Ok(StartRegisterPasskeyPayload::Disabled);
+        }
+
+        let mut repo = state.repository().await?;
+
+        if !verify_password_if_needed(
+            requester,
+            state.site_config(),
+            &state.password_manager(),
+            input.password,
+            user,
+            &mut repo,
+        )
+        .await?
+        {
+            return Ok(StartRegisterPasskeyPayload::IncorrectPassword);
+        }
+
+        let existing = repo.user_passkey().all(user).await?;
+
+        let challenge = webauthn::generate_challenge(&mut rng);
+        let challenge = repo
+            .user_passkey()
+            .add_challenge(&mut rng, &clock, Some(browser_session), challenge)
+            .await?;
+
+        repo.save().await?;
+
+        let relying_party =
+            RelyingParty::new(state.url_builder(), &state.site_config().server_name);
+        let options = relying_party.creation_options(&challenge.challenge, user, &existing);
+
+        Ok(StartRegisterPasskeyPayload::Started {
+            id: challenge.id,
+            options: options.to_string(),
+        })
+    }
+
+    /// Finish registering a passkey, by checking the credential created by
+    /// the browser
+    async fn complete_register_passkey(
+        &self,
+        ctx: &Context<'_>,
+        input: CompleteRegisterPasskeyInput,
+    ) -> Result<CompleteRegisterPasskeyPayload, async_graphql::Error> {
+        let state = ctx.state();
+        let mut rng = state.rng();
+        let clock = state.clock();
+        let requester = ctx.requester();
+
+        // Only allow calling this if the requester is a browser session
+        let Some(browser_session) = requester.browser_session() else {
+            return Err(async_graphql::Error::new("Unauthorized"));
+        };
+        let user = &browser_session.user;
+
+        if !state.site_config().passkeys_enabled {
+            return Ok(CompleteRegisterPasskeyPayload::Disabled);
+        }
+
+        let Some(name) = clean_name(&input.name) else {
+            return Ok(CompleteRegisterPasskeyPayload::InvalidName);
+        };
+
+        let Ok(id) = input.id.parse::<Ulid>() else {
+            return Ok(CompleteRegisterPasskeyPayload::InvalidChallenge);
+        };
+
+        let mut repo = state.repository().await?;
+
+        let Some(challenge) = repo.user_passkey().lookup_challenge(id).await? else {
+            return Ok(CompleteRegisterPasskeyPayload::InvalidChallenge);
+        };
+
+        // The challenge must have been issued to this session, and be fresh
+        if challenge.user_session_id != Some(browser_session.id)
+            || challenge.is_completed()
+            || clock.now() - challenge.created_at > webauthn::CHALLENGE_MAX_AGE
+        {
+            return Ok(CompleteRegisterPasskeyPayload::InvalidChallenge);
+        }
+
+        let challenge = repo
+            .user_passkey()
+            .complete_challenge(&clock, challenge)
+            .await?;
+
+        let relying_party =
+            RelyingParty::new(state.url_builder(), &state.site_config().server_name);
+        let credential = serde_json::from_str(&input.response)
+            .map_err(webauthn::WebauthnError::from)
+            .and_then(|response| {
+                relying_party.verify_registration(&challenge.challenge, &response)
+            });
+        let credential = match credential {
+            Ok(credential) => credential,
+            Err(e) => {
+                tracing::warn!(
+                    error = &e as &dyn std::error::Error,
+                    "Invalid passkey registration"
+                );
+                // Still save, so that the challenge can't be used again
+                repo.save().await?;
+                return Ok(CompleteRegisterPasskeyPayload::InvalidResponse);
+            }
+        };
+
+        let existing = repo
+            .user_passkey()
+            .find_by_credential_id(&credential.credential_id)
+            .await?;
+        if existing.is_some() {
+            repo.save().await?;
+            return Ok(CompleteRegisterPasskeyPayload::Exists);
+        }
+
+        let passkey = repo
+            .user_passkey()
+            .add(
+                &mut rng,
+                &clock,
+                user,
+                credential.credential_id,
+                name,
+                credential.public_key,
+                credential.sign_count,
+                credential.transports,
+            )
+            .await?;
+
+        // The user just proved they have the passkey, so record it on the
+        // current session
+        repo.browser_session()
+            .authenticate_with_passkey(&mut rng, &clock, browser_session, &passkey)
+            .await?;
+
+        repo.save().await?;
+
+        Ok(CompleteRegisterPasskeyPayload::Added(passkey))
+    }
+
+    /// Rename a passkey of the current user
+    async fn rename_passkey(
+        &self,
+        ctx: &Context<'_>,
+        input: RenamePasskeyInput,
+    ) -> Result<RenamePasskeyPayload, async_graphql::Error> {
+        let state = ctx.state();
+        let requester = ctx.requester();
+        let id = NodeType::UserPasskey.extract_ulid(&input.id)?;
+
+        // Only allow calling this if the requester is a browser session
+        let Some(browser_session) = requester.browser_session() else {
+            return Err(async_graphql::Error::new("Unauthorized"));
+        };
+
+        let mut repo = state.repository().await?;
+
+        let passkey = repo.user_passkey().lookup(id).await?;
+        let Some(passkey) = passkey.filter(|p| p.user_id == browser_session.user.id) else {
+            return Ok(RenamePasskeyPayload::NotFound);
+        };
+
+        let Some(name) = clean_name(&input.name) else {
+            return Ok(RenamePasskeyPayload::InvalidName);
+        };
+
+        let passkey = repo.user_passkey().rename(passkey, name).await?;
+
+        repo.save().await?;
+
+        Ok(RenamePasskeyPayload::Renamed(passkey))
+    }
+
+    /// Remove a passkey of the current user
+    async fn remove_passkey(
+        &self,
+        ctx: &Context<'_>,
+        input: RemovePasskeyInput,
+    ) -> Result<RemovePasskeyPayload, async_graphql::Error> {
+        let state = ctx.state();
+        let requester = ctx.requester();
+        let id = NodeType::UserPasskey.extract_ulid(&input.id)?;
+
+        // Only allow calling this if the requester is a browser session
+        let Some(browser_session) = requester.browser_session() else {
+            return Err(async_graphql::Error::new("Unauthorized"));
+        };
+        let user = &browser_session.user;
+
+        let mut repo = state.repository().await?;
+
+        let passkey = repo.user_passkey().lookup(id).await?;
+        let Some(passkey) = passkey.filter(|p| p.user_id == user.id) else {
+            return Ok(RemovePasskeyPayload::NotFound);
+        };
+
+        if !verify_password_if_needed(
+            requester,
+            state.site_config(),
+            &state.password_manager(),
+            input.password,
+            user,
+            &mut repo,
+        )
+        .await?
+        {
+            return Ok(RemovePasskeyPayload::IncorrectPassword);
+        }
+
+        repo.user_passkey().remove(passkey).await?;
+
+        repo.save().await?;
+
+        Ok(RemovePasskeyPayload::Removed(user.clone()))
+    }
+}
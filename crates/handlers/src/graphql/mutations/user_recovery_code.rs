@@ -0,0 +1,139 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+use async_graphql::{Context, Description, Enum, InputObject, Object};
+use mas_storage::{
+    RepositoryAccess,
+    user::{UserPasskeyRepository, UserTotpRepository},
+};
+
+use super::verify_password_if_needed;
+use crate::graphql::{model::User, state::ContextExt};
+
+#[derive(Default)]
+pub struct UserRecoveryCodeMutations {
+    _private: (),
+}
+
+/// The input for the `generateRecoveryCodes` mutation
+#[derive(InputObject)]
+struct GenerateRecoveryCodesInput {
+    /// The user's current password. This is required if the user has a
+    /// password on its account.
+    password: Option<String>,
+}
+
+/// The status of the `generateRecoveryCodes` mutation
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+enum GenerateRecoveryCodesStatus {
+    /// New recovery codes were generated, replacing any previous ones
+    Generated,
+
+    /// The user has no second factor, so recovery codes would be useless
+    NoSecondFactor,
+
+    /// The password provided is incorrect
+    IncorrectPassword,
+}
+
+/// The payload of the `generateRecoveryCodes` mutation
+#[derive(Description)]
+enum GenerateRecoveryCodesPayload {
+    Generated {
+        user: mas_data_model::User,
+        codes: Vec<String>,
+    },
+    NoSecondFactor,
+    IncorrectPassword,
+}
+
+#[Object(use_type_description)]
+impl GenerateRecoveryCodesPayload {
+    /// Status of the operation
+    async fn status(&self) -> GenerateRecoveryCodesStatus {
+        match self {
+            Self::Generated { .. } => GenerateRecoveryCodesStatus::Generated,
+            Self::NoSecondFactor => GenerateRecoveryCodesStatus::NoSecondFactor,
+            Self::IncorrectPassword => GenerateRecoveryCodesStatus::IncorrectPassword,
+        }
+    }
+
+    /// The new recovery codes. They are only shown once, and each of them can
+    /// be used once.
+    async fn codes(&self) -> Option<&[String]> {
+        match self {
+            Self::Generated { codes, .. } => Some(codes),
+            Self::NoSecondFactor | Self::IncorrectPassword => None,
+        }
+    }
+
+    /// The user who generated the codes
+    async fn user(&self) -> Option<User> {
+        match self {
+            Self::Generated { user, .. } => Some(User(user.clone())),
+            Self::NoSecondFactor | Self::IncorrectPassword => None,
+        }
+    }
+}
+
+#[Object]
+impl UserRecoveryCodeMutations {
+    /// Generate a new set of recovery codes for the current user. Any
+    /// previous codes, used or not, stop working.
+    async fn generate_recovery_codes(
+        &self,
+        ctx: &Context<'_>,
+        input: GenerateRecoveryCodesInput,
+    ) -> Result<GenerateRecoveryCodesPayload, async_graphql::Error> {
+        let state = ctx.state();
+        let mut rng = state.rng();
+        let clock = state.clock();
+        let requester = ctx.requester();
+        let password_manager = state.password_manager();
+
+        // Only allow calling this if the requester is a browser session
+        let Some(browser_session) = requester.browser_session() else {
+            return Err(async_graphql::Error::new("Unauthorized"));
+        };
+        let user = &browser_session.user;
+
+        let mut repo = state.repository().await?;
+
+        let has_totp = repo
+            .user_totp()
+            .find(user)
+            .await?
+            .is_some_and(|a| a.is_verified());
+        let has_passkeys = state.site_config().passkeys_enabled
+            && !repo.user_passkey().all(user).await?.is_empty();
+        if !has_totp && !has_passkeys {
+            return Ok(GenerateRecoveryCodesPayload::NoSecondFactor);
+        }
+
+        if !verify_password_if_needed(
+            requester,
+            state.site_config(),
+            &password_manager,
+            input.password,
+            user,
+            &mut repo,
+        )
+        .await?
+        {
+            return Ok(GenerateRecoveryCodesPayload::IncorrectPassword);
+        }
+
+        let codes =
+            crate::recovery_codes::regenerate(&mut rng, &clock, &password_manager, &mut repo, user)
+                .await?;
+
+        repo.save().await?;
+
+        Ok(GenerateRecoveryCodesPayload::Generated {
+            user: user.clone(),
+            codes,
+        })
+    }
+}
@@ -0,0 +1,354 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+use anyhow::Context as _;
+use async_graphql::{Context, Description, Enum, InputObject, Object};
+use mas_storage::{
+    RepositoryAccess,
+    user::{BrowserSessionRepository, UserTotpRepository},
+};
+
+use super::verify_password_if_needed;
+use crate::graphql::{model::User, state::ContextExt};
+
+#[derive(Default)]
+pub struct UserTotpMutations {
+    _private: (),
+}
+
+/// The input for the `startTotpEnrollment` mutation
+#[derive(InputObject)]
+struct StartTotpEnrollmentInput {
+    /// The user's current password. This is required if the user has a
+    /// password on its account.
+    password: Option<String>,
+}
+
+/// The status of the `startTotpEnrollment` mutation
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+enum StartTotpEnrollmentStatus {
+    /// The enrollment started, the secret must now be added to an
+    /// authenticator app and verified
+    Started,
+
+    /// The user already has a verified TOTP authenticator
+    AlreadyEnrolled,
+
+    /// The password provided is incorrect
+    IncorrectPassword,
+}
+
+/// The payload of the `startTotpEnrollment` mutation
+#[derive(Description)]
+enum StartTotpEnrollmentPayload {
+    Started { secret: String, uri: String },
+    AlreadyEnrolled,
+    IncorrectPassword,
+}
+
+#[Object(use_type_description)]
+impl StartTotpEnrollmentPayload {
+    /// Status of the operation
+    async fn status(&self) -> StartTotpEnrollmentStatus {
+        match self {
+            Self::Started { .. } => StartTotpEnrollmentStatus::Started,
+            Self::AlreadyEnrolled => StartTotpEnrollmentStatus::AlreadyEnrolled,
+            Self::IncorrectPassword => StartTotpEnrollmentStatus::IncorrectPassword,
+        }
+    }
+
+    /// The shared secret, base32-encoded, for manual entry in an
+    /// authenticator app
+    async fn secret(&self) -> Option<&str> {
+        match self {
+            Self::Started { secret, .. } => Some(secret),
+            Self::AlreadyEnrolled | Self::IncorrectPassword => None,
+        }
+    }
+
+    /// The `otpauth://` URI to display as a QR code
+    async fn uri(&self) -> Option<&str> {
+        match self {
+            Self::Started { uri, .. } => Some(uri),
+            Self::AlreadyEnrolled | Self::IncorrectPassword => None,
+        }
+    }
+}
+
+/// The input for the `verifyTotpEnrollment` mutation
+#[derive(InputObject)]
+struct VerifyTotpEnrollmentInput {
+    /// The code generated by the authenticator app
+    code: String,
+}
+
+/// The status of the `verifyTotpEnrollment` mutation
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+enum VerifyTotpEnrollmentStatus {
+    /// The authenticator was verified, and will now be required on password
+    /// logins
+    Verified,
+
+    /// The code is invalid
+    InvalidCode,
+
+    /// There is no pending enrollment
+    NotStarted,
+
+    /// Too many attempts, try again later
+    RateLimited,
+}
+
+/// The payload of the `verifyTotpEnrollment` mutation
+#[derive(Description)]
+enum VerifyTotpEnrollmentPayload {
+    Verified(mas_data_model::User),
+    InvalidCode,
+    NotStarted,
+    RateLimited,
+}
+
+#[Object(use_type_description)]
+impl VerifyTotpEnrollmentPayload {
+    /// Status of the operation
+    async fn status(&self) -> VerifyTotpEnrollmentStatus {
+        match self {
+            Self::Verified(_) => VerifyTotpEnrollmentStatus::Verified,
+            Self::InvalidCode => VerifyTotpEnrollmentStatus::InvalidCode,
+            Self::NotStarted => VerifyTotpEnrollmentStatus::NotStarted,
+            Self::RateLimited => VerifyTotpEnrollmentStatus::RateLimited,
+        }
+    }
+
+    /// The user who enrolled the authenticator
+    async fn user(&self) -> Option<User> {
+        match self {
+            Self::Verified(user) => Some(User(user.clone())),
+            Self::InvalidCode | Self::NotStarted | Self::RateLimited => None,
+        }
+    }
+}
+
+/// The input for the `removeTotpAuthenticator` mutation
+#[derive(InputObject)]
+struct RemoveTotpAuthenticatorInput {
+    /// The user's current password. This is required if the user has a
+    /// password on its account.
+    password: Option<String>,
+}
+
+/// The status of the `removeTotpAuthenticator` mutation
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+enum RemoveTotpAuthenticatorStatus {
+    /// The authenticator was removed
+    Removed,
+
+    /// The user has no TOTP authenticator
+    NotFound,
+
+    /// The password provided is incorrect
+    IncorrectPassword,
+}
+
+/// The payload of the `removeTotpAuthenticator` mutation
+#[derive(Description)]
+enum RemoveTotpAuthenticatorPayload {
+    Removed(mas_data_model::User),
+    NotFound,
+    IncorrectPassword,
+}
+
+#[Object(use_type_description)]
+impl RemoveTotpAuthenticatorPayload {
+    /// Status of the operation
+    async fn status(&self) -> RemoveTotpAuthenticatorStatus {
+        match self {
+            Self::Removed(_) => RemoveTotpAuthenticatorStatus::Removed,
+            Self::NotFound => RemoveTotpAuthenticatorStatus::NotFound,
+            Self::IncorrectPassword => RemoveTotpAuthenticatorStatus::IncorrectPassword,
+        }
+    }
+
+    /// The user whose authenticator was removed
+    async fn user(&self) -> Option<User> {
+        match self {
+            Self::Removed(user) => Some(User(user.clone())),
+            Self::NotFound | Self::IncorrectPassword => None,
+        }
+    }
+}
+
+#[Object]
+impl UserTotpMutations {
+    /// Start enrolling a TOTP authenticator for the current user. Any pending
+    /// enrollment is replaced.
+    async fn start_totp_enrollment(
+        &self,
+        ctx: &Context<'_>,
+        input: StartTotpEnrollmentInput,
+    ) -> Result<StartTotpEnrollmentPayload, async_graphql::Error> {
+        let state = ctx.state();
+        let mut rng = state.rng();
+        let clock = state.clock();
+        let requester = ctx.requester();
+
+        // Only allow calling this if the requester is a browser session
+        let Some(browser_session) = requester.browser_session() else {
+            return Err(async_graphql::Error::new("Unauthorized"));
+        };
+        let user = &browser_session.user;
+
+        let mut repo = state.repository().await?;
+
+        if !verify_password_if_needed(
+            requester,
+            state.site_config(),
+            &state.password_manager(),
+            input.password,
+            user,
+            &mut repo,
+        )
+        .await?
+        {
+            return Ok(StartTotpEnrollmentPayload::IncorrectPassword);
+        }
+
+        let existing = repo.user_totp().find(user).await?;
+        if let Some(authenticator) = existing {
+            if authenticator.is_verified() {
+                return Ok(StartTotpEnrollmentPayload::AlreadyEnrolled);
+            }
+
+            // Replace the pending enrollment
+            repo.user_totp().remove(authenticator).await?;
+        }
+
+        let secret = crate::totp::generate_secret(&mut rng);
+        let encrypted_secret = state
+            .encrypter()
+            .encrypt_to_string(&secret)
+            .map_err(|_| async_graphql::Error::new("Failed to encrypt the TOTP secret"))?;
+
+        repo.user_totp()
+            .add(&mut rng, &clock, user, encrypted_secret)
+            .await?;
+
+        repo.save().await?;
+
+        let uri = crate::totp::provisioning_uri(
+            &secret,
+            &state.site_config().server_name,
+            &user.username,
+        );
+
+        Ok(StartTotpEnrollmentPayload::Started {
+            secret: crate::totp::encode_secret(&secret),
+            uri: uri.into(),
+        })
+    }
+
+    /// Finish enrolling a TOTP authenticator, by checking a code generated by
+    /// the authenticator app
+    async fn verify_totp_enrollment(
+        &self,
+        ctx: &Context<'_>,
+        input: VerifyTotpEnrollmentInput,
+    ) -> Result<VerifyTotpEnrollmentPayload, async_graphql::Error> {
+        let state = ctx.state();
+        let mut rng = state.rng();
+        let clock = state.clock();
+        let requester = ctx.requester();
+        let limiter = state.limiter();
+
+        // Only allow calling this if the requester is a browser session
+        let Some(browser_session) = requester.browser_session() else {
+            return Err(async_graphql::Error::new("Unauthorized"));
+        };
+        let user = &browser_session.user;
+
+        let mut repo = state.repository().await?;
+
+        let Some(authenticator) = repo.user_totp().find(user).await? else {
+            return Ok(VerifyTotpEnrollmentPayload::NotStarted);
+        };
+
+        if authenticator.is_verified() {
+            return Ok(VerifyTotpEnrollmentPayload::NotStarted);
+        }
+
+        if let Err(e) = limiter.check_password(requester.fingerprint(), user) {
+            tracing::warn!(error = &e as &dyn std::error::Error);
+            return Ok(VerifyTotpEnrollmentPayload::RateLimited);
+        }
+
+        let secret = state
+            .encrypter()
+            .decrypt_string(&authenticator.encrypted_secret)
+            .context("Failed to decrypt the TOTP secret")?;
+
+        let Some(step) = crate::totp::verify(
+            &secret,
+            &input.code,
+            clock.now(),
+            authenticator.last_used_step,
+        ) else {
+            return Ok(VerifyTotpEnrollmentPayload::InvalidCode);
+        };
+
+        let authenticator = repo.user_totp().verify(&clock, authenticator).await?;
+        let authenticator = repo.user_totp().record_use(authenticator, step).await?;
+
+        // The user just proved they have the authenticator, so record it on the
+        // current session
+        repo.browser_session()
+            .authenticate_with_totp(&mut rng, &clock, browser_session, &authenticator)
+            .await?;
+
+        repo.save().await?;
+
+        Ok(VerifyTotpEnrollmentPayload::Verified(user.clone()))
+    }
+
+    /// Remove the TOTP authenticator of the current user
+    async fn remove_totp_authenticator(
+        &self,
+        ctx: &Context<'_>,
+        input: RemoveTotpAuthenticatorInput,
+    ) -> Result<RemoveTotpAuthenticatorPayload, async_graphql::Error> {
+        let state = ctx.state();
+        let requester = ctx.requester();
+
+        // Only allow calling this if the requester is a browser session
+        let Some(browser_session) = requester.browser_session() else {
+            return Err(async_graphql::Error::new("Unauthorized"));
+        };
+        let user = &browser_session.user;
+
+        let mut repo = state.repository().await?;
+
+        let Some(authenticator) = repo.user_totp().find(user).await? else {
+            return Ok(RemoveTotpAuthenticatorPayload::NotFound);
+        };
+
+        if !verify_password_if_needed(
+            requester,
+            state.site_config(),
+            &state.password_manager(),
+            input.password,
+            user,
+            &mut repo,
+        )
+        .await?
+        {
+            return Ok(RemoveTotpAuthenticatorPayload::IncorrectPassword);
+        }
+
+        repo.user_totp().remove(authenticator).await?;
+
+        repo.save().await?;
+
+        Ok(RemoveTotpAuthenticatorPayload::Removed(user.clone()))
+    }
+}
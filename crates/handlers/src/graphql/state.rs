@@ -5,6 +5,7 @@
 // Please see LICENSE in the repository root for full details.
 
 use mas_data_model::SiteConfig;
+use mas_keystore::Encrypter;
 use mas_matrix::HomeserverConnection;
 use mas_policy::Policy;
 use mas_router::UrlBuilder;
@@ -23,6 +24,7 @@ pub trait State {
     fn site_config(&self) -> &SiteConfig;
     fn url_builder(&self) -> &UrlBuilder;
     fn limiter(&self) -> &Limiter;
+    fn encrypter(&self) -> &Encrypter;
 }
 
 pub type BoxState = Box<dyn State + Send + Sync + 'static>;
@@ -51,6 +51,20 @@ async fn create_test_client(state: &TestState) -> Client {
             None,
             None,
             None,
+            Vec::new(),
+            None,
+            false,
+            false,
+            None,
+            Vec::new(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
         )
         .await
         .unwrap();
@@ -101,7 +115,15 @@ async fn start_oauth_session(
 
     let access_token = repo
         .oauth2_access_token()
-        .add(&mut rng, &state.clock, &session, access_token_str, None)
+        .add(
+            &mut rng,
+            &state.clock,
+            &session,
+            access_token_str,
+            None,
+            None,
+            None,
+        )
         .await
         .unwrap();
 
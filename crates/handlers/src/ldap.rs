@@ -0,0 +1,784 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+//! Password checks against an LDAP directory
+//!
+//! The entry of the user is first looked up with a search, optionally bound
+//! as a service account, and the password is then checked by binding as that
+//! entry.
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use ldap3::{Ldap, LdapConnAsync, LdapConnSettings, Scope, SearchEntry, ldap_escape};
+use mas_config::{LdapConfig, UpstreamOAuth2ImportAction as ImportAction};
+use mas_data_model::User;
+use mas_matrix::HomeserverConnection;
+use mas_storage::{
+    BoxRepository, Clock, RepositoryAccess, RepositoryError,
+    queue::{ProvisionUserJob, QueueJobRepositoryExt as _},
+    user::{UserEmailRepository, UserRepository},
+};
+use minijinja::Environment;
+use rand::{CryptoRng, RngCore};
+use thiserror::Error;
+
+/// The result code returned by the server when binding with a wrong password
+const INVALID_CREDENTIALS: u32 = 49;
+
+const DEFAULT_LOCALPART_TEMPLATE: &str = "{{ user.uid }}";
+const DEFAULT_DISPLAYNAME_TEMPLATE: &str = "{{ user.cn }}";
+const DEFAULT_EMAIL_TEMPLATE: &str = "{{ user.mail }}";
+
+#[derive(Debug, Error)]
+pub enum LdapError {
+    #[error("Failed to query the LDAP directory")]
+    Ldap(#[source] Box<ldap3::LdapError>),
+
+    #[error("Template {template:?} rendered to an empty string for required attribute")]
+    RequiredAttributeEmpty { template: String },
+
+    #[error("Template {template:?} could not be rendered for required attribute")]
+    RequiredAttributeRender {
+        template: String,
+
+        #[source]
+        source: minijinja::Error,
+    },
+
+    #[error(transparent)]
+    Repository(#[from] RepositoryError),
+
+    #[error("Failed to check the username availability on the homeserver")]
+    Homeserver(#[source] anyhow::Error),
+}
+
+impl From<ldap3::LdapError> for LdapError {
+    fn from(err: ldap3::LdapError) -> Self {
+        Self::Ldap(Box::new(err))
+    }
+}
+
+/// The outcome of checking a password against the directory
+#[derive(Debug)]
+pub(crate) enum LdapLogin {
+    /// No entry in the directory matches the username, so the password should
+    /// be checked against the local database instead
+    UnknownUser,
+
+    /// The password is wrong, or there is no local user for this entry
+    InvalidCredentials,
+
+    /// The password was accepted, and matches this user
+    Success {
+        /// The local user, which might have been created for this login
+        user: User,
+
+        /// The DN of the directory entry
+        dn: String,
+
+        /// Whether the user was created for this login. Provisioning it on
+        /// the homeserver is queued, and only happens once the repository is
+        /// saved
+        created: bool,
+    },
+}
+
+/// The operations needed from the directory to check a password
+///
+/// This is implemented by [`LdapDirectory`], and abstracted so that the login
+/// flow can be tested without an LDAP server.
+#[async_trait]
+pub(crate) trait Directory: Send + Sync {
+    /// Find the entry matching the username
+    async fn search(&self, username: &str) -> Result<Option<SearchEntry>, ldap3::LdapError>;
+
+    /// Check the password by binding as the entry
+    async fn check_password(&self, dn: &str, password: &str) -> Result<bool, ldap3::LdapError>;
+}
+
+#[derive(Clone)]
+pub struct LdapBackend {
+    inner: Option<Arc<InnerLdapBackend>>,
+}
+
+struct InnerLdapBackend {
+    directory: Arc<dyn Directory>,
+    localpart_template: String,
+    displayname_action: ImportAction,
+    displayname_template: String,
+    email_action: ImportAction,
+    email_template: String,
+    create_users: bool,
+}
+
+impl LdapBackend {
+    /// Creates a new [`LdapBackend`] from the configuration
+    ///
+    /// The backend is disabled if no server URL is configured
+    #[must_use]
+    pub fn new(config: &LdapConfig) -> Self {
+        let Some(directory) = LdapDirectory::new(config) else {
+            return Self::disabled();
+        };
+
+        Self::with_directory(config, Arc::new(directory))
+    }
+
+    /// Creates a new [`LdapBackend`] which checks passwords against the given
+    /// directory, using the attribute imports from the configuration
+    pub(crate) fn with_directory(config: &LdapConfig, directory: Arc<dyn Directory>) -> Self {
+        let imports = &config.attributes_imports;
+
+        Self {
+            inner: Some(Arc::new(InnerLdapBackend {
+                directory,
+                localpart_template: imports
+                    .localpart
+                    .template
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_LOCALPART_TEMPLATE.to_owned()),
+                displayname_action: imports.displayname.action,
+                displayname_template: imports
+                    .displayname
+                    .template
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_DISPLAYNAME_TEMPLATE.to_owned()),
+                email_action: imports.email.action,
+                email_template: imports
+                    .email
+                    .template
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_EMAIL_TEMPLATE.to_owned()),
+                create_users: config.create_users,
+            })),
+        }
+    }
+
+    /// Creates a new disabled LDAP backend
+    #[must_use]
+    pub const fn disabled() -> Self {
+        Self { inner: None }
+    }
+
+    /// Checks if the LDAP backend is enabled or not
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.inner.is_some()
+    }
+
+    /// Check the password of a user against the directory, and find the
+    /// matching local user, creating it if allowed
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory could not be queried, if a required
+    /// attribute is missing, or if the repository fails
+    pub(crate) async fn login(
+        &self,
+        rng: &mut (impl RngCore + CryptoRng + Send),
+        clock: &impl Clock,
+        repo: &mut BoxRepository,
+        homeserver: &dyn HomeserverConnection,
+        username: &str,
+        password: &str,
+    ) -> Result<LdapLogin, LdapError> {
+        let Some(inner) = &self.inner else {
+            return Ok(LdapLogin::UnknownUser);
+        };
+
+        // An empty password would result in an unauthenticated bind, which
+        // servers accept without checking anything
+        if password.is_empty() {
+            return Ok(LdapLogin::InvalidCredentials);
+        }
+
+        let Some(entry) = inner.directory.search(username).await? else {
+            return Ok(LdapLogin::UnknownUser);
+        };
+
+        if !inner.directory.check_password(&entry.dn, password).await? {
+            return Ok(LdapLogin::InvalidCredentials);
+        }
+
+        let environment = crate::upstream_oauth2::template::environment();
+        let context = attributes_context(&entry);
+
+        let localpart =
+            render_attribute_template(&environment, &inner.localpart_template, &context, true)?
+                .unwrap_or_default();
+
+        if let Some(user) = repo.user().find_by_username(&localpart).await? {
+            return Ok(LdapLogin::Success {
+                user,
+                dn: entry.dn,
+                created: false,
+            });
+        }
+
+        if !inner.create_users {
+            tracing::info!(
+                dn = %entry.dn,
+                %localpart,
+                "No local user for this LDAP entry, and creating users is disabled"
+            );
+            return Ok(LdapLogin::InvalidCredentials);
+        }
+
+        if !homeserver
+            .is_localpart_available(&localpart)
+            .await
+            .map_err(LdapError::Homeserver)?
+        {
+            tracing::warn!(
+                dn = %entry.dn,
+                %localpart,
+                "Homeserver denied the localpart of the LDAP entry"
+            );
+            return Ok(LdapLogin::InvalidCredentials);
+        }
+
+        let display_name = render_import(
+            &environment,
+            inner.displayname_action,
+            &inner.displayname_template,
+            &context,
+        )?;
+        let email = render_import(
+            &environment,
+            inner.email_action,
+            &inner.email_template,
+            &context,
+        )?;
+
+        let user = repo.user().add(rng, clock, localpart).await?;
+
+        let mut job = ProvisionUserJob::new(&user);
+        if let Some(name) = display_name {
+            job = job.set_display_name(name);
+        }
+        repo.queue_job().schedule_job(rng, clock, job).await?;
+
+        if let Some(email) = email {
+            repo.user_email().add(rng, clock, &user, email).await?;
+        }
+
+        Ok(LdapLogin::Success {
+            user,
+            dn: entry.dn,
+            created: true,
+        })
+    }
+}
+
+/// A [`Directory`] backed by an LDAP server
+struct LdapDirectory {
+    url: String,
+    starttls: bool,
+    timeout: Duration,
+    bind: Option<(String, String)>,
+    base_dn: String,
+    search_filter: String,
+}
+
+impl LdapDirectory {
+    /// Creates a new [`LdapDirectory`] from the configuration, or `None` if no
+    /// server URL is configured
+    fn new(config: &LdapConfig) -> Option<Self> {
+        let url = config.url.as_ref()?;
+
+        let bind = config.bind_dn.clone().map(|bind_dn| {
+            let bind_password = config.bind_password.clone().unwrap_or_default();
+            (bind_dn, bind_password)
+        });
+
+        Some(Self {
+            url: url.to_string(),
+            starttls: config.starttls,
+            timeout: config.timeout.to_std().unwrap_or(Duration::from_secs(10)),
+            bind,
+            base_dn: config.base_dn.clone().unwrap_or_default(),
+            search_filter: config.search_filter.clone(),
+        })
+    }
+
+    async fn connect(&self) -> Result<Ldap, ldap3::LdapError> {
+        let settings = LdapConnSettings::new()
+            .set_conn_timeout(self.timeout)
+            .set_starttls(self.starttls);
+        let (conn, ldap) = LdapConnAsync::with_settings(settings, &self.url).await?;
+        ldap3::drive!(conn);
+        Ok(ldap)
+    }
+}
+
+#[async_trait]
+impl Directory for LdapDirectory {
+    async fn search(&self, username: &str) -> Result<Option<SearchEntry>, ldap3::LdapError> {
+        let mut ldap = self.connect().await?;
+
+        if let Some((bind_dn, bind_password)) = &self.bind {
+            ldap.with_timeout(self.timeout)
+                .simple_bind(bind_dn, bind_password)
+                .await?
+                .success()?;
+        }
+
+        let filter = search_filter(&self.search_filter, username);
+        let (entries, _) = ldap
+            .with_timeout(self.timeout)
+            .search(&self.base_dn, Scope::Subtree, &filter, vec!["*"])
+            .await?
+            .success()?;
+        let _ = ldap.unbind().await;
+
+        let mut entries = entries
+            .into_iter()
+            .filter(|entry| !entry.is_ref())
+            .map(SearchEntry::construct);
+
+        match (entries.next(), entries.next()) {
+            (Some(entry), None) => Ok(Some(entry)),
+            (None, _) => Ok(None),
+            (Some(_), Some(_)) => {
+                // Treat this as if the user didn't exist, as we can't tell which
+                // entry to check the password against
+                tracing::warn!(%filter, "More than one LDAP entry matches the username");
+                Ok(None)
+            }
+        }
+    }
+
+    async fn check_password(&self, dn: &str, password: &str) -> Result<bool, ldap3::LdapError> {
+        let mut ldap = self.connect().await?;
+        let result = ldap
+            .with_timeout(self.timeout)
+            .simple_bind(dn, password)
+            .await?;
+        let _ = ldap.unbind().await;
+
+        match result.rc {
+            0 => Ok(true),
+            INVALID_CREDENTIALS => Ok(false),
+            _ => Err(ldap3::LdapError::LdapResult { result }),
+        }
+    }
+}
+
+/// A [`Directory`] holding a fixed set of entries, keyed by username
+#[cfg(test)]
+#[derive(Default)]
+pub(crate) struct MockDirectory {
+    entries: HashMap<String, (SearchEntry, String)>,
+    unreachable: bool,
+    binds: std::sync::Mutex<Vec<String>>,
+}
+
+#[cfg(test)]
+impl MockDirectory {
+    /// A directory which fails every query, as if the server was down
+    pub(crate) fn unreachable() -> Self {
+        Self {
+            unreachable: true,
+            ..Self::default()
+        }
+    }
+
+    /// Add an entry for the given username, with the given password and
+    /// attributes. The `uid` attribute is always set to the username
+    #[must_use]
+    pub(crate) fn with_entry(
+        mut self,
+        username: &str,
+        password: &str,
+        attrs: &[(&str, &str)],
+    ) -> Self {
+        let mut attrs: HashMap<String, Vec<String>> = attrs
+            .iter()
+            .map(|(name, value)| ((*name).to_owned(), vec![(*value).to_owned()]))
+            .collect();
+        attrs.insert("uid".to_owned(), vec![username.to_owned()]);
+
+        let entry = SearchEntry {
+            dn: format!("uid={username},ou=people,dc=example,dc=com"),
+            attrs,
+            bin_attrs: HashMap::new(),
+        };
+        self.entries
+            .insert(username.to_owned(), (entry, password.to_owned()));
+        self
+    }
+
+    /// The DNs which were bound as, in order
+    pub(crate) fn binds(&self) -> Vec<String> {
+        self.binds.lock().unwrap().clone()
+    }
+
+    fn check_reachable(&self) -> Result<(), ldap3::LdapError> {
+        if self.unreachable {
+            return Err(std::io::Error::from(std::io::ErrorKind::ConnectionRefused).into());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[async_trait]
+impl Directory for MockDirectory {
+    async fn search(&self, username: &str) -> Result<Option<SearchEntry>, ldap3::LdapError> {
+        self.check_reachable()?;
+        Ok(self.entries.get(username).map(|(entry, _)| entry.clone()))
+    }
+
+    async fn check_password(&self, dn: &str, password: &str) -> Result<bool, ldap3::LdapError> {
+        self.check_reachable()?;
+        self.binds.lock().unwrap().push(dn.to_owned());
+        Ok(self
+            .entries
+            .values()
+            .any(|(entry, entry_password)| entry.dn == dn && entry_password == password))
+    }
+}
+
+/// Build the search filter for the username, escaping it so that it can't
+/// change the meaning of the filter
+fn search_filter(template: &str, username: &str) -> String {
+    template.replace("{username}", &ldap_escape(username))
+}
+
+/// Build the template context from the attributes of the entry. Only the
+/// first value of each attribute is kept
+fn attributes_context(entry: &SearchEntry) -> minijinja::Value {
+    let user: HashMap<&str, &str> = entry
+        .attrs
+        .iter()
+        .filter_map(|(name, values)| Some((name.as_str(), values.first()?.as_str())))
+        .collect();
+
+    minijinja::context! { user }
+}
+
+fn render_import(
+    environment: &Environment,
+    action: ImportAction,
+    template: &str,
+    context: &minijinja::Value,
+) -> Result<Option<String>, LdapError> {
+    match action {
+        ImportAction::Ignore => Ok(None),
+        ImportAction::Suggest | ImportAction::Force => {
+            render_attribute_template(environment, template, context, false)
+        }
+        ImportAction::Require => render_attribute_template(environment, template, context, true),
+    }
+}
+
+/// Render an attribute template
+///
+/// # Errors
+///
+/// Returns an error if the attribute is required but fails to render or is
+/// empty
+fn render_attribute_template(
+    environment: &Environment,
+    template: &str,
+    context: &minijinja::Value,
+    required: bool,
+) -> Result<Option<String>, LdapError> {
+    match environment.render_str(template, context) {
+        Ok(value) if value.is_empty() => {
+            if required {
+                return Err(LdapError::RequiredAttributeEmpty {
+                    template: template.to_owned(),
+                });
+            }
+
+            Ok(None)
+        }
+
+        Ok(value) => Ok(Some(value)),
+
+        Err(source) => {
+            if required {
+                return Err(LdapError::RequiredAttributeRender {
+                    template: template.to_owned(),
+                    source,
+                });
+            }
+
+            tracing::warn!(error = &source as &dyn std::error::Error, %template, "Error while rendering template");
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mas_config::{
+        LdapAttributesImports, LdapDisplaynameImportPreference, LdapEmailImportPreference,
+    };
+    use mas_storage::{Clock as _, RepositoryAccess as _};
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::test_utils::{TestState, setup};
+
+    fn config(create_users: bool) -> LdapConfig {
+        LdapConfig {
+            create_users,
+            attributes_imports: LdapAttributesImports {
+                displayname: LdapDisplaynameImportPreference {
+                    action: ImportAction::Suggest,
+                    ..LdapDisplaynameImportPreference::default()
+                },
+                email: LdapEmailImportPreference {
+                    action: ImportAction::Force,
+                    ..LdapEmailImportPreference::default()
+                },
+                ..LdapAttributesImports::default()
+            },
+            ..LdapConfig::default()
+        }
+    }
+
+    fn directory() -> MockDirectory {
+        MockDirectory::default().with_entry(
+            "john",
+            "hunter2",
+            &[("cn", "John Doe"), ("mail", "john@example.com")],
+        )
+    }
+
+    async fn login(
+        state: &TestState,
+        backend: &LdapBackend,
+        username: &str,
+        password: &str,
+    ) -> Result<LdapLogin, LdapError> {
+        let mut repo = state.repository().await.unwrap();
+        let res = backend
+            .login(
+                &mut state.rng(),
+                &state.clock,
+                &mut repo,
+                state.homeserver_connection.as_ref(),
+                username,
+                password,
+            )
+            .await;
+        repo.save().await.unwrap();
+        res
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_login_existing_user(pool: PgPool) {
+        setup();
+        let state = TestState::from_pool(pool).await.unwrap();
+        let directory = Arc::new(directory());
+        let backend = LdapBackend::with_directory(&config(false), directory.clone());
+
+        // Users which aren't in the directory are left to the local password check
+        let res = login(&state, &backend, "alice", "hunter2").await.unwrap();
+        assert!(matches!(res, LdapLogin::UnknownUser));
+        assert!(directory.binds().is_empty());
+
+        // An empty password is rejected without binding
+        let res = login(&state, &backend, "john", "").await.unwrap();
+        assert!(matches!(res, LdapLogin::InvalidCredentials));
+        assert!(directory.binds().is_empty());
+
+        // The password is checked by binding as the entry found by the search
+        let res = login(&state, &backend, "john", "wrong").await.unwrap();
+        assert!(matches!(res, LdapLogin::InvalidCredentials));
+        assert_eq!(directory.binds(), ["uid=john,ou=people,dc=example,dc=com"]);
+
+        // Without a local user, the login fails as users can't be created
+        let res = login(&state, &backend, "john", "hunter2").await.unwrap();
+        assert!(matches!(res, LdapLogin::InvalidCredentials));
+
+        let mut repo = state.repository().await.unwrap();
+        let user = repo
+            .user()
+            .add(&mut state.rng(), &state.clock, "john".to_owned())
+            .await
+            .unwrap();
+        repo.save().await.unwrap();
+
+        // With a local user, the login succeeds
+        let res = login(&state, &backend, "john", "hunter2").await.unwrap();
+        let LdapLogin::Success {
+            user: logged_in,
+            dn,
+            created,
+        } = res
+        else {
+            panic!("expected a successful login, got {res:?}");
+        };
+        assert_eq!(logged_in.id, user.id);
+        assert_eq!(dn, "uid=john,ou=people,dc=example,dc=com");
+        assert!(!created);
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_login_creates_user(pool: PgPool) {
+        setup();
+        let state = TestState::from_pool(pool).await.unwrap();
+        let backend = LdapBackend::with_directory(&config(true), Arc::new(directory()));
+
+        let res = login(&state, &backend, "john", "hunter2").await.unwrap();
+        let LdapLogin::Success { user, created, .. } = res else {
+            panic!("expected a successful login, got {res:?}");
+        };
+        assert!(created);
+        assert_eq!(user.username, "john");
+        assert_eq!(user.created_at, state.clock.now());
+
+        // The email was imported
+        let mut repo = state.repository().await.unwrap();
+        let emails = repo.user_email().all(&user).await.unwrap();
+        assert_eq!(emails.len(), 1);
+        assert_eq!(emails[0].email, "john@example.com");
+        repo.cancel().await.unwrap();
+
+        // The next login finds the same user
+        let res = login(&state, &backend, "john", "hunter2").await.unwrap();
+        let LdapLogin::Success {
+            user: logged_in,
+            created,
+            ..
+        } = res
+        else {
+            panic!("expected a successful login, got {res:?}");
+        };
+        assert!(!created);
+        assert_eq!(logged_in.id, user.id);
+
+        // Users aren't created if the homeserver doesn't allow the localpart
+        let directory = directory().with_entry("admin", "hunter2", &[]);
+        let backend = LdapBackend::with_directory(&config(true), Arc::new(directory));
+        state.homeserver_connection.reserve_localpart("admin").await;
+        let res = login(&state, &backend, "admin", "hunter2").await.unwrap();
+        assert!(matches!(res, LdapLogin::InvalidCredentials));
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_login_unreachable_directory(pool: PgPool) {
+        setup();
+        let state = TestState::from_pool(pool).await.unwrap();
+        let backend =
+            LdapBackend::with_directory(&config(true), Arc::new(MockDirectory::unreachable()));
+
+        let res = login(&state, &backend, "john", "hunter2").await;
+        assert!(matches!(res, Err(LdapError::Ldap(_))));
+    }
+
+    /// Check the password of a user against a real LDAP server, connecting
+    /// with StartTLS. The server certificate must be trusted by the system.
+    ///
+    /// This expects the server at `MAS_TEST_LDAP_URL` to have an entry with
+    /// `uid=john` and the password `hunter2` under `dc=example,dc=com`,
+    /// searchable by `cn=admin,dc=example,dc=com` with the password `admin`.
+    #[tokio::test]
+    #[ignore = "this requires an LDAP server, set MAS_TEST_LDAP_URL to run it"]
+    async fn test_ldap_directory() {
+        let url = std::env::var("MAS_TEST_LDAP_URL").expect("MAS_TEST_LDAP_URL is not set");
+        let config = LdapConfig {
+            url: Some(url.parse().unwrap()),
+            starttls: true,
+            bind_dn: Some("cn=admin,dc=example,dc=com".to_owned()),
+            bind_password: Some("admin".to_owned()),
+            base_dn: Some("dc=example,dc=com".to_owned()),
+            ..LdapConfig::default()
+        };
+        let directory = LdapDirectory::new(&config).unwrap();
+        assert!(directory.starttls);
+
+        assert!(directory.search("nobody").await.unwrap().is_none());
+
+        let entry = directory.search("john").await.unwrap().unwrap();
+        assert!(
+            directory
+                .check_password(&entry.dn, "hunter2")
+                .await
+                .unwrap()
+        );
+        assert!(!directory.check_password(&entry.dn, "wrong").await.unwrap());
+    }
+
+    #[test]
+    fn test_search_filter() {
+        assert_eq!(search_filter("(uid={username})", "john"), "(uid=john)");
+
+        // Special characters are escaped
+        assert_eq!(
+            search_filter("(uid={username})", "*)(uid=*"),
+            r"(uid=\2a\29\28uid=\2a)"
+        );
+    }
+
+    #[test]
+    fn test_attributes_templates() {
+        let entry = SearchEntry {
+            dn: "uid=john,ou=people,dc=example,dc=com".to_owned(),
+            attrs: HashMap::from([
+                ("uid".to_owned(), vec!["john".to_owned()]),
+                (
+                    "mail".to_owned(),
+                    vec!["john@example.com".to_owned(), "jd@example.com".to_owned()],
+                ),
+            ]),
+            bin_attrs: HashMap::new(),
+        };
+
+        let environment = crate::upstream_oauth2::template::environment();
+        let context = attributes_context(&entry);
+
+        let localpart =
+            render_attribute_template(&environment, DEFAULT_LOCALPART_TEMPLATE, &context, true)
+                .unwrap();
+        assert_eq!(localpart.as_deref(), Some("john"));
+
+        // Only the first value is used
+        let email = render_import(
+            &environment,
+            ImportAction::Force,
+            DEFAULT_EMAIL_TEMPLATE,
+            &context,
+        )
+        .unwrap();
+        assert_eq!(email.as_deref(), Some("john@example.com"));
+
+        // Missing attributes are only an error when required
+        let display_name = render_import(
+            &environment,
+            ImportAction::Force,
+            DEFAULT_DISPLAYNAME_TEMPLATE,
+            &context,
+        )
+        .unwrap();
+        assert_eq!(display_name, None);
+
+        let display_name = render_import(
+            &environment,
+            ImportAction::Require,
+            DEFAULT_DISPLAYNAME_TEMPLATE,
+            &context,
+        );
+        assert!(matches!(
+            display_name,
+            Err(LdapError::RequiredAttributeEmpty { .. })
+        ));
+
+        let display_name = render_import(
+            &environment,
+            ImportAction::Ignore,
+            "{{ user.uid }}",
+            &context,
+        )
+        .unwrap();
+        assert_eq!(display_name, None);
+    }
+}
@@ -49,12 +49,13 @@ use sqlx::PgPool;
 use tower::util::AndThenLayer;
 use tower_http::cors::{Any, CorsLayer};
 
-use self::{graphql::ExtraRouterParameters, passwords::PasswordManager};
+use self::{graphql::ExtraRouterParameters, ldap::LdapBackend, passwords::PasswordManager};
 
 mod admin;
 mod compat;
 mod graphql;
 mod health;
+pub mod ldap;
 mod oauth2;
 pub mod passwords;
 pub mod upstream_oauth2;
@@ -64,9 +65,12 @@ mod activity_tracker;
 mod captcha;
 mod preferred_language;
 mod rate_limit;
+mod recovery_codes;
 mod session;
 #[cfg(test)]
 mod test_utils;
+mod totp;
+mod webauthn;
 
 static METER: LazyLock<Meter> = LazyLock::new(|| {
     let scope = opentelemetry::InstrumentationScope::builder(env!("CARGO_PKG_NAME"))
@@ -234,10 +238,20 @@ where
             mas_router::OAuth2RegistrationEndpoint::route(),
             post(self::oauth2::registration::post),
         )
+        .route(
+            mas_router::OAuth2ClientConfigurationEndpoint::route(),
+            get(self::oauth2::registration::get)
+                .put(self::oauth2::registration::put)
+                .delete(self::oauth2::registration::delete),
+        )
         .route(
             mas_router::OAuth2DeviceAuthorizationEndpoint::route(),
             post(self::oauth2::device::authorize::post),
         )
+        .route(
+            mas_router::OAuth2PushedAuthorizationRequestEndpoint::route(),
+            post(self::oauth2::par::post),
+        )
         .layer(
             CorsLayer::new()
                 .allow_origin(Any)
@@ -261,6 +275,7 @@ where
     SiteConfig: FromRef<S>,
     Arc<dyn HomeserverConnection>: FromRef<S>,
     PasswordManager: FromRef<S>,
+    LdapBackend: FromRef<S>,
     Limiter: FromRef<S>,
     BoundActivityTracker: FromRequestParts<S>,
     RequesterFingerprint: FromRequestParts<S>,
@@ -323,6 +338,7 @@ where
     Templates: FromRef<S>,
     Keystore: FromRef<S>,
     PasswordManager: FromRef<S>,
+    LdapBackend: FromRef<S>,
     MetadataCache: FromRef<S>,
     SiteConfig: FromRef<S>,
     Limiter: FromRef<S>,
@@ -370,6 +386,22 @@ where
             mas_router::Login::route(),
             get(self::views::login::get).post(self::views::login::post),
         )
+        .route(
+            mas_router::LoginTotp::route(),
+            get(self::views::login_totp::get).post(self::views::login_totp::post),
+        )
+        .route(
+            mas_router::LoginPasskey::route(),
+            get(self::views::login_passkey::get).post(self::views::login_passkey::post),
+        )
+        .route(
+            mas_router::LoginRecoveryCode::route(),
+            get(self::views::login_recovery_code::get).post(self::views::login_recovery_code::post),
+        )
+        .route(
+            mas_router::LoginEmailCode::route(),
+            get(self::views::login_email_code::get).post(self::views::login_email_code::post),
+        )
         .route(mas_router::Logout::route(), post(self::views::logout::post))
         .route(
             mas_router::Reauth::route(),
@@ -442,6 +474,14 @@ where
             mas_router::DeviceCodeConsent::route(),
             get(self::oauth2::device::consent::get).post(self::oauth2::device::consent::post),
         )
+        .route(
+            mas_router::OidcEndSession::route(),
+            get(self::oauth2::end_session::get).post(self::oauth2::end_session::post),
+        )
+        .route(
+            mas_router::OidcEndSessionConfirm::route(),
+            post(self::oauth2::end_session::confirm),
+        )
         .layer(AndThenLayer::new(
             async move |response: axum::response::Response| {
                 if response.status().is_server_error() {
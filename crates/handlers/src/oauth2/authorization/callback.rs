@@ -31,6 +31,7 @@ pub struct CallbackDestination {
     mode: CallbackDestinationMode,
     safe_redirect_uri: Url,
     state: Option<String>,
+    issuer: String,
 }
 
 #[derive(Debug, Error)]
@@ -54,23 +55,28 @@ pub enum CallbackDestinationError {
     ParamsSerialization(#[from] serde_urlencoded::ser::Error),
 }
 
-impl TryFrom<&AuthorizationGrant> for CallbackDestination {
-    type Error = IntoCallbackDestinationError;
-
-    fn try_from(value: &AuthorizationGrant) -> Result<Self, Self::Error> {
+impl CallbackDestination {
+    pub fn try_from_grant(
+        grant: &AuthorizationGrant,
+        issuer: &Url,
+    ) -> Result<Self, IntoCallbackDestinationError> {
         Self::try_new(
-            &value.response_mode,
-            value.redirect_uri.clone(),
-            value.state.clone(),
+            &grant.response_mode,
+            grant.redirect_uri.clone(),
+            grant.state.clone(),
+            issuer,
         )
     }
-}
 
-impl CallbackDestination {
+    /// Create a new callback destination
+    ///
+    /// The `issuer` is sent back with every response in the `iss` parameter,
+    /// as per RFC 9207, so that clients can detect mix-up attacks.
     pub fn try_new(
         mode: &ResponseMode,
         mut redirect_uri: Url,
         state: Option<String>,
+        issuer: &Url,
     ) -> Result<Self, IntoCallbackDestinationError> {
         if redirect_uri.fragment().is_some() {
             return Err(IntoCallbackDestinationError::RedirectUriFragmentNotAllowed);
@@ -98,6 +104,7 @@ impl CallbackDestination {
             mode,
             safe_redirect_uri: redirect_uri,
             state,
+            issuer: issuer.to_string(),
         })
     }
 
@@ -115,18 +122,22 @@ impl CallbackDestination {
             #[serde(skip_serializing_if = "Option::is_none")]
             state: Option<String>,
 
+            iss: String,
+
             #[serde(flatten)]
             params: T,
         }
 
         let mut redirect_uri = self.safe_redirect_uri;
         let state = self.state;
+        let iss = self.issuer;
 
         match self.mode {
             CallbackDestinationMode::Query { existing_params } => {
                 let merged = AllParams {
                     existing: Some(&existing_params),
                     state,
+                    iss,
                     params,
                 };
 
@@ -141,6 +152,7 @@ impl CallbackDestination {
                 let merged = AllParams {
                     existing: None,
                     state,
+                    iss,
                     params,
                 };
 
@@ -155,6 +167,7 @@ impl CallbackDestination {
                 let merged = AllParams {
                     existing: None,
                     state,
+                    iss,
                     params,
                 };
                 let ctx = FormPostContext::new_for_url(redirect_uri, merged).with_language(locale);
@@ -4,6 +4,8 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 // Please see LICENSE in the repository root for full details.
 
+use std::sync::Arc;
+
 use axum::{
     extract::{Path, State},
     response::{Html, IntoResponse, Response},
@@ -12,13 +14,15 @@ use axum_extra::TypedHeader;
 use hyper::StatusCode;
 use mas_axum_utils::{SessionInfoExt, cookies::CookieJar, csrf::CsrfExt, sentry::SentryEventID};
 use mas_data_model::{AuthorizationGrant, BrowserSession, Client, Device};
-use mas_keystore::Keystore;
+use mas_i18n::DataLocale;
+use mas_keystore::{Encrypter, Keystore};
+use mas_matrix::HomeserverConnection;
 use mas_policy::{EvaluationResult, Policy};
 use mas_router::{PostAuthAction, UrlBuilder};
 use mas_storage::{
     BoxClock, BoxRepository, BoxRng, Clock, RepositoryAccess,
     oauth2::{OAuth2AuthorizationGrantRepository, OAuth2ClientRepository, OAuth2SessionRepository},
-    user::BrowserSessionRepository,
+    user::{BrowserSessionRepository, UserRepository},
 };
 use mas_templates::{PolicyViolationContext, TemplateContext, Templates};
 use oauth2_types::requests::AuthorizationResponse;
@@ -28,7 +32,13 @@ use ulid::Ulid;
 
 use super::callback::CallbackDestination;
 use crate::{
-    BoundActivityTracker, PreferredLanguage, impl_from_error_for_route, oauth2::generate_id_token,
+    BoundActivityTracker, PreferredLanguage, impl_from_error_for_route,
+    oauth2::{
+        encryption::encrypt_id_token,
+        generate_id_token,
+        profile::{id_token_claims, user_claims},
+        subject_for_client,
+    },
 };
 
 #[derive(Debug, Error)]
@@ -88,6 +98,9 @@ pub(crate) async fn get(
     State(templates): State<Templates>,
     State(url_builder): State<UrlBuilder>,
     State(key_store): State<Keystore>,
+    State(http_client): State<reqwest::Client>,
+    State(encrypter): State<Encrypter>,
+    State(homeserver): State<Arc<dyn HomeserverConnection>>,
     policy: Policy,
     activity_tracker: BoundActivityTracker,
     user_agent: Option<TypedHeader<headers::UserAgent>>,
@@ -107,7 +120,8 @@ pub(crate) async fn get(
         .await?
         .ok_or(RouteError::NotFound)?;
 
-    let callback_destination = CallbackDestination::try_from(&grant)?;
+    let callback_destination =
+        CallbackDestination::try_from_grant(&grant, &url_builder.oidc_issuer())?;
     let continue_grant = PostAuthAction::continue_grant(grant.id);
 
     let Some(session) = maybe_session else {
@@ -137,8 +151,12 @@ pub(crate) async fn get(
         user_agent,
         repo,
         key_store,
+        &http_client,
+        &encrypter,
         policy,
         &url_builder,
+        &*homeserver,
+        &locale,
         grant,
         &client,
         &session,
@@ -198,7 +216,8 @@ impl_from_error_for_route!(GrantCompletionError: mas_storage::RepositoryError);
 impl_from_error_for_route!(GrantCompletionError: super::callback::IntoCallbackDestinationError);
 impl_from_error_for_route!(GrantCompletionError: mas_policy::LoadError);
 impl_from_error_for_route!(GrantCompletionError: mas_policy::EvaluationError);
-impl_from_error_for_route!(GrantCompletionError: super::super::IdTokenSignatureError);
+impl_from_error_for_route!(GrantCompletionError: super::super::TokenSignatureError);
+impl_from_error_for_route!(GrantCompletionError: super::super::encryption::ResponseEncryptionError);
 
 pub(crate) async fn complete(
     rng: &mut (impl rand::RngCore + rand::CryptoRng + Send),
@@ -207,8 +226,12 @@ pub(crate) async fn complete(
     user_agent: Option<String>,
     mut repo: BoxRepository,
     key_store: Keystore,
+    http_client: &reqwest::Client,
+    encrypter: &Encrypter,
     mut policy: Policy,
     url_builder: &UrlBuilder,
+    homeserver: &dyn HomeserverConnection,
+    locale: &DataLocale,
     grant: AuthorizationGrant,
     client: &Client,
     browser_session: &BrowserSession,
@@ -230,13 +253,18 @@ pub(crate) async fn complete(
         return Err(GrantCompletionError::RequiresReauth);
     };
 
+    let authentication_methods =
+        crate::oauth2::authentication_methods(&mut repo, browser_session).await?;
+
     // Run through the policy
     let res = policy
         .evaluate_authorization_grant(mas_policy::AuthorizationGrantInput {
             user: Some(&browser_session.user),
             client,
             scope: &grant.scope,
+            resources: &grant.resources,
             grant_type: mas_policy::GrantType::AuthorizationCode,
+            authentication_methods,
             requester: mas_policy::Requester {
                 ip_address: activity_tracker.ip(),
                 user_agent,
@@ -265,12 +293,28 @@ pub(crate) async fn complete(
         return Err(GrantCompletionError::RequiresConsent);
     }
 
+    // Remember the language the user last authorized a client with, so that it
+    // can be shared with clients through the `locale` claim
+    let mut user = browser_session.user.clone();
+    let locale = locale.to_string();
+    if user.locale.as_deref() != Some(locale.as_str()) {
+        user = repo.user().set_locale(clock, user, locale).await?;
+    }
+
     // All good, let's start the session
-    let session = repo
+    let mut session = repo
         .oauth2_session()
         .add_from_browser_session(rng, clock, client, browser_session, grant.scope.clone())
         .await?;
 
+    // Restrict the tokens of the session to the requested resources, if any
+    if !grant.resources.is_empty() {
+        session = repo
+            .oauth2_session()
+            .set_resources(session, grant.resources.clone())
+            .await?;
+    }
+
     let grant = repo
         .oauth2_authorization_grant()
         .fulfill(clock, &session, grant)
@@ -281,7 +325,11 @@ pub(crate) async fn complete(
 
     // Did they request an ID token?
     if grant.response_type_id_token {
-        params.id_token = Some(generate_id_token(
+        let claims = id_token_claims(grant.claims.as_ref());
+        let user_claims = user_claims(homeserver, &user, &claims).await;
+        let subject = subject_for_client(rng, clock, &mut repo, client, &user).await?;
+
+        let id_token = generate_id_token(
             rng,
             clock,
             url_builder,
@@ -289,9 +337,14 @@ pub(crate) async fn complete(
             client,
             Some(&grant),
             browser_session,
+            &subject,
             None,
             Some(&valid_authentication),
-        )?);
+            user_claims,
+        )?;
+
+        params.id_token =
+            Some(encrypt_id_token(rng, http_client, encrypter, client, id_token).await?);
     }
 
     // Did they request an auth code?
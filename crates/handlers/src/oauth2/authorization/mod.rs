@@ -4,6 +4,8 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 // Please see LICENSE in the repository root for full details.
 
+use std::sync::Arc;
+
 use axum::{
     extract::{Form, State},
     response::{Html, IntoResponse, Response},
@@ -11,13 +13,17 @@ use axum::{
 use axum_extra::TypedHeader;
 use hyper::StatusCode;
 use mas_axum_utils::{SessionInfoExt, cookies::CookieJar, csrf::CsrfExt, sentry::SentryEventID};
-use mas_data_model::{AuthorizationCode, Pkce};
-use mas_keystore::Keystore;
+use mas_data_model::{AuthorizationCode, PUSHED_AUTHORIZATION_REQUEST_URI_PREFIX, Pkce};
+use mas_keystore::{Encrypter, Keystore};
+use mas_matrix::HomeserverConnection;
 use mas_policy::Policy;
 use mas_router::{PostAuthAction, UrlBuilder};
 use mas_storage::{
     BoxClock, BoxRepository, BoxRng,
-    oauth2::{OAuth2AuthorizationGrantRepository, OAuth2ClientRepository},
+    oauth2::{
+        OAuth2AuthorizationGrantRepository, OAuth2ClientRepository,
+        OAuth2PushedAuthorizationRequestRepository,
+    },
 };
 use mas_templates::{PolicyViolationContext, TemplateContext, Templates};
 use oauth2_types::{
@@ -27,15 +33,21 @@ use oauth2_types::{
     response_type::ResponseType,
 };
 use rand::{Rng, distributions::Alphanumeric};
-use serde::Deserialize;
+use serde::{Deserialize, de::value::MapDeserializer};
 use thiserror::Error;
 use tracing::warn;
+use url::Url;
 
-use self::{callback::CallbackDestination, complete::GrantCompletionError};
+use self::{
+    callback::CallbackDestination,
+    complete::GrantCompletionError,
+    request_object::{RequestObjectError, resolve_request_object},
+};
 use crate::{BoundActivityTracker, PreferredLanguage, impl_from_error_for_route};
 
 mod callback;
 pub mod complete;
+mod request_object;
 
 #[derive(Debug, Error)]
 pub enum RouteError {
@@ -53,6 +65,18 @@ pub enum RouteError {
 
     #[error("invalid redirect uri")]
     UnknownRedirectUri(#[from] mas_data_model::InvalidRedirectUriError),
+
+    #[error("invalid parameters")]
+    InvalidParameters(#[source] serde::de::value::Error),
+
+    #[error("invalid or expired request_uri")]
+    InvalidRequestUri,
+
+    #[error("client requires pushed authorization requests")]
+    PushedAuthorizationRequired,
+
+    #[error("invalid request object")]
+    InvalidRequestObject(#[from] RequestObjectError),
 }
 
 impl IntoResponse for RouteError {
@@ -77,6 +101,22 @@ impl IntoResponse for RouteError {
                 format!("Invalid redirect URI ({e})"),
             )
                 .into_response(),
+            RouteError::InvalidParameters(e) => {
+                (StatusCode::BAD_REQUEST, format!("Invalid parameters ({e})")).into_response()
+            }
+            RouteError::InvalidRequestUri => {
+                (StatusCode::BAD_REQUEST, "invalid or expired request_uri").into_response()
+            }
+            RouteError::PushedAuthorizationRequired => (
+                StatusCode::BAD_REQUEST,
+                "this client must use pushed authorization requests",
+            )
+                .into_response(),
+            RouteError::InvalidRequestObject(e) => (
+                StatusCode::BAD_REQUEST,
+                format!("Invalid request object ({e})"),
+            )
+                .into_response(),
         };
 
         (SentryEventID::from(event_id), response).into_response()
@@ -124,7 +164,7 @@ fn resolve_response_mode(
 
 #[tracing::instrument(
     name = "handlers.oauth2.authorization.get",
-    fields(client.id = %params.auth.client_id),
+    fields(client.id),
     skip_all,
     err,
 )]
@@ -136,20 +176,84 @@ pub(crate) async fn get(
     State(templates): State<Templates>,
     State(key_store): State<Keystore>,
     State(url_builder): State<UrlBuilder>,
+    State(http_client): State<reqwest::Client>,
+    State(encrypter): State<Encrypter>,
+    State(homeserver): State<Arc<dyn HomeserverConnection>>,
     policy: Policy,
     user_agent: Option<TypedHeader<headers::UserAgent>>,
     activity_tracker: BoundActivityTracker,
     mut repo: BoxRepository,
     cookie_jar: CookieJar,
-    Form(params): Form<Params>,
+    Form(form): Form<Vec<(String, String)>>,
 ) -> Result<Response, RouteError> {
+    // Only look at the parameters needed to figure out where the rest of the
+    // authorization request parameters come from for now
+    let parameter = |name: &str| {
+        form.iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.clone())
+    };
+    let client_id = parameter("client_id").ok_or_else(|| {
+        RouteError::InvalidParameters(serde::de::Error::missing_field("client_id"))
+    })?;
+    let request_uri = parameter("request_uri");
+    tracing::Span::current().record("client.id", &client_id);
+
     // First, figure out what client it is
     let client = repo
         .oauth2_client()
-        .find_by_client_id(&params.auth.client_id)
+        .find_by_client_id(&client_id)
         .await?
         .ok_or(RouteError::ClientNotFound)?;
 
+    // If the request references a pushed authorization request, load the
+    // parameters from it. Other kinds of `request_uri` reference request objects,
+    // which are resolved below.
+    let parameters = match request_uri {
+        Some(request_uri) if request_uri.starts_with(PUSHED_AUTHORIZATION_REQUEST_URI_PREFIX) => {
+            let pushed_authorization_request = repo
+                .oauth2_pushed_authorization_request()
+                .find_by_request_uri(&request_uri)
+                .await?
+                .filter(|par| par.client_id == client.id && par.is_valid(clock.now()))
+                .ok_or(RouteError::InvalidRequestUri)?;
+
+            // Pushed authorization requests can only be used once. This happens
+            // in the same transaction as the creation of the authorization grant
+            let pushed_authorization_request = repo
+                .oauth2_pushed_authorization_request()
+                .consume(&clock, pushed_authorization_request)
+                .await?;
+
+            pushed_authorization_request.parameters
+        }
+        _ if client.require_pushed_authorization_requests => {
+            return Err(RouteError::PushedAuthorizationRequired);
+        }
+        _ => form,
+    };
+
+    // Parameters from the request object, if any, override the ones from the
+    // query, as required by RFC 9101
+    let parameters = resolve_request_object(
+        &clock,
+        &http_client,
+        &encrypter,
+        &url_builder.oidc_issuer(),
+        &client,
+        parameters,
+    )
+    .await?;
+
+    // The `resource` parameter can be repeated, which the deserializer doesn't
+    // support, so it is extracted separately
+    let (resources, parameters): (Vec<_>, Vec<_>) = parameters
+        .into_iter()
+        .partition(|(key, _)| key == "resource");
+
+    let params = Params::deserialize(MapDeserializer::new(parameters.into_iter()))
+        .map_err(RouteError::InvalidParameters)?;
+
     // And resolve the redirect_uri and response_mode
     let redirect_uri = client
         .resolve_redirect_uri(&params.auth.redirect_uri)?
@@ -162,6 +266,7 @@ pub(crate) async fn get(
         &response_mode,
         redirect_uri.clone(),
         params.auth.state.clone(),
+        &url_builder.oidc_issuer(),
     )?;
 
     // Get the session info from the cookie
@@ -179,28 +284,6 @@ pub(crate) async fn get(
             let maybe_session = session_info.load_active_session(&mut repo).await?;
             let prompt = params.auth.prompt.as_deref().unwrap_or_default();
 
-            // Check if the request/request_uri/registration params are used. If so, reply
-            // with the right error since we don't support them.
-            if params.auth.request.is_some() {
-                return Ok(callback_destination
-                    .go(
-                        &templates,
-                        &locale,
-                        ClientError::from(ClientErrorCode::RequestNotSupported),
-                    )
-                    .await?);
-            }
-
-            if params.auth.request_uri.is_some() {
-                return Ok(callback_destination
-                    .go(
-                        &templates,
-                        &locale,
-                        ClientError::from(ClientErrorCode::RequestUriNotSupported),
-                    )
-                    .await?);
-            }
-
             // Check if the client asked for a `token` response type, and bail out if it's
             // the case, since we don't support them
             if response_type.has_token() {
@@ -225,6 +308,8 @@ pub(crate) async fn get(
                     .await?);
             }
 
+            // Check if the registration param is used. If so, reply with the right error
+            // since we don't support it.
             if params.auth.registration.is_some() {
                 return Ok(callback_destination
                     .go(
@@ -235,6 +320,26 @@ pub(crate) async fn get(
                     .await?);
             }
 
+            // Resource indicators must be absolute URIs without a fragment, as per
+            // RFC 8707
+            let resources: Option<Vec<Url>> = resources
+                .into_iter()
+                .map(|(_, resource)| {
+                    Url::parse(&resource)
+                        .ok()
+                        .filter(|resource| resource.fragment().is_none())
+                })
+                .collect();
+            let Some(resources) = resources else {
+                return Ok(callback_destination
+                    .go(
+                        &templates,
+                        &locale,
+                        ClientError::from(ClientErrorCode::InvalidTarget),
+                    )
+                    .await?);
+            };
+
             // Fail early if prompt=none and there is no active session
             if prompt.contains(&Prompt::None) && maybe_session.is_none() {
                 return Ok(callback_destination
@@ -305,6 +410,8 @@ pub(crate) async fn get(
                     response_type.has_id_token(),
                     requires_consent,
                     params.auth.login_hint,
+                    params.auth.claims,
+                    resources,
                 )
                 .await?;
             let continue_grant = PostAuthAction::continue_grant(grant.id);
@@ -356,8 +463,12 @@ pub(crate) async fn get(
                         user_agent,
                         repo,
                         key_store,
+                        &http_client,
+                        &encrypter,
                         policy,
                         &url_builder,
+                        &*homeserver,
+                        &locale,
                         grant,
                         &client,
                         &user_session,
@@ -409,8 +520,12 @@ pub(crate) async fn get(
                         user_agent,
                         repo,
                         key_store,
+                        &http_client,
+                        &encrypter,
                         policy,
                         &url_builder,
+                        &*homeserver,
+                        &locale,
                         grant,
                         &client,
                         &user_session,
@@ -468,3 +583,162 @@ pub(crate) async fn get(
 
     Ok((cookie_jar, response).into_response())
 }
+
+#[cfg(test)]
+mod tests {
+    use hyper::{Request, StatusCode, header::LOCATION};
+    use mas_router::SimpleRoute;
+    use oauth2_types::{
+        registration::ClientRegistrationResponse, requests::PushedAuthorizationResponse,
+    };
+    use sqlx::PgPool;
+
+    use crate::test_utils::{RequestBuilderExt, ResponseExt, TestState, setup};
+
+    /// Register a public client using the authorization code grant
+    async fn register_client(state: &TestState, require_par: bool) -> String {
+        let request =
+            Request::post(mas_router::OAuth2RegistrationEndpoint::PATH).json(serde_json::json!({
+                "client_uri": "https://example.com/",
+                "redirect_uris": ["https://example.com/callback"],
+                "token_endpoint_auth_method": "none",
+                "grant_types": ["authorization_code"],
+                "response_types": ["code"],
+                "require_pushed_authorization_requests": require_par,
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CREATED);
+        let response: ClientRegistrationResponse = response.json();
+        response.client_id
+    }
+
+    /// Push an authorization request, and return the `request_uri`
+    async fn push_request(state: &TestState, client_id: &str) -> String {
+        let request = Request::post(mas_router::OAuth2PushedAuthorizationRequestEndpoint::PATH)
+            .form(serde_json::json!({
+                "client_id": client_id,
+                "response_type": "code",
+                "redirect_uri": "https://example.com/callback",
+                "scope": "openid",
+            }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CREATED);
+        let response: PushedAuthorizationResponse = response.json();
+        response.request_uri
+    }
+
+    fn authorize(parameters: &serde_json::Value) -> Request<String> {
+        let query = serde_urlencoded::to_string(parameters).unwrap();
+        Request::get(format!(
+            "{}?{query}",
+            mas_router::OAuth2AuthorizationEndpoint::PATH
+        ))
+        .empty()
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_pushed_authorization_request_uri(pool: PgPool) {
+        setup();
+        let state = TestState::from_pool(pool).await.unwrap();
+        let client_id = register_client(&state, false).await;
+
+        // The authorization endpoint loads the pushed request, and starts the
+        // login flow for it
+        let request_uri = push_request(&state, &client_id).await;
+        let response = state
+            .request(authorize(&serde_json::json!({
+                "client_id": client_id,
+                "request_uri": request_uri,
+            })))
+            .await;
+        response.assert_status(StatusCode::SEE_OTHER);
+        assert!(response.headers().get(LOCATION).is_some());
+
+        // The request_uri is consumed, and can't be used a second time
+        let response = state
+            .request(authorize(&serde_json::json!({
+                "client_id": client_id,
+                "request_uri": request_uri,
+            })))
+            .await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+
+        // It can't be used by another client either
+        let request_uri = push_request(&state, &client_id).await;
+        let request =
+            Request::post(mas_router::OAuth2RegistrationEndpoint::PATH).json(serde_json::json!({
+                "client_uri": "https://other.example.com/",
+                "redirect_uris": ["https://other.example.com/callback"],
+                "token_endpoint_auth_method": "none",
+                "grant_types": ["authorization_code"],
+                "response_types": ["code"],
+            }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CREATED);
+        let response: ClientRegistrationResponse = response.json();
+        let other_client_id = response.client_id;
+        assert_ne!(other_client_id, client_id);
+
+        let response = state
+            .request(authorize(&serde_json::json!({
+                "client_id": other_client_id,
+                "request_uri": request_uri,
+            })))
+            .await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+
+        // Nor after it expired
+        let request_uri = push_request(&state, &client_id).await;
+        state.clock.advance(chrono::Duration::minutes(2));
+        let response = state
+            .request(authorize(&serde_json::json!({
+                "client_id": client_id,
+                "request_uri": request_uri,
+            })))
+            .await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+
+        // Unknown request_uri are rejected
+        let response = state
+            .request(authorize(&serde_json::json!({
+                "client_id": client_id,
+                "request_uri": "urn:ietf:params:oauth:request_uri:unknown",
+            })))
+            .await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_require_pushed_authorization_requests(pool: PgPool) {
+        setup();
+        let state = TestState::from_pool(pool).await.unwrap();
+        let client_id = register_client(&state, true).await;
+
+        // Sending the parameters directly to the authorization endpoint fails
+        let response = state
+            .request(authorize(&serde_json::json!({
+                "client_id": client_id,
+                "response_type": "code",
+                "redirect_uri": "https://example.com/callback",
+                "scope": "openid",
+            })))
+            .await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        assert!(
+            response
+                .body()
+                .contains("this client must use pushed authorization requests")
+        );
+
+        // Pushing them first works
+        let request_uri = push_request(&state, &client_id).await;
+        let response = state
+            .request(authorize(&serde_json::json!({
+                "client_id": client_id,
+                "request_uri": request_uri,
+            })))
+            .await;
+        response.assert_status(StatusCode::SEE_OTHER);
+    }
+}
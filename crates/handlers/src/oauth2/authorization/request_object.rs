@@ -0,0 +1,400 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+//! Resolution of [JWT-Secured Authorization Requests]
+//!
+//! [JWT-Secured Authorization Requests]: https://www.rfc-editor.org/rfc/rfc9101
+
+use std::collections::HashMap;
+
+use axum::BoxError;
+use mas_axum_utils::client_authorization::fetch_jwks;
+use mas_data_model::Client;
+use mas_http::RequestBuilderExt as _;
+use mas_iana::jose::JsonWebSignatureAlg;
+use mas_jose::{
+    claims::{self, ClaimError, TimeOptions},
+    jwt::{Jwt, JwtDecodeError},
+};
+use mas_keystore::Encrypter;
+use mas_storage::Clock;
+use serde_json::Value;
+use thiserror::Error;
+use url::Url;
+
+/// The JWS algorithms which use the client secret as a shared key
+const SYMMETRIC_ALGORITHMS: [JsonWebSignatureAlg; 3] = [
+    JsonWebSignatureAlg::Hs256,
+    JsonWebSignatureAlg::Hs384,
+    JsonWebSignatureAlg::Hs512,
+];
+
+#[derive(Debug, Error)]
+pub(crate) enum RequestObjectError {
+    #[error("the request and request_uri parameters are mutually exclusive")]
+    RequestAndRequestUri,
+
+    #[error("the request_uri is not registered for this client")]
+    UnregisteredRequestUri,
+
+    #[error("could not fetch the request object")]
+    Fetch(#[source] reqwest::Error),
+
+    #[error("could not decode the request object")]
+    Decode(#[from] JwtDecodeError),
+
+    #[error("the request object is not signed")]
+    Unsigned,
+
+    #[error("the request object must be signed with {expected}")]
+    AlgorithmMismatch { expected: JsonWebSignatureAlg },
+
+    #[error("the client has no key to verify the request object with")]
+    NoKey,
+
+    #[error("could not fetch the client JWKS")]
+    JwksFetch(#[source] BoxError),
+
+    #[error("the request object signature is invalid")]
+    InvalidSignature,
+
+    #[error("the request object has invalid claims")]
+    InvalidClaims(#[from] ClaimError),
+
+    #[error("the client_id of the request object does not match the request")]
+    ClientIdMismatch,
+}
+
+/// Resolve the request object of an authorization request, passed either by
+/// value in the `request` parameter or by reference in the `request_uri`
+/// parameter
+///
+/// The parameters of the request object override the ones passed alongside
+/// it. The parameters are returned unchanged if there is no request object.
+///
+/// # Errors
+///
+/// Returns an error if the request object could not be fetched, or if it is
+/// invalid
+pub(crate) async fn resolve_request_object(
+    clock: &impl Clock,
+    http_client: &reqwest::Client,
+    encrypter: &Encrypter,
+    issuer: &Url,
+    client: &Client,
+    parameters: Vec<(String, String)>,
+) -> Result<Vec<(String, String)>, RequestObjectError> {
+    let mut request = None;
+    let mut request_uri = None;
+    let mut parameters: Vec<(String, String)> = parameters
+        .into_iter()
+        .filter_map(|(key, value)| match key.as_str() {
+            "request" => {
+                request = Some(value);
+                None
+            }
+            "request_uri" => {
+                request_uri = Some(value);
+                None
+            }
+            _ => Some((key, value)),
+        })
+        .collect();
+
+    let request = match (request, request_uri) {
+        (None, None) => return Ok(parameters),
+        (Some(request), None) => request,
+        (None, Some(request_uri)) => {
+            fetch_request_object(http_client, client, &request_uri).await?
+        }
+        (Some(_), Some(_)) => return Err(RequestObjectError::RequestAndRequestUri),
+    };
+
+    let jwt: Jwt<'_, HashMap<String, Value>> = Jwt::try_from(request.as_str())?;
+
+    let alg = jwt.header().alg();
+    if let Some(expected) = &client.request_object_signing_alg {
+        if alg != expected {
+            return Err(RequestObjectError::AlgorithmMismatch {
+                expected: expected.clone(),
+            });
+        }
+    }
+
+    if *alg == JsonWebSignatureAlg::None {
+        return Err(RequestObjectError::Unsigned);
+    } else if SYMMETRIC_ALGORITHMS.contains(alg) {
+        let encrypted_client_secret = client
+            .encrypted_client_secret
+            .as_ref()
+            .ok_or(RequestObjectError::NoKey)?;
+
+        let client_secret = encrypter
+            .decrypt_string(encrypted_client_secret)
+            .map_err(|_| RequestObjectError::NoKey)?;
+
+        jwt.verify_with_shared_secret(client_secret)
+            .map_err(|_| RequestObjectError::InvalidSignature)?;
+    } else {
+        let jwks = client.jwks.as_ref().ok_or(RequestObjectError::NoKey)?;
+        let jwks = fetch_jwks(http_client, jwks)
+            .await
+            .map_err(RequestObjectError::JwksFetch)?;
+
+        jwt.verify_with_jwks(&jwks)
+            .map_err(|_| RequestObjectError::InvalidSignature)?;
+    }
+
+    let (_header, mut claims) = jwt.into_parts();
+
+    // The JWT claims are validated if present, and are not authorization request
+    // parameters
+    let time_options = TimeOptions::new(clock.now());
+    claims::ISS.extract_optional_with_options(&mut claims, client.client_id.as_str())?;
+    claims::AUD.extract_optional_with_options(&mut claims, &issuer.to_string())?;
+    claims::EXP.extract_optional_with_options(&mut claims, &time_options)?;
+    claims::NBF.extract_optional_with_options(&mut claims, &time_options)?;
+    claims::IAT.extract_optional_with_options(&mut claims, &time_options)?;
+    claims::JTI.extract_optional(&mut claims)?;
+
+    if let Some(client_id) = claims.get("client_id") {
+        if client_id.as_str() != Some(client.client_id.as_str()) {
+            return Err(RequestObjectError::ClientIdMismatch);
+        }
+    }
+
+    for (key, value) in claims {
+        let values = match value {
+            Value::Null => continue,
+            // Resource indicators can be passed as an array in request objects, which
+            // translates to a repeated parameter
+            Value::Array(values) if key == "resource" => values,
+            value => vec![value],
+        };
+
+        parameters.retain(|(k, _)| *k != key);
+        for value in values {
+            let value = match value {
+                Value::String(value) => value,
+                value => value.to_string(),
+            };
+            parameters.push((key.clone(), value));
+        }
+    }
+
+    Ok(parameters)
+}
+
+/// Fetch a request object passed by reference
+///
+/// Only the `request_uri` values registered by the client are fetched, so
+/// that clients can't make us send requests to arbitrary URLs.
+async fn fetch_request_object(
+    http_client: &reqwest::Client,
+    client: &Client,
+    request_uri: &str,
+) -> Result<String, RequestObjectError> {
+    let request_uri: Url = request_uri
+        .parse()
+        .map_err(|_| RequestObjectError::UnregisteredRequestUri)?;
+
+    // The fragment can be used by clients to bust caches, so it is ignored when
+    // looking for the registered URI
+    let without_fragment = |uri: &Url| {
+        let mut uri = uri.clone();
+        uri.set_fragment(None);
+        uri
+    };
+
+    let registered = client
+        .request_uris
+        .iter()
+        .any(|uri| without_fragment(uri) == without_fragment(&request_uri));
+    if !registered {
+        return Err(RequestObjectError::UnregisteredRequestUri);
+    }
+
+    http_client
+        .get(request_uri.as_str())
+        .send_traced()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(RequestObjectError::Fetch)?
+        .text()
+        .await
+        .map_err(RequestObjectError::Fetch)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use mas_data_model::JwksOrJwksUri;
+    use mas_jose::{
+        jwk::{PublicJsonWebKey, PublicJsonWebKeySet},
+        jwt::JsonWebSignatureHeader,
+    };
+    use mas_keystore::PrivateKey;
+    use mas_storage::clock::MockClock;
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    use super::*;
+    use crate::test_utils::setup;
+
+    const ISSUER: &str = "https://example.com/";
+
+    fn sign(key: &PrivateKey, alg: JsonWebSignatureAlg, claims: &Value) -> String {
+        let mut rng = ChaChaRng::seed_from_u64(42);
+        let signer = key.signing_key_for_alg(&alg).unwrap();
+        let claims: HashMap<String, Value> = serde_json::from_value(claims.clone()).unwrap();
+        Jwt::sign_with_rng(&mut rng, JsonWebSignatureHeader::new(alg), claims, &signer)
+            .unwrap()
+            .into_string()
+    }
+
+    /// Resolve an authorization request with the given extra parameters
+    async fn resolve(
+        clock: &MockClock,
+        client: &Client,
+        extra: &[(&str, &str)],
+    ) -> Result<Vec<(String, String)>, RequestObjectError> {
+        let mut parameters = vec![
+            ("client_id".to_owned(), client.client_id.clone()),
+            ("response_type".to_owned(), "code".to_owned()),
+            ("scope".to_owned(), "profile".to_owned()),
+        ];
+        parameters.extend(
+            extra
+                .iter()
+                .map(|(k, v)| ((*k).to_owned(), (*v).to_owned())),
+        );
+
+        let mut parameters = resolve_request_object(
+            clock,
+            &mas_http::reqwest_client(),
+            &Encrypter::new(&[0x42; 32]),
+            &Url::parse(ISSUER).unwrap(),
+            client,
+            parameters,
+        )
+        .await?;
+        parameters.sort();
+        Ok(parameters)
+    }
+
+    #[tokio::test]
+    async fn test_resolve_request_object() {
+        setup();
+        let clock = MockClock::default();
+        let mut rng = ChaChaRng::seed_from_u64(42);
+
+        let key = PrivateKey::generate_ec_p256(&mut rng);
+        let jwks = PublicJsonWebKeySet::new(vec![
+            PublicJsonWebKey::new((&key).into()).with_alg(JsonWebSignatureAlg::Es256),
+        ]);
+        let mut client = Client::samples(clock.now(), &mut rng).remove(0);
+        client.jwks = Some(JwksOrJwksUri::Jwks(jwks));
+        client.request_object_signing_alg = Some(JsonWebSignatureAlg::Es256);
+
+        // Requests without a request object are left untouched
+        let parameters = resolve(&clock, &client, &[]).await.unwrap();
+        assert_eq!(parameters.len(), 3);
+
+        // Parameters in the request object override the query
+        let request = sign(
+            &key,
+            JsonWebSignatureAlg::Es256,
+            &serde_json::json!({
+                "iss": client.client_id,
+                "aud": ISSUER,
+                "exp": (clock.now() + Duration::minutes(5)).timestamp(),
+                "client_id": client.client_id,
+                "scope": "openid",
+                "max_age": 60,
+            }),
+        );
+        let parameters = resolve(&clock, &client, &[("request", &request)])
+            .await
+            .unwrap();
+        assert_eq!(
+            parameters,
+            vec![
+                ("client_id".to_owned(), client.client_id.clone()),
+                ("max_age".to_owned(), "60".to_owned()),
+                ("response_type".to_owned(), "code".to_owned()),
+                ("scope".to_owned(), "openid".to_owned()),
+            ]
+        );
+
+        // Request objects for another client are rejected
+        let request = sign(
+            &key,
+            JsonWebSignatureAlg::Es256,
+            &serde_json::json!({ "client_id": "other" }),
+        );
+        let err = resolve(&clock, &client, &[("request", &request)])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RequestObjectError::ClientIdMismatch));
+
+        // Expired request objects are rejected
+        let request = sign(
+            &key,
+            JsonWebSignatureAlg::Es256,
+            &serde_json::json!({ "exp": (clock.now() - Duration::hours(1)).timestamp() }),
+        );
+        let err = resolve(&clock, &client, &[("request", &request)])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RequestObjectError::InvalidClaims(_)));
+
+        // Request objects signed with another key are rejected
+        let other_key = PrivateKey::generate_ec_p256(&mut rng);
+        let request = sign(
+            &other_key,
+            JsonWebSignatureAlg::Es256,
+            &serde_json::json!({ "scope": "openid" }),
+        );
+        let err = resolve(&clock, &client, &[("request", &request)])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RequestObjectError::InvalidSignature));
+
+        // Request objects must use the registered algorithm
+        let other_key = PrivateKey::generate_ec_p384(&mut rng);
+        let request = sign(
+            &other_key,
+            JsonWebSignatureAlg::Es384,
+            &serde_json::json!({ "scope": "openid" }),
+        );
+        let err = resolve(&clock, &client, &[("request", &request)])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RequestObjectError::AlgorithmMismatch { .. }));
+
+        // Only registered request_uri are fetched
+        let err = resolve(
+            &clock,
+            &client,
+            &[("request_uri", "https://attacker.example.com/request.jwt")],
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, RequestObjectError::UnregisteredRequestUri));
+
+        // Both parameters can't be used at the same time
+        let err = resolve(
+            &clock,
+            &client,
+            &[
+                ("request", "a.b.c"),
+                ("request_uri", "https://example.com/request.jwt"),
+            ],
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, RequestObjectError::RequestAndRequestUri));
+    }
+}
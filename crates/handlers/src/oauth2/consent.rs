@@ -130,12 +130,16 @@ pub(crate) async fn get(
 
     let (csrf_token, cookie_jar) = cookie_jar.csrf_token(&clock, &mut rng);
 
+    let authentication_methods = crate::oauth2::authentication_methods(&mut repo, &session).await?;
+
     let res = policy
         .evaluate_authorization_grant(mas_policy::AuthorizationGrantInput {
             user: Some(&session.user),
             client: &client,
             scope: &grant.scope,
+            resources: &grant.resources,
             grant_type: mas_policy::GrantType::AuthorizationCode,
+            authentication_methods,
             requester: mas_policy::Requester {
                 ip_address: activity_tracker.ip(),
                 user_agent,
@@ -223,12 +227,16 @@ pub(crate) async fn post(
         .await?
         .ok_or(RouteError::NoSuchClient)?;
 
+    let authentication_methods = crate::oauth2::authentication_methods(&mut repo, &session).await?;
+
     let res = policy
         .evaluate_authorization_grant(mas_policy::AuthorizationGrantInput {
             user: Some(&session.user),
             client: &client,
             scope: &grant.scope,
+            resources: &grant.resources,
             grant_type: mas_policy::GrantType::AuthorizationCode,
+            authentication_methods,
             requester: mas_policy::Requester {
                 ip_address: activity_tracker.ip(),
                 user_agent,
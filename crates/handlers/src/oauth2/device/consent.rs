@@ -97,13 +97,17 @@ pub(crate) async fn get(
         .await?
         .context("Client not found")?;
 
+    let authentication_methods = crate::oauth2::authentication_methods(&mut repo, &session).await?;
+
     // Evaluate the policy
     let res = policy
         .evaluate_authorization_grant(mas_policy::AuthorizationGrantInput {
             grant_type: mas_policy::GrantType::DeviceCode,
             client: &client,
             scope: &grant.scope,
+            resources: &[],
             user: Some(&session.user),
+            authentication_methods,
             requester: mas_policy::Requester {
                 ip_address: activity_tracker.ip(),
                 user_agent,
@@ -193,13 +197,17 @@ pub(crate) async fn post(
         .await?
         .context("Client not found")?;
 
+    let authentication_methods = crate::oauth2::authentication_methods(&mut repo, &session).await?;
+
     // Evaluate the policy
     let res = policy
         .evaluate_authorization_grant(mas_policy::AuthorizationGrantInput {
             grant_type: mas_policy::GrantType::DeviceCode,
             client: &client,
             scope: &grant.scope,
+            resources: &[],
             user: Some(&session.user),
+            authentication_methods,
             requester: mas_policy::Requester {
                 ip_address: activity_tracker.ip(),
                 user_agent,
@@ -9,7 +9,9 @@ use mas_iana::oauth::{
     OAuthAuthorizationEndpointResponseType, OAuthClientAuthenticationMethod,
     PkceCodeChallengeMethod,
 };
-use mas_jose::jwa::SUPPORTED_SIGNING_ALGORITHMS;
+use mas_jose::jwa::{
+    SUPPORTED_ENCRYPTION_ALGORITHMS, SUPPORTED_ENCRYPTION_ENCODINGS, SUPPORTED_SIGNING_ALGORITHMS,
+};
 use mas_keystore::Keystore;
 use mas_router::UrlBuilder;
 use oauth2_types::{
@@ -47,6 +49,8 @@ pub(crate) async fn get(
         OAuthClientAuthenticationMethod::ClientSecretPost,
         OAuthClientAuthenticationMethod::ClientSecretJwt,
         OAuthClientAuthenticationMethod::PrivateKeyJwt,
+        OAuthClientAuthenticationMethod::TlsClientAuth,
+        OAuthClientAuthenticationMethod::SelfSignedTlsClientAuth,
         OAuthClientAuthenticationMethod::None,
     ]);
 
@@ -65,9 +69,16 @@ pub(crate) async fn get(
     let introspection_endpoint = Some(url_builder.oauth_introspection_endpoint());
     let revocation_endpoint = Some(url_builder.oauth_revocation_endpoint());
     let userinfo_endpoint = Some(url_builder.oidc_userinfo_endpoint());
+    let end_session_endpoint = Some(url_builder.oidc_end_session_endpoint());
     let registration_endpoint = Some(url_builder.oauth_registration_endpoint());
+    let pushed_authorization_request_endpoint =
+        Some(url_builder.oauth_pushed_authorization_request_endpoint());
 
-    let scopes_supported = Some(vec![scope::OPENID.to_string(), scope::EMAIL.to_string()]);
+    let scopes_supported = Some(vec![
+        scope::OPENID.to_string(),
+        scope::PROFILE.to_string(),
+        scope::EMAIL.to_string(),
+    ]);
 
     let response_types_supported = Some(vec![
         OAuthAuthorizationEndpointResponseType::Code.into(),
@@ -86,6 +97,8 @@ pub(crate) async fn get(
         GrantType::RefreshToken,
         GrantType::ClientCredentials,
         GrantType::DeviceCode,
+        GrantType::TokenExchange,
+        GrantType::JwtBearer,
     ]);
 
     let token_endpoint_auth_methods_supported = client_auth_methods_supported.clone();
@@ -106,30 +119,61 @@ pub(crate) async fn get(
         PkceCodeChallengeMethod::S256,
     ]);
 
-    let subject_types_supported = Some(vec![SubjectType::Public]);
+    let subject_types_supported = Some(vec![SubjectType::Public, SubjectType::Pairwise]);
 
     let id_token_signing_alg_values_supported = jwt_signing_alg_values_supported.clone();
-    let userinfo_signing_alg_values_supported = jwt_signing_alg_values_supported;
+    let userinfo_signing_alg_values_supported = jwt_signing_alg_values_supported.clone();
+    let introspection_signing_alg_values_supported = jwt_signing_alg_values_supported;
+
+    // Those are the encryption algorithms supported by `mas-jose`
+    let id_token_encryption_alg_values_supported = Some(SUPPORTED_ENCRYPTION_ALGORITHMS.to_vec());
+    let id_token_encryption_enc_values_supported = Some(SUPPORTED_ENCRYPTION_ENCODINGS.to_vec());
+    let userinfo_encryption_alg_values_supported = id_token_encryption_alg_values_supported.clone();
+    let userinfo_encryption_enc_values_supported = id_token_encryption_enc_values_supported.clone();
 
     let display_values_supported = Some(vec![Display::Page]);
 
     let claim_types_supported = Some(vec![ClaimType::Normal]);
 
-    let claims_supported = Some(vec![
-        "iss".to_owned(),
-        "sub".to_owned(),
-        "aud".to_owned(),
-        "iat".to_owned(),
-        "exp".to_owned(),
-        "nonce".to_owned(),
-        "auth_time".to_owned(),
-        "at_hash".to_owned(),
-        "c_hash".to_owned(),
-    ]);
+    let claims_supported = Some(
+        [
+            "iss",
+            "sub",
+            "aud",
+            "iat",
+            "exp",
+            "nonce",
+            "auth_time",
+            "at_hash",
+            "c_hash",
+            "sid",
+        ]
+        .into_iter()
+        .chain(super::profile::PROFILE_CLAIMS)
+        .map(ToOwned::to_owned)
+        .collect(),
+    );
+
+    let claims_parameter_supported = Some(true);
+    let request_parameter_supported = Some(true);
+    let request_uri_parameter_supported = Some(true);
+    let require_request_uri_registration = Some(true);
 
-    let claims_parameter_supported = Some(false);
-    let request_parameter_supported = Some(false);
-    let request_uri_parameter_supported = Some(false);
+    // Request objects are verified with the client keys, like client assertions
+    let request_object_signing_alg_values_supported = Some(SUPPORTED_SIGNING_ALGORITHMS.to_vec());
+
+    let backchannel_logout_supported = Some(true);
+    let backchannel_logout_session_supported = Some(true);
+
+    let dpop_signing_alg_values_supported =
+        Some(mas_axum_utils::dpop::SUPPORTED_ALGORITHMS.to_vec());
+
+    // We always send the `iss` parameter back in authorization responses
+    let authorization_response_iss_parameter_supported = Some(true);
+
+    // Access tokens issued to clients using mutual-TLS are bound to their
+    // certificate
+    let tls_client_certificate_bound_access_tokens = Some(true);
 
     let prompt_values_supported = Some({
         let mut v = vec![Prompt::None, Prompt::Login];
@@ -160,19 +204,33 @@ pub(crate) async fn get(
         introspection_endpoint,
         introspection_endpoint_auth_methods_supported,
         introspection_endpoint_auth_signing_alg_values_supported,
+        introspection_signing_alg_values_supported,
         code_challenge_methods_supported,
         userinfo_endpoint,
         subject_types_supported,
         id_token_signing_alg_values_supported,
         userinfo_signing_alg_values_supported,
+        id_token_encryption_alg_values_supported,
+        id_token_encryption_enc_values_supported,
+        userinfo_encryption_alg_values_supported,
+        userinfo_encryption_enc_values_supported,
         display_values_supported,
         claim_types_supported,
         claims_supported,
         claims_parameter_supported,
         request_parameter_supported,
         request_uri_parameter_supported,
+        require_request_uri_registration,
+        request_object_signing_alg_values_supported,
         prompt_values_supported,
         device_authorization_endpoint,
+        end_session_endpoint,
+        backchannel_logout_supported,
+        backchannel_logout_session_supported,
+        dpop_signing_alg_values_supported,
+        authorization_response_iss_parameter_supported,
+        tls_client_certificate_bound_access_tokens,
+        pushed_authorization_request_endpoint,
         ..ProviderMetadata::default()
     };
 
@@ -210,8 +268,12 @@ mod tests {
         response.assert_status(StatusCode::OK);
 
         let metadata: ProviderMetadata = response.json();
-        metadata
+        let metadata = metadata
             .validate(state.url_builder.oidc_issuer().as_str())
             .expect("Invalid metadata");
+
+        // We always send the `iss` parameter in authorization responses
+        assert!(metadata.authorization_response_iss_parameter_supported());
+        assert!(metadata.tls_client_certificate_bound_access_tokens());
     }
 }
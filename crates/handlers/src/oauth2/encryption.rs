@@ -0,0 +1,183 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+//! Encryption of the ID tokens and user info responses returned to clients
+//! which registered `id_token_encrypted_response_alg` or
+//! `userinfo_encrypted_response_alg`
+
+use axum::BoxError;
+use mas_axum_utils::client_authorization::fetch_jwks;
+use mas_data_model::Client;
+use mas_iana::jose::{JsonWebEncryptionAlg, JsonWebEncryptionEnc};
+use mas_jose::{
+    constraints::Constrainable,
+    jwa::content_encryption,
+    jwe::{EncryptionKey, JsonWebEncryptionHeader, Jwe, JweEncryptionError},
+};
+use mas_keystore::Encrypter;
+use oauth2_types::registration::DEFAULT_ENCRYPTION_ENC_ALGORITHM;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub(crate) enum ResponseEncryptionError {
+    #[error("the client has no JWKS to encrypt responses with")]
+    MissingJwks,
+
+    #[error("could not fetch the client JWKS")]
+    JwksFetch(#[source] BoxError),
+
+    #[error("the client has no key suitable for {0}")]
+    NoSuitableKey(JsonWebEncryptionAlg),
+
+    #[error("the client has no secret to derive an encryption key from")]
+    MissingClientSecret,
+
+    #[error("could not decrypt the client secret")]
+    ClientSecret(#[from] mas_keystore::DecryptError),
+
+    #[error("content encryption algorithm {0} is not supported")]
+    UnsupportedEncoding(JsonWebEncryptionEnc),
+
+    #[error(transparent)]
+    Encryption(#[from] JweEncryptionError),
+}
+
+/// Derive the symmetric key used with `dir` from the client secret, as per
+/// section 10.2 of OpenID Connect Core
+fn derive_key_from_secret(secret: &[u8], len: usize) -> Vec<u8> {
+    let mut key = if len <= 32 {
+        Sha256::digest(secret).to_vec()
+    } else if len <= 48 {
+        Sha384::digest(secret).to_vec()
+    } else {
+        Sha512::digest(secret).to_vec()
+    };
+    key.truncate(len);
+    key
+}
+
+/// Find the key to encrypt a response for the client with, along with its
+/// key ID if it has one
+async fn encryption_key(
+    http_client: &reqwest::Client,
+    encrypter: &Encrypter,
+    client: &Client,
+    alg: &JsonWebEncryptionAlg,
+    enc: &JsonWebEncryptionEnc,
+) -> Result<(EncryptionKey, Option<String>), ResponseEncryptionError> {
+    if *alg == JsonWebEncryptionAlg::Dir {
+        let len = content_encryption::key_len(enc)
+            .ok_or_else(|| ResponseEncryptionError::UnsupportedEncoding(enc.clone()))?;
+        let encrypted_client_secret = client
+            .encrypted_client_secret
+            .as_deref()
+            .ok_or(ResponseEncryptionError::MissingClientSecret)?;
+        let client_secret = encrypter.decrypt_string(encrypted_client_secret)?;
+        let key = derive_key_from_secret(&client_secret, len);
+        return Ok((EncryptionKey::Direct(key), None));
+    }
+
+    let jwks = client
+        .jwks
+        .as_ref()
+        .ok_or(ResponseEncryptionError::MissingJwks)?;
+    let jwks = fetch_jwks(http_client, jwks)
+        .await
+        .map_err(ResponseEncryptionError::JwksFetch)?;
+
+    let jwk = jwks
+        .encryption_key_for_algorithm(alg)
+        .ok_or_else(|| ResponseEncryptionError::NoSuitableKey(alg.clone()))?;
+    let key = EncryptionKey::from_jwk(jwk.params())
+        .map_err(|_| ResponseEncryptionError::NoSuitableKey(alg.clone()))?;
+
+    Ok((key, jwk.kid().map(ToOwned::to_owned)))
+}
+
+/// Encrypt a payload for the client as a compact JWE
+///
+/// # Errors
+///
+/// Returns an error if no suitable key could be found for the client, or if
+/// the encryption itself failed
+pub(crate) async fn encrypt_for_client(
+    rng: &mut (impl rand::RngCore + rand::CryptoRng + Send),
+    http_client: &reqwest::Client,
+    encrypter: &Encrypter,
+    client: &Client,
+    alg: &JsonWebEncryptionAlg,
+    enc: &JsonWebEncryptionEnc,
+    cty: Option<&str>,
+    payload: &[u8],
+) -> Result<Jwe, ResponseEncryptionError> {
+    let (key, kid) = encryption_key(http_client, encrypter, client, alg, enc).await?;
+
+    let mut header = JsonWebEncryptionHeader::new(alg.clone(), enc.clone());
+    if let Some(kid) = kid {
+        header = header.with_kid(kid);
+    }
+    if let Some(cty) = cty {
+        header = header.with_cty(cty.to_owned());
+    }
+
+    Ok(Jwe::encrypt(rng, header, &key, payload)?)
+}
+
+/// Encrypt a signed ID token if the client asked for encrypted ID tokens,
+/// else return it as is
+///
+/// # Errors
+///
+/// Returns an error if the ID token could not be encrypted
+pub(crate) async fn encrypt_id_token(
+    rng: &mut (impl rand::RngCore + rand::CryptoRng + Send),
+    http_client: &reqwest::Client,
+    encrypter: &Encrypter,
+    client: &Client,
+    id_token: String,
+) -> Result<String, ResponseEncryptionError> {
+    let Some(alg) = &client.id_token_encrypted_response_alg else {
+        return Ok(id_token);
+    };
+    let enc = client
+        .id_token_encrypted_response_enc
+        .as_ref()
+        .unwrap_or(DEFAULT_ENCRYPTION_ENC_ALGORITHM);
+
+    // ID tokens are signed then encrypted, making them nested JWTs
+    let jwe = encrypt_for_client(
+        rng,
+        http_client,
+        encrypter,
+        client,
+        alg,
+        enc,
+        Some("JWT"),
+        id_token.as_bytes(),
+    )
+    .await?;
+
+    Ok(jwe.into_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derive_key_from_secret() {
+        let secret = b"client-secret";
+
+        let key = derive_key_from_secret(secret, 16);
+        assert_eq!(key, Sha256::digest(secret)[..16]);
+
+        let key = derive_key_from_secret(secret, 32);
+        assert_eq!(key, Sha256::digest(secret)[..]);
+
+        let key = derive_key_from_secret(secret, 64);
+        assert_eq!(key, Sha512::digest(secret)[..]);
+    }
+}
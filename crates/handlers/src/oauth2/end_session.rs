@@ -0,0 +1,622 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+//! Handlers for the [OpenID Connect RP-Initiated Logout] endpoint
+//!
+//! [OpenID Connect RP-Initiated Logout]: https://openid.net/specs/openid-connect-rpinitiated-1_0.html
+
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Form, Query, State},
+    response::{Html, IntoResponse, Redirect, Response},
+};
+use hyper::StatusCode;
+use mas_axum_utils::{
+    SessionInfoExt,
+    cookies::CookieJar,
+    csrf::{CsrfExt, ProtectedForm},
+    sentry::SentryEventID,
+};
+use mas_data_model::Client;
+use mas_jose::{
+    claims::{self, OneOrMany},
+    jwt::Jwt,
+};
+use mas_keystore::Keystore;
+use mas_router::UrlBuilder;
+use mas_storage::{
+    BoxClock, BoxRepository, BoxRng, RepositoryAccess,
+    oauth2::OAuth2ClientRepository,
+    queue::{BrowserSessionBackchannelLogoutJob, QueueJobRepositoryExt as _},
+    user::BrowserSessionRepository,
+};
+use mas_templates::{EndSessionContext, TemplateContext, Templates};
+use oauth2_types::oidc::RpInitiatedLogoutRequest;
+use serde::Deserialize;
+use thiserror::Error;
+use url::Url;
+
+use crate::{BoundActivityTracker, PreferredLanguage, impl_from_error_for_route};
+
+#[derive(Debug, Error)]
+pub(crate) enum RouteError {
+    #[error(transparent)]
+    Internal(Box<dyn std::error::Error + Send + Sync + 'static>),
+
+    #[error("invalid id_token_hint")]
+    InvalidIdTokenHint,
+
+    #[error("client_id does not match the id_token_hint audience")]
+    ClientIdMismatch,
+
+    #[error("could not find client")]
+    ClientNotFound,
+
+    #[error("post_logout_redirect_uri requires a client_id or an id_token_hint")]
+    MissingClient,
+
+    #[error("post_logout_redirect_uri is not registered for this client")]
+    UnknownPostLogoutRedirectUri,
+}
+
+impl IntoResponse for RouteError {
+    fn into_response(self) -> Response {
+        let event_id = sentry::capture_error(&self);
+        let response = match self {
+            Self::Internal(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+            e => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+        };
+
+        (SentryEventID::from(event_id), response).into_response()
+    }
+}
+
+impl_from_error_for_route!(mas_storage::RepositoryError);
+impl_from_error_for_route!(mas_templates::TemplateError);
+impl_from_error_for_route!(mas_axum_utils::csrf::CsrfError);
+
+/// The hidden fields carried by the confirmation form
+#[derive(Deserialize, Debug)]
+pub(crate) struct ConfirmForm {
+    client_id: Option<String>,
+    post_logout_redirect_uri: Option<Url>,
+    state: Option<String>,
+}
+
+/// Validate the `id_token_hint`, and figure out which client it was issued to
+///
+/// If a `client_id` was given alongside the hint, it must be part of the ID
+/// token audience. Expired ID tokens are accepted, as the RP may well be asking
+/// to end a session which has been open for longer than the ID token lifetime.
+fn client_id_from_id_token_hint(
+    key_store: &Keystore,
+    url_builder: &UrlBuilder,
+    id_token_hint: &str,
+    client_id: Option<String>,
+) -> Result<Option<String>, RouteError> {
+    let jwt: Jwt<HashMap<String, serde_json::Value>> =
+        Jwt::try_from(id_token_hint).map_err(|_| RouteError::InvalidIdTokenHint)?;
+
+    jwt.verify_with_jwks(&key_store.public_jwks())
+        .map_err(|_| RouteError::InvalidIdTokenHint)?;
+
+    let (_header, mut claims) = jwt.into_parts();
+
+    claims::ISS
+        .extract_required_with_options(&mut claims, url_builder.oidc_issuer().as_str())
+        .map_err(|_| RouteError::InvalidIdTokenHint)?;
+
+    if let Some(client_id) = client_id {
+        claims::AUD
+            .extract_required_with_options(&mut claims, &client_id)
+            .map_err(|_| RouteError::ClientIdMismatch)?;
+
+        return Ok(Some(client_id));
+    }
+
+    let audience: OneOrMany<String> = claims
+        .remove("aud")
+        .and_then(|aud| serde_json::from_value(aud).ok())
+        .ok_or(RouteError::InvalidIdTokenHint)?;
+
+    // Only infer the client if the ID token was issued to a single audience
+    match &audience[..] {
+        [aud] => Ok(Some(aud.clone())),
+        _ => Ok(None),
+    }
+}
+
+/// Figure out which client the request is coming from, and check that the
+/// `post_logout_redirect_uri` was registered by that client
+async fn resolve_client(
+    repo: &mut impl RepositoryAccess<Error = mas_storage::RepositoryError>,
+    client_id: Option<&str>,
+    post_logout_redirect_uri: Option<&Url>,
+) -> Result<Option<Client>, RouteError> {
+    let client = match client_id {
+        Some(client_id) => Some(
+            repo.oauth2_client()
+                .find_by_client_id(client_id)
+                .await?
+                .ok_or(RouteError::ClientNotFound)?,
+        ),
+        None => None,
+    };
+
+    if let Some(post_logout_redirect_uri) = post_logout_redirect_uri {
+        let client = client.as_ref().ok_or(RouteError::MissingClient)?;
+        if !client.has_post_logout_redirect_uri(post_logout_redirect_uri) {
+            return Err(RouteError::UnknownPostLogoutRedirectUri);
+        }
+    }
+
+    Ok(client)
+}
+
+/// Where to send the user once the logout is done
+fn destination(
+    url_builder: &UrlBuilder,
+    post_logout_redirect_uri: Option<Url>,
+    state: Option<&str>,
+) -> Redirect {
+    match post_logout_redirect_uri {
+        Some(mut uri) => {
+            if let Some(state) = state {
+                uri.query_pairs_mut().append_pair("state", state);
+            }
+            Redirect::to(uri.as_str())
+        }
+        None => url_builder.redirect(&mas_router::Login::default()),
+    }
+}
+
+#[tracing::instrument(name = "handlers.oauth2.end_session.get", skip_all, err)]
+pub(crate) async fn get(
+    rng: BoxRng,
+    clock: BoxClock,
+    PreferredLanguage(locale): PreferredLanguage,
+    State(templates): State<Templates>,
+    State(key_store): State<Keystore>,
+    State(url_builder): State<UrlBuilder>,
+    repo: BoxRepository,
+    cookie_jar: CookieJar,
+    Query(params): Query<RpInitiatedLogoutRequest>,
+) -> Result<Response, RouteError> {
+    handle(
+        rng,
+        clock,
+        locale,
+        templates,
+        key_store,
+        url_builder,
+        repo,
+        cookie_jar,
+        params,
+    )
+    .await
+}
+
+#[tracing::instrument(name = "handlers.oauth2.end_session.post", skip_all, err)]
+pub(crate) async fn post(
+    rng: BoxRng,
+    clock: BoxClock,
+    PreferredLanguage(locale): PreferredLanguage,
+    State(templates): State<Templates>,
+    State(key_store): State<Keystore>,
+    State(url_builder): State<UrlBuilder>,
+    repo: BoxRepository,
+    cookie_jar: CookieJar,
+    Form(params): Form<RpInitiatedLogoutRequest>,
+) -> Result<Response, RouteError> {
+    handle(
+        rng,
+        clock,
+        locale,
+        templates,
+        key_store,
+        url_builder,
+        repo,
+        cookie_jar,
+        params,
+    )
+    .await
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn handle(
+    mut rng: BoxRng,
+    clock: BoxClock,
+    locale: mas_i18n::DataLocale,
+    templates: Templates,
+    key_store: Keystore,
+    url_builder: UrlBuilder,
+    mut repo: BoxRepository,
+    cookie_jar: CookieJar,
+    params: RpInitiatedLogoutRequest,
+) -> Result<Response, RouteError> {
+    let client_id = match &params.id_token_hint {
+        Some(id_token_hint) => {
+            client_id_from_id_token_hint(&key_store, &url_builder, id_token_hint, params.client_id)?
+        }
+        None => params.client_id,
+    };
+
+    let client = resolve_client(
+        &mut repo,
+        client_id.as_deref(),
+        params.post_logout_redirect_uri.as_ref(),
+    )
+    .await?;
+
+    let (session_info, cookie_jar) = cookie_jar.session_info();
+    let maybe_session = session_info.load_active_session(&mut repo).await?;
+
+    let Some(session) = maybe_session else {
+        // There is no session to end, send the user back straight away
+        let destination = destination(
+            &url_builder,
+            params.post_logout_redirect_uri,
+            params.state.as_deref(),
+        );
+        return Ok((cookie_jar, destination).into_response());
+    };
+
+    // Always ask the user to confirm, as this endpoint can be reached through a
+    // simple link from any website
+    let (csrf_token, cookie_jar) = cookie_jar.csrf_token(&clock, &mut rng);
+    let mut ctx = EndSessionContext::new(client);
+    if let Some(post_logout_redirect_uri) = params.post_logout_redirect_uri {
+        ctx = ctx.with_post_logout_redirect_uri(post_logout_redirect_uri, params.state);
+    }
+    let ctx = ctx
+        .with_session(session)
+        .with_csrf(csrf_token.form_value())
+        .with_language(locale);
+
+    let content = templates.render_end_session(&ctx)?;
+
+    Ok((cookie_jar, Html(content)).into_response())
+}
+
+#[tracing::instrument(name = "handlers.oauth2.end_session.confirm", skip_all, err)]
+pub(crate) async fn confirm(
+    clock: BoxClock,
+    mut rng: BoxRng,
+    mut repo: BoxRepository,
+    cookie_jar: CookieJar,
+    State(url_builder): State<UrlBuilder>,
+    activity_tracker: BoundActivityTracker,
+    Form(form): Form<ProtectedForm<ConfirmForm>>,
+) -> Result<Response, RouteError> {
+    let form = cookie_jar.verify_form(&clock, form)?;
+
+    // The form fields could have been tampered with, check them again
+    resolve_client(
+        &mut repo,
+        form.client_id.as_deref(),
+        form.post_logout_redirect_uri.as_ref(),
+    )
+    .await?;
+
+    let (session_info, cookie_jar) = cookie_jar.session_info();
+
+    if let Some(session) = session_info.load_active_session(&mut repo).await? {
+        activity_tracker
+            .record_browser_session(&clock, &session)
+            .await;
+
+        repo.queue_job()
+            .schedule_job(
+                &mut rng,
+                &clock,
+                BrowserSessionBackchannelLogoutJob::new(&session),
+            )
+            .await?;
+
+        repo.browser_session().finish(&clock, session).await?;
+    }
+
+    repo.save().await?;
+
+    let cookie_jar = cookie_jar.update_session_info(&session_info.mark_session_ended());
+
+    let destination = destination(
+        &url_builder,
+        form.post_logout_redirect_uri,
+        form.state.as_deref(),
+    );
+
+    Ok((cookie_jar, destination).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::Duration;
+    use hyper::{Request, StatusCode, header::LOCATION};
+    use mas_axum_utils::SessionInfoExt as _;
+    use mas_iana::jose::JsonWebSignatureAlg;
+    use mas_jose::{
+        claims,
+        constraints::Constrainable as _,
+        jwt::{JsonWebSignatureHeader, Jwt},
+    };
+    use mas_router::SimpleRoute;
+    use mas_storage::{Clock as _, RepositoryAccess, user::BrowserSessionRepository};
+    use oauth2_types::registration::ClientRegistrationResponse;
+    use sqlx::PgPool;
+
+    use crate::test_utils::{CookieHelper, RequestBuilderExt, ResponseExt, TestState, setup};
+
+    /// Register a public client which can be sent back to
+    /// `https://example.com/logged-out`, and return its `client_id`
+    async fn register_client(state: &TestState) -> String {
+        let request =
+            Request::post(mas_router::OAuth2RegistrationEndpoint::PATH).json(serde_json::json!({
+                "client_uri": "https://example.com/",
+                "redirect_uris": ["https://example.com/callback"],
+                "post_logout_redirect_uris": ["https://example.com/logged-out"],
+                "token_endpoint_auth_method": "none",
+                "response_types": ["code"],
+                "grant_types": ["authorization_code"],
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CREATED);
+        let ClientRegistrationResponse { client_id, .. } = response.json();
+        client_id
+    }
+
+    /// Sign an ID token for the given audience, which expired a while ago
+    fn id_token_hint(state: &TestState, audience: serde_json::Value) -> String {
+        let alg = JsonWebSignatureAlg::Rs256;
+        let key = state.key_store.signing_key_for_algorithm(&alg).unwrap();
+        let signer = key.params().signing_key_for_alg(&alg).unwrap();
+        let header = JsonWebSignatureHeader::new(alg).with_kid(key.kid().unwrap());
+
+        let now = state.clock.now();
+        let mut claims = HashMap::new();
+        claims::ISS
+            .insert(&mut claims, state.url_builder.oidc_issuer().to_string())
+            .unwrap();
+        claims::SUB.insert(&mut claims, "subject").unwrap();
+        claims.insert("aud".to_owned(), audience);
+        claims::IAT
+            .insert(&mut claims, now - Duration::hours(2))
+            .unwrap();
+        claims::EXP
+            .insert(&mut claims, now - Duration::hours(1))
+            .unwrap();
+
+        Jwt::sign_with_rng(&mut state.rng(), header, claims, &signer)
+            .unwrap()
+            .into_string()
+    }
+
+    fn extract_csrf(body: &str) -> String {
+        body.split("name=\"csrf\" value=\"")
+            .nth(1)
+            .unwrap()
+            .split('\"')
+            .next()
+            .unwrap()
+            .to_owned()
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_end_session_without_browser_session(pool: PgPool) {
+        setup();
+        let state = TestState::from_pool(pool).await.unwrap();
+
+        let request =
+            Request::post(mas_router::OAuth2RegistrationEndpoint::PATH).json(serde_json::json!({
+                "client_uri": "https://example.com/",
+                "redirect_uris": ["https://example.com/callback"],
+                "post_logout_redirect_uris": ["https://example.com/logged-out"],
+                "token_endpoint_auth_method": "none",
+                "response_types": ["code"],
+                "grant_types": ["authorization_code"],
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CREATED);
+        let ClientRegistrationResponse { client_id, .. } = response.json();
+
+        // A registered post_logout_redirect_uri gets the user redirected straight
+        // away, with the state passed back
+        let request = Request::get(format!(
+            "{}?client_id={client_id}&post_logout_redirect_uri=https%3A%2F%2Fexample.com%2Flogged-out&state=abc",
+            mas_router::OidcEndSession::PATH,
+        ))
+        .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::SEE_OTHER);
+        response.assert_header_value(
+            hyper::header::LOCATION,
+            "https://example.com/logged-out?state=abc",
+        );
+
+        // An unknown post_logout_redirect_uri is rejected
+        let request = Request::get(format!(
+            "{}?client_id={client_id}&post_logout_redirect_uri=https%3A%2F%2Fexample.com%2Fsomewhere-else",
+            mas_router::OidcEndSession::PATH,
+        ))
+        .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+
+        // A post_logout_redirect_uri without a client is rejected
+        let request = Request::get(format!(
+            "{}?post_logout_redirect_uri=https%3A%2F%2Fexample.com%2Flogged-out",
+            mas_router::OidcEndSession::PATH,
+        ))
+        .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_end_session_with_id_token_hint(pool: PgPool) {
+        setup();
+        let state = TestState::from_pool(pool).await.unwrap();
+
+        let client_id = register_client(&state).await;
+        let other_client_id = register_client(&state).await;
+
+        // The client is inferred from the hint, even if it expired
+        let hint = id_token_hint(&state, serde_json::json!(client_id));
+        let request = Request::get(format!(
+            "{}?id_token_hint={hint}&post_logout_redirect_uri=https%3A%2F%2Fexample.com%2Flogged-out",
+            mas_router::OidcEndSession::PATH,
+        ))
+        .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::SEE_OTHER);
+        response.assert_header_value(LOCATION, "https://example.com/logged-out");
+
+        // The client_id must match the audience of the hint
+        let request = Request::get(format!(
+            "{}?id_token_hint={hint}&client_id={other_client_id}",
+            mas_router::OidcEndSession::PATH,
+        ))
+        .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+
+        // The post_logout_redirect_uri must be registered by the inferred client
+        let request = Request::get(format!(
+            "{}?id_token_hint={hint}&post_logout_redirect_uri=https%3A%2F%2Fexample.com%2Fsomewhere-else",
+            mas_router::OidcEndSession::PATH,
+        ))
+        .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+
+        // With multiple audiences, the client can't be inferred
+        let hint = id_token_hint(&state, serde_json::json!([client_id, other_client_id]));
+        let request = Request::get(format!(
+            "{}?id_token_hint={hint}&post_logout_redirect_uri=https%3A%2F%2Fexample.com%2Flogged-out",
+            mas_router::OidcEndSession::PATH,
+        ))
+        .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+
+        // Unless it is given explicitly
+        let request = Request::get(format!(
+            "{}?id_token_hint={hint}&client_id={other_client_id}&post_logout_redirect_uri=https%3A%2F%2Fexample.com%2Flogged-out",
+            mas_router::OidcEndSession::PATH,
+        ))
+        .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::SEE_OTHER);
+        response.assert_header_value(LOCATION, "https://example.com/logged-out");
+
+        // Hints which weren't signed by us are rejected
+        let request = Request::get(format!(
+            "{}?id_token_hint=not-a-jwt&client_id={client_id}",
+            mas_router::OidcEndSession::PATH,
+        ))
+        .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_end_session_confirm(pool: PgPool) {
+        setup();
+        let state = TestState::from_pool(pool).await.unwrap();
+        let cookies = CookieHelper::new();
+        let mut rng = state.rng();
+
+        let client_id = register_client(&state).await;
+
+        // Provision a user with an active browser session
+        let mut repo = state.repository().await.unwrap();
+        let user = repo
+            .user()
+            .add(&mut rng, &state.clock, "john".to_owned())
+            .await
+            .unwrap();
+        let browser_session = repo
+            .browser_session()
+            .add(&mut rng, &state.clock, &user, None)
+            .await
+            .unwrap();
+        repo.save().await.unwrap();
+        cookies.import(state.cookie_jar().set_session(&browser_session));
+
+        // The user is asked to confirm
+        let request = Request::get(format!(
+            "{}?client_id={client_id}&post_logout_redirect_uri=https%3A%2F%2Fexample.com%2Flogged-out&state=abc",
+            mas_router::OidcEndSession::PATH,
+        ))
+        .empty();
+        let request = cookies.with_cookies(request);
+        let response = state.request(request).await;
+        cookies.save_cookies(&response);
+        response.assert_status(StatusCode::OK);
+        let csrf_token = extract_csrf(response.body());
+
+        // Tampering with the redirect URI in the form is detected
+        let request =
+            Request::post(mas_router::OidcEndSessionConfirm::PATH).form(serde_json::json!({
+                "csrf": csrf_token,
+                "client_id": client_id,
+                "post_logout_redirect_uri": "https://example.com/somewhere-else",
+                "state": "abc",
+            }));
+        let request = cookies.with_cookies(request);
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+
+        let mut repo = state.repository().await.unwrap();
+        let session = repo
+            .browser_session()
+            .lookup(browser_session.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(session.finished_at.is_none());
+        repo.cancel().await.unwrap();
+
+        // Confirming ends the session and sends the user back to the client
+        let request =
+            Request::post(mas_router::OidcEndSessionConfirm::PATH).form(serde_json::json!({
+                "csrf": csrf_token,
+                "client_id": client_id,
+                "post_logout_redirect_uri": "https://example.com/logged-out",
+                "state": "abc",
+            }));
+        let request = cookies.with_cookies(request);
+        let response = state.request(request).await;
+        cookies.save_cookies(&response);
+        response.assert_status(StatusCode::SEE_OTHER);
+        response.assert_header_value(LOCATION, "https://example.com/logged-out?state=abc");
+
+        let mut repo = state.repository().await.unwrap();
+        let session = repo
+            .browser_session()
+            .lookup(browser_session.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(session.finished_at.is_some());
+        repo.cancel().await.unwrap();
+
+        // Without a session, the user is sent back straight away
+        let request = Request::get(format!(
+            "{}?client_id={client_id}&post_logout_redirect_uri=https%3A%2F%2Fexample.com%2Flogged-out",
+            mas_router::OidcEndSession::PATH,
+        ))
+        .empty();
+        let request = cookies.with_cookies(request);
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::SEE_OTHER);
+        response.assert_header_value(LOCATION, "https://example.com/logged-out");
+    }
+}
@@ -6,29 +6,48 @@
 
 use std::sync::LazyLock;
 
-use axum::{Json, extract::State, http::HeaderValue, response::IntoResponse};
-use hyper::{HeaderMap, StatusCode};
+use axum::{
+    Json,
+    extract::State,
+    http::HeaderValue,
+    response::{IntoResponse, Response},
+};
+use axum_extra::typed_header::TypedHeader;
+use headers::ContentType;
+use hyper::{HeaderMap, StatusCode, header::ACCEPT};
 use mas_axum_utils::{
     client_authorization::{ClientAuthorization, CredentialsVerificationError},
+    dpop::{DpopProofError, verify_dpop_proof},
     sentry::SentryEventID,
 };
-use mas_data_model::{Device, TokenFormatError, TokenType};
-use mas_iana::oauth::{OAuthClientAuthenticationMethod, OAuthTokenTypeHint};
-use mas_keystore::Encrypter;
+use mas_data_model::{Client, Device, TokenFormatError, TokenType};
+use mas_iana::{
+    jose::JsonWebSignatureAlg,
+    oauth::{OAuthClientAuthenticationMethod, OAuthTokenTypeHint},
+};
+use mas_jose::{
+    constraints::Constrainable,
+    jwt::{JsonWebSignatureHeader, Jwt},
+};
+use mas_keystore::{Encrypter, Keystore};
+use mas_router::UrlBuilder;
 use mas_storage::{
-    BoxClock, BoxRepository, Clock,
+    BoxClock, BoxRepository, BoxRng, Clock,
     compat::{CompatAccessTokenRepository, CompatRefreshTokenRepository, CompatSessionRepository},
     oauth2::{OAuth2AccessTokenRepository, OAuth2RefreshTokenRepository, OAuth2SessionRepository},
     user::UserRepository,
 };
+use mime::Mime;
 use oauth2_types::{
     errors::{ClientError, ClientErrorCode},
-    requests::{IntrospectionRequest, IntrospectionResponse},
+    requests::{Actor, Confirmation, IntrospectionRequest, IntrospectionResponse},
     scope::ScopeToken,
 };
 use opentelemetry::{Key, KeyValue, metrics::Counter};
+use serde::Serialize;
 use thiserror::Error;
 
+use super::subject_for_client;
 use crate::{ActivityTracker, METER, impl_from_error_for_route};
 
 static INTROSPECTION_COUNTER: LazyLock<Counter<u64>> = LazyLock::new(|| {
@@ -103,6 +122,48 @@ pub enum RouteError {
 
     #[error(transparent)]
     ClientCredentialsVerification(#[from] CredentialsVerificationError),
+
+    /// The `DPoP` proof forwarded with the request is invalid.
+    #[error("invalid DPoP proof")]
+    InvalidDpopProof(#[source] DpopProofError<mas_storage::RepositoryError>),
+
+    /// The `DPoP` proof forwarded with the request was signed by another key
+    /// than the one the token is bound to.
+    #[error("DPoP proof does not match the key the token is bound to")]
+    DpopKeyMismatch,
+
+    /// No key is available to sign the JWT response.
+    #[error("no suitable key found for signing")]
+    InvalidSigningKey,
+}
+
+impl RouteError {
+    /// Whether this error means the token is inactive, as opposed to the
+    /// request failing
+    fn is_inactive(&self) -> bool {
+        matches!(
+            self,
+            Self::UnknownToken(_)
+                | Self::UnexpectedTokenType
+                | Self::InvalidToken(_)
+                | Self::InvalidUser
+                | Self::InvalidCompatSession
+                | Self::InvalidOAuthSession
+                | Self::InvalidTokenFormat(_)
+                | Self::CantEncodeDeviceID(_)
+                | Self::InvalidDpopProof(_)
+                | Self::DpopKeyMismatch
+        )
+    }
+}
+
+impl From<DpopProofError<mas_storage::RepositoryError>> for RouteError {
+    fn from(e: DpopProofError<mas_storage::RepositoryError>) -> Self {
+        match e {
+            DpopProofError::Internal(e) => Self::Internal(Box::new(e)),
+            e => Self::InvalidDpopProof(e),
+        }
+    }
 }
 
 impl IntoResponse for RouteError {
@@ -110,6 +171,7 @@ impl IntoResponse for RouteError {
         let event_id = sentry::capture_error(&self);
         let response = match self {
             e @ (Self::Internal(_)
+            | Self::InvalidSigningKey
             | Self::CantLoadCompatSession
             | Self::CantLoadOAuthSession
             | Self::CantLoadUser) => (
@@ -140,7 +202,9 @@ impl IntoResponse for RouteError {
             | Self::InvalidCompatSession
             | Self::InvalidOAuthSession
             | Self::InvalidTokenFormat(_)
-            | Self::CantEncodeDeviceID(_) => {
+            | Self::CantEncodeDeviceID(_)
+            | Self::InvalidDpopProof(_)
+            | Self::DpopKeyMismatch => {
                 INTROSPECTION_COUNTER.add(1, &[KeyValue::new(ACTIVE.clone(), false)]);
 
                 Json(INACTIVE).into_response()
@@ -163,6 +227,32 @@ impl IntoResponse for RouteError {
 }
 
 impl_from_error_for_route!(mas_storage::RepositoryError);
+impl_from_error_for_route!(mas_keystore::WrongAlgorithmError);
+impl_from_error_for_route!(mas_jose::jwt::JwtSignatureError);
+
+/// The claims of a JWT introspection response, as per RFC 9701
+#[derive(Serialize)]
+struct SignedIntrospectionResponse {
+    iss: String,
+    aud: String,
+    iat: i64,
+    token_introspection: IntrospectionResponse,
+}
+
+static TOKEN_INTROSPECTION_JWT: LazyLock<Mime> =
+    LazyLock::new(|| "application/token-introspection+jwt".parse().unwrap());
+
+/// Whether the resource server asked for a JWT response through the `Accept`
+/// header
+fn accepts_jwt_response(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|media_range| media_range.split(';').next())
+        .any(|media_type| media_type.trim() == TOKEN_INTROSPECTION_JWT.essence_str())
+}
 
 const INACTIVE: IntrospectionResponse = IntrospectionResponse {
     active: false,
@@ -179,6 +269,8 @@ const INACTIVE: IntrospectionResponse = IntrospectionResponse {
     iss: None,
     jti: None,
     device_id: None,
+    cnf: None,
+    act: None,
 };
 
 const API_SCOPE: ScopeToken = ScopeToken::from_static("urn:matrix:org.matrix.msc2967.client:api:*");
@@ -192,14 +284,17 @@ const SYNAPSE_ADMIN_SCOPE: ScopeToken = ScopeToken::from_static("urn:synapse:adm
 )]
 #[allow(clippy::too_many_lines)]
 pub(crate) async fn post(
+    mut rng: BoxRng,
     clock: BoxClock,
     State(http_client): State<reqwest::Client>,
     mut repo: BoxRepository,
     activity_tracker: ActivityTracker,
     State(encrypter): State<Encrypter>,
+    State(key_store): State<Keystore>,
+    State(url_builder): State<UrlBuilder>,
     headers: HeaderMap,
     client_authorization: ClientAuthorization<IntrospectionRequest>,
-) -> Result<impl IntoResponse, RouteError> {
+) -> Result<Response, RouteError> {
     let client = client_authorization
         .credentials
         .fetch(&mut repo)
@@ -222,10 +317,73 @@ pub(crate) async fn post(
         return Err(RouteError::BadRequest);
     };
 
+    let jwt_response = accepts_jwt_response(&headers);
+    let reply = match introspect(
+        &mut rng,
+        &clock,
+        &mut repo,
+        &activity_tracker,
+        &headers,
+        &client,
+        &form,
+    )
+    .await
+    {
+        Ok(reply) => reply,
+        // Signed responses are also expected for inactive tokens
+        Err(e) if jwt_response && e.is_inactive() => {
+            INTROSPECTION_COUNTER.add(1, &[KeyValue::new(ACTIVE, false)]);
+            INACTIVE
+        }
+        Err(e) => return Err(e),
+    };
+
+    repo.save().await?;
+
+    if !jwt_response {
+        return Ok(Json(reply).into_response());
+    }
+
+    let alg = client
+        .introspection_signed_response_alg
+        .clone()
+        .unwrap_or(JsonWebSignatureAlg::Rs256);
+    let key = key_store
+        .signing_key_for_algorithm(&alg)
+        .ok_or(RouteError::InvalidSigningKey)?;
+
+    let signer = key.params().signing_key_for_alg(&alg)?;
+    let header = JsonWebSignatureHeader::new(alg)
+        .with_typ("token-introspection+jwt".to_owned())
+        .with_kid(key.kid().ok_or(RouteError::InvalidSigningKey)?);
+
+    let claims = SignedIntrospectionResponse {
+        iss: url_builder.oidc_issuer().to_string(),
+        aud: client.client_id,
+        iat: clock.now().timestamp(),
+        token_introspection: reply,
+    };
+
+    let token = Jwt::sign_with_rng(&mut rng, header, claims, &signer)?;
+    let content_type = ContentType::from(TOKEN_INTROSPECTION_JWT.clone());
+    Ok((TypedHeader(content_type), token.into_string()).into_response())
+}
+
+/// Introspect the token in the request, on behalf of the given client
+#[allow(clippy::too_many_lines)]
+async fn introspect(
+    rng: &mut BoxRng,
+    clock: &BoxClock,
+    repo: &mut BoxRepository,
+    activity_tracker: &ActivityTracker,
+    headers: &HeaderMap,
+    client: &Client,
+    form: &IntrospectionRequest,
+) -> Result<IntrospectionResponse, RouteError> {
     let token = &form.token;
     let token_type = TokenType::check(token)?;
-    if let Some(hint) = form.token_type_hint {
-        if token_type != hint {
+    if let Some(hint) = &form.token_type_hint {
+        if token_type != *hint {
             return Err(RouteError::UnexpectedTokenType);
         }
     }
@@ -255,6 +413,16 @@ pub(crate) async fn post(
                 return Err(RouteError::InvalidToken(TokenType::AccessToken));
             }
 
+            // The resource server may forward the DPoP proof it received alongside
+            // the token. In this case, we check it against the token and record it
+            // to prevent replays. Otherwise, the resource server is expected to
+            // check the proof against the `cnf` claim itself.
+            if let Some(proof) = verify_dpop_proof(clock, repo, headers, None, Some(token)).await? {
+                if access_token.dpop_jkt.as_deref() != Some(proof.jkt.as_str()) {
+                    return Err(RouteError::DpopKeyMismatch);
+                }
+            }
+
             let session = repo
                 .oauth2_session()
                 .lookup(access_token.session_id)
@@ -269,12 +437,13 @@ pub(crate) async fn post(
             if !access_token.is_used() {
                 access_token = repo
                     .oauth2_access_token()
-                    .mark_used(&clock, access_token)
+                    .mark_used(clock, access_token)
                     .await?;
             }
 
             // The session might not have a user on it (for Client Credentials grants for
-            // example), so we're optionally fetching the user
+            // example), so we're optionally fetching the user. The subject is the one
+            // known by the introspecting client, which may be a pairwise identifier.
             let (sub, username) = if let Some(user_id) = session.user_id {
                 let user = repo
                     .user()
@@ -286,13 +455,14 @@ pub(crate) async fn post(
                     return Err(RouteError::InvalidUser);
                 }
 
-                (Some(user.sub), Some(user.username))
+                let sub = subject_for_client(rng, clock, repo, client, &user).await?;
+                (Some(sub), Some(user.username))
             } else {
                 (None, None)
             };
 
             activity_tracker
-                .record_oauth2_session(&clock, &session, ip)
+                .record_oauth2_session(clock, &session, ip)
                 .await;
 
             INTROSPECTION_COUNTER.add(
@@ -303,6 +473,8 @@ pub(crate) async fn post(
                 ],
             );
 
+            let aud = session.audiences();
+
             IntrospectionResponse {
                 active: true,
                 scope: Some(session.scope),
@@ -316,10 +488,19 @@ pub(crate) async fn post(
                 iat: Some(access_token.created_at),
                 nbf: Some(access_token.created_at),
                 sub,
-                aud: None,
+                aud: Some(aud).filter(|aud| !aud.is_empty()),
                 iss: None,
                 jti: Some(access_token.jti()),
                 device_id: None,
+                cnf: (access_token.dpop_jkt.is_some() || access_token.x5t_s256.is_some())
+                    .then_some(Confirmation {
+                        jkt: access_token.dpop_jkt,
+                        x5t_s256: access_token.x5t_s256,
+                    }),
+                act: session.actor_client_id.map(|actor_client_id| Actor {
+                    sub: Some(actor_client_id.to_string()),
+                    act: None,
+                }),
             }
         }
 
@@ -357,13 +538,14 @@ pub(crate) async fn post(
                     return Err(RouteError::InvalidUser);
                 }
 
-                (Some(user.sub), Some(user.username))
+                let sub = subject_for_client(rng, clock, repo, client, &user).await?;
+                (Some(sub), Some(user.username))
             } else {
                 (None, None)
             };
 
             activity_tracker
-                .record_oauth2_session(&clock, &session, ip)
+                .record_oauth2_session(clock, &session, ip)
                 .await;
 
             INTROSPECTION_COUNTER.add(
@@ -374,6 +556,8 @@ pub(crate) async fn post(
                 ],
             );
 
+            let aud = session.audiences();
+
             IntrospectionResponse {
                 active: true,
                 scope: Some(session.scope),
@@ -385,10 +569,18 @@ pub(crate) async fn post(
                 iat: Some(refresh_token.created_at),
                 nbf: Some(refresh_token.created_at),
                 sub,
-                aud: None,
+                aud: Some(aud).filter(|aud| !aud.is_empty()),
                 iss: None,
                 jti: Some(refresh_token.jti()),
                 device_id: None,
+                cnf: refresh_token.dpop_jkt.map(|jkt| Confirmation {
+                    jkt: Some(jkt),
+                    x5t_s256: None,
+                }),
+                act: session.actor_client_id.map(|actor_client_id| Actor {
+                    sub: Some(actor_client_id.to_string()),
+                    act: None,
+                }),
             }
         }
 
@@ -423,6 +615,8 @@ pub(crate) async fn post(
                 return Err(RouteError::InvalidUser)?;
             }
 
+            let sub = subject_for_client(rng, clock, repo, client, &user).await?;
+
             // Grant the synapse admin scope if the session has the admin flag set.
             let synapse_admin_scope_opt = session.is_synapse_admin.then_some(SYNAPSE_ADMIN_SCOPE);
 
@@ -445,7 +639,7 @@ pub(crate) async fn post(
                 .collect();
 
             activity_tracker
-                .record_compat_session(&clock, &session, ip)
+                .record_compat_session(clock, &session, ip)
                 .await;
 
             INTROSPECTION_COUNTER.add(
@@ -468,11 +662,13 @@ pub(crate) async fn post(
                     .map(|expires_at| expires_at.signed_duration_since(clock.now())),
                 iat: Some(access_token.created_at),
                 nbf: Some(access_token.created_at),
-                sub: Some(user.sub),
+                sub: Some(sub),
                 aud: None,
                 iss: None,
                 jti: None,
                 device_id: session.device.map(Device::into),
+                cnf: None,
+                act: None,
             }
         }
 
@@ -507,6 +703,8 @@ pub(crate) async fn post(
                 return Err(RouteError::InvalidUser)?;
             }
 
+            let sub = subject_for_client(rng, clock, repo, client, &user).await?;
+
             // Grant the synapse admin scope if the session has the admin flag set.
             let synapse_admin_scope_opt = session.is_synapse_admin.then_some(SYNAPSE_ADMIN_SCOPE);
 
@@ -529,7 +727,7 @@ pub(crate) async fn post(
                 .collect();
 
             activity_tracker
-                .record_compat_session(&clock, &session, ip)
+                .record_compat_session(clock, &session, ip)
                 .await;
 
             INTROSPECTION_COUNTER.add(
@@ -550,26 +748,36 @@ pub(crate) async fn post(
                 expires_in: None,
                 iat: Some(refresh_token.created_at),
                 nbf: Some(refresh_token.created_at),
-                sub: Some(user.sub),
+                sub: Some(sub),
                 aud: None,
                 iss: None,
                 jti: None,
                 device_id: session.device.map(Device::into),
+                cnf: None,
+                act: None,
             }
         }
-    };
 
-    repo.save().await?;
+        // Initial access tokens are only meant for the client registration
+        // endpoint, not for resource servers
+        TokenType::InitialAccessToken => return Err(RouteError::UnexpectedTokenType),
+    };
 
-    Ok(Json(reply))
+    Ok(reply)
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
     use chrono::Duration;
-    use hyper::{Request, StatusCode};
-    use mas_data_model::{AccessToken, RefreshToken};
-    use mas_iana::oauth::OAuthTokenTypeHint;
+    use hyper::{
+        Request, StatusCode,
+        header::{ACCEPT, CONTENT_TYPE},
+    };
+    use mas_data_model::{AccessToken, AccessTokenFormat, RefreshToken, TokenType};
+    use mas_iana::oauth::{OAuthClientAuthenticationMethod, OAuthTokenTypeHint};
+    use mas_jose::jwt::Jwt;
     use mas_matrix::{HomeserverConnection, ProvisionRequest};
     use mas_router::{OAuth2Introspection, OAuth2RegistrationEndpoint, SimpleRoute};
     use mas_storage::Clock;
@@ -580,10 +788,11 @@ mod tests {
     };
     use serde_json::json;
     use sqlx::PgPool;
+    use ulid::Ulid;
     use zeroize::Zeroizing;
 
     use crate::{
-        oauth2::generate_token_pair,
+        oauth2::{generate_access_token, generate_token_pair},
         test_utils::{RequestBuilderExt, ResponseExt, TestState, setup},
     };
 
@@ -664,7 +873,10 @@ mod tests {
                 &state.clock,
                 &mut repo,
                 &session,
+                TokenType::AccessToken.generate(&mut state.rng()),
                 Duration::microseconds(5 * 60 * 1000 * 1000),
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -978,4 +1190,214 @@ mod tests {
         let response: IntrospectionResponse = response.json();
         assert!(response.active);
     }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_introspect_jwt_access_token(pool: PgPool) {
+        setup();
+        let state = TestState::from_pool(pool).await.unwrap();
+        let mut repo = state.repository().await.unwrap();
+
+        // Provision a static client which gets JWT access tokens, and which also
+        // introspects them
+        let client_secret = "secret";
+        let encrypted_client_secret = state
+            .encrypter
+            .encrypt_to_string(client_secret.as_bytes())
+            .unwrap();
+        let client = repo
+            .oauth2_client()
+            .upsert_static(
+                Ulid::from_datetime_with_source(state.clock.now().into(), &mut state.rng()),
+                OAuthClientAuthenticationMethod::ClientSecretBasic,
+                Some(encrypted_client_secret),
+                None,
+                None,
+                vec!["https://client.com/".parse().unwrap()],
+                Vec::new(),
+                None,
+                false,
+                false,
+                None,
+                AccessTokenFormat::Jwt,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let user = repo
+            .user()
+            .add(&mut state.rng(), &state.clock, "alice".to_owned())
+            .await
+            .unwrap();
+
+        let browser_session = repo
+            .browser_session()
+            .add(&mut state.rng(), &state.clock, &user, None)
+            .await
+            .unwrap();
+
+        let session = repo
+            .oauth2_session()
+            .add_from_browser_session(
+                &mut state.rng(),
+                &state.clock,
+                &client,
+                &browser_session,
+                Scope::from_iter([OPENID]),
+            )
+            .await
+            .unwrap();
+
+        let ttl = Duration::microseconds(5 * 60 * 1000 * 1000);
+        let access_token_str = generate_access_token(
+            &mut state.rng(),
+            &state.clock,
+            &mut repo,
+            &state.url_builder,
+            &state.key_store,
+            &client,
+            &session,
+            ttl,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        let (AccessToken { access_token, .. }, _) = generate_token_pair(
+            &mut state.rng(),
+            &state.clock,
+            &mut repo,
+            &session,
+            access_token_str,
+            ttl,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        repo.save().await.unwrap();
+
+        // The token is an RFC 9068 JWT
+        let jwt: Jwt<'_, HashMap<String, serde_json::Value>> =
+            Jwt::try_from(access_token.as_str()).unwrap();
+        assert_eq!(jwt.header().typ(), Some("at+jwt"));
+        let claims = jwt.payload();
+        assert_eq!(claims["sub"], json!(user.sub));
+        assert_eq!(claims["client_id"], json!(client.client_id));
+        assert_eq!(claims["scope"], json!("openid"));
+        assert_eq!(claims["sid"], json!(browser_session.id.to_string()));
+        assert_eq!(
+            claims["aud"],
+            json!(state.url_builder.oidc_issuer().to_string())
+        );
+
+        // It can still be introspected
+        let request = Request::post(OAuth2Introspection::PATH)
+            .basic_auth(&client.client_id, client_secret)
+            .form(json!({ "token": access_token, "token_type_hint": "access_token" }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let response: IntrospectionResponse = response.json();
+        assert!(response.active);
+        assert_eq!(response.username, Some("alice".to_owned()));
+        assert_eq!(response.token_type, Some(OAuthTokenTypeHint::AccessToken));
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_introspect_jwt_response(pool: PgPool) {
+        setup();
+        let state = TestState::from_pool(pool).await.unwrap();
+        let mut repo = state.repository().await.unwrap();
+
+        let client_secret = "secret";
+        let encrypted_client_secret = state
+            .encrypter
+            .encrypt_to_string(client_secret.as_bytes())
+            .unwrap();
+        let client = repo
+            .oauth2_client()
+            .upsert_static(
+                Ulid::from_datetime_with_source(state.clock.now().into(), &mut state.rng()),
+                OAuthClientAuthenticationMethod::ClientSecretBasic,
+                Some(encrypted_client_secret),
+                None,
+                None,
+                Vec::new(),
+                Vec::new(),
+                None,
+                false,
+                false,
+                None,
+                AccessTokenFormat::Opaque,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let session = repo
+            .oauth2_session()
+            .add_from_client_credentials(
+                &mut state.rng(),
+                &state.clock,
+                &client,
+                Scope::from_iter([OPENID]),
+            )
+            .await
+            .unwrap();
+
+        let AccessToken { access_token, .. } = repo
+            .oauth2_access_token()
+            .add(
+                &mut state.rng(),
+                &state.clock,
+                &session,
+                TokenType::AccessToken.generate(&mut state.rng()),
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        repo.save().await.unwrap();
+
+        let introspect_as_jwt = |token: &str| {
+            Request::post(OAuth2Introspection::PATH)
+                .header(ACCEPT, "application/token-introspection+jwt")
+                .basic_auth(&client.client_id, client_secret)
+                .form(json!({ "token": token }))
+        };
+
+        let response = state.request(introspect_as_jwt(&access_token)).await;
+        response.assert_status(StatusCode::OK);
+        response.assert_header_value(CONTENT_TYPE, "application/token-introspection+jwt");
+
+        let jwt: Jwt<'_, serde_json::Value> = Jwt::try_from(response.body().as_str()).unwrap();
+        jwt.verify_with_jwks(&state.key_store.public_jwks())
+            .unwrap();
+        assert_eq!(jwt.header().typ(), Some("token-introspection+jwt"));
+        let claims = jwt.payload();
+        assert_eq!(
+            claims["iss"],
+            json!(state.url_builder.oidc_issuer().to_string())
+        );
+        assert_eq!(claims["aud"], json!(client.client_id));
+        assert_eq!(claims["token_introspection"]["active"], json!(true));
+        assert_eq!(
+            claims["token_introspection"]["client_id"],
+            json!(client.client_id)
+        );
+
+        // Inactive tokens also get a signed response
+        let response = state.request(introspect_as_jwt("mat_unknown")).await;
+        response.assert_status(StatusCode::OK);
+        response.assert_header_value(CONTENT_TYPE, "application/token-introspection+jwt");
+
+        let jwt: Jwt<'_, serde_json::Value> = Jwt::try_from(response.body().as_str()).unwrap();
+        assert_eq!(
+            jwt.payload()["token_introspection"],
+            json!({ "active": false })
+        );
+    }
 }
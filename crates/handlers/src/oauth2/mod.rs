@@ -8,8 +8,8 @@ use std::collections::HashMap;
 
 use chrono::Duration;
 use mas_data_model::{
-    AccessToken, Authentication, AuthorizationGrant, BrowserSession, Client, RefreshToken, Session,
-    TokenType,
+    AccessToken, AccessTokenFormat, Authentication, AuthenticationMethod, AuthorizationGrant,
+    BrowserSession, Client, RefreshToken, Session, TokenType, User,
 };
 use mas_iana::jose::JsonWebSignatureAlg;
 use mas_jose::{
@@ -19,30 +19,39 @@ use mas_jose::{
 };
 use mas_keystore::Keystore;
 use mas_router::UrlBuilder;
-use mas_storage::{Clock, RepositoryAccess};
+use mas_storage::{BoxRepository, Clock, RepositoryAccess};
 use thiserror::Error;
+use ulid::Ulid;
 
 pub mod authorization;
 pub mod consent;
 pub mod device;
 pub mod discovery;
+mod encryption;
+pub mod end_session;
 pub mod introspection;
 pub mod keys;
+pub mod par;
+mod profile;
 pub mod registration;
 pub mod revoke;
+mod software_statement;
 pub mod token;
 pub mod userinfo;
 pub mod webfinger;
 
 #[derive(Debug, Error)]
 #[error(transparent)]
-pub(crate) enum IdTokenSignatureError {
+pub(crate) enum TokenSignatureError {
     #[error("The signing key is invalid")]
     InvalidSigningKey,
     Claim(#[from] mas_jose::claims::ClaimError),
     JwtSignature(#[from] mas_jose::jwt::JwtSignatureError),
     WrongAlgorithm(#[from] mas_keystore::WrongAlgorithmError),
     TokenHash(#[from] mas_jose::claims::TokenHashError),
+    Repository(#[from] mas_storage::RepositoryError),
+    #[error("The user of the session could not be found")]
+    NoSuchUser,
 }
 
 pub(crate) fn generate_id_token(
@@ -53,16 +62,19 @@ pub(crate) fn generate_id_token(
     client: &Client,
     grant: Option<&AuthorizationGrant>,
     browser_session: &BrowserSession,
+    subject: &str,
     access_token: Option<&AccessToken>,
     last_authentication: Option<&Authentication>,
-) -> Result<String, IdTokenSignatureError> {
-    let mut claims = HashMap::new();
+    user_claims: HashMap<String, serde_json::Value>,
+) -> Result<String, TokenSignatureError> {
+    let mut claims = user_claims;
     let now = clock.now();
     claims::ISS.insert(&mut claims, url_builder.oidc_issuer().to_string())?;
-    claims::SUB.insert(&mut claims, &browser_session.user.sub)?;
+    claims::SUB.insert(&mut claims, subject)?;
     claims::AUD.insert(&mut claims, client.client_id.clone())?;
     claims::IAT.insert(&mut claims, now)?;
     claims::EXP.insert(&mut claims, now + Duration::try_hours(1).unwrap())?;
+    claims::SID.insert(&mut claims, browser_session.id.to_string())?;
 
     if let Some(nonce) = grant.and_then(|grant| grant.nonce.as_ref()) {
         claims::NONCE.insert(&mut claims, nonce)?;
@@ -78,7 +90,7 @@ pub(crate) fn generate_id_token(
         .unwrap_or(JsonWebSignatureAlg::Rs256);
     let key = key_store
         .signing_key_for_algorithm(&alg)
-        .ok_or(IdTokenSignatureError::InvalidSigningKey)?;
+        .ok_or(TokenSignatureError::InvalidSigningKey)?;
 
     if let Some(access_token) = access_token {
         claims::AT_HASH.insert(&mut claims, hash_token(&alg, &access_token.access_token)?)?;
@@ -90,25 +102,187 @@ pub(crate) fn generate_id_token(
 
     let signer = key.params().signing_key_for_alg(&alg)?;
     let header = JsonWebSignatureHeader::new(alg)
-        .with_kid(key.kid().ok_or(IdTokenSignatureError::InvalidSigningKey)?);
+        .with_kid(key.kid().ok_or(TokenSignatureError::InvalidSigningKey)?);
     let id_token = Jwt::sign_with_rng(rng, header, claims, &signer)?;
 
     Ok(id_token.into_string())
 }
 
+/// Generate a new access token for a session, in the format the client is
+/// configured for.
+///
+/// JWT access tokens follow RFC 9068. Their `sub` is the same subject
+/// identifier the client gets in ID tokens, or the `client_id` for sessions
+/// without a user.
+pub(crate) async fn generate_access_token(
+    rng: &mut (impl rand::RngCore + rand::CryptoRng + Send),
+    clock: &impl Clock,
+    repo: &mut BoxRepository,
+    url_builder: &UrlBuilder,
+    key_store: &Keystore,
+    client: &Client,
+    session: &Session,
+    ttl: Duration,
+    dpop_jkt: Option<&str>,
+    x5t_s256: Option<&str>,
+) -> Result<String, TokenSignatureError> {
+    if client.access_token_format == AccessTokenFormat::Opaque {
+        return Ok(TokenType::AccessToken.generate(rng));
+    }
+
+    let subject = if let Some(user_id) = session.user_id {
+        let user = repo
+            .user()
+            .lookup(user_id)
+            .await?
+            .ok_or(TokenSignatureError::NoSuchUser)?;
+        subject_for_client(rng, clock, repo, client, &user).await?
+    } else {
+        client.client_id.clone()
+    };
+
+    let issuer = url_builder.oidc_issuer().to_string();
+    let now = clock.now();
+    let mut claims = HashMap::new();
+    claims::ISS.insert(&mut claims, issuer.clone())?;
+    claims::SUB.insert(&mut claims, subject)?;
+    let mut audiences = session.audiences();
+    if audiences.is_empty() {
+        audiences.push(issuer);
+    }
+    claims::AUD.insert(&mut claims, audiences)?;
+    claims::IAT.insert(&mut claims, now)?;
+    claims::EXP.insert(&mut claims, now + ttl)?;
+    claims::JTI.insert(
+        &mut claims,
+        Ulid::from_datetime_with_source(now.into(), rng).to_string(),
+    )?;
+    claims::CLIENT_ID.insert(&mut claims, client.client_id.clone())?;
+    claims::SCOPE.insert(&mut claims, session.scope.to_string())?;
+
+    if let Some(user_session_id) = session.user_session_id {
+        claims::SID.insert(&mut claims, user_session_id.to_string())?;
+    }
+
+    let mut cnf = serde_json::Map::new();
+    if let Some(jkt) = dpop_jkt {
+        cnf.insert("jkt".to_owned(), jkt.into());
+    }
+    if let Some(x5t_s256) = x5t_s256 {
+        cnf.insert("x5t#S256".to_owned(), x5t_s256.into());
+    }
+    if !cnf.is_empty() {
+        claims::CNF.insert(&mut claims, serde_json::Value::Object(cnf))?;
+    }
+
+    // Access tokens are signed with the same algorithm as the ID tokens if the
+    // client configured one. Otherwise, we prefer RS256, but fall back to any
+    // algorithm we have a key for.
+    let alg = client
+        .id_token_signed_response_alg
+        .clone()
+        .unwrap_or_else(|| {
+            let available = key_store.available_signing_algorithms();
+            if available.contains(&JsonWebSignatureAlg::Rs256) {
+                JsonWebSignatureAlg::Rs256
+            } else {
+                available
+                    .into_iter()
+                    .next()
+                    .unwrap_or(JsonWebSignatureAlg::Rs256)
+            }
+        });
+    let key = key_store
+        .signing_key_for_algorithm(&alg)
+        .ok_or(TokenSignatureError::InvalidSigningKey)?;
+
+    let signer = key.params().signing_key_for_alg(&alg)?;
+    let header = JsonWebSignatureHeader::new(alg)
+        .with_typ("at+jwt".to_owned())
+        .with_kid(key.kid().ok_or(TokenSignatureError::InvalidSigningKey)?);
+    let access_token = Jwt::sign_with_rng(rng, header, claims, &signer)?;
+
+    Ok(access_token.into_string())
+}
+
+/// Get the `sub` value to give out to a client for a user, which is a
+/// pairwise identifier if the client asked for one
+pub(crate) async fn subject_for_client<R: RepositoryAccess>(
+    rng: &mut (impl rand::RngCore + Send),
+    clock: &impl Clock,
+    repo: &mut R,
+    client: &Client,
+    user: &User,
+) -> Result<String, R::Error> {
+    match client.sector_identifier() {
+        Some(sector_identifier) => {
+            repo.oauth2_pairwise_subject()
+                .find_or_add(rng, clock, sector_identifier, user)
+                .await
+        }
+        None => Ok(user.sub.clone()),
+    }
+}
+
+/// List the methods used to authenticate a browser session, as given to the
+/// authorization grant policy
+pub(crate) async fn authentication_methods<R: RepositoryAccess>(
+    repo: &mut R,
+    browser_session: &BrowserSession,
+) -> Result<Vec<mas_policy::AuthenticationMethod>, R::Error> {
+    let authentications = repo
+        .browser_session()
+        .list_authentications(browser_session)
+        .await?;
+
+    let mut methods = Vec::new();
+    for authentication in authentications {
+        let method = match authentication.authentication_method {
+            AuthenticationMethod::Password { .. } => mas_policy::AuthenticationMethod::Password,
+            AuthenticationMethod::UpstreamOAuth2 { .. } => {
+                mas_policy::AuthenticationMethod::UpstreamOAuth2
+            }
+            AuthenticationMethod::Totp { .. } => mas_policy::AuthenticationMethod::Totp,
+            AuthenticationMethod::Passkey { .. } => mas_policy::AuthenticationMethod::Passkey,
+            AuthenticationMethod::RecoveryCode { .. } => {
+                mas_policy::AuthenticationMethod::RecoveryCode
+            }
+            AuthenticationMethod::EmailCode { .. } => mas_policy::AuthenticationMethod::EmailCode,
+            AuthenticationMethod::Ldap { .. } => mas_policy::AuthenticationMethod::Ldap,
+            AuthenticationMethod::Unknown => continue,
+        };
+
+        if !methods.contains(&method) {
+            methods.push(method);
+        }
+    }
+
+    Ok(methods)
+}
+
 pub(crate) async fn generate_token_pair<R: RepositoryAccess>(
     rng: &mut (impl rand::RngCore + Send),
     clock: &impl Clock,
     repo: &mut R,
     session: &Session,
+    access_token_str: String,
     ttl: Duration,
+    dpop_jkt: Option<String>,
+    x5t_s256: Option<String>,
 ) -> Result<(AccessToken, RefreshToken), R::Error> {
-    let access_token_str = TokenType::AccessToken.generate(rng);
     let refresh_token_str = TokenType::RefreshToken.generate(rng);
 
     let access_token = repo
         .oauth2_access_token()
-        .add(rng, clock, session, access_token_str, Some(ttl))
+        .add(
+            rng,
+            clock,
+            session,
+            access_token_str,
+            Some(ttl),
+            dpop_jkt,
+            x5t_s256,
+        )
         .await?;
 
     let refresh_token = repo
@@ -118,3 +292,148 @@ pub(crate) async fn generate_token_pair<R: RepositoryAccess>(
 
     Ok((access_token, refresh_token))
 }
+
+#[cfg(test)]
+mod tests {
+    use mas_data_model::SessionState;
+    use mas_keystore::{JsonWebKey, JsonWebKeySet, PrivateKey};
+    use mas_storage::user::UserRepository;
+    use oauth2_types::{
+        oidc::SubjectType,
+        scope::{OPENID, Scope},
+    };
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::test_utils::{TestState, setup};
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_jwt_access_token(pool: PgPool) {
+        setup();
+        let state = TestState::from_pool(pool).await.unwrap();
+        let mut repo = state.repository().await.unwrap();
+
+        let user = repo
+            .user()
+            .add(&mut state.rng(), &state.clock, "alice".to_owned())
+            .await
+            .unwrap();
+
+        // A keystore without any RSA key
+        let key = PrivateKey::generate_ec_p256(state.rng());
+        let key_store = Keystore::new(JsonWebKeySet::new(vec![
+            JsonWebKey::new(key).with_kid("ec"),
+        ]));
+
+        let mut client = Client::samples(state.clock.now(), &mut state.rng()).remove(0);
+        client.access_token_format = AccessTokenFormat::Jwt;
+        client.id_token_signed_response_alg = None;
+
+        let mut session = Session {
+            id: Ulid::from_datetime_with_source(state.clock.now().into(), &mut state.rng()),
+            state: SessionState::Valid,
+            created_at: state.clock.now(),
+            user_id: Some(user.id),
+            user_session_id: None,
+            client_id: client.id,
+            scope: Scope::from_iter([OPENID]),
+            user_agent: None,
+            last_active_at: None,
+            last_active_ip: None,
+            parent_session_id: None,
+            actor_client_id: None,
+            audience: None,
+            resources: Vec::new(),
+        };
+
+        let decode = |access_token: &str| {
+            let jwt: Jwt<'static, HashMap<String, serde_json::Value>> =
+                Jwt::try_from(access_token.to_owned()).unwrap();
+            jwt.verify_with_jwks(&key_store.public_jwks()).unwrap();
+            jwt
+        };
+
+        // Without a configured algorithm, we use the one we have a key for
+        let access_token = generate_access_token(
+            &mut state.rng(),
+            &state.clock,
+            &mut repo,
+            &state.url_builder,
+            &key_store,
+            &client,
+            &session,
+            Duration::minutes(5),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let jwt = decode(&access_token);
+        assert_eq!(jwt.header().alg(), &JsonWebSignatureAlg::Es256);
+        assert_eq!(jwt.header().kid(), Some("ec"));
+        assert_eq!(jwt.payload()["sub"], user.sub);
+
+        // Clients asking for pairwise subject identifiers get the same one as in
+        // ID tokens
+        client.subject_type = Some(SubjectType::Pairwise);
+        let subject = subject_for_client(&mut state.rng(), &state.clock, &mut repo, &client, &user)
+            .await
+            .unwrap();
+        assert_ne!(subject, user.sub);
+
+        let access_token = generate_access_token(
+            &mut state.rng(),
+            &state.clock,
+            &mut repo,
+            &state.url_builder,
+            &key_store,
+            &client,
+            &session,
+            Duration::minutes(5),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(decode(&access_token).payload()["sub"], subject);
+
+        // Sessions without a user get the client ID as subject
+        session.user_id = None;
+        let access_token = generate_access_token(
+            &mut state.rng(),
+            &state.clock,
+            &mut repo,
+            &state.url_builder,
+            &key_store,
+            &client,
+            &session,
+            Duration::minutes(5),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(decode(&access_token).payload()["sub"], client.client_id);
+
+        // A configured algorithm we don't have a key for is an error
+        client.id_token_signed_response_alg = Some(JsonWebSignatureAlg::Rs256);
+        let result = generate_access_token(
+            &mut state.rng(),
+            &state.clock,
+            &mut repo,
+            &state.url_builder,
+            &key_store,
+            &client,
+            &session,
+            Duration::minutes(5),
+            None,
+            None,
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(TokenSignatureError::InvalidSigningKey)
+        ));
+    }
+}
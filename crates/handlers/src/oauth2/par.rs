@@ -0,0 +1,280 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+//! Handler for the [Pushed Authorization Request] endpoint
+//!
+//! [Pushed Authorization Request]: https://www.rfc-editor.org/rfc/rfc9126.html
+
+use std::collections::BTreeMap;
+
+use axum::{Json, extract::State, response::IntoResponse};
+use axum_extra::typed_header::TypedHeader;
+use chrono::Duration;
+use headers::{CacheControl, Pragma};
+use hyper::StatusCode;
+use mas_axum_utils::{
+    client_authorization::{ClientAuthorization, CredentialsVerificationError},
+    sentry::SentryEventID,
+};
+use mas_data_model::PUSHED_AUTHORIZATION_REQUEST_URI_PREFIX;
+use mas_keystore::Encrypter;
+use mas_storage::{
+    BoxClock, BoxRepository, BoxRng, oauth2::OAuth2PushedAuthorizationRequestRepository,
+};
+use oauth2_types::{
+    errors::{ClientError, ClientErrorCode},
+    requests::{AuthorizationRequest, GrantType, PushedAuthorizationResponse},
+};
+use rand::distributions::{Alphanumeric, DistString};
+use serde::{Deserialize, de::value::MapDeserializer};
+use thiserror::Error;
+
+use crate::impl_from_error_for_route;
+
+/// How long the `request_uri` returned by the endpoint is valid for
+const EXPIRES_IN: Duration = Duration::seconds(60);
+
+/// The pushed parameters, parsed the same way as the authorization endpoint
+/// does
+#[derive(Deserialize)]
+struct Params {
+    #[serde(flatten)]
+    auth: AuthorizationRequest,
+}
+
+#[derive(Debug, Error)]
+pub(crate) enum RouteError {
+    #[error(transparent)]
+    Internal(Box<dyn std::error::Error + Send + Sync + 'static>),
+
+    #[error("client not found")]
+    ClientNotFound,
+
+    #[error("client not allowed")]
+    ClientNotAllowed,
+
+    #[error("could not verify client credentials")]
+    ClientCredentialsVerification(#[from] CredentialsVerificationError),
+
+    #[error("invalid authorization request")]
+    InvalidRequest(#[source] serde::de::value::Error),
+
+    #[error("the request_uri parameter is not allowed in pushed authorization requests")]
+    RequestUriNotAllowed,
+
+    #[error("invalid redirect uri")]
+    InvalidRedirectUri(#[from] mas_data_model::InvalidRedirectUriError),
+}
+
+impl_from_error_for_route!(mas_storage::RepositoryError);
+
+impl IntoResponse for RouteError {
+    fn into_response(self) -> axum::response::Response {
+        let event_id = sentry::capture_error(&self);
+
+        let response = match self {
+            Self::Internal(_) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ClientError::from(ClientErrorCode::ServerError)),
+            ),
+            Self::ClientNotFound | Self::ClientCredentialsVerification(_) => (
+                StatusCode::UNAUTHORIZED,
+                Json(ClientError::from(ClientErrorCode::InvalidClient)),
+            ),
+            Self::ClientNotAllowed => (
+                StatusCode::UNAUTHORIZED,
+                Json(ClientError::from(ClientErrorCode::UnauthorizedClient)),
+            ),
+            Self::InvalidRequest(ref e) => (
+                StatusCode::BAD_REQUEST,
+                Json(
+                    ClientError::from(ClientErrorCode::InvalidRequest)
+                        .with_description(e.to_string()),
+                ),
+            ),
+            Self::RequestUriNotAllowed => (
+                StatusCode::BAD_REQUEST,
+                Json(
+                    ClientError::from(ClientErrorCode::InvalidRequest).with_description(
+                        "The request_uri parameter is not allowed in pushed authorization requests"
+                            .to_owned(),
+                    ),
+                ),
+            ),
+            Self::InvalidRedirectUri(ref e) => (
+                StatusCode::BAD_REQUEST,
+                Json(
+                    ClientError::from(ClientErrorCode::InvalidRequest)
+                        .with_description(format!("Invalid redirect URI ({e})")),
+                ),
+            ),
+        };
+
+        (SentryEventID::from(event_id), response).into_response()
+    }
+}
+
+#[tracing::instrument(
+    name = "handlers.oauth2.par.post",
+    fields(client.id = client_authorization.client_id()),
+    skip_all,
+    err,
+)]
+pub(crate) async fn post(
+    mut rng: BoxRng,
+    clock: BoxClock,
+    mut repo: BoxRepository,
+    State(http_client): State<reqwest::Client>,
+    State(encrypter): State<Encrypter>,
+    client_authorization: ClientAuthorization<BTreeMap<String, String>>,
+) -> Result<impl IntoResponse, RouteError> {
+    let client = client_authorization
+        .credentials
+        .fetch(&mut repo)
+        .await?
+        .ok_or(RouteError::ClientNotFound)?;
+
+    // Reuse the token endpoint auth method to verify the client
+    let method = client
+        .token_endpoint_auth_method
+        .as_ref()
+        .ok_or(RouteError::ClientNotAllowed)?;
+
+    client_authorization
+        .credentials
+        .verify(&http_client, &encrypter, method, &client)
+        .await?;
+
+    if !client.grant_types.contains(&GrantType::AuthorizationCode) {
+        return Err(RouteError::ClientNotAllowed);
+    }
+
+    // The client credentials are consumed by the client authorization
+    // extractor, so we add back the client_id to the pushed parameters, making
+    // them a standalone authorization request
+    let mut parameters = client_authorization.form.unwrap_or_default();
+    parameters.insert("client_id".to_owned(), client.client_id.clone());
+
+    // Pushed authorization requests can't themselves reference another request
+    if parameters.contains_key("request_uri") {
+        return Err(RouteError::RequestUriNotAllowed);
+    }
+
+    // Validate the request early, so that the client gets the errors directly
+    // instead of through the redirect
+    let params = Params::deserialize(MapDeserializer::new(parameters.clone().into_iter()))
+        .map_err(RouteError::InvalidRequest)?;
+    client.resolve_redirect_uri(&params.auth.redirect_uri)?;
+
+    let request_uri = format!(
+        "{PUSHED_AUTHORIZATION_REQUEST_URI_PREFIX}{}",
+        Alphanumeric.sample_string(&mut rng, 32)
+    );
+
+    let pushed_authorization_request = repo
+        .oauth2_pushed_authorization_request()
+        .add(
+            &mut rng,
+            &clock,
+            &client,
+            request_uri,
+            parameters.into_iter().collect(),
+            EXPIRES_IN,
+        )
+        .await?;
+
+    repo.save().await?;
+
+    let response = PushedAuthorizationResponse {
+        request_uri: pushed_authorization_request.request_uri,
+        expires_in: EXPIRES_IN,
+    };
+
+    Ok((
+        StatusCode::CREATED,
+        TypedHeader(CacheControl::new().with_no_store()),
+        TypedHeader(Pragma::no_cache()),
+        Json(response),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::{Request, StatusCode};
+    use mas_router::SimpleRoute;
+    use oauth2_types::{
+        errors::{ClientError, ClientErrorCode},
+        registration::ClientRegistrationResponse,
+        requests::PushedAuthorizationResponse,
+    };
+    use sqlx::PgPool;
+
+    use crate::test_utils::{RequestBuilderExt, ResponseExt, TestState, setup};
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_pushed_authorization_request(pool: PgPool) {
+        setup();
+        let state = TestState::from_pool(pool).await.unwrap();
+
+        // Provision a client
+        let request =
+            Request::post(mas_router::OAuth2RegistrationEndpoint::PATH).json(serde_json::json!({
+                "client_uri": "https://example.com/",
+                "redirect_uris": ["https://example.com/callback"],
+                "token_endpoint_auth_method": "none",
+                "grant_types": ["authorization_code"],
+                "response_types": ["code"],
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CREATED);
+
+        let response: ClientRegistrationResponse = response.json();
+        let client_id = response.client_id;
+
+        // Push a valid authorization request
+        let request = Request::post(mas_router::OAuth2PushedAuthorizationRequestEndpoint::PATH)
+            .form(serde_json::json!({
+                "client_id": client_id,
+                "response_type": "code",
+                "redirect_uri": "https://example.com/callback",
+                "scope": "openid",
+            }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CREATED);
+
+        let response: PushedAuthorizationResponse = response.json();
+        assert!(
+            response
+                .request_uri
+                .starts_with("urn:ietf:params:oauth:request_uri:")
+        );
+        assert_eq!(response.expires_in.num_seconds(), 60);
+
+        // Pushing a request with an unknown redirect URI should fail
+        let request = Request::post(mas_router::OAuth2PushedAuthorizationRequestEndpoint::PATH)
+            .form(serde_json::json!({
+                "client_id": client_id,
+                "response_type": "code",
+                "redirect_uri": "https://example.com/other",
+                "scope": "openid",
+            }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        let response: ClientError = response.json();
+        assert_eq!(response.error, ClientErrorCode::InvalidRequest);
+
+        // Pushing a request which itself has a request_uri should fail
+        let request = Request::post(mas_router::OAuth2PushedAuthorizationRequestEndpoint::PATH)
+            .form(serde_json::json!({
+                "client_id": client_id,
+                "request_uri": "urn:ietf:params:oauth:request_uri:foo",
+            }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        let response: ClientError = response.json();
+        assert_eq!(response.error, ClientErrorCode::InvalidRequest);
+    }
+}
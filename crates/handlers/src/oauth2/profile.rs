@@ -0,0 +1,157 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+//! Resolution of the user claims released to clients, either through the
+//! `profile` scope or individually through the [`claims` request parameter]
+//!
+//! [`claims` request parameter]: https://openid.net/specs/openid-connect-core-1_0.html#ClaimsParameter
+
+use std::collections::HashMap;
+
+use mas_data_model::User;
+use mas_matrix::HomeserverConnection;
+use oauth2_types::{
+    requests::ClaimsRequest,
+    scope::{PROFILE, Scope},
+};
+use serde_json::Value;
+
+/// The claims released with the `profile` scope.
+pub(crate) const PROFILE_CLAIMS: [&str; 5] = [
+    "name",
+    "preferred_username",
+    "picture",
+    "locale",
+    "updated_at",
+];
+
+/// The claims to return from the `UserInfo` endpoint
+///
+/// This includes all the profile claims if the `profile` scope was granted, as
+/// well as the ones individually requested for the `UserInfo` endpoint.
+pub(crate) fn userinfo_claims(scope: &Scope, claims: Option<&ClaimsRequest>) -> Vec<&'static str> {
+    PROFILE_CLAIMS
+        .into_iter()
+        .filter(|claim| {
+            scope.contains(&PROFILE)
+                || claims.is_some_and(|claims| claims.userinfo_claims().any(|c| c == *claim))
+        })
+        .collect()
+}
+
+/// The claims to return in the ID Token
+///
+/// Because an access token is always issued alongside the ID Token, the
+/// profile claims are only included if they were individually requested for
+/// the ID Token.
+pub(crate) fn id_token_claims(claims: Option<&ClaimsRequest>) -> Vec<&'static str> {
+    PROFILE_CLAIMS
+        .into_iter()
+        .filter(|claim| claims.is_some_and(|claims| claims.id_token_claims().any(|c| c == *claim)))
+        .collect()
+}
+
+/// Get the values of the given claims for a user
+///
+/// The display name and avatar are fetched from the homeserver if needed. If
+/// this fails, those claims are omitted rather than failing the whole request.
+pub(crate) async fn user_claims(
+    homeserver: &dyn HomeserverConnection,
+    user: &User,
+    claims: &[&str],
+) -> HashMap<String, Value> {
+    let mut values = HashMap::new();
+
+    let needs_profile = claims.iter().any(|c| matches!(*c, "name" | "picture"));
+    let matrix_user = if needs_profile {
+        let mxid = homeserver.mxid(&user.username);
+        match homeserver.query_user(&mxid).await {
+            Ok(matrix_user) => Some(matrix_user),
+            Err(err) => {
+                tracing::warn!(
+                    error = &*err as &dyn std::error::Error,
+                    "Failed to query the user profile on the homeserver"
+                );
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    for claim in claims {
+        let value = match *claim {
+            "name" => matrix_user
+                .as_ref()
+                .and_then(|u| u.displayname.clone())
+                .map(Value::String),
+            "picture" => matrix_user
+                .as_ref()
+                .and_then(|u| u.avatar_url.clone())
+                .map(Value::String),
+            "preferred_username" => Some(Value::String(user.username.clone())),
+            "locale" => user.locale.clone().map(Value::String),
+            // Changes made directly on the homeserver aren't tracked, so this is
+            // the last time the profile was changed through us
+            "updated_at" => Some(Value::from(user.profile_updated_at().timestamp())),
+            _ => None,
+        };
+
+        if let Some(value) = value {
+            values.insert((*claim).to_owned(), value);
+        }
+    }
+
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use oauth2_types::scope::OPENID;
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    use super::*;
+
+    #[test]
+    fn test_granted_claims() {
+        let openid = Scope::from_iter([OPENID]);
+        let profile = Scope::from_iter([OPENID, PROFILE]);
+        let claims: ClaimsRequest = serde_json::from_value(serde_json::json!({
+            "userinfo": { "name": null, "email": null },
+            "id_token": { "preferred_username": { "essential": true } },
+        }))
+        .unwrap();
+
+        // Without the profile scope, only the individually requested claims
+        assert!(userinfo_claims(&openid, None).is_empty());
+        assert_eq!(userinfo_claims(&openid, Some(&claims)), ["name"]);
+
+        // With the profile scope, all the profile claims
+        assert_eq!(userinfo_claims(&profile, None), PROFILE_CLAIMS);
+        assert_eq!(userinfo_claims(&profile, Some(&claims)), PROFILE_CLAIMS);
+
+        // The ID Token only gets the claims requested for it
+        assert!(id_token_claims(None).is_empty());
+        assert_eq!(id_token_claims(Some(&claims)), ["preferred_username"]);
+    }
+
+    #[tokio::test]
+    async fn test_updated_at() {
+        let homeserver = mas_matrix::MockHomeserverConnection::new("example.com");
+        let now = chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let mut rng = ChaChaRng::seed_from_u64(42);
+        let mut user = User::samples(now, &mut rng).remove(0);
+
+        // Falls back to the creation time of the user
+        let claims = user_claims(&homeserver, &user, &["updated_at"]).await;
+        assert_eq!(claims["updated_at"], serde_json::json!(1_700_000_000));
+
+        // Reflects the last recorded profile change
+        user.profile_updated_at = Some(now + chrono::Duration::try_hours(1).unwrap());
+        let claims = user_claims(&homeserver, &user, &["updated_at"]).await;
+        assert_eq!(claims["updated_at"], serde_json::json!(1_700_003_600));
+    }
+}
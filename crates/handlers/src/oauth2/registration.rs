@@ -4,18 +4,31 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 // Please see LICENSE in the repository root for full details.
 
-use std::sync::LazyLock;
+use std::{collections::HashMap, sync::LazyLock};
 
-use axum::{Json, extract::State, response::IntoResponse};
+use axum::{
+    Json,
+    extract::{Path, State},
+    response::IntoResponse,
+};
 use axum_extra::TypedHeader;
-use hyper::StatusCode;
+use headers::{Authorization, authorization::Bearer};
+use hyper::{StatusCode, header::WWW_AUTHENTICATE};
 use mas_axum_utils::sentry::SentryEventID;
-use mas_iana::oauth::OAuthClientAuthenticationMethod;
+use mas_data_model::{Client, ClientRegistrationConfig, InitialAccessToken, SiteConfig, TokenType};
+use mas_http::RequestBuilderExt as _;
+use mas_iana::{jose::JsonWebEncryptionAlg, oauth::OAuthClientAuthenticationMethod};
+use mas_jose::jwa::{SUPPORTED_ENCRYPTION_ALGORITHMS, SUPPORTED_ENCRYPTION_ENCODINGS};
 use mas_keystore::Encrypter;
 use mas_policy::{Policy, Violation};
-use mas_storage::{BoxClock, BoxRepository, BoxRng, oauth2::OAuth2ClientRepository};
+use mas_router::UrlBuilder;
+use mas_storage::{
+    BoxClock, BoxRepository, BoxRng, Clock,
+    oauth2::{OAuth2ClientRepository, OAuth2InitialAccessTokenRepository},
+};
 use oauth2_types::{
     errors::{ClientError, ClientErrorCode},
+    oidc::SubjectType,
     registration::{
         ClientMetadata, ClientMetadataVerificationError, ClientRegistrationResponse, Localized,
         VerifiedClientMetadata,
@@ -25,11 +38,16 @@ use opentelemetry::{Key, KeyValue, metrics::Counter};
 use psl::Psl;
 use rand::distributions::{Alphanumeric, DistString};
 use serde::Serialize;
+use serde_json::Value;
 use sha2::Digest as _;
 use thiserror::Error;
 use tracing::info;
+use ulid::Ulid;
 use url::Url;
 
+use super::software_statement::{
+    SoftwareStatementError, apply_software_statement, verify_software_statement,
+};
 use crate::{BoundActivityTracker, METER, impl_from_error_for_route};
 
 static REGISTRATION_COUNTER: LazyLock<Counter<u64>> = LazyLock::new(|| {
@@ -55,8 +73,38 @@ pub(crate) enum RouteError {
     #[error("{0} is a public suffix, not a valid domain")]
     UrlIsPublicSuffix(&'static str),
 
+    #[error("subject_type is not supported")]
+    UnsupportedSubjectType,
+
+    #[error("could not fetch the sector_identifier_uri")]
+    SectorIdentifierFetch(#[source] reqwest::Error),
+
+    #[error("redirect_uri is not listed in the sector_identifier_uri")]
+    RedirectUriNotInSector,
+
+    #[error("the {0} encryption algorithms are not supported")]
+    UnsupportedEncryption(&'static str),
+
+    #[error("{0} encryption requires the client to have keys suitable for the algorithm")]
+    MissingEncryptionKey(&'static str),
+
     #[error("denied by the policy: {0:?}")]
     PolicyDenied(Vec<Violation>),
+
+    #[error("invalid registration access token")]
+    InvalidRegistrationAccessToken,
+
+    #[error("an initial access token is required")]
+    MissingInitialAccessToken,
+
+    #[error("invalid initial access token")]
+    InvalidInitialAccessToken,
+
+    #[error("a software statement is required")]
+    MissingSoftwareStatement,
+
+    #[error(transparent)]
+    SoftwareStatement(#[from] SoftwareStatementError),
 }
 
 impl_from_error_for_route!(mas_storage::RepositoryError);
@@ -64,15 +112,18 @@ impl_from_error_for_route!(mas_policy::LoadError);
 impl_from_error_for_route!(mas_policy::EvaluationError);
 impl_from_error_for_route!(mas_keystore::aead::Error);
 impl_from_error_for_route!(serde_json::Error);
+impl_from_error_for_route!(mas_keystore::DecryptError);
+impl_from_error_for_route!(std::string::FromUtf8Error);
 
 impl IntoResponse for RouteError {
+    #[allow(clippy::too_many_lines)]
     fn into_response(self) -> axum::response::Response {
         let event_id = sentry::capture_error(&self);
 
         REGISTRATION_COUNTER.add(1, &[KeyValue::new(RESULT, "denied")]);
 
         let response = match self {
-            Self::Internal(_) => (
+            Self::Internal(_) | Self::SoftwareStatement(SoftwareStatementError::JwksFetch(_)) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(ClientError::from(ClientErrorCode::ServerError)),
             )
@@ -140,6 +191,56 @@ impl IntoResponse for RouteError {
             )
                 .into_response(),
 
+            // Errors related to pairwise subject identifiers and to response
+            // encryption are reported as `invalid_client_metadata`, with a
+            // description of what went wrong
+            Self::UnsupportedSubjectType
+            | Self::SectorIdentifierFetch(_)
+            | Self::RedirectUriNotInSector
+            | Self::UnsupportedEncryption(_)
+            | Self::MissingEncryptionKey(_) => (
+                StatusCode::BAD_REQUEST,
+                Json(
+                    ClientError::from(ClientErrorCode::InvalidClientMetadata)
+                        .with_description(self.to_string()),
+                ),
+            )
+                .into_response(),
+
+            // RFC 7592 requires a 401 response if the registration access token
+            // is invalid, or if the client doesn't exist. Invalid initial
+            // access tokens are reported the same way, as per RFC 6750
+            Self::InvalidRegistrationAccessToken | Self::InvalidInitialAccessToken => (
+                StatusCode::UNAUTHORIZED,
+                [(WWW_AUTHENTICATE, r#"Bearer error="invalid_token""#)],
+            )
+                .into_response(),
+
+            Self::MissingInitialAccessToken => {
+                (StatusCode::UNAUTHORIZED, [(WWW_AUTHENTICATE, "Bearer")]).into_response()
+            }
+
+            // Software statements from unknown issuers are reported as
+            // `unapproved_software_statement`, and all other software statement
+            // errors as `invalid_software_statement`
+            Self::SoftwareStatement(SoftwareStatementError::UntrustedIssuer(_)) => (
+                StatusCode::BAD_REQUEST,
+                Json(
+                    ClientError::from(ClientErrorCode::UnapprovedSoftwareStatement)
+                        .with_description(self.to_string()),
+                ),
+            )
+                .into_response(),
+
+            Self::MissingSoftwareStatement | Self::SoftwareStatement(_) => (
+                StatusCode::BAD_REQUEST,
+                Json(
+                    ClientError::from(ClientErrorCode::InvalidSoftwareStatement)
+                        .with_description(self.to_string()),
+                ),
+            )
+                .into_response(),
+
             // For policy violations, we return an `invalid_client_metadata` error with the details
             // of the violations in most cases. If a violation includes `redirect_uri` in the
             // message, we return an `invalid_redirect_uri` error instead.
@@ -202,37 +303,38 @@ fn host_is_public_suffix(url: &Url) -> bool {
     false
 }
 
+/// Fetch the list of redirect URIs published at a sector identifier URI
+async fn fetch_sector_redirect_uris(
+    http_client: &reqwest::Client,
+    sector_identifier_uri: &Url,
+) -> Result<Vec<Url>, reqwest::Error> {
+    http_client
+        .get(sector_identifier_uri.as_str())
+        .send_traced()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+}
+
 /// Check if any of the URLs in the given `Localized` field is a public suffix
 fn localised_url_has_public_suffix(url: &Localized<Url>) -> bool {
     url.iter().any(|(_lang, url)| host_is_public_suffix(url))
 }
 
-#[tracing::instrument(name = "handlers.oauth2.registration.post", skip_all, err)]
-pub(crate) async fn post(
-    mut rng: BoxRng,
-    clock: BoxClock,
-    mut repo: BoxRepository,
-    mut policy: Policy,
-    activity_tracker: BoundActivityTracker,
-    user_agent: Option<TypedHeader<headers::UserAgent>>,
-    State(encrypter): State<Encrypter>,
-    body: Result<Json<ClientMetadata>, axum::extract::rejection::JsonRejection>,
-) -> Result<impl IntoResponse, RouteError> {
-    // Propagate any JSON extraction error
-    let Json(body) = body?;
-
-    // Sort the properties to ensure a stable serialisation order for hashing
-    let body = body.sorted();
-
-    // We need to serialize the body to compute the hash, and to log it
-    let body_json = serde_json::to_string(&body)?;
-
-    info!(body = body_json, "Client registration");
-
-    let user_agent = user_agent.map(|ua| ua.to_string());
-
+/// Validate the client metadata, including the checks which the `validate`
+/// method doesn't do, and evaluate the `client_registration` policy on it
+#[allow(clippy::too_many_lines)]
+async fn verify_metadata(
+    metadata: ClientMetadata,
+    software_statement: Option<&HashMap<String, Value>>,
+    policy: &mut Policy,
+    activity_tracker: &BoundActivityTracker,
+    user_agent: Option<String>,
+    http_client: &reqwest::Client,
+) -> Result<VerifiedClientMetadata, RouteError> {
     // Validate the body
-    let metadata = body.validate()?;
+    let metadata = metadata.validate()?;
 
     // Some extra validation that is hard to do in OPA and not done by the
     // `validate` method either
@@ -272,9 +374,79 @@ pub(crate) async fn post(
         }
     }
 
+    for post_logout_redirect_uri in metadata.post_logout_redirect_uris.iter().flatten() {
+        if host_is_public_suffix(post_logout_redirect_uri) {
+            return Err(RouteError::UrlIsPublicSuffix("post_logout_redirect_uri"));
+        }
+    }
+
+    if let Some(backchannel_logout_uri) = &metadata.backchannel_logout_uri {
+        if host_is_public_suffix(backchannel_logout_uri) {
+            return Err(RouteError::UrlIsPublicSuffix("backchannel_logout_uri"));
+        }
+    }
+
+    for request_uri in metadata.request_uris.iter().flatten() {
+        if host_is_public_suffix(request_uri) {
+            return Err(RouteError::UrlIsPublicSuffix("request_uri"));
+        }
+    }
+
+    if matches!(metadata.subject_type, Some(SubjectType::Unknown(_))) {
+        return Err(RouteError::UnsupportedSubjectType);
+    }
+
+    // Encrypted responses need algorithms we implement, and a key to encrypt
+    // with: the client secret for `dir`, or one of the client's keys otherwise
+    for (field, encryption) in [
+        ("id_token", metadata.id_token_encrypted_response()),
+        ("userinfo", metadata.userinfo_encrypted_response()),
+    ] {
+        let Some((alg, enc)) = encryption else {
+            continue;
+        };
+
+        if !SUPPORTED_ENCRYPTION_ALGORITHMS.contains(alg)
+            || !SUPPORTED_ENCRYPTION_ENCODINGS.contains(enc)
+        {
+            return Err(RouteError::UnsupportedEncryption(field));
+        }
+
+        let has_key = if *alg == JsonWebEncryptionAlg::Dir {
+            requires_client_secret(metadata.token_endpoint_auth_method.as_ref())
+        } else {
+            metadata.jwks.is_some() || metadata.jwks_uri.is_some()
+        };
+
+        if !has_key {
+            return Err(RouteError::MissingEncryptionKey(field));
+        }
+    }
+
+    // As per the OpenID Connect Registration spec, the sector identifier URI
+    // must point to a JSON array containing all the redirect URIs of the client
+    if let Some(sector_identifier_uri) = &metadata.sector_identifier_uri {
+        if host_is_public_suffix(sector_identifier_uri) {
+            return Err(RouteError::UrlIsPublicSuffix("sector_identifier_uri"));
+        }
+
+        let sector_redirect_uris = fetch_sector_redirect_uris(http_client, sector_identifier_uri)
+            .await
+            .map_err(RouteError::SectorIdentifierFetch)?;
+
+        if metadata
+            .redirect_uris()
+            .iter()
+            .any(|uri| !sector_redirect_uris.contains(uri))
+        {
+            return Err(RouteError::RedirectUriNotInSector);
+        }
+    }
+
     let res = policy
         .evaluate_client_registration(mas_policy::ClientRegistrationInput {
             client_metadata: &metadata,
+            software_statement,
             requester: mas_policy::Requester {
                 ip_address: activity_tracker.ip(),
                 user_agent,
@@ -285,23 +457,225 @@ pub(crate) async fn post(
         return Err(RouteError::PolicyDenied(res.violations));
     }
 
-    let (client_secret, encrypted_client_secret) = match metadata.token_endpoint_auth_method {
+    Ok(metadata)
+}
+
+/// Check the initial access token presented to the registration endpoint, if
+/// any
+///
+/// Returns an error if the token is invalid, or if it is missing but required
+/// by the configuration.
+async fn check_initial_access_token(
+    repo: &mut BoxRepository,
+    clock: &impl Clock,
+    config: &ClientRegistrationConfig,
+    authorization: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Result<Option<InitialAccessToken>, RouteError> {
+    let Some(TypedHeader(authorization)) = authorization else {
+        if config.require_initial_access_token {
+            return Err(RouteError::MissingInitialAccessToken);
+        }
+
+        return Ok(None);
+    };
+
+    let token = authorization.token();
+
+    // Check the token format first, to avoid a database lookup for tokens
+    // which are obviously not initial access tokens
+    if TokenType::check(token).ok() != Some(TokenType::InitialAccessToken) {
+        return Err(RouteError::InvalidInitialAccessToken);
+    }
+
+    let initial_access_token = repo
+        .oauth2_initial_access_token()
+        .find_by_token(token)
+        .await?
+        .filter(|initial_access_token| initial_access_token.is_valid(clock.now()))
+        .ok_or(RouteError::InvalidInitialAccessToken)?;
+
+    Ok(Some(initial_access_token))
+}
+
+/// Verify the software statement included in the client metadata, if any, and
+/// override the metadata with its claims
+///
+/// Returns the resulting metadata, and the claims of the software statement.
+async fn resolve_software_statement(
+    clock: &impl Clock,
+    http_client: &reqwest::Client,
+    config: &ClientRegistrationConfig,
+    metadata: ClientMetadata,
+) -> Result<(ClientMetadata, Option<HashMap<String, Value>>), RouteError> {
+    let Some(software_statement) = metadata.software_statement.as_deref() else {
+        if config.require_software_statement {
+            return Err(RouteError::MissingSoftwareStatement);
+        }
+
+        return Ok((metadata, None));
+    };
+
+    let claims = verify_software_statement(
+        clock,
+        http_client,
+        &config.software_statement_issuers,
+        software_statement,
+    )
+    .await?;
+
+    let metadata = apply_software_statement(metadata, &claims)?;
+
+    Ok((metadata, Some(claims)))
+}
+
+/// Whether the given authentication method requires a client secret
+fn requires_client_secret(method: Option<&OAuthClientAuthenticationMethod>) -> bool {
+    matches!(
+        method,
         Some(
             OAuthClientAuthenticationMethod::ClientSecretJwt
-            | OAuthClientAuthenticationMethod::ClientSecretPost
-            | OAuthClientAuthenticationMethod::ClientSecretBasic,
-        ) => {
+                | OAuthClientAuthenticationMethod::ClientSecretPost
+                | OAuthClientAuthenticationMethod::ClientSecretBasic,
+        )
+    )
+}
+
+/// Decrypt a secret stored with the [`Encrypter`]
+fn decrypt_secret(encrypter: &Encrypter, encrypted_secret: &str) -> Result<String, RouteError> {
+    let secret = encrypter.decrypt_string(encrypted_secret)?;
+    Ok(String::from_utf8(secret)?)
+}
+
+/// Authenticate a request to the client configuration endpoint, returning the
+/// client and its registration access token
+async fn authenticate_client(
+    repo: &mut BoxRepository,
+    encrypter: &Encrypter,
+    client_id: Ulid,
+    authorization: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Result<(Client, String), RouteError> {
+    let TypedHeader(authorization) =
+        authorization.ok_or(RouteError::InvalidRegistrationAccessToken)?;
+
+    // Unknown clients are reported the same way as invalid tokens, so that the
+    // endpoint can't be used to find out which clients exist
+    let client = repo
+        .oauth2_client()
+        .lookup(client_id)
+        .await?
+        .ok_or(RouteError::InvalidRegistrationAccessToken)?;
+
+    let encrypted_registration_access_token = client
+        .encrypted_registration_access_token
+        .as_deref()
+        .ok_or(RouteError::InvalidRegistrationAccessToken)?;
+
+    let registration_access_token = decrypt_secret(encrypter, encrypted_registration_access_token)?;
+
+    if authorization.token() != registration_access_token {
+        return Err(RouteError::InvalidRegistrationAccessToken);
+    }
+
+    Ok((client, registration_access_token))
+}
+
+/// Build the response returned by the registration and client configuration
+/// endpoints
+fn client_information_response(
+    client: Client,
+    client_secret: Option<String>,
+    registration_access_token: Option<String>,
+    url_builder: &UrlBuilder,
+) -> Result<RouteResponse, RouteError> {
+    // Clients without a registration access token can't be managed, so we don't
+    // advertise their configuration endpoint
+    let registration_client_uri = registration_access_token.is_some().then(|| {
+        url_builder.absolute_url_for(&mas_router::OAuth2ClientConfigurationEndpoint(client.id))
+    });
+
+    let response = ClientRegistrationResponse {
+        client_id: client.client_id.clone(),
+        client_secret,
+        // XXX: we should have a `created_at` field on the clients
+        client_id_issued_at: Some(client.id.datetime().into()),
+        client_secret_expires_at: None,
+        registration_access_token,
+        registration_client_uri,
+    };
+
+    // We round-trip back to the metadata to output it in the response
+    // This should never fail, as the client is valid
+    let metadata = client.into_metadata().validate()?;
+
+    Ok(RouteResponse { response, metadata })
+}
+
+#[tracing::instrument(name = "handlers.oauth2.registration.post", skip_all, err)]
+pub(crate) async fn post(
+    mut rng: BoxRng,
+    clock: BoxClock,
+    mut repo: BoxRepository,
+    mut policy: Policy,
+    activity_tracker: BoundActivityTracker,
+    user_agent: Option<TypedHeader<headers::UserAgent>>,
+    State(encrypter): State<Encrypter>,
+    State(http_client): State<reqwest::Client>,
+    State(url_builder): State<UrlBuilder>,
+    State(site_config): State<SiteConfig>,
+    authorization: Option<TypedHeader<Authorization<Bearer>>>,
+    body: Result<Json<ClientMetadata>, axum::extract::rejection::JsonRejection>,
+) -> Result<impl IntoResponse, RouteError> {
+    let initial_access_token = check_initial_access_token(
+        &mut repo,
+        &clock,
+        &site_config.client_registration,
+        authorization,
+    )
+    .await?;
+
+    // Propagate any JSON extraction error
+    let Json(body) = body?;
+
+    // Sort the properties to ensure a stable serialisation order for hashing
+    let body = body.sorted();
+
+    // We need to serialize the body to compute the hash, and to log it
+    let body_json = serde_json::to_string(&body)?;
+
+    info!(body = body_json, "Client registration");
+
+    let (body, software_statement) =
+        resolve_software_statement(&clock, &http_client, &site_config.client_registration, body)
+            .await?;
+
+    let user_agent = user_agent.map(|ua| ua.to_string());
+
+    let metadata = verify_metadata(
+        body,
+        software_statement.as_ref(),
+        &mut policy,
+        &activity_tracker,
+        user_agent,
+        &http_client,
+    )
+    .await?;
+
+    let (client_secret, encrypted_client_secret) =
+        if requires_client_secret(metadata.token_endpoint_auth_method.as_ref()) {
             // Let's generate a random client secret
             let client_secret = Alphanumeric.sample_string(&mut rng, 20);
             let encrypted_client_secret = encrypter.encrypt_to_string(client_secret.as_bytes())?;
             (Some(client_secret), Some(encrypted_client_secret))
-        }
-        _ => (None, None),
-    };
+        } else {
+            (None, None)
+        };
 
-    // If the client doesn't have a secret, we may be able to deduplicate it. To
-    // do so, we hash the client metadata, and look for it in the database
-    let (digest_hash, existing_client) = if client_secret.is_none() {
+    // Public clients used to be deduplicated, by hashing their metadata and
+    // looking for it in the database. Clients registered since then get a
+    // registration access token which lets whoever registered them manage them,
+    // so they are never shared, and their metadata digest isn't saved. Clients
+    // registered before that can't be managed, so we keep reusing them.
+    let existing_client = if client_secret.is_none() {
         // XXX: One interesting caveat is that we hash *before* saving to the database.
         // It means it takes into account fields that we don't care about *yet*.
         //
@@ -310,24 +684,33 @@ pub(crate) async fn post(
         // database
         let hash = sha2::Sha256::digest(body_json);
         let hash = hex::encode(hash);
-        let client = repo.oauth2_client().find_by_metadata_digest(&hash).await?;
-        (Some(hash), client)
+        repo.oauth2_client()
+            .find_by_metadata_digest(&hash)
+            .await?
+            .filter(|client| client.encrypted_registration_access_token.is_none())
     } else {
-        (None, None)
+        None
     };
 
-    let client = if let Some(client) = existing_client {
+    let (client, registration_access_token) = if let Some(client) = existing_client {
         tracing::info!(%client.id, "Reusing existing client");
         REGISTRATION_COUNTER.add(1, &[KeyValue::new(RESULT, "reused")]);
-        client
+
+        // Those clients can't be managed, so they don't get a registration
+        // access token
+        (client, None)
     } else {
+        let registration_access_token = Alphanumeric.sample_string(&mut rng, 32);
+        let encrypted_registration_access_token =
+            encrypter.encrypt_to_string(registration_access_token.as_bytes())?;
+
         let client = repo
             .oauth2_client()
             .add(
                 &mut rng,
                 &clock,
                 metadata.redirect_uris().to_vec(),
-                digest_hash,
+                None,
                 encrypted_client_secret,
                 metadata.application_type.clone(),
                 //&metadata.response_types(),
@@ -348,46 +731,273 @@ pub(crate) async fn post(
                 metadata.token_endpoint_auth_method.clone(),
                 metadata.token_endpoint_auth_signing_alg.clone(),
                 metadata.initiate_login_uri.clone(),
+                metadata
+                    .post_logout_redirect_uris
+                    .clone()
+                    .unwrap_or_default(),
+                metadata.backchannel_logout_uri.clone(),
+                metadata.backchannel_logout_session_required(),
+                metadata.require_pushed_authorization_requests(),
+                metadata.request_object_signing_alg.clone(),
+                metadata.request_uris.clone().unwrap_or_default(),
+                metadata.subject_type.clone(),
+                metadata.sector_identifier_uri.clone(),
+                metadata.introspection_signed_response_alg.clone(),
+                metadata
+                    .id_token_encrypted_response()
+                    .map(|(alg, _)| alg.clone()),
+                metadata
+                    .id_token_encrypted_response()
+                    .map(|(_, enc)| enc.clone()),
+                metadata
+                    .userinfo_encrypted_response()
+                    .map(|(alg, _)| alg.clone()),
+                metadata
+                    .userinfo_encrypted_response()
+                    .map(|(_, enc)| enc.clone()),
+                Some(encrypted_registration_access_token),
             )
             .await?;
         tracing::info!(%client.id, "Registered new client");
         REGISTRATION_COUNTER.add(1, &[KeyValue::new(RESULT, "created")]);
-        client
+        (client, Some(registration_access_token))
     };
 
-    let response = ClientRegistrationResponse {
-        client_id: client.client_id.clone(),
+    if let Some(initial_access_token) = initial_access_token {
+        // This fails if the token got used up by a concurrent registration
+        repo.oauth2_initial_access_token()
+            .record_use(&clock, initial_access_token)
+            .await?
+            .ok_or(RouteError::InvalidInitialAccessToken)?;
+    }
+
+    let response = client_information_response(
+        client,
         client_secret,
-        // XXX: we should have a `created_at` field on the clients
-        client_id_issued_at: Some(client.id.datetime().into()),
-        client_secret_expires_at: None,
-    };
+        registration_access_token,
+        &url_builder,
+    )?;
 
-    // We round-trip back to the metadata to output it in the response
-    // This should never fail, as the client is valid
-    let metadata = client.into_metadata().validate()?;
+    repo.save().await?;
+
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+#[tracing::instrument(
+    name = "handlers.oauth2.registration.get",
+    fields(client.id = %client_id),
+    skip_all,
+    err,
+)]
+pub(crate) async fn get(
+    mut repo: BoxRepository,
+    State(encrypter): State<Encrypter>,
+    State(url_builder): State<UrlBuilder>,
+    Path(client_id): Path<Ulid>,
+    authorization: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Result<impl IntoResponse, RouteError> {
+    let (client, registration_access_token) =
+        authenticate_client(&mut repo, &encrypter, client_id, authorization).await?;
+
+    let client_secret = client
+        .encrypted_client_secret
+        .as_deref()
+        .map(|encrypted| decrypt_secret(&encrypter, encrypted))
+        .transpose()?;
+
+    let response = client_information_response(
+        client,
+        client_secret,
+        Some(registration_access_token),
+        &url_builder,
+    )?;
+
+    repo.cancel().await?;
+
+    Ok(Json(response))
+}
+
+#[tracing::instrument(
+    name = "handlers.oauth2.registration.put",
+    fields(client.id = %client_id),
+    skip_all,
+    err,
+)]
+pub(crate) async fn put(
+    mut rng: BoxRng,
+    clock: BoxClock,
+    mut repo: BoxRepository,
+    mut policy: Policy,
+    activity_tracker: BoundActivityTracker,
+    user_agent: Option<TypedHeader<headers::UserAgent>>,
+    State(encrypter): State<Encrypter>,
+    State(http_client): State<reqwest::Client>,
+    State(url_builder): State<UrlBuilder>,
+    State(site_config): State<SiteConfig>,
+    Path(client_id): Path<Ulid>,
+    authorization: Option<TypedHeader<Authorization<Bearer>>>,
+    body: Result<Json<ClientMetadata>, axum::extract::rejection::JsonRejection>,
+) -> Result<impl IntoResponse, RouteError> {
+    let (client, registration_access_token) =
+        authenticate_client(&mut repo, &encrypter, client_id, authorization).await?;
+
+    // Propagate any JSON extraction error
+    let Json(body) = body?;
+
+    info!(body = serde_json::to_string(&body)?, "Client update");
+
+    let (body, software_statement) =
+        resolve_software_statement(&clock, &http_client, &site_config.client_registration, body)
+            .await?;
+
+    let user_agent = user_agent.map(|ua| ua.to_string());
+
+    let metadata = verify_metadata(
+        body,
+        software_statement.as_ref(),
+        &mut policy,
+        &activity_tracker,
+        user_agent,
+        &http_client,
+    )
+    .await?;
+
+    // Keep the existing client secret if the client still needs one, and
+    // generate one if it didn't have any
+    let (client_secret, encrypted_client_secret) =
+        if requires_client_secret(metadata.token_endpoint_auth_method.as_ref()) {
+            if let Some(encrypted_client_secret) = client.encrypted_client_secret.clone() {
+                let client_secret = decrypt_secret(&encrypter, &encrypted_client_secret)?;
+                (Some(client_secret), Some(encrypted_client_secret))
+            } else {
+                let client_secret = Alphanumeric.sample_string(&mut rng, 20);
+                let encrypted_client_secret =
+                    encrypter.encrypt_to_string(client_secret.as_bytes())?;
+                (Some(client_secret), Some(encrypted_client_secret))
+            }
+        } else {
+            (None, None)
+        };
+
+    let client = repo
+        .oauth2_client()
+        .update(
+            client,
+            metadata.redirect_uris().to_vec(),
+            encrypted_client_secret,
+            metadata.application_type.clone(),
+            metadata.grant_types().to_vec(),
+            metadata
+                .client_name
+                .clone()
+                .map(Localized::to_non_localized),
+            metadata.logo_uri.clone().map(Localized::to_non_localized),
+            metadata.client_uri.clone().map(Localized::to_non_localized),
+            metadata.policy_uri.clone().map(Localized::to_non_localized),
+            metadata.tos_uri.clone().map(Localized::to_non_localized),
+            metadata.jwks_uri.clone(),
+            metadata.jwks.clone(),
+            metadata.id_token_signed_response_alg.clone(),
+            metadata.userinfo_signed_response_alg.clone(),
+            metadata.token_endpoint_auth_method.clone(),
+            metadata.token_endpoint_auth_signing_alg.clone(),
+            metadata.initiate_login_uri.clone(),
+            metadata
+                .post_logout_redirect_uris
+                .clone()
+                .unwrap_or_default(),
+            metadata.backchannel_logout_uri.clone(),
+            metadata.backchannel_logout_session_required(),
+            metadata.require_pushed_authorization_requests(),
+            metadata.request_object_signing_alg.clone(),
+            metadata.request_uris.clone().unwrap_or_default(),
+            metadata.subject_type.clone(),
+            metadata.sector_identifier_uri.clone(),
+            metadata.introspection_signed_response_alg.clone(),
+            metadata
+                .id_token_encrypted_response()
+                .map(|(alg, _)| alg.clone()),
+            metadata
+                .id_token_encrypted_response()
+                .map(|(_, enc)| enc.clone()),
+            metadata
+                .userinfo_encrypted_response()
+                .map(|(alg, _)| alg.clone()),
+            metadata
+                .userinfo_encrypted_response()
+                .map(|(_, enc)| enc.clone()),
+        )
+        .await?;
+    tracing::info!(%client.id, "Updated client");
+
+    let response = client_information_response(
+        client,
+        client_secret,
+        Some(registration_access_token),
+        &url_builder,
+    )?;
 
     repo.save().await?;
 
-    let response = RouteResponse { response, metadata };
+    Ok(Json(response))
+}
 
-    Ok((StatusCode::CREATED, Json(response)))
+#[tracing::instrument(
+    name = "handlers.oauth2.registration.delete",
+    fields(client.id = %client_id),
+    skip_all,
+    err,
+)]
+pub(crate) async fn delete(
+    mut repo: BoxRepository,
+    State(encrypter): State<Encrypter>,
+    Path(client_id): Path<Ulid>,
+    authorization: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Result<impl IntoResponse, RouteError> {
+    let (client, _registration_access_token) =
+        authenticate_client(&mut repo, &encrypter, client_id, authorization).await?;
+
+    // This also removes the sessions and tokens issued to the client
+    repo.oauth2_client().delete(client).await?;
+    tracing::info!(client.id = %client_id, "Deleted client");
+
+    repo.save().await?;
+
+    Ok(StatusCode::NO_CONTENT)
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
+    use chrono::Duration;
     use hyper::{Request, StatusCode};
+    use mas_data_model::{
+        ClientRegistrationConfig, JwksOrJwksUri, SoftwareStatementIssuer, TokenType,
+    };
+    use mas_iana::jose::JsonWebSignatureAlg;
+    use mas_jose::{
+        claims,
+        jwk::{PublicJsonWebKey, PublicJsonWebKeySet},
+        jwt::{JsonWebSignatureHeader, Jwt},
+    };
+    use mas_keystore::PrivateKey;
     use mas_router::SimpleRoute;
+    use mas_storage::{Clock, RepositoryAccess};
+    use oauth2_types::registration::ClientMetadata;
     use oauth2_types::{
         errors::{ClientError, ClientErrorCode},
         registration::ClientRegistrationResponse,
     };
+    use rand::SeedableRng;
+    use sha2::Digest;
     use sqlx::PgPool;
+    use ulid::Ulid;
     use url::Url;
 
     use crate::{
         oauth2::registration::host_is_public_suffix,
-        test_utils::{RequestBuilderExt, ResponseExt, TestState, setup},
+        test_utils::{RequestBuilderExt, ResponseExt, TestState, setup, test_site_config},
     };
 
     #[test]
@@ -501,6 +1111,41 @@ mod tests {
             response.error_description.unwrap(),
             "client_uri is not using a valid domain"
         );
+
+        // Asking for encrypted ID tokens with an unsupported algorithm
+        let request =
+            Request::post(mas_router::OAuth2RegistrationEndpoint::PATH).json(serde_json::json!({
+                "client_uri": "https://example.com/",
+                "redirect_uris": ["https://example.com/"],
+                "response_types": ["code"],
+                "grant_types": ["authorization_code"],
+                "jwks_uri": "https://example.com/jwks.json",
+                "id_token_encrypted_response_alg": "RSA1_5",
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        let response: ClientError = response.json();
+        assert_eq!(response.error, ClientErrorCode::InvalidClientMetadata);
+        assert_eq!(
+            response.error_description.unwrap(),
+            "the id_token encryption algorithms are not supported"
+        );
+
+        // Asking for encrypted user info responses without any key to encrypt with
+        let request =
+            Request::post(mas_router::OAuth2RegistrationEndpoint::PATH).json(serde_json::json!({
+                "client_uri": "https://example.com/",
+                "redirect_uris": ["https://example.com/"],
+                "response_types": ["code"],
+                "grant_types": ["authorization_code"],
+                "userinfo_encrypted_response_alg": "RSA-OAEP-256",
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        let response: ClientError = response.json();
+        assert_eq!(response.error, ClientErrorCode::InvalidClientMetadata);
     }
 
     #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
@@ -545,29 +1190,62 @@ mod tests {
         setup();
         let state = TestState::from_pool(pool).await.unwrap();
 
-        // Post a client registration twice, we should get the same client ID
-        let request =
-            Request::post(mas_router::OAuth2RegistrationEndpoint::PATH).json(serde_json::json!({
-                "client_uri": "https://example.com/",
-                "client_name": "Example",
-                "client_name#en": "Example",
-                "client_name#fr": "Exemple",
-                "client_name#de": "Beispiel",
-                "redirect_uris": ["https://example.com/", "https://example.com/callback"],
-                "response_types": ["code"],
-                "grant_types": ["authorization_code", "urn:ietf:params:oauth:grant-type:device_code"],
-                "token_endpoint_auth_method": "none",
-            }));
-
+        let metadata = serde_json::json!({
+            "client_uri": "https://example.com/",
+            "client_name": "Example",
+            "client_name#en": "Example",
+            "client_name#fr": "Exemple",
+            "client_name#de": "Beispiel",
+            "redirect_uris": ["https://example.com/", "https://example.com/callback"],
+            "response_types": ["code"],
+            "grant_types": ["authorization_code", "urn:ietf:params:oauth:grant-type:device_code"],
+            "token_endpoint_auth_method": "none",
+        });
+        let request = Request::post(mas_router::OAuth2RegistrationEndpoint::PATH).json(&metadata);
+
+        // Clients which can be managed through their registration access token
+        // are never shared, as it would let anyone manage them
         let response = state.request(request.clone()).await;
         response.assert_status(StatusCode::CREATED);
         let response: ClientRegistrationResponse = response.json();
         let client_id = response.client_id;
+        let token = response.registration_access_token.unwrap();
 
+        let response = state.request(request.clone()).await;
+        response.assert_status(StatusCode::CREATED);
+        let response: ClientRegistrationResponse = response.json();
+        assert_ne!(response.client_id, client_id);
+        assert_ne!(response.registration_access_token.as_deref(), Some(&*token));
+
+        // Turn the first client into one registered before registration access
+        // tokens were introduced, which were deduplicated using a digest of their
+        // metadata
+        let body = serde_json::from_value::<ClientMetadata>(metadata)
+            .unwrap()
+            .sorted();
+        let digest = hex::encode(sha2::Sha256::digest(serde_json::to_string(&body).unwrap()));
+        let client_ulid: Ulid = client_id.parse().unwrap();
+        sqlx::query(
+            r"
+                UPDATE oauth2_clients
+                SET metadata_digest = $1
+                  , encrypted_registration_access_token = NULL
+                WHERE oauth2_client_id = encode($2, 'hex')::uuid
+            ",
+        )
+        .bind(digest)
+        .bind(client_ulid.to_bytes().to_vec())
+        .execute(&state.pool)
+        .await
+        .unwrap();
+
+        // Those are still reused, but can't be managed
         let response = state.request(request).await;
         response.assert_status(StatusCode::CREATED);
         let response: ClientRegistrationResponse = response.json();
         assert_eq!(response.client_id, client_id);
+        assert!(response.registration_access_token.is_none());
+        assert!(response.registration_client_uri.is_none());
 
         // Check that the order of some properties doesn't matter
         let request =
@@ -610,4 +1288,268 @@ mod tests {
         let response: ClientRegistrationResponse = response.json();
         assert_ne!(response.client_id, client_id);
     }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_client_configuration(pool: PgPool) {
+        setup();
+        let state = TestState::from_pool(pool).await.unwrap();
+
+        let metadata = serde_json::json!({
+            "client_uri": "https://example.com/",
+            "redirect_uris": ["https://example.com/callback"],
+            "response_types": ["code"],
+            "grant_types": ["authorization_code"],
+            "token_endpoint_auth_method": "none",
+        });
+
+        let request = Request::post(mas_router::OAuth2RegistrationEndpoint::PATH).json(&metadata);
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CREATED);
+        let response: ClientRegistrationResponse = response.json();
+        let client_id = response.client_id;
+        let token = response.registration_access_token.unwrap();
+        let uri = response.registration_client_uri.unwrap();
+        assert_eq!(uri.path(), format!("/oauth2/registration/{client_id}"));
+
+        // Registering the same metadata again gives another client, with its own
+        // token
+        let request = Request::post(mas_router::OAuth2RegistrationEndpoint::PATH).json(&metadata);
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CREATED);
+        let response: ClientRegistrationResponse = response.json();
+        assert_ne!(response.client_id, client_id);
+        assert_ne!(response.registration_access_token.as_deref(), Some(&*token));
+
+        // Reading the registration requires the registration access token
+        let request = Request::get(uri.path()).empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+
+        let request = Request::get(uri.path()).bearer("wrong").empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+
+        let request = Request::get(uri.path()).bearer(&token).empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let response: serde_json::Value = response.json();
+        assert_eq!(response["client_id"], client_id);
+        assert_eq!(
+            response["redirect_uris"],
+            serde_json::json!(["https://example.com/callback"])
+        );
+
+        // Update the redirect URIs
+        let request = Request::put(uri.path())
+            .bearer(&token)
+            .json(serde_json::json!({
+                "client_uri": "https://example.com/",
+                "redirect_uris": ["https://example.com/new-callback"],
+                "response_types": ["code"],
+                "grant_types": ["authorization_code"],
+                "token_endpoint_auth_method": "none",
+            }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let response: serde_json::Value = response.json();
+        assert_eq!(
+            response["redirect_uris"],
+            serde_json::json!(["https://example.com/new-callback"])
+        );
+
+        // Updates go through the same validation as registrations
+        let request = Request::put(uri.path())
+            .bearer(&token)
+            .json(serde_json::json!({
+                "client_uri": "https://example.com/",
+                "redirect_uris": ["https://github.io/"],
+                "response_types": ["code"],
+                "grant_types": ["authorization_code"],
+                "token_endpoint_auth_method": "none",
+            }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        let response: ClientError = response.json();
+        assert_eq!(response.error, ClientErrorCode::InvalidRedirectUri);
+
+        // The updated client is not reused for new registrations anymore
+        let request = Request::post(mas_router::OAuth2RegistrationEndpoint::PATH).json(&metadata);
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CREATED);
+        let response: ClientRegistrationResponse = response.json();
+        assert_ne!(response.client_id, client_id);
+
+        // Delete the client
+        let request = Request::delete(uri.path()).bearer(&token).empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::NO_CONTENT);
+
+        let request = Request::get(uri.path()).bearer(&token).empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_registration_initial_access_token(pool: PgPool) {
+        setup();
+        let mut site_config = test_site_config();
+        site_config.client_registration = ClientRegistrationConfig {
+            require_initial_access_token: true,
+            ..ClientRegistrationConfig::default()
+        };
+        let state = TestState::from_pool_with_site_config(pool, site_config)
+            .await
+            .unwrap();
+
+        let metadata = serde_json::json!({
+            "client_uri": "https://example.com/",
+            "redirect_uris": ["https://example.com/callback"],
+            "response_types": ["code"],
+            "grant_types": ["authorization_code"],
+            "token_endpoint_auth_method": "none",
+        });
+
+        // Registering without a token is not allowed
+        let request = Request::post(mas_router::OAuth2RegistrationEndpoint::PATH).json(&metadata);
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+
+        // Neither is registering with an unknown token
+        let unknown_token = TokenType::InitialAccessToken.generate(&mut state.rng());
+        let request = Request::post(mas_router::OAuth2RegistrationEndpoint::PATH)
+            .bearer(&unknown_token)
+            .json(&metadata);
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+
+        // Mint a token which can be used once
+        let mut repo = state.repository().await.unwrap();
+        let token = TokenType::InitialAccessToken.generate(&mut state.rng());
+        repo.oauth2_initial_access_token()
+            .add(
+                &mut state.rng(),
+                &state.clock,
+                token.clone(),
+                Some(Duration::try_hours(1).unwrap()),
+                Some(1),
+            )
+            .await
+            .unwrap();
+        repo.save().await.unwrap();
+
+        let request = Request::post(mas_router::OAuth2RegistrationEndpoint::PATH)
+            .bearer(&token)
+            .json(&metadata);
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CREATED);
+
+        // The token is now used up
+        let request = Request::post(mas_router::OAuth2RegistrationEndpoint::PATH)
+            .bearer(&token)
+            .json(&metadata);
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_registration_software_statement(pool: PgPool) {
+        setup();
+
+        let mut rng = rand_chacha::ChaChaRng::seed_from_u64(42);
+        let key = PrivateKey::generate_ec_p256(&mut rng);
+        let jwks = PublicJsonWebKeySet::new(vec![
+            PublicJsonWebKey::new((&key).into()).with_alg(JsonWebSignatureAlg::Es256),
+        ]);
+
+        let mut site_config = test_site_config();
+        site_config.client_registration = ClientRegistrationConfig {
+            require_software_statement: true,
+            software_statement_issuers: vec![SoftwareStatementIssuer {
+                issuer: "https://trusted.example.com/".to_owned(),
+                jwks: JwksOrJwksUri::Jwks(jwks),
+            }],
+            ..ClientRegistrationConfig::default()
+        };
+        let state = TestState::from_pool_with_site_config(pool, site_config)
+            .await
+            .unwrap();
+
+        let software_statement = |issuer: &str, key: &PrivateKey| {
+            let alg = JsonWebSignatureAlg::Es256;
+            let signer = key.signing_key_for_alg(&alg).unwrap();
+            let header = JsonWebSignatureHeader::new(alg);
+
+            let now = state.clock.now();
+            let mut claims = HashMap::new();
+            claims::ISS.insert(&mut claims, issuer).unwrap();
+            claims::IAT.insert(&mut claims, now).unwrap();
+            claims::EXP
+                .insert(&mut claims, now + Duration::try_hours(1).unwrap())
+                .unwrap();
+            claims.insert(
+                "client_name".to_owned(),
+                serde_json::json!("Trusted Client"),
+            );
+            claims.insert(
+                "redirect_uris".to_owned(),
+                serde_json::json!(["https://trusted.example.com/callback"]),
+            );
+
+            Jwt::sign_with_rng(&mut state.rng(), header, claims, &signer)
+                .unwrap()
+                .into_string()
+        };
+
+        let metadata = |software_statement: Option<String>| {
+            serde_json::json!({
+                "client_name": "Some Client",
+                "client_uri": "https://trusted.example.com/",
+                "redirect_uris": ["https://evil.example.com/callback"],
+                "response_types": ["code"],
+                "grant_types": ["authorization_code"],
+                "token_endpoint_auth_method": "none",
+                "software_statement": software_statement,
+            })
+        };
+
+        // A software statement is required
+        let request =
+            Request::post(mas_router::OAuth2RegistrationEndpoint::PATH).json(metadata(None));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        let response: ClientError = response.json();
+        assert_eq!(response.error, ClientErrorCode::InvalidSoftwareStatement);
+
+        // Statements from unknown issuers are not approved
+        let statement = software_statement("https://untrusted.example.com/", &key);
+        let request = Request::post(mas_router::OAuth2RegistrationEndpoint::PATH)
+            .json(metadata(Some(statement)));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        let response: ClientError = response.json();
+        assert_eq!(response.error, ClientErrorCode::UnapprovedSoftwareStatement);
+
+        // Statements signed with another key are invalid
+        let other_key = PrivateKey::generate_ec_p256(&mut rng);
+        let statement = software_statement("https://trusted.example.com/", &other_key);
+        let request = Request::post(mas_router::OAuth2RegistrationEndpoint::PATH)
+            .json(metadata(Some(statement)));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        let response: ClientError = response.json();
+        assert_eq!(response.error, ClientErrorCode::InvalidSoftwareStatement);
+
+        // The claims of a valid statement override the submitted metadata
+        let statement = software_statement("https://trusted.example.com/", &key);
+        let request = Request::post(mas_router::OAuth2RegistrationEndpoint::PATH)
+            .json(metadata(Some(statement)));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CREATED);
+        let response: serde_json::Value = response.json();
+        assert_eq!(response["client_name"], "Trusted Client");
+        assert_eq!(
+            response["redirect_uris"],
+            serde_json::json!(["https://trusted.example.com/callback"])
+        );
+    }
 }
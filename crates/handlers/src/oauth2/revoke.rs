@@ -15,7 +15,7 @@ use mas_iana::oauth::OAuthTokenTypeHint;
 use mas_keystore::Encrypter;
 use mas_storage::{
     BoxClock, BoxRepository, BoxRng, RepositoryAccess,
-    queue::{QueueJobRepositoryExt as _, SyncDevicesJob},
+    queue::{QueueJobRepositoryExt as _, SendBackchannelLogoutJob, SyncDevicesJob},
 };
 use oauth2_types::{
     errors::{ClientError, ClientErrorCode},
@@ -215,6 +215,11 @@ pub(crate) async fn post(
             .await?;
     }
 
+    // Notify the client through the back-channel, if it registered for it
+    repo.queue_job()
+        .schedule_job(&mut rng, &clock, SendBackchannelLogoutJob::new(&session))
+        .await?;
+
     // Now that we checked everything, we can end the session.
     repo.oauth2_session().finish(&clock, session).await?;
 
@@ -307,7 +312,10 @@ mod tests {
                 &state.clock,
                 &mut repo,
                 &session,
+                TokenType::AccessToken.generate(&mut state.rng()),
                 Duration::microseconds(5 * 60 * 1000 * 1000),
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -374,7 +382,10 @@ mod tests {
                 &state.clock,
                 &mut repo,
                 &session,
+                TokenType::AccessToken.generate(&mut state.rng()),
                 Duration::microseconds(5 * 60 * 1000 * 1000),
+                None,
+                None,
             )
             .await
             .unwrap();
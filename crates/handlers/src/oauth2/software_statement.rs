@@ -0,0 +1,131 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+//! Verification of the [software statements] presented to the dynamic client
+//! registration endpoint
+//!
+//! [software statements]: https://www.rfc-editor.org/rfc/rfc7591#section-2.3
+
+use std::collections::HashMap;
+
+use axum::BoxError;
+use mas_axum_utils::client_authorization::fetch_jwks;
+use mas_data_model::SoftwareStatementIssuer;
+use mas_iana::jose::JsonWebSignatureAlg;
+use mas_jose::{
+    claims::{self, ClaimError, TimeOptions},
+    jwt::{Jwt, JwtDecodeError},
+};
+use mas_storage::Clock;
+use oauth2_types::registration::ClientMetadata;
+use serde_json::Value;
+use thiserror::Error;
+
+/// The claims of a software statement which describe the JWT itself, and
+/// which are not client metadata
+const JWT_CLAIMS: [&str; 6] = ["iss", "aud", "exp", "nbf", "iat", "jti"];
+
+#[derive(Debug, Error)]
+pub(crate) enum SoftwareStatementError {
+    #[error("could not decode the software statement")]
+    Decode(#[from] JwtDecodeError),
+
+    #[error("the software statement is not signed")]
+    Unsigned,
+
+    #[error("the software statement has no issuer")]
+    MissingIssuer,
+
+    #[error("the software statement has invalid claims")]
+    InvalidClaims(#[from] ClaimError),
+
+    #[error("the software statement issuer {0:?} is not trusted")]
+    UntrustedIssuer(String),
+
+    #[error("could not fetch the JWKS of the software statement issuer")]
+    JwksFetch(#[source] BoxError),
+
+    #[error("the software statement signature is invalid")]
+    InvalidSignature,
+
+    #[error("the software statement contains invalid client metadata")]
+    InvalidMetadata(#[source] serde_json::Error),
+}
+
+/// Verify a software statement against the list of trusted issuers
+///
+/// Returns the claims of the software statement, which include the `iss` and
+/// `software_id` claims, among others.
+///
+/// # Errors
+///
+/// Returns an error if the software statement is malformed, if it isn't
+/// signed by a trusted issuer, or if it expired
+pub(crate) async fn verify_software_statement(
+    clock: &impl Clock,
+    http_client: &reqwest::Client,
+    issuers: &[SoftwareStatementIssuer],
+    software_statement: &str,
+) -> Result<HashMap<String, Value>, SoftwareStatementError> {
+    let jwt: Jwt<'_, HashMap<String, Value>> = Jwt::try_from(software_statement)?;
+
+    if *jwt.header().alg() == JsonWebSignatureAlg::None {
+        return Err(SoftwareStatementError::Unsigned);
+    }
+
+    let Some(Value::String(issuer)) = jwt.payload().get("iss") else {
+        return Err(SoftwareStatementError::MissingIssuer);
+    };
+
+    let trusted_issuer = issuers
+        .iter()
+        .find(|trusted_issuer| trusted_issuer.issuer == *issuer)
+        .ok_or_else(|| SoftwareStatementError::UntrustedIssuer(issuer.clone()))?;
+
+    let jwks = fetch_jwks(http_client, &trusted_issuer.jwks)
+        .await
+        .map_err(SoftwareStatementError::JwksFetch)?;
+
+    jwt.verify_with_jwks(&jwks)
+        .map_err(|_| SoftwareStatementError::InvalidSignature)?;
+
+    let (_header, claims) = jwt.into_parts();
+
+    // Check the validity period of the statement, without consuming the claims,
+    // so that they are all exposed to the policy
+    let time_options = TimeOptions::new(clock.now());
+    let mut validity_claims = claims.clone();
+    claims::EXP.extract_optional_with_options(&mut validity_claims, &time_options)?;
+    claims::NBF.extract_optional_with_options(&mut validity_claims, &time_options)?;
+
+    Ok(claims)
+}
+
+/// Override the client metadata with the claims of a verified software
+/// statement
+///
+/// # Errors
+///
+/// Returns an error if the claims of the software statement are not valid
+/// client metadata
+pub(crate) fn apply_software_statement(
+    metadata: ClientMetadata,
+    claims: &HashMap<String, Value>,
+) -> Result<ClientMetadata, SoftwareStatementError> {
+    let mut metadata =
+        serde_json::to_value(metadata).map_err(SoftwareStatementError::InvalidMetadata)?;
+
+    if let Value::Object(metadata) = &mut metadata {
+        for (key, value) in claims {
+            if JWT_CLAIMS.contains(&key.as_str()) {
+                continue;
+            }
+
+            metadata.insert(key.clone(), value.clone());
+        }
+    }
+
+    serde_json::from_value(metadata).map_err(SoftwareStatementError::InvalidMetadata)
+}
@@ -4,19 +4,31 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 // Please see LICENSE in the repository root for full details.
 
-use std::sync::{Arc, LazyLock};
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock},
+};
 
 use axum::{Json, extract::State, response::IntoResponse};
 use axum_extra::typed_header::TypedHeader;
 use chrono::Duration;
 use headers::{CacheControl, HeaderMap, HeaderMapExt, Pragma};
-use hyper::StatusCode;
+use hyper::{Method, StatusCode};
 use mas_axum_utils::{
-    client_authorization::{ClientAuthorization, CredentialsVerificationError},
+    client_authorization::{
+        ClientAuthorization, ClientCertificate, CredentialsVerificationError, fetch_jwks,
+    },
+    dpop::{DpopProofError, verify_dpop_proof},
     sentry::SentryEventID,
 };
 use mas_data_model::{
-    AuthorizationGrantStage, Client, Device, DeviceCodeGrantState, SiteConfig, TokenType, UserAgent,
+    AccessToken, AuthorizationGrantStage, Client, Device, DeviceCodeGrantState, Session,
+    SiteConfig, TokenType, UserAgent,
+};
+use mas_iana::oauth::OAuthAccessTokenType;
+use mas_jose::{
+    claims::{self, TimeOptions},
+    jwt::Jwt,
 };
 use mas_keystore::{Encrypter, Keystore};
 use mas_matrix::HomeserverConnection;
@@ -26,26 +38,37 @@ use mas_router::UrlBuilder;
 use mas_storage::{
     BoxClock, BoxRepository, BoxRng, Clock, RepositoryAccess,
     oauth2::{
-        OAuth2AccessTokenRepository, OAuth2AuthorizationGrantRepository,
+        OAuth2AccessTokenRepository, OAuth2AuthorizationGrantRepository, OAuth2ClientRepository,
         OAuth2RefreshTokenRepository, OAuth2SessionRepository,
     },
-    user::BrowserSessionRepository,
+    queue::{
+        QueueJobRepositoryExt as _, SendBackchannelLogoutJob, SendSessionRevokedEmailJob,
+        SyncDevicesJob,
+    },
+    user::{BrowserSessionRepository, UserRepository},
 };
 use oauth2_types::{
     errors::{ClientError, ClientErrorCode},
     pkce::CodeChallengeError,
     requests::{
         AccessTokenRequest, AccessTokenResponse, AuthorizationCodeGrant, ClientCredentialsGrant,
-        DeviceCodeGrant, GrantType, RefreshTokenGrant,
+        DeviceCodeGrant, GrantType, JwtBearerGrant, RefreshTokenGrant, TokenExchangeGrant,
+        TokenTypeIdentifier,
     },
     scope,
 };
 use opentelemetry::{Key, KeyValue, metrics::Counter};
 use thiserror::Error;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 use ulid::Ulid;
+use url::Url;
 
-use super::{generate_id_token, generate_token_pair};
+use super::{
+    encryption::encrypt_id_token,
+    generate_access_token, generate_id_token, generate_token_pair,
+    profile::{id_token_claims, user_claims},
+    subject_for_client,
+};
 use crate::{BoundActivityTracker, METER, impl_from_error_for_route};
 
 static TOKEN_REQUEST_COUNTER: LazyLock<Counter<u64>> = LazyLock::new(|| {
@@ -55,9 +78,21 @@ static TOKEN_REQUEST_COUNTER: LazyLock<Counter<u64>> = LazyLock::new(|| {
         .with_unit("{request}")
         .build()
 });
+static REFRESH_TOKEN_REUSE_COUNTER: LazyLock<Counter<u64>> = LazyLock::new(|| {
+    METER
+        .u64_counter("mas.oauth2.refresh_token_reuse")
+        .with_description(
+            "How many OAuth 2.0 sessions were revoked because a refresh token was reused",
+        )
+        .with_unit("{session}")
+        .build()
+});
 const GRANT_TYPE: Key = Key::from_static_str("grant_type");
 const RESULT: Key = Key::from_static_str("successful");
 
+/// How far in the future the expiration of a JWT bearer assertion can be
+const MAX_ASSERTION_LIFETIME: Duration = Duration::hours(1);
+
 #[derive(Debug, Error)]
 pub(crate) enum RouteError {
     #[error(transparent)]
@@ -90,6 +125,9 @@ pub(crate) enum RouteError {
     #[error("refresh token {0} is invalid")]
     RefreshTokenInvalid(Ulid),
 
+    #[error("refresh token {0} was reused, the session was revoked")]
+    RefreshTokenReused(Ulid),
+
     #[error("session {0} is invalid")]
     SessionInvalid(Ulid),
 
@@ -141,9 +179,46 @@ pub(crate) enum RouteError {
 
     #[error("failed to provision device")]
     ProvisionDeviceFailed(#[source] anyhow::Error),
+
+    #[error("invalid DPoP proof")]
+    InvalidDpopProof(#[source] DpopProofError<mas_storage::RepositoryError>),
+
+    #[error("token {0} is bound to another DPoP key")]
+    DpopKeyMismatch(Ulid),
+
+    #[error("unsupported token type {0}")]
+    UnsupportedTokenType(TokenTypeIdentifier),
+
+    #[error("invalid subject token")]
+    InvalidSubjectToken,
+
+    #[error("invalid actor token")]
+    InvalidActorToken,
+
+    #[error("requested scope is not allowed")]
+    InvalidScope,
+
+    #[error("policy denied the requested audience or resource")]
+    InvalidTarget(Vec<mas_policy::Violation>),
+
+    #[error("invalid or unknown resource indicator")]
+    InvalidResource,
+
+    #[error("invalid assertion")]
+    InvalidAssertion,
+}
+
+impl From<DpopProofError<mas_storage::RepositoryError>> for RouteError {
+    fn from(e: DpopProofError<mas_storage::RepositoryError>) -> Self {
+        match e {
+            DpopProofError::Internal(e) => Self::Internal(Box::new(e)),
+            e => Self::InvalidDpopProof(e),
+        }
+    }
 }
 
 impl IntoResponse for RouteError {
+    #[allow(clippy::too_many_lines)]
     fn into_response(self) -> axum::response::Response {
         let event_id = sentry::capture_error(&self);
 
@@ -164,6 +239,57 @@ impl IntoResponse for RouteError {
                 StatusCode::BAD_REQUEST,
                 Json(ClientError::from(ClientErrorCode::InvalidRequest)),
             ),
+            Self::UnsupportedTokenType(ref token_type) => (
+                StatusCode::BAD_REQUEST,
+                Json(
+                    ClientError::from(ClientErrorCode::InvalidRequest)
+                        .with_description(format!("Unsupported token type {token_type}")),
+                ),
+            ),
+            Self::InvalidSubjectToken => (
+                StatusCode::BAD_REQUEST,
+                Json(
+                    ClientError::from(ClientErrorCode::InvalidRequest)
+                        .with_description("The subject token is invalid".to_owned()),
+                ),
+            ),
+            Self::InvalidActorToken => (
+                StatusCode::BAD_REQUEST,
+                Json(
+                    ClientError::from(ClientErrorCode::InvalidRequest)
+                        .with_description("The actor token is invalid".to_owned()),
+                ),
+            ),
+            Self::InvalidScope => (
+                StatusCode::BAD_REQUEST,
+                Json(ClientError::from(ClientErrorCode::InvalidScope)),
+            ),
+            Self::InvalidAssertion => (
+                StatusCode::BAD_REQUEST,
+                Json(
+                    ClientError::from(ClientErrorCode::InvalidGrant)
+                        .with_description("The assertion is invalid".to_owned()),
+                ),
+            ),
+            Self::InvalidTarget(violations) => (
+                StatusCode::BAD_REQUEST,
+                Json(
+                    ClientError::from(ClientErrorCode::InvalidTarget).with_description(
+                        violations
+                            .into_iter()
+                            .map(|violation| violation.msg)
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                    ),
+                ),
+            ),
+            Self::InvalidResource => (
+                StatusCode::BAD_REQUEST,
+                Json(
+                    ClientError::from(ClientErrorCode::InvalidTarget)
+                        .with_description("The requested resource is invalid".to_owned()),
+                ),
+            ),
             Self::PkceVerification(err) => (
                 StatusCode::BAD_REQUEST,
                 Json(
@@ -207,6 +333,7 @@ impl IntoResponse for RouteError {
             | Self::DeviceCodeExchanged
             | Self::RefreshTokenNotFound
             | Self::RefreshTokenInvalid(_)
+            | Self::RefreshTokenReused(_)
             | Self::SessionInvalid(_)
             | Self::ClientIDMismatch { .. }
             | Self::GrantNotFound => (
@@ -217,6 +344,17 @@ impl IntoResponse for RouteError {
                 StatusCode::BAD_REQUEST,
                 Json(ClientError::from(ClientErrorCode::UnsupportedGrantType)),
             ),
+            Self::InvalidDpopProof(ref e) => (
+                StatusCode::BAD_REQUEST,
+                Json(
+                    ClientError::from(ClientErrorCode::InvalidDpopProof)
+                        .with_description(e.to_string()),
+                ),
+            ),
+            Self::DpopKeyMismatch(_) => (
+                StatusCode::BAD_REQUEST,
+                Json(ClientError::from(ClientErrorCode::InvalidDpopProof)),
+            ),
         };
 
         (SentryEventID::from(event_id), response).into_response()
@@ -225,7 +363,8 @@ impl IntoResponse for RouteError {
 
 impl_from_error_for_route!(mas_storage::RepositoryError);
 impl_from_error_for_route!(mas_policy::EvaluationError);
-impl_from_error_for_route!(super::IdTokenSignatureError);
+impl_from_error_for_route!(super::TokenSignatureError);
+impl_from_error_for_route!(super::encryption::ResponseEncryptionError);
 
 #[tracing::instrument(
     name = "handlers.oauth2.token.post",
@@ -246,6 +385,7 @@ pub(crate) async fn post(
     State(encrypter): State<Encrypter>,
     policy: Policy,
     user_agent: Option<TypedHeader<headers::UserAgent>>,
+    headers: HeaderMap,
     client_authorization: ClientAuthorization<AccessTokenRequest>,
 ) -> Result<impl IntoResponse, RouteError> {
     let user_agent = user_agent.map(|ua| UserAgent::parse(ua.as_str().to_owned()));
@@ -269,7 +409,26 @@ pub(crate) async fn post(
 
     let grant_type = form.grant_type();
 
-    let (reply, repo) = match form {
+    // If the client sent a DPoP proof, the tokens we issue get bound to its key
+    let token_endpoint = url_builder.oauth_token_endpoint();
+    let dpop_proof = verify_dpop_proof(
+        &clock,
+        &mut repo,
+        &headers,
+        Some((&Method::POST, &token_endpoint)),
+        None,
+    )
+    .await?;
+    let dpop_jkt = dpop_proof.map(|proof| proof.jkt);
+
+    // If the client presented a certificate over mutual-TLS, the access tokens
+    // we issue get bound to it, as per RFC 8705
+    let x5t_s256 = client_authorization
+        .credentials
+        .client_certificate()
+        .map(ClientCertificate::thumbprint_sha256);
+
+    let (mut reply, repo) = match form {
         AccessTokenRequest::AuthorizationCode(grant) => {
             authorization_code_grant(
                 &mut rng,
@@ -277,12 +436,16 @@ pub(crate) async fn post(
                 &activity_tracker,
                 &grant,
                 &client,
+                &http_client,
+                &encrypter,
                 &key_store,
                 &url_builder,
                 &site_config,
                 repo,
                 &homeserver,
                 user_agent,
+                dpop_jkt.clone(),
+                x5t_s256.clone(),
             )
             .await?
         }
@@ -293,9 +456,13 @@ pub(crate) async fn post(
                 &activity_tracker,
                 &grant,
                 &client,
+                &key_store,
+                &url_builder,
                 &site_config,
                 repo,
                 user_agent,
+                dpop_jkt.clone(),
+                x5t_s256.clone(),
             )
             .await?
         }
@@ -306,10 +473,52 @@ pub(crate) async fn post(
                 &activity_tracker,
                 &grant,
                 &client,
+                &key_store,
+                &url_builder,
+                &site_config,
+                repo,
+                policy,
+                user_agent,
+                dpop_jkt.clone(),
+                x5t_s256.clone(),
+            )
+            .await?
+        }
+        AccessTokenRequest::TokenExchange(grant) => {
+            token_exchange_grant(
+                &mut rng,
+                &clock,
+                &activity_tracker,
+                &grant,
+                &client,
+                &key_store,
+                &url_builder,
+                &site_config,
+                repo,
+                policy,
+                user_agent,
+                dpop_jkt.clone(),
+                x5t_s256.clone(),
+            )
+            .await?
+        }
+        AccessTokenRequest::JwtBearer(grant) => {
+            jwt_bearer_grant(
+                &mut rng,
+                &clock,
+                &activity_tracker,
+                &grant,
+                &client,
+                &http_client,
+                &key_store,
+                &url_builder,
                 &site_config,
                 repo,
+                &homeserver,
                 policy,
                 user_agent,
+                dpop_jkt.clone(),
+                x5t_s256.clone(),
             )
             .await?
         }
@@ -320,12 +529,16 @@ pub(crate) async fn post(
                 &activity_tracker,
                 &grant,
                 &client,
+                &http_client,
+                &encrypter,
                 &key_store,
                 &url_builder,
                 &site_config,
                 repo,
                 &homeserver,
                 user_agent,
+                dpop_jkt.clone(),
+                x5t_s256.clone(),
             )
             .await?
         }
@@ -336,6 +549,10 @@ pub(crate) async fn post(
 
     repo.save().await?;
 
+    if dpop_jkt.is_some() {
+        reply = reply.with_token_type(OAuthAccessTokenType::DPoP);
+    }
+
     TOKEN_REQUEST_COUNTER.add(
         1,
         &[
@@ -351,6 +568,46 @@ pub(crate) async fn post(
     Ok((headers, Json(reply)))
 }
 
+/// Validate the resource indicator sent when starting a new session at the
+/// token endpoint, as per RFC 8707
+fn requested_resources(resource: Option<&Url>) -> Result<Vec<Url>, RouteError> {
+    match resource {
+        // Resource indicators must not include a fragment
+        Some(resource) if resource.fragment().is_some() => Err(RouteError::InvalidResource),
+        Some(resource) => Ok(vec![resource.clone()]),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Check that the resource indicator sent when using an existing session is one
+/// of the resources the session is restricted to.
+///
+/// Tokens are always restricted to all the resources of their session, so this
+/// only rejects requests for resources which were not authorized.
+fn check_session_resource(session: &Session, resource: Option<&Url>) -> Result<(), RouteError> {
+    match resource {
+        Some(resource) if !session.resources.contains(resource) => Err(RouteError::InvalidResource),
+        _ => Ok(()),
+    }
+}
+
+/// Turn the policy violations into the right error, if any
+fn check_policy_result(res: mas_policy::EvaluationResult) -> Result<(), RouteError> {
+    if res.valid() {
+        return Ok(());
+    }
+
+    if res
+        .violations
+        .iter()
+        .any(|violation| violation.field.as_deref() == Some("resource"))
+    {
+        return Err(RouteError::InvalidTarget(res.violations));
+    }
+
+    Err(RouteError::DeniedByPolicy(res.violations))
+}
+
 #[allow(clippy::too_many_lines)] // TODO: refactor some parts out
 async fn authorization_code_grant(
     mut rng: &mut BoxRng,
@@ -358,12 +615,16 @@ async fn authorization_code_grant(
     activity_tracker: &BoundActivityTracker,
     grant: &AuthorizationCodeGrant,
     client: &Client,
+    http_client: &reqwest::Client,
+    encrypter: &Encrypter,
     key_store: &Keystore,
     url_builder: &UrlBuilder,
     site_config: &SiteConfig,
     mut repo: BoxRepository,
     homeserver: &Arc<dyn HomeserverConnection>,
     user_agent: Option<UserAgent>,
+    dpop_jkt: Option<String>,
+    x5t_s256: Option<String>,
 ) -> Result<(AccessTokenResponse, BoxRepository), RouteError> {
     // Check that the client is allowed to use this grant type
     if !client.grant_types.contains(&GrantType::AuthorizationCode) {
@@ -398,6 +659,9 @@ async fn authorization_code_grant(
                     .lookup(session_id)
                     .await?
                     .ok_or(RouteError::NoSuchOAuthSession)?;
+                repo.queue_job()
+                    .schedule_job(&mut rng, clock, SendBackchannelLogoutJob::new(&session))
+                    .await?;
                 repo.oauth2_session().finish(clock, session).await?;
                 repo.save().await?;
             }
@@ -441,6 +705,8 @@ async fn authorization_code_grant(
         return Err(RouteError::UnauthorizedClient);
     }
 
+    check_session_resource(&session, grant.resource.as_ref())?;
+
     match (code.pkce.as_ref(), grant.code_verifier.as_ref()) {
         (None, None) => {}
         // We have a challenge but no verifier (or vice-versa)? Bad request.
@@ -468,11 +734,38 @@ async fn authorization_code_grant(
         .await?;
 
     let ttl = site_config.access_token_ttl;
-    let (access_token, refresh_token) =
-        generate_token_pair(&mut rng, clock, &mut repo, &session, ttl).await?;
+    let access_token_str = generate_access_token(
+        &mut rng,
+        clock,
+        &mut repo,
+        url_builder,
+        key_store,
+        client,
+        &session,
+        ttl,
+        dpop_jkt.as_deref(),
+        x5t_s256.as_deref(),
+    )
+    .await?;
+    let (access_token, refresh_token) = generate_token_pair(
+        &mut rng,
+        clock,
+        &mut repo,
+        &session,
+        access_token_str,
+        ttl,
+        dpop_jkt,
+        x5t_s256,
+    )
+    .await?;
 
     let id_token = if session.scope.contains(&scope::OPENID) {
-        Some(generate_id_token(
+        let claims = id_token_claims(authz_grant.claims.as_ref());
+        let user_claims = user_claims(&**homeserver, &browser_session.user, &claims).await;
+        let subject =
+            subject_for_client(&mut rng, clock, &mut repo, client, &browser_session.user).await?;
+
+        let id_token = generate_id_token(
             &mut rng,
             clock,
             url_builder,
@@ -480,9 +773,13 @@ async fn authorization_code_grant(
             client,
             Some(&authz_grant),
             &browser_session,
+            &subject,
             Some(&access_token),
             last_authentication.as_ref(),
-        )?)
+            user_claims,
+        )?;
+
+        Some(encrypt_id_token(&mut rng, http_client, encrypter, client, id_token).await?)
     } else {
         None
     };
@@ -526,6 +823,59 @@ async fn authorization_code_grant(
     Ok((params, repo))
 }
 
+/// End a session in which a refresh token was reused, as this means the
+/// token was likely stolen
+async fn revoke_session_on_refresh_token_reuse(
+    rng: &mut BoxRng,
+    clock: &impl Clock,
+    repo: &mut BoxRepository,
+    site_config: &SiteConfig,
+    client: &Client,
+    session: Session,
+    refresh_token_id: Ulid,
+) -> Result<(), RouteError> {
+    warn!(
+        oauth2_session.id = %session.id,
+        oauth2_client.id = %client.id,
+        user.id = session.user_id.map(tracing::field::display),
+        refresh_token.id = %refresh_token_id,
+        "Refresh token reused, revoking the session",
+    );
+    REFRESH_TOKEN_REUSE_COUNTER.add(1, &[]);
+
+    let user = if let Some(user_id) = session.user_id {
+        repo.user().lookup(user_id).await?
+    } else {
+        None
+    };
+
+    if let Some(user) = &user {
+        // Schedule a job to sync the devices of the user with the homeserver
+        repo.queue_job()
+            .schedule_job(&mut *rng, clock, SyncDevicesJob::new(user))
+            .await?;
+
+        if site_config.refresh_token_reuse_notification_enabled {
+            repo.queue_job()
+                .schedule_job(
+                    &mut *rng,
+                    clock,
+                    SendSessionRevokedEmailJob::new(user, &session),
+                )
+                .await?;
+        }
+    }
+
+    // Notify the client through the back-channel, if it registered for it
+    repo.queue_job()
+        .schedule_job(&mut *rng, clock, SendBackchannelLogoutJob::new(&session))
+        .await?;
+
+    repo.oauth2_session().finish(clock, session).await?;
+
+    Ok(())
+}
+
 #[allow(clippy::too_many_lines)]
 async fn refresh_token_grant(
     rng: &mut BoxRng,
@@ -533,9 +883,13 @@ async fn refresh_token_grant(
     activity_tracker: &BoundActivityTracker,
     grant: &RefreshTokenGrant,
     client: &Client,
+    key_store: &Keystore,
+    url_builder: &UrlBuilder,
     site_config: &SiteConfig,
     mut repo: BoxRepository,
     user_agent: Option<UserAgent>,
+    dpop_jkt: Option<String>,
+    x5t_s256: Option<String>,
 ) -> Result<(AccessTokenResponse, BoxRepository), RouteError> {
     // Check that the client is allowed to use this grant type
     if !client.grant_types.contains(&GrantType::RefreshToken) {
@@ -575,6 +929,14 @@ async fn refresh_token_grant(
         });
     }
 
+    // If the refresh token is bound to a DPoP key, the request must come with a
+    // proof of possession of the same key
+    if refresh_token.dpop_jkt.is_some() && refresh_token.dpop_jkt != dpop_jkt {
+        return Err(RouteError::DpopKeyMismatch(refresh_token.id));
+    }
+
+    check_session_resource(&session, grant.resource.as_ref())?;
+
     if !refresh_token.is_valid() {
         // We're seing a refresh token that already has been consumed, this might be a
         // double-refresh or a replay attack
@@ -599,8 +961,20 @@ async fn refresh_token_grant(
 
         // Check if the next refresh token was already consumed or not
         if !next_refresh_token.is_valid() {
-            // XXX: This is a replay, we *may* want to invalidate the session
-            return Err(RouteError::RefreshTokenInvalid(next_refresh_token.id));
+            // This is a replay: the token was rotated and the new one was used since.
+            // Treat it as stolen and revoke the whole session.
+            revoke_session_on_refresh_token_reuse(
+                rng,
+                clock,
+                &mut repo,
+                site_config,
+                client,
+                session,
+                refresh_token.id,
+            )
+            .await?;
+            repo.save().await?;
+            return Err(RouteError::RefreshTokenReused(refresh_token.id));
         }
 
         // Check if the associated access token was already used
@@ -623,8 +997,20 @@ async fn refresh_token_grant(
             })?;
 
         if next_access_token.is_used() {
-            // XXX: This is a replay, we *may* want to invalidate the session
-            return Err(RouteError::RefreshTokenInvalid(next_refresh_token.id));
+            // This is a replay: the tokens issued on the previous refresh were already
+            // used. Treat it as stolen and revoke the whole session.
+            revoke_session_on_refresh_token_reuse(
+                rng,
+                clock,
+                &mut repo,
+                site_config,
+                client,
+                session,
+                refresh_token.id,
+            )
+            .await?;
+            repo.save().await?;
+            return Err(RouteError::RefreshTokenReused(refresh_token.id));
         }
 
         // Looks like it's a double-refresh, client lost their refresh token on
@@ -651,8 +1037,30 @@ async fn refresh_token_grant(
         .await;
 
     let ttl = site_config.access_token_ttl;
-    let (new_access_token, new_refresh_token) =
-        generate_token_pair(rng, clock, &mut repo, &session, ttl).await?;
+    let access_token_str = generate_access_token(
+        rng,
+        clock,
+        &mut repo,
+        url_builder,
+        key_store,
+        client,
+        &session,
+        ttl,
+        dpop_jkt.as_deref(),
+        x5t_s256.as_deref(),
+    )
+    .await?;
+    let (new_access_token, new_refresh_token) = generate_token_pair(
+        rng,
+        clock,
+        &mut repo,
+        &session,
+        access_token_str,
+        ttl,
+        dpop_jkt,
+        x5t_s256,
+    )
+    .await?;
 
     let refresh_token = repo
         .oauth2_refresh_token()
@@ -685,10 +1093,14 @@ async fn client_credentials_grant(
     activity_tracker: &BoundActivityTracker,
     grant: &ClientCredentialsGrant,
     client: &Client,
+    key_store: &Keystore,
+    url_builder: &UrlBuilder,
     site_config: &SiteConfig,
     mut repo: BoxRepository,
     mut policy: Policy,
     user_agent: Option<UserAgent>,
+    dpop_jkt: Option<String>,
+    x5t_s256: Option<String>,
 ) -> Result<(AccessTokenResponse, BoxRepository), RouteError> {
     // Check that the client is allowed to use this grant type
     if !client.grant_types.contains(&GrantType::ClientCredentials) {
@@ -701,22 +1113,24 @@ async fn client_credentials_grant(
         .clone()
         .unwrap_or_else(|| std::iter::empty::<ScopeToken>().collect());
 
+    let resources = requested_resources(grant.resource.as_ref())?;
+
     // Make the request go through the policy engine
     let res = policy
         .evaluate_authorization_grant(mas_policy::AuthorizationGrantInput {
             user: None,
             client,
             scope: &scope,
+            resources: &resources,
             grant_type: mas_policy::GrantType::ClientCredentials,
+            authentication_methods: Vec::new(),
             requester: mas_policy::Requester {
                 ip_address: activity_tracker.ip(),
                 user_agent: user_agent.clone().map(|ua| ua.raw),
             },
         })
         .await?;
-    if !res.valid() {
-        return Err(RouteError::DeniedByPolicy(res.violations));
-    }
+    check_policy_result(res)?;
 
     // Start the session
     let mut session = repo
@@ -731,12 +1145,39 @@ async fn client_credentials_grant(
             .await?;
     }
 
+    if !resources.is_empty() {
+        session = repo
+            .oauth2_session()
+            .set_resources(session, resources)
+            .await?;
+    }
+
     let ttl = site_config.access_token_ttl;
-    let access_token_str = TokenType::AccessToken.generate(rng);
+    let access_token_str = generate_access_token(
+        rng,
+        clock,
+        &mut repo,
+        url_builder,
+        key_store,
+        client,
+        &session,
+        ttl,
+        dpop_jkt.as_deref(),
+        x5t_s256.as_deref(),
+    )
+    .await?;
 
     let access_token = repo
         .oauth2_access_token()
-        .add(rng, clock, &session, access_token_str, Some(ttl))
+        .add(
+            rng,
+            clock,
+            &session,
+            access_token_str,
+            Some(ttl),
+            dpop_jkt,
+            x5t_s256,
+        )
         .await?;
 
     let mut params = AccessTokenResponse::new(access_token.access_token).with_expires_in(ttl);
@@ -756,74 +1197,151 @@ async fn client_credentials_grant(
     Ok((params, repo))
 }
 
-async fn device_code_grant(
+#[allow(clippy::too_many_lines, clippy::too_many_arguments)]
+async fn jwt_bearer_grant(
     rng: &mut BoxRng,
     clock: &impl Clock,
     activity_tracker: &BoundActivityTracker,
-    grant: &DeviceCodeGrant,
+    grant: &JwtBearerGrant,
     client: &Client,
+    http_client: &reqwest::Client,
     key_store: &Keystore,
     url_builder: &UrlBuilder,
     site_config: &SiteConfig,
     mut repo: BoxRepository,
     homeserver: &Arc<dyn HomeserverConnection>,
+    mut policy: Policy,
     user_agent: Option<UserAgent>,
+    dpop_jkt: Option<String>,
+    x5t_s256: Option<String>,
 ) -> Result<(AccessTokenResponse, BoxRepository), RouteError> {
-    // Check that the client is allowed to use this grant type
-    if !client.grant_types.contains(&GrantType::DeviceCode) {
+    // Check that the client is allowed to use this grant type, and on behalf of
+    // which user
+    if !client.grant_types.contains(&GrantType::JwtBearer) {
         return Err(RouteError::UnauthorizedClient);
     }
 
-    let grant = repo
-        .oauth2_device_code_grant()
-        .find_by_device_code(&grant.device_code)
-        .await?
-        .ok_or(RouteError::GrantNotFound)?;
+    let subject = client
+        .jwt_bearer_subject
+        .as_deref()
+        .ok_or(RouteError::UnauthorizedClient)?;
 
-    // Check that the client match
-    if client.id != grant.client_id {
-        return Err(RouteError::ClientIDMismatch {
-            expected: grant.client_id,
-            actual: client.id,
-        });
-    }
+    // The assertion must be signed by one of the client keys
+    let jwks = client.jwks.as_ref().ok_or(RouteError::UnauthorizedClient)?;
+    let jwks = fetch_jwks(http_client, jwks)
+        .await
+        .map_err(RouteError::Internal)?;
 
-    if grant.expires_at < clock.now() {
-        return Err(RouteError::DeviceCodeExpired);
-    }
+    let jwt: Jwt<'_, HashMap<String, serde_json::Value>> =
+        Jwt::try_from(grant.assertion.as_str()).map_err(|_| RouteError::InvalidAssertion)?;
+    jwt.verify_with_jwks(&jwks)
+        .map_err(|_| RouteError::InvalidAssertion)?;
 
-    let browser_session_id = match &grant.state {
-        DeviceCodeGrantState::Pending => {
-            return Err(RouteError::DeviceCodePending);
-        }
-        DeviceCodeGrantState::Rejected { .. } => {
-            return Err(RouteError::DeviceCodeRejected);
-        }
-        DeviceCodeGrantState::Exchanged { .. } => {
-            return Err(RouteError::DeviceCodeExchanged);
+    let (_header, mut claims) = jwt.into_parts();
+
+    // The assertion is issued by the client itself, for the configured user, to
+    // be used at our token endpoint
+    let now = clock.now();
+    let time_options = TimeOptions::new(now);
+    let token_endpoint = url_builder.oauth_token_endpoint().to_string();
+    claims::ISS
+        .extract_required_with_options(&mut claims, client.client_id.as_str())
+        .map_err(|_| RouteError::InvalidAssertion)?;
+    let sub = claims::SUB
+        .extract_required(&mut claims)
+        .map_err(|_| RouteError::InvalidAssertion)?;
+    claims::AUD
+        .extract_required_with_options(&mut claims, &token_endpoint)
+        .map_err(|_| RouteError::InvalidAssertion)?;
+    let exp = claims::EXP
+        .extract_required_with_options(&mut claims, &time_options)
+        .map_err(|_| RouteError::InvalidAssertion)?;
+    claims::NBF
+        .extract_optional_with_options(&mut claims, &time_options)
+        .map_err(|_| RouteError::InvalidAssertion)?;
+    claims::IAT
+        .extract_optional_with_options(&mut claims, &time_options)
+        .map_err(|_| RouteError::InvalidAssertion)?;
+    let jti = claims::JTI
+        .extract_required(&mut claims)
+        .map_err(|_| RouteError::InvalidAssertion)?;
+
+    // Pairwise clients refer to the user with the subject identifier they were
+    // given for it, so it needs to be mapped back to the user first
+    let sub = match client.sector_identifier() {
+        Some(sector_identifier) => {
+            let user_id = repo
+                .oauth2_pairwise_subject()
+                .find_user_id(sector_identifier, &sub)
+                .await?
+                .ok_or(RouteError::InvalidAssertion)?;
+
+            repo.user()
+                .lookup(user_id)
+                .await?
+                .ok_or(RouteError::InvalidGrant)?
+                .username
         }
-        DeviceCodeGrantState::Fulfilled {
-            browser_session_id, ..
-        } => browser_session_id,
+        None => sub,
     };
 
-    let browser_session = repo
-        .browser_session()
-        .lookup(*browser_session_id)
+    if sub != subject {
+        return Err(RouteError::InvalidAssertion);
+    }
+
+    // Long-lived assertions would be as good as long-lived tokens
+    if *exp > now + MAX_ASSERTION_LIFETIME {
+        return Err(RouteError::InvalidAssertion);
+    }
+
+    // Each assertion can only be used once. It is remembered until it expires,
+    // after which it would be rejected anyway.
+    let first_use = repo
+        .oauth2_jwt_bearer_assertion()
+        .record(clock, client, &jti, *exp)
+        .await?;
+    if !first_use {
+        return Err(RouteError::InvalidAssertion);
+    }
+
+    let user = repo
+        .user()
+        .find_by_username(subject)
         .await?
-        .ok_or(RouteError::NoSuchBrowserSession)?;
+        .filter(mas_data_model::User::is_valid)
+        .ok_or(RouteError::InvalidGrant)?;
+
+    // Default to an empty scope if none is provided
+    let scope = grant
+        .scope
+        .clone()
+        .unwrap_or_else(|| std::iter::empty::<ScopeToken>().collect());
+
+    let resources = requested_resources(grant.resource.as_ref())?;
+
+    // Make the request go through the policy engine
+    let res = policy
+        .evaluate_authorization_grant(mas_policy::AuthorizationGrantInput {
+            user: Some(&user),
+            client,
+            scope: &scope,
+            resources: &resources,
+            grant_type: mas_policy::GrantType::JwtBearer,
+            authentication_methods: Vec::new(),
+            requester: mas_policy::Requester {
+                ip_address: activity_tracker.ip(),
+                user_agent: user_agent.clone().map(|ua| ua.raw),
+            },
+        })
+        .await?;
+    check_policy_result(res)?;
 
     // Start the session
     let mut session = repo
         .oauth2_session()
-        .add_from_browser_session(rng, clock, client, &browser_session, grant.scope.clone())
-        .await?;
-
-    repo.oauth2_device_code_grant()
-        .exchange(clock, grant, &session)
+        .add(rng, clock, client, Some(&user), None, scope)
         .await?;
 
-    // XXX: should we get the user agent from the device code grant instead?
     if let Some(user_agent) = user_agent {
         session = repo
             .oauth2_session()
@@ -831,24 +1349,420 @@ async fn device_code_grant(
             .await?;
     }
 
+    if !resources.is_empty() {
+        session = repo
+            .oauth2_session()
+            .set_resources(session, resources)
+            .await?;
+    }
+
     let ttl = site_config.access_token_ttl;
-    let access_token_str = TokenType::AccessToken.generate(rng);
+    let access_token_str = generate_access_token(
+        rng,
+        clock,
+        &mut repo,
+        url_builder,
+        key_store,
+        client,
+        &session,
+        ttl,
+        dpop_jkt.as_deref(),
+        x5t_s256.as_deref(),
+    )
+    .await?;
 
     let access_token = repo
         .oauth2_access_token()
-        .add(rng, clock, &session, access_token_str, Some(ttl))
+        .add(
+            rng,
+            clock,
+            &session,
+            access_token_str,
+            Some(ttl),
+            dpop_jkt,
+            x5t_s256,
+        )
         .await?;
 
-    let mut params =
-        AccessTokenResponse::new(access_token.access_token.clone()).with_expires_in(ttl);
+    // Lock the user sync to make sure we don't get into a race condition
+    repo.user().acquire_lock_for_sync(&user).await?;
 
-    // If the client uses the refresh token grant type, we also generate a refresh
-    // token
-    if client.grant_types.contains(&GrantType::RefreshToken) {
-        let refresh_token_str = TokenType::RefreshToken.generate(rng);
+    // Look for device to provision
+    let mxid = homeserver.mxid(&user.username);
+    for scope in &*session.scope {
+        if let Some(device) = Device::from_scope_token(scope) {
+            homeserver
+                .create_device(&mxid, device.as_str())
+                .await
+                .map_err(RouteError::ProvisionDeviceFailed)?;
+        }
+    }
 
-        let refresh_token = repo
-            .oauth2_refresh_token()
+    let mut params = AccessTokenResponse::new(access_token.access_token).with_expires_in(ttl);
+
+    // XXX: there is a potential (but unlikely) race here, where the activity for
+    // the session is recorded before the transaction is committed. We would have to
+    // save the repository here to fix that.
+    activity_tracker
+        .record_oauth2_session(clock, &session)
+        .await;
+
+    if !session.scope.is_empty() {
+        // We only return the scope if it's not empty
+        params = params.with_scope(session.scope);
+    }
+
+    Ok((params, repo))
+}
+
+/// Lookup an access token presented in a token exchange, along with its
+/// session
+///
+/// Returns [`None`] if the token is unknown, expired or revoked, or if its
+/// session is finished
+async fn lookup_exchanged_access_token(
+    clock: &impl Clock,
+    repo: &mut BoxRepository,
+    token: &str,
+) -> Result<Option<(AccessToken, Session)>, RouteError> {
+    let Some(access_token) = repo.oauth2_access_token().find_by_token(token).await? else {
+        return Ok(None);
+    };
+
+    if !access_token.is_valid(clock.now()) {
+        return Ok(None);
+    }
+
+    let session = repo
+        .oauth2_session()
+        .lookup(access_token.session_id)
+        .await?
+        .ok_or(RouteError::NoSuchOAuthSession)?;
+
+    if !session.is_valid() {
+        return Ok(None);
+    }
+
+    Ok(Some((access_token, session)))
+}
+
+/// Exchange a user access token for a new, down-scoped one (RFC 8693).
+///
+/// The exchanged token lives in a child session of the subject token's
+/// session: finishing the parent session also finishes the child one, and the
+/// new token never expires after the subject token.
+#[allow(clippy::too_many_lines)]
+async fn token_exchange_grant(
+    rng: &mut BoxRng,
+    clock: &impl Clock,
+    activity_tracker: &BoundActivityTracker,
+    grant: &TokenExchangeGrant,
+    client: &Client,
+    key_store: &Keystore,
+    url_builder: &UrlBuilder,
+    site_config: &SiteConfig,
+    mut repo: BoxRepository,
+    mut policy: Policy,
+    user_agent: Option<UserAgent>,
+    dpop_jkt: Option<String>,
+    x5t_s256: Option<String>,
+) -> Result<(AccessTokenResponse, BoxRepository), RouteError> {
+    // Check that the client is allowed to use this grant type
+    if !client.grant_types.contains(&GrantType::TokenExchange) {
+        return Err(RouteError::UnauthorizedClient);
+    }
+
+    // We only support exchanging access tokens for other access tokens
+    if grant.subject_token_type != TokenTypeIdentifier::AccessToken {
+        return Err(RouteError::UnsupportedTokenType(
+            grant.subject_token_type.clone(),
+        ));
+    }
+
+    if let Some(requested_token_type) = &grant.requested_token_type {
+        if *requested_token_type != TokenTypeIdentifier::AccessToken {
+            return Err(RouteError::UnsupportedTokenType(
+                requested_token_type.clone(),
+            ));
+        }
+    }
+
+    let (subject_token, subject_session) =
+        lookup_exchanged_access_token(clock, &mut repo, &grant.subject_token)
+            .await?
+            .ok_or(RouteError::InvalidSubjectToken)?;
+
+    // If the subject token is bound to a DPoP key, the request must come with a
+    // proof of possession of the same key
+    if subject_token.dpop_jkt.is_some() && subject_token.dpop_jkt != dpop_jkt {
+        return Err(RouteError::DpopKeyMismatch(subject_token.id));
+    }
+
+    // Only tokens issued to users can be exchanged
+    let Some(user_id) = subject_session.user_id else {
+        return Err(RouteError::InvalidSubjectToken);
+    };
+
+    let user = repo
+        .user()
+        .lookup(user_id)
+        .await?
+        .filter(mas_data_model::User::is_valid)
+        .ok_or(RouteError::InvalidSubjectToken)?;
+
+    let subject_client = repo
+        .oauth2_client()
+        .lookup(subject_session.client_id)
+        .await?
+        .ok_or(RouteError::InvalidSubjectToken)?;
+
+    let actor_client = match (&grant.actor_token, &grant.actor_token_type) {
+        (None, None) => None,
+        (Some(actor_token), Some(TokenTypeIdentifier::AccessToken)) => {
+            let (_, actor_session) = lookup_exchanged_access_token(clock, &mut repo, actor_token)
+                .await?
+                .ok_or(RouteError::InvalidActorToken)?;
+
+            let actor_client = repo
+                .oauth2_client()
+                .lookup(actor_session.client_id)
+                .await?
+                .ok_or(RouteError::InvalidActorToken)?;
+
+            Some(actor_client)
+        }
+        (Some(_), Some(actor_token_type)) => {
+            return Err(RouteError::UnsupportedTokenType(actor_token_type.clone()));
+        }
+        // The actor_token and actor_token_type parameters go together
+        (Some(_), None) | (None, Some(_)) => return Err(RouteError::BadRequest),
+    };
+
+    // The requested scope must be a subset of the scope of the subject token
+    let scope = grant
+        .scope
+        .clone()
+        .unwrap_or_else(|| subject_session.scope.clone());
+    if !scope
+        .iter()
+        .all(|token| subject_session.scope.contains(token))
+    {
+        return Err(RouteError::InvalidScope);
+    }
+
+    // Make the request go through the policy engine
+    let res = policy
+        .evaluate_token_exchange(mas_policy::TokenExchangeInput {
+            user: &user,
+            client,
+            subject_client: &subject_client,
+            actor_client: actor_client.as_ref(),
+            scope: &scope,
+            audience: grant.audience.as_deref(),
+            requester: mas_policy::Requester {
+                ip_address: activity_tracker.ip(),
+                user_agent: user_agent.clone().map(|ua| ua.raw),
+            },
+        })
+        .await?;
+    if !res.valid() {
+        if res
+            .violations
+            .iter()
+            .any(|violation| violation.field.as_deref() == Some("audience"))
+        {
+            return Err(RouteError::InvalidTarget(res.violations));
+        }
+
+        return Err(RouteError::DeniedByPolicy(res.violations));
+    }
+
+    // Start the session
+    let mut session = repo
+        .oauth2_session()
+        .add_from_token_exchange(
+            rng,
+            clock,
+            client,
+            &subject_session,
+            actor_client.as_ref(),
+            grant.audience.clone(),
+            scope,
+        )
+        .await?;
+
+    if let Some(user_agent) = user_agent {
+        session = repo
+            .oauth2_session()
+            .record_user_agent(session, user_agent)
+            .await?;
+    }
+
+    // The new token can't outlive the subject token
+    let mut ttl = site_config.access_token_ttl;
+    if let Some(expires_at) = subject_token.expires_at {
+        ttl = ttl.min(expires_at - clock.now());
+    }
+
+    let access_token_str = generate_access_token(
+        rng,
+        clock,
+        &mut repo,
+        url_builder,
+        key_store,
+        client,
+        &session,
+        ttl,
+        dpop_jkt.as_deref(),
+        x5t_s256.as_deref(),
+    )
+    .await?;
+    let access_token = repo
+        .oauth2_access_token()
+        .add(
+            rng,
+            clock,
+            &session,
+            access_token_str,
+            Some(ttl),
+            dpop_jkt,
+            x5t_s256,
+        )
+        .await?;
+
+    // XXX: there is a potential (but unlikely) race here, where the activity for
+    // the session is recorded before the transaction is committed. We would have to
+    // save the repository here to fix that.
+    activity_tracker
+        .record_oauth2_session(clock, &session)
+        .await;
+
+    let params = AccessTokenResponse::new(access_token.access_token)
+        .with_expires_in(ttl)
+        .with_scope(session.scope)
+        .with_issued_token_type(TokenTypeIdentifier::AccessToken);
+
+    Ok((params, repo))
+}
+
+#[allow(clippy::too_many_lines)]
+async fn device_code_grant(
+    rng: &mut BoxRng,
+    clock: &impl Clock,
+    activity_tracker: &BoundActivityTracker,
+    grant: &DeviceCodeGrant,
+    client: &Client,
+    http_client: &reqwest::Client,
+    encrypter: &Encrypter,
+    key_store: &Keystore,
+    url_builder: &UrlBuilder,
+    site_config: &SiteConfig,
+    mut repo: BoxRepository,
+    homeserver: &Arc<dyn HomeserverConnection>,
+    user_agent: Option<UserAgent>,
+    dpop_jkt: Option<String>,
+    x5t_s256: Option<String>,
+) -> Result<(AccessTokenResponse, BoxRepository), RouteError> {
+    // Check that the client is allowed to use this grant type
+    if !client.grant_types.contains(&GrantType::DeviceCode) {
+        return Err(RouteError::UnauthorizedClient);
+    }
+
+    let grant = repo
+        .oauth2_device_code_grant()
+        .find_by_device_code(&grant.device_code)
+        .await?
+        .ok_or(RouteError::GrantNotFound)?;
+
+    // Check that the client match
+    if client.id != grant.client_id {
+        return Err(RouteError::ClientIDMismatch {
+            expected: grant.client_id,
+            actual: client.id,
+        });
+    }
+
+    if grant.expires_at < clock.now() {
+        return Err(RouteError::DeviceCodeExpired);
+    }
+
+    let browser_session_id = match &grant.state {
+        DeviceCodeGrantState::Pending => {
+            return Err(RouteError::DeviceCodePending);
+        }
+        DeviceCodeGrantState::Rejected { .. } => {
+            return Err(RouteError::DeviceCodeRejected);
+        }
+        DeviceCodeGrantState::Exchanged { .. } => {
+            return Err(RouteError::DeviceCodeExchanged);
+        }
+        DeviceCodeGrantState::Fulfilled {
+            browser_session_id, ..
+        } => browser_session_id,
+    };
+
+    let browser_session = repo
+        .browser_session()
+        .lookup(*browser_session_id)
+        .await?
+        .ok_or(RouteError::NoSuchBrowserSession)?;
+
+    // Start the session
+    let mut session = repo
+        .oauth2_session()
+        .add_from_browser_session(rng, clock, client, &browser_session, grant.scope.clone())
+        .await?;
+
+    repo.oauth2_device_code_grant()
+        .exchange(clock, grant, &session)
+        .await?;
+
+    // XXX: should we get the user agent from the device code grant instead?
+    if let Some(user_agent) = user_agent {
+        session = repo
+            .oauth2_session()
+            .record_user_agent(session, user_agent)
+            .await?;
+    }
+
+    let ttl = site_config.access_token_ttl;
+    let access_token_str = generate_access_token(
+        rng,
+        clock,
+        &mut repo,
+        url_builder,
+        key_store,
+        client,
+        &session,
+        ttl,
+        dpop_jkt.as_deref(),
+        x5t_s256.as_deref(),
+    )
+    .await?;
+
+    let access_token = repo
+        .oauth2_access_token()
+        .add(
+            rng,
+            clock,
+            &session,
+            access_token_str,
+            Some(ttl),
+            dpop_jkt,
+            x5t_s256,
+        )
+        .await?;
+
+    let mut params =
+        AccessTokenResponse::new(access_token.access_token.clone()).with_expires_in(ttl);
+
+    // If the client uses the refresh token grant type, we also generate a refresh
+    // token
+    if client.grant_types.contains(&GrantType::RefreshToken) {
+        let refresh_token_str = TokenType::RefreshToken.generate(rng);
+
+        let refresh_token = repo
+            .oauth2_refresh_token()
             .add(rng, clock, &session, &access_token, refresh_token_str)
             .await?;
 
@@ -857,6 +1771,8 @@ async fn device_code_grant(
 
     // If the client asked for an ID token, we generate one
     if session.scope.contains(&scope::OPENID) {
+        let subject =
+            subject_for_client(rng, clock, &mut repo, client, &browser_session.user).await?;
         let id_token = generate_id_token(
             rng,
             clock,
@@ -865,9 +1781,12 @@ async fn device_code_grant(
             client,
             None,
             &browser_session,
+            &subject,
             Some(&access_token),
             None,
+            HashMap::new(),
         )?;
+        let id_token = encrypt_id_token(rng, http_client, encrypter, client, id_token).await?;
 
         params = params.with_id_token(id_token);
     }
@@ -905,18 +1824,29 @@ async fn device_code_grant(
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
+    use base64ct::{Base64, Base64UrlUnpadded, Encoding};
     use hyper::Request;
-    use mas_data_model::{AccessToken, AuthorizationCode, RefreshToken};
+    use mas_data_model::{AccessToken, AccessTokenFormat, AuthorizationCode, RefreshToken};
+    use mas_iana::{jose::JsonWebSignatureAlg, oauth::OAuthClientAuthenticationMethod};
+    use mas_jose::{
+        claims,
+        jwk::{JsonWebKeyPublicParameters, PublicJsonWebKey, PublicJsonWebKeySet},
+        jwt::{JsonWebSignatureHeader, Jwt},
+    };
+    use mas_keystore::PrivateKey;
     use mas_router::SimpleRoute;
     use oauth2_types::{
         registration::ClientRegistrationResponse,
-        requests::{DeviceAuthorizationResponse, ResponseMode},
+        requests::{DeviceAuthorizationResponse, IntrospectionResponse, ResponseMode},
         scope::{OPENID, Scope},
     };
-    use sqlx::PgPool;
+    use sha2::{Digest, Sha256};
+    use sqlx::{PgPool, types::Json};
 
     use super::*;
-    use crate::test_utils::{RequestBuilderExt, ResponseExt, TestState, setup};
+    use crate::test_utils::{RequestBuilderExt, ResponseExt, TestState, setup, test_site_config};
 
     #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
     async fn test_auth_code_grant(pool: PgPool) {
@@ -983,6 +1913,8 @@ mod tests {
                 false,
                 false,
                 None,
+                None,
+                Vec::new(),
             )
             .await
             .unwrap();
@@ -1084,6 +2016,8 @@ mod tests {
                 false,
                 false,
                 None,
+                None,
+                Vec::new(),
             )
             .await
             .unwrap();
@@ -1192,7 +2126,10 @@ mod tests {
                 &state.clock,
                 &mut repo,
                 &session,
+                TokenType::AccessToken.generate(&mut state.rng()),
                 Duration::microseconds(5 * 60 * 1000 * 1000),
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -1225,7 +2162,22 @@ mod tests {
         // Check that the old token is no longer valid
         assert!(!state.is_access_token_valid(&old_access_token).await);
 
-        // Call it again with the old token, it should fail
+        // Call it again with the new token, it should work
+        let request =
+            Request::post(mas_router::OAuth2TokenEndpoint::PATH).form(serde_json::json!({
+                "grant_type": "refresh_token",
+                "refresh_token": refresh_token,
+                "client_id": client.client_id,
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let response: AccessTokenResponse = response.json();
+        let access_token = response.access_token;
+        let refresh_token = response.refresh_token.expect("to have a refresh token");
+
+        // Call it again with the old token, it should fail, and revoke the session
+        // as the refresh token was reused
         let request =
             Request::post(mas_router::OAuth2TokenEndpoint::PATH).form(serde_json::json!({
                 "grant_type": "refresh_token",
@@ -1238,7 +2190,9 @@ mod tests {
         let ClientError { error, .. } = response.json();
         assert_eq!(error, ClientErrorCode::InvalidGrant);
 
-        // Call it again with the new token, it should work
+        // The latest tokens should not be usable anymore
+        assert!(!state.is_access_token_valid(&access_token).await);
+
         let request =
             Request::post(mas_router::OAuth2TokenEndpoint::PATH).form(serde_json::json!({
                 "grant_type": "refresh_token",
@@ -1247,8 +2201,9 @@ mod tests {
             }));
 
         let response = state.request(request).await;
-        response.assert_status(StatusCode::OK);
-        let _: AccessTokenResponse = response.json();
+        response.assert_status(StatusCode::BAD_REQUEST);
+        let ClientError { error, .. } = response.json();
+        assert_eq!(error, ClientErrorCode::InvalidGrant);
     }
 
     #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
@@ -1314,7 +2269,10 @@ mod tests {
                 &state.clock,
                 &mut repo,
                 &session,
+                TokenType::AccessToken.generate(&mut state.rng()),
                 Duration::microseconds(5 * 60 * 1000 * 1000),
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -1379,17 +2337,64 @@ mod tests {
         let third_response = state.request(request).await;
         third_response.assert_status(StatusCode::BAD_REQUEST);
 
-        // The other reason we consider a new refresh token to be 'used' is if
-        // it was already used in a refresh
-        // So, if we do a refresh with the second_response.refresh_token, then
-        // another refresh with the result, redoing one with
-        // second_response.refresh_token again should fail
-        let request =
-            Request::post(mas_router::OAuth2TokenEndpoint::PATH).form(serde_json::json!({
-                "grant_type": "refresh_token",
-                "refresh_token": second_response.refresh_token,
-                "client_id": client.client_id,
-            }));
+        // This was a refresh token reuse, so the whole session got revoked
+        assert!(
+            !state
+                .is_access_token_valid(&second_response.access_token)
+                .await
+        );
+
+        // Start over with a new session
+        let mut repo = state.repository().await.unwrap();
+        let session = repo
+            .oauth2_session()
+            .add_from_browser_session(
+                &mut state.rng(),
+                &state.clock,
+                &client,
+                &browser_session,
+                Scope::from_iter([OPENID]),
+            )
+            .await
+            .unwrap();
+
+        let (_, RefreshToken { refresh_token, .. }) = generate_token_pair(
+            &mut state.rng(),
+            &state.clock,
+            &mut repo,
+            &session,
+            TokenType::AccessToken.generate(&mut state.rng()),
+            Duration::microseconds(5 * 60 * 1000 * 1000),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        repo.save().await.unwrap();
+
+        let request =
+            Request::post(mas_router::OAuth2TokenEndpoint::PATH).form(serde_json::json!({
+                "grant_type": "refresh_token",
+                "refresh_token": refresh_token,
+                "client_id": client.client_id,
+            }));
+
+        let second_response = state.request(request).await;
+        second_response.assert_status(StatusCode::OK);
+        let second_response: AccessTokenResponse = second_response.json();
+
+        // The other reason we consider a new refresh token to be 'used' is if
+        // it was already used in a refresh
+        // So, if we do a refresh with the second_response.refresh_token, then
+        // another refresh with the result, redoing one with
+        // second_response.refresh_token again should fail
+        let request =
+            Request::post(mas_router::OAuth2TokenEndpoint::PATH).form(serde_json::json!({
+                "grant_type": "refresh_token",
+                "refresh_token": second_response.refresh_token,
+                "client_id": client.client_id,
+            }));
 
         // This one is fine
         let fourth_response = state.request(request).await;
@@ -1418,6 +2423,169 @@ mod tests {
 
         let sixth_response = state.request(request).await;
         sixth_response.assert_status(StatusCode::BAD_REQUEST);
+
+        // This was a refresh token reuse, so the whole session got revoked
+        let fifth_response: AccessTokenResponse = fifth_response.json();
+        assert!(
+            !state
+                .is_access_token_valid(&fifth_response.access_token)
+                .await
+        );
+
+        // Which means the original refresh token can't be used either anymore
+        let request =
+            Request::post(mas_router::OAuth2TokenEndpoint::PATH).form(serde_json::json!({
+                "grant_type": "refresh_token",
+                "refresh_token": refresh_token,
+                "client_id": client.client_id,
+            }));
+
+        let seventh_response = state.request(request).await;
+        seventh_response.assert_status(StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_refresh_token_reuse(pool: PgPool) {
+        setup();
+        let state = TestState::from_pool_with_site_config(
+            pool.clone(),
+            SiteConfig {
+                refresh_token_reuse_notification_enabled: true,
+                ..test_site_config()
+            },
+        )
+        .await
+        .unwrap();
+
+        // Provision a client
+        let request =
+            Request::post(mas_router::OAuth2RegistrationEndpoint::PATH).json(serde_json::json!({
+                "client_uri": "https://example.com/",
+                "redirect_uris": ["https://example.com/callback"],
+                "token_endpoint_auth_method": "none",
+                "response_types": ["code"],
+                "grant_types": ["authorization_code", "refresh_token"],
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CREATED);
+
+        let ClientRegistrationResponse { client_id, .. } = response.json();
+
+        // Provision a user with a session
+        let mut repo = state.repository().await.unwrap();
+
+        let user = repo
+            .user()
+            .add(&mut state.rng(), &state.clock, "alice".to_owned())
+            .await
+            .unwrap();
+
+        let browser_session = repo
+            .browser_session()
+            .add(&mut state.rng(), &state.clock, &user, None)
+            .await
+            .unwrap();
+
+        let client = repo
+            .oauth2_client()
+            .find_by_client_id(&client_id)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let session = repo
+            .oauth2_session()
+            .add_from_browser_session(
+                &mut state.rng(),
+                &state.clock,
+                &client,
+                &browser_session,
+                Scope::from_iter([OPENID]),
+            )
+            .await
+            .unwrap();
+
+        let (_, RefreshToken { refresh_token, .. }) = generate_token_pair(
+            &mut state.rng(),
+            &state.clock,
+            &mut repo,
+            &session,
+            TokenType::AccessToken.generate(&mut state.rng()),
+            Duration::microseconds(5 * 60 * 1000 * 1000),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        repo.save().await.unwrap();
+
+        // Refresh the token, and use the new access token. This ends the window in
+        // which the old refresh token can still be used, in case the client lost
+        // the response
+        let request =
+            Request::post(mas_router::OAuth2TokenEndpoint::PATH).form(serde_json::json!({
+                "grant_type": "refresh_token",
+                "refresh_token": refresh_token,
+                "client_id": client.client_id,
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let response: AccessTokenResponse = response.json();
+        let new_access_token = response.access_token;
+        let new_refresh_token = response.refresh_token.expect("to have a refresh token");
+        assert!(state.is_access_token_valid(&new_access_token).await);
+
+        // Replaying the consumed refresh token is now considered a theft
+        let request =
+            Request::post(mas_router::OAuth2TokenEndpoint::PATH).form(serde_json::json!({
+                "grant_type": "refresh_token",
+                "refresh_token": refresh_token,
+                "client_id": client.client_id,
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        let ClientError { error, .. } = response.json();
+        assert_eq!(error, ClientErrorCode::InvalidGrant);
+
+        // The session was revoked
+        let mut repo = state.repository().await.unwrap();
+        let session = repo
+            .oauth2_session()
+            .lookup(session.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(session.is_finished());
+        repo.save().await.unwrap();
+
+        // And with it, all the tokens of the session
+        assert!(!state.is_access_token_valid(&new_access_token).await);
+
+        let request =
+            Request::post(mas_router::OAuth2TokenEndpoint::PATH).form(serde_json::json!({
+                "grant_type": "refresh_token",
+                "refresh_token": new_refresh_token,
+                "client_id": client.client_id,
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        let ClientError { error, .. } = response.json();
+        assert_eq!(error, ClientErrorCode::InvalidGrant);
+
+        // The user gets notified by email
+        let job: Json<serde_json::Value> = sqlx::query_scalar(
+            "SELECT payload FROM queue_jobs WHERE queue_name = 'send-session-revoked-email'",
+        )
+        .fetch_one(&pool)
+        .await
+        .expect("Session revoked email job to be scheduled");
+        assert_eq!(job["user_id"], serde_json::json!(user.id));
+        assert_eq!(job["oauth2_session_id"], serde_json::json!(session.id));
     }
 
     #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
@@ -1784,4 +2952,714 @@ mod tests {
         let ClientError { error, .. } = response.json();
         assert_eq!(error, ClientErrorCode::UnsupportedGrantType);
     }
+
+    /// Create a JWT assertion signed by the given key, issued by the given
+    /// client for the given subject
+    fn jwt_assertion(
+        state: &TestState,
+        key: &PrivateKey,
+        client_id: &str,
+        subject: &str,
+        lifetime: Duration,
+    ) -> String {
+        let alg = JsonWebSignatureAlg::Es256;
+        let signer = key.signing_key_for_alg(&alg).unwrap();
+        let header = JsonWebSignatureHeader::new(alg);
+
+        let now = state.clock.now();
+        let mut claims = HashMap::new();
+        claims::ISS.insert(&mut claims, client_id).unwrap();
+        claims::SUB.insert(&mut claims, subject).unwrap();
+        claims::AUD
+            .insert(
+                &mut claims,
+                state.url_builder.oauth_token_endpoint().to_string(),
+            )
+            .unwrap();
+        claims::JTI
+            .insert(
+                &mut claims,
+                Ulid::from_datetime_with_source(now.into(), &mut state.rng()).to_string(),
+            )
+            .unwrap();
+        claims::IAT.insert(&mut claims, now).unwrap();
+        claims::EXP.insert(&mut claims, now + lifetime).unwrap();
+
+        Jwt::sign_with_rng(&mut state.rng(), header, claims, &signer)
+            .unwrap()
+            .into_string()
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_jwt_bearer_grant(pool: PgPool) {
+        setup();
+        let state = TestState::from_pool(pool).await.unwrap();
+
+        let key = PrivateKey::generate_ec_p256(state.rng());
+        let jwks = PublicJsonWebKeySet::new(vec![
+            PublicJsonWebKey::new((&key).into()).with_alg(JsonWebSignatureAlg::Es256),
+        ]);
+
+        // Provision a user and a service account acting on their behalf. Those
+        // can only be configured statically, so we use the repository directly.
+        let mut repo = state.repository().await.unwrap();
+
+        repo.user()
+            .add(&mut state.rng(), &state.clock, "alice".to_owned())
+            .await
+            .unwrap();
+
+        let client = repo
+            .oauth2_client()
+            .upsert_static(
+                Ulid::from_datetime_with_source(state.clock.now().into(), &mut state.rng()),
+                OAuthClientAuthenticationMethod::PrivateKeyJwt,
+                None,
+                Some(jwks),
+                None,
+                Vec::new(),
+                Vec::new(),
+                None,
+                false,
+                false,
+                Some("alice".to_owned()),
+                AccessTokenFormat::Opaque,
+                None,
+            )
+            .await
+            .unwrap();
+
+        repo.save().await.unwrap();
+
+        let client_assertion = |state: &TestState| {
+            jwt_assertion(
+                state,
+                &key,
+                &client.client_id,
+                &client.client_id,
+                Duration::minutes(5),
+            )
+        };
+
+        // Get an access token for the configured user
+        let assertion = jwt_assertion(
+            &state,
+            &key,
+            &client.client_id,
+            "alice",
+            Duration::minutes(5),
+        );
+        let request =
+            Request::post(mas_router::OAuth2TokenEndpoint::PATH).form(serde_json::json!({
+                "grant_type": "urn:ietf:params:oauth:grant-type:jwt-bearer",
+                "assertion": assertion,
+                "scope": "openid",
+                "client_assertion_type": "urn:ietf:params:oauth:client-assertion-type:jwt-bearer",
+                "client_assertion": client_assertion(&state),
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+
+        let response: AccessTokenResponse = response.json();
+        assert!(response.refresh_token.is_none());
+        assert!(response.expires_in.is_some());
+        assert_eq!(response.scope, Some(Scope::from_iter([OPENID])));
+        assert!(state.is_access_token_valid(&response.access_token).await);
+
+        // The same assertion can't be used twice
+        let request =
+            Request::post(mas_router::OAuth2TokenEndpoint::PATH).form(serde_json::json!({
+                "grant_type": "urn:ietf:params:oauth:grant-type:jwt-bearer",
+                "assertion": assertion,
+                "scope": "openid",
+                "client_assertion_type": "urn:ietf:params:oauth:client-assertion-type:jwt-bearer",
+                "client_assertion": client_assertion(&state),
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        let ClientError { error, .. } = response.json();
+        assert_eq!(error, ClientErrorCode::InvalidGrant);
+
+        // The assertion must be for the configured user
+        let request =
+            Request::post(mas_router::OAuth2TokenEndpoint::PATH).form(serde_json::json!({
+                "grant_type": "urn:ietf:params:oauth:grant-type:jwt-bearer",
+                "assertion": jwt_assertion(&state, &key, &client.client_id, "bob", Duration::minutes(5)),
+                "client_assertion_type": "urn:ietf:params:oauth:client-assertion-type:jwt-bearer",
+                "client_assertion": client_assertion(&state),
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        let ClientError { error, .. } = response.json();
+        assert_eq!(error, ClientErrorCode::InvalidGrant);
+
+        // Long-lived assertions are rejected
+        let request =
+            Request::post(mas_router::OAuth2TokenEndpoint::PATH).form(serde_json::json!({
+                "grant_type": "urn:ietf:params:oauth:grant-type:jwt-bearer",
+                "assertion": jwt_assertion(&state, &key, &client.client_id, "alice", Duration::days(1)),
+                "client_assertion_type": "urn:ietf:params:oauth:client-assertion-type:jwt-bearer",
+                "client_assertion": client_assertion(&state),
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        let ClientError { error, .. } = response.json();
+        assert_eq!(error, ClientErrorCode::InvalidGrant);
+
+        // So are expired ones
+        let request =
+            Request::post(mas_router::OAuth2TokenEndpoint::PATH).form(serde_json::json!({
+                "grant_type": "urn:ietf:params:oauth:grant-type:jwt-bearer",
+                "assertion": jwt_assertion(&state, &key, &client.client_id, "alice", Duration::minutes(-10)),
+                "client_assertion_type": "urn:ietf:params:oauth:client-assertion-type:jwt-bearer",
+                "client_assertion": client_assertion(&state),
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        let ClientError { error, .. } = response.json();
+        assert_eq!(error, ClientErrorCode::InvalidGrant);
+    }
+
+    /// Create a `DPoP` proof signed by the given key
+    fn dpop_proof(
+        state: &TestState,
+        key: &PrivateKey,
+        jti: &str,
+        access_token: Option<&str>,
+    ) -> String {
+        let alg = JsonWebSignatureAlg::Es256;
+        let signer = key.signing_key_for_alg(&alg).unwrap();
+        let header = JsonWebSignatureHeader::new(alg)
+            .with_typ("dpop+jwt".to_owned())
+            .with_jwk(PublicJsonWebKey::new(key.into()));
+
+        let mut claims = HashMap::new();
+        claims::JTI.insert(&mut claims, jti).unwrap();
+        claims::HTM.insert(&mut claims, "POST").unwrap();
+        claims::HTU
+            .insert(
+                &mut claims,
+                state.url_builder.oauth_token_endpoint().as_str(),
+            )
+            .unwrap();
+        claims::IAT.insert(&mut claims, state.clock.now()).unwrap();
+        if let Some(access_token) = access_token {
+            let ath = Base64UrlUnpadded::encode_string(&Sha256::digest(access_token));
+            claims::ATH.insert(&mut claims, ath).unwrap();
+        }
+
+        Jwt::sign_with_rng(&mut state.rng(), header, claims, &signer)
+            .unwrap()
+            .into_string()
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_dpop_bound_tokens(pool: PgPool) {
+        setup();
+        let state = TestState::from_pool(pool).await.unwrap();
+
+        // Provision a client
+        let request =
+            Request::post(mas_router::OAuth2RegistrationEndpoint::PATH).json(serde_json::json!({
+                "client_uri": "https://example.com/",
+                "token_endpoint_auth_method": "client_secret_post",
+                "grant_types": ["client_credentials"],
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CREATED);
+
+        let response: ClientRegistrationResponse = response.json();
+        let client_id = response.client_id;
+        let client_secret = response.client_secret.expect("to have a client secret");
+
+        let key = PrivateKey::generate_ec_p256(state.rng());
+        let jkt = JsonWebKeyPublicParameters::from(&key).thumbprint_sha256();
+
+        // Ask for a token with a DPoP proof
+        let proof = dpop_proof(&state, &key, "first", None);
+        let request = Request::post(mas_router::OAuth2TokenEndpoint::PATH)
+            .header("DPoP", &proof)
+            .form(serde_json::json!({
+                "grant_type": "client_credentials",
+                "client_id": client_id,
+                "client_secret": client_secret,
+                "scope": "urn:mas:graphql:*",
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+
+        let response: AccessTokenResponse = response.json();
+        assert_eq!(response.token_type, OAuthAccessTokenType::DPoP);
+        let access_token = response.access_token;
+
+        // Replaying the same proof should fail
+        let request = Request::post(mas_router::OAuth2TokenEndpoint::PATH)
+            .header("DPoP", &proof)
+            .form(serde_json::json!({
+                "grant_type": "client_credentials",
+                "client_id": client_id,
+                "client_secret": client_secret,
+                "scope": "urn:mas:graphql:*",
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        let ClientError { error, .. } = response.json();
+        assert_eq!(error, ClientErrorCode::InvalidDpopProof);
+
+        // Introspecting the token should give back the key thumbprint
+        let request =
+            Request::post(mas_router::OAuth2Introspection::PATH).form(serde_json::json!({
+                "token": access_token,
+                "client_id": client_id,
+                "client_secret": client_secret,
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let response: IntrospectionResponse = response.json();
+        assert!(response.active);
+        assert_eq!(response.cnf.and_then(|cnf| cnf.jkt), Some(jkt));
+
+        // Introspecting the token with a proof from another key should fail
+        let other_key = PrivateKey::generate_ec_p256(state.rng());
+        let proof = dpop_proof(&state, &other_key, "second", Some(&access_token));
+        let request = Request::post(mas_router::OAuth2Introspection::PATH)
+            .header("DPoP", &proof)
+            .form(serde_json::json!({
+                "token": access_token,
+                "client_id": client_id,
+                "client_secret": client_secret,
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let response: IntrospectionResponse = response.json();
+        assert!(!response.active);
+
+        // With a proof from the right key, it should work once
+        let proof = dpop_proof(&state, &key, "third", Some(&access_token));
+        let request = Request::post(mas_router::OAuth2Introspection::PATH)
+            .header("DPoP", &proof)
+            .form(serde_json::json!({
+                "token": access_token,
+                "client_id": client_id,
+                "client_secret": client_secret,
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let response: IntrospectionResponse = response.json();
+        assert!(response.active);
+
+        let request = Request::post(mas_router::OAuth2Introspection::PATH)
+            .header("DPoP", &proof)
+            .form(serde_json::json!({
+                "token": access_token,
+                "client_id": client_id,
+                "client_secret": client_secret,
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let response: IntrospectionResponse = response.json();
+        assert!(!response.active);
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_token_exchange(pool: PgPool) {
+        setup();
+        let state = TestState::from_pool(pool).await.unwrap();
+
+        // Provision the client which gets tokens from the user
+        let request =
+            Request::post(mas_router::OAuth2RegistrationEndpoint::PATH).json(serde_json::json!({
+                "client_uri": "https://example.com/",
+                "redirect_uris": ["https://example.com/callback"],
+                "token_endpoint_auth_method": "none",
+                "response_types": ["code"],
+                "grant_types": ["authorization_code", "refresh_token"],
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CREATED);
+        let ClientRegistrationResponse { client_id, .. } = response.json();
+
+        // Provision the client which exchanges those tokens
+        let request =
+            Request::post(mas_router::OAuth2RegistrationEndpoint::PATH).json(serde_json::json!({
+                "client_uri": "https://backend.example.com/",
+                "token_endpoint_auth_method": "client_secret_post",
+                "grant_types": ["urn:ietf:params:oauth:grant-type:token-exchange"],
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CREATED);
+        let response: ClientRegistrationResponse = response.json();
+        let backend_client_id = response.client_id;
+        let backend_client_secret = response.client_secret.expect("to have a client secret");
+
+        // Provision a user with a session and an access token
+        let mut repo = state.repository().await.unwrap();
+
+        let user = repo
+            .user()
+            .add(&mut state.rng(), &state.clock, "alice".to_owned())
+            .await
+            .unwrap();
+
+        let browser_session = repo
+            .browser_session()
+            .add(&mut state.rng(), &state.clock, &user, None)
+            .await
+            .unwrap();
+
+        let client = repo
+            .oauth2_client()
+            .find_by_client_id(&client_id)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let session = repo
+            .oauth2_session()
+            .add_from_browser_session(
+                &mut state.rng(),
+                &state.clock,
+                &client,
+                &browser_session,
+                Scope::from_iter([OPENID]),
+            )
+            .await
+            .unwrap();
+
+        let (AccessToken { access_token, .. }, _) = generate_token_pair(
+            &mut state.rng(),
+            &state.clock,
+            &mut repo,
+            &session,
+            TokenType::AccessToken.generate(&mut state.rng()),
+            Duration::microseconds(5 * 60 * 1000 * 1000),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        repo.save().await.unwrap();
+
+        // By default, the policy doesn't allow any client to exchange tokens
+        let request =
+            Request::post(mas_router::OAuth2TokenEndpoint::PATH).form(serde_json::json!({
+                "grant_type": "urn:ietf:params:oauth:grant-type:token-exchange",
+                "client_id": backend_client_id,
+                "client_secret": backend_client_secret,
+                "subject_token": access_token,
+                "subject_token_type": "urn:ietf:params:oauth:token-type:access_token",
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::FORBIDDEN);
+
+        // Allow the backend client in the policy
+        let state = {
+            let mut state = state;
+            state.policy_factory = crate::test_utils::policy_factory(
+                "example.com",
+                serde_json::json!({
+                    "token_exchange": {
+                        "allowed_clients": [backend_client_id],
+                    }
+                }),
+            )
+            .await
+            .unwrap();
+            state
+        };
+
+        // Asking for more scopes than the subject token has should fail
+        let request =
+            Request::post(mas_router::OAuth2TokenEndpoint::PATH).form(serde_json::json!({
+                "grant_type": "urn:ietf:params:oauth:grant-type:token-exchange",
+                "client_id": backend_client_id,
+                "client_secret": backend_client_secret,
+                "subject_token": access_token,
+                "subject_token_type": "urn:ietf:params:oauth:token-type:access_token",
+                "scope": "openid urn:mas:graphql:*",
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        let ClientError { error, .. } = response.json();
+        assert_eq!(error, ClientErrorCode::InvalidScope);
+
+        // Only access tokens can be exchanged
+        let request =
+            Request::post(mas_router::OAuth2TokenEndpoint::PATH).form(serde_json::json!({
+                "grant_type": "urn:ietf:params:oauth:grant-type:token-exchange",
+                "client_id": backend_client_id,
+                "client_secret": backend_client_secret,
+                "subject_token": access_token,
+                "subject_token_type": "urn:ietf:params:oauth:token-type:id_token",
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+
+        // Let some time pass, so that the subject token expires before the
+        // configured TTL of the exchanged token
+        state
+            .clock
+            .advance(Duration::microseconds(60 * 1000 * 1000));
+
+        // Now the exchange should work
+        let request =
+            Request::post(mas_router::OAuth2TokenEndpoint::PATH).form(serde_json::json!({
+                "grant_type": "urn:ietf:params:oauth:grant-type:token-exchange",
+                "client_id": backend_client_id,
+                "client_secret": backend_client_secret,
+                "subject_token": access_token,
+                "subject_token_type": "urn:ietf:params:oauth:token-type:access_token",
+                "audience": "https://api.example.com/",
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+
+        let response: AccessTokenResponse = response.json();
+        assert!(response.refresh_token.is_none());
+        assert_eq!(
+            response.issued_token_type,
+            Some(TokenTypeIdentifier::AccessToken)
+        );
+        assert_eq!(response.scope, Some(Scope::from_iter([OPENID])));
+        // The exchanged token can't outlive the subject token
+        assert_eq!(
+            response.expires_in,
+            Some(Duration::microseconds(4 * 60 * 1000 * 1000))
+        );
+        assert!(state.is_access_token_valid(&response.access_token).await);
+
+        // Ending the user's session also ends the exchanged one
+        let mut repo = state.repository().await.unwrap();
+        repo.oauth2_session()
+            .finish(&state.clock, session)
+            .await
+            .unwrap();
+        repo.save().await.unwrap();
+
+        assert!(!state.is_access_token_valid(&response.access_token).await);
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_resource_indicators(pool: PgPool) {
+        setup();
+        let state = TestState::from_pool(pool).await.unwrap();
+
+        // Provision a client
+        let request =
+            Request::post(mas_router::OAuth2RegistrationEndpoint::PATH).json(serde_json::json!({
+                "client_uri": "https://example.com/",
+                "token_endpoint_auth_method": "client_secret_post",
+                "grant_types": ["client_credentials"],
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CREATED);
+
+        let response: ClientRegistrationResponse = response.json();
+        let client_id = response.client_id;
+        let client_secret = response.client_secret.expect("to have a client secret");
+
+        // Only allow this client to request tokens for one resource
+        let state = {
+            let mut state = state;
+            state.policy_factory = crate::test_utils::policy_factory(
+                "example.com",
+                serde_json::json!({
+                    "resources": {
+                        "https://api.example.com/": [client_id],
+                    }
+                }),
+            )
+            .await
+            .unwrap();
+            state
+        };
+
+        // Other resources are denied by the policy
+        let request =
+            Request::post(mas_router::OAuth2TokenEndpoint::PATH).form(serde_json::json!({
+                "grant_type": "client_credentials",
+                "client_id": client_id,
+                "client_secret": client_secret,
+                "resource": "https://other.example.com/",
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        let ClientError { error, .. } = response.json();
+        assert_eq!(error, ClientErrorCode::InvalidTarget);
+
+        // Resource indicators can't have a fragment
+        let request =
+            Request::post(mas_router::OAuth2TokenEndpoint::PATH).form(serde_json::json!({
+                "grant_type": "client_credentials",
+                "client_id": client_id,
+                "client_secret": client_secret,
+                "resource": "https://api.example.com/#fragment",
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        let ClientError { error, .. } = response.json();
+        assert_eq!(error, ClientErrorCode::InvalidTarget);
+
+        // The allowed resource works, and the session is restricted to it
+        let request =
+            Request::post(mas_router::OAuth2TokenEndpoint::PATH).form(serde_json::json!({
+                "grant_type": "client_credentials",
+                "client_id": client_id,
+                "client_secret": client_secret,
+                "resource": "https://api.example.com/",
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let response: AccessTokenResponse = response.json();
+
+        let mut repo = state.repository().await.unwrap();
+        let access_token = repo
+            .oauth2_access_token()
+            .find_by_token(&response.access_token)
+            .await
+            .unwrap()
+            .unwrap();
+        let session = repo
+            .oauth2_session()
+            .lookup(access_token.session_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(session.audiences(), ["https://api.example.com/"]);
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_tls_client_auth(pool: PgPool) {
+        setup();
+        let state = TestState::from_pool(pool).await.unwrap();
+
+        // Self-signed certificates with the `CN=partner.example.com,O=Partner` and
+        // `CN=other.example.com,O=Partner` subjects
+        let certificate = Base64::decode_vec(concat!(
+            "MIIBtzCCAV2gAwIBAgIUaQhPQKSzSwBt09+66HUczLGhXUcwCgYIKoZIzj0EAwIwMDEQMA4G",
+            "A1UECgwHUGFydG5lcjEcMBoGA1UEAwwTcGFydG5lci5leGFtcGxlLmNvbTAgFw0yNjEwMTcw",
+            "NzE5NTlaGA8yMTI2MDkyMzA3MTk1OVowMDEQMA4GA1UECgwHUGFydG5lcjEcMBoGA1UEAwwT",
+            "cGFydG5lci5leGFtcGxlLmNvbTBZMBMGByqGSM49AgEGCCqGSM49AwEHA0IABJpXUg8zFEPW",
+            "i71I5u6UtzYjSEbdHaLrgs6lNNjNV9O7jvD72cXczqmfzv4GFIJNxDEIwCRab7rLfq2vqjIS",
+            "DJmjUzBRMB0GA1UdDgQWBBSMl0jHTktuhDunwfnnvehaZkjAqjAfBgNVHSMEGDAWgBSMl0jH",
+            "TktuhDunwfnnvehaZkjAqjAPBgNVHRMBAf8EBTADAQH/MAoGCCqGSM49BAMCA0gAMEUCIHQ+",
+            "EKBv+6Ug/w03I9LFCKHDu0pVMbkS284u84MVj2uGAiEAyZ5P/8ihFfpA/G/W7ThWPpKXMAUa",
+            "F0sn02XfBNhNVBk=",
+        ))
+        .unwrap();
+        let other_certificate = Base64::decode_vec(concat!(
+            "MIIBszCCAVmgAwIBAgIUZ4WxVachfJk/NQZj1ilUB+rqDGowCgYIKoZIzj0EAwIwLjEQMA4G",
+            "A1UECgwHUGFydG5lcjEaMBgGA1UEAwwRb3RoZXIuZXhhbXBsZS5jb20wIBcNMjYxMDE3MDcy",
+            "MDE0WhgPMjEyNjA5MjMwNzIwMTRaMC4xEDAOBgNVBAoMB1BhcnRuZXIxGjAYBgNVBAMMEW90",
+            "aGVyLmV4YW1wbGUuY29tMFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEhAxvNz4u4YYmw0ZJ",
+            "QTYsgAy261C456ehWDmib31+meGhU5Zh/vEngOeooSpPoxsRXHXwlL4A7RgIgT7LZt1eZqNT",
+            "MFEwHQYDVR0OBBYEFLOJgDtHGU6gDaNKRAPogQ3CXLHLMB8GA1UdIwQYMBaAFLOJgDtHGU6g",
+            "DaNKRAPogQ3CXLHLMA8GA1UdEwEB/wQFMAMBAf8wCgYIKoZIzj0EAwIDSAAwRQIgOsC2YY5E",
+            "zU534wTlmORhagGmZ0Fbm/vL+12wb/fvjDICIQCn1CxGfmZcKdZkAaTurAE4bCInGF4SoWqO",
+            "p8b7yeXj2g==",
+        ))
+        .unwrap();
+
+        // Clients using mutual-TLS can only be configured statically
+        let mut repo = state.repository().await.unwrap();
+        let client = repo
+            .oauth2_client()
+            .upsert_static(
+                Ulid::from_datetime_with_source(state.clock.now().into(), &mut state.rng()),
+                OAuthClientAuthenticationMethod::TlsClientAuth,
+                None,
+                None,
+                None,
+                Vec::new(),
+                Vec::new(),
+                None,
+                false,
+                false,
+                None,
+                AccessTokenFormat::Opaque,
+                Some("CN=partner.example.com,O=Partner".to_owned()),
+            )
+            .await
+            .unwrap();
+        repo.save().await.unwrap();
+
+        let token_request = |certificate: ClientCertificate| {
+            let mut request =
+                Request::post(mas_router::OAuth2TokenEndpoint::PATH).form(serde_json::json!({
+                    "grant_type": "client_credentials",
+                    "client_id": client.client_id,
+                    "scope": "urn:mas:graphql:*",
+                }));
+            request.extensions_mut().insert(certificate);
+            request
+        };
+
+        // A certificate which wasn't verified by the listener is rejected
+        let request = token_request(ClientCertificate::new(certificate.clone(), false));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+        let ClientError { error, .. } = response.json();
+        assert_eq!(error, ClientErrorCode::InvalidClient);
+
+        // So is a certificate with another subject
+        let request = token_request(ClientCertificate::new(other_certificate, true));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+        let ClientError { error, .. } = response.json();
+        assert_eq!(error, ClientErrorCode::InvalidClient);
+
+        // No certificate at all is a `none` authentication, which is not allowed
+        let request =
+            Request::post(mas_router::OAuth2TokenEndpoint::PATH).form(serde_json::json!({
+                "grant_type": "client_credentials",
+                "client_id": client.client_id,
+            }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+
+        // With the right certificate, we get a token bound to it
+        let certificate = ClientCertificate::new(certificate, true);
+        let request = token_request(certificate.clone());
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let response: AccessTokenResponse = response.json();
+
+        let mut request =
+            Request::post(mas_router::OAuth2Introspection::PATH).form(serde_json::json!({
+                "token": response.access_token,
+                "client_id": client.client_id,
+            }));
+        request.extensions_mut().insert(certificate.clone());
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let response: IntrospectionResponse = response.json();
+        assert!(response.active);
+        assert_eq!(
+            response.cnf.and_then(|cnf| cnf.x5t_s256),
+            Some(certificate.thumbprint_sha256())
+        );
+    }
 }
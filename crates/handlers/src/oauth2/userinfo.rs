@@ -4,6 +4,8 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 // Please see LICENSE in the repository root for full details.
 
+use std::{collections::HashMap, sync::Arc};
+
 use axum::{
     Json,
     extract::State,
@@ -11,7 +13,7 @@ use axum::{
 };
 use hyper::StatusCode;
 use mas_axum_utils::{
-    jwt::JwtResponse,
+    jwt::{JweResponse, JwtResponse},
     sentry::SentryEventID,
     user_authorization::{AuthorizationVerificationError, UserAuthorization},
 };
@@ -19,13 +21,23 @@ use mas_jose::{
     constraints::Constrainable,
     jwt::{JsonWebSignatureHeader, Jwt},
 };
-use mas_keystore::Keystore;
+use mas_keystore::{Encrypter, Keystore};
+use mas_matrix::HomeserverConnection;
 use mas_router::UrlBuilder;
-use mas_storage::{BoxClock, BoxRepository, BoxRng, oauth2::OAuth2ClientRepository};
+use mas_storage::{
+    BoxClock, BoxRepository, BoxRng,
+    oauth2::{OAuth2AuthorizationGrantRepository, OAuth2ClientRepository},
+};
+use oauth2_types::registration::DEFAULT_ENCRYPTION_ENC_ALGORITHM;
 use serde::Serialize;
 use serde_with::skip_serializing_none;
 use thiserror::Error;
 
+use super::{
+    encryption::encrypt_for_client,
+    profile::{user_claims, userinfo_claims},
+    subject_for_client,
+};
 use crate::{BoundActivityTracker, impl_from_error_for_route};
 
 #[skip_serializing_none]
@@ -33,6 +45,8 @@ use crate::{BoundActivityTracker, impl_from_error_for_route};
 struct UserInfo {
     sub: String,
     username: String,
+    #[serde(flatten)]
+    claims: HashMap<String, serde_json::Value>,
 }
 
 #[derive(Serialize)]
@@ -69,6 +83,8 @@ pub enum RouteError {
 impl_from_error_for_route!(mas_storage::RepositoryError);
 impl_from_error_for_route!(mas_keystore::WrongAlgorithmError);
 impl_from_error_for_route!(mas_jose::jwt::JwtSignatureError);
+impl_from_error_for_route!(super::encryption::ResponseEncryptionError);
+impl_from_error_for_route!(serde_json::Error);
 
 impl IntoResponse for RouteError {
     fn into_response(self) -> axum::response::Response {
@@ -77,6 +93,13 @@ impl IntoResponse for RouteError {
             Self::Internal(_) | Self::InvalidSigningKey | Self::NoSuchClient | Self::NoSuchUser => {
                 (StatusCode::INTERNAL_SERVER_ERROR, self.to_string()).into_response()
             }
+            // Tell the client how to present DPoP-bound tokens
+            Self::AuthorizationVerificationError(
+                e @ (AuthorizationVerificationError::DpopRequired
+                | AuthorizationVerificationError::MissingDpopProof
+                | AuthorizationVerificationError::InvalidDpopProof(_)
+                | AuthorizationVerificationError::DpopKeyMismatch),
+            ) => e.into_response(),
             Self::AuthorizationVerificationError(_) | Self::Unauthorized => {
                 StatusCode::UNAUTHORIZED.into_response()
             }
@@ -94,9 +117,14 @@ pub async fn get(
     activity_tracker: BoundActivityTracker,
     mut repo: BoxRepository,
     State(key_store): State<Keystore>,
+    State(http_client): State<reqwest::Client>,
+    State(encrypter): State<Encrypter>,
+    State(homeserver): State<Arc<dyn HomeserverConnection>>,
     user_authorization: UserAuthorization,
 ) -> Result<Response, RouteError> {
-    let session = user_authorization.protected(&mut repo, &clock).await?;
+    let session = user_authorization
+        .protected(&mut repo, &clock, &url_builder.oidc_userinfo_endpoint())
+        .await?;
 
     // This endpoint requires the `openid` scope.
     if !session.scope.contains("openid") {
@@ -118,10 +146,16 @@ pub async fn get(
         .await?
         .ok_or(RouteError::NoSuchUser)?;
 
-    let user_info = UserInfo {
-        sub: user.sub.clone(),
-        username: user.username.clone(),
-    };
+    // Individual claims can be requested through the authorization grant which
+    // created the session
+    let grant = repo
+        .oauth2_authorization_grant()
+        .find_by_session(&session)
+        .await?;
+    let claims = userinfo_claims(
+        &session.scope,
+        grant.as_ref().and_then(|grant| grant.claims.as_ref()),
+    );
 
     let client = repo
         .oauth2_client()
@@ -129,9 +163,16 @@ pub async fn get(
         .await?
         .ok_or(RouteError::NoSuchClient)?;
 
+    let user_info = UserInfo {
+        sub: subject_for_client(&mut rng, &clock, &mut repo, &client, &user).await?,
+        username: user.username.clone(),
+        claims: user_claims(&*homeserver, &user, &claims).await,
+    };
+
     repo.save().await?;
 
-    if let Some(alg) = client.userinfo_signed_response_alg {
+    // Sign the response if the client asked for it
+    let signed = if let Some(alg) = client.userinfo_signed_response_alg.clone() {
         let key = key_store
             .signing_key_for_algorithm(&alg)
             .ok_or(RouteError::InvalidSigningKey)?;
@@ -142,13 +183,277 @@ pub async fn get(
 
         let user_info = SignedUserInfo {
             iss: url_builder.oidc_issuer().to_string(),
-            aud: client.client_id,
+            aud: client.client_id.clone(),
             user_info,
         };
 
-        let token = Jwt::sign_with_rng(&mut rng, header, user_info, &signer)?;
-        Ok(JwtResponse(token).into_response())
+        Ok(Jwt::sign_with_rng(&mut rng, header, user_info, &signer)?)
     } else {
-        Ok(Json(user_info).into_response())
+        Err(user_info)
+    };
+
+    // Then encrypt it if the client asked for it, in which case a signed
+    // response becomes a nested JWT
+    let Some(alg) = &client.userinfo_encrypted_response_alg else {
+        return Ok(match signed {
+            Ok(token) => JwtResponse(token).into_response(),
+            Err(user_info) => Json(user_info).into_response(),
+        });
+    };
+    let enc = client
+        .userinfo_encrypted_response_enc
+        .as_ref()
+        .unwrap_or(DEFAULT_ENCRYPTION_ENC_ALGORITHM);
+
+    let (cty, payload) = match signed {
+        Ok(token) => (Some("JWT"), token.into_string().into_bytes()),
+        Err(user_info) => (None, serde_json::to_vec(&user_info)?),
+    };
+
+    let jwe = encrypt_for_client(
+        &mut rng,
+        &http_client,
+        &encrypter,
+        &client,
+        alg,
+        enc,
+        cty,
+        &payload,
+    )
+    .await?;
+
+    Ok(JweResponse(jwe).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use base64ct::{Base64UrlUnpadded, Encoding};
+    use chrono::Duration;
+    use hyper::{Request, header::WWW_AUTHENTICATE};
+    use mas_axum_utils::client_authorization::ClientCertificate;
+    use mas_data_model::{AccessToken, TokenType};
+    use mas_iana::jose::JsonWebSignatureAlg;
+    use mas_jose::{
+        claims,
+        jwk::{JsonWebKeyPublicParameters, PublicJsonWebKey},
+    };
+    use mas_keystore::PrivateKey;
+    use mas_router::SimpleRoute;
+    use mas_storage::{
+        Clock, RepositoryAccess,
+        oauth2::{OAuth2ClientRepository, OAuth2SessionRepository},
+        user::{BrowserSessionRepository, UserRepository},
+    };
+    use oauth2_types::{
+        registration::ClientRegistrationResponse,
+        scope::{OPENID, Scope},
+    };
+    use sha2::{Digest, Sha256};
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::{
+        oauth2::generate_token_pair,
+        test_utils::{RequestBuilderExt, ResponseExt, TestState, setup},
+    };
+
+    /// Create a `DPoP` proof for a call to the userinfo endpoint
+    fn dpop_proof(state: &TestState, key: &PrivateKey, jti: &str, access_token: &str) -> String {
+        let alg = JsonWebSignatureAlg::Es256;
+        let signer = key.signing_key_for_alg(&alg).unwrap();
+        let header = JsonWebSignatureHeader::new(alg)
+            .with_typ("dpop+jwt".to_owned())
+            .with_jwk(PublicJsonWebKey::new(key.into()));
+
+        let mut claims = HashMap::new();
+        claims::JTI.insert(&mut claims, jti).unwrap();
+        claims::HTM.insert(&mut claims, "GET").unwrap();
+        claims::HTU
+            .insert(
+                &mut claims,
+                state.url_builder.oidc_userinfo_endpoint().as_str(),
+            )
+            .unwrap();
+        claims::IAT.insert(&mut claims, state.clock.now()).unwrap();
+        let ath = Base64UrlUnpadded::encode_string(&Sha256::digest(access_token));
+        claims::ATH.insert(&mut claims, ath).unwrap();
+
+        Jwt::sign_with_rng(&mut state.rng(), header, claims, &signer)
+            .unwrap()
+            .into_string()
+    }
+
+    /// Provision a user with a session and an access token bound to either a
+    /// `DPoP` key or a client certificate
+    async fn provision_access_token(
+        state: &TestState,
+        dpop_jkt: Option<String>,
+        x5t_s256: Option<String>,
+    ) -> String {
+        let request =
+            Request::post(mas_router::OAuth2RegistrationEndpoint::PATH).json(serde_json::json!({
+                "client_uri": "https://example.com/",
+                "redirect_uris": ["https://example.com/callback"],
+                "token_endpoint_auth_method": "none",
+                "response_types": ["code"],
+                "grant_types": ["authorization_code"],
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CREATED);
+        let ClientRegistrationResponse { client_id, .. } = response.json();
+
+        let mut repo = state.repository().await.unwrap();
+
+        let user = repo
+            .user()
+            .add(&mut state.rng(), &state.clock, "alice".to_owned())
+            .await
+            .unwrap();
+
+        let browser_session = repo
+            .browser_session()
+            .add(&mut state.rng(), &state.clock, &user, None)
+            .await
+            .unwrap();
+
+        let client = repo
+            .oauth2_client()
+            .find_by_client_id(&client_id)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let session = repo
+            .oauth2_session()
+            .add_from_browser_session(
+                &mut state.rng(),
+                &state.clock,
+                &client,
+                &browser_session,
+                Scope::from_iter([OPENID]),
+            )
+            .await
+            .unwrap();
+
+        let (AccessToken { access_token, .. }, _) = generate_token_pair(
+            &mut state.rng(),
+            &state.clock,
+            &mut repo,
+            &session,
+            TokenType::AccessToken.generate(&mut state.rng()),
+            Duration::minutes(5),
+            dpop_jkt,
+            x5t_s256,
+        )
+        .await
+        .unwrap();
+
+        repo.save().await.unwrap();
+
+        access_token
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_dpop_bound_token(pool: PgPool) {
+        setup();
+        let state = TestState::from_pool(pool).await.unwrap();
+
+        let key = PrivateKey::generate_ec_p256(state.rng());
+        let jkt = JsonWebKeyPublicParameters::from(&key).thumbprint_sha256();
+        let access_token = provision_access_token(&state, Some(jkt), None).await;
+
+        // Presenting the token as a bearer token should fail
+        let request = Request::get(mas_router::OidcUserinfo::PATH)
+            .bearer(&access_token)
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+        let www_authenticate = response.headers().get(WWW_AUTHENTICATE).unwrap();
+        assert!(www_authenticate.to_str().unwrap().starts_with("DPoP "));
+
+        // So should presenting it without a proof
+        let request = Request::get(mas_router::OidcUserinfo::PATH)
+            .header("Authorization", format!("DPoP {access_token}"))
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+        let www_authenticate = response.headers().get(WWW_AUTHENTICATE).unwrap();
+        assert!(
+            www_authenticate
+                .to_str()
+                .unwrap()
+                .contains("error=\"invalid_dpop_proof\"")
+        );
+
+        // Or with a proof from another key
+        let other_key = PrivateKey::generate_ec_p256(state.rng());
+        let request = Request::get(mas_router::OidcUserinfo::PATH)
+            .header("Authorization", format!("DPoP {access_token}"))
+            .header(
+                "DPoP",
+                dpop_proof(&state, &other_key, "first", &access_token),
+            )
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+
+        // With a proof from the right key, it should work
+        let proof = dpop_proof(&state, &key, "second", &access_token);
+        let request = Request::get(mas_router::OidcUserinfo::PATH)
+            .header("Authorization", format!("DPoP {access_token}"))
+            .header("DPoP", &proof)
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["username"], "alice");
+
+        // But replaying the proof should fail
+        let request = Request::get(mas_router::OidcUserinfo::PATH)
+            .header("Authorization", format!("DPoP {access_token}"))
+            .header("DPoP", &proof)
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_certificate_bound_token(pool: PgPool) {
+        setup();
+        let state = TestState::from_pool(pool).await.unwrap();
+
+        let certificate = ClientCertificate::new(b"certificate".to_vec(), true);
+        let access_token =
+            provision_access_token(&state, None, Some(certificate.thumbprint_sha256())).await;
+
+        let userinfo_request = |certificate: Option<ClientCertificate>| {
+            let mut request = Request::get(mas_router::OidcUserinfo::PATH)
+                .bearer(&access_token)
+                .empty();
+            if let Some(certificate) = certificate {
+                request.extensions_mut().insert(certificate);
+            }
+            request
+        };
+
+        // Without a certificate, the token is rejected
+        let response = state.request(userinfo_request(None)).await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+
+        // So it is with another certificate
+        let other_certificate = ClientCertificate::new(b"other certificate".to_vec(), true);
+        let response = state
+            .request(userinfo_request(Some(other_certificate)))
+            .await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+
+        // With the certificate the token is bound to, it works
+        let response = state.request(userinfo_request(Some(certificate))).await;
+        response.assert_status(StatusCode::OK);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["username"], "alice");
     }
 }
@@ -20,13 +20,16 @@ pub enum AccountRecoveryLimitedError {
     Email(String),
 }
 
-#[derive(Debug, Clone, Copy, thiserror::Error)]
+#[derive(Debug, Clone, thiserror::Error)]
 pub enum PasswordCheckLimitedError {
     #[error("Too many password checks for requester {0}")]
     Requester(RequesterFingerprint),
 
     #[error("Too many password checks for user {0}")]
     User(Ulid),
+
+    #[error("Too many password checks for username {0}")]
+    Username(String),
 }
 
 #[derive(Debug, Clone, thiserror::Error)]
@@ -89,6 +92,7 @@ struct LimiterInner {
     account_recovery_per_email: KeyedRateLimiter<String>,
     password_check_for_requester: KeyedRateLimiter<RequesterFingerprint>,
     password_check_for_user: KeyedRateLimiter<Ulid>,
+    password_check_for_username: KeyedRateLimiter<String>,
     registration_per_requester: KeyedRateLimiter<RequesterFingerprint>,
     email_authentication_per_requester: KeyedRateLimiter<RequesterFingerprint>,
     email_authentication_per_email: KeyedRateLimiter<String>,
@@ -107,6 +111,7 @@ impl LimiterInner {
             ),
             password_check_for_requester: RateLimiter::keyed(config.login.per_ip.to_quota()?),
             password_check_for_user: RateLimiter::keyed(config.login.per_account.to_quota()?),
+            password_check_for_username: RateLimiter::keyed(config.login.per_account.to_quota()?),
             registration_per_requester: RateLimiter::keyed(config.registration.to_quota()?),
             email_authentication_per_email: RateLimiter::keyed(
                 config.email_authentication.per_address.to_quota()?,
@@ -154,6 +159,7 @@ impl Limiter {
                 this.inner.account_recovery_per_requester.retain_recent();
                 this.inner.password_check_for_requester.retain_recent();
                 this.inner.password_check_for_user.retain_recent();
+                this.inner.password_check_for_username.retain_recent();
                 this.inner.registration_per_requester.retain_recent();
                 this.inner.email_authentication_per_email.retain_recent();
                 this.inner
@@ -221,6 +227,35 @@ impl Limiter {
         Ok(())
     }
 
+    /// Check if a password check against the LDAP directory can be performed
+    ///
+    /// This is keyed on the username, as the local user might not exist yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the operation is rate limited.
+    pub fn check_ldap_password(
+        &self,
+        key: RequesterFingerprint,
+        username: &str,
+    ) -> Result<(), PasswordCheckLimitedError> {
+        self.inner
+            .password_check_for_requester
+            .check_key(&key)
+            .map_err(|_| PasswordCheckLimitedError::Requester(key))?;
+
+        // Convert to lowercase to prevent bypassing the limit by enumerating
+        // different case variations, as directories usually match usernames
+        // case-insensitively
+        let canonical_username = username.to_lowercase();
+        self.inner
+            .password_check_for_username
+            .check_key(&canonical_username)
+            .map_err(|_| PasswordCheckLimitedError::Username(canonical_username))?;
+
+        Ok(())
+    }
+
     /// Check if an account registration can be performed
     ///
     /// # Errors
@@ -329,6 +364,8 @@ mod tests {
             locked_at: None,
             deactivated_at: None,
             can_request_admin: false,
+            locale: None,
+            profile_updated_at: None,
         };
 
         let bob = User {
@@ -339,6 +376,8 @@ mod tests {
             locked_at: None,
             deactivated_at: None,
             can_request_admin: false,
+            locale: None,
+            profile_updated_at: None,
         };
 
         // Three times the same IP address should be allowed
@@ -372,4 +411,28 @@ mod tests {
         // The other account isn't rate-limited
         assert!(limiter.check_password(requesters[603], &bob).is_ok());
     }
+
+    #[test]
+    fn test_ldap_password_check_limiter() {
+        let limiter = Limiter::new(&RateLimitingConfig::default()).unwrap();
+
+        let requesters: Vec<_> = (0..=255)
+            .flat_map(|a| (0..3).map(move |b| RequesterFingerprint::new([a, a, b, b].into())))
+            .collect();
+
+        // Consume all the cells for the username, using different case variations
+        for requester in requesters.iter().take(600) {
+            assert!(limiter.check_ldap_password(*requester, "alice").is_ok());
+            assert!(limiter.check_ldap_password(*requester, "Alice").is_ok());
+            assert!(limiter.check_ldap_password(*requester, "ALICE").is_ok());
+        }
+
+        assert!(matches!(
+            limiter.check_ldap_password(requesters[600], "aLiCe"),
+            Err(PasswordCheckLimitedError::Username(username)) if username == "alice"
+        ));
+
+        // The other username isn't rate-limited
+        assert!(limiter.check_ldap_password(requesters[601], "bob").is_ok());
+    }
 }
@@ -0,0 +1,147 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+//! Single-use recovery codes, which users can enter instead of their second
+//! factor if they lost access to it
+//!
+//! Codes are hashed at rest with the [`PasswordManager`], like passwords.
+
+use mas_data_model::{User, UserRecoveryCode};
+use mas_storage::{BoxRepository, Clock, RepositoryAccess as _, RepositoryError};
+use rand::{CryptoRng, Rng, RngCore, distributions::Slice};
+use zeroize::Zeroizing;
+
+use crate::passwords::PasswordManager;
+
+/// Number of codes generated at once
+pub const COUNT: usize = 10;
+
+/// Number of characters in a code, excluding the separators
+const LENGTH: usize = 12;
+
+/// Characters are grouped by this many when displayed
+const GROUP: usize = 4;
+
+/// Characters used in codes. Characters which look alike (`0`/`o`, `1`/`l`/`i`)
+/// are left out
+const ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+
+/// Generate a new random code, formatted for display
+fn generate(rng: &mut (impl RngCore + ?Sized)) -> String {
+    let distribution = Slice::new(ALPHABET).expect("alphabet is not empty");
+    let chars: Vec<char> = rng
+        .sample_iter(distribution)
+        .take(LENGTH)
+        .map(|c| char::from(*c))
+        .collect();
+
+    chars
+        .chunks(GROUP)
+        .map(|chunk| chunk.iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Normalize a code entered by the user, ignoring case, spaces and separators
+fn normalize(code: &str) -> Zeroizing<Vec<u8>> {
+    Zeroizing::new(
+        code.bytes()
+            .filter(|b| !b.is_ascii_whitespace() && *b != b'-')
+            .map(|b| b.to_ascii_lowercase())
+            .collect(),
+    )
+}
+
+/// Replace all the recovery codes of a user with a new set
+///
+/// Returns the new codes, formatted for display. This is the only time they
+/// are available in clear.
+///
+/// # Errors
+///
+/// Returns an error if hashing the codes failed or if the repository failed
+pub async fn regenerate(
+    rng: &mut (impl RngCore + CryptoRng + Send),
+    clock: &dyn Clock,
+    password_manager: &PasswordManager,
+    repo: &mut BoxRepository,
+    user: &User,
+) -> Result<Vec<String>, anyhow::Error> {
+    repo.user_recovery_code().remove_all(user).await?;
+
+    let mut codes = Vec::with_capacity(COUNT);
+    for _ in 0..COUNT {
+        let code = generate(&mut *rng);
+        let (version, hashed_code) = password_manager.hash(&mut *rng, normalize(&code)).await?;
+        repo.user_recovery_code()
+            .add(&mut *rng, clock, user, version, hashed_code)
+            .await?;
+        codes.push(code);
+    }
+
+    Ok(codes)
+}
+
+/// Find the unused recovery code of the user matching the one they entered
+///
+/// The code still has to be marked as consumed by the caller.
+///
+/// # Errors
+///
+/// Returns an error if the repository failed
+pub async fn find(
+    password_manager: &PasswordManager,
+    repo: &mut BoxRepository,
+    user: &User,
+    code: &str,
+) -> Result<Option<UserRecoveryCode>, RepositoryError> {
+    let code = normalize(code);
+    if code.len() != LENGTH {
+        return Ok(None);
+    }
+
+    for recovery_code in repo.user_recovery_code().unused(user).await? {
+        let res = password_manager
+            .verify(
+                recovery_code.version,
+                code.clone(),
+                recovery_code.hashed_code.clone(),
+            )
+            .await;
+
+        if res.is_ok() {
+            return Ok(Some(recovery_code));
+        }
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn test_generate() {
+        let mut rng = rand_chacha::ChaChaRng::seed_from_u64(42);
+        let code = generate(&mut rng);
+
+        assert_eq!(code.len(), LENGTH + LENGTH / GROUP - 1);
+        assert!(code.split('-').all(|group| group.len() == GROUP));
+        assert_eq!(normalize(&code).len(), LENGTH);
+
+        assert_ne!(code, generate(&mut rng));
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(
+            normalize(" ABCD-efgh ijkm\n").as_slice(),
+            b"abcdefghijkm".as_slice()
+        );
+    }
+}
@@ -28,7 +28,7 @@ use mas_axum_utils::{
     cookies::{CookieJar, CookieManager},
 };
 use mas_config::RateLimitingConfig;
-use mas_data_model::SiteConfig;
+use mas_data_model::{ClientRegistrationConfig, SiteConfig};
 use mas_i18n::Translator;
 use mas_keystore::{Encrypter, JsonWebKey, JsonWebKeySet, Keystore, PrivateKey};
 use mas_matrix::{HomeserverConnection, MockHomeserverConnection};
@@ -51,6 +51,7 @@ use url::Url;
 
 use crate::{
     ActivityTracker, BoundActivityTracker, Limiter, RequesterFingerprint, graphql,
+    ldap::LdapBackend,
     passwords::{Hasher, PasswordManager},
     upstream_oauth2::cache::MetadataCache,
 };
@@ -80,6 +81,7 @@ pub(crate) async fn policy_factory(
         register: "register/violation".to_owned(),
         client_registration: "client_registration/violation".to_owned(),
         authorization_grant: "authorization_grant/violation".to_owned(),
+        token_exchange: "token_exchange/violation".to_owned(),
         email: "email/violation".to_owned(),
     };
 
@@ -103,6 +105,7 @@ pub(crate) struct TestState {
     pub policy_factory: Arc<PolicyFactory>,
     pub graphql_schema: graphql::Schema,
     pub password_manager: PasswordManager,
+    pub ldap: LdapBackend,
     pub site_config: SiteConfig,
     pub activity_tracker: ActivityTracker,
     pub limiter: Limiter,
@@ -142,6 +145,11 @@ pub fn test_site_config() -> SiteConfig {
         minimum_password_complexity: 1,
         session_expiration: None,
         login_with_email_allowed: true,
+        passkeys_enabled: true,
+        email_code_login_enabled: true,
+        refresh_token_reuse_notification_enabled: false,
+        client_registration: ClientRegistrationConfig::default(),
+        signing_key_rotation: None,
     }
 }
 
@@ -218,6 +226,7 @@ impl TestState {
             password_manager: password_manager.clone(),
             url_builder: url_builder.clone(),
             limiter: limiter.clone(),
+            encrypter: encrypter.clone(),
         };
         let state: crate::graphql::BoxState = Box::new(graphql_state);
 
@@ -242,6 +251,7 @@ impl TestState {
             policy_factory,
             graphql_schema,
             password_manager,
+            ldap: LdapBackend::disabled(),
             site_config,
             activity_tracker,
             limiter,
@@ -402,6 +412,7 @@ struct TestGraphQLState {
     password_manager: PasswordManager,
     url_builder: UrlBuilder,
     limiter: Limiter,
+    encrypter: Encrypter,
 }
 
 #[async_trait::async_trait]
@@ -442,6 +453,10 @@ impl graphql::State for TestGraphQLState {
         &self.limiter
     }
 
+    fn encrypter(&self) -> &Encrypter {
+        &self.encrypter
+    }
+
     fn rng(&self) -> BoxRng {
         let mut parent_rng = self.rng.lock().expect("Failed to lock RNG");
         let rng = ChaChaRng::from_rng(&mut *parent_rng).expect("Failed to seed RNG");
@@ -497,6 +512,12 @@ impl FromRef<TestState> for PasswordManager {
     }
 }
 
+impl FromRef<TestState> for LdapBackend {
+    fn from_ref(input: &TestState) -> Self {
+        input.ldap.clone()
+    }
+}
+
 impl FromRef<TestState> for CookieManager {
     fn from_ref(input: &TestState) -> Self {
         input.cookie_manager.clone()
@@ -0,0 +1,214 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+//! Time-based one-time passwords, as per RFC 6238, used as a second factor
+//!
+//! This uses the parameters supported by virtually all authenticator apps:
+//! HMAC-SHA1, 6 digits and a 30 seconds period.
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha1::Sha1;
+use url::Url;
+
+/// Number of digits in a code
+const DIGITS: u32 = 6;
+
+/// Duration of a time step, in seconds
+const PERIOD: i64 = 30;
+
+/// How many steps before and after the current one are accepted, to account
+/// for clock drift and for the time it takes the user to type the code
+const SKEW: u64 = 1;
+
+/// Length of the generated secrets, in bytes. RFC 4226 recommends 160 bits
+const SECRET_LENGTH: usize = 20;
+
+/// Generate a new random shared secret
+pub fn generate_secret(rng: &mut (impl RngCore + ?Sized)) -> Vec<u8> {
+    let mut secret = vec![0; SECRET_LENGTH];
+    rng.fill_bytes(&mut secret);
+    secret
+}
+
+/// Encode a secret as unpadded base32, which is how authenticator apps expect
+/// it
+pub fn encode_secret(secret: &[u8]) -> String {
+    const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+    let mut encoded = String::with_capacity(secret.len().div_ceil(5) * 8);
+    let mut buffer: u16 = 0;
+    let mut bits = 0;
+    for byte in secret {
+        buffer = (buffer << 8) | u16::from(*byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(ALPHABET[usize::from((buffer >> bits) & 0x1f)].into());
+        }
+    }
+
+    if bits > 0 {
+        encoded.push(ALPHABET[usize::from((buffer << (5 - bits)) & 0x1f)].into());
+    }
+
+    encoded
+}
+
+/// Build the `otpauth://` URI used to enroll the secret in an authenticator
+/// app, usually displayed as a QR code
+pub fn provisioning_uri(secret: &[u8], issuer: &str, account: &str) -> Url {
+    let mut uri = Url::parse("otpauth://totp/").expect("valid base URI");
+    uri.path_segments_mut()
+        .expect("URI can be a base")
+        .pop()
+        .push(&format!("{issuer}:{account}"));
+    uri.query_pairs_mut()
+        .append_pair("secret", &encode_secret(secret))
+        .append_pair("issuer", issuer)
+        .append_pair("algorithm", "SHA1")
+        .append_pair("digits", &DIGITS.to_string())
+        .append_pair("period", &PERIOD.to_string());
+    uri
+}
+
+/// Compute the time step for the given instant
+fn step_at(now: DateTime<Utc>) -> u64 {
+    // Timestamps before the UNIX epoch don't make sense here
+    u64::try_from(now.timestamp().div_euclid(PERIOD)).unwrap_or_default()
+}
+
+/// Compute the code for a given time step
+fn code_for_step(secret: &[u8], step: u64) -> String {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC accepts keys of any size");
+    mac.update(&step.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+
+    // Dynamic truncation, as per RFC 4226 section 5.3
+    let offset = usize::from(hash[hash.len() - 1] & 0x0f);
+    let value = u32::from_be_bytes([
+        hash[offset] & 0x7f,
+        hash[offset + 1],
+        hash[offset + 2],
+        hash[offset + 3],
+    ]);
+
+    format!(
+        "{:0width$}",
+        value % 10u32.pow(DIGITS),
+        width = DIGITS as usize
+    )
+}
+
+/// Compute the code valid at the given instant
+#[cfg(test)]
+pub fn code_at(secret: &[u8], now: DateTime<Utc>) -> String {
+    code_for_step(secret, step_at(now))
+}
+
+/// Verify a code against the secret
+///
+/// Codes for time steps up to and including `last_used_step` are rejected, so
+/// that a code can't be used twice.
+///
+/// Returns the time step of the code if it is valid, which should then be
+/// recorded as the last used step
+pub fn verify(
+    secret: &[u8],
+    code: &str,
+    now: DateTime<Utc>,
+    last_used_step: Option<u64>,
+) -> Option<u64> {
+    let code = code.trim();
+    if code.len() != DIGITS as usize || !code.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let current = step_at(now);
+    (current.saturating_sub(SKEW)..=current.saturating_add(SKEW))
+        .filter(|step| last_used_step.is_none_or(|last| *step > last))
+        .find(|step| code_for_step(secret, *step) == code)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    /// The SHA1 secret from the RFC 6238 test vectors
+    const SECRET: &[u8] = b"12345678901234567890";
+
+    #[test]
+    fn test_rfc6238_vectors() {
+        // The test vectors have 8 digits, we only keep the last 6
+        for (time, expected) in [
+            (59, "287082"),
+            (1_111_111_109, "081804"),
+            (1_111_111_111, "050471"),
+            (1_234_567_890, "005924"),
+            (2_000_000_000, "279037"),
+        ] {
+            let now = Utc.timestamp_opt(time, 0).unwrap();
+            assert_eq!(code_for_step(SECRET, step_at(now)), expected);
+        }
+    }
+
+    #[test]
+    fn test_verify() {
+        let now = Utc.timestamp_opt(1_111_111_109, 0).unwrap();
+        let step = step_at(now);
+
+        // The current code is valid
+        assert_eq!(verify(SECRET, "081804", now, None), Some(step));
+        assert_eq!(verify(SECRET, " 081804 ", now, None), Some(step));
+
+        // Codes from the previous and next steps are valid as well
+        let previous = code_for_step(SECRET, step - 1);
+        let next = code_for_step(SECRET, step + 1);
+        assert_eq!(verify(SECRET, &previous, now, None), Some(step - 1));
+        assert_eq!(verify(SECRET, &next, now, None), Some(step + 1));
+
+        // But not further away
+        let old = code_for_step(SECRET, step - 2);
+        assert_eq!(verify(SECRET, &old, now, None), None);
+
+        // Codes can't be reused
+        assert_eq!(verify(SECRET, "081804", now, Some(step)), None);
+        assert_eq!(verify(SECRET, &next, now, Some(step)), Some(step + 1));
+
+        // Garbage is rejected
+        assert_eq!(verify(SECRET, "", now, None), None);
+        assert_eq!(verify(SECRET, "81804", now, None), None);
+        assert_eq!(verify(SECRET, "08180a", now, None), None);
+    }
+
+    #[test]
+    fn test_encode_secret() {
+        // Test vectors from RFC 4648, without the padding
+        assert_eq!(encode_secret(b""), "");
+        assert_eq!(encode_secret(b"f"), "MY");
+        assert_eq!(encode_secret(b"fo"), "MZXQ");
+        assert_eq!(encode_secret(b"foo"), "MZXW6");
+        assert_eq!(encode_secret(b"foob"), "MZXW6YQ");
+        assert_eq!(encode_secret(b"fooba"), "MZXW6YTB");
+        assert_eq!(encode_secret(b"foobar"), "MZXW6YTBOI");
+    }
+
+    #[test]
+    fn test_provisioning_uri() {
+        let mut rng = rand_chacha::ChaChaRng::seed_from_u64(42);
+        let secret = generate_secret(&mut rng);
+        assert_eq!(secret.len(), SECRET_LENGTH);
+
+        let uri = provisioning_uri(SECRET, "example.com", "alice");
+        assert_eq!(
+            uri.as_str(),
+            "otpauth://totp/example.com:alice?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=example.com&algorithm=SHA1&digits=6&period=30"
+        );
+    }
+}
@@ -139,6 +139,19 @@ impl<'a> LazyProviderInfos<'a> {
 
         Ok(methods)
     }
+
+    /// Whether the provider advertises including the `iss` parameter in its
+    /// authorization responses.
+    ///
+    /// Defaults to `false` if discovery is disabled.
+    pub async fn authorization_response_iss_parameter_supported(
+        &mut self,
+    ) -> Result<bool, DiscoveryError> {
+        Ok(self
+            .maybe_discover()
+            .await?
+            .is_some_and(|metadata| metadata.authorization_response_iss_parameter_supported()))
+    }
 }
 
 /// A simple OIDC metadata cache
@@ -17,7 +17,9 @@ use mas_axum_utils::{cookies::CookieJar, sentry::SentryEventID};
 use mas_data_model::{UpstreamOAuthProvider, UpstreamOAuthProviderResponseMode};
 use mas_jose::claims::TokenHash;
 use mas_keystore::{Encrypter, Keystore};
-use mas_oidc_client::requests::jose::JwtVerificationData;
+use mas_oidc_client::requests::{
+    authorization_code::validate_authorization_response_issuer, jose::JwtVerificationData,
+};
 use mas_router::UrlBuilder;
 use mas_storage::{
     BoxClock, BoxRepository, BoxRng, Clock,
@@ -66,6 +68,10 @@ pub struct Params {
     #[serde(skip_serializing_if = "Option::is_none")]
     code: Option<String>,
 
+    /// The issuer identifier, as per RFC 9207
+    #[serde(skip_serializing_if = "Option::is_none")]
+    iss: Option<String>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     error: Option<ClientErrorCode>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -137,6 +143,9 @@ pub(crate) enum RouteError {
         expected: UpstreamOAuthProviderResponseMode,
     },
 
+    #[error(transparent)]
+    AuthorizationResponse(#[from] mas_oidc_client::error::AuthorizationResponseError),
+
     #[error(transparent)]
     Internal(Box<dyn std::error::Error + Send + Sync + 'static>),
 }
@@ -229,6 +238,20 @@ pub(crate) async fn handler(
         (Some(expected), _) => return Err(RouteError::InvalidResponseMode { expected }),
     }
 
+    let mut lazy_metadata = LazyProviderInfos::new(&metadata_cache, &provider, &client);
+
+    // Check the `iss` parameter to protect against mix-up attacks. This is done
+    // before anything else, as error responses also carry it.
+    if let Some(issuer) = &provider.issuer {
+        validate_authorization_response_issuer(
+            params.iss.as_deref(),
+            issuer,
+            lazy_metadata
+                .authorization_response_iss_parameter_supported()
+                .await?,
+        )?;
+    }
+
     if let Some(error) = params.error {
         CALLBACK_COUNTER.add(
             1,
@@ -286,8 +309,6 @@ pub(crate) async fn handler(
         ],
     );
 
-    let mut lazy_metadata = LazyProviderInfos::new(&metadata_cache, &provider, &client);
-
     // Figure out the client credentials
     let client_credentials = client_credentials_for_provider(
         &provider,
@@ -306,6 +327,7 @@ pub(crate) async fn handler(
             code: code.clone(),
             redirect_uri: Some(redirect_uri),
             code_verifier: session.code_challenge_verifier.clone(),
+            resource: None,
         }),
         clock.now(),
         &mut rng,
@@ -7,7 +7,7 @@
 use std::sync::{Arc, LazyLock};
 
 use axum::{
-    extract::{Form, Query, State},
+    extract::{Form, FromRef, Query, State},
     response::{Html, IntoResponse, Response},
 };
 use axum_extra::typed_header::TypedHeader;
@@ -23,8 +23,12 @@ use mas_matrix::HomeserverConnection;
 use mas_router::{UpstreamOAuth2Authorize, UrlBuilder};
 use mas_storage::{
     BoxClock, BoxRepository, BoxRng, Clock, RepositoryAccess,
+    queue::{QueueJobRepositoryExt as _, SendEmailAuthenticationCodeJob},
     upstream_oauth2::UpstreamOAuthProviderRepository,
-    user::{BrowserSessionRepository, UserPasswordRepository, UserRepository},
+    user::{
+        BrowserSessionRepository, UserPasskeyRepository, UserPasswordRepository, UserRepository,
+        UserTotpRepository,
+    },
 };
 use mas_templates::{
     AccountInactiveContext, FieldError, FormError, FormState, LoginContext, LoginFormField,
@@ -35,9 +39,13 @@ use rand::Rng;
 use serde::{Deserialize, Serialize};
 use zeroize::Zeroizing;
 
-use super::shared::OptionalPostAuthAction;
+use super::{
+    login_totp::{FirstFactor, PendingTotpLogin},
+    shared::OptionalPostAuthAction,
+};
 use crate::{
     BoundActivityTracker, Limiter, METER, PreferredLanguage, RequesterFingerprint, SiteConfig,
+    ldap::{LdapBackend, LdapError, LdapLogin},
     passwords::PasswordManager,
     session::{SessionOrFallback, load_session_or_fallback},
 };
@@ -51,10 +59,46 @@ static PASSWORD_LOGIN_COUNTER: LazyLock<Counter<u64>> = LazyLock::new(|| {
 });
 const RESULT: Key = Key::from_static_str("result");
 
+/// Which button was used to submit the login form
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum LoginMethod {
+    /// Log in with the username and password
+    #[default]
+    Password,
+
+    /// Send a one-time code to the email address entered as the username
+    EmailCode,
+}
+
+/// The backends passwords are checked against, extracted together to keep the
+/// number of extractors of the handler within what axum supports
+#[derive(Clone)]
+pub(crate) struct PasswordBackends {
+    password_manager: PasswordManager,
+    ldap: LdapBackend,
+}
+
+impl<S> FromRef<S> for PasswordBackends
+where
+    PasswordManager: FromRef<S>,
+    LdapBackend: FromRef<S>,
+{
+    fn from_ref(input: &S) -> Self {
+        Self {
+            password_manager: PasswordManager::from_ref(input),
+            ldap: LdapBackend::from_ref(input),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub(crate) struct LoginForm {
     username: String,
+    #[serde(default)]
     password: String,
+    #[serde(default)]
+    method: LoginMethod,
 }
 
 impl ToFormState for LoginForm {
@@ -99,9 +143,13 @@ pub(crate) async fn get(
 
     let providers = repo.upstream_oauth_provider().all_enabled().await?;
 
-    // If password-based login is disabled, and there is only one upstream provider,
+    // If there is no other way to log in, and there is only one upstream provider,
     // we can directly start an authorization flow
-    if !site_config.password_login_enabled && providers.len() == 1 {
+    if !site_config.password_login_enabled
+        && !site_config.email_code_login_enabled
+        && !site_config.passkeys_enabled
+        && providers.len() == 1
+    {
         let provider = providers.into_iter().next().unwrap();
 
         let mut destination = UpstreamOAuth2Authorize::new(provider.id);
@@ -132,7 +180,10 @@ pub(crate) async fn post(
     mut rng: BoxRng,
     clock: BoxClock,
     PreferredLanguage(locale): PreferredLanguage,
-    State(password_manager): State<PasswordManager>,
+    State(PasswordBackends {
+        password_manager,
+        ldap,
+    }): State<PasswordBackends>,
     State(site_config): State<SiteConfig>,
     State(templates): State<Templates>,
     State(url_builder): State<UrlBuilder>,
@@ -147,13 +198,32 @@ pub(crate) async fn post(
     Form(form): Form<ProtectedForm<LoginForm>>,
 ) -> Result<Response, FancyError> {
     let user_agent = user_agent.map(|ua| UserAgent::parse(ua.as_str().to_owned()));
+    let form = cookie_jar.verify_form(&clock, form)?;
+
+    if form.method == LoginMethod::EmailCode {
+        return start_email_code_login(
+            rng,
+            clock,
+            locale,
+            site_config,
+            templates,
+            url_builder,
+            limiter,
+            homeserver,
+            repo,
+            requester,
+            query,
+            cookie_jar,
+            form,
+        )
+        .await;
+    }
+
     if !site_config.password_login_enabled {
         // XXX: is it necessary to have better errors here?
         return Ok(StatusCode::METHOD_NOT_ALLOWED.into_response());
     }
 
-    let form = cookie_jar.verify_form(&clock, form)?;
-
     // Validate the form
     let mut form_state = form.to_form_state();
 
@@ -186,91 +256,79 @@ pub(crate) async fn post(
         .localpart(&form.username)
         .unwrap_or(&form.username);
 
-    // First, lookup the user
-    let Some(user) = get_user_by_email_or_by_username(site_config, &mut repo, username).await?
-    else {
-        let form_state = form_state.with_error_on_form(FormError::InvalidCredentials);
-        PASSWORD_LOGIN_COUNTER.add(1, &[KeyValue::new(RESULT, "error")]);
-        return render(
-            locale,
-            cookie_jar,
-            form_state,
-            query,
-            &mut repo,
-            &clock,
-            &mut rng,
-            &templates,
-            &homeserver,
-        )
-        .await;
-    };
-
-    // Check the rate limit
-    if let Err(e) = limiter.check_password(requester, &user) {
-        tracing::warn!(error = &e as &dyn std::error::Error);
-        let form_state = form_state.with_error_on_form(FormError::RateLimitExceeded);
-        PASSWORD_LOGIN_COUNTER.add(1, &[KeyValue::new(RESULT, "error")]);
-        return render(
-            locale,
-            cookie_jar,
-            form_state,
-            query,
-            &mut repo,
-            &clock,
-            &mut rng,
-            &templates,
-            &homeserver,
-        )
-        .await;
-    }
+    let passkeys_enabled = site_config.passkeys_enabled;
 
-    // And its password
-    let Some(user_password) = repo.user_password().active(&user).await? else {
-        // There is no password for this user, but we don't want to disclose that. Show
-        // a generic 'invalid credentials' error instead
-        let form_state = form_state.with_error_on_form(FormError::InvalidCredentials);
-        PASSWORD_LOGIN_COUNTER.add(1, &[KeyValue::new(RESULT, "error")]);
-        return render(
-            locale,
-            cookie_jar,
-            form_state,
-            query,
-            &mut repo,
-            &clock,
-            &mut rng,
-            &templates,
-            &homeserver,
-        )
-        .await;
-    };
-
-    let password = Zeroizing::new(form.password.as_bytes().to_vec());
+    // If an LDAP directory is configured, check the password against it first.
+    // Users which are not found in the directory go through the local password
+    // check
+    let ldap_login = if ldap.is_enabled() {
+        if let Err(e) = limiter.check_ldap_password(requester, username) {
+            tracing::warn!(error = &e as &dyn std::error::Error);
+            let form_state = form_state.with_error_on_form(FormError::RateLimitExceeded);
+            PASSWORD_LOGIN_COUNTER.add(1, &[KeyValue::new(RESULT, "error")]);
+            return render(
+                locale,
+                cookie_jar,
+                form_state,
+                query,
+                &mut repo,
+                &clock,
+                &mut rng,
+                &templates,
+                &homeserver,
+            )
+            .await;
+        }
 
-    // Verify the password, and upgrade it on-the-fly if needed
-    let user_password = match password_manager
-        .verify_and_upgrade(
-            &mut rng,
-            user_password.version,
-            password,
-            user_password.hashed_password.clone(),
-        )
-        .await
-    {
-        Ok(Some((version, new_password_hash))) => {
-            // Save the upgraded password
-            repo.user_password()
-                .add(
-                    &mut rng,
+        match ldap
+            .login(
+                &mut rng,
+                &clock,
+                &mut repo,
+                homeserver.as_ref(),
+                username,
+                &form.password,
+            )
+            .await
+        {
+            Ok(LdapLogin::Success { user, dn, .. }) => Some((user, FirstFactor::Ldap { dn })),
+            Ok(LdapLogin::UnknownUser) => None,
+            Ok(LdapLogin::InvalidCredentials) => {
+                let form_state = form_state.with_error_on_form(FormError::InvalidCredentials);
+                PASSWORD_LOGIN_COUNTER.add(1, &[KeyValue::new(RESULT, "error")]);
+                return render(
+                    locale,
+                    cookie_jar,
+                    form_state,
+                    query,
+                    &mut repo,
                     &clock,
-                    &user,
-                    version,
-                    new_password_hash,
-                    Some(&user_password),
+                    &mut rng,
+                    &templates,
+                    &homeserver,
                 )
-                .await?
+                .await;
+            }
+            Err(LdapError::Ldap(e)) => {
+                // Don't lock everyone out if the directory is unreachable
+                tracing::error!(
+                    error = &e as &dyn std::error::Error,
+                    "Could not check the password against the LDAP directory"
+                );
+                None
+            }
+            Err(e) => return Err(e.into()),
         }
-        Ok(None) => user_password,
-        Err(_) => {
+    } else {
+        None
+    };
+
+    let (user, first_factor) = if let Some(ldap_login) = ldap_login {
+        ldap_login
+    } else {
+        // First, lookup the user
+        let Some(user) = get_user_by_email_or_by_username(site_config, &mut repo, username).await?
+        else {
             let form_state = form_state.with_error_on_form(FormError::InvalidCredentials);
             PASSWORD_LOGIN_COUNTER.add(1, &[KeyValue::new(RESULT, "error")]);
             return render(
@@ -285,7 +343,92 @@ pub(crate) async fn post(
                 &homeserver,
             )
             .await;
+        };
+
+        // Check the rate limit
+        if let Err(e) = limiter.check_password(requester, &user) {
+            tracing::warn!(error = &e as &dyn std::error::Error);
+            let form_state = form_state.with_error_on_form(FormError::RateLimitExceeded);
+            PASSWORD_LOGIN_COUNTER.add(1, &[KeyValue::new(RESULT, "error")]);
+            return render(
+                locale,
+                cookie_jar,
+                form_state,
+                query,
+                &mut repo,
+                &clock,
+                &mut rng,
+                &templates,
+                &homeserver,
+            )
+            .await;
         }
+
+        // And its password
+        let Some(user_password) = repo.user_password().active(&user).await? else {
+            // There is no password for this user, but we don't want to disclose that. Show
+            // a generic 'invalid credentials' error instead
+            let form_state = form_state.with_error_on_form(FormError::InvalidCredentials);
+            PASSWORD_LOGIN_COUNTER.add(1, &[KeyValue::new(RESULT, "error")]);
+            return render(
+                locale,
+                cookie_jar,
+                form_state,
+                query,
+                &mut repo,
+                &clock,
+                &mut rng,
+                &templates,
+                &homeserver,
+            )
+            .await;
+        };
+
+        let password = Zeroizing::new(form.password.as_bytes().to_vec());
+
+        // Verify the password, and upgrade it on-the-fly if needed
+        let user_password = match password_manager
+            .verify_and_upgrade(
+                &mut rng,
+                user_password.version,
+                password,
+                user_password.hashed_password.clone(),
+            )
+            .await
+        {
+            Ok(Some((version, new_password_hash))) => {
+                // Save the upgraded password
+                repo.user_password()
+                    .add(
+                        &mut rng,
+                        &clock,
+                        &user,
+                        version,
+                        new_password_hash,
+                        Some(&user_password),
+                    )
+                    .await?
+            }
+            Ok(None) => user_password,
+            Err(_) => {
+                let form_state = form_state.with_error_on_form(FormError::InvalidCredentials);
+                PASSWORD_LOGIN_COUNTER.add(1, &[KeyValue::new(RESULT, "error")]);
+                return render(
+                    locale,
+                    cookie_jar,
+                    form_state,
+                    query,
+                    &mut repo,
+                    &clock,
+                    &mut rng,
+                    &templates,
+                    &homeserver,
+                )
+                .await;
+            }
+        };
+
+        (user, FirstFactor::Password(user_password))
     };
 
     // Now that we have checked the user password, we now want to show an error if
@@ -314,6 +457,23 @@ pub(crate) async fn post(
     // want it to crash in tests/debug builds
     debug_assert!(user.is_valid());
 
+    // If the user enrolled a TOTP authenticator or a passkey, ask for a second
+    // factor before starting the session
+    let authenticator = repo.user_totp().find(&user).await?;
+    let has_passkeys = passkeys_enabled && !repo.user_passkey().all(&user).await?.is_empty();
+    if authenticator.is_some_and(|a| a.is_verified()) || has_passkeys {
+        // The password might have been upgraded, or the user created from the
+        // directory entry
+        repo.save().await?;
+
+        PASSWORD_LOGIN_COUNTER.add(1, &[KeyValue::new(RESULT, "success")]);
+
+        let pending = PendingTotpLogin::new(&mut rng, &clock, &user, &first_factor);
+        let reply =
+            super::login_totp::redirect(cookie_jar, &url_builder, &pending, query.post_auth_action);
+        return Ok(reply);
+    }
+
     // Start a new session
     let user_session = repo
         .browser_session()
@@ -321,8 +481,8 @@ pub(crate) async fn post(
         .await?;
 
     // And mark it as authenticated by the password
-    repo.browser_session()
-        .authenticate_with_password(&mut rng, &clock, &user_session, &user_password)
+    first_factor
+        .authenticate(&mut rng, &clock, &mut repo, &user_session)
         .await?;
 
     repo.save().await?;
@@ -338,6 +498,83 @@ pub(crate) async fn post(
     Ok((cookie_jar, reply).into_response())
 }
 
+/// Start a passwordless login, by sending a one-time code to the email address
+/// entered in the username field
+async fn start_email_code_login(
+    mut rng: BoxRng,
+    clock: BoxClock,
+    locale: DataLocale,
+    site_config: SiteConfig,
+    templates: Templates,
+    url_builder: UrlBuilder,
+    limiter: Limiter,
+    homeserver: Arc<dyn HomeserverConnection>,
+    mut repo: BoxRepository,
+    requester: RequesterFingerprint,
+    query: OptionalPostAuthAction,
+    cookie_jar: CookieJar,
+    form: LoginForm,
+) -> Result<Response, FancyError> {
+    if !site_config.email_code_login_enabled {
+        return Ok(StatusCode::METHOD_NOT_ALLOWED.into_response());
+    }
+
+    let mut form_state = form.to_form_state();
+    let email = form.username.trim();
+
+    if email.is_empty() {
+        form_state.add_error_on_field(LoginFormField::Username, FieldError::Required);
+    } else if email.parse::<lettre::Address>().is_err() {
+        form_state.add_error_on_field(LoginFormField::Username, FieldError::Invalid);
+    } else if let Err(e) = limiter.check_email_authentication_email(requester, email) {
+        tracing::warn!(error = &e as &dyn std::error::Error);
+        form_state.add_error_on_form(FormError::RateLimitExceeded);
+    }
+
+    if !form_state.is_valid() {
+        return render(
+            locale,
+            cookie_jar,
+            form_state,
+            query,
+            &mut repo,
+            &clock,
+            &mut rng,
+            &templates,
+            &homeserver,
+        )
+        .await;
+    }
+
+    // We don't want to disclose whether the address can be used to log in, so
+    // the flow goes on in all cases, but a code only gets sent if it can
+    let user = super::login_email_code::find_user(&site_config, &mut repo, email).await?;
+
+    let authentication = repo
+        .user_email()
+        .add_authentication_for_login(&mut rng, &clock, email.to_owned(), user.as_ref())
+        .await?;
+
+    if user.is_some() {
+        repo.queue_job()
+            .schedule_job(
+                &mut rng,
+                &clock,
+                SendEmailAuthenticationCodeJob::new(&authentication, locale.to_string()),
+            )
+            .await?;
+    }
+
+    repo.save().await?;
+
+    let mut destination = mas_router::LoginEmailCode::new(authentication.id);
+    if let Some(action) = query.post_auth_action {
+        destination = destination.and_then(action);
+    }
+
+    Ok((cookie_jar, url_builder.redirect(&destination)).into_response())
+}
+
 async fn get_user_by_email_or_by_username(
     site_config: SiteConfig,
     repo: &mut impl RepositoryAccess,
@@ -436,6 +673,7 @@ mod test {
 
     use crate::{
         SiteConfig,
+        ldap::{LdapBackend, MockDirectory},
         test_utils::{
             CookieHelper, RequestBuilderExt, ResponseExt, TestState, setup, test_site_config,
         },
@@ -448,6 +686,8 @@ mod test {
             pool,
             SiteConfig {
                 password_login_enabled: false,
+                passkeys_enabled: false,
+                email_code_login_enabled: false,
                 ..test_site_config()
             },
         )
@@ -907,4 +1147,88 @@ mod test {
         assert!(!response.body().contains("Account deleted"));
         assert!(response.body().contains("Invalid credentials"));
     }
+
+    /// Submit the login form with the given credentials
+    async fn submit_login(
+        state: &TestState,
+        cookies: &CookieHelper,
+        username: &str,
+        password: &str,
+    ) -> hyper::Response<String> {
+        let request = cookies.with_cookies(Request::get("/login").empty());
+        let response = state.request(request).await;
+        cookies.save_cookies(&response);
+        response.assert_status(StatusCode::OK);
+        let csrf_token = response
+            .body()
+            .split("name=\"csrf\" value=\"")
+            .nth(1)
+            .unwrap()
+            .split('\"')
+            .next()
+            .unwrap()
+            .to_owned();
+
+        let request = Request::post("/login").form(serde_json::json!({
+            "csrf": csrf_token,
+            "username": username,
+            "password": password,
+        }));
+        let request = cookies.with_cookies(request);
+        let response = state.request(request).await;
+        cookies.save_cookies(&response);
+        response
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_ldap_login(pool: PgPool) {
+        setup();
+        let mut state = TestState::from_pool(pool).await.unwrap();
+        let config = mas_config::LdapConfig {
+            create_users: true,
+            ..mas_config::LdapConfig::default()
+        };
+        let directory = MockDirectory::default().with_entry("john", "hunter2", &[]);
+        state.ldap = LdapBackend::with_directory(&config, std::sync::Arc::new(directory));
+
+        // This does more password checks than the default rate limits allow
+        let mut rate_limiting = mas_config::RateLimitingConfig::default();
+        rate_limiting.login.per_ip.burst = std::num::NonZeroU32::new(10).unwrap();
+        state.limiter = crate::Limiter::new(&rate_limiting).unwrap();
+
+        // A local user, which isn't in the directory
+        user_with_password(&state, "alice", "wonderland").await;
+
+        // A wrong password is rejected
+        let cookies = CookieHelper::new();
+        let response = submit_login(&state, &cookies, "john", "wrong").await;
+        response.assert_status(StatusCode::OK);
+        assert!(response.body().contains("Invalid credentials"));
+
+        // The right one creates the user and starts a session
+        let response = submit_login(&state, &cookies, "john", "hunter2").await;
+        response.assert_status(StatusCode::SEE_OTHER);
+
+        let request = cookies.with_cookies(Request::get("/").empty());
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        assert!(response.body().contains("john"));
+
+        let mut repo = state.repository().await.unwrap();
+        let user = repo.user().find_by_username("john").await.unwrap();
+        assert!(user.is_some());
+        repo.cancel().await.unwrap();
+
+        // Users which aren't in the directory use their local password
+        let cookies = CookieHelper::new();
+        let response = submit_login(&state, &cookies, "alice", "wonderland").await;
+        response.assert_status(StatusCode::SEE_OTHER);
+
+        // Which also works if the directory is unreachable
+        state.ldap =
+            LdapBackend::with_directory(&config, std::sync::Arc::new(MockDirectory::unreachable()));
+        let cookies = CookieHelper::new();
+        let response = submit_login(&state, &cookies, "alice", "wonderland").await;
+        response.assert_status(StatusCode::SEE_OTHER);
+    }
 }
@@ -0,0 +1,485 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+//! Passwordless login, using a one-time code sent to one of the user's email
+//! addresses. The flow is started from the login page.
+
+use std::sync::LazyLock;
+
+use anyhow::Context as _;
+use axum::{
+    extract::{Form, Path, Query, State},
+    response::{Html, IntoResponse, Response},
+};
+use axum_extra::typed_header::TypedHeader;
+use hyper::StatusCode;
+use mas_axum_utils::{
+    FancyError, SessionInfoExt,
+    cookies::CookieJar,
+    csrf::{CsrfExt, ProtectedForm},
+};
+use mas_data_model::{User, UserAgent, UserEmailAuthentication};
+use mas_i18n::DataLocale;
+use mas_router::UrlBuilder;
+use mas_storage::{
+    BoxClock, BoxRepository, BoxRng, Clock, RepositoryAccess, RepositoryError,
+    user::{
+        BrowserSessionRepository, UserEmailRepository, UserPasskeyRepository,
+        UserPasswordRepository, UserRepository, UserTotpRepository,
+    },
+};
+use mas_templates::{
+    FieldError, FormError, FormState, LoginEmailCodeContext, LoginEmailCodeFormField,
+    TemplateContext, Templates, ToFormState,
+};
+use opentelemetry::{Key, KeyValue, metrics::Counter};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use ulid::Ulid;
+
+use super::shared::OptionalPostAuthAction;
+use crate::{BoundActivityTracker, Limiter, METER, PreferredLanguage, SiteConfig};
+
+static EMAIL_CODE_LOGIN_COUNTER: LazyLock<Counter<u64>> = LazyLock::new(|| {
+    METER
+        .u64_counter("mas.user.email_code_login_attempt")
+        .with_description("Number of email code login attempts")
+        .with_unit("{attempt}")
+        .build()
+});
+const RESULT: Key = Key::from_static_str("result");
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct LoginEmailCodeForm {
+    code: String,
+}
+
+impl ToFormState for LoginEmailCodeForm {
+    type Field = LoginEmailCodeFormField;
+}
+
+/// Find the user who can log in with a code sent to the given email address
+///
+/// Only active users without a password nor a second factor can log in this
+/// way. Returns `None` for everyone else.
+pub(crate) async fn find_user(
+    site_config: &SiteConfig,
+    repo: &mut BoxRepository,
+    email: &str,
+) -> Result<Option<User>, RepositoryError> {
+    let Some(user_email) = repo.user_email().find_by_email(email).await? else {
+        return Ok(None);
+    };
+
+    let user = repo.user().lookup(user_email.user_id).await?;
+    let Some(user) = user.filter(User::is_valid) else {
+        return Ok(None);
+    };
+
+    if !can_log_in(site_config, repo, &user).await? {
+        return Ok(None);
+    }
+
+    Ok(Some(user))
+}
+
+/// Check that the user can log in with an email code
+async fn can_log_in(
+    site_config: &SiteConfig,
+    repo: &mut BoxRepository,
+    user: &User,
+) -> Result<bool, RepositoryError> {
+    if repo.user_password().active(user).await?.is_some() {
+        return Ok(false);
+    }
+
+    let authenticator = repo.user_totp().find(user).await?;
+    if authenticator.is_some_and(|a| a.is_verified()) {
+        return Ok(false);
+    }
+
+    if site_config.passkeys_enabled && !repo.user_passkey().all(user).await?.is_empty() {
+        return Ok(false);
+    }
+
+    Ok(true)
+}
+
+/// Load an email authentication started from the login page, which was not
+/// completed yet
+async fn load_authentication(
+    repo: &mut BoxRepository,
+    id: Ulid,
+) -> Result<UserEmailAuthentication, FancyError> {
+    let authentication = repo
+        .user_email()
+        .lookup_authentication(id)
+        .await?
+        .filter(|a| a.user_session_id.is_none() && a.user_registration_id.is_none())
+        .context("Could not find email authentication")?;
+
+    if authentication.completed_at.is_some() {
+        // XXX: display a better error here
+        return Err(FancyError::from(anyhow::anyhow!(
+            "Email authentication already completed"
+        )));
+    }
+
+    Ok(authentication)
+}
+
+#[tracing::instrument(
+    name = "handlers.views.login_email_code.get",
+    fields(user_email_authentication.id = %id),
+    skip_all,
+    err,
+)]
+pub(crate) async fn get(
+    mut rng: BoxRng,
+    clock: BoxClock,
+    PreferredLanguage(locale): PreferredLanguage,
+    State(site_config): State<SiteConfig>,
+    State(templates): State<Templates>,
+    State(url_builder): State<UrlBuilder>,
+    mut repo: BoxRepository,
+    Path(id): Path<Ulid>,
+    Query(query): Query<OptionalPostAuthAction>,
+    cookie_jar: CookieJar,
+) -> Result<Response, FancyError> {
+    if !site_config.email_code_login_enabled {
+        let login = mas_router::Login::from(query.post_auth_action);
+        return Ok(url_builder.redirect(&login).into_response());
+    }
+
+    let authentication = load_authentication(&mut repo, id).await?;
+
+    render(
+        locale,
+        cookie_jar,
+        FormState::default(),
+        authentication,
+        query,
+        &mut repo,
+        &clock,
+        &mut rng,
+        &templates,
+    )
+    .await
+}
+
+#[tracing::instrument(
+    name = "handlers.views.login_email_code.post",
+    fields(user_email_authentication.id = %id),
+    skip_all,
+    err,
+)]
+pub(crate) async fn post(
+    mut rng: BoxRng,
+    clock: BoxClock,
+    PreferredLanguage(locale): PreferredLanguage,
+    State(site_config): State<SiteConfig>,
+    State(templates): State<Templates>,
+    State(url_builder): State<UrlBuilder>,
+    State(limiter): State<Limiter>,
+    mut repo: BoxRepository,
+    activity_tracker: BoundActivityTracker,
+    Path(id): Path<Ulid>,
+    Query(query): Query<OptionalPostAuthAction>,
+    cookie_jar: CookieJar,
+    user_agent: Option<TypedHeader<headers::UserAgent>>,
+    Form(form): Form<ProtectedForm<LoginEmailCodeForm>>,
+) -> Result<Response, FancyError> {
+    let user_agent = user_agent.map(|ua| UserAgent::parse(ua.as_str().to_owned()));
+    if !site_config.email_code_login_enabled {
+        return Ok(StatusCode::METHOD_NOT_ALLOWED.into_response());
+    }
+
+    let form = cookie_jar.verify_form(&clock, form)?;
+    let form_state = form.to_form_state();
+
+    let authentication = load_authentication(&mut repo, id).await?;
+
+    if let Err(e) = limiter.check_email_authentication_attempt(&authentication) {
+        tracing::warn!(error = &e as &dyn std::error::Error);
+        let form_state = form_state.with_error_on_form(FormError::RateLimitExceeded);
+        EMAIL_CODE_LOGIN_COUNTER.add(1, &[KeyValue::new(RESULT, "error")]);
+        return render(
+            locale,
+            cookie_jar,
+            form_state,
+            authentication,
+            query,
+            &mut repo,
+            &clock,
+            &mut rng,
+            &templates,
+        )
+        .await;
+    }
+
+    // Authentications for addresses which can't be used to log in don't have a
+    // user, and no code was sent for them, so any code is invalid. Also make
+    // sure the user can still log in this way, as this might have changed
+    // since the code was sent.
+    let user = if let Some(user_id) = authentication.user_id {
+        repo.user().lookup(user_id).await?.filter(User::is_valid)
+    } else {
+        None
+    };
+    let user = match user {
+        Some(user) if can_log_in(&site_config, &mut repo, &user).await? => Some(user),
+        _ => None,
+    };
+
+    let code = repo
+        .user_email()
+        .find_authentication_code(&authentication, &form.code)
+        .await?
+        .filter(|code| code.expires_at > clock.now());
+
+    let (Some(user), Some(code)) = (user, code) else {
+        let form_state =
+            form_state.with_error_on_field(LoginEmailCodeFormField::Code, FieldError::Invalid);
+        EMAIL_CODE_LOGIN_COUNTER.add(1, &[KeyValue::new(RESULT, "error")]);
+        return render(
+            locale,
+            cookie_jar,
+            form_state,
+            authentication,
+            query,
+            &mut repo,
+            &clock,
+            &mut rng,
+            &templates,
+        )
+        .await;
+    };
+
+    let authentication = repo
+        .user_email()
+        .complete_authentication(&clock, authentication, &code)
+        .await?;
+
+    // Start a new session, authenticated by the email code
+    let user_session = repo
+        .browser_session()
+        .add(&mut rng, &clock, &user, user_agent)
+        .await?;
+
+    repo.browser_session()
+        .authenticate_with_email_code(&mut rng, &clock, &user_session, &authentication)
+        .await?;
+
+    repo.save().await?;
+
+    EMAIL_CODE_LOGIN_COUNTER.add(1, &[KeyValue::new(RESULT, "success")]);
+
+    activity_tracker
+        .record_browser_session(&clock, &user_session)
+        .await;
+
+    let cookie_jar = cookie_jar.set_session(&user_session);
+    let reply = query.go_next(&url_builder);
+    Ok((cookie_jar, reply).into_response())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn render(
+    locale: DataLocale,
+    cookie_jar: CookieJar,
+    form_state: FormState<LoginEmailCodeFormField>,
+    authentication: UserEmailAuthentication,
+    action: OptionalPostAuthAction,
+    repo: &mut impl RepositoryAccess,
+    clock: &impl Clock,
+    rng: impl Rng,
+    templates: &Templates,
+) -> Result<Response, FancyError> {
+    let (csrf_token, cookie_jar) = cookie_jar.csrf_token(clock, rng);
+
+    let ctx = LoginEmailCodeContext::new(authentication).with_form_state(form_state);
+    let next = action.load_context(repo).await?;
+    let ctx = if let Some(next) = next {
+        ctx.with_post_action(next)
+    } else {
+        ctx
+    };
+    let ctx = ctx.with_csrf(csrf_token.form_value()).with_language(locale);
+
+    let content = templates.render_login_email_code(&ctx)?;
+    Ok((cookie_jar, Html(content)).into_response())
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::Duration;
+    use hyper::{
+        Request, StatusCode,
+        header::{CONTENT_TYPE, LOCATION},
+    };
+    use mas_storage::{
+        RepositoryAccess,
+        user::{UserEmailRepository, UserPasswordRepository, UserRepository},
+    };
+    use sqlx::PgPool;
+    use ulid::Ulid;
+    use zeroize::Zeroizing;
+
+    use crate::test_utils::{CookieHelper, RequestBuilderExt, ResponseExt, TestState, setup};
+
+    fn extract_csrf(body: &str) -> String {
+        body.split("name=\"csrf\" value=\"")
+            .nth(1)
+            .unwrap()
+            .split('\"')
+            .next()
+            .unwrap()
+            .to_owned()
+    }
+
+    /// Submit the login form to ask for an email code, and return the ID of
+    /// the email authentication it started
+    async fn start(state: &TestState, cookies: &CookieHelper, email: &str) -> Ulid {
+        let request = cookies.with_cookies(Request::get("/login").empty());
+        let response = state.request(request).await;
+        cookies.save_cookies(&response);
+        response.assert_status(StatusCode::OK);
+        assert!(response.body().contains("email_code"));
+        let csrf_token = extract_csrf(response.body());
+
+        let request = Request::post("/login").form(serde_json::json!({
+            "csrf": csrf_token,
+            "username": email,
+            "password": "",
+            "method": "email_code",
+        }));
+        let request = cookies.with_cookies(request);
+        let response = state.request(request).await;
+        cookies.save_cookies(&response);
+        response.assert_status(StatusCode::SEE_OTHER);
+
+        let location = response.headers().get(LOCATION).unwrap().to_str().unwrap();
+        location
+            .strip_prefix("/login/email/")
+            .unwrap()
+            .parse()
+            .unwrap()
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_email_code_login(pool: PgPool) {
+        setup();
+        let state = TestState::from_pool(pool).await.unwrap();
+        let cookies = CookieHelper::new();
+        let mut rng = state.rng();
+
+        // Provision a user without a password, and one with a password
+        let mut repo = state.repository().await.unwrap();
+        let user = repo
+            .user()
+            .add(&mut rng, &state.clock, "john".to_owned())
+            .await
+            .unwrap();
+        repo.user_email()
+            .add(&mut rng, &state.clock, &user, "john@example.com".to_owned())
+            .await
+            .unwrap();
+
+        let other = repo
+            .user()
+            .add(&mut rng, &state.clock, "jane".to_owned())
+            .await
+            .unwrap();
+        repo.user_email()
+            .add(
+                &mut rng,
+                &state.clock,
+                &other,
+                "jane@example.com".to_owned(),
+            )
+            .await
+            .unwrap();
+        let (version, hash) = state
+            .password_manager
+            .hash(&mut rng, Zeroizing::new(b"hunter2".to_vec()))
+            .await
+            .unwrap();
+        repo.user_password()
+            .add(&mut rng, &state.clock, &other, version, hash, None)
+            .await
+            .unwrap();
+        repo.save().await.unwrap();
+
+        // Users with a password can't log in this way, but the flow looks the same
+        let id = start(&state, &cookies, "jane@example.com").await;
+        let mut repo = state.repository().await.unwrap();
+        let authentication = repo
+            .user_email()
+            .lookup_authentication(id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(authentication.user_id, None);
+        repo.cancel().await.unwrap();
+
+        let id = start(&state, &cookies, "john@example.com").await;
+        let mut repo = state.repository().await.unwrap();
+        let authentication = repo
+            .user_email()
+            .lookup_authentication(id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(authentication.user_id, Some(user.id));
+
+        // The code is normally generated by the job sending the email
+        repo.user_email()
+            .add_authentication_code(
+                &mut rng,
+                &state.clock,
+                Duration::minutes(5),
+                &authentication,
+                "123456".to_owned(),
+            )
+            .await
+            .unwrap();
+        repo.save().await.unwrap();
+
+        let path = format!("/login/email/{id}");
+        let request = cookies.with_cookies(Request::get(&path).empty());
+        let response = state.request(request).await;
+        cookies.save_cookies(&response);
+        response.assert_status(StatusCode::OK);
+        response.assert_header_value(CONTENT_TYPE, "text/html; charset=utf-8");
+        assert!(response.body().contains("john@example.com"));
+        let csrf_token = extract_csrf(response.body());
+
+        // A wrong code is rejected
+        let request = Request::post(&path).form(serde_json::json!({
+            "csrf": csrf_token,
+            "code": "654321",
+        }));
+        let request = cookies.with_cookies(request);
+        let response = state.request(request).await;
+        cookies.save_cookies(&response);
+        response.assert_status(StatusCode::OK);
+        let csrf_token = extract_csrf(response.body());
+
+        // The right one starts the session
+        let request = Request::post(&path).form(serde_json::json!({
+            "csrf": csrf_token,
+            "code": "123456",
+        }));
+        let request = cookies.with_cookies(request);
+        let response = state.request(request).await;
+        cookies.save_cookies(&response);
+        response.assert_status(StatusCode::SEE_OTHER);
+
+        let request = cookies.with_cookies(Request::get("/").empty());
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        assert!(response.body().contains("john"));
+    }
+}
@@ -0,0 +1,685 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+//! Login with a `WebAuthn` passkey
+//!
+//! Depending on the state of the browser, this either:
+//!
+//!  - finishes a password login which is waiting for a second factor
+//!  - re-authenticates the current session
+//!  - logs in without a password, using a discoverable credential
+
+use std::sync::LazyLock;
+
+use axum::{
+    extract::{Form, Query, State},
+    response::{Html, IntoResponse, Response},
+};
+use axum_extra::typed_header::TypedHeader;
+use hyper::StatusCode;
+use mas_axum_utils::{
+    FancyError, SessionInfoExt,
+    cookies::CookieJar,
+    csrf::{CsrfExt, ProtectedForm},
+};
+use mas_data_model::{BrowserSession, User, UserAgent};
+use mas_i18n::DataLocale;
+use mas_router::UrlBuilder;
+use mas_storage::{
+    BoxClock, BoxRepository, BoxRng, Clock, RepositoryAccess,
+    user::{
+        BrowserSessionRepository, UserPasskeyRepository, UserRecoveryCodeRepository, UserRepository,
+    },
+};
+use mas_templates::{
+    FormError, FormState, LoginPasskeyContext, LoginPasskeyFormField, TemplateContext, Templates,
+    ToFormState,
+};
+use opentelemetry::{Key, KeyValue, metrics::Counter};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use ulid::Ulid;
+
+use super::{
+    login_totp::{FirstFactor, PendingTotpLogin},
+    shared::OptionalPostAuthAction,
+};
+use crate::{
+    BoundActivityTracker, METER, PreferredLanguage, SiteConfig,
+    session::{SessionOrFallback, load_session_or_fallback},
+    webauthn::{self, AuthenticationResponse, RelyingParty},
+};
+
+static PASSKEY_LOGIN_COUNTER: LazyLock<Counter<u64>> = LazyLock::new(|| {
+    METER
+        .u64_counter("mas.user.passkey_login_attempt")
+        .with_description("Number of passkey login attempts")
+        .with_unit("{attempt}")
+        .build()
+});
+const RESULT: Key = Key::from_static_str("result");
+const MODE: Key = Key::from_static_str("mode");
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct LoginPasskeyForm {
+    challenge: String,
+    response: String,
+}
+
+impl ToFormState for LoginPasskeyForm {
+    type Field = LoginPasskeyFormField;
+}
+
+/// What the passkey is used for
+enum Mode {
+    /// Second step of a password login
+    SecondFactor {
+        user: User,
+        first_factor: FirstFactor,
+    },
+
+    /// Re-authenticating the current session
+    Reauth { session: BrowserSession },
+
+    /// Logging in with the passkey alone
+    Passwordless,
+}
+
+impl Mode {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::SecondFactor { .. } => "second_factor",
+            Self::Reauth { .. } => "reauth",
+            Self::Passwordless => "passwordless",
+        }
+    }
+
+    /// The browser session challenges are bound to in this mode
+    fn browser_session(&self) -> Option<&BrowserSession> {
+        match self {
+            Self::Reauth { session } => Some(session),
+            Self::SecondFactor { .. } | Self::Passwordless => None,
+        }
+    }
+
+    /// The user expected to own the passkey, if already known
+    fn user(&self) -> Option<&User> {
+        match self {
+            Self::SecondFactor { user, .. } => Some(user),
+            Self::Reauth { session } => Some(&session.user),
+            Self::Passwordless => None,
+        }
+    }
+
+    /// When the passkey is the only factor, the authenticator must have
+    /// verified the user, with a PIN or biometrics
+    fn require_user_verification(&self) -> bool {
+        !matches!(self, Self::SecondFactor { .. })
+    }
+}
+
+/// Figure out in which mode we are, or return an early response
+async fn load_mode(
+    cookie_jar: CookieJar,
+    clock: &BoxClock,
+    rng: &mut BoxRng,
+    templates: &Templates,
+    locale: &DataLocale,
+    url_builder: &UrlBuilder,
+    repo: &mut BoxRepository,
+    query: &OptionalPostAuthAction,
+) -> Result<Result<(CookieJar, Mode), Response>, FancyError> {
+    if let Some(pending) = PendingTotpLogin::load(&cookie_jar, clock) {
+        // Make sure the user is still allowed to log in, and that the password
+        // didn't change in the meantime. If anything changed, restart the login
+        let user = repo.user().lookup(pending.user_id()).await?;
+        let Some(user) = user.filter(User::is_valid) else {
+            let cookie_jar = PendingTotpLogin::clear(cookie_jar);
+            let login = mas_router::Login::from(query.post_auth_action.clone());
+            return Ok(Err(
+                (cookie_jar, url_builder.redirect(&login)).into_response()
+            ));
+        };
+
+        let Some(first_factor) = pending.first_factor(repo, &user).await? else {
+            let cookie_jar = PendingTotpLogin::clear(cookie_jar);
+            let login = mas_router::Login::from(query.post_auth_action.clone());
+            return Ok(Err(
+                (cookie_jar, url_builder.redirect(&login)).into_response()
+            ));
+        };
+
+        return Ok(Ok((cookie_jar, Mode::SecondFactor { user, first_factor })));
+    }
+
+    let (cookie_jar, maybe_session) =
+        match load_session_or_fallback(cookie_jar, clock, rng, templates, locale, repo).await? {
+            SessionOrFallback::MaybeSession {
+                cookie_jar,
+                maybe_session,
+                ..
+            } => (cookie_jar, maybe_session),
+            SessionOrFallback::Fallback { response } => return Ok(Err(response)),
+        };
+
+    let mode = match maybe_session {
+        Some(session) => Mode::Reauth { session },
+        None => Mode::Passwordless,
+    };
+
+    Ok(Ok((cookie_jar, mode)))
+}
+
+#[tracing::instrument(name = "handlers.views.login_passkey.get", skip_all, err)]
+pub(crate) async fn get(
+    mut rng: BoxRng,
+    clock: BoxClock,
+    PreferredLanguage(locale): PreferredLanguage,
+    State(site_config): State<SiteConfig>,
+    State(templates): State<Templates>,
+    State(url_builder): State<UrlBuilder>,
+    mut repo: BoxRepository,
+    Query(query): Query<OptionalPostAuthAction>,
+    cookie_jar: CookieJar,
+) -> Result<Response, FancyError> {
+    if !site_config.passkeys_enabled {
+        let login = mas_router::Login::from(query.post_auth_action);
+        return Ok(url_builder.redirect(&login).into_response());
+    }
+
+    let (cookie_jar, mode) = match load_mode(
+        cookie_jar,
+        &clock,
+        &mut rng,
+        &templates,
+        &locale,
+        &url_builder,
+        &mut repo,
+        &query,
+    )
+    .await?
+    {
+        Ok(res) => res,
+        Err(response) => return Ok(response),
+    };
+
+    let response = render(
+        locale,
+        cookie_jar,
+        FormState::default(),
+        query,
+        &mode,
+        &site_config,
+        &url_builder,
+        &mut repo,
+        &clock,
+        &mut rng,
+        &templates,
+    )
+    .await?;
+
+    // Save the challenge we just created
+    repo.save().await?;
+
+    Ok(response)
+}
+
+#[tracing::instrument(name = "handlers.views.login_passkey.post", skip_all, err)]
+pub(crate) async fn post(
+    mut rng: BoxRng,
+    clock: BoxClock,
+    PreferredLanguage(locale): PreferredLanguage,
+    State(site_config): State<SiteConfig>,
+    State(templates): State<Templates>,
+    State(url_builder): State<UrlBuilder>,
+    mut repo: BoxRepository,
+    activity_tracker: BoundActivityTracker,
+    Query(query): Query<OptionalPostAuthAction>,
+    cookie_jar: CookieJar,
+    user_agent: Option<TypedHeader<headers::UserAgent>>,
+    Form(form): Form<ProtectedForm<LoginPasskeyForm>>,
+) -> Result<Response, FancyError> {
+    let user_agent = user_agent.map(|ua| UserAgent::parse(ua.as_str().to_owned()));
+    if !site_config.passkeys_enabled {
+        return Ok(StatusCode::METHOD_NOT_ALLOWED.into_response());
+    }
+
+    let form = cookie_jar.verify_form(&clock, form)?;
+
+    let (cookie_jar, mode) = match load_mode(
+        cookie_jar,
+        &clock,
+        &mut rng,
+        &templates,
+        &locale,
+        &url_builder,
+        &mut repo,
+        &query,
+    )
+    .await?
+    {
+        Ok(res) => res,
+        Err(response) => return Ok(response),
+    };
+
+    let form_state = form.to_form_state();
+
+    let Some(user_passkey) =
+        check_response(&mut repo, &clock, &site_config, &url_builder, &mode, &form).await?
+    else {
+        PASSKEY_LOGIN_COUNTER.add(
+            1,
+            &[
+                KeyValue::new(RESULT, "error"),
+                KeyValue::new(MODE, mode.as_str()),
+            ],
+        );
+        let form_state = form_state.with_error_on_form(FormError::InvalidCredentials);
+        let response = render(
+            locale,
+            cookie_jar,
+            form_state,
+            query,
+            &mode,
+            &site_config,
+            &url_builder,
+            &mut repo,
+            &clock,
+            &mut rng,
+            &templates,
+        )
+        .await?;
+
+        // Save both the challenge which was answered and the new one
+        repo.save().await?;
+
+        return Ok(response);
+    };
+
+    let cookie_jar = match &mode {
+        Mode::Reauth { session } => {
+            repo.browser_session()
+                .authenticate_with_passkey(&mut rng, &clock, session, &user_passkey)
+                .await?;
+
+            activity_tracker
+                .record_browser_session(&clock, session)
+                .await;
+
+            cookie_jar.set_session(session)
+        }
+
+        Mode::SecondFactor { user, first_factor } => {
+            // Start a new session, authenticated by both factors
+            let user_session = repo
+                .browser_session()
+                .add(&mut rng, &clock, user, user_agent)
+                .await?;
+
+            first_factor
+                .authenticate(&mut rng, &clock, &mut repo, &user_session)
+                .await?;
+
+            repo.browser_session()
+                .authenticate_with_passkey(&mut rng, &clock, &user_session, &user_passkey)
+                .await?;
+
+            activity_tracker
+                .record_browser_session(&clock, &user_session)
+                .await;
+
+            let cookie_jar = PendingTotpLogin::clear(cookie_jar);
+            cookie_jar.set_session(&user_session)
+        }
+
+        Mode::Passwordless => {
+            let user = repo
+                .user()
+                .lookup(user_passkey.user_id)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("Passkey owner not found"))?;
+
+            let user_session = repo
+                .browser_session()
+                .add(&mut rng, &clock, &user, user_agent)
+                .await?;
+
+            repo.browser_session()
+                .authenticate_with_passkey(&mut rng, &clock, &user_session, &user_passkey)
+                .await?;
+
+            activity_tracker
+                .record_browser_session(&clock, &user_session)
+                .await;
+
+            cookie_jar.set_session(&user_session)
+        }
+    };
+
+    repo.save().await?;
+
+    PASSKEY_LOGIN_COUNTER.add(
+        1,
+        &[
+            KeyValue::new(RESULT, "success"),
+            KeyValue::new(MODE, mode.as_str()),
+        ],
+    );
+
+    let reply = query.go_next(&url_builder);
+    Ok((cookie_jar, reply).into_response())
+}
+
+/// Check the challenge and the signed assertion sent by the browser
+///
+/// Returns the passkey which was used, with its usage recorded, or `None` if
+/// the response should be rejected
+async fn check_response(
+    repo: &mut BoxRepository,
+    clock: &BoxClock,
+    site_config: &SiteConfig,
+    url_builder: &UrlBuilder,
+    mode: &Mode,
+    form: &LoginPasskeyForm,
+) -> Result<Option<mas_data_model::UserPasskey>, FancyError> {
+    let Ok(challenge_id) = form.challenge.parse::<Ulid>() else {
+        return Ok(None);
+    };
+
+    let challenge = repo.user_passkey().lookup_challenge(challenge_id).await?;
+    let Some(challenge) = challenge else {
+        return Ok(None);
+    };
+
+    // The challenge must have been issued for this mode, and be fresh
+    if challenge.user_session_id != mode.browser_session().map(|session| session.id)
+        || challenge.is_completed()
+        || clock.now() - challenge.created_at > webauthn::CHALLENGE_MAX_AGE
+    {
+        return Ok(None);
+    }
+
+    // Each challenge can only be answered once, even if the answer is wrong
+    let challenge = repo
+        .user_passkey()
+        .complete_challenge(clock, challenge)
+        .await?;
+
+    let response: AuthenticationResponse = match serde_json::from_str(&form.response) {
+        Ok(response) => response,
+        Err(e) => {
+            tracing::warn!(
+                error = &e as &dyn std::error::Error,
+                "Invalid passkey authentication response"
+            );
+            return Ok(None);
+        }
+    };
+
+    let user_passkey = repo
+        .user_passkey()
+        .find_by_credential_id(response.id.trim_end_matches('='))
+        .await?;
+    let Some(user_passkey) = user_passkey else {
+        return Ok(None);
+    };
+
+    let user = match mode.user() {
+        Some(user) if user.id == user_passkey.user_id => user.clone(),
+        Some(_) => return Ok(None),
+        None => {
+            let user = repo.user().lookup(user_passkey.user_id).await?;
+            let Some(user) = user.filter(User::is_valid) else {
+                return Ok(None);
+            };
+            user
+        }
+    };
+
+    let relying_party = RelyingParty::new(url_builder, &site_config.server_name);
+    let sign_count = match relying_party.verify_assertion(
+        &challenge.challenge,
+        &user,
+        &user_passkey,
+        &response,
+        mode.require_user_verification(),
+    ) {
+        Ok(sign_count) => sign_count,
+        Err(e) => {
+            tracing::warn!(
+                error = &e as &dyn std::error::Error,
+                "Passkey assertion rejected"
+            );
+            return Ok(None);
+        }
+    };
+
+    let user_passkey = repo
+        .user_passkey()
+        .record_use(clock, user_passkey, sign_count)
+        .await?;
+
+    Ok(Some(user_passkey))
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn render(
+    locale: DataLocale,
+    cookie_jar: CookieJar,
+    form_state: FormState<LoginPasskeyFormField>,
+    action: OptionalPostAuthAction,
+    mode: &Mode,
+    site_config: &SiteConfig,
+    url_builder: &UrlBuilder,
+    repo: &mut BoxRepository,
+    clock: &impl Clock,
+    rng: &mut (impl RngCore + Send),
+    templates: &Templates,
+) -> Result<Response, FancyError> {
+    let (csrf_token, cookie_jar) = cookie_jar.csrf_token(clock, &mut *rng);
+
+    // When the user is already known, only offer their passkeys. Otherwise,
+    // let the browser offer any discoverable credential for this site
+    let allowed = if let Some(user) = mode.user() {
+        repo.user_passkey().all(user).await?
+    } else {
+        Vec::new()
+    };
+
+    let challenge = webauthn::generate_challenge(&mut *rng);
+    let challenge = repo
+        .user_passkey()
+        .add_challenge(&mut *rng, clock, mode.browser_session(), challenge)
+        .await?;
+
+    let relying_party = RelyingParty::new(url_builder, &site_config.server_name);
+    let options = relying_party.request_options(
+        &challenge.challenge,
+        &allowed,
+        mode.require_user_verification(),
+    );
+
+    // On the second step of a password login, recovery codes can be used
+    // instead of the passkey
+    let recovery_codes = if let Mode::SecondFactor { user, .. } = mode {
+        repo.user_recovery_code().count_unused(user).await? > 0
+    } else {
+        false
+    };
+
+    let ctx = LoginPasskeyContext::new(challenge.id, options)
+        .with_form_state(form_state)
+        .with_recovery_codes(recovery_codes);
+    let next = action.load_context(repo).await?;
+    let ctx = if let Some(next) = next {
+        ctx.with_post_action(next)
+    } else {
+        ctx
+    };
+    let ctx = ctx.with_csrf(csrf_token.form_value()).with_language(locale);
+
+    let content = templates.render_login_passkey(&ctx)?;
+    Ok((cookie_jar, Html(content)).into_response())
+}
+
+#[cfg(test)]
+mod test {
+    use base64ct::{Base64UrlUnpadded, Encoding};
+    use hyper::{Request, StatusCode};
+    use mas_data_model::UserPasskey;
+    use mas_storage::{
+        RepositoryAccess,
+        user::{UserPasskeyRepository, UserRepository},
+    };
+    use p256::ecdsa::SigningKey;
+    use sqlx::PgPool;
+    use ulid::Ulid;
+
+    use crate::{
+        test_utils::{CookieHelper, RequestBuilderExt, ResponseExt, TestState, setup},
+        webauthn::tests::{assertion, cose_key},
+    };
+
+    fn extract_input(body: &str, name: &str) -> String {
+        body.split(&format!("name=\"{name}\" value=\""))
+            .nth(1)
+            .unwrap()
+            .split('\"')
+            .next()
+            .unwrap()
+            .to_owned()
+    }
+
+    /// Serialize an assertion like `PublicKeyCredential.toJSON()` does
+    fn response_json(
+        key: &SigningKey,
+        passkey: &UserPasskey,
+        challenge: &str,
+        count: u32,
+    ) -> String {
+        // User present and user verified
+        let response = assertion(key, passkey, challenge, 0x05, count);
+        serde_json::json!({
+            "id": response.id,
+            "rawId": response.id,
+            "type": "public-key",
+            "response": {
+                "clientDataJSON": response.response.client_data_json,
+                "authenticatorData": response.response.authenticator_data,
+                "signature": response.response.signature,
+            },
+        })
+        .to_string()
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_passwordless_passkey_login(pool: PgPool) {
+        setup();
+        let state = TestState::from_pool(pool).await.unwrap();
+        let cookies = CookieHelper::new();
+        let mut rng = state.rng();
+
+        // Provision a user with a passkey and no password
+        let key = SigningKey::random(&mut rng);
+        let mut repo = state.repository().await.unwrap();
+        let user = repo
+            .user()
+            .add(&mut rng, &state.clock, "john".to_owned())
+            .await
+            .unwrap();
+        let passkey = repo
+            .user_passkey()
+            .add(
+                &mut rng,
+                &state.clock,
+                &user,
+                Base64UrlUnpadded::encode_string(b"credential-id"),
+                "Laptop".to_owned(),
+                cose_key(&key),
+                0,
+                vec!["internal".to_owned()],
+            )
+            .await
+            .unwrap();
+        repo.save().await.unwrap();
+
+        let request = cookies.with_cookies(Request::get("/login/passkey").empty());
+        let response = state.request(request).await;
+        cookies.save_cookies(&response);
+        response.assert_status(StatusCode::OK);
+        let csrf_token = extract_input(response.body(), "csrf");
+        let challenge_id = extract_input(response.body(), "challenge");
+
+        let mut repo = state.repository().await.unwrap();
+        let challenge = repo
+            .user_passkey()
+            .lookup_challenge(challenge_id.parse::<Ulid>().unwrap())
+            .await
+            .unwrap()
+            .unwrap();
+        repo.cancel().await.unwrap();
+
+        // A response signed by another key is rejected
+        let other_key = SigningKey::random(&mut rng);
+        let request = Request::post("/login/passkey").form(serde_json::json!({
+            "csrf": csrf_token,
+            "challenge": challenge_id,
+            "response": response_json(&other_key, &passkey, &challenge.challenge, 1),
+        }));
+        let request = cookies.with_cookies(request);
+        let response = state.request(request).await;
+        cookies.save_cookies(&response);
+        response.assert_status(StatusCode::OK);
+
+        // The challenge can't be reused, even with a valid response
+        let request = Request::post("/login/passkey").form(serde_json::json!({
+            "csrf": csrf_token,
+            "challenge": challenge_id,
+            "response": response_json(&key, &passkey, &challenge.challenge, 1),
+        }));
+        let request = cookies.with_cookies(request);
+        let response = state.request(request).await;
+        cookies.save_cookies(&response);
+        response.assert_status(StatusCode::OK);
+        let csrf_token = extract_input(response.body(), "csrf");
+        let challenge_id = extract_input(response.body(), "challenge");
+
+        let mut repo = state.repository().await.unwrap();
+        let challenge = repo
+            .user_passkey()
+            .lookup_challenge(challenge_id.parse::<Ulid>().unwrap())
+            .await
+            .unwrap()
+            .unwrap();
+        repo.cancel().await.unwrap();
+
+        // A fresh challenge with the right key starts the session
+        let request = Request::post("/login/passkey").form(serde_json::json!({
+            "csrf": csrf_token,
+            "challenge": challenge_id,
+            "response": response_json(&key, &passkey, &challenge.challenge, 1),
+        }));
+        let request = cookies.with_cookies(request);
+        let response = state.request(request).await;
+        cookies.save_cookies(&response);
+        response.assert_status(StatusCode::SEE_OTHER);
+
+        let request = cookies.with_cookies(Request::get("/").empty());
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        assert!(response.body().contains("john"));
+
+        // The signature counter was saved
+        let mut repo = state.repository().await.unwrap();
+        let passkey = repo
+            .user_passkey()
+            .lookup(passkey.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(passkey.sign_count, 1);
+        assert!(passkey.last_used_at.is_some());
+    }
+}
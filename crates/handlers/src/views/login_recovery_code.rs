@@ -0,0 +1,389 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+//! Second step of the password login, using one of the user's recovery codes
+//! instead of their second factor
+
+use std::sync::LazyLock;
+
+use axum::{
+    extract::{Form, Query, State},
+    response::{Html, IntoResponse, Response},
+};
+use axum_extra::typed_header::TypedHeader;
+use hyper::StatusCode;
+use mas_axum_utils::{
+    FancyError, SessionInfoExt,
+    cookies::CookieJar,
+    csrf::{CsrfExt, ProtectedForm},
+};
+use mas_data_model::{User, UserAgent};
+use mas_i18n::DataLocale;
+use mas_router::UrlBuilder;
+use mas_storage::{
+    BoxClock, BoxRepository, BoxRng, Clock, RepositoryAccess,
+    queue::{QueueJobRepositoryExt as _, SendRecoveryCodeUsedEmailJob},
+    user::{BrowserSessionRepository, UserRepository},
+};
+use mas_templates::{
+    FieldError, FormError, FormState, LoginRecoveryCodeContext, LoginRecoveryCodeFormField,
+    TemplateContext, Templates, ToFormState,
+};
+use opentelemetry::{Key, KeyValue, metrics::Counter};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::{login_totp::PendingTotpLogin, shared::OptionalPostAuthAction};
+use crate::{
+    BoundActivityTracker, Limiter, METER, PreferredLanguage, RequesterFingerprint, SiteConfig,
+    passwords::PasswordManager,
+};
+
+static RECOVERY_CODE_LOGIN_COUNTER: LazyLock<Counter<u64>> = LazyLock::new(|| {
+    METER
+        .u64_counter("mas.user.recovery_code_login_attempt")
+        .with_description("Number of recovery code attempts after a password login")
+        .with_unit("{attempt}")
+        .build()
+});
+const RESULT: Key = Key::from_static_str("result");
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct LoginRecoveryCodeForm {
+    code: String,
+}
+
+impl ToFormState for LoginRecoveryCodeForm {
+    type Field = LoginRecoveryCodeFormField;
+}
+
+#[tracing::instrument(name = "handlers.views.login_recovery_code.get", skip_all, err)]
+pub(crate) async fn get(
+    mut rng: BoxRng,
+    clock: BoxClock,
+    PreferredLanguage(locale): PreferredLanguage,
+    State(templates): State<Templates>,
+    State(url_builder): State<UrlBuilder>,
+    mut repo: BoxRepository,
+    Query(query): Query<OptionalPostAuthAction>,
+    cookie_jar: CookieJar,
+) -> Result<Response, FancyError> {
+    let login = mas_router::Login::from(query.post_auth_action.clone());
+    let Some(pending) = PendingTotpLogin::load(&cookie_jar, &clock) else {
+        // Nothing to do here, start from the beginning
+        return Ok(url_builder.redirect(&login).into_response());
+    };
+
+    let user = repo.user().lookup(pending.user_id()).await?;
+    let Some(user) = user.filter(User::is_valid) else {
+        let cookie_jar = PendingTotpLogin::clear(cookie_jar);
+        return Ok((cookie_jar, url_builder.redirect(&login)).into_response());
+    };
+
+    // Users without any recovery code left have to use their second factor
+    if repo.user_recovery_code().count_unused(&user).await? == 0 {
+        let destination = mas_router::LoginTotp::from(query.post_auth_action);
+        return Ok(url_builder.redirect(&destination).into_response());
+    }
+
+    render(
+        locale,
+        cookie_jar,
+        FormState::default(),
+        query,
+        &mut repo,
+        &clock,
+        &mut rng,
+        &templates,
+    )
+    .await
+}
+
+#[tracing::instrument(name = "handlers.views.login_recovery_code.post", skip_all, err)]
+pub(crate) async fn post(
+    mut rng: BoxRng,
+    clock: BoxClock,
+    PreferredLanguage(locale): PreferredLanguage,
+    State(site_config): State<SiteConfig>,
+    State(templates): State<Templates>,
+    State(url_builder): State<UrlBuilder>,
+    State(limiter): State<Limiter>,
+    State(password_manager): State<PasswordManager>,
+    mut repo: BoxRepository,
+    activity_tracker: BoundActivityTracker,
+    requester: RequesterFingerprint,
+    Query(query): Query<OptionalPostAuthAction>,
+    cookie_jar: CookieJar,
+    user_agent: Option<TypedHeader<headers::UserAgent>>,
+    Form(form): Form<ProtectedForm<LoginRecoveryCodeForm>>,
+) -> Result<Response, FancyError> {
+    let user_agent = user_agent.map(|ua| UserAgent::parse(ua.as_str().to_owned()));
+    if !site_config.password_login_enabled {
+        return Ok(StatusCode::METHOD_NOT_ALLOWED.into_response());
+    }
+
+    let form = cookie_jar.verify_form(&clock, form)?;
+
+    let login = mas_router::Login::from(query.post_auth_action.clone());
+    let Some(pending) = PendingTotpLogin::load(&cookie_jar, &clock) else {
+        return Ok(url_builder.redirect(&login).into_response());
+    };
+
+    // Make sure the user is still allowed to log in, and that the password
+    // didn't change in the meantime. If anything changed, restart the login
+    let Some(user) = repo
+        .user()
+        .lookup(pending.user_id())
+        .await?
+        .filter(User::is_valid)
+    else {
+        let cookie_jar = PendingTotpLogin::clear(cookie_jar);
+        return Ok((cookie_jar, url_builder.redirect(&login)).into_response());
+    };
+    let Some(first_factor) = pending.first_factor(&mut repo, &user).await? else {
+        let cookie_jar = PendingTotpLogin::clear(cookie_jar);
+        return Ok((cookie_jar, url_builder.redirect(&login)).into_response());
+    };
+
+    let form_state = form.to_form_state();
+
+    // Codes are checked like passwords, so they share the password rate limit
+    if let Err(e) = limiter.check_password(requester, &user) {
+        tracing::warn!(error = &e as &dyn std::error::Error);
+        let form_state = form_state.with_error_on_form(FormError::RateLimitExceeded);
+        RECOVERY_CODE_LOGIN_COUNTER.add(1, &[KeyValue::new(RESULT, "error")]);
+        return render(
+            locale, cookie_jar, form_state, query, &mut repo, &clock, &mut rng, &templates,
+        )
+        .await;
+    }
+
+    let recovery_code =
+        crate::recovery_codes::find(&password_manager, &mut repo, &user, &form.code).await?;
+    let Some(recovery_code) = recovery_code else {
+        let form_state =
+            form_state.with_error_on_field(LoginRecoveryCodeFormField::Code, FieldError::Invalid);
+        RECOVERY_CODE_LOGIN_COUNTER.add(1, &[KeyValue::new(RESULT, "error")]);
+        return render(
+            locale, cookie_jar, form_state, query, &mut repo, &clock, &mut rng, &templates,
+        )
+        .await;
+    };
+
+    let recovery_code = repo
+        .user_recovery_code()
+        .consume(&clock, recovery_code)
+        .await?;
+
+    // Start a new session, authenticated by the password and the recovery code
+    let user_session = repo
+        .browser_session()
+        .add(&mut rng, &clock, &user, user_agent)
+        .await?;
+
+    first_factor
+        .authenticate(&mut rng, &clock, &mut repo, &user_session)
+        .await?;
+
+    repo.browser_session()
+        .authenticate_with_recovery_code(&mut rng, &clock, &user_session, &recovery_code)
+        .await?;
+
+    // Let the user know, in case someone else got hold of their codes
+    repo.queue_job()
+        .schedule_job(
+            &mut rng,
+            &clock,
+            SendRecoveryCodeUsedEmailJob::new(&user, &recovery_code),
+        )
+        .await?;
+
+    repo.save().await?;
+
+    RECOVERY_CODE_LOGIN_COUNTER.add(1, &[KeyValue::new(RESULT, "success")]);
+
+    activity_tracker
+        .record_browser_session(&clock, &user_session)
+        .await;
+
+    let cookie_jar = PendingTotpLogin::clear(cookie_jar);
+    let cookie_jar = cookie_jar.set_session(&user_session);
+    let reply = query.go_next(&url_builder);
+    Ok((cookie_jar, reply).into_response())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn render(
+    locale: DataLocale,
+    cookie_jar: CookieJar,
+    form_state: FormState<LoginRecoveryCodeFormField>,
+    action: OptionalPostAuthAction,
+    repo: &mut impl RepositoryAccess,
+    clock: &impl Clock,
+    rng: impl Rng,
+    templates: &Templates,
+) -> Result<Response, FancyError> {
+    let (csrf_token, cookie_jar) = cookie_jar.csrf_token(clock, rng);
+
+    let ctx = LoginRecoveryCodeContext::default().with_form_state(form_state);
+    let next = action.load_context(repo).await?;
+    let ctx = if let Some(next) = next {
+        ctx.with_post_action(next)
+    } else {
+        ctx
+    };
+    let ctx = ctx.with_csrf(csrf_token.form_value()).with_language(locale);
+
+    let content = templates.render_login_recovery_code(&ctx)?;
+    Ok((cookie_jar, Html(content)).into_response())
+}
+
+#[cfg(test)]
+mod test {
+    use hyper::{
+        Request, StatusCode,
+        header::{CONTENT_TYPE, LOCATION},
+    };
+    use mas_storage::{
+        RepositoryAccess,
+        user::{UserRecoveryCodeRepository, UserTotpRepository},
+    };
+    use sqlx::PgPool;
+    use zeroize::Zeroizing;
+
+    use crate::test_utils::{CookieHelper, RequestBuilderExt, ResponseExt, TestState, setup};
+
+    fn extract_csrf(body: &str) -> String {
+        body.split("name=\"csrf\" value=\"")
+            .nth(1)
+            .unwrap()
+            .split('\"')
+            .next()
+            .unwrap()
+            .to_owned()
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_password_login_with_recovery_code(pool: PgPool) {
+        setup();
+        let state = TestState::from_pool(pool).await.unwrap();
+        let cookies = CookieHelper::new();
+        let mut rng = state.rng();
+
+        // Provision a user with a password, a verified TOTP authenticator and
+        // recovery codes
+        let mut repo = state.repository().await.unwrap();
+        let user = repo
+            .user()
+            .add(&mut rng, &state.clock, "john".to_owned())
+            .await
+            .unwrap();
+        let (version, hash) = state
+            .password_manager
+            .hash(&mut rng, Zeroizing::new(b"hunter2".to_vec()))
+            .await
+            .unwrap();
+        repo.user_password()
+            .add(&mut rng, &state.clock, &user, version, hash, None)
+            .await
+            .unwrap();
+        let authenticator = repo
+            .user_totp()
+            .add(&mut rng, &state.clock, &user, "encrypted".to_owned())
+            .await
+            .unwrap();
+        repo.user_totp()
+            .verify(&state.clock, authenticator)
+            .await
+            .unwrap();
+        let codes = crate::recovery_codes::regenerate(
+            &mut rng,
+            &state.clock,
+            &state.password_manager,
+            &mut repo,
+            &user,
+        )
+        .await
+        .unwrap();
+        repo.save().await.unwrap();
+
+        // Going straight to the recovery code prompt redirects to the login page
+        let response = state
+            .request(Request::get("/login/recovery-code").empty())
+            .await;
+        response.assert_status(StatusCode::SEE_OTHER);
+        response.assert_header_value(LOCATION, "/login");
+
+        let request = cookies.with_cookies(Request::get("/login").empty());
+        let response = state.request(request).await;
+        cookies.save_cookies(&response);
+        response.assert_status(StatusCode::OK);
+        let csrf_token = extract_csrf(response.body());
+
+        let request = Request::post("/login").form(serde_json::json!({
+            "csrf": csrf_token,
+            "username": "john",
+            "password": "hunter2",
+        }));
+        let request = cookies.with_cookies(request);
+        let response = state.request(request).await;
+        cookies.save_cookies(&response);
+        response.assert_status(StatusCode::SEE_OTHER);
+        response.assert_header_value(LOCATION, "/login/totp");
+
+        // The TOTP prompt offers to use a recovery code instead
+        let request = cookies.with_cookies(Request::get("/login/totp").empty());
+        let response = state.request(request).await;
+        cookies.save_cookies(&response);
+        response.assert_status(StatusCode::OK);
+        assert!(response.body().contains("Use a recovery code instead"));
+
+        let request = cookies.with_cookies(Request::get("/login/recovery-code").empty());
+        let response = state.request(request).await;
+        cookies.save_cookies(&response);
+        response.assert_status(StatusCode::OK);
+        response.assert_header_value(CONTENT_TYPE, "text/html; charset=utf-8");
+        let csrf_token = extract_csrf(response.body());
+
+        // A wrong code is rejected
+        let request = Request::post("/login/recovery-code").form(serde_json::json!({
+            "csrf": csrf_token,
+            "code": "aaaa-bbbb-cccc",
+        }));
+        let request = cookies.with_cookies(request);
+        let response = state.request(request).await;
+        cookies.save_cookies(&response);
+        response.assert_status(StatusCode::OK);
+        let csrf_token = extract_csrf(response.body());
+
+        // A valid one starts the session, regardless of case and separators
+        let code = codes[3].replace('-', " ").to_uppercase();
+        let request = Request::post("/login/recovery-code").form(serde_json::json!({
+            "csrf": csrf_token,
+            "code": code,
+        }));
+        let request = cookies.with_cookies(request);
+        let response = state.request(request).await;
+        cookies.save_cookies(&response);
+        response.assert_status(StatusCode::SEE_OTHER);
+
+        let request = cookies.with_cookies(Request::get("/").empty());
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        assert!(response.body().contains("john"));
+
+        // The code was consumed
+        let mut repo = state.repository().await.unwrap();
+        assert_eq!(
+            repo.user_recovery_code().count_unused(&user).await.unwrap(),
+            9
+        );
+        let found =
+            crate::recovery_codes::find(&state.password_manager, &mut repo, &user, &codes[3])
+                .await
+                .unwrap();
+        assert!(found.is_none());
+    }
+}
@@ -0,0 +1,562 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+//! Second step of the password login, for users who enrolled a TOTP
+//! authenticator
+
+use std::sync::LazyLock;
+
+use axum::{
+    extract::{Form, Query, State},
+    response::{Html, IntoResponse, Response},
+};
+use axum_extra::typed_header::TypedHeader;
+use chrono::{DateTime, Duration, Utc};
+use hyper::StatusCode;
+use mas_axum_utils::{
+    FancyError, SessionInfoExt,
+    cookies::CookieJar,
+    csrf::{CsrfExt, ProtectedForm},
+};
+use mas_data_model::{BrowserSession, Password, User, UserAgent};
+use mas_i18n::DataLocale;
+use mas_keystore::Encrypter;
+use mas_router::{PostAuthAction, UrlBuilder};
+use mas_storage::{
+    BoxClock, BoxRepository, BoxRng, Clock, RepositoryAccess, RepositoryError,
+    user::{
+        BrowserSessionRepository, UserPasskeyRepository, UserPasswordRepository,
+        UserRecoveryCodeRepository, UserRepository, UserTotpRepository,
+    },
+};
+use mas_templates::{
+    FieldError, FormError, FormState, LoginTotpContext, LoginTotpFormField, TemplateContext,
+    Templates, ToFormState,
+};
+use opentelemetry::{Key, KeyValue, metrics::Counter};
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use ulid::Ulid;
+
+use super::shared::OptionalPostAuthAction;
+use crate::{
+    BoundActivityTracker, Limiter, METER, PreferredLanguage, RequesterFingerprint, SiteConfig,
+};
+
+static TOTP_LOGIN_COUNTER: LazyLock<Counter<u64>> = LazyLock::new(|| {
+    METER
+        .u64_counter("mas.user.totp_login_attempt")
+        .with_description("Number of TOTP code attempts after a password login")
+        .with_unit("{attempt}")
+        .build()
+});
+const RESULT: Key = Key::from_static_str("result");
+
+/// Name of the cookie
+static COOKIE_NAME: &str = "pending-totp-login";
+
+/// Pending logins expire after 10 minutes
+static PENDING_LOGIN_MAX_TIME: Duration = Duration::microseconds(10 * 60 * 1000 * 1000);
+
+/// The first factor of a login, which was checked before asking for the
+/// second one
+#[derive(Debug)]
+pub(crate) enum FirstFactor {
+    /// The password stored in the database
+    Password(Password),
+
+    /// A password checked against the LDAP directory entry with this DN
+    Ldap { dn: String },
+}
+
+impl FirstFactor {
+    /// Record the first factor as an authentication of the browser session
+    pub async fn authenticate(
+        &self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &impl Clock,
+        repo: &mut BoxRepository,
+        user_session: &BrowserSession,
+    ) -> Result<(), RepositoryError> {
+        match self {
+            Self::Password(user_password) => {
+                repo.browser_session()
+                    .authenticate_with_password(rng, clock, user_session, user_password)
+                    .await?;
+            }
+            Self::Ldap { dn } => {
+                repo.browser_session()
+                    .authenticate_with_ldap(rng, clock, user_session, dn.clone())
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A password login waiting for the second factor, saved in a cookie between
+/// the two steps
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct PendingTotpLogin {
+    id: Ulid,
+    user_id: Ulid,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    user_password_id: Option<Ulid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ldap_dn: Option<String>,
+}
+
+impl PendingTotpLogin {
+    /// Start a pending login for a user who successfully entered their
+    /// password
+    pub fn new(
+        rng: &mut impl Rng,
+        clock: &impl Clock,
+        user: &User,
+        first_factor: &FirstFactor,
+    ) -> Self {
+        let (user_password_id, ldap_dn) = match first_factor {
+            FirstFactor::Password(user_password) => (Some(user_password.id), None),
+            FirstFactor::Ldap { dn } => (None, Some(dn.clone())),
+        };
+
+        Self {
+            id: Ulid::from_datetime_with_source(clock.now().into(), rng),
+            user_id: user.id,
+            user_password_id,
+            ldap_dn,
+        }
+    }
+
+    /// The ID of the user who entered their password
+    pub fn user_id(&self) -> Ulid {
+        self.user_id
+    }
+
+    /// Load the first factor of the login, making sure that the password
+    /// didn't change in the meantime
+    pub async fn first_factor(
+        &self,
+        repo: &mut BoxRepository,
+        user: &User,
+    ) -> Result<Option<FirstFactor>, RepositoryError> {
+        match (self.user_password_id, &self.ldap_dn) {
+            (Some(user_password_id), None) => {
+                let user_password = repo.user_password().active(user).await?;
+                Ok(user_password
+                    .filter(|password| password.id == user_password_id)
+                    .map(FirstFactor::Password))
+            }
+            (None, Some(dn)) => Ok(Some(FirstFactor::Ldap { dn: dn.clone() })),
+            _ => Ok(None),
+        }
+    }
+
+    fn expired(&self, now: DateTime<Utc>) -> bool {
+        let Ok(ts) = self.id.timestamp_ms().try_into() else {
+            return true;
+        };
+        let Some(when) = DateTime::from_timestamp_millis(ts) else {
+            return true;
+        };
+        now - when > PENDING_LOGIN_MAX_TIME
+    }
+
+    /// Load the pending login from the cookie, if any and not expired
+    pub fn load(cookie_jar: &CookieJar, clock: &impl Clock) -> Option<Self> {
+        match cookie_jar.load::<Self>(COOKIE_NAME) {
+            Ok(Some(pending)) if !pending.expired(clock.now()) => Some(pending),
+            Ok(_) => None,
+            Err(e) => {
+                tracing::warn!("Invalid pending TOTP login cookie: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Save the pending login to the cookie jar
+    pub fn save(&self, cookie_jar: CookieJar) -> CookieJar {
+        cookie_jar.save(COOKIE_NAME, self, false)
+    }
+
+    /// Remove the pending login from the cookie jar
+    pub fn clear(cookie_jar: CookieJar) -> CookieJar {
+        cookie_jar.remove(COOKIE_NAME)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct LoginTotpForm {
+    code: String,
+}
+
+impl ToFormState for LoginTotpForm {
+    type Field = LoginTotpFormField;
+}
+
+#[tracing::instrument(name = "handlers.views.login_totp.get", skip_all, err)]
+pub(crate) async fn get(
+    mut rng: BoxRng,
+    clock: BoxClock,
+    PreferredLanguage(locale): PreferredLanguage,
+    State(site_config): State<SiteConfig>,
+    State(templates): State<Templates>,
+    State(url_builder): State<UrlBuilder>,
+    mut repo: BoxRepository,
+    Query(query): Query<OptionalPostAuthAction>,
+    cookie_jar: CookieJar,
+) -> Result<Response, FancyError> {
+    let Some(pending) = PendingTotpLogin::load(&cookie_jar, &clock) else {
+        // Nothing to do here, start from the beginning
+        let login = mas_router::Login::from(query.post_auth_action);
+        return Ok(url_builder.redirect(&login).into_response());
+    };
+
+    let user = repo.user().lookup(pending.user_id).await?;
+    let Some(user) = user.filter(User::is_valid) else {
+        let cookie_jar = PendingTotpLogin::clear(cookie_jar);
+        let login = mas_router::Login::from(query.post_auth_action);
+        return Ok((cookie_jar, url_builder.redirect(&login)).into_response());
+    };
+
+    let has_passkeys =
+        site_config.passkeys_enabled && !repo.user_passkey().all(&user).await?.is_empty();
+
+    // Users with only passkeys as a second factor go straight to the passkey
+    // prompt
+    let authenticator = repo.user_totp().find(&user).await?;
+    if !authenticator.is_some_and(|a| a.is_verified()) {
+        if has_passkeys {
+            let destination = mas_router::LoginPasskey::from(query.post_auth_action);
+            return Ok(url_builder.redirect(&destination).into_response());
+        }
+
+        let cookie_jar = PendingTotpLogin::clear(cookie_jar);
+        let login = mas_router::Login::from(query.post_auth_action);
+        return Ok((cookie_jar, url_builder.redirect(&login)).into_response());
+    }
+
+    let has_recovery_codes = repo.user_recovery_code().count_unused(&user).await? > 0;
+
+    render(
+        locale,
+        cookie_jar,
+        FormState::default(),
+        query,
+        has_passkeys,
+        has_recovery_codes,
+        &mut repo,
+        &clock,
+        &mut rng,
+        &templates,
+    )
+    .await
+}
+
+#[tracing::instrument(name = "handlers.views.login_totp.post", skip_all, err)]
+pub(crate) async fn post(
+    mut rng: BoxRng,
+    clock: BoxClock,
+    PreferredLanguage(locale): PreferredLanguage,
+    State(site_config): State<SiteConfig>,
+    State(templates): State<Templates>,
+    State(url_builder): State<UrlBuilder>,
+    State(limiter): State<Limiter>,
+    State(encrypter): State<Encrypter>,
+    mut repo: BoxRepository,
+    activity_tracker: BoundActivityTracker,
+    requester: RequesterFingerprint,
+    Query(query): Query<OptionalPostAuthAction>,
+    cookie_jar: CookieJar,
+    user_agent: Option<TypedHeader<headers::UserAgent>>,
+    Form(form): Form<ProtectedForm<LoginTotpForm>>,
+) -> Result<Response, FancyError> {
+    let user_agent = user_agent.map(|ua| UserAgent::parse(ua.as_str().to_owned()));
+    if !site_config.password_login_enabled {
+        return Ok(StatusCode::METHOD_NOT_ALLOWED.into_response());
+    }
+
+    let form = cookie_jar.verify_form(&clock, form)?;
+
+    let login = mas_router::Login::from(query.post_auth_action.clone());
+    let Some(pending) = PendingTotpLogin::load(&cookie_jar, &clock) else {
+        return Ok(url_builder.redirect(&login).into_response());
+    };
+
+    // Make sure the user is still allowed to log in, and that the password
+    // didn't change in the meantime. If anything changed, restart the login
+    let Some(user) = repo
+        .user()
+        .lookup(pending.user_id)
+        .await?
+        .filter(User::is_valid)
+    else {
+        let cookie_jar = PendingTotpLogin::clear(cookie_jar);
+        return Ok((cookie_jar, url_builder.redirect(&login)).into_response());
+    };
+    let first_factor = pending.first_factor(&mut repo, &user).await?;
+    let authenticator = repo.user_totp().find(&user).await?;
+    let (Some(first_factor), Some(authenticator)) = (first_factor, authenticator) else {
+        let cookie_jar = PendingTotpLogin::clear(cookie_jar);
+        return Ok((cookie_jar, url_builder.redirect(&login)).into_response());
+    };
+    if !authenticator.is_verified() {
+        let cookie_jar = PendingTotpLogin::clear(cookie_jar);
+        return Ok((cookie_jar, url_builder.redirect(&login)).into_response());
+    }
+
+    let form_state = form.to_form_state();
+    let has_passkeys =
+        site_config.passkeys_enabled && !repo.user_passkey().all(&user).await?.is_empty();
+    let has_recovery_codes = repo.user_recovery_code().count_unused(&user).await? > 0;
+
+    // Codes are short, so they share the password rate limit
+    if let Err(e) = limiter.check_password(requester, &user) {
+        tracing::warn!(error = &e as &dyn std::error::Error);
+        let form_state = form_state.with_error_on_form(FormError::RateLimitExceeded);
+        TOTP_LOGIN_COUNTER.add(1, &[KeyValue::new(RESULT, "error")]);
+        return render(
+            locale,
+            cookie_jar,
+            form_state,
+            query,
+            has_passkeys,
+            has_recovery_codes,
+            &mut repo,
+            &clock,
+            &mut rng,
+            &templates,
+        )
+        .await;
+    }
+
+    let secret = encrypter.decrypt_string(&authenticator.encrypted_secret)?;
+    let Some(step) = crate::totp::verify(
+        &secret,
+        &form.code,
+        clock.now(),
+        authenticator.last_used_step,
+    ) else {
+        let form_state =
+            form_state.with_error_on_field(LoginTotpFormField::Code, FieldError::Invalid);
+        TOTP_LOGIN_COUNTER.add(1, &[KeyValue::new(RESULT, "error")]);
+        return render(
+            locale,
+            cookie_jar,
+            form_state,
+            query,
+            has_passkeys,
+            has_recovery_codes,
+            &mut repo,
+            &clock,
+            &mut rng,
+            &templates,
+        )
+        .await;
+    };
+
+    let authenticator = repo.user_totp().record_use(authenticator, step).await?;
+
+    // Start a new session, authenticated by both factors
+    let user_session = repo
+        .browser_session()
+        .add(&mut rng, &clock, &user, user_agent)
+        .await?;
+
+    first_factor
+        .authenticate(&mut rng, &clock, &mut repo, &user_session)
+        .await?;
+
+    repo.browser_session()
+        .authenticate_with_totp(&mut rng, &clock, &user_session, &authenticator)
+        .await?;
+
+    repo.save().await?;
+
+    TOTP_LOGIN_COUNTER.add(1, &[KeyValue::new(RESULT, "success")]);
+
+    activity_tracker
+        .record_browser_session(&clock, &user_session)
+        .await;
+
+    let cookie_jar = PendingTotpLogin::clear(cookie_jar);
+    let cookie_jar = cookie_jar.set_session(&user_session);
+    let reply = query.go_next(&url_builder);
+    Ok((cookie_jar, reply).into_response())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn render(
+    locale: DataLocale,
+    cookie_jar: CookieJar,
+    form_state: FormState<LoginTotpFormField>,
+    action: OptionalPostAuthAction,
+    passkeys: bool,
+    recovery_codes: bool,
+    repo: &mut impl RepositoryAccess,
+    clock: &impl Clock,
+    rng: impl Rng,
+    templates: &Templates,
+) -> Result<Response, FancyError> {
+    let (csrf_token, cookie_jar) = cookie_jar.csrf_token(clock, rng);
+
+    let ctx = LoginTotpContext::default()
+        .with_form_state(form_state)
+        .with_passkeys(passkeys)
+        .with_recovery_codes(recovery_codes);
+    let next = action.load_context(repo).await?;
+    let ctx = if let Some(next) = next {
+        ctx.with_post_action(next)
+    } else {
+        ctx
+    };
+    let ctx = ctx.with_csrf(csrf_token.form_value()).with_language(locale);
+
+    let content = templates.render_login_totp(&ctx)?;
+    Ok((cookie_jar, Html(content)).into_response())
+}
+
+/// Redirect to the TOTP prompt, remembering that the password step succeeded
+pub(crate) fn redirect(
+    cookie_jar: CookieJar,
+    url_builder: &UrlBuilder,
+    pending: &PendingTotpLogin,
+    post_auth_action: Option<PostAuthAction>,
+) -> Response {
+    let cookie_jar = pending.save(cookie_jar);
+    let destination = mas_router::LoginTotp::from(post_auth_action);
+    (cookie_jar, url_builder.redirect(&destination)).into_response()
+}
+
+#[cfg(test)]
+mod test {
+    use hyper::{
+        Request, StatusCode,
+        header::{CONTENT_TYPE, LOCATION},
+    };
+    use mas_storage::{Clock, RepositoryAccess, user::UserTotpRepository};
+    use sqlx::PgPool;
+    use zeroize::Zeroizing;
+
+    use crate::test_utils::{CookieHelper, RequestBuilderExt, ResponseExt, TestState, setup};
+
+    const SECRET: &[u8] = b"12345678901234567890";
+
+    fn extract_csrf(body: &str) -> String {
+        body.split("name=\"csrf\" value=\"")
+            .nth(1)
+            .unwrap()
+            .split('\"')
+            .next()
+            .unwrap()
+            .to_owned()
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_password_login_with_totp(pool: PgPool) {
+        setup();
+        let state = TestState::from_pool(pool).await.unwrap();
+        let cookies = CookieHelper::new();
+        let mut rng = state.rng();
+
+        // Provision a user with a password and a verified TOTP authenticator
+        let mut repo = state.repository().await.unwrap();
+        let user = repo
+            .user()
+            .add(&mut rng, &state.clock, "john".to_owned())
+            .await
+            .unwrap();
+        let (version, hash) = state
+            .password_manager
+            .hash(&mut rng, Zeroizing::new(b"hunter2".to_vec()))
+            .await
+            .unwrap();
+        repo.user_password()
+            .add(&mut rng, &state.clock, &user, version, hash, None)
+            .await
+            .unwrap();
+        let encrypted_secret = state.encrypter.encrypt_to_string(SECRET).unwrap();
+        let authenticator = repo
+            .user_totp()
+            .add(&mut rng, &state.clock, &user, encrypted_secret)
+            .await
+            .unwrap();
+        repo.user_totp()
+            .verify(&state.clock, authenticator)
+            .await
+            .unwrap();
+        repo.save().await.unwrap();
+
+        // Going straight to the TOTP prompt redirects to the login page
+        let response = state.request(Request::get("/login/totp").empty()).await;
+        response.assert_status(StatusCode::SEE_OTHER);
+        response.assert_header_value(LOCATION, "/login");
+
+        let request = cookies.with_cookies(Request::get("/login").empty());
+        let response = state.request(request).await;
+        cookies.save_cookies(&response);
+        response.assert_status(StatusCode::OK);
+        let csrf_token = extract_csrf(response.body());
+
+        // Submitting the password asks for a code
+        let request = Request::post("/login").form(serde_json::json!({
+            "csrf": csrf_token,
+            "username": "john",
+            "password": "hunter2",
+        }));
+        let request = cookies.with_cookies(request);
+        let response = state.request(request).await;
+        cookies.save_cookies(&response);
+        response.assert_status(StatusCode::SEE_OTHER);
+        response.assert_header_value(LOCATION, "/login/totp");
+
+        // There is no session yet
+        let request = cookies.with_cookies(Request::get("/").empty());
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        assert!(!response.body().contains("john"));
+
+        let request = cookies.with_cookies(Request::get("/login/totp").empty());
+        let response = state.request(request).await;
+        cookies.save_cookies(&response);
+        response.assert_status(StatusCode::OK);
+        response.assert_header_value(CONTENT_TYPE, "text/html; charset=utf-8");
+        let csrf_token = extract_csrf(response.body());
+
+        // A wrong code is rejected
+        let code = crate::totp::code_at(SECRET, state.clock.now());
+        let wrong_code = if code == "000000" { "111111" } else { "000000" };
+        let request = Request::post("/login/totp").form(serde_json::json!({
+            "csrf": csrf_token,
+            "code": wrong_code,
+        }));
+        let request = cookies.with_cookies(request);
+        let response = state.request(request).await;
+        cookies.save_cookies(&response);
+        response.assert_status(StatusCode::OK);
+        let csrf_token = extract_csrf(response.body());
+
+        // The right one starts the session
+        let request = Request::post("/login/totp").form(serde_json::json!({
+            "csrf": csrf_token,
+            "code": code,
+        }));
+        let request = cookies.with_cookies(request);
+        let response = state.request(request).await;
+        cookies.save_cookies(&response);
+        response.assert_status(StatusCode::SEE_OTHER);
+
+        let request = cookies.with_cookies(Request::get("/").empty());
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        assert!(response.body().contains("john"));
+
+        // The code was recorded, so it can't be used again
+        let mut repo = state.repository().await.unwrap();
+        let authenticator = repo.user_totp().find(&user).await.unwrap().unwrap();
+        assert!(authenticator.last_used_step.is_some());
+    }
+}
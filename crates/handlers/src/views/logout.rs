@@ -14,13 +14,18 @@ use mas_axum_utils::{
     csrf::{CsrfExt, ProtectedForm},
 };
 use mas_router::{PostAuthAction, UrlBuilder};
-use mas_storage::{BoxClock, BoxRepository, user::BrowserSessionRepository};
+use mas_storage::{
+    BoxClock, BoxRepository, BoxRng,
+    queue::{BrowserSessionBackchannelLogoutJob, QueueJobRepositoryExt as _},
+    user::BrowserSessionRepository,
+};
 
 use crate::BoundActivityTracker;
 
 #[tracing::instrument(name = "handlers.views.logout.post", skip_all, err)]
 pub(crate) async fn post(
     clock: BoxClock,
+    mut rng: BoxRng,
     mut repo: BoxRepository,
     cookie_jar: CookieJar,
     State(url_builder): State<UrlBuilder>,
@@ -39,6 +44,14 @@ pub(crate) async fn post(
                     .record_browser_session(&clock, &session)
                     .await;
 
+                repo.queue_job()
+                    .schedule_job(
+                        &mut rng,
+                        &clock,
+                        BrowserSessionBackchannelLogoutJob::new(&session),
+                    )
+                    .await?;
+
                 repo.browser_session().finish(&clock, session).await?;
             }
         }
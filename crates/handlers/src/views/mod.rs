@@ -7,6 +7,10 @@
 pub mod app;
 pub mod index;
 pub mod login;
+pub mod login_email_code;
+pub mod login_passkey;
+pub mod login_recovery_code;
+pub mod login_totp;
 pub mod logout;
 pub mod reauth;
 pub mod recovery;
@@ -0,0 +1,962 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+//! A minimal `WebAuthn` relying party, to register and use passkeys
+//!
+//! Only the `none` attestation conveyance is requested: the attestation
+//! statement is ignored, and the credential public key is trusted as-is. The
+//! supported algorithms are ES256, `EdDSA` (Ed25519) and RS256, which covers
+//! all the common platform and roaming authenticators.
+
+use base64ct::{Base64UrlUnpadded, Encoding};
+use chrono::Duration;
+use mas_data_model::{User, UserPasskey};
+use mas_router::UrlBuilder;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+/// Length of the generated challenges, in bytes
+const CHALLENGE_LENGTH: usize = 32;
+
+/// How long the browser should wait for the user, in milliseconds
+const TIMEOUT_MS: u32 = 5 * 60 * 1000;
+
+/// Challenges expire a bit after the browser stops waiting for the user
+pub const CHALLENGE_MAX_AGE: Duration = Duration::microseconds(10 * 60 * 1000 * 1000);
+
+/// COSE algorithm identifiers, as per the IANA COSE registry
+const COSE_ALG_ES256: i128 = -7;
+const COSE_ALG_EDDSA: i128 = -8;
+const COSE_ALG_RS256: i128 = -257;
+
+/// Authenticator data flags
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_USER_VERIFIED: u8 = 0x04;
+const FLAG_ATTESTED_CREDENTIAL_DATA: u8 = 0x40;
+
+#[derive(Debug, Error)]
+pub enum WebauthnError {
+    #[error("invalid base64url encoding for {0}")]
+    Encoding(&'static str),
+
+    #[error("invalid response")]
+    Response(#[from] serde_json::Error),
+
+    #[error("invalid client data")]
+    ClientData(#[source] serde_json::Error),
+
+    #[error("unexpected client data type {0:?}")]
+    ClientDataType(String),
+
+    #[error("challenge mismatch")]
+    ChallengeMismatch,
+
+    #[error("unexpected origin {0:?}")]
+    OriginMismatch(String),
+
+    #[error("invalid CBOR data")]
+    Cbor,
+
+    #[error("invalid authenticator data")]
+    AuthenticatorData,
+
+    #[error("relying party ID mismatch")]
+    RpIdMismatch,
+
+    #[error("the user was not present")]
+    UserNotPresent,
+
+    #[error("the user was not verified")]
+    UserNotVerified,
+
+    #[error("credential ID mismatch")]
+    CredentialIdMismatch,
+
+    #[error("user handle mismatch")]
+    UserHandleMismatch,
+
+    #[error("unsupported or invalid credential public key")]
+    PublicKey,
+
+    #[error("invalid signature")]
+    Signature,
+
+    #[error("signature counter went backwards, the authenticator may have been cloned")]
+    SignCount,
+}
+
+/// Generate a new random challenge, base64url-encoded
+pub fn generate_challenge(rng: &mut (impl RngCore + ?Sized)) -> String {
+    let mut challenge = [0; CHALLENGE_LENGTH];
+    rng.fill_bytes(&mut challenge);
+    Base64UrlUnpadded::encode_string(&challenge)
+}
+
+/// The opaque handle identifying a user to authenticators
+fn user_handle(user: &User) -> String {
+    Base64UrlUnpadded::encode_string(&user.id.to_bytes())
+}
+
+fn decode(what: &'static str, value: &str) -> Result<Vec<u8>, WebauthnError> {
+    Base64UrlUnpadded::decode_vec(value.trim_end_matches('='))
+        .map_err(|_| WebauthnError::Encoding(what))
+}
+
+/// The response of a `navigator.credentials.create()` call, as serialized by
+/// `PublicKeyCredential.toJSON()`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegistrationResponse {
+    pub id: String,
+    pub response: AuthenticatorAttestationResponse,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthenticatorAttestationResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    pub attestation_object: String,
+    #[serde(default)]
+    pub transports: Vec<String>,
+}
+
+/// The response of a `navigator.credentials.get()` call, as serialized by
+/// `PublicKeyCredential.toJSON()`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthenticationResponse {
+    pub id: String,
+    pub response: AuthenticatorAssertionResponse,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthenticatorAssertionResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    pub authenticator_data: String,
+    pub signature: String,
+    pub user_handle: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CollectedClientData {
+    #[serde(rename = "type")]
+    kind: String,
+    challenge: String,
+    origin: String,
+}
+
+/// A credential which passed the registration ceremony
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RegisteredCredential {
+    /// The credential ID, base64url-encoded
+    pub credential_id: String,
+
+    /// The credential public key, as a COSE key
+    pub public_key: Vec<u8>,
+
+    /// The initial signature counter
+    pub sign_count: u32,
+
+    /// The transports reported by the browser
+    pub transports: Vec<String>,
+}
+
+/// The relying party, which is this service, as seen by authenticators
+#[derive(Debug, Clone)]
+pub struct RelyingParty {
+    id: String,
+    name: String,
+    origin: String,
+}
+
+impl RelyingParty {
+    /// Create the relying party from the public base URL of the service
+    pub fn new(url_builder: &UrlBuilder, name: &str) -> Self {
+        Self {
+            id: url_builder.public_hostname().to_owned(),
+            name: name.to_owned(),
+            origin: url_builder.http_base().origin().ascii_serialization(),
+        }
+    }
+
+    /// Build the options to pass to `navigator.credentials.create()`, in the
+    /// format accepted by `PublicKeyCredential.parseCreationOptionsFromJSON()`
+    pub fn creation_options(
+        &self,
+        challenge: &str,
+        user: &User,
+        existing: &[UserPasskey],
+    ) -> serde_json::Value {
+        let exclude_credentials: Vec<_> = existing
+            .iter()
+            .map(|passkey| {
+                json!({
+                    "type": "public-key",
+                    "id": passkey.credential_id,
+                    "transports": passkey.transports,
+                })
+            })
+            .collect();
+
+        json!({
+            "rp": {
+                "id": self.id,
+                "name": self.name,
+            },
+            "user": {
+                "id": user_handle(user),
+                "name": user.username,
+                "displayName": user.username,
+            },
+            "challenge": challenge,
+            "pubKeyCredParams": [
+                { "type": "public-key", "alg": COSE_ALG_ES256 },
+                { "type": "public-key", "alg": COSE_ALG_EDDSA },
+                { "type": "public-key", "alg": COSE_ALG_RS256 },
+            ],
+            "timeout": TIMEOUT_MS,
+            "excludeCredentials": exclude_credentials,
+            "authenticatorSelection": {
+                "residentKey": "preferred",
+                "userVerification": "preferred",
+            },
+            "attestation": "none",
+        })
+    }
+
+    /// Build the options to pass to `navigator.credentials.get()`, in the
+    /// format accepted by `PublicKeyCredential.parseRequestOptionsFromJSON()`
+    ///
+    /// An empty list of allowed credentials lets the user pick any
+    /// discoverable credential, which is what passwordless logins use.
+    pub fn request_options(
+        &self,
+        challenge: &str,
+        allowed: &[UserPasskey],
+        require_user_verification: bool,
+    ) -> serde_json::Value {
+        let allow_credentials: Vec<_> = allowed
+            .iter()
+            .map(|passkey| {
+                json!({
+                    "type": "public-key",
+                    "id": passkey.credential_id,
+                    "transports": passkey.transports,
+                })
+            })
+            .collect();
+
+        json!({
+            "rpId": self.id,
+            "challenge": challenge,
+            "timeout": TIMEOUT_MS,
+            "allowCredentials": allow_credentials,
+            "userVerification": if require_user_verification { "required" } else { "preferred" },
+        })
+    }
+
+    fn verify_client_data(
+        &self,
+        client_data_json: &[u8],
+        expected_type: &str,
+        challenge: &str,
+    ) -> Result<(), WebauthnError> {
+        let client_data: CollectedClientData =
+            serde_json::from_slice(client_data_json).map_err(WebauthnError::ClientData)?;
+
+        if client_data.kind != expected_type {
+            return Err(WebauthnError::ClientDataType(client_data.kind));
+        }
+
+        if client_data.challenge.trim_end_matches('=') != challenge {
+            return Err(WebauthnError::ChallengeMismatch);
+        }
+
+        if client_data.origin != self.origin {
+            return Err(WebauthnError::OriginMismatch(client_data.origin));
+        }
+
+        Ok(())
+    }
+
+    fn verify_flags(
+        &self,
+        auth_data: &AuthenticatorData<'_>,
+        require_user_verification: bool,
+    ) -> Result<(), WebauthnError> {
+        if auth_data.rp_id_hash != Sha256::digest(self.id.as_bytes()).as_slice() {
+            return Err(WebauthnError::RpIdMismatch);
+        }
+
+        if auth_data.flags & FLAG_USER_PRESENT == 0 {
+            return Err(WebauthnError::UserNotPresent);
+        }
+
+        if require_user_verification && auth_data.flags & FLAG_USER_VERIFIED == 0 {
+            return Err(WebauthnError::UserNotVerified);
+        }
+
+        Ok(())
+    }
+
+    /// Verify the response of a registration ceremony
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the response is invalid, or doesn't match the
+    /// challenge
+    pub fn verify_registration(
+        &self,
+        challenge: &str,
+        response: &RegistrationResponse,
+    ) -> Result<RegisteredCredential, WebauthnError> {
+        let client_data_json = decode("clientDataJSON", &response.response.client_data_json)?;
+        self.verify_client_data(&client_data_json, "webauthn.create", challenge)?;
+
+        let attestation_object =
+            decode("attestationObject", &response.response.attestation_object)?;
+        let (attestation_object, _) = cbor::decode(&attestation_object)?;
+        let auth_data = attestation_object
+            .get_text("authData")
+            .and_then(cbor::Value::as_bytes)
+            .ok_or(WebauthnError::Cbor)?;
+        let auth_data = AuthenticatorData::parse(auth_data)?;
+
+        self.verify_flags(&auth_data, false)?;
+
+        let Some((credential_id, public_key)) = auth_data.attested_credential else {
+            return Err(WebauthnError::AuthenticatorData);
+        };
+
+        let credential_id = Base64UrlUnpadded::encode_string(credential_id);
+        if credential_id != response.id.trim_end_matches('=') {
+            return Err(WebauthnError::CredentialIdMismatch);
+        }
+
+        // Make sure we'll be able to use the key later on
+        CoseKey::parse(public_key)?;
+
+        Ok(RegisteredCredential {
+            credential_id,
+            public_key: public_key.to_vec(),
+            sign_count: auth_data.sign_count,
+            transports: response.response.transports.clone(),
+        })
+    }
+
+    /// Verify the response of an authentication ceremony for the given passkey
+    ///
+    /// Returns the new signature counter, which should be saved
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the response is invalid, doesn't match the
+    /// challenge, or isn't signed by the passkey
+    pub fn verify_assertion(
+        &self,
+        challenge: &str,
+        user: &User,
+        passkey: &UserPasskey,
+        response: &AuthenticationResponse,
+        require_user_verification: bool,
+    ) -> Result<u32, WebauthnError> {
+        if response.id.trim_end_matches('=') != passkey.credential_id {
+            return Err(WebauthnError::CredentialIdMismatch);
+        }
+
+        if let Some(handle) = &response.response.user_handle {
+            if handle.trim_end_matches('=') != user_handle(user) {
+                return Err(WebauthnError::UserHandleMismatch);
+            }
+        }
+
+        let client_data_json = decode("clientDataJSON", &response.response.client_data_json)?;
+        self.verify_client_data(&client_data_json, "webauthn.get", challenge)?;
+
+        let raw_auth_data = decode("authenticatorData", &response.response.authenticator_data)?;
+        let auth_data = AuthenticatorData::parse(&raw_auth_data)?;
+        self.verify_flags(&auth_data, require_user_verification)?;
+
+        // The signature covers the authenticator data and the hash of the
+        // client data
+        let mut message = raw_auth_data.clone();
+        message.extend_from_slice(&Sha256::digest(&client_data_json));
+
+        let signature = decode("signature", &response.response.signature)?;
+        CoseKey::parse(&passkey.public_key)?.verify(&message, &signature)?;
+
+        // Authenticators which don't implement the counter always return 0
+        if (auth_data.sign_count != 0 || passkey.sign_count != 0)
+            && auth_data.sign_count <= passkey.sign_count
+        {
+            return Err(WebauthnError::SignCount);
+        }
+
+        Ok(auth_data.sign_count)
+    }
+}
+
+/// The parsed authenticator data, as per section 6.1 of the `WebAuthn` spec
+struct AuthenticatorData<'a> {
+    rp_id_hash: &'a [u8],
+    flags: u8,
+    sign_count: u32,
+
+    /// The credential ID and COSE public key, only present during
+    /// registration
+    attested_credential: Option<(&'a [u8], &'a [u8])>,
+}
+
+impl<'a> AuthenticatorData<'a> {
+    fn parse(data: &'a [u8]) -> Result<Self, WebauthnError> {
+        if data.len() < 37 {
+            return Err(WebauthnError::AuthenticatorData);
+        }
+
+        let (rp_id_hash, rest) = data.split_at(32);
+        let flags = rest[0];
+        let sign_count = u32::from_be_bytes([rest[1], rest[2], rest[3], rest[4]]);
+        let rest = &rest[5..];
+
+        let attested_credential = if flags & FLAG_ATTESTED_CREDENTIAL_DATA == 0 {
+            None
+        } else {
+            // 16 bytes of AAGUID, then the length of the credential ID
+            if rest.len() < 18 {
+                return Err(WebauthnError::AuthenticatorData);
+            }
+            let length = usize::from(u16::from_be_bytes([rest[16], rest[17]]));
+            let rest = &rest[18..];
+            if rest.len() < length {
+                return Err(WebauthnError::AuthenticatorData);
+            }
+            let (credential_id, rest) = rest.split_at(length);
+
+            // The public key is followed by the extensions, if any, so we need
+            // to decode it to know where it ends
+            let (_, key_length) = cbor::decode(rest)?;
+            Some((credential_id, &rest[..key_length]))
+        };
+
+        Ok(Self {
+            rp_id_hash,
+            flags,
+            sign_count,
+            attested_credential,
+        })
+    }
+}
+
+/// A credential public key, decoded from its COSE representation
+enum CoseKey {
+    Es256(p256::ecdsa::VerifyingKey),
+    EdDsa(ed25519_dalek::VerifyingKey),
+    Rs256(rsa::pkcs1v15::VerifyingKey<Sha256>),
+}
+
+impl CoseKey {
+    /// Parse a COSE key, as per RFC 9053
+    fn parse(data: &[u8]) -> Result<Self, WebauthnError> {
+        let (key, _) = cbor::decode(data).map_err(|_| WebauthnError::PublicKey)?;
+        let param = |label: i128| key.get_int(label).ok_or(WebauthnError::PublicKey);
+        let bytes = |label: i128| param(label)?.as_bytes().ok_or(WebauthnError::PublicKey);
+
+        let kty = param(1)?.as_int();
+        let alg = param(3)?.as_int();
+        match (kty, alg) {
+            // EC2 key on the P-256 curve
+            (Some(2), Some(COSE_ALG_ES256)) if param(-1)?.as_int() == Some(1) => {
+                let (x, y) = (bytes(-2)?, bytes(-3)?);
+                if x.len() != 32 || y.len() != 32 {
+                    return Err(WebauthnError::PublicKey);
+                }
+                let point = p256::EncodedPoint::from_affine_coordinates(x.into(), y.into(), false);
+                let key = p256::ecdsa::VerifyingKey::from_encoded_point(&point)
+                    .map_err(|_| WebauthnError::PublicKey)?;
+                Ok(Self::Es256(key))
+            }
+
+            // OKP key on the Ed25519 curve
+            (Some(1), Some(COSE_ALG_EDDSA)) if param(-1)?.as_int() == Some(6) => {
+                let x: &[u8; 32] = bytes(-2)?
+                    .try_into()
+                    .map_err(|_| WebauthnError::PublicKey)?;
+                let key = ed25519_dalek::VerifyingKey::from_bytes(x)
+                    .map_err(|_| WebauthnError::PublicKey)?;
+                Ok(Self::EdDsa(key))
+            }
+
+            // RSA key
+            (Some(3), Some(COSE_ALG_RS256)) => {
+                let n = rsa::BigUint::from_bytes_be(bytes(-1)?);
+                let e = rsa::BigUint::from_bytes_be(bytes(-2)?);
+                let key = rsa::RsaPublicKey::new(n, e).map_err(|_| WebauthnError::PublicKey)?;
+                Ok(Self::Rs256(rsa::pkcs1v15::VerifyingKey::new(key)))
+            }
+
+            _ => Err(WebauthnError::PublicKey),
+        }
+    }
+
+    fn verify(&self, message: &[u8], signature: &[u8]) -> Result<(), WebauthnError> {
+        use signature::Verifier;
+
+        match self {
+            // ECDSA signatures are DER-encoded in WebAuthn
+            Self::Es256(key) => {
+                let signature = p256::ecdsa::Signature::from_der(signature)
+                    .map_err(|_| WebauthnError::Signature)?;
+                key.verify(message, &signature)
+            }
+            Self::EdDsa(key) => {
+                let signature = ed25519_dalek::Signature::from_slice(signature)
+                    .map_err(|_| WebauthnError::Signature)?;
+                key.verify_strict(message, &signature)
+            }
+            Self::Rs256(key) => {
+                let signature = rsa::pkcs1v15::Signature::try_from(signature)
+                    .map_err(|_| WebauthnError::Signature)?;
+                key.verify(message, &signature)
+            }
+        }
+        .map_err(|_| WebauthnError::Signature)
+    }
+}
+
+/// Just enough of a CBOR decoder (RFC 8949) to read attestation objects and
+/// COSE keys
+mod cbor {
+    use super::WebauthnError;
+
+    /// Maximum nesting of arrays and maps
+    const MAX_DEPTH: usize = 16;
+
+    #[derive(Debug, PartialEq, Eq)]
+    pub enum Value {
+        Integer(i128),
+        Bytes(Vec<u8>),
+        Text(String),
+        Array(Vec<Value>),
+        Map(Vec<(Value, Value)>),
+        Bool(bool),
+        Null,
+    }
+
+    impl Value {
+        pub fn as_int(&self) -> Option<i128> {
+            match self {
+                Self::Integer(value) => Some(*value),
+                _ => None,
+            }
+        }
+
+        pub fn as_bytes(&self) -> Option<&[u8]> {
+            match self {
+                Self::Bytes(value) => Some(value),
+                _ => None,
+            }
+        }
+
+        fn get(&self, predicate: impl Fn(&Value) -> bool) -> Option<&Value> {
+            match self {
+                Self::Map(entries) => entries
+                    .iter()
+                    .find(|(key, _)| predicate(key))
+                    .map(|(_, value)| value),
+                _ => None,
+            }
+        }
+
+        /// Get the value of a map entry with an integer key
+        pub fn get_int(&self, label: i128) -> Option<&Value> {
+            self.get(|key| key.as_int() == Some(label))
+        }
+
+        /// Get the value of a map entry with a text key
+        pub fn get_text(&self, label: &str) -> Option<&Value> {
+            self.get(|key| matches!(key, Self::Text(key) if key == label))
+        }
+    }
+
+    /// Decode a single CBOR item, returning it along with the number of bytes
+    /// it used
+    pub fn decode(data: &[u8]) -> Result<(Value, usize), WebauthnError> {
+        let mut reader = Reader { data, position: 0 };
+        let value = reader.value(0)?;
+        Ok((value, reader.position))
+    }
+
+    struct Reader<'a> {
+        data: &'a [u8],
+        position: usize,
+    }
+
+    impl Reader<'_> {
+        fn take(&mut self, length: usize) -> Result<&[u8], WebauthnError> {
+            let end = self
+                .position
+                .checked_add(length)
+                .filter(|end| *end <= self.data.len())
+                .ok_or(WebauthnError::Cbor)?;
+            let slice = &self.data[self.position..end];
+            self.position = end;
+            Ok(slice)
+        }
+
+        fn remaining(&self) -> usize {
+            self.data.len() - self.position
+        }
+
+        /// Read the argument of an item header
+        fn argument(&mut self, info: u8) -> Result<u64, WebauthnError> {
+            let length = match info {
+                0..24 => return Ok(u64::from(info)),
+                24 => 1,
+                25 => 2,
+                26 => 4,
+                27 => 8,
+                // Indefinite lengths and reserved values are not supported
+                _ => return Err(WebauthnError::Cbor),
+            };
+
+            Ok(self
+                .take(length)?
+                .iter()
+                .fold(0, |acc, byte| (acc << 8) | u64::from(*byte)))
+        }
+
+        /// Read a length, making sure it can't be larger than the remaining
+        /// input, to avoid large allocations on malformed data
+        fn length(&mut self, info: u8) -> Result<usize, WebauthnError> {
+            usize::try_from(self.argument(info)?)
+                .ok()
+                .filter(|length| *length <= self.remaining())
+                .ok_or(WebauthnError::Cbor)
+        }
+
+        fn value(&mut self, depth: usize) -> Result<Value, WebauthnError> {
+            if depth > MAX_DEPTH {
+                return Err(WebauthnError::Cbor);
+            }
+
+            let header = self.take(1)?[0];
+            let (major, info) = (header >> 5, header & 0x1f);
+            match major {
+                0 => Ok(Value::Integer(i128::from(self.argument(info)?))),
+                1 => Ok(Value::Integer(-1 - i128::from(self.argument(info)?))),
+                2 => {
+                    let length = self.length(info)?;
+                    Ok(Value::Bytes(self.take(length)?.to_vec()))
+                }
+                3 => {
+                    let length = self.length(info)?;
+                    let text =
+                        std::str::from_utf8(self.take(length)?).map_err(|_| WebauthnError::Cbor)?;
+                    Ok(Value::Text(text.to_owned()))
+                }
+                4 => {
+                    let length = self.length(info)?;
+                    let items = (0..length)
+                        .map(|_| self.value(depth + 1))
+                        .collect::<Result<_, _>>()?;
+                    Ok(Value::Array(items))
+                }
+                5 => {
+                    let length = self.length(info)?;
+                    let entries = (0..length)
+                        .map(|_| {
+                            Ok::<_, WebauthnError>((self.value(depth + 1)?, self.value(depth + 1)?))
+                        })
+                        .collect::<Result<_, _>>()?;
+                    Ok(Value::Map(entries))
+                }
+                7 => match info {
+                    20 => Ok(Value::Bool(false)),
+                    21 => Ok(Value::Bool(true)),
+                    22 => Ok(Value::Null),
+                    _ => Err(WebauthnError::Cbor),
+                },
+                // Tags are not supported
+                _ => Err(WebauthnError::Cbor),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use mas_storage::{Clock, clock::MockClock};
+    use p256::ecdsa::{DerSignature, SigningKey, signature::Signer};
+    use rand::SeedableRng;
+    use ulid::Ulid;
+
+    use super::*;
+
+    /// Encode a CBOR item header
+    fn header(major: u8, argument: usize) -> Vec<u8> {
+        let major = major << 5;
+        match argument {
+            0..24 => vec![major | u8::try_from(argument).unwrap()],
+            24..256 => vec![major | 0x18, u8::try_from(argument).unwrap()],
+            _ => {
+                let mut out = vec![major | 0x19];
+                out.extend_from_slice(&u16::try_from(argument).unwrap().to_be_bytes());
+                out
+            }
+        }
+    }
+
+    /// Encode the public part of a P-256 key as a COSE key
+    pub(crate) fn cose_key(key: &SigningKey) -> Vec<u8> {
+        let point = key.verifying_key().to_encoded_point(false);
+        let mut out = header(5, 5);
+        // kty: EC2, alg: ES256, crv: P-256
+        out.extend_from_slice(&[0x01, 0x02, 0x03, 0x26, 0x20, 0x01]);
+        out.push(0x21);
+        out.extend(header(2, 32));
+        out.extend_from_slice(point.x().unwrap());
+        out.push(0x22);
+        out.extend(header(2, 32));
+        out.extend_from_slice(point.y().unwrap());
+        out
+    }
+
+    fn auth_data(rp_id: &str, flags: u8, sign_count: u32, credential: Option<&[u8]>) -> Vec<u8> {
+        let mut out = Sha256::digest(rp_id.as_bytes()).to_vec();
+        out.push(flags);
+        out.extend_from_slice(&sign_count.to_be_bytes());
+        if let Some(credential) = credential {
+            out.extend_from_slice(credential);
+        }
+        out
+    }
+
+    fn client_data(kind: &str, challenge: &str, origin: &str) -> String {
+        let json = json!({ "type": kind, "challenge": challenge, "origin": origin });
+        Base64UrlUnpadded::encode_string(json.to_string().as_bytes())
+    }
+
+    fn setup() -> (RelyingParty, User, SigningKey, String) {
+        let mut rng = rand_chacha::ChaChaRng::seed_from_u64(42);
+        let url_builder = UrlBuilder::new("https://example.com/".parse().unwrap(), None, None);
+        let rp = RelyingParty::new(&url_builder, "example.com");
+        let now = MockClock::default().now();
+        let user = User {
+            id: Ulid::from_datetime_with_source(now.into(), &mut rng),
+            username: "alice".to_owned(),
+            sub: "123".to_owned(),
+            created_at: now,
+            locked_at: None,
+            deactivated_at: None,
+            can_request_admin: false,
+            locale: None,
+            profile_updated_at: None,
+        };
+        let key = SigningKey::random(&mut rng);
+        let challenge = generate_challenge(&mut rng);
+        (rp, user, key, challenge)
+    }
+
+    fn register(
+        rp: &RelyingParty,
+        user: &User,
+        key: &SigningKey,
+        challenge: &str,
+    ) -> Result<UserPasskey, WebauthnError> {
+        let credential_id = b"credential-id";
+        let mut credential = vec![0; 16];
+        credential.extend_from_slice(&u16::try_from(credential_id.len()).unwrap().to_be_bytes());
+        credential.extend_from_slice(credential_id);
+        credential.extend(cose_key(key));
+        let auth_data = auth_data(
+            "example.com",
+            FLAG_USER_PRESENT | FLAG_ATTESTED_CREDENTIAL_DATA,
+            0,
+            Some(&credential),
+        );
+
+        // { "fmt": "none", "attStmt": {}, "authData": ... }
+        let mut attestation_object = header(5, 3);
+        attestation_object.extend(header(3, 3));
+        attestation_object.extend_from_slice(b"fmt");
+        attestation_object.extend(header(3, 4));
+        attestation_object.extend_from_slice(b"none");
+        attestation_object.extend(header(3, 7));
+        attestation_object.extend_from_slice(b"attStmt");
+        attestation_object.extend(header(5, 0));
+        attestation_object.extend(header(3, 8));
+        attestation_object.extend_from_slice(b"authData");
+        attestation_object.extend(header(2, auth_data.len()));
+        attestation_object.extend(auth_data);
+
+        let response = RegistrationResponse {
+            id: Base64UrlUnpadded::encode_string(credential_id),
+            response: AuthenticatorAttestationResponse {
+                client_data_json: client_data("webauthn.create", challenge, "https://example.com"),
+                attestation_object: Base64UrlUnpadded::encode_string(&attestation_object),
+                transports: vec!["internal".to_owned()],
+            },
+        };
+
+        let credential = rp.verify_registration(challenge, &response)?;
+        Ok(UserPasskey {
+            id: Ulid::nil(),
+            user_id: user.id,
+            credential_id: credential.credential_id,
+            name: "Test".to_owned(),
+            public_key: credential.public_key,
+            sign_count: credential.sign_count,
+            transports: credential.transports,
+            created_at: user.created_at,
+            last_used_at: None,
+        })
+    }
+
+    /// Sign an assertion for the given challenge, like an authenticator would
+    pub(crate) fn assertion(
+        key: &SigningKey,
+        passkey: &UserPasskey,
+        challenge: &str,
+        flags: u8,
+        sign_count: u32,
+    ) -> AuthenticationResponse {
+        let auth_data = auth_data("example.com", flags, sign_count, None);
+        let client_data_json = client_data("webauthn.get", challenge, "https://example.com");
+
+        let mut message = auth_data.clone();
+        message.extend_from_slice(&Sha256::digest(
+            Base64UrlUnpadded::decode_vec(&client_data_json).unwrap(),
+        ));
+        let signature: DerSignature = key.sign(&message);
+
+        AuthenticationResponse {
+            id: passkey.credential_id.clone(),
+            response: AuthenticatorAssertionResponse {
+                client_data_json,
+                authenticator_data: Base64UrlUnpadded::encode_string(&auth_data),
+                signature: Base64UrlUnpadded::encode_string(signature.as_bytes()),
+                user_handle: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_cbor_decode() {
+        let (value, length) = cbor::decode(&[0xa2, 0x01, 0x02, 0x20, 0x43, 1, 2, 3, 0xff]).unwrap();
+        assert_eq!(length, 8);
+        assert_eq!(value.get_int(1), Some(&cbor::Value::Integer(2)));
+        assert_eq!(value.get_int(-1), Some(&cbor::Value::Bytes(vec![1, 2, 3])));
+
+        // Truncated input
+        assert!(cbor::decode(&[0x43, 1, 2]).is_err());
+        // Lengths larger than the input
+        assert!(cbor::decode(&[0x9b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]).is_err());
+        // Indefinite lengths
+        assert!(cbor::decode(&[0x9f, 0x01, 0xff]).is_err());
+        // Too deeply nested
+        assert!(cbor::decode(&[0x81; 32]).is_err());
+    }
+
+    #[test]
+    fn test_registration() {
+        let (rp, user, key, challenge) = setup();
+
+        let passkey = register(&rp, &user, &key, &challenge).unwrap();
+        assert_eq!(
+            passkey.credential_id,
+            Base64UrlUnpadded::encode_string(b"credential-id")
+        );
+        assert_eq!(passkey.sign_count, 0);
+        assert_eq!(passkey.transports, vec!["internal".to_owned()]);
+
+        // Wrong challenge
+        let other = generate_challenge(&mut rand_chacha::ChaChaRng::seed_from_u64(1));
+        let response = RegistrationResponse {
+            id: passkey.credential_id.clone(),
+            response: AuthenticatorAttestationResponse {
+                client_data_json: client_data("webauthn.create", &other, "https://example.com"),
+                attestation_object: String::new(),
+                transports: Vec::new(),
+            },
+        };
+        assert!(matches!(
+            rp.verify_registration(&challenge, &response),
+            Err(WebauthnError::ChallengeMismatch)
+        ));
+
+        // Wrong origin
+        let url_builder = UrlBuilder::new("https://evil.example/".parse().unwrap(), None, None);
+        let evil = RelyingParty::new(&url_builder, "evil.example");
+        assert!(matches!(
+            register(&evil, &user, &key, &challenge),
+            Err(WebauthnError::OriginMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_assertion() {
+        let (rp, user, key, challenge) = setup();
+        let passkey = register(&rp, &user, &key, &challenge).unwrap();
+
+        let response = assertion(&key, &passkey, &challenge, FLAG_USER_PRESENT, 1);
+        assert_eq!(
+            rp.verify_assertion(&challenge, &user, &passkey, &response, false)
+                .unwrap(),
+            1
+        );
+
+        // User verification is required for passwordless logins
+        assert!(matches!(
+            rp.verify_assertion(&challenge, &user, &passkey, &response, true),
+            Err(WebauthnError::UserNotVerified)
+        ));
+        let verified = assertion(
+            &key,
+            &passkey,
+            &challenge,
+            FLAG_USER_PRESENT | FLAG_USER_VERIFIED,
+            1,
+        );
+        assert!(
+            rp.verify_assertion(&challenge, &user, &passkey, &verified, true)
+                .is_ok()
+        );
+
+        // The counter must go forward
+        let counted = UserPasskey {
+            sign_count: 1,
+            ..passkey.clone()
+        };
+        assert!(matches!(
+            rp.verify_assertion(&challenge, &user, &counted, &response, false),
+            Err(WebauthnError::SignCount)
+        ));
+
+        // Signatures from another key are rejected
+        let other_key = SigningKey::random(&mut rand_chacha::ChaChaRng::seed_from_u64(1));
+        let forged = assertion(&other_key, &passkey, &challenge, FLAG_USER_PRESENT, 2);
+        assert!(matches!(
+            rp.verify_assertion(&challenge, &user, &passkey, &forged, false),
+            Err(WebauthnError::Signature)
+        ));
+
+        // Tampered authenticator data is rejected
+        let mut tampered = assertion(&key, &passkey, &challenge, FLAG_USER_PRESENT, 2);
+        tampered.response.authenticator_data =
+            assertion(&key, &passkey, &challenge, FLAG_USER_PRESENT, 3)
+                .response
+                .authenticator_data;
+        assert!(matches!(
+            rp.verify_assertion(&challenge, &user, &passkey, &tampered, false),
+            Err(WebauthnError::Signature)
+        ));
+    }
+}
@@ -525,7 +525,43 @@ mod oidc_core {
     pub const UPDATED_AT: Claim<Timestamp> = Claim::new("updated_at");
 }
 
-pub use self::{oidc_core::*, rfc7519::*};
+/// Claims defined in the OIDC Back-Channel Logout spec
+/// <https://openid.net/specs/openid-connect-backchannel-1_0.html#LogoutToken>
+mod oidc_backchannel {
+    use super::Claim;
+
+    pub const SID: Claim<String> = Claim::new("sid");
+    pub const EVENTS: Claim<serde_json::Value> = Claim::new("events");
+}
+
+/// Claims defined in RFC9449 sec. 4.2
+/// <https://www.rfc-editor.org/rfc/rfc9449.html#section-4.2>
+mod rfc9449 {
+    use super::Claim;
+
+    pub const HTM: Claim<String> = Claim::new("htm");
+    pub const HTU: Claim<String> = Claim::new("htu");
+    pub const ATH: Claim<String> = Claim::new("ath");
+}
+
+/// Claims defined in RFC7800 sec. 3.1
+/// <https://www.rfc-editor.org/rfc/rfc7800.html#section-3.1>
+mod rfc7800 {
+    use super::Claim;
+
+    pub const CNF: Claim<serde_json::Value> = Claim::new("cnf");
+}
+
+/// Claims defined in RFC8693 sec. 4, used by RFC9068 access tokens
+/// <https://www.rfc-editor.org/rfc/rfc8693.html#section-4>
+mod rfc8693 {
+    use super::Claim;
+
+    pub const SCOPE: Claim<String> = Claim::new("scope");
+    pub const CLIENT_ID: Claim<String> = Claim::new("client_id");
+}
+
+pub use self::{oidc_backchannel::*, oidc_core::*, rfc7519::*, rfc7800::*, rfc8693::*, rfc9449::*};
 
 #[cfg(test)]
 mod tests {
@@ -5,7 +5,7 @@
 // Please see LICENSE in the repository root for full details.
 
 use digest::Digest;
-use mas_iana::jose::{JsonWebKeyEcEllipticCurve, JsonWebSignatureAlg};
+use mas_iana::jose::{JsonWebKeyEcEllipticCurve, JsonWebKeyOkpEllipticCurve, JsonWebSignatureAlg};
 use sha2::{Sha256, Sha384, Sha512};
 use signature::rand_core::CryptoRngCore;
 use thiserror::Error;
@@ -27,6 +27,12 @@ pub enum AsymmetricKeyFromJwkError {
         inner: elliptic_curve::Error,
     },
 
+    #[error("Invalid Edwards Curve parameters")]
+    EdwardsCurve {
+        #[from]
+        inner: ed25519_dalek::SignatureError,
+    },
+
     #[error("Unsupported algorithm {alg}")]
     UnsupportedAlgorithm { alg: JsonWebSignatureAlg },
 
@@ -46,6 +52,7 @@ pub enum AsymmetricSigningKey {
     Es256(super::Es256SigningKey),
     Es384(super::Es384SigningKey),
     Es256K(super::Es256KSigningKey),
+    EdDsa(super::EdDsaSigningKey),
 }
 
 impl AsymmetricSigningKey {
@@ -121,6 +128,13 @@ impl AsymmetricSigningKey {
         Self::Es256K(ecdsa::SigningKey::from(key))
     }
 
+    /// Create a new signing key with the `EdDSA` algorithm from the given
+    /// Ed25519 private key.
+    #[must_use]
+    pub fn eddsa(key: ed25519_dalek::SigningKey) -> Self {
+        Self::EdDsa(key)
+    }
+
     /// Create a new signing key for the given algorithm from the given private
     /// JWK parameters.
     ///
@@ -167,8 +181,10 @@ impl AsymmetricSigningKey {
                 Ok(Self::es256k(params.try_into()?))
             }
 
-            (JsonWebKeyPrivateParameters::Okp(_params), JsonWebSignatureAlg::EdDsa) => {
-                Err(AsymmetricKeyFromJwkError::UnsupportedAlgorithm { alg: alg.clone() })
+            (JsonWebKeyPrivateParameters::Okp(params), JsonWebSignatureAlg::EdDsa)
+                if params.crv == JsonWebKeyOkpEllipticCurve::Ed25519 =>
+            {
+                Ok(Self::eddsa(params.try_into()?))
             }
 
             _ => Err(AsymmetricKeyFromJwkError::KeyNotSuitable { alg: alg.clone() }),
@@ -230,6 +246,12 @@ impl From<super::Es256KSigningKey> for AsymmetricSigningKey {
     }
 }
 
+impl From<super::EdDsaSigningKey> for AsymmetricSigningKey {
+    fn from(key: super::EdDsaSigningKey) -> Self {
+        Self::EdDsa(key)
+    }
+}
+
 impl signature::RandomizedSigner<Signature> for AsymmetricSigningKey {
     fn try_sign_with_rng(
         &self,
@@ -273,6 +295,11 @@ impl signature::RandomizedSigner<Signature> for AsymmetricSigningKey {
                 let signature: ecdsa::Signature<_> = key.try_sign_with_rng(rng, msg)?;
                 Ok(Signature::from_signature(&signature))
             }
+            Self::EdDsa(key) => {
+                // Ed25519 signatures are deterministic, so the RNG is not used
+                let signature: ed25519_dalek::Signature = signature::Signer::try_sign(key, msg)?;
+                Ok(Signature::from_signature(&signature))
+            }
         }
     }
 }
@@ -289,6 +316,7 @@ pub enum AsymmetricVerifyingKey {
     Es256(super::Es256VerifyingKey),
     Es384(super::Es384VerifyingKey),
     Es256K(super::Es256KVerifyingKey),
+    EdDsa(super::EdDsaVerifyingKey),
 }
 
 impl AsymmetricVerifyingKey {
@@ -355,6 +383,13 @@ impl AsymmetricVerifyingKey {
         Self::Es256K(ecdsa::VerifyingKey::from(key))
     }
 
+    /// Create a new verifying key with the `EdDSA` algorithm from the given
+    /// Ed25519 public key.
+    #[must_use]
+    pub fn eddsa(key: ed25519_dalek::VerifyingKey) -> Self {
+        Self::EdDsa(key)
+    }
+
     /// Create a new verifying key for the given algorithm from the given public
     /// JWK parameters.
     ///
@@ -401,8 +436,10 @@ impl AsymmetricVerifyingKey {
                 Ok(Self::es256k(params.try_into()?))
             }
 
-            (JsonWebKeyPublicParameters::Okp(_params), JsonWebSignatureAlg::EdDsa) => {
-                Err(AsymmetricKeyFromJwkError::UnsupportedAlgorithm { alg: alg.clone() })
+            (JsonWebKeyPublicParameters::Okp(params), JsonWebSignatureAlg::EdDsa)
+                if params.crv == JsonWebKeyOkpEllipticCurve::Ed25519 =>
+            {
+                Ok(Self::eddsa(params.try_into()?))
             }
 
             _ => Err(AsymmetricKeyFromJwkError::KeyNotSuitable { alg: alg.clone() }),
@@ -464,6 +501,12 @@ impl From<super::Es256KVerifyingKey> for AsymmetricVerifyingKey {
     }
 }
 
+impl From<super::EdDsaVerifyingKey> for AsymmetricVerifyingKey {
+    fn from(key: super::EdDsaVerifyingKey) -> Self {
+        Self::EdDsa(key)
+    }
+}
+
 impl signature::Verifier<Signature> for AsymmetricVerifyingKey {
     fn verify(&self, msg: &[u8], signature: &Signature) -> Result<(), ecdsa::Error> {
         match self {
@@ -503,6 +546,10 @@ impl signature::Verifier<Signature> for AsymmetricVerifyingKey {
                 let signature: ecdsa::Signature<_> = signature.to_signature()?;
                 key.verify(msg, &signature)
             }
+            Self::EdDsa(key) => {
+                let signature: ed25519_dalek::Signature = signature.to_signature()?;
+                key.verify_strict(msg, &signature)
+            }
         }
     }
 }
@@ -0,0 +1,378 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+//! Content encryption algorithms used by JWE, as defined in [RFC 7518 section
+//! 5]
+//!
+//! [RFC 7518 section 5]: https://www.rfc-editor.org/rfc/rfc7518#section-5
+
+use aes_gcm::{AeadInPlace, Aes128Gcm, Aes256Gcm, KeyInit, Nonce, Tag};
+use cbc::cipher::{BlockCipher, BlockDecryptMut, BlockEncryptMut, KeyIvInit, block_padding::Pkcs7};
+use hmac::{Hmac, Mac};
+use mas_iana::jose::JsonWebEncryptionEnc;
+use rand::{CryptoRng, RngCore};
+use sha2::{Sha256, Sha512};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ContentEncryptionError {
+    #[error("unsupported content encryption algorithm {0}")]
+    UnsupportedAlgorithm(JsonWebEncryptionEnc),
+
+    #[error("invalid content encryption key length")]
+    InvalidKeyLength,
+
+    #[error("invalid initialization vector length")]
+    InvalidIvLength,
+
+    #[error("invalid authentication tag length")]
+    InvalidTagLength,
+
+    #[error("failed to encrypt the content")]
+    Encrypt,
+
+    #[error("failed to decrypt the content")]
+    Decrypt,
+}
+
+/// The result of encrypting some content
+pub struct EncryptedContent {
+    pub iv: Vec<u8>,
+    pub ciphertext: Vec<u8>,
+    pub tag: Vec<u8>,
+}
+
+/// The length of the content encryption key, in bytes, for the given
+/// algorithm. Returns `None` if the algorithm is not supported.
+#[must_use]
+pub fn key_len(enc: &JsonWebEncryptionEnc) -> Option<usize> {
+    match enc {
+        JsonWebEncryptionEnc::A128Gcm => Some(16),
+        JsonWebEncryptionEnc::A256Gcm | JsonWebEncryptionEnc::A128CbcHs256 => Some(32),
+        JsonWebEncryptionEnc::A256CbcHs512 => Some(64),
+        _ => None,
+    }
+}
+
+/// Encrypt and integrity-protect the plaintext with the given content
+/// encryption key, using a random initialization vector
+///
+/// # Errors
+///
+/// Returns an error if the algorithm is not supported, or if the key does not
+/// have the right length
+pub fn encrypt(
+    rng: &mut (impl RngCore + CryptoRng),
+    enc: &JsonWebEncryptionEnc,
+    cek: &[u8],
+    aad: &[u8],
+    plaintext: &[u8],
+) -> Result<EncryptedContent, ContentEncryptionError> {
+    let expected_len =
+        key_len(enc).ok_or_else(|| ContentEncryptionError::UnsupportedAlgorithm(enc.clone()))?;
+    if cek.len() != expected_len {
+        return Err(ContentEncryptionError::InvalidKeyLength);
+    }
+
+    match enc {
+        JsonWebEncryptionEnc::A128Gcm => gcm_encrypt::<Aes128Gcm>(rng, cek, aad, plaintext),
+        JsonWebEncryptionEnc::A256Gcm => gcm_encrypt::<Aes256Gcm>(rng, cek, aad, plaintext),
+        JsonWebEncryptionEnc::A128CbcHs256 => {
+            cbc_hmac_encrypt::<aes::Aes128, Hmac<Sha256>>(rng, cek, aad, plaintext)
+        }
+        JsonWebEncryptionEnc::A256CbcHs512 => {
+            cbc_hmac_encrypt::<aes::Aes256, Hmac<Sha512>>(rng, cek, aad, plaintext)
+        }
+        _ => Err(ContentEncryptionError::UnsupportedAlgorithm(enc.clone())),
+    }
+}
+
+/// Check the authentication tag and decrypt the ciphertext with the given
+/// content encryption key
+///
+/// # Errors
+///
+/// Returns an error if the algorithm is not supported, if any of the
+/// parameters does not have the right length, or if the content could not be
+/// authenticated
+pub fn decrypt(
+    enc: &JsonWebEncryptionEnc,
+    cek: &[u8],
+    aad: &[u8],
+    iv: &[u8],
+    ciphertext: &[u8],
+    tag: &[u8],
+) -> Result<Vec<u8>, ContentEncryptionError> {
+    let expected_len =
+        key_len(enc).ok_or_else(|| ContentEncryptionError::UnsupportedAlgorithm(enc.clone()))?;
+    if cek.len() != expected_len {
+        return Err(ContentEncryptionError::InvalidKeyLength);
+    }
+
+    match enc {
+        JsonWebEncryptionEnc::A128Gcm => gcm_decrypt::<Aes128Gcm>(cek, aad, iv, ciphertext, tag),
+        JsonWebEncryptionEnc::A256Gcm => gcm_decrypt::<Aes256Gcm>(cek, aad, iv, ciphertext, tag),
+        JsonWebEncryptionEnc::A128CbcHs256 => {
+            cbc_hmac_decrypt::<aes::Aes128, Hmac<Sha256>>(cek, aad, iv, ciphertext, tag)
+        }
+        JsonWebEncryptionEnc::A256CbcHs512 => {
+            cbc_hmac_decrypt::<aes::Aes256, Hmac<Sha512>>(cek, aad, iv, ciphertext, tag)
+        }
+        _ => Err(ContentEncryptionError::UnsupportedAlgorithm(enc.clone())),
+    }
+}
+
+/// AES GCM uses 96-bit initialization vectors
+const GCM_IV_LEN: usize = 12;
+
+/// AES GCM produces 128-bit authentication tags
+const GCM_TAG_LEN: usize = 16;
+
+fn gcm_encrypt<C: AeadInPlace + KeyInit>(
+    rng: &mut (impl RngCore + CryptoRng),
+    cek: &[u8],
+    aad: &[u8],
+    plaintext: &[u8],
+) -> Result<EncryptedContent, ContentEncryptionError> {
+    let cipher = C::new_from_slice(cek).map_err(|_| ContentEncryptionError::InvalidKeyLength)?;
+
+    let mut iv = vec![0; GCM_IV_LEN];
+    rng.fill_bytes(&mut iv);
+
+    let mut ciphertext = plaintext.to_vec();
+    let tag = cipher
+        .encrypt_in_place_detached(Nonce::from_slice(&iv), aad, &mut ciphertext)
+        .map_err(|_| ContentEncryptionError::Encrypt)?;
+
+    Ok(EncryptedContent {
+        iv,
+        ciphertext,
+        tag: tag.to_vec(),
+    })
+}
+
+fn gcm_decrypt<C: AeadInPlace + KeyInit>(
+    cek: &[u8],
+    aad: &[u8],
+    iv: &[u8],
+    ciphertext: &[u8],
+    tag: &[u8],
+) -> Result<Vec<u8>, ContentEncryptionError> {
+    let cipher = C::new_from_slice(cek).map_err(|_| ContentEncryptionError::InvalidKeyLength)?;
+
+    if iv.len() != GCM_IV_LEN {
+        return Err(ContentEncryptionError::InvalidIvLength);
+    }
+
+    if tag.len() != GCM_TAG_LEN {
+        return Err(ContentEncryptionError::InvalidTagLength);
+    }
+
+    let mut plaintext = ciphertext.to_vec();
+    cipher
+        .decrypt_in_place_detached(
+            Nonce::from_slice(iv),
+            aad,
+            &mut plaintext,
+            Tag::from_slice(tag),
+        )
+        .map_err(|_| ContentEncryptionError::Decrypt)?;
+
+    Ok(plaintext)
+}
+
+/// AES CBC uses 128-bit initialization vectors
+const CBC_IV_LEN: usize = 16;
+
+/// Compute the authentication tag of the composite AES CBC + HMAC SHA-2
+/// algorithms, as per RFC 7518 section 5.2.2.1
+fn cbc_hmac_tag<M: Mac + KeyInit>(
+    mac_key: &[u8],
+    aad: &[u8],
+    iv: &[u8],
+    ciphertext: &[u8],
+) -> Result<M, ContentEncryptionError> {
+    let mut mac = <M as Mac>::new_from_slice(mac_key)
+        .map_err(|_| ContentEncryptionError::InvalidKeyLength)?;
+
+    // The last block is the length of the additional authenticated data, in bits
+    let aad_len = u64::try_from(aad.len())
+        .ok()
+        .and_then(|len| len.checked_mul(8))
+        .ok_or(ContentEncryptionError::Encrypt)?;
+
+    mac.update(aad);
+    mac.update(iv);
+    mac.update(ciphertext);
+    mac.update(&aad_len.to_be_bytes());
+    Ok(mac)
+}
+
+fn cbc_hmac_encrypt<C, M>(
+    rng: &mut (impl RngCore + CryptoRng),
+    cek: &[u8],
+    aad: &[u8],
+    plaintext: &[u8],
+) -> Result<EncryptedContent, ContentEncryptionError>
+where
+    C: BlockCipher + BlockEncryptMut,
+    cbc::Encryptor<C>: KeyIvInit,
+    M: Mac + KeyInit,
+{
+    // The first half of the key is used for the MAC, the second half for the
+    // encryption
+    let (mac_key, enc_key) = cek.split_at(cek.len() / 2);
+
+    let mut iv = vec![0; CBC_IV_LEN];
+    rng.fill_bytes(&mut iv);
+
+    let ciphertext = cbc::Encryptor::<C>::new_from_slices(enc_key, &iv)
+        .map_err(|_| ContentEncryptionError::InvalidKeyLength)?
+        .encrypt_padded_vec_mut::<Pkcs7>(plaintext);
+
+    // The tag is the first half of the MAC, which is as long as the MAC key
+    let mac = cbc_hmac_tag::<M>(mac_key, aad, &iv, &ciphertext)?.finalize();
+    let tag = mac.into_bytes()[..mac_key.len()].to_vec();
+
+    Ok(EncryptedContent {
+        iv,
+        ciphertext,
+        tag,
+    })
+}
+
+fn cbc_hmac_decrypt<C, M>(
+    cek: &[u8],
+    aad: &[u8],
+    iv: &[u8],
+    ciphertext: &[u8],
+    tag: &[u8],
+) -> Result<Vec<u8>, ContentEncryptionError>
+where
+    C: BlockCipher + BlockDecryptMut,
+    cbc::Decryptor<C>: KeyIvInit,
+    M: Mac + KeyInit,
+{
+    let (mac_key, enc_key) = cek.split_at(cek.len() / 2);
+
+    if iv.len() != CBC_IV_LEN {
+        return Err(ContentEncryptionError::InvalidIvLength);
+    }
+
+    if tag.len() != mac_key.len() {
+        return Err(ContentEncryptionError::InvalidTagLength);
+    }
+
+    // Check the tag before decrypting anything
+    cbc_hmac_tag::<M>(mac_key, aad, iv, ciphertext)?
+        .verify_truncated_left(tag)
+        .map_err(|_| ContentEncryptionError::Decrypt)?;
+
+    cbc::Decryptor::<C>::new_from_slices(enc_key, iv)
+        .map_err(|_| ContentEncryptionError::InvalidKeyLength)?
+        .decrypt_padded_vec_mut::<Pkcs7>(ciphertext)
+        .map_err(|_| ContentEncryptionError::Decrypt)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+
+    /// Test vector from RFC 7518 appendix B.1
+    #[test]
+    fn test_a128cbc_hs256_vector() {
+        let cek: Vec<u8> = (0x00..0x20).collect();
+        let iv = [
+            0x1a, 0xf3, 0x8c, 0x2d, 0xc2, 0xb9, 0x6f, 0xfd, 0xd8, 0x66, 0x94, 0x09, 0x23, 0x41,
+            0xbc, 0x04,
+        ];
+        let plaintext = b"A cipher system must not be required to be secret, and it must be able \
+            to fall into the hands of the enemy without inconvenience";
+        let aad = b"The second principle of Auguste Kerckhoffs";
+
+        let (mac_key, enc_key) = cek.split_at(16);
+        let ciphertext = cbc::Encryptor::<aes::Aes128>::new_from_slices(enc_key, &iv)
+            .unwrap()
+            .encrypt_padded_vec_mut::<Pkcs7>(plaintext);
+        let mac = cbc_hmac_tag::<Hmac<Sha256>>(mac_key, aad, &iv, &ciphertext)
+            .unwrap()
+            .finalize()
+            .into_bytes();
+
+        assert_eq!(
+            &mac[..16],
+            &[
+                0x65, 0x2c, 0x3f, 0xa3, 0x6b, 0x0a, 0x7c, 0x5b, 0x32, 0x19, 0xfa, 0xb3, 0xa3, 0x0b,
+                0xc1, 0xc4
+            ]
+        );
+
+        let decrypted = decrypt(
+            &JsonWebEncryptionEnc::A128CbcHs256,
+            &cek,
+            aad,
+            &iv,
+            &ciphertext,
+            &mac[..16],
+        )
+        .unwrap();
+        assert_eq!(decrypted, plaintext);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut rng = rand_chacha::ChaChaRng::seed_from_u64(42);
+        let aad = b"header";
+        let plaintext = b"hello world";
+
+        for enc in [
+            JsonWebEncryptionEnc::A128Gcm,
+            JsonWebEncryptionEnc::A256Gcm,
+            JsonWebEncryptionEnc::A128CbcHs256,
+            JsonWebEncryptionEnc::A256CbcHs512,
+        ] {
+            let mut cek = vec![0; key_len(&enc).unwrap()];
+            rng.fill_bytes(&mut cek);
+
+            let encrypted = encrypt(&mut rng, &enc, &cek, aad, plaintext).unwrap();
+            let decrypted = decrypt(
+                &enc,
+                &cek,
+                aad,
+                &encrypted.iv,
+                &encrypted.ciphertext,
+                &encrypted.tag,
+            )
+            .unwrap();
+            assert_eq!(decrypted, plaintext);
+
+            // Tampering with the additional authenticated data must be detected
+            let result = decrypt(
+                &enc,
+                &cek,
+                b"tampered",
+                &encrypted.iv,
+                &encrypted.ciphertext,
+                &encrypted.tag,
+            );
+            assert!(matches!(result, Err(ContentEncryptionError::Decrypt)));
+        }
+
+        // A key with the wrong length is rejected
+        let result = encrypt(
+            &mut rng,
+            &JsonWebEncryptionEnc::A128Gcm,
+            &[0; 32],
+            aad,
+            plaintext,
+        );
+        assert!(matches!(
+            result,
+            Err(ContentEncryptionError::InvalidKeyLength)
+        ));
+    }
+}
@@ -4,10 +4,11 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 // Please see LICENSE in the repository root for full details.
 
-use mas_iana::jose::JsonWebSignatureAlg;
+use mas_iana::jose::{JsonWebEncryptionAlg, JsonWebEncryptionEnc, JsonWebSignatureAlg};
 use sha2::{Sha256, Sha384, Sha512};
 
 mod asymmetric;
+pub mod content_encryption;
 pub(crate) mod hmac;
 mod signature;
 mod symmetric;
@@ -42,8 +43,11 @@ pub type Es384VerifyingKey = ecdsa::VerifyingKey<p384::NistP384>;
 pub type Es256KSigningKey = ecdsa::SigningKey<k256::Secp256k1>;
 pub type Es256KVerifyingKey = ecdsa::VerifyingKey<k256::Secp256k1>;
 
+pub type EdDsaSigningKey = ed25519_dalek::SigningKey;
+pub type EdDsaVerifyingKey = ed25519_dalek::VerifyingKey;
+
 /// All the signing algorithms supported by this crate.
-pub const SUPPORTED_SIGNING_ALGORITHMS: [JsonWebSignatureAlg; 12] = [
+pub const SUPPORTED_SIGNING_ALGORITHMS: [JsonWebSignatureAlg; 13] = [
     JsonWebSignatureAlg::Hs256,
     JsonWebSignatureAlg::Hs384,
     JsonWebSignatureAlg::Hs512,
@@ -56,4 +60,22 @@ pub const SUPPORTED_SIGNING_ALGORITHMS: [JsonWebSignatureAlg; 12] = [
     JsonWebSignatureAlg::Es256,
     JsonWebSignatureAlg::Es384,
     JsonWebSignatureAlg::Es256K,
+    JsonWebSignatureAlg::EdDsa,
+];
+
+/// All the key management algorithms supported by this crate for JWE.
+pub const SUPPORTED_ENCRYPTION_ALGORITHMS: [JsonWebEncryptionAlg; 5] = [
+    JsonWebEncryptionAlg::RsaOaep256,
+    JsonWebEncryptionAlg::EcdhEs,
+    JsonWebEncryptionAlg::EcdhEsA128Kw,
+    JsonWebEncryptionAlg::EcdhEsA256Kw,
+    JsonWebEncryptionAlg::Dir,
+];
+
+/// All the content encryption algorithms supported by this crate for JWE.
+pub const SUPPORTED_ENCRYPTION_ENCODINGS: [JsonWebEncryptionEnc; 4] = [
+    JsonWebEncryptionEnc::A128CbcHs256,
+    JsonWebEncryptionEnc::A256CbcHs512,
+    JsonWebEncryptionEnc::A128Gcm,
+    JsonWebEncryptionEnc::A256Gcm,
 ];
@@ -0,0 +1,483 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+use aes_kw::{KekAes128, KekAes256};
+use base64ct::{Base64UrlUnpadded, Encoding};
+use digest::Digest;
+use elliptic_curve::{
+    AffinePoint, CurveArithmetic, FieldBytesSize, PublicKey, SecretKey,
+    ecdh::{EphemeralSecret, diffie_hellman},
+    sec1::{FromEncodedPoint, ModulusSize, ToEncodedPoint},
+};
+use mas_iana::jose::JsonWebEncryptionAlg;
+use rand::{CryptoRng, RngCore};
+use sha2::Sha256;
+use thiserror::Error;
+
+use super::{
+    header::JsonWebEncryptionHeader,
+    key::{DecryptionKey, EncryptionKey},
+};
+use crate::{
+    jwa::content_encryption::{self, ContentEncryptionError},
+    jwk::{JsonWebKeyPublicParameters, JwkEcCurve, PublicJsonWebKey},
+};
+
+/// A JWE, in its compact serialization
+#[derive(Clone, PartialEq, Eq)]
+pub struct Jwe {
+    raw: String,
+    header: JsonWebEncryptionHeader,
+    encrypted_key: Vec<u8>,
+    iv: Vec<u8>,
+    ciphertext: Vec<u8>,
+    tag: Vec<u8>,
+}
+
+impl std::fmt::Display for Jwe {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.raw)
+    }
+}
+
+impl std::fmt::Debug for Jwe {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Jwe")
+            .field("raw", &"...")
+            .field("header", &self.header)
+            .field("encrypted_key", &"...")
+            .field("iv", &"...")
+            .field("ciphertext", &"...")
+            .field("tag", &"...")
+            .finish()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum JweDecodeError {
+    #[error("JWE must have exactly five parts")]
+    InvalidParts,
+
+    #[error("failed to decode JWE part")]
+    Decode {
+        #[source]
+        inner: base64ct::Error,
+    },
+
+    #[error("failed to deserialize JWE header")]
+    DeserializeHeader {
+        #[source]
+        inner: serde_json::Error,
+    },
+}
+
+impl JweDecodeError {
+    fn decode(inner: base64ct::Error) -> Self {
+        Self::Decode { inner }
+    }
+
+    fn deserialize_header(inner: serde_json::Error) -> Self {
+        Self::DeserializeHeader { inner }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum JweEncryptionError {
+    #[error("key management algorithm {0} is not supported with this key")]
+    UnsupportedAlgorithm(JsonWebEncryptionAlg),
+
+    #[error("failed to serialize JWE header")]
+    EncodeHeader {
+        #[source]
+        inner: serde_json::Error,
+    },
+
+    #[error("failed to encrypt the content encryption key")]
+    KeyEncryption,
+
+    #[error(transparent)]
+    ContentEncryption(#[from] ContentEncryptionError),
+}
+
+#[derive(Debug, Error)]
+pub enum JweDecryptionError {
+    #[error("key management algorithm {0} is not supported with this key")]
+    UnsupportedAlgorithm(JsonWebEncryptionAlg),
+
+    #[error("the ephemeral public key is missing or invalid")]
+    InvalidEphemeralKey,
+
+    #[error("failed to decrypt the content encryption key")]
+    KeyDecryption,
+
+    #[error(transparent)]
+    ContentEncryption(#[from] ContentEncryptionError),
+}
+
+impl TryFrom<String> for Jwe {
+    type Error = JweDecodeError;
+
+    fn try_from(raw: String) -> Result<Self, Self::Error> {
+        let parts: Vec<&str> = raw.split('.').collect();
+        let [header, encrypted_key, iv, ciphertext, tag] = parts[..] else {
+            return Err(JweDecodeError::InvalidParts);
+        };
+
+        let header = Base64UrlUnpadded::decode_vec(header).map_err(JweDecodeError::decode)?;
+        let header = serde_json::from_slice(&header).map_err(JweDecodeError::deserialize_header)?;
+        let encrypted_key =
+            Base64UrlUnpadded::decode_vec(encrypted_key).map_err(JweDecodeError::decode)?;
+        let iv = Base64UrlUnpadded::decode_vec(iv).map_err(JweDecodeError::decode)?;
+        let ciphertext =
+            Base64UrlUnpadded::decode_vec(ciphertext).map_err(JweDecodeError::decode)?;
+        let tag = Base64UrlUnpadded::decode_vec(tag).map_err(JweDecodeError::decode)?;
+
+        Ok(Self {
+            raw,
+            header,
+            encrypted_key,
+            iv,
+            ciphertext,
+            tag,
+        })
+    }
+}
+
+impl TryFrom<&str> for Jwe {
+    type Error = JweDecodeError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::try_from(value.to_owned())
+    }
+}
+
+impl Jwe {
+    /// Get the JWE header
+    #[must_use]
+    pub fn header(&self) -> &JsonWebEncryptionHeader {
+        &self.header
+    }
+
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+
+    #[must_use]
+    pub fn into_string(self) -> String {
+        self.raw
+    }
+
+    /// The base64url-encoded protected header, which is used as the additional
+    /// authenticated data
+    fn protected_header(&self) -> &str {
+        self.raw.split('.').next().unwrap_or_default()
+    }
+
+    /// Encrypt the plaintext for the recipient owning the given key.
+    ///
+    /// The key management algorithm and the content encryption algorithm are
+    /// taken from the header.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the algorithms are not supported, or if the key
+    /// can't be used with the key management algorithm.
+    pub fn encrypt(
+        rng: &mut (impl RngCore + CryptoRng),
+        header: JsonWebEncryptionHeader,
+        key: &EncryptionKey,
+        plaintext: &[u8],
+    ) -> Result<Self, JweEncryptionError> {
+        let alg = header.alg().clone();
+        let enc = header.enc().clone();
+        let cek_len = content_encryption::key_len(&enc)
+            .ok_or_else(|| ContentEncryptionError::UnsupportedAlgorithm(enc.clone()))?;
+
+        let (header, cek, encrypted_key) = match (&alg, key) {
+            (JsonWebEncryptionAlg::RsaOaep256, EncryptionKey::Rsa(key)) => {
+                let cek = random_key(rng, cek_len);
+                let encrypted_key = key
+                    .encrypt(rng, rsa::Oaep::new::<Sha256>(), &cek)
+                    .map_err(|_| JweEncryptionError::KeyEncryption)?;
+                (header, cek, encrypted_key)
+            }
+
+            (
+                JsonWebEncryptionAlg::EcdhEs
+                | JsonWebEncryptionAlg::EcdhEsA128Kw
+                | JsonWebEncryptionAlg::EcdhEsA256Kw,
+                EncryptionKey::P256(_) | EncryptionKey::P384(_),
+            ) => {
+                let (shared_secret, epk) = match key {
+                    EncryptionKey::P256(key) => ephemeral_agreement(rng, key),
+                    EncryptionKey::P384(key) => ephemeral_agreement(rng, key),
+                    _ => unreachable!(),
+                };
+                let header = header.with_epk(PublicJsonWebKey::new(epk));
+                let derived_key = ecdh_es_derive_key(&header, &shared_secret, cek_len);
+
+                if alg == JsonWebEncryptionAlg::EcdhEs {
+                    (header, derived_key, Vec::new())
+                } else {
+                    let cek = random_key(rng, cek_len);
+                    let encrypted_key = wrap_key(&derived_key, &cek)?;
+                    (header, cek, encrypted_key)
+                }
+            }
+
+            (JsonWebEncryptionAlg::Dir, EncryptionKey::Direct(key)) => {
+                (header, key.clone(), Vec::new())
+            }
+
+            _ => return Err(JweEncryptionError::UnsupportedAlgorithm(alg)),
+        };
+
+        let protected_header = serde_json::to_vec(&header)
+            .map_err(|inner| JweEncryptionError::EncodeHeader { inner })?;
+        let protected_header = Base64UrlUnpadded::encode_string(&protected_header);
+
+        let encrypted =
+            content_encryption::encrypt(rng, &enc, &cek, protected_header.as_bytes(), plaintext)?;
+
+        let raw = [
+            protected_header,
+            Base64UrlUnpadded::encode_string(&encrypted_key),
+            Base64UrlUnpadded::encode_string(&encrypted.iv),
+            Base64UrlUnpadded::encode_string(&encrypted.ciphertext),
+            Base64UrlUnpadded::encode_string(&encrypted.tag),
+        ]
+        .join(".");
+
+        Ok(Self {
+            raw,
+            header,
+            encrypted_key,
+            iv: encrypted.iv,
+            ciphertext: encrypted.ciphertext,
+            tag: encrypted.tag,
+        })
+    }
+
+    /// Decrypt this JWE using the given key
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the algorithms are not supported, if the key can't
+    /// be used with the key management algorithm, or if the content could not
+    /// be decrypted.
+    pub fn decrypt(&self, key: &DecryptionKey) -> Result<Vec<u8>, JweDecryptionError> {
+        let alg = self.header.alg();
+        let enc = self.header.enc();
+        let cek_len = content_encryption::key_len(enc)
+            .ok_or_else(|| ContentEncryptionError::UnsupportedAlgorithm(enc.clone()))?;
+
+        let cek = match (alg, key) {
+            (JsonWebEncryptionAlg::RsaOaep256, DecryptionKey::Rsa(key)) => key
+                .decrypt(rsa::Oaep::new::<Sha256>(), &self.encrypted_key)
+                .map_err(|_| JweDecryptionError::KeyDecryption)?,
+
+            (
+                JsonWebEncryptionAlg::EcdhEs
+                | JsonWebEncryptionAlg::EcdhEsA128Kw
+                | JsonWebEncryptionAlg::EcdhEsA256Kw,
+                DecryptionKey::P256(_) | DecryptionKey::P384(_),
+            ) => {
+                let epk = self
+                    .header
+                    .epk()
+                    .ok_or(JweDecryptionError::InvalidEphemeralKey)?;
+                let shared_secret = match key {
+                    DecryptionKey::P256(key) => static_agreement(key, epk.params())?,
+                    DecryptionKey::P384(key) => static_agreement(key, epk.params())?,
+                    _ => unreachable!(),
+                };
+
+                let derived_key = ecdh_es_derive_key(&self.header, &shared_secret, cek_len);
+
+                if *alg == JsonWebEncryptionAlg::EcdhEs {
+                    // With direct key agreement, the encrypted key must be empty
+                    if !self.encrypted_key.is_empty() {
+                        return Err(JweDecryptionError::KeyDecryption);
+                    }
+                    derived_key
+                } else {
+                    unwrap_key(&derived_key, &self.encrypted_key)?
+                }
+            }
+
+            (JsonWebEncryptionAlg::Dir, DecryptionKey::Direct(key)) => {
+                // With direct encryption, the encrypted key must be empty
+                if !self.encrypted_key.is_empty() {
+                    return Err(JweDecryptionError::KeyDecryption);
+                }
+                key.clone()
+            }
+
+            _ => return Err(JweDecryptionError::UnsupportedAlgorithm(alg.clone())),
+        };
+
+        let plaintext = content_encryption::decrypt(
+            enc,
+            &cek,
+            self.protected_header().as_bytes(),
+            &self.iv,
+            &self.ciphertext,
+            &self.tag,
+        )?;
+
+        Ok(plaintext)
+    }
+}
+
+fn random_key(rng: &mut (impl RngCore + CryptoRng), len: usize) -> Vec<u8> {
+    let mut key = vec![0; len];
+    rng.fill_bytes(&mut key);
+    key
+}
+
+/// Agree on a shared secret with the recipient, using a freshly generated
+/// ephemeral key. Returns the shared secret and the ephemeral public key.
+fn ephemeral_agreement<C>(
+    rng: &mut (impl RngCore + CryptoRng),
+    public_key: &PublicKey<C>,
+) -> (Vec<u8>, JsonWebKeyPublicParameters)
+where
+    C: CurveArithmetic + JwkEcCurve,
+    AffinePoint<C>: FromEncodedPoint<C> + ToEncodedPoint<C>,
+    FieldBytesSize<C>: ModulusSize,
+{
+    let ephemeral = EphemeralSecret::<C>::random(rng);
+    let shared_secret = ephemeral.diffie_hellman(public_key);
+    (
+        shared_secret.raw_secret_bytes().to_vec(),
+        ephemeral.public_key().into(),
+    )
+}
+
+/// Agree on a shared secret with the sender, using its ephemeral public key
+fn static_agreement<C>(
+    secret_key: &SecretKey<C>,
+    epk: &JsonWebKeyPublicParameters,
+) -> Result<Vec<u8>, JweDecryptionError>
+where
+    C: CurveArithmetic + JwkEcCurve,
+    AffinePoint<C>: FromEncodedPoint<C> + ToEncodedPoint<C>,
+    FieldBytesSize<C>: ModulusSize,
+{
+    let epk = epk.ec().ok_or(JweDecryptionError::InvalidEphemeralKey)?;
+    if epk.crv != C::CRV {
+        return Err(JweDecryptionError::InvalidEphemeralKey);
+    }
+
+    let epk = PublicKey::<C>::try_from(epk).map_err(|_| JweDecryptionError::InvalidEphemeralKey)?;
+    let shared_secret = diffie_hellman(secret_key.to_nonzero_scalar(), epk.as_affine());
+    Ok(shared_secret.raw_secret_bytes().to_vec())
+}
+
+/// Derive a key from an ECDH-ES shared secret.
+///
+/// With `ECDH-ES`, the derived key is directly used as the content encryption
+/// key. With `ECDH-ES+A128KW` and `ECDH-ES+A256KW`, it is used to wrap the
+/// content encryption key.
+fn ecdh_es_derive_key(
+    header: &JsonWebEncryptionHeader,
+    shared_secret: &[u8],
+    cek_len: usize,
+) -> Vec<u8> {
+    let apu = header.apu().unwrap_or_default();
+    let apv = header.apv().unwrap_or_default();
+
+    match header.alg() {
+        JsonWebEncryptionAlg::EcdhEsA128Kw => {
+            concat_kdf(shared_secret, "ECDH-ES+A128KW", apu, apv, 16)
+        }
+        JsonWebEncryptionAlg::EcdhEsA256Kw => {
+            concat_kdf(shared_secret, "ECDH-ES+A256KW", apu, apv, 32)
+        }
+        // In direct key agreement mode, the algorithm ID is the content
+        // encryption algorithm
+        _ => concat_kdf(shared_secret, &header.enc().to_string(), apu, apv, cek_len),
+    }
+}
+
+fn wrap_key(kek: &[u8], cek: &[u8]) -> Result<Vec<u8>, JweEncryptionError> {
+    let wrapped = match kek.len() {
+        16 => KekAes128::try_from(kek).and_then(|kek| kek.wrap_vec(cek)),
+        32 => KekAes256::try_from(kek).and_then(|kek| kek.wrap_vec(cek)),
+        _ => return Err(JweEncryptionError::KeyEncryption),
+    };
+
+    wrapped.map_err(|_| JweEncryptionError::KeyEncryption)
+}
+
+fn unwrap_key(kek: &[u8], encrypted_key: &[u8]) -> Result<Vec<u8>, JweDecryptionError> {
+    let unwrapped = match kek.len() {
+        16 => KekAes128::try_from(kek).and_then(|kek| kek.unwrap_vec(encrypted_key)),
+        32 => KekAes256::try_from(kek).and_then(|kek| kek.unwrap_vec(encrypted_key)),
+        _ => return Err(JweDecryptionError::KeyDecryption),
+    };
+
+    unwrapped.map_err(|_| JweDecryptionError::KeyDecryption)
+}
+
+/// The Concat KDF, as defined in [NIST SP 800-56A] and profiled for JWA in
+/// [RFC 7518 section 4.6.2]
+///
+/// [NIST SP 800-56A]: https://nvlpubs.nist.gov/nistpubs/SpecialPublications/NIST.SP.800-56Ar3.pdf
+/// [RFC 7518 section 4.6.2]: https://www.rfc-editor.org/rfc/rfc7518#section-4.6.2
+#[allow(clippy::cast_possible_truncation)] // All the lengths involved are small
+fn concat_kdf(
+    shared_secret: &[u8],
+    algorithm_id: &str,
+    apu: &[u8],
+    apv: &[u8],
+    key_len: usize,
+) -> Vec<u8> {
+    let mut other_info = Vec::new();
+    for part in [algorithm_id.as_bytes(), apu, apv] {
+        other_info.extend_from_slice(&(part.len() as u32).to_be_bytes());
+        other_info.extend_from_slice(part);
+    }
+    other_info.extend_from_slice(&((key_len * 8) as u32).to_be_bytes());
+
+    let mut key = Vec::with_capacity(key_len);
+    let mut counter: u32 = 1;
+    while key.len() < key_len {
+        let round = Sha256::new()
+            .chain_update(counter.to_be_bytes())
+            .chain_update(shared_secret)
+            .chain_update(&other_info)
+            .finalize();
+        key.extend_from_slice(&round);
+        counter += 1;
+    }
+
+    key.truncate(key_len);
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Example from RFC 7518 appendix C
+    #[test]
+    fn test_concat_kdf_vector() {
+        let shared_secret = [
+            158, 86, 217, 29, 129, 113, 53, 211, 114, 131, 66, 131, 191, 132, 38, 156, 251, 49,
+            110, 163, 218, 128, 106, 72, 246, 218, 167, 121, 140, 254, 144, 196,
+        ];
+
+        let key = concat_kdf(&shared_secret, "A128GCM", b"Alice", b"Bob", 16);
+
+        assert_eq!(
+            key,
+            [
+                86, 170, 141, 234, 248, 35, 109, 32, 92, 34, 40, 205, 113, 167, 16, 26
+            ]
+        );
+    }
+}
@@ -0,0 +1,161 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+use mas_iana::jose::{JsonWebEncryptionAlg, JsonWebKeyEcEllipticCurve};
+use thiserror::Error;
+
+use crate::jwk::{JsonWebKeyPrivateParameters, JsonWebKeyPublicParameters};
+
+/// A key used to encrypt content for a recipient
+#[non_exhaustive]
+pub enum EncryptionKey {
+    /// An RSA public key, used with `RSA-OAEP-256`
+    Rsa(rsa::RsaPublicKey),
+
+    /// A P-256 public key, used with the `ECDH-ES` family of algorithms
+    P256(p256::PublicKey),
+
+    /// A P-384 public key, used with the `ECDH-ES` family of algorithms
+    P384(p384::PublicKey),
+
+    /// A symmetric key, directly used as the content encryption key with
+    /// `dir`
+    Direct(Vec<u8>),
+}
+
+#[derive(Debug, Error)]
+#[error("Invalid encryption key")]
+pub struct InvalidEncryptionKey;
+
+impl EncryptionKey {
+    /// Create an encryption key from the public parameters of a JWK
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key is invalid or of an unsupported type
+    pub fn from_jwk(params: &JsonWebKeyPublicParameters) -> Result<Self, InvalidEncryptionKey> {
+        match params {
+            JsonWebKeyPublicParameters::Rsa(params) => params
+                .try_into()
+                .map(Self::Rsa)
+                .map_err(|_| InvalidEncryptionKey),
+
+            JsonWebKeyPublicParameters::Ec(params) => match params.crv {
+                JsonWebKeyEcEllipticCurve::P256 => params
+                    .try_into()
+                    .map(Self::P256)
+                    .map_err(|_| InvalidEncryptionKey),
+                JsonWebKeyEcEllipticCurve::P384 => params
+                    .try_into()
+                    .map(Self::P384)
+                    .map_err(|_| InvalidEncryptionKey),
+                _ => Err(InvalidEncryptionKey),
+            },
+
+            JsonWebKeyPublicParameters::Okp(_) => Err(InvalidEncryptionKey),
+        }
+    }
+
+    /// Whether this key can be used with the given key management algorithm
+    #[must_use]
+    pub fn supports(&self, alg: &JsonWebEncryptionAlg) -> bool {
+        matches!(
+            (self, alg),
+            (Self::Rsa(_), JsonWebEncryptionAlg::RsaOaep256)
+                | (
+                    Self::P256(_) | Self::P384(_),
+                    JsonWebEncryptionAlg::EcdhEs
+                        | JsonWebEncryptionAlg::EcdhEsA128Kw
+                        | JsonWebEncryptionAlg::EcdhEsA256Kw
+                )
+                | (Self::Direct(_), JsonWebEncryptionAlg::Dir)
+        )
+    }
+}
+
+impl From<rsa::RsaPublicKey> for EncryptionKey {
+    fn from(key: rsa::RsaPublicKey) -> Self {
+        Self::Rsa(key)
+    }
+}
+
+impl From<p256::PublicKey> for EncryptionKey {
+    fn from(key: p256::PublicKey) -> Self {
+        Self::P256(key)
+    }
+}
+
+impl From<p384::PublicKey> for EncryptionKey {
+    fn from(key: p384::PublicKey) -> Self {
+        Self::P384(key)
+    }
+}
+
+/// A key used to decrypt content encrypted for us
+#[non_exhaustive]
+pub enum DecryptionKey {
+    /// An RSA private key, used with `RSA-OAEP-256`
+    Rsa(Box<rsa::RsaPrivateKey>),
+
+    /// A P-256 private key, used with the `ECDH-ES` family of algorithms
+    P256(p256::SecretKey),
+
+    /// A P-384 private key, used with the `ECDH-ES` family of algorithms
+    P384(p384::SecretKey),
+
+    /// A symmetric key, directly used as the content encryption key with
+    /// `dir`
+    Direct(Vec<u8>),
+}
+
+impl DecryptionKey {
+    /// Create a decryption key from the private parameters of a JWK
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key is invalid or of an unsupported type
+    pub fn from_jwk(params: &JsonWebKeyPrivateParameters) -> Result<Self, InvalidEncryptionKey> {
+        match params {
+            JsonWebKeyPrivateParameters::Rsa(params) => params
+                .try_into()
+                .map(|key| Self::Rsa(Box::new(key)))
+                .map_err(|_| InvalidEncryptionKey),
+
+            JsonWebKeyPrivateParameters::Ec(params) => match params.crv {
+                JsonWebKeyEcEllipticCurve::P256 => params
+                    .try_into()
+                    .map(Self::P256)
+                    .map_err(|_| InvalidEncryptionKey),
+                JsonWebKeyEcEllipticCurve::P384 => params
+                    .try_into()
+                    .map(Self::P384)
+                    .map_err(|_| InvalidEncryptionKey),
+                _ => Err(InvalidEncryptionKey),
+            },
+
+            JsonWebKeyPrivateParameters::Oct(_) | JsonWebKeyPrivateParameters::Okp(_) => {
+                Err(InvalidEncryptionKey)
+            }
+        }
+    }
+}
+
+impl From<rsa::RsaPrivateKey> for DecryptionKey {
+    fn from(key: rsa::RsaPrivateKey) -> Self {
+        Self::Rsa(Box::new(key))
+    }
+}
+
+impl From<p256::SecretKey> for DecryptionKey {
+    fn from(key: p256::SecretKey) -> Self {
+        Self::P256(key)
+    }
+}
+
+impl From<p384::SecretKey> for DecryptionKey {
+    fn from(key: p384::SecretKey) -> Self {
+        Self::P384(key)
+    }
+}
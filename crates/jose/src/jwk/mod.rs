@@ -7,8 +7,8 @@
 //! Ref: <https://www.rfc-editor.org/rfc/rfc7517.html>
 
 use mas_iana::jose::{
-    JsonWebKeyEcEllipticCurve, JsonWebKeyOperation, JsonWebKeyType, JsonWebKeyUse,
-    JsonWebSignatureAlg,
+    JsonWebEncryptionAlg, JsonWebKeyEcEllipticCurve, JsonWebKeyOperation, JsonWebKeyType,
+    JsonWebKeyUse, JsonWebSignatureAlg,
 };
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -35,7 +35,7 @@ pub trait ParametersInfo {
 
 /// An utilitary trait to figure out the [`JsonWebKeyEcEllipticCurve`] value for
 /// elliptic curves
-trait JwkEcCurve {
+pub(crate) trait JwkEcCurve {
     const CRV: JsonWebKeyEcEllipticCurve;
 }
 
@@ -232,6 +232,13 @@ impl<P> JsonWebKey<P> {
         self.alg.as_ref()
     }
 
+    /// Get the DER-encoded certificate containing this key, which is the first
+    /// one of the `x5c` field of this [`JsonWebKey`], if set.
+    #[must_use]
+    pub fn certificate(&self) -> Option<&[u8]> {
+        self.x5c.as_ref()?.first().map(Base64::as_bytes)
+    }
+
     /// Get the inner parameters of this [`JsonWebKey`].
     #[must_use]
     pub const fn params(&self) -> &P {
@@ -338,6 +345,35 @@ impl<P> JsonWebKeySet<P> {
         self.find_key(&constraints)
     }
 
+    /// Find a key to encrypt content for with the given key management
+    /// algorithm. Returns `None` if no suitable key was found.
+    ///
+    /// Keys explicitly meant for signatures, or for another algorithm, are
+    /// skipped.
+    #[must_use]
+    pub fn encryption_key_for_algorithm(&self, alg: &JsonWebEncryptionAlg) -> Option<&JsonWebKey<P>>
+    where
+        P: ParametersInfo,
+    {
+        let kty = match alg {
+            JsonWebEncryptionAlg::RsaOaep256 => JsonWebKeyType::Rsa,
+            JsonWebEncryptionAlg::EcdhEs
+            | JsonWebEncryptionAlg::EcdhEsA128Kw
+            | JsonWebEncryptionAlg::EcdhEsA256Kw => JsonWebKeyType::Ec,
+            _ => return None,
+        };
+
+        // The `alg` parameter of keys is parsed as a signature algorithm, so
+        // encryption algorithms end up as unknown values, which we compare as
+        // strings
+        let alg = alg.to_string();
+        self.keys.iter().find(|key| {
+            key.parameters.kty() == kty
+                && key.r#use.as_ref().is_none_or(|u| *u == JsonWebKeyUse::Enc)
+                && key.alg.as_ref().is_none_or(|a| a.to_string() == alg)
+        })
+    }
+
     /// Get a list of available signing algorithms for this [`JsonWebKeySet`]
     #[must_use]
     pub fn available_signing_algorithms(&self) -> Vec<JsonWebSignatureAlg>
@@ -368,6 +404,24 @@ mod tests {
     use super::*;
     use crate::constraints::ConstraintSet;
 
+    #[test]
+    fn rfc7638_thumbprint() {
+        // Example from RFC7638 sec. 3.1
+        let jwk = serde_json::json!({
+            "kty": "RSA",
+            "n": "0vx7agoebGcQSuuPiLJXZptN9nndrQmbXEps2aiAFbWhM78LhWx4cbbfAAtVT86zwu1RK7aPFFxuhDR1L6tSoc_BJECPebWKRXjBZCiFV4n3oknjhMstn64tZ_2W-5JsGY4Hc5n9yBXArwl93lqt7_RN5w6Cf0h4QyQ5v-65YGjQR0_FDW2QvzqY368QQMicAtaSqzs8KJZgnYb9c7d0zgdAZHzu6qMQvRL5hajrn1n91CbOpbISD08qNLyrdkt-bFTWhAI4vMQFh6WeZu0fM4lFd2NcRwr3XPksINHaQ-G_xBniIqbw0Ls1jF44-csFCur-kEgU8awapJzKnqDKgw",
+            "e": "AQAB",
+            "alg": "RS256",
+            "kid": "2011-04-29"
+        });
+
+        let jwk: PublicJsonWebKey = serde_json::from_value(jwk).unwrap();
+        assert_eq!(
+            jwk.params().thumbprint_sha256(),
+            "NzbLsXh8uDCcd-6MNwXF4W_7noWXFZAfHkxZsRGC9Xs"
+        );
+    }
+
     #[test]
     fn load_google_keys() {
         let jwks = serde_json::json!({
@@ -336,10 +336,13 @@ mod ec_impls {
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct OkpPrivateParameters {
-    crv: JsonWebKeyOkpEllipticCurve,
+    pub(crate) crv: JsonWebKeyOkpEllipticCurve,
 
     #[schemars(with = "String")]
     x: Base64UrlNoPad,
+
+    #[schemars(with = "String")]
+    d: Base64UrlNoPad,
 }
 
 impl ParametersInfo for OkpPrivateParameters {
@@ -357,3 +360,53 @@ impl From<OkpPrivateParameters> for super::public_parameters::OkpPublicParameter
         Self::new(params.crv, params.x)
     }
 }
+
+mod okp_impls {
+    use ed25519_dalek::{SignatureError, SigningKey};
+    use mas_iana::jose::JsonWebKeyOkpEllipticCurve;
+
+    use super::OkpPrivateParameters;
+    use crate::base64::Base64UrlNoPad;
+
+    impl TryFrom<OkpPrivateParameters> for SigningKey {
+        type Error = SignatureError;
+        fn try_from(value: OkpPrivateParameters) -> Result<Self, Self::Error> {
+            Self::try_from(&value)
+        }
+    }
+
+    impl TryFrom<&OkpPrivateParameters> for SigningKey {
+        type Error = SignatureError;
+
+        fn try_from(value: &OkpPrivateParameters) -> Result<Self, Self::Error> {
+            if value.crv != JsonWebKeyOkpEllipticCurve::Ed25519 {
+                return Err(SignatureError::new());
+            }
+
+            let key = SigningKey::try_from(value.d.as_bytes())?;
+
+            // Make sure the public part matches the private part
+            if key.verifying_key().as_bytes() != value.x.as_bytes() {
+                return Err(SignatureError::new());
+            }
+
+            Ok(key)
+        }
+    }
+
+    impl From<SigningKey> for OkpPrivateParameters {
+        fn from(key: SigningKey) -> Self {
+            (&key).into()
+        }
+    }
+
+    impl From<&SigningKey> for OkpPrivateParameters {
+        fn from(key: &SigningKey) -> Self {
+            OkpPrivateParameters {
+                crv: JsonWebKeyOkpEllipticCurve::Ed25519,
+                x: Base64UrlNoPad::new(key.verifying_key().to_bytes().to_vec()),
+                d: Base64UrlNoPad::new(key.to_bytes().to_vec()),
+            }
+        }
+    }
+}
@@ -9,6 +9,7 @@ use mas_iana::jose::{
 };
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
 use super::ParametersInfo;
 use crate::base64::Base64UrlNoPad;
@@ -50,6 +51,36 @@ impl JsonWebKeyPublicParameters {
             _ => None,
         }
     }
+
+    /// Compute the SHA-256 [JWK Thumbprint] of this key, encoded as base64url
+    ///
+    /// [JWK Thumbprint]: https://www.rfc-editor.org/rfc/rfc7638
+    #[must_use]
+    pub fn thumbprint_sha256(&self) -> String {
+        // The thumbprint is computed over a JSON object with only the required
+        // members, in lexicographic order and without any whitespace
+        let canonical = match self {
+            Self::Rsa(params) => format!(
+                r#"{{"e":"{}","kty":"RSA","n":"{}"}}"#,
+                params.e.encode(),
+                params.n.encode(),
+            ),
+            Self::Ec(params) => format!(
+                r#"{{"crv":"{}","kty":"EC","x":"{}","y":"{}"}}"#,
+                params.crv,
+                params.x.encode(),
+                params.y.encode(),
+            ),
+            Self::Okp(params) => format!(
+                r#"{{"crv":"{}","kty":"OKP","x":"{}"}}"#,
+                params.crv,
+                params.x.encode(),
+            ),
+        };
+
+        let hash = Sha256::digest(canonical.as_bytes());
+        Base64UrlNoPad::new(hash.to_vec()).encode()
+    }
 }
 
 impl ParametersInfo for JsonWebKeyPublicParameters {
@@ -137,7 +168,7 @@ impl ParametersInfo for EcPublicParameters {
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct OkpPublicParameters {
-    crv: JsonWebKeyOkpEllipticCurve,
+    pub(crate) crv: JsonWebKeyOkpEllipticCurve,
 
     #[schemars(with = "String")]
     x: Base64UrlNoPad,
@@ -300,3 +331,56 @@ mod ec_impls {
         }
     }
 }
+
+mod okp_impls {
+    use ed25519_dalek::{SignatureError, VerifyingKey};
+    use mas_iana::jose::JsonWebKeyOkpEllipticCurve;
+
+    use super::{JsonWebKeyPublicParameters, OkpPublicParameters};
+    use crate::base64::Base64UrlNoPad;
+
+    impl TryFrom<OkpPublicParameters> for VerifyingKey {
+        type Error = SignatureError;
+        fn try_from(value: OkpPublicParameters) -> Result<Self, Self::Error> {
+            (&value).try_into()
+        }
+    }
+
+    impl TryFrom<&OkpPublicParameters> for VerifyingKey {
+        type Error = SignatureError;
+        fn try_from(value: &OkpPublicParameters) -> Result<Self, Self::Error> {
+            if value.crv != JsonWebKeyOkpEllipticCurve::Ed25519 {
+                return Err(SignatureError::new());
+            }
+
+            VerifyingKey::try_from(value.x.as_bytes())
+        }
+    }
+
+    impl From<VerifyingKey> for JsonWebKeyPublicParameters {
+        fn from(key: VerifyingKey) -> Self {
+            Self::from(&key)
+        }
+    }
+
+    impl From<&VerifyingKey> for JsonWebKeyPublicParameters {
+        fn from(key: &VerifyingKey) -> Self {
+            Self::Okp(key.into())
+        }
+    }
+
+    impl From<VerifyingKey> for OkpPublicParameters {
+        fn from(key: VerifyingKey) -> Self {
+            Self::from(&key)
+        }
+    }
+
+    impl From<&VerifyingKey> for OkpPublicParameters {
+        fn from(key: &VerifyingKey) -> Self {
+            Self {
+                crv: JsonWebKeyOkpEllipticCurve::Ed25519,
+                x: Base64UrlNoPad::new(key.to_bytes().to_vec()),
+            }
+        }
+    }
+}
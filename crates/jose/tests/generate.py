@@ -106,8 +106,10 @@ k256_key = import_key("k256.priv.pem", "EC")
 ed25519_key = import_key("ed25519.priv.pem", "OKP")
 ed448_key = import_key("ed448.priv.pem", "OKP")
 
+# The Ed25519 key comes last, so that it gets picked over the Ed448 key, which
+# isn't supported for signing, when looking for an EdDSA key
 key_set = KeySet(
-    [rsa_key, p256_key, p384_key, p521_key, k256_key, ed25519_key, ed448_key]
+    [rsa_key, p256_key, p384_key, p521_key, k256_key, ed448_key, ed25519_key]
 )
 
 with open(keys_path / "jwks.pub.json", "w", encoding="utf8") as f:
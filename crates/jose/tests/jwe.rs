@@ -0,0 +1,214 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+use base64ct::{Base64UrlUnpadded, Encoding};
+use mas_iana::jose::{JsonWebEncryptionAlg, JsonWebEncryptionEnc, JsonWebKeyType};
+use mas_jose::{
+    constraints::Constrainable,
+    jwa::SUPPORTED_ENCRYPTION_ENCODINGS,
+    jwe::{DecryptionKey, EncryptionKey, JsonWebEncryptionHeader, Jwe, JweDecryptionError},
+    jwk::{PrivateJsonWebKeySet, PublicJsonWebKeySet},
+};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+static PLAINTEXT: &[u8] = b"The true sign of intelligence is not knowledge but imagination.";
+
+fn public_jwks() -> PublicJsonWebKeySet {
+    serde_json::from_str(include_str!("./keys/jwks.pub.json")).unwrap()
+}
+
+fn private_jwks() -> PrivateJsonWebKeySet {
+    serde_json::from_str(include_str!("./keys/jwks.priv.json")).unwrap()
+}
+
+/// Find the public and private keys to use for the given algorithm
+fn keys(alg: &JsonWebEncryptionAlg, crv: &str) -> (EncryptionKey, DecryptionKey) {
+    let public_jwks = public_jwks();
+    let private_jwks = private_jwks();
+
+    let public_key = public_jwks
+        .iter()
+        .find(|key| {
+            key.kty() == expected_kty(alg)
+                && key
+                    .params()
+                    .ec()
+                    .is_none_or(|_| serde_json::to_value(key).unwrap()["crv"] == crv)
+        })
+        .unwrap();
+    let kid = public_key.kid().unwrap();
+    let private_key = private_jwks
+        .iter()
+        .find(|key| key.kid() == Some(kid))
+        .unwrap();
+
+    (
+        EncryptionKey::from_jwk(public_key.params()).unwrap(),
+        DecryptionKey::from_jwk(private_key.params()).unwrap(),
+    )
+}
+
+fn expected_kty(alg: &JsonWebEncryptionAlg) -> JsonWebKeyType {
+    match alg {
+        JsonWebEncryptionAlg::RsaOaep256 => JsonWebKeyType::Rsa,
+        _ => JsonWebKeyType::Ec,
+    }
+}
+
+fn round_trip(alg: &JsonWebEncryptionAlg, crv: &str) {
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+    let (encryption_key, decryption_key) = keys(alg, crv);
+    assert!(encryption_key.supports(alg));
+
+    for enc in SUPPORTED_ENCRYPTION_ENCODINGS {
+        let header =
+            JsonWebEncryptionHeader::new(alg.clone(), enc.clone()).with_cty("JWT".to_owned());
+        let jwe = Jwe::encrypt(&mut rng, header, &encryption_key, PLAINTEXT).unwrap();
+
+        let jwe = Jwe::try_from(jwe.as_str()).unwrap();
+        assert_eq!(jwe.header().alg(), alg);
+        assert_eq!(*jwe.header().enc(), enc);
+        assert_eq!(jwe.header().cty(), Some("JWT"));
+
+        let plaintext = jwe.decrypt(&decryption_key).unwrap();
+        assert_eq!(plaintext, PLAINTEXT);
+    }
+}
+
+#[test]
+fn rsa_oaep_256() {
+    round_trip(&JsonWebEncryptionAlg::RsaOaep256, "");
+}
+
+#[test]
+fn ecdh_es_p256() {
+    round_trip(&JsonWebEncryptionAlg::EcdhEs, "P-256");
+}
+
+#[test]
+fn ecdh_es_p384() {
+    round_trip(&JsonWebEncryptionAlg::EcdhEs, "P-384");
+}
+
+#[test]
+fn ecdh_es_a128kw() {
+    round_trip(&JsonWebEncryptionAlg::EcdhEsA128Kw, "P-256");
+}
+
+#[test]
+fn ecdh_es_a256kw() {
+    round_trip(&JsonWebEncryptionAlg::EcdhEsA256Kw, "P-384");
+}
+
+#[test]
+fn dir() {
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+    let key: Vec<u8> = (0..32).collect();
+
+    let header =
+        JsonWebEncryptionHeader::new(JsonWebEncryptionAlg::Dir, JsonWebEncryptionEnc::A256Gcm);
+    let jwe = Jwe::encrypt(
+        &mut rng,
+        header,
+        &EncryptionKey::Direct(key.clone()),
+        PLAINTEXT,
+    )
+    .unwrap();
+
+    // The encrypted key is empty with direct encryption
+    assert!(jwe.as_str().contains(".."));
+
+    let plaintext = jwe.decrypt(&DecryptionKey::Direct(key)).unwrap();
+    assert_eq!(plaintext, PLAINTEXT);
+
+    // A key with the wrong length can't be used
+    let header =
+        JsonWebEncryptionHeader::new(JsonWebEncryptionAlg::Dir, JsonWebEncryptionEnc::A128Gcm);
+    assert!(
+        Jwe::encrypt(
+            &mut rng,
+            header,
+            &EncryptionKey::Direct(vec![0; 32]),
+            PLAINTEXT
+        )
+        .is_err()
+    );
+}
+
+#[test]
+fn wrong_key() {
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+    let (encryption_key, _) = keys(&JsonWebEncryptionAlg::EcdhEs, "P-256");
+
+    let header =
+        JsonWebEncryptionHeader::new(JsonWebEncryptionAlg::EcdhEs, JsonWebEncryptionEnc::A128Gcm);
+    let jwe = Jwe::encrypt(&mut rng, header, &encryption_key, PLAINTEXT).unwrap();
+
+    // Another key on the same curve doesn't decrypt the content
+    let other_key = DecryptionKey::P256(p256::SecretKey::random(&mut rng));
+    assert!(matches!(
+        jwe.decrypt(&other_key),
+        Err(JweDecryptionError::ContentEncryption(_))
+    ));
+
+    // A key of the wrong type isn't usable with the algorithm
+    let (_, rsa_key) = keys(&JsonWebEncryptionAlg::RsaOaep256, "");
+    assert!(matches!(
+        jwe.decrypt(&rsa_key),
+        Err(JweDecryptionError::UnsupportedAlgorithm(_))
+    ));
+
+    // The RSA key can't be used with ECDH-ES
+    let (rsa_key, _) = keys(&JsonWebEncryptionAlg::RsaOaep256, "");
+    let header =
+        JsonWebEncryptionHeader::new(JsonWebEncryptionAlg::EcdhEs, JsonWebEncryptionEnc::A128Gcm);
+    assert!(Jwe::encrypt(&mut rng, header, &rsa_key, PLAINTEXT).is_err());
+}
+
+#[test]
+fn tampered_tag() {
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+    let (encryption_key, decryption_key) = keys(&JsonWebEncryptionAlg::RsaOaep256, "");
+
+    let header = JsonWebEncryptionHeader::new(
+        JsonWebEncryptionAlg::RsaOaep256,
+        JsonWebEncryptionEnc::A128CbcHs256,
+    );
+    let jwe = Jwe::encrypt(&mut rng, header, &encryption_key, PLAINTEXT).unwrap();
+
+    // Flip a bit of the authentication tag
+    let mut parts: Vec<String> = jwe.as_str().split('.').map(ToOwned::to_owned).collect();
+    let mut tag = Base64UrlUnpadded::decode_vec(&parts[4]).unwrap();
+    tag[0] ^= 1;
+    parts[4] = Base64UrlUnpadded::encode_string(&tag);
+
+    let tampered = Jwe::try_from(parts.join(".")).unwrap();
+    assert!(matches!(
+        tampered.decrypt(&decryption_key),
+        Err(JweDecryptionError::ContentEncryption(_))
+    ));
+}
+
+#[test]
+fn find_encryption_key() {
+    let jwks = public_jwks();
+
+    let key = jwks
+        .encryption_key_for_algorithm(&JsonWebEncryptionAlg::RsaOaep256)
+        .unwrap();
+    assert_eq!(key.kty(), JsonWebKeyType::Rsa);
+
+    let key = jwks
+        .encryption_key_for_algorithm(&JsonWebEncryptionAlg::EcdhEsA128Kw)
+        .unwrap();
+    assert_eq!(key.kty(), JsonWebKeyType::Ec);
+
+    // Symmetric algorithms don't use keys from a JWKS
+    assert!(
+        jwks.encryption_key_for_algorithm(&JsonWebEncryptionAlg::Dir)
+            .is_none()
+    );
+}
@@ -8,6 +8,7 @@
 
 use std::{ops::Deref, sync::Arc};
 
+use arc_swap::ArcSwap;
 use der::{Decode, Encode, EncodePem, zeroize::Zeroizing};
 use elliptic_curve::{pkcs8::EncodePrivateKey, sec1::ToEncodedPoint};
 use mas_iana::jose::{JsonWebKeyType, JsonWebSignatureAlg};
@@ -119,12 +120,13 @@ impl LoadError {
 
 /// A single private key
 #[non_exhaustive]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum PrivateKey {
     Rsa(Box<rsa::RsaPrivateKey>),
     EcP256(Box<elliptic_curve::SecretKey<p256::NistP256>>),
     EcP384(Box<elliptic_curve::SecretKey<p384::NistP384>>),
     EcK256(Box<elliptic_curve::SecretKey<k256::Secp256k1>>),
+    Ed25519(Box<ed25519_dalek::SigningKey>),
 }
 
 /// Error returned when the key can't be used for the requested algorithm
@@ -160,6 +162,7 @@ impl PrivateKey {
                 k256::Secp256k1::OID => Ok(Self::EcK256(Box::new(info.try_into()?))),
                 oid => Err(LoadError::UnknownEllipticCurveOid { oid }),
             },
+            ed25519_dalek::pkcs8::ALGORITHM_OID => Ok(Self::Ed25519(Box::new(info.try_into()?))),
             oid => Err(LoadError::UnknownAlgorithmOid { oid }),
         }
     }
@@ -182,7 +185,7 @@ impl PrivateKey {
     /// Serialize the key as a DER document
     ///
     /// It will use the most common format depending on the key type: PKCS1 for
-    /// RSA keys and SEC1 for elliptic curve keys
+    /// RSA keys, SEC1 for elliptic curve keys and PKCS8 for Ed25519 keys
     ///
     /// # Errors
     ///
@@ -193,6 +196,7 @@ impl PrivateKey {
             PrivateKey::EcP256(key) => to_sec1_der(key)?,
             PrivateKey::EcP384(key) => to_sec1_der(key)?,
             PrivateKey::EcK256(key) => to_sec1_der(key)?,
+            PrivateKey::Ed25519(key) => ed25519_keypair_bytes(key).to_pkcs8_der()?.to_bytes(),
         };
 
         Ok(der)
@@ -209,6 +213,7 @@ impl PrivateKey {
             PrivateKey::EcP256(key) => key.to_pkcs8_der()?,
             PrivateKey::EcP384(key) => key.to_pkcs8_der()?,
             PrivateKey::EcK256(key) => key.to_pkcs8_der()?,
+            PrivateKey::Ed25519(key) => ed25519_keypair_bytes(key).to_pkcs8_der()?,
         };
 
         Ok(der.to_bytes())
@@ -217,7 +222,7 @@ impl PrivateKey {
     /// Serialize the key as a PEM document
     ///
     /// It will use the most common format depending on the key type: PKCS1 for
-    /// RSA keys and SEC1 for elliptic curve keys
+    /// RSA keys, SEC1 for elliptic curve keys and PKCS8 for Ed25519 keys
     ///
     /// # Errors
     ///
@@ -231,6 +236,7 @@ impl PrivateKey {
             PrivateKey::EcP256(key) => to_sec1_pem(key, line_ending)?,
             PrivateKey::EcP384(key) => to_sec1_pem(key, line_ending)?,
             PrivateKey::EcK256(key) => to_sec1_pem(key, line_ending)?,
+            PrivateKey::Ed25519(key) => ed25519_keypair_bytes(key).to_pkcs8_pem(line_ending)?,
         };
 
         Ok(pem)
@@ -442,6 +448,10 @@ impl PrivateKey {
                 AsymmetricVerifyingKey::es256k(key.public_key())
             }
 
+            (Self::Ed25519(key), JsonWebSignatureAlg::EdDsa) => {
+                AsymmetricVerifyingKey::eddsa(key.verifying_key())
+            }
+
             _ => return Err(WrongAlgorithmError),
         };
 
@@ -484,6 +494,10 @@ impl PrivateKey {
                 AsymmetricSigningKey::es256k(*key.clone())
             }
 
+            (Self::Ed25519(key), JsonWebSignatureAlg::EdDsa) => {
+                AsymmetricSigningKey::eddsa(*key.clone())
+            }
+
             _ => return Err(WrongAlgorithmError),
         };
 
@@ -517,6 +531,21 @@ impl PrivateKey {
         let key = elliptic_curve::SecretKey::random(&mut rng);
         Self::EcK256(Box::new(key))
     }
+
+    /// Generate an Edwards Curve key for the Ed25519 curve
+    pub fn generate_ed25519<R: RngCore + CryptoRng>(mut rng: R) -> Self {
+        let key = ed25519_dalek::SigningKey::generate(&mut rng);
+        Self::Ed25519(Box::new(key))
+    }
+}
+
+// Ed25519 keys are serialized without their public key, as a version 1 PKCS8
+// document, which is what OpenSSL does
+fn ed25519_keypair_bytes(key: &ed25519_dalek::SigningKey) -> ed25519_dalek::pkcs8::KeypairBytes {
+    ed25519_dalek::pkcs8::KeypairBytes {
+        secret_key: key.to_bytes(),
+        public_key: None,
+    }
 }
 
 // The default implementation of SecretKey::to_sec1_pem/der do not include the
@@ -568,6 +597,7 @@ impl From<&PrivateKey> for JsonWebKeyPublicParameters {
             PrivateKey::EcP256(key) => key.public_key().into(),
             PrivateKey::EcP384(key) => key.public_key().into(),
             PrivateKey::EcK256(key) => key.public_key().into(),
+            PrivateKey::Ed25519(key) => key.verifying_key().into(),
         }
     }
 }
@@ -579,6 +609,7 @@ impl ParametersInfo for PrivateKey {
             PrivateKey::EcP256(_) | PrivateKey::EcP384(_) | PrivateKey::EcK256(_) => {
                 JsonWebKeyType::Ec
             }
+            PrivateKey::Ed25519(_) => JsonWebKeyType::Okp,
         }
     }
 
@@ -595,6 +626,7 @@ impl ParametersInfo for PrivateKey {
             PrivateKey::EcP256(_) => &[JsonWebSignatureAlg::Es256],
             PrivateKey::EcP384(_) => &[JsonWebSignatureAlg::Es384],
             PrivateKey::EcK256(_) => &[JsonWebSignatureAlg::Es256K],
+            PrivateKey::Ed25519(_) => &[JsonWebSignatureAlg::EdDsa],
         }
     }
 }
@@ -602,9 +634,14 @@ impl ParametersInfo for PrivateKey {
 /// A structure to store a list of [`PrivateKey`]. The keys are held in an
 /// [`Arc`] to ensure they are only loaded once in memory and allow cheap
 /// cloning
+///
+/// On top of the keys used for signing, a keystore can hold keys which are
+/// only published, like the keys which are about to be used for signing, or
+/// the ones which were retired recently.
 #[derive(Clone, Default)]
 pub struct Keystore {
     keys: Arc<JsonWebKeySet<PrivateKey>>,
+    published_keys: Arc<Vec<JsonWebKey<PrivateKey>>>,
 }
 
 impl Keystore {
@@ -612,7 +649,18 @@ impl Keystore {
     #[must_use]
     pub fn new(keys: JsonWebKeySet<PrivateKey>) -> Self {
         let keys = Arc::new(keys);
-        Self { keys }
+        Self {
+            keys,
+            published_keys: Arc::default(),
+        }
+    }
+
+    /// Set the keys which are published in the public JSON Web Key Set, but
+    /// not used for signing
+    #[must_use]
+    pub fn with_published_keys(mut self, keys: Vec<JsonWebKey<PrivateKey>>) -> Self {
+        self.published_keys = Arc::new(keys);
+        self
     }
 
     /// Get the public JSON Web Key Set for the keys stored in this [`Keystore`]
@@ -620,6 +668,7 @@ impl Keystore {
     pub fn public_jwks(&self) -> PublicJsonWebKeySet {
         self.keys
             .iter()
+            .chain(self.published_keys.iter())
             .map(|key| {
                 key.cloned_map(|params: &PrivateKey| JsonWebKeyPublicParameters::from(params))
             })
@@ -627,6 +676,35 @@ impl Keystore {
     }
 }
 
+/// A [`Keystore`] which can be replaced at runtime, for example when signing
+/// keys get rotated. All the clones of a [`SharedKeystore`] see the same
+/// [`Keystore`].
+#[derive(Clone, Default)]
+pub struct SharedKeystore {
+    inner: Arc<ArcSwap<Keystore>>,
+}
+
+impl SharedKeystore {
+    /// Create a [`SharedKeystore`] out of an initial [`Keystore`]
+    #[must_use]
+    pub fn new(key_store: Keystore) -> Self {
+        Self {
+            inner: Arc::new(ArcSwap::from_pointee(key_store)),
+        }
+    }
+
+    /// Get the current [`Keystore`]
+    #[must_use]
+    pub fn load(&self) -> Keystore {
+        Keystore::clone(&self.inner.load())
+    }
+
+    /// Replace the current [`Keystore`]
+    pub fn store(&self, key_store: Keystore) {
+        self.inner.store(Arc::new(key_store));
+    }
+}
+
 impl Deref for Keystore {
     type Target = JsonWebKeySet<PrivateKey>;
 
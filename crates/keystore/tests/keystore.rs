@@ -7,6 +7,7 @@
 use der::pem::LineEnding;
 use mas_iana::jose::JsonWebSignatureAlg;
 use mas_jose::{
+    constraints::Constrainable,
     jwk::ParametersInfo,
     jwt::{JsonWebSignatureHeader, Jwt},
 };
@@ -109,6 +110,8 @@ plain_test!(plain_ec_k256_sec1_pem, EcK256, "ec-k256.sec1.pem");
 plain_test!(plain_ec_k256_sec1_der, EcK256, "ec-k256.sec1.der");
 plain_test!(plain_ec_k256_pkcs8_pem, EcK256, "ec-k256.pkcs8.pem");
 plain_test!(plain_ec_k256_pkcs8_der, EcK256, "ec-k256.pkcs8.der");
+plain_test!(plain_ed25519_pkcs8_pem, Ed25519, "ed25519.pkcs8.pem");
+plain_test!(plain_ed25519_pkcs8_der, Ed25519, "ed25519.pkcs8.der");
 
 enc_test!(enc_rsa_pkcs8_pem, Rsa, "rsa.pkcs8.encrypted.pem");
 enc_test!(enc_rsa_pkcs8_der, Rsa, "rsa.pkcs8.encrypted.der");
@@ -118,6 +121,16 @@ enc_test!(enc_ec_p384_pkcs8_pem, EcP384, "ec-p384.pkcs8.encrypted.pem");
 enc_test!(enc_ec_p384_pkcs8_der, EcP384, "ec-p384.pkcs8.encrypted.der");
 enc_test!(enc_ec_k256_pkcs8_pem, EcK256, "ec-k256.pkcs8.encrypted.pem");
 enc_test!(enc_ec_k256_pkcs8_der, EcK256, "ec-k256.pkcs8.encrypted.der");
+enc_test!(
+    enc_ed25519_pkcs8_pem,
+    Ed25519,
+    "ed25519.pkcs8.encrypted.pem"
+);
+enc_test!(
+    enc_ed25519_pkcs8_der,
+    Ed25519,
+    "ed25519.pkcs8.encrypted.der"
+);
 
 // Test PEM/DER serialization
 pem_test!(serialize_rsa_pkcs1_pem, "rsa.pkcs1");
@@ -128,6 +141,8 @@ pem_test!(serialize_ec_p384_sec1_pem, "ec-p384.sec1");
 der_test!(serialize_ec_p384_sec1_der, "ec-p384.sec1");
 pem_test!(serialize_ec_k256_sec1_pem, "ec-k256.sec1");
 der_test!(serialize_ec_k256_sec1_der, "ec-k256.sec1");
+pem_test!(serialize_ed25519_pkcs8_pem, "ed25519.pkcs8");
+der_test!(serialize_ed25519_pkcs8_der, "ed25519.pkcs8");
 
 #[test]
 fn load_encrypted_as_unencrypted_error() {
@@ -208,3 +223,47 @@ fn generate_sign_and_verify() {
         token.verify_with_jwks(&jwks).unwrap();
     }
 }
+
+#[test]
+fn generate_ed25519_sign_and_verify() {
+    // Use a seeded RNG to keep the snapshot stable
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(42);
+
+    let ed25519 = PrivateKey::generate_ed25519(&mut rng);
+    insta::assert_snapshot!(&*ed25519.to_pem(LineEnding::LF).unwrap());
+
+    let keyset = Keystore::new(JsonWebKeySet::new(vec![JsonWebKey::new(ed25519)]));
+    let jwks = keyset.public_jwks();
+    insta::assert_yaml_snapshot!(jwks);
+
+    let alg = JsonWebSignatureAlg::EdDsa;
+    let key = keyset.signing_key_for_algorithm(&alg).unwrap();
+    let signer = key.params().signing_key_for_alg(&alg).unwrap();
+    let header = JsonWebSignatureHeader::new(alg.clone());
+    let token = Jwt::sign_with_rng(&mut rng, header, "", &signer).unwrap();
+    insta::assert_snapshot!(format!("jwt_{alg}"), token.as_str());
+
+    token.verify_with_jwks(&jwks).unwrap();
+}
+
+#[test]
+fn published_keys_are_not_used_for_signing() {
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(42);
+
+    let active = JsonWebKey::new(PrivateKey::generate_ec_p256(&mut rng)).with_kid("active");
+    let published = JsonWebKey::new(PrivateKey::generate_ec_p256(&mut rng)).with_kid("published");
+
+    let keyset =
+        Keystore::new(JsonWebKeySet::new(vec![active])).with_published_keys(vec![published]);
+
+    // Both keys are published
+    let jwks = keyset.public_jwks();
+    let kids: Vec<_> = jwks.iter().filter_map(Constrainable::kid).collect();
+    assert_eq!(kids, ["active", "published"]);
+
+    // But only the active one is used for signing
+    let key = keyset
+        .signing_key_for_algorithm(&JsonWebSignatureAlg::Es256)
+        .unwrap();
+    assert_eq!(key.kid(), Some("active"));
+}
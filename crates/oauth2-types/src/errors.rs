@@ -219,6 +219,21 @@ pub enum ClientErrorCode {
     /// From [RFC7591](https://www.rfc-editor.org/rfc/rfc7591#section-3.2.2).
     InvalidClientMetadata,
 
+    /// `invalid_software_statement`
+    ///
+    /// The software statement presented is invalid.
+    ///
+    /// From [RFC7591](https://www.rfc-editor.org/rfc/rfc7591#section-3.2.2).
+    InvalidSoftwareStatement,
+
+    /// `unapproved_software_statement`
+    ///
+    /// The software statement presented is not approved for use by this
+    /// authorization server.
+    ///
+    /// From [RFC7591](https://www.rfc-editor.org/rfc/rfc7591#section-3.2.2).
+    UnapprovedSoftwareStatement,
+
     /// `authorization_pending`
     ///
     /// The authorization request is still pending as the end user hasn't yet
@@ -265,6 +280,21 @@ pub enum ClientErrorCode {
     /// From [RFC7009](https://www.rfc-editor.org/rfc/rfc7009#section-2.2.1).
     UnsupportedTokenType,
 
+    /// `invalid_dpop_proof`
+    ///
+    /// The `DPoP` proof presented with the request is invalid.
+    ///
+    /// From [RFC9449](https://www.rfc-editor.org/rfc/rfc9449#section-5).
+    InvalidDpopProof,
+
+    /// `invalid_target`
+    ///
+    /// The requested resource or audience is invalid, unknown, or malformed.
+    ///
+    /// From [RFC8707](https://www.rfc-editor.org/rfc/rfc8707#section-2) and
+    /// [RFC8693](https://www.rfc-editor.org/rfc/rfc8693#section-2.2.2).
+    InvalidTarget,
+
     /// Another error code.
     Unknown(String),
 }
@@ -293,10 +323,16 @@ impl core::fmt::Display for ClientErrorCode {
             ClientErrorCode::RegistrationNotSupported => f.write_str("registration_not_supported"),
             ClientErrorCode::InvalidRedirectUri => f.write_str("invalid_redirect_uri"),
             ClientErrorCode::InvalidClientMetadata => f.write_str("invalid_client_metadata"),
+            ClientErrorCode::InvalidSoftwareStatement => f.write_str("invalid_software_statement"),
+            ClientErrorCode::UnapprovedSoftwareStatement => {
+                f.write_str("unapproved_software_statement")
+            }
             ClientErrorCode::AuthorizationPending => f.write_str("authorization_pending"),
             ClientErrorCode::SlowDown => f.write_str("slow_down"),
             ClientErrorCode::ExpiredToken => f.write_str("expired_token"),
             ClientErrorCode::UnsupportedTokenType => f.write_str("unsupported_token_type"),
+            ClientErrorCode::InvalidDpopProof => f.write_str("invalid_dpop_proof"),
+            ClientErrorCode::InvalidTarget => f.write_str("invalid_target"),
             ClientErrorCode::Unknown(value) => f.write_str(value),
         }
     }
@@ -328,10 +364,14 @@ impl core::str::FromStr for ClientErrorCode {
             "registration_not_supported" => Ok(ClientErrorCode::RegistrationNotSupported),
             "invalid_redirect_uri" => Ok(ClientErrorCode::InvalidRedirectUri),
             "invalid_client_metadata" => Ok(ClientErrorCode::InvalidClientMetadata),
+            "invalid_software_statement" => Ok(ClientErrorCode::InvalidSoftwareStatement),
+            "unapproved_software_statement" => Ok(ClientErrorCode::UnapprovedSoftwareStatement),
             "authorization_pending" => Ok(ClientErrorCode::AuthorizationPending),
             "slow_down" => Ok(ClientErrorCode::SlowDown),
             "expired_token" => Ok(ClientErrorCode::ExpiredToken),
             "unsupported_token_type" => Ok(ClientErrorCode::UnsupportedTokenType),
+            "invalid_dpop_proof" => Ok(ClientErrorCode::InvalidDpopProof),
+            "invalid_target" => Ok(ClientErrorCode::InvalidTarget),
             _ => Ok(ClientErrorCode::Unknown(s.to_owned())),
         }
     }
@@ -411,6 +451,10 @@ impl ClientErrorCode {
             ClientErrorCode::InvalidClientMetadata => {
                 "The value of one of the client metadata fields is invalid"
             }
+            ClientErrorCode::InvalidSoftwareStatement => "The software statement is invalid",
+            ClientErrorCode::UnapprovedSoftwareStatement => {
+                "The software statement is not approved for use by this server"
+            }
             ClientErrorCode::AuthorizationPending => "The authorization request is still pending",
             ClientErrorCode::SlowDown => {
                 "The interval must be increased by 5 seconds for this and all subsequent requests"
@@ -421,6 +465,10 @@ impl ClientErrorCode {
             ClientErrorCode::UnsupportedTokenType => {
                 "The authorization server does not support the revocation of the presented token type."
             }
+            ClientErrorCode::InvalidDpopProof => "The DPoP proof is invalid.",
+            ClientErrorCode::InvalidTarget => {
+                "The requested resource or audience is invalid, unknown, or malformed."
+            }
             ClientErrorCode::Unknown(_) => "",
         }
     }
@@ -516,6 +564,14 @@ mod tests {
             serde_json::to_string(&ClientErrorCode::InvalidClientMetadata).unwrap(),
             "\"invalid_client_metadata\""
         );
+        assert_eq!(
+            serde_json::to_string(&ClientErrorCode::InvalidSoftwareStatement).unwrap(),
+            "\"invalid_software_statement\""
+        );
+        assert_eq!(
+            serde_json::to_string(&ClientErrorCode::UnapprovedSoftwareStatement).unwrap(),
+            "\"unapproved_software_statement\""
+        );
 
         assert_eq!(
             serde_json::to_string(&ClientErrorCode::Unknown("unknown_error_code".to_owned()))
@@ -610,6 +666,14 @@ mod tests {
             serde_json::from_str::<ClientErrorCode>("\"invalid_client_metadata\"").unwrap(),
             ClientErrorCode::InvalidClientMetadata
         );
+        assert_eq!(
+            serde_json::from_str::<ClientErrorCode>("\"invalid_software_statement\"").unwrap(),
+            ClientErrorCode::InvalidSoftwareStatement
+        );
+        assert_eq!(
+            serde_json::from_str::<ClientErrorCode>("\"unapproved_software_statement\"").unwrap(),
+            ClientErrorCode::UnapprovedSoftwareStatement
+        );
 
         assert_eq!(
             serde_json::from_str::<ClientErrorCode>("\"unknown_error_code\"").unwrap(),
@@ -475,6 +475,12 @@ pub struct ProviderMetadata {
     /// [`OAuthClientAuthenticationMethod::ClientSecretJwt`].
     pub introspection_endpoint_auth_signing_alg_values_supported: Option<Vec<JsonWebSignatureAlg>>,
 
+    /// JSON array containing a list of the JWS algorithms supported by the
+    /// introspection endpoint to sign [JWT introspection responses].
+    ///
+    /// [JWT introspection responses]: https://www.rfc-editor.org/rfc/rfc9701
+    pub introspection_signing_alg_values_supported: Option<Vec<JsonWebSignatureAlg>>,
+
     /// [PKCE code challenge methods] supported by this authorization server.
     /// If omitted, the authorization server does not support PKCE.
     ///
@@ -614,6 +620,41 @@ pub struct ProviderMetadata {
     /// [RP-Initiated Logout endpoint]: https://openid.net/specs/openid-connect-rpinitiated-1_0.html
     pub end_session_endpoint: Option<Url>,
 
+    /// Whether the OP supports [Back-Channel Logout].
+    ///
+    /// Defaults to `false`.
+    ///
+    /// [Back-Channel Logout]: https://openid.net/specs/openid-connect-backchannel-1_0.html
+    pub backchannel_logout_supported: Option<bool>,
+
+    /// Whether the OP can pass a `sid` Claim in the Logout Token to identify
+    /// the RP session with the OP.
+    ///
+    /// Defaults to `false`.
+    pub backchannel_logout_session_supported: Option<bool>,
+
+    /// JSON array containing a list of the JWS algorithms supported for
+    /// [DPoP] proof JWTs.
+    ///
+    /// [DPoP]: https://www.rfc-editor.org/rfc/rfc9449
+    pub dpop_signing_alg_values_supported: Option<Vec<JsonWebSignatureAlg>>,
+
+    /// Whether the authorization server provides the `iss` parameter in the
+    /// [authorization response].
+    ///
+    /// Defaults to `false`.
+    ///
+    /// [authorization response]: https://www.rfc-editor.org/rfc/rfc9207
+    pub authorization_response_iss_parameter_supported: Option<bool>,
+
+    /// Whether the authorization server supports [certificate-bound access
+    /// tokens].
+    ///
+    /// Defaults to `false`.
+    ///
+    /// [certificate-bound access tokens]: https://www.rfc-editor.org/rfc/rfc8705#section-3
+    pub tls_client_certificate_bound_access_tokens: Option<bool>,
+
     /// URL where the user is able to access the account management capabilities
     /// of this OP.
     ///
@@ -916,6 +957,26 @@ impl ProviderMetadata {
     pub fn require_pushed_authorization_requests(&self) -> bool {
         self.require_pushed_authorization_requests.unwrap_or(false)
     }
+
+    /// Whether the authorization server provides the `iss` parameter in the
+    /// authorization response.
+    ///
+    /// Defaults to `false`.
+    #[must_use]
+    pub fn authorization_response_iss_parameter_supported(&self) -> bool {
+        self.authorization_response_iss_parameter_supported
+            .unwrap_or(false)
+    }
+
+    /// Whether the authorization server supports certificate-bound access
+    /// tokens.
+    ///
+    /// Defaults to `false`.
+    #[must_use]
+    pub fn tls_client_certificate_bound_access_tokens(&self) -> bool {
+        self.tls_client_certificate_bound_access_tokens
+            .unwrap_or(false)
+    }
 }
 
 /// The verified authorization server metadata.
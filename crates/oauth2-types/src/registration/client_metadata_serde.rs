@@ -102,6 +102,7 @@ pub struct ClientMetadataSerdeHelper {
     jwks: Option<PublicJsonWebKeySet>,
     software_id: Option<String>,
     software_version: Option<String>,
+    software_statement: Option<String>,
     sector_identifier_uri: Option<Url>,
     subject_type: Option<SubjectType>,
     token_endpoint_auth_method: Option<OAuthClientAuthenticationMethod>,
@@ -127,6 +128,8 @@ pub struct ClientMetadataSerdeHelper {
     introspection_encrypted_response_alg: Option<JsonWebEncryptionAlg>,
     introspection_encrypted_response_enc: Option<JsonWebEncryptionEnc>,
     post_logout_redirect_uris: Option<Vec<Url>>,
+    backchannel_logout_uri: Option<Url>,
+    backchannel_logout_session_required: Option<bool>,
     #[serde(flatten)]
     extra: ClientMetadataLocalizedFields,
 }
@@ -154,6 +157,7 @@ impl From<ClientMetadata> for ClientMetadataSerdeHelper {
             jwks,
             software_id,
             software_version,
+            software_statement,
             sector_identifier_uri,
             subject_type,
             token_endpoint_auth_method,
@@ -178,6 +182,8 @@ impl From<ClientMetadata> for ClientMetadataSerdeHelper {
             introspection_encrypted_response_alg,
             introspection_encrypted_response_enc,
             post_logout_redirect_uris,
+            backchannel_logout_uri,
+            backchannel_logout_session_required,
         } = metadata;
 
         ClientMetadataSerdeHelper {
@@ -190,6 +196,7 @@ impl From<ClientMetadata> for ClientMetadataSerdeHelper {
             jwks,
             software_id,
             software_version,
+            software_statement,
             sector_identifier_uri,
             subject_type,
             token_endpoint_auth_method,
@@ -214,6 +221,8 @@ impl From<ClientMetadata> for ClientMetadataSerdeHelper {
             introspection_encrypted_response_alg,
             introspection_encrypted_response_enc,
             post_logout_redirect_uris,
+            backchannel_logout_uri,
+            backchannel_logout_session_required,
             extra: ClientMetadataLocalizedFields {
                 client_name,
                 logo_uri,
@@ -237,6 +246,7 @@ impl From<ClientMetadataSerdeHelper> for ClientMetadata {
             jwks,
             software_id,
             software_version,
+            software_statement,
             sector_identifier_uri,
             subject_type,
             token_endpoint_auth_method,
@@ -261,6 +271,8 @@ impl From<ClientMetadataSerdeHelper> for ClientMetadata {
             introspection_encrypted_response_alg,
             introspection_encrypted_response_enc,
             post_logout_redirect_uris,
+            backchannel_logout_uri,
+            backchannel_logout_session_required,
             extra:
                 ClientMetadataLocalizedFields {
                     client_name,
@@ -286,6 +298,7 @@ impl From<ClientMetadataSerdeHelper> for ClientMetadata {
             jwks,
             software_id,
             software_version,
+            software_statement,
             sector_identifier_uri,
             subject_type,
             token_endpoint_auth_method,
@@ -310,6 +323,8 @@ impl From<ClientMetadataSerdeHelper> for ClientMetadata {
             introspection_encrypted_response_alg,
             introspection_encrypted_response_enc,
             post_logout_redirect_uris,
+            backchannel_logout_uri,
+            backchannel_logout_session_required,
         }
     }
 }
@@ -220,6 +220,15 @@ pub struct ClientMetadata {
     /// `software_id`.
     pub software_version: Option<String>,
 
+    /// A [software statement]: a JWT asserting metadata values about the
+    /// client software, signed by a party the authorization server trusts.
+    ///
+    /// Once verified, its claims take precedence over the other metadata
+    /// values.
+    ///
+    /// [software statement]: https://www.rfc-editor.org/rfc/rfc7591#section-2.3
+    pub software_statement: Option<String>,
+
     /// URL to be used in calculating pseudonymous identifiers by the OpenID
     /// Connect provider when [pairwise subject identifiers] are used.
     ///
@@ -429,6 +438,18 @@ pub struct ClientMetadata {
     ///
     /// [RP-Initiated Logout endpoint]: https://openid.net/specs/openid-connect-rpinitiated-1_0.html
     pub post_logout_redirect_uris: Option<Vec<Url>>,
+
+    /// URL that will cause the client to log itself out when sent a Logout
+    /// Token by the provider, using [Back-Channel Logout].
+    ///
+    /// [Back-Channel Logout]: https://openid.net/specs/openid-connect-backchannel-1_0.html
+    pub backchannel_logout_uri: Option<Url>,
+
+    /// Whether the client requires that a `sid` Claim be included in the
+    /// Logout Token to identify the session to log out.
+    ///
+    /// Defaults to `false`.
+    pub backchannel_logout_session_required: Option<bool>,
 }
 
 impl ClientMetadata {
@@ -497,6 +518,17 @@ impl ClientMetadata {
             ));
         }
 
+        // Without a sector identifier URI, the sector is derived from the host of
+        // the redirect URIs, so they must all share the same one
+        if self.subject_type == Some(SubjectType::Pairwise) && self.sector_identifier_uri.is_none()
+        {
+            let mut hosts = self.redirect_uris.iter().flatten().map(Url::host_str);
+            let first = hosts.next().flatten();
+            if first.is_none() || hosts.any(|host| host != first) {
+                return Err(ClientMetadataVerificationError::MissingSectorIdentifierUri);
+            }
+        }
+
         if *self.token_endpoint_auth_method() == OAuthClientAuthenticationMethod::PrivateKeyJwt
             && self.jwks_uri.is_none()
             && self.jwks.is_none()
@@ -561,6 +593,27 @@ impl ClientMetadata {
             )?;
         }
 
+        if let Some(uri) = self
+            .post_logout_redirect_uris
+            .iter()
+            .flatten()
+            .find(|uri| uri.fragment().is_some())
+        {
+            return Err(
+                ClientMetadataVerificationError::PostLogoutRedirectUriWithFragment(uri.clone()),
+            );
+        }
+
+        if let Some(uri) = self
+            .backchannel_logout_uri
+            .as_ref()
+            .filter(|uri| uri.fragment().is_some())
+        {
+            return Err(
+                ClientMetadataVerificationError::BackchannelLogoutUriWithFragment(uri.clone()),
+            );
+        }
+
         Ok(VerifiedClientMetadata { inner: self })
     }
 
@@ -782,6 +835,15 @@ impl ClientMetadata {
             .unwrap_or_default()
     }
 
+    /// Whether the client requires that a `sid` Claim be included in the
+    /// Logout Token sent to its `backchannel_logout_uri`.
+    ///
+    /// Defaults to `false`.
+    #[must_use]
+    pub fn backchannel_logout_session_required(&self) -> bool {
+        self.backchannel_logout_session_required.unwrap_or_default()
+    }
+
     /// [JWE] `alg` and `enc` algorithms for encrypting responses of the
     /// [introspection endpoint].
     ///
@@ -893,6 +955,19 @@ pub enum ClientMetadataVerificationError {
     /// The given encryption field has an `enc` value but not `alg` value.
     #[error("{0} missing encryption alg value")]
     MissingEncryptionAlg(&'static str),
+
+    /// The post logout redirect URI has a fragment, which is not allowed.
+    #[error("post logout redirect URI with fragment: {0}")]
+    PostLogoutRedirectUriWithFragment(Url),
+
+    /// The back-channel logout URI contains a fragment, which is not allowed.
+    #[error("back-channel logout URI with fragment: {0}")]
+    BackchannelLogoutUriWithFragment(Url),
+
+    /// The pairwise subject type was requested, but the redirect URIs don't
+    /// share a single host and no sector identifier URI was given.
+    #[error("sector_identifier_uri is required for pairwise subjects with these redirect URIs")]
+    MissingSectorIdentifierUri,
 }
 
 /// The issuer response to dynamic client registration.
@@ -918,6 +993,23 @@ pub struct ClientRegistrationResponse {
     #[serde(default)]
     #[serde_as(as = "Option<TimestampSeconds<i64>>")]
     pub client_secret_expires_at: Option<DateTime<Utc>>,
+
+    /// An access token the client can use at the client configuration endpoint
+    /// to read, update or delete its registration.
+    ///
+    /// Defined in [RFC 7592].
+    ///
+    /// [RFC 7592]: https://www.rfc-editor.org/rfc/rfc7592#section-3
+    #[serde(default)]
+    pub registration_access_token: Option<String>,
+
+    /// The location of the client configuration endpoint for this client.
+    ///
+    /// Defined in [RFC 7592].
+    ///
+    /// [RFC 7592]: https://www.rfc-editor.org/rfc/rfc7592#section-3
+    #[serde(default)]
+    pub registration_client_uri: Option<Url>,
 }
 
 #[cfg(test)]
@@ -931,7 +1023,7 @@ mod tests {
     use url::Url;
 
     use super::{ClientMetadata, ClientMetadataVerificationError};
-    use crate::{requests::GrantType, response_type::ResponseType};
+    use crate::{oidc::SubjectType, requests::GrantType, response_type::ResponseType};
 
     fn valid_client_metadata() -> ClientMetadata {
         ClientMetadata {
@@ -1228,6 +1320,36 @@ mod tests {
         metadata.validate().unwrap();
     }
 
+    #[test]
+    fn validate_pairwise_subject_type() {
+        let mut metadata = valid_client_metadata();
+        metadata.subject_type = Some(SubjectType::Pairwise);
+
+        // Ok - a single redirect URI
+        metadata.clone().validate().unwrap();
+
+        // Ok - redirect URIs on the same host
+        metadata.redirect_uris = Some(vec![
+            Url::parse("https://localhost/one").unwrap(),
+            Url::parse("https://localhost/two").unwrap(),
+        ]);
+        metadata.clone().validate().unwrap();
+
+        // Err - redirect URIs on different hosts
+        metadata.redirect_uris = Some(vec![
+            Url::parse("https://one.localhost/").unwrap(),
+            Url::parse("https://two.localhost/").unwrap(),
+        ]);
+        assert_matches!(
+            metadata.clone().validate(),
+            Err(ClientMetadataVerificationError::MissingSectorIdentifierUri)
+        );
+
+        // Ok - a sector identifier URI was given
+        metadata.sector_identifier_uri = Some(Url::parse("https://localhost/sector").unwrap());
+        metadata.validate().unwrap();
+    }
+
     #[test]
     fn validate_token_endpoint_auth_method() {
         let mut metadata = valid_client_metadata();
@@ -1405,6 +1527,49 @@ mod tests {
         metadata.validate().unwrap();
     }
 
+    #[test]
+    fn validate_post_logout_redirect_uris() {
+        let mut metadata = valid_client_metadata();
+
+        // Err - Fragment
+        let wrong_uri = Url::parse("https://localhost/logged-out#fragment").unwrap();
+        metadata.post_logout_redirect_uris = Some(vec![
+            Url::parse("https://localhost/").unwrap(),
+            wrong_uri.clone(),
+        ]);
+        let uri = assert_matches!(
+            metadata.clone().validate(),
+            Err(ClientMetadataVerificationError::PostLogoutRedirectUriWithFragment(uri)) => uri
+        );
+        assert_eq!(uri, wrong_uri);
+
+        // Ok - Path & Query
+        metadata.post_logout_redirect_uris = Some(vec![
+            Url::parse("https://localhost/").unwrap(),
+            Url::parse("https://localhost/logged-out?from=oidc").unwrap(),
+        ]);
+        metadata.validate().unwrap();
+    }
+
+    #[test]
+    fn validate_backchannel_logout_uri() {
+        let mut metadata = valid_client_metadata();
+
+        // Err - Fragment
+        let wrong_uri = Url::parse("https://localhost/backchannel-logout#fragment").unwrap();
+        metadata.backchannel_logout_uri = Some(wrong_uri.clone());
+        let uri = assert_matches!(
+            metadata.clone().validate(),
+            Err(ClientMetadataVerificationError::BackchannelLogoutUriWithFragment(uri)) => uri
+        );
+        assert_eq!(uri, wrong_uri);
+
+        // Ok - Path & Query
+        metadata.backchannel_logout_uri =
+            Some(Url::parse("https://localhost/backchannel-logout?from=oidc").unwrap());
+        metadata.validate().unwrap();
+    }
+
     #[test]
     fn validate_introspection_encrypted_response() {
         let mut metadata = valid_client_metadata();
@@ -8,15 +8,23 @@
 //!
 //! [OAuth 2.0]: https://oauth.net/2/
 
-use std::{collections::HashSet, fmt, hash::Hash, num::NonZeroU32};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt,
+    hash::Hash,
+    num::NonZeroU32,
+};
 
 use chrono::{DateTime, Duration, Utc};
 use language_tags::LanguageTag;
 use mas_iana::oauth::{OAuthAccessTokenType, OAuthTokenTypeHint};
 use serde::{Deserialize, Serialize};
 use serde_with::{
-    DeserializeFromStr, DisplayFromStr, DurationSeconds, SerializeDisplay, StringWithSeparator,
-    TimestampSeconds, formats::SpaceSeparator, serde_as, skip_serializing_none,
+    DeserializeFromStr, DisplayFromStr, DurationSeconds, OneOrMany, SerializeDisplay,
+    StringWithSeparator, TimestampSeconds,
+    formats::{PreferOne, SpaceSeparator},
+    json::JsonString,
+    serde_as, skip_serializing_none,
 };
 use url::Url;
 
@@ -306,6 +314,12 @@ pub struct AuthorizationRequest {
     ///
     /// [Self-Issued OpenID Provider]: https://openid.net/specs/openid-connect-core-1_0.html#SelfIssued
     pub registration: Option<String>,
+
+    /// Individual claims to be returned from the `UserInfo` endpoint and/or
+    /// in the ID Token.
+    #[serde_as(as = "Option<JsonString>")]
+    #[serde(default)]
+    pub claims: Option<ClaimsRequest>,
 }
 
 impl AuthorizationRequest {
@@ -330,6 +344,7 @@ impl AuthorizationRequest {
             request: None,
             request_uri: None,
             registration: None,
+            claims: None,
         }
     }
 }
@@ -350,10 +365,56 @@ impl fmt::Debug for AuthorizationRequest {
             .field("request", &self.request)
             .field("request_uri", &self.request_uri)
             .field("registration", &self.registration)
+            .field("claims", &self.claims)
             .finish_non_exhaustive()
     }
 }
 
+/// The value of the [`claims` request parameter], used to request individual
+/// claims.
+///
+/// [`claims` request parameter]: https://openid.net/specs/openid-connect-core-1_0.html#ClaimsParameter
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct ClaimsRequest {
+    /// Individual claims to be returned from the `UserInfo` endpoint.
+    pub userinfo: Option<BTreeMap<String, Option<IndividualClaimRequest>>>,
+
+    /// Individual claims to be returned in the ID Token.
+    pub id_token: Option<BTreeMap<String, Option<IndividualClaimRequest>>>,
+}
+
+impl ClaimsRequest {
+    /// The names of the claims requested from the `UserInfo` endpoint.
+    pub fn userinfo_claims(&self) -> impl Iterator<Item = &str> {
+        self.userinfo
+            .iter()
+            .flat_map(|c| c.keys().map(String::as_str))
+    }
+
+    /// The names of the claims requested in the ID Token.
+    pub fn id_token_claims(&self) -> impl Iterator<Item = &str> {
+        self.id_token
+            .iter()
+            .flat_map(|c| c.keys().map(String::as_str))
+    }
+}
+
+/// Additional information about a claim requested in a [`ClaimsRequest`].
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct IndividualClaimRequest {
+    /// Whether the claim is essential to the client.
+    pub essential: Option<bool>,
+
+    /// The value the claim is requested to have.
+    pub value: Option<serde_json::Value>,
+
+    /// A set of values the claim is requested to have, in order of
+    /// preference.
+    pub values: Option<Vec<serde_json::Value>>,
+}
+
 /// A successful response from the [Authorization Endpoint].
 ///
 /// [Authorization Endpoint]: https://www.rfc-editor.org/rfc/rfc6749.html#section-3.1
@@ -480,12 +541,19 @@ pub struct AuthorizationCodeGrant {
     /// authorization endpoint.
     // TODO: move this somehow in the pkce module
     pub code_verifier: Option<String>,
+
+    /// The [resource indicator] of the target service where the client intends
+    /// to use the requested token.
+    ///
+    /// [resource indicator]: https://www.rfc-editor.org/rfc/rfc8707
+    pub resource: Option<Url>,
 }
 
 impl fmt::Debug for AuthorizationCodeGrant {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("AuthorizationCodeGrant")
             .field("redirect_uri", &self.redirect_uri)
+            .field("resource", &self.resource)
             .finish_non_exhaustive()
     }
 }
@@ -506,12 +574,19 @@ pub struct RefreshTokenGrant {
     /// the resource owner, and if omitted is treated as equal to the scope
     /// originally granted by the resource owner.
     pub scope: Option<Scope>,
+
+    /// The [resource indicator] of the target service where the client intends
+    /// to use the requested token.
+    ///
+    /// [resource indicator]: https://www.rfc-editor.org/rfc/rfc8707
+    pub resource: Option<Url>,
 }
 
 impl fmt::Debug for RefreshTokenGrant {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("RefreshTokenGrant")
             .field("scope", &self.scope)
+            .field("resource", &self.resource)
             .finish_non_exhaustive()
     }
 }
@@ -524,6 +599,12 @@ impl fmt::Debug for RefreshTokenGrant {
 pub struct ClientCredentialsGrant {
     /// The scope of the access request.
     pub scope: Option<Scope>,
+
+    /// The [resource indicator] of the target service where the client intends
+    /// to use the requested token.
+    ///
+    /// [resource indicator]: https://www.rfc-editor.org/rfc/rfc8707
+    pub resource: Option<Url>,
 }
 
 /// A request to the [Token Endpoint] for the [Device Authorization] grant type.
@@ -542,6 +623,148 @@ impl fmt::Debug for DeviceCodeGrant {
     }
 }
 
+/// All possible token type identifiers, as used in the [Token Exchange] grant
+/// type.
+///
+/// [Token Exchange]: https://www.rfc-editor.org/rfc/rfc8693#section-3
+#[derive(
+    Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Clone, SerializeDisplay, DeserializeFromStr,
+)]
+pub enum TokenTypeIdentifier {
+    /// `urn:ietf:params:oauth:token-type:access_token`
+    AccessToken,
+
+    /// `urn:ietf:params:oauth:token-type:refresh_token`
+    RefreshToken,
+
+    /// `urn:ietf:params:oauth:token-type:id_token`
+    IdToken,
+
+    /// `urn:ietf:params:oauth:token-type:saml1`
+    Saml1,
+
+    /// `urn:ietf:params:oauth:token-type:saml2`
+    Saml2,
+
+    /// `urn:ietf:params:oauth:token-type:jwt`
+    Jwt,
+
+    /// An unknown value.
+    Unknown(String),
+}
+
+impl core::fmt::Display for TokenTypeIdentifier {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            TokenTypeIdentifier::AccessToken => {
+                f.write_str("urn:ietf:params:oauth:token-type:access_token")
+            }
+            TokenTypeIdentifier::RefreshToken => {
+                f.write_str("urn:ietf:params:oauth:token-type:refresh_token")
+            }
+            TokenTypeIdentifier::IdToken => {
+                f.write_str("urn:ietf:params:oauth:token-type:id_token")
+            }
+            TokenTypeIdentifier::Saml1 => f.write_str("urn:ietf:params:oauth:token-type:saml1"),
+            TokenTypeIdentifier::Saml2 => f.write_str("urn:ietf:params:oauth:token-type:saml2"),
+            TokenTypeIdentifier::Jwt => f.write_str("urn:ietf:params:oauth:token-type:jwt"),
+            TokenTypeIdentifier::Unknown(s) => f.write_str(s),
+        }
+    }
+}
+
+impl core::str::FromStr for TokenTypeIdentifier {
+    type Err = core::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "urn:ietf:params:oauth:token-type:access_token" => Ok(TokenTypeIdentifier::AccessToken),
+            "urn:ietf:params:oauth:token-type:refresh_token" => {
+                Ok(TokenTypeIdentifier::RefreshToken)
+            }
+            "urn:ietf:params:oauth:token-type:id_token" => Ok(TokenTypeIdentifier::IdToken),
+            "urn:ietf:params:oauth:token-type:saml1" => Ok(TokenTypeIdentifier::Saml1),
+            "urn:ietf:params:oauth:token-type:saml2" => Ok(TokenTypeIdentifier::Saml2),
+            "urn:ietf:params:oauth:token-type:jwt" => Ok(TokenTypeIdentifier::Jwt),
+            s => Ok(TokenTypeIdentifier::Unknown(s.to_owned())),
+        }
+    }
+}
+
+/// A request to the [Token Endpoint] for the [Token Exchange] grant type.
+///
+/// [Token Endpoint]: https://www.rfc-editor.org/rfc/rfc6749#section-3.2
+/// [Token Exchange]: https://www.rfc-editor.org/rfc/rfc8693#section-2.1
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct TokenExchangeGrant {
+    /// The logical name of the target service where the client intends to use
+    /// the requested token.
+    pub audience: Option<String>,
+
+    /// The scope of the requested token.
+    pub scope: Option<Scope>,
+
+    /// The type of the requested token.
+    pub requested_token_type: Option<TokenTypeIdentifier>,
+
+    /// A token that represents the identity of the party on behalf of whom the
+    /// request is being made.
+    pub subject_token: String,
+
+    /// The type of the `subject_token`.
+    pub subject_token_type: TokenTypeIdentifier,
+
+    /// A token that represents the identity of the acting party.
+    pub actor_token: Option<String>,
+
+    /// The type of the `actor_token`.
+    ///
+    /// Required if `actor_token` is present.
+    pub actor_token_type: Option<TokenTypeIdentifier>,
+}
+
+impl fmt::Debug for TokenExchangeGrant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TokenExchangeGrant")
+            .field("audience", &self.audience)
+            .field("scope", &self.scope)
+            .field("requested_token_type", &self.requested_token_type)
+            .field("subject_token_type", &self.subject_token_type)
+            .field("actor_token_type", &self.actor_token_type)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A request to the [Token Endpoint] for the [JWT Bearer] grant type.
+///
+/// [Token Endpoint]: https://www.rfc-editor.org/rfc/rfc6749#section-3.2
+/// [JWT Bearer]: https://www.rfc-editor.org/rfc/rfc7523#section-2.1
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct JwtBearerGrant {
+    /// The signed JWT used as an authorization grant.
+    pub assertion: String,
+
+    /// The scope of the access request.
+    pub scope: Option<Scope>,
+
+    /// The [resource indicator] of the target service where the client intends
+    /// to use the requested token.
+    ///
+    /// [resource indicator]: https://www.rfc-editor.org/rfc/rfc8707
+    pub resource: Option<Url>,
+}
+
+impl fmt::Debug for JwtBearerGrant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JwtBearerGrant")
+            .field("scope", &self.scope)
+            .field("resource", &self.resource)
+            .finish_non_exhaustive()
+    }
+}
+
 /// All possible values for the `grant_type` parameter.
 #[derive(
     Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Clone, SerializeDisplay, DeserializeFromStr,
@@ -571,6 +794,9 @@ pub enum GrantType {
     /// [`urn:openid:params:grant-type:ciba`](https://openid.net/specs/openid-client-initiated-backchannel-authentication-core-1_0.html)
     ClientInitiatedBackchannelAuthentication,
 
+    /// [`urn:ietf:params:oauth:grant-type:token-exchange`](https://www.rfc-editor.org/rfc/rfc8693)
+    TokenExchange,
+
     /// An unknown value.
     Unknown(String),
 }
@@ -588,6 +814,9 @@ impl core::fmt::Display for GrantType {
             GrantType::ClientInitiatedBackchannelAuthentication => {
                 f.write_str("urn:openid:params:grant-type:ciba")
             }
+            GrantType::TokenExchange => {
+                f.write_str("urn:ietf:params:oauth:grant-type:token-exchange")
+            }
             GrantType::Unknown(s) => f.write_str(s),
         }
     }
@@ -608,6 +837,7 @@ impl core::str::FromStr for GrantType {
             "urn:openid:params:grant-type:ciba" => {
                 Ok(GrantType::ClientInitiatedBackchannelAuthentication)
             }
+            "urn:ietf:params:oauth:grant-type:token-exchange" => Ok(GrantType::TokenExchange),
             s => Ok(GrantType::Unknown(s.to_owned())),
         }
     }
@@ -633,6 +863,14 @@ pub enum AccessTokenRequest {
     #[serde(rename = "urn:ietf:params:oauth:grant-type:device_code")]
     DeviceCode(DeviceCodeGrant),
 
+    /// A request in the Token Exchange flow.
+    #[serde(rename = "urn:ietf:params:oauth:grant-type:token-exchange")]
+    TokenExchange(TokenExchangeGrant),
+
+    /// A request using a JWT as an authorization grant.
+    #[serde(rename = "urn:ietf:params:oauth:grant-type:jwt-bearer")]
+    JwtBearer(JwtBearerGrant),
+
     /// An unsupported request.
     #[serde(skip_serializing, other)]
     Unsupported,
@@ -647,6 +885,8 @@ impl AccessTokenRequest {
             Self::RefreshToken(_) => "refresh_token",
             Self::ClientCredentials(_) => "client_credentials",
             Self::DeviceCode(_) => "urn:ietf:params:oauth:grant-type:device_code",
+            Self::TokenExchange(_) => "urn:ietf:params:oauth:grant-type:token-exchange",
+            Self::JwtBearer(_) => "urn:ietf:params:oauth:grant-type:jwt-bearer",
             Self::Unsupported => "unsupported",
         }
     }
@@ -678,6 +918,11 @@ pub struct AccessTokenResponse {
 
     /// The scope of the access token.
     pub scope: Option<Scope>,
+
+    /// The type of the issued token, in the [Token Exchange] grant type.
+    ///
+    /// [Token Exchange]: https://www.rfc-editor.org/rfc/rfc8693#section-2.2.1
+    pub issued_token_type: Option<TokenTypeIdentifier>,
 }
 
 impl AccessTokenResponse {
@@ -691,6 +936,7 @@ impl AccessTokenResponse {
             token_type: OAuthAccessTokenType::Bearer,
             expires_in: None,
             scope: None,
+            issued_token_type: None,
         }
     }
 
@@ -721,6 +967,20 @@ impl AccessTokenResponse {
         self.expires_in = Some(expires_in);
         self
     }
+
+    /// Sets the token type of an `AccessTokenResponse`.
+    #[must_use]
+    pub fn with_token_type(mut self, token_type: OAuthAccessTokenType) -> Self {
+        self.token_type = token_type;
+        self
+    }
+
+    /// Sets the type of the issued token of an `AccessTokenResponse`.
+    #[must_use]
+    pub fn with_issued_token_type(mut self, issued_token_type: TokenTypeIdentifier) -> Self {
+        self.issued_token_type = Some(issued_token_type);
+        self
+    }
 }
 
 impl fmt::Debug for AccessTokenResponse {
@@ -729,6 +989,7 @@ impl fmt::Debug for AccessTokenResponse {
             .field("token_type", &self.token_type)
             .field("expires_in", &self.expires_in)
             .field("scope", &self.scope)
+            .field("issued_token_type", &self.issued_token_type)
             .finish_non_exhaustive()
     }
 }
@@ -798,7 +1059,8 @@ pub struct IntrospectionResponse {
     pub sub: Option<String>,
 
     /// Intended audience of the token.
-    pub aud: Option<String>,
+    #[serde_as(as = "Option<OneOrMany<_, PreferOne>>")]
+    pub aud: Option<Vec<String>>,
 
     /// Issuer of the token.
     pub iss: Option<String>,
@@ -808,6 +1070,45 @@ pub struct IntrospectionResponse {
 
     /// MAS extension: explicit device ID
     pub device_id: Option<String>,
+
+    /// Confirmation of the key the token is bound to, if any.
+    pub cnf: Option<Confirmation>,
+
+    /// The party acting on behalf of the subject of the token, if any.
+    pub act: Option<Actor>,
+}
+
+/// The [actor] claim of a token obtained through delegation.
+///
+/// [actor]: https://www.rfc-editor.org/rfc/rfc8693#section-4.1
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct Actor {
+    /// The identifier of the acting party.
+    pub sub: Option<String>,
+
+    /// The prior actor, in case of a chain of delegations.
+    pub act: Option<Box<Actor>>,
+}
+
+/// The [confirmation] claim of a sender-constrained token.
+///
+/// [confirmation]: https://www.rfc-editor.org/rfc/rfc7800#section-3.1
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct Confirmation {
+    /// The SHA-256 [JWK Thumbprint] of the [DPoP] key the token is bound to.
+    ///
+    /// [JWK Thumbprint]: https://www.rfc-editor.org/rfc/rfc7638
+    /// [DPoP]: https://www.rfc-editor.org/rfc/rfc9449#section-6.2
+    pub jkt: Option<String>,
+
+    /// The SHA-256 thumbprint of the [client certificate] the token is bound
+    /// to.
+    ///
+    /// [client certificate]: https://www.rfc-editor.org/rfc/rfc8705#section-3.1
+    #[serde(rename = "x5t#S256")]
+    pub x5t_s256: Option<String>,
 }
 
 /// A request to the [Revocation Endpoint].
@@ -872,6 +1173,7 @@ mod tests {
         let req = AccessTokenRequest::RefreshToken(RefreshTokenGrant {
             refresh_token: "abcd".into(),
             scope,
+            resource: None,
         });
 
         assert_serde_json(&req, expected);
@@ -889,6 +1191,70 @@ mod tests {
             code: "abcd".into(),
             redirect_uri: Some("https://example.com/redirect".parse().unwrap()),
             code_verifier: None,
+            resource: None,
+        });
+
+        assert_serde_json(&req, expected);
+    }
+
+    #[test]
+    fn serde_token_exchange_grant() {
+        let expected = json!({
+            "grant_type": "urn:ietf:params:oauth:grant-type:token-exchange",
+            "audience": "backend",
+            "subject_token": "abcd",
+            "subject_token_type": "urn:ietf:params:oauth:token-type:access_token",
+        });
+
+        let req = AccessTokenRequest::TokenExchange(TokenExchangeGrant {
+            audience: Some("backend".to_owned()),
+            scope: None,
+            requested_token_type: None,
+            subject_token: "abcd".to_owned(),
+            subject_token_type: TokenTypeIdentifier::AccessToken,
+            actor_token: None,
+            actor_token_type: None,
+        });
+
+        assert_serde_json(&req, expected);
+    }
+
+    #[test]
+    fn serde_claims_request() {
+        // The claims parameter is a JSON object encoded as a string
+        let request: AuthorizationRequest = serde_json::from_value(serde_json::json!({
+            "response_type": "code",
+            "client_id": "abcd",
+            "scope": "openid",
+            "claims": r#"{"userinfo":{"name":null},"id_token":{"picture":{"essential":true}}}"#,
+        }))
+        .unwrap();
+
+        let claims = request.claims.unwrap();
+        assert_eq!(claims.userinfo_claims().collect::<Vec<_>>(), ["name"]);
+        assert_eq!(claims.id_token_claims().collect::<Vec<_>>(), ["picture"]);
+        assert_eq!(
+            claims.id_token.unwrap()["picture"],
+            Some(IndividualClaimRequest {
+                essential: Some(true),
+                ..IndividualClaimRequest::default()
+            })
+        );
+    }
+
+    #[test]
+    fn serde_jwt_bearer_grant() {
+        let expected = json!({
+            "grant_type": "urn:ietf:params:oauth:grant-type:jwt-bearer",
+            "assertion": "eyJhbGciOiJFUzI1NiJ9.e30.c2lnbmF0dXJl",
+            "scope": "openid",
+            "resource": "https://api.example.com/",
+        });
+
+        let req = AccessTokenRequest::JwtBearer(JwtBearerGrant {
+            assertion: "eyJhbGciOiJFUzI1NiJ9.e30.c2lnbmF0dXJl".to_owned(),
+            scope: Some(Scope::from_iter([OPENID])),
+            resource: Some("https://api.example.com/".parse().unwrap()),
         });
 
         assert_serde_json(&req, expected);
@@ -924,6 +1290,10 @@ mod tests {
             serde_json::to_string(&GrantType::ClientInitiatedBackchannelAuthentication).unwrap(),
             "\"urn:openid:params:grant-type:ciba\""
         );
+        assert_eq!(
+            serde_json::to_string(&GrantType::TokenExchange).unwrap(),
+            "\"urn:ietf:params:oauth:grant-type:token-exchange\""
+        );
     }
 
     #[test]
@@ -957,6 +1327,13 @@ mod tests {
             serde_json::from_str::<GrantType>("\"urn:openid:params:grant-type:ciba\"").unwrap(),
             GrantType::ClientInitiatedBackchannelAuthentication
         );
+        assert_eq!(
+            serde_json::from_str::<GrantType>(
+                "\"urn:ietf:params:oauth:grant-type:token-exchange\""
+            )
+            .unwrap(),
+            GrantType::TokenExchange
+        );
     }
 
     #[test]
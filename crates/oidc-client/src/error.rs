@@ -29,6 +29,9 @@ pub enum Error {
     /// An error occurred building the authorization URL.
     Authorization(#[from] AuthorizationError),
 
+    /// An error occurred validating the authorization response.
+    AuthorizationResponse(#[from] AuthorizationResponseError),
+
     /// An error occurred exchanging an authorization code for an access token.
     TokenAuthorizationCode(#[from] TokenAuthorizationCodeError),
 
@@ -76,6 +79,26 @@ pub enum AuthorizationError {
     UrlEncoded(#[from] serde_urlencoded::ser::Error),
 }
 
+/// All possible errors when validating the response of the authorization
+/// endpoint.
+#[derive(Debug, Error)]
+pub enum AuthorizationResponseError {
+    /// The provider advertises support for the `iss` parameter but didn't
+    /// include it in the response.
+    #[error("The authorization response is missing the 'iss' parameter")]
+    MissingIssuer,
+
+    /// The `iss` parameter doesn't match the expected issuer.
+    #[error("Issuer mismatch in the authorization response: expected {expected:?}, got {got:?}")]
+    IssuerMismatch {
+        /// The expected issuer.
+        expected: String,
+
+        /// The issuer in the response.
+        got: String,
+    },
+}
+
 /// All possible errors when requesting an access token.
 #[derive(Debug, Error)]
 #[error("Request to the token endpoint failed")]
@@ -33,7 +33,9 @@ use url::Url;
 
 use super::jose::JwtVerificationData;
 use crate::{
-    error::{AuthorizationError, IdTokenError, TokenAuthorizationCodeError},
+    error::{
+        AuthorizationError, AuthorizationResponseError, IdTokenError, TokenAuthorizationCodeError,
+    },
     requests::{jose::verify_id_token, token::request_access_token},
     types::{IdToken, client_credentials::ClientCredentials},
 };
@@ -276,6 +278,7 @@ fn build_authorization_request(
             request: None,
             request_uri: None,
             registration: None,
+            claims: None,
         },
         pkce,
     };
@@ -352,6 +355,45 @@ pub fn build_authorization_url(
     Ok((authorization_url, validation_data))
 }
 
+/// Validate the `iss` parameter of an authorization response, as defined in
+/// [RFC 9207].
+///
+/// This protects against mix-up attacks when the client interacts with
+/// several authorization servers.
+///
+/// # Arguments
+///
+/// * `iss` - The `iss` parameter received at the redirect URI, if any.
+///
+/// * `expected_issuer` - The issuer of the authorization server the request was
+///   sent to.
+///
+/// * `iss_parameter_supported` - Whether the authorization server advertises
+///   the `authorization_response_iss_parameter_supported` metadata.
+///
+/// # Errors
+///
+/// Returns an error if the `iss` parameter doesn't match the expected issuer,
+/// or if it is missing while the authorization server advertises support for
+/// it.
+///
+/// [RFC 9207]: https://www.rfc-editor.org/rfc/rfc9207
+pub fn validate_authorization_response_issuer(
+    iss: Option<&str>,
+    expected_issuer: &str,
+    iss_parameter_supported: bool,
+) -> Result<(), AuthorizationResponseError> {
+    match iss {
+        Some(iss) if iss == expected_issuer => Ok(()),
+        Some(iss) => Err(AuthorizationResponseError::IssuerMismatch {
+            expected: expected_issuer.to_owned(),
+            got: iss.to_owned(),
+        }),
+        None if iss_parameter_supported => Err(AuthorizationResponseError::MissingIssuer),
+        None => Ok(()),
+    }
+}
+
 /// Exchange an authorization code for an access token.
 ///
 /// This should be used as the first step for logging in, and to request a
@@ -411,6 +453,7 @@ pub async fn access_token_with_authorization_code(
             code: code.clone(),
             redirect_uri: Some(validation_data.redirect_uri),
             code_verifier: validation_data.code_challenge_verifier,
+            resource: None,
         }),
         now,
         rng,
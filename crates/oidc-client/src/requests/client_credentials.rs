@@ -59,7 +59,10 @@ pub async fn access_token_with_client_credentials(
         http_client,
         client_credentials,
         token_endpoint,
-        AccessTokenRequest::ClientCredentials(ClientCredentialsGrant { scope }),
+        AccessTokenRequest::ClientCredentials(ClientCredentialsGrant {
+            scope,
+            resource: None,
+        }),
         now,
         rng,
     )
@@ -86,6 +86,7 @@ pub async fn refresh_access_token(
         AccessTokenRequest::RefreshToken(RefreshTokenGrant {
             refresh_token,
             scope,
+            resource: None,
         }),
         now,
         rng,
@@ -12,11 +12,12 @@ use mas_iana::oauth::{
 };
 use mas_jose::{claims::ClaimError, jwk::PublicJsonWebKeySet};
 use mas_oidc_client::{
-    error::{IdTokenError, TokenAuthorizationCodeError},
+    error::{AuthorizationResponseError, IdTokenError, TokenAuthorizationCodeError},
     requests::{
         authorization_code::{
             AuthorizationRequestData, AuthorizationValidationData,
             access_token_with_authorization_code, build_authorization_url,
+            validate_authorization_response_issuer,
         },
         jose::JwtVerificationData,
     },
@@ -131,6 +132,32 @@ fn pass_full_authorization_url() {
     assert_eq!(query_pairs.get("code_challenge_method"), None);
 }
 
+#[test]
+fn pass_authorization_response_issuer() {
+    let issuer = "http://localhost/";
+
+    validate_authorization_response_issuer(Some(issuer), issuer, true).unwrap();
+    validate_authorization_response_issuer(Some(issuer), issuer, false).unwrap();
+    // The parameter is optional if the provider doesn't advertise support for it
+    validate_authorization_response_issuer(None, issuer, false).unwrap();
+}
+
+#[test]
+fn fail_authorization_response_issuer() {
+    let issuer = "http://localhost/";
+
+    let error =
+        validate_authorization_response_issuer(Some("http://evil/"), issuer, false).unwrap_err();
+    assert_matches!(
+        error,
+        AuthorizationResponseError::IssuerMismatch { expected, got }
+            if expected == issuer && got == "http://evil/"
+    );
+
+    let error = validate_authorization_response_issuer(None, issuer, true).unwrap_err();
+    assert_matches!(error, AuthorizationResponseError::MissingIssuer);
+}
+
 /// Check if the given request to the token endpoint is valid.
 fn is_valid_token_endpoint_request(req: &Request) -> bool {
     let body = form_urlencoded::parse(&req.body).collect::<HashMap<_, _>>();
@@ -210,6 +237,7 @@ async fn pass_access_token_with_authorization_code() {
                 token_type: OAuthAccessTokenType::Bearer,
                 expires_in: None,
                 scope: Some([OPENID].into_iter().collect()),
+                issued_token_type: None,
             }),
         )
         .mount(&mock_server)
@@ -268,6 +296,7 @@ async fn fail_access_token_with_authorization_code_wrong_nonce() {
                 token_type: OAuthAccessTokenType::Bearer,
                 expires_in: None,
                 scope: Some([OPENID].into_iter().collect()),
+                issued_token_type: None,
             }),
         )
         .mount(&mock_server)
@@ -329,6 +358,7 @@ async fn fail_access_token_with_authorization_code_no_id_token() {
                 token_type: OAuthAccessTokenType::Bearer,
                 expires_in: None,
                 scope: Some([OPENID].into_iter().collect()),
+                issued_token_type: None,
             }),
         )
         .mount(&mock_server)
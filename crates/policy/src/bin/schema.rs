@@ -7,7 +7,7 @@
 use std::path::{Path, PathBuf};
 
 use mas_policy::model::{
-    AuthorizationGrantInput, ClientRegistrationInput, EmailInput, RegisterInput,
+    AuthorizationGrantInput, ClientRegistrationInput, EmailInput, RegisterInput, TokenExchangeInput,
 };
 use schemars::{JsonSchema, r#gen::SchemaSettings};
 
@@ -44,5 +44,6 @@ fn main() {
     write_schema::<RegisterInput>(output_root, "register_input.json");
     write_schema::<ClientRegistrationInput>(output_root, "client_registration_input.json");
     write_schema::<AuthorizationGrantInput>(output_root, "authorization_grant_input.json");
+    write_schema::<TokenExchangeInput>(output_root, "token_exchange_input.json");
     write_schema::<EmailInput>(output_root, "email_input.json");
 }
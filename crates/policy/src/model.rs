@@ -9,11 +9,12 @@
 //! This is useful to generate JSON schemas for each input type, which can then
 //! be type-checked by Open Policy Agent.
 
-use std::net::IpAddr;
+use std::{collections::HashMap, net::IpAddr};
 
 use mas_data_model::{Client, User};
 use oauth2_types::{registration::VerifiedClientMetadata, scope::Scope};
 use serde::{Deserialize, Serialize};
+use url::Url;
 
 /// A well-known policy code.
 #[derive(Deserialize, Debug, Clone, Copy)]
@@ -157,6 +158,12 @@ pub struct ClientRegistrationInput<'a> {
         schemars(with = "std::collections::HashMap<String, serde_json::Value>")
     )]
     pub client_metadata: &'a VerifiedClientMetadata,
+
+    /// The claims of the verified software statement presented by the client,
+    /// if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub software_statement: Option<&'a HashMap<String, serde_json::Value>>,
+
     pub requester: Requester,
 }
 
@@ -168,6 +175,34 @@ pub enum GrantType {
     ClientCredentials,
     #[serde(rename = "urn:ietf:params:oauth:grant-type:device_code")]
     DeviceCode,
+    #[serde(rename = "urn:ietf:params:oauth:grant-type:jwt-bearer")]
+    JwtBearer,
+}
+
+/// A method which was used to authenticate the user in their browser session
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+pub enum AuthenticationMethod {
+    #[serde(rename = "password")]
+    Password,
+
+    #[serde(rename = "upstream-oauth2")]
+    UpstreamOAuth2,
+
+    #[serde(rename = "totp")]
+    Totp,
+
+    #[serde(rename = "passkey")]
+    Passkey,
+
+    #[serde(rename = "recovery-code")]
+    RecoveryCode,
+
+    #[serde(rename = "email-code")]
+    EmailCode,
+
+    #[serde(rename = "ldap")]
+    Ldap,
 }
 
 /// Input for the authorization grant policy.
@@ -190,8 +225,63 @@ pub struct AuthorizationGrantInput<'a> {
     #[cfg_attr(feature = "jsonschema", schemars(with = "String"))]
     pub scope: &'a Scope,
 
+    /// The resources the client requested access to, through resource
+    /// indicators
+    #[cfg_attr(feature = "jsonschema", schemars(with = "Vec<String>"))]
+    pub resources: &'a [Url],
+
     pub grant_type: GrantType,
 
+    /// The methods used to authenticate the user in the browser session
+    /// granting access. Empty for grants which don't involve a browser
+    /// session.
+    pub authentication_methods: Vec<AuthenticationMethod>,
+
+    pub requester: Requester,
+}
+
+/// Input for the token exchange policy.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+pub struct TokenExchangeInput<'a> {
+    /// The user on behalf of whom the token is requested
+    #[cfg_attr(
+        feature = "jsonschema",
+        schemars(with = "std::collections::HashMap<String, serde_json::Value>")
+    )]
+    pub user: &'a User,
+
+    /// The client requesting the exchange
+    #[cfg_attr(
+        feature = "jsonschema",
+        schemars(with = "std::collections::HashMap<String, serde_json::Value>")
+    )]
+    pub client: &'a Client,
+
+    /// The client to which the subject token was issued
+    #[cfg_attr(
+        feature = "jsonschema",
+        schemars(with = "std::collections::HashMap<String, serde_json::Value>")
+    )]
+    pub subject_client: &'a Client,
+
+    /// The client to which the actor token was issued, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(
+        feature = "jsonschema",
+        schemars(with = "Option<std::collections::HashMap<String, serde_json::Value>>")
+    )]
+    pub actor_client: Option<&'a Client>,
+
+    /// The scope of the requested token
+    #[cfg_attr(feature = "jsonschema", schemars(with = "String"))]
+    pub scope: &'a Scope,
+
+    /// The audience of the requested token, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audience: Option<&'a str>,
+
     pub requester: Requester,
 }
 
@@ -117,6 +117,22 @@ impl SimpleRoute for OidcUserinfo {
     const PATH: &'static str = "/oauth2/userinfo";
 }
 
+/// `GET|POST /oauth2/end_session`
+#[derive(Default, Debug, Clone)]
+pub struct OidcEndSession;
+
+impl SimpleRoute for OidcEndSession {
+    const PATH: &'static str = "/oauth2/end_session";
+}
+
+/// `POST /oauth2/end_session/confirm`
+#[derive(Default, Debug, Clone)]
+pub struct OidcEndSessionConfirm;
+
+impl SimpleRoute for OidcEndSessionConfirm {
+    const PATH: &'static str = "/oauth2/end_session/confirm";
+}
+
 /// `POST /oauth2/introspect`
 #[derive(Default, Debug, Clone)]
 pub struct OAuth2Introspection;
@@ -149,6 +165,29 @@ impl SimpleRoute for OAuth2RegistrationEndpoint {
     const PATH: &'static str = "/oauth2/registration";
 }
 
+/// `GET|PUT|DELETE /oauth2/registration/{client_id}`
+#[derive(Debug, Clone)]
+pub struct OAuth2ClientConfigurationEndpoint(pub Ulid);
+
+impl Route for OAuth2ClientConfigurationEndpoint {
+    type Query = ();
+    fn route() -> &'static str {
+        "/oauth2/registration/{client_id}"
+    }
+
+    fn path(&self) -> std::borrow::Cow<'static, str> {
+        format!("/oauth2/registration/{}", self.0).into()
+    }
+}
+
+/// `POST /oauth2/par`
+#[derive(Default, Debug, Clone)]
+pub struct OAuth2PushedAuthorizationRequestEndpoint;
+
+impl SimpleRoute for OAuth2PushedAuthorizationRequestEndpoint {
+    const PATH: &'static str = "/oauth2/par";
+}
+
 /// `GET /authorize`
 #[derive(Default, Debug, Clone)]
 pub struct OAuth2AuthorizationEndpoint;
@@ -247,6 +286,117 @@ impl From<Option<PostAuthAction>> for Login {
     }
 }
 
+/// `GET|POST /login/totp`
+#[derive(Default, Debug, Clone)]
+pub struct LoginTotp {
+    post_auth_action: Option<PostAuthAction>,
+}
+
+impl Route for LoginTotp {
+    type Query = PostAuthAction;
+
+    fn route() -> &'static str {
+        "/login/totp"
+    }
+
+    fn query(&self) -> Option<&Self::Query> {
+        self.post_auth_action.as_ref()
+    }
+}
+
+impl From<Option<PostAuthAction>> for LoginTotp {
+    fn from(post_auth_action: Option<PostAuthAction>) -> Self {
+        Self { post_auth_action }
+    }
+}
+
+/// `GET|POST /login/passkey`
+#[derive(Default, Debug, Clone)]
+pub struct LoginPasskey {
+    post_auth_action: Option<PostAuthAction>,
+}
+
+impl Route for LoginPasskey {
+    type Query = PostAuthAction;
+
+    fn route() -> &'static str {
+        "/login/passkey"
+    }
+
+    fn query(&self) -> Option<&Self::Query> {
+        self.post_auth_action.as_ref()
+    }
+}
+
+impl From<Option<PostAuthAction>> for LoginPasskey {
+    fn from(post_auth_action: Option<PostAuthAction>) -> Self {
+        Self { post_auth_action }
+    }
+}
+
+/// `GET|POST /login/recovery-code`
+#[derive(Default, Debug, Clone)]
+pub struct LoginRecoveryCode {
+    post_auth_action: Option<PostAuthAction>,
+}
+
+impl Route for LoginRecoveryCode {
+    type Query = PostAuthAction;
+
+    fn route() -> &'static str {
+        "/login/recovery-code"
+    }
+
+    fn query(&self) -> Option<&Self::Query> {
+        self.post_auth_action.as_ref()
+    }
+}
+
+impl From<Option<PostAuthAction>> for LoginRecoveryCode {
+    fn from(post_auth_action: Option<PostAuthAction>) -> Self {
+        Self { post_auth_action }
+    }
+}
+
+/// `GET|POST /login/email/{id}`
+#[derive(Debug, Clone)]
+pub struct LoginEmailCode {
+    id: Ulid,
+    post_auth_action: Option<PostAuthAction>,
+}
+
+impl LoginEmailCode {
+    #[must_use]
+    pub const fn new(id: Ulid) -> Self {
+        Self {
+            id,
+            post_auth_action: None,
+        }
+    }
+
+    #[must_use]
+    pub fn and_then(mut self, action: PostAuthAction) -> Self {
+        self.post_auth_action = Some(action);
+        self
+    }
+}
+
+impl Route for LoginEmailCode {
+    type Query = PostAuthAction;
+
+    fn route() -> &'static str {
+        "/login/email/{id}"
+    }
+
+    fn path(&self) -> std::borrow::Cow<'static, str> {
+        format!("/login/email/{}", self.id).into()
+    }
+
+    fn query(&self) -> Option<&Self::Query> {
+        self.post_auth_action.as_ref()
+    }
+}
+
 /// `POST /logout`
 #[derive(Default, Debug, Clone)]
 pub struct Logout;
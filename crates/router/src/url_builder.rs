@@ -166,6 +166,12 @@ impl UrlBuilder {
         self.absolute_url_for(&crate::endpoints::OAuth2RegistrationEndpoint)
     }
 
+    /// OAuth 2.0 pushed authorization request endpoint
+    #[must_use]
+    pub fn oauth_pushed_authorization_request_endpoint(&self) -> Url {
+        self.absolute_url_for(&crate::endpoints::OAuth2PushedAuthorizationRequestEndpoint)
+    }
+
     /// OAuth 2.0 device authorization endpoint
     #[must_use]
     pub fn oauth_device_authorization_endpoint(&self) -> Url {
@@ -190,6 +196,12 @@ impl UrlBuilder {
         self.absolute_url_for(&crate::endpoints::OidcUserinfo)
     }
 
+    /// OIDC end session endpoint
+    #[must_use]
+    pub fn oidc_end_session_endpoint(&self) -> Url {
+        self.absolute_url_for(&crate::endpoints::OidcEndSession)
+    }
+
     /// JWKS URI
     #[must_use]
     pub fn jwks_uri(&self) -> Url {
@@ -0,0 +1,9 @@
+-- Copyright 2025 New Vector Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only
+-- Please see LICENSE in the repository root for full details.
+
+-- Adds columns to store the OIDC Back-Channel Logout settings of the client
+ALTER TABLE oauth2_clients
+  ADD COLUMN backchannel_logout_uri TEXT,
+  ADD COLUMN backchannel_logout_session_required BOOLEAN NOT NULL DEFAULT FALSE;
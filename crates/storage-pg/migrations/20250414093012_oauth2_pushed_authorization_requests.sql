@@ -0,0 +1,33 @@
+-- Copyright 2025 New Vector Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only
+-- Please see LICENSE in the repository root for full details.
+
+-- Adds a table to store authorization requests pushed by clients to the
+-- pushed authorization request endpoint (RFC 9126)
+CREATE TABLE "oauth2_pushed_authorization_requests" (
+    "oauth2_pushed_authorization_request_id" UUID NOT NULL
+        PRIMARY KEY,
+
+    -- The client which pushed the authorization request
+    "oauth2_client_id" UUID NOT NULL
+        REFERENCES "oauth2_clients" ("oauth2_client_id")
+        ON DELETE CASCADE,
+
+    -- The `request_uri` the client uses to reference this request
+    "request_uri" TEXT NOT NULL
+        UNIQUE,
+
+    -- The authorization request parameters, as a list of key-value pairs
+    "parameters" JSONB NOT NULL,
+
+    -- Timestamp when the authorization request was pushed
+    "created_at" TIMESTAMP WITH TIME ZONE NOT NULL,
+
+    -- Timestamp when the authorization request expires
+    "expires_at" TIMESTAMP WITH TIME ZONE NOT NULL,
+
+    -- Timestamp when the authorization request was used at the authorization
+    -- endpoint
+    "consumed_at" TIMESTAMP WITH TIME ZONE
+);
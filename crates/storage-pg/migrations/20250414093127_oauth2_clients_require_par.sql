@@ -0,0 +1,8 @@
+-- Copyright 2025 New Vector Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only
+-- Please see LICENSE in the repository root for full details.
+
+-- Adds a column to require clients to use pushed authorization requests
+ALTER TABLE oauth2_clients
+  ADD COLUMN require_pushed_authorization_requests BOOLEAN NOT NULL DEFAULT FALSE;
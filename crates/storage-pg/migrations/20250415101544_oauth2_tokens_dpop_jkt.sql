@@ -0,0 +1,12 @@
+-- Copyright 2025 New Vector Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only
+-- Please see LICENSE in the repository root for full details.
+
+-- Records the JWK thumbprint of the DPoP key OAuth 2.0 tokens are bound to
+-- (RFC 9449)
+ALTER TABLE "oauth2_access_tokens"
+  ADD COLUMN "dpop_jkt" TEXT;
+
+ALTER TABLE "oauth2_refresh_tokens"
+  ADD COLUMN "dpop_jkt" TEXT;
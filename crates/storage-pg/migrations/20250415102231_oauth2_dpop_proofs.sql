@@ -0,0 +1,26 @@
+-- Copyright 2025 New Vector Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only
+-- Please see LICENSE in the repository root for full details.
+
+-- Adds a table to keep track of the DPoP proofs we've seen, so that they can't
+-- be replayed (RFC 9449)
+CREATE TABLE "oauth2_dpop_proofs" (
+    -- The JWK thumbprint of the key which signed the proof
+    "jkt" TEXT NOT NULL,
+
+    -- The unique identifier of the proof, as set by the client
+    "jti" TEXT NOT NULL,
+
+    -- Timestamp when the proof was first seen
+    "created_at" TIMESTAMP WITH TIME ZONE NOT NULL,
+
+    -- Timestamp after which the proof would be rejected anyway, and can be
+    -- forgotten
+    "expires_at" TIMESTAMP WITH TIME ZONE NOT NULL,
+
+    PRIMARY KEY ("jkt", "jti")
+);
+
+CREATE INDEX "oauth2_dpop_proofs_expires_at_idx"
+    ON "oauth2_dpop_proofs" ("expires_at");
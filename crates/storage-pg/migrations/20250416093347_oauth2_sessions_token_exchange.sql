@@ -0,0 +1,21 @@
+-- Copyright 2025 New Vector Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only
+-- Please see LICENSE in the repository root for full details.
+
+-- Add a flag on oauth2_clients to indicate whether they support the token
+-- exchange grant (RFC 8693)
+ALTER TABLE "oauth2_clients"
+  ADD COLUMN "grant_type_token_exchange" BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Keep track of sessions obtained through a token exchange
+ALTER TABLE "oauth2_sessions"
+  ADD COLUMN "parent_oauth2_session_id" UUID
+    REFERENCES "oauth2_sessions" ("oauth2_session_id") ON DELETE SET NULL,
+  ADD COLUMN "actor_oauth2_client_id" UUID
+    REFERENCES "oauth2_clients" ("oauth2_client_id") ON DELETE SET NULL,
+  ADD COLUMN "audience" TEXT;
+
+CREATE INDEX "oauth2_sessions_parent_oauth2_session_id_idx"
+  ON "oauth2_sessions" ("parent_oauth2_session_id")
+  WHERE "parent_oauth2_session_id" IS NOT NULL;
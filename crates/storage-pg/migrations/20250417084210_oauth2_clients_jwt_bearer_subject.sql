@@ -0,0 +1,9 @@
+-- Copyright 2025 New Vector Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only
+-- Please see LICENSE in the repository root for full details.
+
+-- Adds a column to configure the user on behalf of which a client can get
+-- access tokens using the JWT bearer grant
+ALTER TABLE oauth2_clients
+  ADD COLUMN jwt_bearer_subject TEXT;
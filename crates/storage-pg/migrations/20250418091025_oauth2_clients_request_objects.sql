@@ -0,0 +1,9 @@
+-- Copyright 2025 New Vector Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only
+-- Please see LICENSE in the repository root for full details.
+
+-- Adds the client metadata used to verify request objects
+ALTER TABLE oauth2_clients
+  ADD COLUMN request_object_signing_alg TEXT,
+  ADD COLUMN request_uris TEXT[] NOT NULL DEFAULT '{}';
@@ -0,0 +1,15 @@
+-- Copyright 2025 New Vector Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only
+-- Please see LICENSE in the repository root for full details.
+
+-- Keep track of the individual claims requested through the `claims`
+-- authorization request parameter
+ALTER TABLE "oauth2_authorization_grants"
+  ADD COLUMN "claims" JSONB;
+
+-- The UserInfo endpoint looks up the authorization grant of a session to know
+-- which claims were requested
+CREATE INDEX "oauth2_authorization_grants_oauth2_session_id_idx"
+  ON "oauth2_authorization_grants" ("oauth2_session_id")
+  WHERE "oauth2_session_id" IS NOT NULL;
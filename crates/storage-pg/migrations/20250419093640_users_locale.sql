@@ -0,0 +1,9 @@
+-- Copyright 2025 New Vector Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only
+-- Please see LICENSE in the repository root for full details.
+
+-- The locale of the user, as seen when they last authorized a client. This is
+-- exposed as the `locale` claim to clients with the `profile` scope.
+ALTER TABLE "users"
+  ADD COLUMN "locale" TEXT;
@@ -0,0 +1,9 @@
+-- Copyright 2025 New Vector Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only
+-- Please see LICENSE in the repository root for full details.
+
+-- Adds the client metadata used to give out pairwise subject identifiers
+ALTER TABLE oauth2_clients
+  ADD COLUMN subject_type TEXT,
+  ADD COLUMN sector_identifier_uri TEXT;
@@ -0,0 +1,30 @@
+-- Copyright 2025 New Vector Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only
+-- Please see LICENSE in the repository root for full details.
+
+-- Adds a table to keep track of the pairwise subject identifiers given out to
+-- each sector, so that they can be mapped back to the user
+CREATE TABLE "oauth2_pairwise_subjects" (
+    -- The sector identifier, which is the host of the client's sector
+    -- identifier URI or redirect URIs
+    "sector_identifier" TEXT NOT NULL,
+
+    -- The user this subject identifier refers to
+    "user_id" UUID NOT NULL
+        REFERENCES "users" ("user_id"),
+
+    -- The subject identifier given out to clients of this sector
+    "subject" TEXT NOT NULL,
+
+    -- Timestamp when the subject identifier was first given out
+    "created_at" TIMESTAMP WITH TIME ZONE NOT NULL,
+
+    PRIMARY KEY ("sector_identifier", "user_id")
+);
+
+CREATE UNIQUE INDEX "oauth2_pairwise_subjects_sector_identifier_subject_idx"
+    ON "oauth2_pairwise_subjects" ("sector_identifier", "subject");
+
+CREATE INDEX "oauth2_pairwise_subjects_user_id_idx"
+    ON "oauth2_pairwise_subjects" ("user_id");
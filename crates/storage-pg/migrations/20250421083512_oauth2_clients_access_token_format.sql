@@ -0,0 +1,9 @@
+-- Copyright 2025 New Vector Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only
+-- Please see LICENSE in the repository root for full details.
+
+-- Adds the format of the access tokens issued to each client, either 'opaque'
+-- or 'jwt' (RFC 9068)
+ALTER TABLE oauth2_clients
+  ADD COLUMN access_token_format TEXT NOT NULL DEFAULT 'opaque';
@@ -0,0 +1,9 @@
+-- Copyright 2025 New Vector Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only
+-- Please see LICENSE in the repository root for full details.
+
+-- Adds the algorithm used to sign JWT introspection responses (RFC 9701)
+-- returned to each client
+ALTER TABLE oauth2_clients
+  ADD COLUMN introspection_signed_response_alg TEXT;
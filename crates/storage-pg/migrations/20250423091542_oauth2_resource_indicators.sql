@@ -0,0 +1,12 @@
+-- Copyright 2025 New Vector Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only
+-- Please see LICENSE in the repository root for full details.
+
+-- Keep track of the resource indicators (RFC 8707) requested by clients. The
+-- tokens of a session are restricted to those resources, if any.
+ALTER TABLE "oauth2_authorization_grants"
+  ADD COLUMN "resources" TEXT[] NOT NULL DEFAULT '{}';
+
+ALTER TABLE "oauth2_sessions"
+  ADD COLUMN "resources" TEXT[] NOT NULL DEFAULT '{}';
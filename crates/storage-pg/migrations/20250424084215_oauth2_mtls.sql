@@ -0,0 +1,14 @@
+-- Copyright 2025 New Vector Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only
+-- Please see LICENSE in the repository root for full details.
+
+-- Adds the subject DN clients using the `tls_client_auth` method must present
+-- in their certificate (RFC 8705)
+ALTER TABLE oauth2_clients
+  ADD COLUMN tls_client_auth_subject_dn TEXT;
+
+-- Records the SHA-256 thumbprint of the client certificate OAuth 2.0 access
+-- tokens are bound to (RFC 8705)
+ALTER TABLE "oauth2_access_tokens"
+  ADD COLUMN "x5t_s256" TEXT;
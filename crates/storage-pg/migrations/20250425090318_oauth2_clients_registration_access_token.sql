@@ -0,0 +1,9 @@
+-- Copyright 2025 New Vector Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only
+-- Please see LICENSE in the repository root for full details.
+
+-- Adds the encrypted registration access token (RFC 7592) dynamically
+-- registered clients use to read, update and delete their registration
+ALTER TABLE oauth2_clients
+  ADD COLUMN encrypted_registration_access_token TEXT;
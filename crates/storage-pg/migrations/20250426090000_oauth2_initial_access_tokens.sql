@@ -0,0 +1,24 @@
+-- Copyright 2025 New Vector Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only
+-- Please see LICENSE in the repository root for full details.
+
+-- Initial access tokens (RFC 7591) minted by administrators, which can be
+-- required to use the dynamic client registration endpoint
+CREATE TABLE oauth2_initial_access_tokens (
+  oauth2_initial_access_token_id UUID NOT NULL
+    CONSTRAINT oauth2_initial_access_tokens_pkey
+    PRIMARY KEY,
+
+  token TEXT NOT NULL
+    CONSTRAINT oauth2_initial_access_tokens_token_unique
+    UNIQUE,
+
+  max_uses INTEGER,
+  use_count INTEGER NOT NULL DEFAULT 0,
+
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+  expires_at TIMESTAMP WITH TIME ZONE,
+  last_used_at TIMESTAMP WITH TIME ZONE,
+  revoked_at TIMESTAMP WITH TIME ZONE
+);
@@ -0,0 +1,12 @@
+-- Copyright 2025 New Vector Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only
+-- Please see LICENSE in the repository root for full details.
+
+-- Adds the JWE algorithms used to encrypt the ID tokens and user info
+-- responses returned to each client
+ALTER TABLE oauth2_clients
+  ADD COLUMN id_token_encrypted_response_alg TEXT,
+  ADD COLUMN id_token_encrypted_response_enc TEXT,
+  ADD COLUMN userinfo_encrypted_response_alg TEXT,
+  ADD COLUMN userinfo_encrypted_response_enc TEXT;
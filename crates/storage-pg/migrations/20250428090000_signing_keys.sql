@@ -0,0 +1,19 @@
+-- Copyright 2025 New Vector Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only
+-- Please see LICENSE in the repository root for full details.
+
+-- Add a table which stores the signing keys generated by the automatic key
+-- rotation
+--
+-- Keys are published as soon as they are created, are used for signing once
+-- `activated_at` is set, and stop being used for signing once `retired_at` is
+-- set. Retired keys are deleted once they are not published anymore.
+CREATE TABLE IF NOT EXISTS signing_keys (
+    signing_key_id UUID PRIMARY KEY,
+    kid TEXT NOT NULL UNIQUE,
+    encrypted_key TEXT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    activated_at TIMESTAMP WITH TIME ZONE,
+    retired_at TIMESTAMP WITH TIME ZONE
+);
@@ -0,0 +1,26 @@
+-- Copyright 2025 New Vector Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only
+-- Please see LICENSE in the repository root for full details.
+
+-- Add a table which stores the TOTP authenticators users enrolled as a second
+-- factor. A user can only have one authenticator at a time.
+--
+-- The authenticator is only used once `verified_at` is set, meaning the user
+-- entered a valid code when enrolling it. `last_used_step` records the time
+-- step of the last accepted code, so that codes can't be replayed.
+CREATE TABLE IF NOT EXISTS user_totp_authenticators (
+    user_totp_authenticator_id UUID PRIMARY KEY,
+    user_id UUID NOT NULL UNIQUE
+        REFERENCES users (user_id) ON DELETE CASCADE,
+    encrypted_secret TEXT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    verified_at TIMESTAMP WITH TIME ZONE,
+    last_used_step BIGINT
+);
+
+-- Record when a user session was authenticated with a TOTP code
+ALTER TABLE user_session_authentications
+    ADD COLUMN user_totp_authenticator_id UUID
+        REFERENCES user_totp_authenticators (user_totp_authenticator_id)
+        ON DELETE SET NULL;
@@ -0,0 +1,43 @@
+-- Copyright 2025 New Vector Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only
+-- Please see LICENSE in the repository root for full details.
+
+-- Add a table which stores the WebAuthn credentials (passkeys) registered by
+-- users. They can be used as a passwordless login or as a second factor.
+--
+-- `sign_count` is the signature counter last reported by the authenticator,
+-- used to detect cloned authenticators.
+CREATE TABLE IF NOT EXISTS user_passkeys (
+    user_passkey_id UUID PRIMARY KEY,
+    user_id UUID NOT NULL
+        REFERENCES users (user_id) ON DELETE CASCADE,
+    credential_id TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    public_key BYTEA NOT NULL,
+    sign_count BIGINT NOT NULL,
+    transports TEXT[] NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    last_used_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS user_passkeys_user_id_idx
+    ON user_passkeys (user_id);
+
+-- Challenges issued for WebAuthn ceremonies. Registrations are tied to the
+-- browser session which started them, passwordless logins aren't tied to any
+-- session.
+CREATE TABLE IF NOT EXISTS user_passkey_challenges (
+    user_passkey_challenge_id UUID PRIMARY KEY,
+    user_session_id UUID
+        REFERENCES user_sessions (user_session_id) ON DELETE CASCADE,
+    challenge TEXT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    completed_at TIMESTAMP WITH TIME ZONE
+);
+
+-- Record when a user session was authenticated with a passkey
+ALTER TABLE user_session_authentications
+    ADD COLUMN user_passkey_id UUID
+        REFERENCES user_passkeys (user_passkey_id)
+        ON DELETE SET NULL;
@@ -0,0 +1,28 @@
+-- Copyright 2025 New Vector Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only
+-- Please see LICENSE in the repository root for full details.
+
+-- Add a table which stores the single-use recovery codes users can enter
+-- instead of their second factor.
+--
+-- Codes are hashed with the same schemes as passwords, and `version` records
+-- which scheme was used. Codes are kept once used, with `consumed_at` set.
+CREATE TABLE IF NOT EXISTS user_recovery_codes (
+    user_recovery_code_id UUID PRIMARY KEY,
+    user_id UUID NOT NULL
+        REFERENCES users (user_id) ON DELETE CASCADE,
+    hashed_code TEXT NOT NULL,
+    version INTEGER NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    consumed_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS user_recovery_codes_user_id_idx
+    ON user_recovery_codes (user_id);
+
+-- Record when a user session was authenticated with a recovery code
+ALTER TABLE user_session_authentications
+    ADD COLUMN user_recovery_code_id UUID
+        REFERENCES user_recovery_codes (user_recovery_code_id)
+        ON DELETE SET NULL;
@@ -0,0 +1,19 @@
+-- Copyright 2025 New Vector Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only
+-- Please see LICENSE in the repository root for full details.
+
+-- Allow using user email authentications to log in without a password.
+--
+-- `user_id` is the user the authentication would log in as. It is NULL when
+-- the address doesn't belong to a user allowed to log in this way, in which
+-- case the authentication can never complete.
+ALTER TABLE user_email_authentications
+    ADD COLUMN user_id UUID
+        REFERENCES users (user_id) ON DELETE CASCADE;
+
+-- Record when a user session was authenticated with an email code
+ALTER TABLE user_session_authentications
+    ADD COLUMN user_email_authentication_id UUID
+        REFERENCES user_email_authentications (user_email_authentication_id)
+        ON DELETE SET NULL;
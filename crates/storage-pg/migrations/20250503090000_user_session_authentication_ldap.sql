@@ -0,0 +1,9 @@
+-- Copyright 2025 New Vector Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only
+-- Please see LICENSE in the repository root for full details.
+
+-- Record when a user session was authenticated by binding to an LDAP
+-- directory, along with the DN of the entry which was used
+ALTER TABLE user_session_authentications
+    ADD COLUMN ldap_dn TEXT;
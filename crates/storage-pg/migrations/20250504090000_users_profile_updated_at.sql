@@ -0,0 +1,10 @@
+-- Copyright 2025 New Vector Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only
+-- Please see LICENSE in the repository root for full details.
+
+-- When the profile of the user (display name, avatar or locale) was last
+-- changed through the service. This is exposed as the `updated_at` claim to
+-- clients with the `profile` scope.
+ALTER TABLE "users"
+  ADD COLUMN "profile_updated_at" TIMESTAMP WITH TIME ZONE;
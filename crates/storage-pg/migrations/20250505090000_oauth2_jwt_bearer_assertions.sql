@@ -0,0 +1,27 @@
+-- Copyright 2025 New Vector Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only
+-- Please see LICENSE in the repository root for full details.
+
+-- Adds a table to keep track of the JWT bearer assertions which were used as
+-- authorization grants, so that they can't be replayed (RFC 7523)
+CREATE TABLE "oauth2_jwt_bearer_assertions" (
+    -- The client which issued the assertion. There is no foreign key, as the
+    -- rows are short-lived and cleaned up once they expire
+    "oauth2_client_id" UUID NOT NULL,
+
+    -- The unique identifier of the assertion, as set by the client
+    "jti" TEXT NOT NULL,
+
+    -- Timestamp when the assertion was first seen
+    "created_at" TIMESTAMP WITH TIME ZONE NOT NULL,
+
+    -- Timestamp after which the assertion would be rejected anyway, and can be
+    -- forgotten
+    "expires_at" TIMESTAMP WITH TIME ZONE NOT NULL,
+
+    PRIMARY KEY ("oauth2_client_id", "jti")
+);
+
+CREATE INDEX "oauth2_jwt_bearer_assertions_expires_at_idx"
+    ON "oauth2_jwt_bearer_assertions" ("expires_at");
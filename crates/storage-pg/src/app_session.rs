@@ -76,6 +76,10 @@ mod priv_ {
         pub(super) user_agent: Option<String>,
         pub(super) last_active_at: Option<DateTime<Utc>>,
         pub(super) last_active_ip: Option<IpAddr>,
+        pub(super) parent_oauth2_session_id: Option<Uuid>,
+        pub(super) actor_oauth2_client_id: Option<Uuid>,
+        pub(super) audience: Option<String>,
+        pub(super) resources: Option<Vec<String>>,
     }
 }
 
@@ -104,6 +108,10 @@ impl TryFrom<AppSessionLookup> for AppSession {
             user_agent,
             last_active_at,
             last_active_ip,
+            parent_oauth2_session_id,
+            actor_oauth2_client_id,
+            audience,
+            resources,
         } = value;
 
         let user_agent = user_agent.map(UserAgent::parse);
@@ -179,6 +187,18 @@ impl TryFrom<AppSessionLookup> for AppSession {
                         .source(e)
                 })?;
 
+                let resources = resources
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|resource| resource.parse())
+                    .collect::<Result<_, _>>()
+                    .map_err(|e| {
+                        DatabaseInconsistencyError::on("oauth2_sessions")
+                            .column("resources")
+                            .row(id)
+                            .source(e)
+                    })?;
+
                 let state = match value.finished_at {
                     None => SessionState::Valid,
                     Some(finished_at) => SessionState::Finished { finished_at },
@@ -195,6 +215,10 @@ impl TryFrom<AppSessionLookup> for AppSession {
                     user_agent,
                     last_active_at,
                     last_active_ip,
+                    parent_session_id: parent_oauth2_session_id.map(Ulid::from),
+                    actor_client_id: actor_oauth2_client_id.map(Ulid::from),
+                    audience,
+                    resources,
                 };
 
                 Ok(AppSession::OAuth2(Box::new(session)))
@@ -324,6 +348,22 @@ impl AppSessionRepository for PgAppSessionRepository<'_> {
                 Expr::col((OAuth2Sessions::Table, OAuth2Sessions::LastActiveIp)),
                 AppSessionLookupIden::LastActiveIp,
             )
+            .expr_as(
+                Expr::col((OAuth2Sessions::Table, OAuth2Sessions::ParentOAuth2SessionId)),
+                AppSessionLookupIden::ParentOauth2SessionId,
+            )
+            .expr_as(
+                Expr::col((OAuth2Sessions::Table, OAuth2Sessions::ActorOAuth2ClientId)),
+                AppSessionLookupIden::ActorOauth2ClientId,
+            )
+            .expr_as(
+                Expr::col((OAuth2Sessions::Table, OAuth2Sessions::Audience)),
+                AppSessionLookupIden::Audience,
+            )
+            .expr_as(
+                Expr::col((OAuth2Sessions::Table, OAuth2Sessions::Resources)),
+                AppSessionLookupIden::Resources,
+            )
             .from(OAuth2Sessions::Table)
             .apply_filter(oauth2_filter)
             .clone();
@@ -380,6 +420,16 @@ impl AppSessionRepository for PgAppSessionRepository<'_> {
                 Expr::col((CompatSessions::Table, CompatSessions::LastActiveIp)),
                 AppSessionLookupIden::LastActiveIp,
             )
+            .expr_as(
+                Expr::cust("NULL"),
+                AppSessionLookupIden::ParentOauth2SessionId,
+            )
+            .expr_as(
+                Expr::cust("NULL"),
+                AppSessionLookupIden::ActorOauth2ClientId,
+            )
+            .expr_as(Expr::cust("NULL"), AppSessionLookupIden::Audience)
+            .expr_as(Expr::cust("NULL"), AppSessionLookupIden::Resources)
             .from(CompatSessions::Table)
             .apply_filter(compat_filter)
             .clone();
@@ -646,6 +696,20 @@ mod tests {
                 None,
                 None,
                 Some("https://example.com/login".parse().unwrap()),
+                Vec::new(),
+                None,
+                false,
+                false,
+                None,
+                Vec::new(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -27,6 +27,8 @@ pub enum Users {
     LockedAt,
     DeactivatedAt,
     CanRequestAdmin,
+    Locale,
+    ProfileUpdatedAt,
 }
 
 #[derive(sea_query::Iden)]
@@ -83,6 +85,12 @@ pub enum OAuth2Sessions {
     UserAgent,
     LastActiveAt,
     LastActiveIp,
+    #[iden = "parent_oauth2_session_id"]
+    ParentOAuth2SessionId,
+    #[iden = "actor_oauth2_client_id"]
+    ActorOAuth2ClientId,
+    Audience,
+    Resources,
 }
 
 #[derive(sea_query::Iden)]
@@ -94,6 +102,21 @@ pub enum OAuth2Clients {
     IsStatic,
 }
 
+#[derive(sea_query::Iden)]
+#[iden = "oauth2_initial_access_tokens"]
+pub enum OAuth2InitialAccessTokens {
+    Table,
+    #[iden = "oauth2_initial_access_token_id"]
+    OAuth2InitialAccessTokenId,
+    Token,
+    MaxUses,
+    UseCount,
+    CreatedAt,
+    ExpiresAt,
+    LastUsedAt,
+    RevokedAt,
+}
+
 #[derive(sea_query::Iden)]
 #[iden = "upstream_oauth_providers"]
 pub enum UpstreamOAuthProviders {
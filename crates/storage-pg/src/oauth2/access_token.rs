@@ -37,6 +37,8 @@ struct OAuth2AccessTokenLookup {
     expires_at: Option<DateTime<Utc>>,
     revoked_at: Option<DateTime<Utc>>,
     first_used_at: Option<DateTime<Utc>>,
+    dpop_jkt: Option<String>,
+    x5t_s256: Option<String>,
 }
 
 impl From<OAuth2AccessTokenLookup> for AccessToken {
@@ -54,6 +56,8 @@ impl From<OAuth2AccessTokenLookup> for AccessToken {
             created_at: value.created_at,
             expires_at: value.expires_at,
             first_used_at: value.first_used_at,
+            dpop_jkt: value.dpop_jkt,
+            x5t_s256: value.x5t_s256,
         }
     }
 }
@@ -73,6 +77,8 @@ impl OAuth2AccessTokenRepository for PgOAuth2AccessTokenRepository<'_> {
                      , revoked_at
                      , oauth2_session_id
                      , first_used_at
+                     , dpop_jkt
+                     , x5t_s256
 
                 FROM oauth2_access_tokens
 
@@ -110,6 +116,8 @@ impl OAuth2AccessTokenRepository for PgOAuth2AccessTokenRepository<'_> {
                      , revoked_at
                      , oauth2_session_id
                      , first_used_at
+                     , dpop_jkt
+                     , x5t_s256
 
                 FROM oauth2_access_tokens
 
@@ -143,6 +151,8 @@ impl OAuth2AccessTokenRepository for PgOAuth2AccessTokenRepository<'_> {
         session: &Session,
         access_token: String,
         expires_after: Option<Duration>,
+        dpop_jkt: Option<String>,
+        x5t_s256: Option<String>,
     ) -> Result<AccessToken, Self::Error> {
         let created_at = clock.now();
         let expires_at = expires_after.map(|d| created_at + d);
@@ -153,15 +163,17 @@ impl OAuth2AccessTokenRepository for PgOAuth2AccessTokenRepository<'_> {
         sqlx::query!(
             r#"
                 INSERT INTO oauth2_access_tokens
-                    (oauth2_access_token_id, oauth2_session_id, access_token, created_at, expires_at)
+                    (oauth2_access_token_id, oauth2_session_id, access_token, created_at, expires_at, dpop_jkt, x5t_s256)
                 VALUES
-                    ($1, $2, $3, $4, $5)
+                    ($1, $2, $3, $4, $5, $6, $7)
             "#,
             Uuid::from(id),
             Uuid::from(session.id),
             &access_token,
             created_at,
             expires_at,
+            dpop_jkt.as_deref(),
+            x5t_s256.as_deref(),
         )
             .traced()
         .execute(&mut *self.conn)
@@ -175,6 +187,8 @@ impl OAuth2AccessTokenRepository for PgOAuth2AccessTokenRepository<'_> {
             created_at,
             expires_at,
             first_used_at: None,
+            dpop_jkt,
+            x5t_s256,
         })
     }
 
@@ -13,9 +13,12 @@ use mas_data_model::{
 };
 use mas_iana::oauth::PkceCodeChallengeMethod;
 use mas_storage::{Clock, oauth2::OAuth2AuthorizationGrantRepository};
-use oauth2_types::{requests::ResponseMode, scope::Scope};
+use oauth2_types::{
+    requests::{ClaimsRequest, ResponseMode},
+    scope::Scope,
+};
 use rand::RngCore;
-use sqlx::PgConnection;
+use sqlx::{PgConnection, types::Json};
 use ulid::Ulid;
 use url::Url;
 use uuid::Uuid;
@@ -56,6 +59,8 @@ struct GrantLookup {
     code_challenge_method: Option<String>,
     requires_consent: bool,
     login_hint: Option<String>,
+    claims: Option<Json<ClaimsRequest>>,
+    resources: Vec<String>,
     oauth2_client_id: Uuid,
     oauth2_session_id: Option<Uuid>,
 }
@@ -172,6 +177,18 @@ impl TryFrom<GrantLookup> for AuthorizationGrant {
                     .source(e)
             })?;
 
+        let resources = value
+            .resources
+            .iter()
+            .map(|resource| resource.parse())
+            .collect::<Result<_, _>>()
+            .map_err(|e| {
+                DatabaseInconsistencyError::on("oauth2_authorization_grants")
+                    .column("resources")
+                    .row(id)
+                    .source(e)
+            })?;
+
         Ok(AuthorizationGrant {
             id,
             stage,
@@ -187,6 +204,8 @@ impl TryFrom<GrantLookup> for AuthorizationGrant {
             response_type_id_token: value.response_type_id_token,
             requires_consent: value.requires_consent,
             login_hint: value.login_hint,
+            claims: value.claims.map(|Json(claims)| claims),
+            resources,
         })
     }
 }
@@ -221,6 +240,8 @@ impl OAuth2AuthorizationGrantRepository for PgOAuth2AuthorizationGrantRepository
         response_type_id_token: bool,
         requires_consent: bool,
         login_hint: Option<String>,
+        claims: Option<ClaimsRequest>,
+        resources: Vec<Url>,
     ) -> Result<AuthorizationGrant, Self::Error> {
         let code_challenge = code
             .as_ref()
@@ -233,6 +254,7 @@ impl OAuth2AuthorizationGrantRepository for PgOAuth2AuthorizationGrantRepository
         // TODO: this conversion is a bit ugly
         let max_age_i32 = max_age.map(|x| i32::try_from(u32::from(x)).unwrap_or(i32::MAX));
         let code_str = code.as_ref().map(|c| &c.code);
+        let resources_str: Vec<String> = resources.iter().map(ToString::to_string).collect();
 
         let created_at = clock.now();
         let id = Ulid::from_datetime_with_source(created_at.into(), rng);
@@ -256,10 +278,13 @@ impl OAuth2AuthorizationGrantRepository for PgOAuth2AuthorizationGrantRepository
                      authorization_code,
                      requires_consent,
                      login_hint,
+                     claims,
+                     resources,
                      created_at
                 )
                 VALUES
-                    ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
+                    ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17,
+                     $18)
             "#,
             Uuid::from(id),
             Uuid::from(client.id),
@@ -276,6 +301,8 @@ impl OAuth2AuthorizationGrantRepository for PgOAuth2AuthorizationGrantRepository
             code_str,
             requires_consent,
             login_hint,
+            claims.as_ref().map(Json) as _,
+            &resources_str,
             created_at,
         )
         .traced()
@@ -297,6 +324,8 @@ impl OAuth2AuthorizationGrantRepository for PgOAuth2AuthorizationGrantRepository
             response_type_id_token,
             requires_consent,
             login_hint,
+            claims,
+            resources,
         })
     }
 
@@ -332,6 +361,8 @@ impl OAuth2AuthorizationGrantRepository for PgOAuth2AuthorizationGrantRepository
                      , code_challenge_method
                      , requires_consent
                      , login_hint
+                     , claims as "claims: Json<ClaimsRequest>"
+                     , resources
                      , oauth2_session_id
                 FROM
                     oauth2_authorization_grants
@@ -383,6 +414,8 @@ impl OAuth2AuthorizationGrantRepository for PgOAuth2AuthorizationGrantRepository
                      , code_challenge_method
                      , requires_consent
                      , login_hint
+                     , claims as "claims: Json<ClaimsRequest>"
+                     , resources
                      , oauth2_session_id
                 FROM
                     oauth2_authorization_grants
@@ -400,6 +433,60 @@ impl OAuth2AuthorizationGrantRepository for PgOAuth2AuthorizationGrantRepository
         Ok(Some(res.try_into()?))
     }
 
+    #[tracing::instrument(
+        name = "db.oauth2_authorization_grant.find_by_session",
+        skip_all,
+        fields(
+            db.query.text,
+            %session.id,
+        ),
+        err,
+    )]
+    async fn find_by_session(
+        &mut self,
+        session: &Session,
+    ) -> Result<Option<AuthorizationGrant>, Self::Error> {
+        let res = sqlx::query_as!(
+            GrantLookup,
+            r#"
+                SELECT oauth2_authorization_grant_id
+                     , created_at
+                     , cancelled_at
+                     , fulfilled_at
+                     , exchanged_at
+                     , scope
+                     , state
+                     , redirect_uri
+                     , response_mode
+                     , nonce
+                     , max_age
+                     , oauth2_client_id
+                     , authorization_code
+                     , response_type_code
+                     , response_type_id_token
+                     , code_challenge
+                     , code_challenge_method
+                     , requires_consent
+                     , login_hint
+                     , claims as "claims: Json<ClaimsRequest>"
+                     , resources
+                     , oauth2_session_id
+                FROM
+                    oauth2_authorization_grants
+
+                WHERE oauth2_session_id = $1
+            "#,
+            Uuid::from(session.id),
+        )
+        .traced()
+        .fetch_optional(&mut *self.conn)
+        .await?;
+
+        let Some(res) = res else { return Ok(None) };
+
+        Ok(Some(res.try_into()?))
+    }
+
     #[tracing::instrument(
         name = "db.oauth2_authorization_grant.fulfill",
         skip_all,
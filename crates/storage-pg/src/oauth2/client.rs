@@ -11,12 +11,15 @@ use std::{
 };
 
 use async_trait::async_trait;
-use mas_data_model::{Client, JwksOrJwksUri, User};
-use mas_iana::{jose::JsonWebSignatureAlg, oauth::OAuthClientAuthenticationMethod};
+use mas_data_model::{AccessTokenFormat, Client, JwksOrJwksUri, User};
+use mas_iana::{
+    jose::{JsonWebEncryptionAlg, JsonWebEncryptionEnc, JsonWebSignatureAlg},
+    oauth::OAuthClientAuthenticationMethod,
+};
 use mas_jose::jwk::PublicJsonWebKeySet;
 use mas_storage::{Clock, oauth2::OAuth2ClientRepository};
 use oauth2_types::{
-    oidc::ApplicationType,
+    oidc::{ApplicationType, SubjectType},
     requests::GrantType,
     scope::{Scope, ScopeToken},
 };
@@ -55,6 +58,7 @@ struct OAuth2ClientLookup {
     grant_type_refresh_token: bool,
     grant_type_client_credentials: bool,
     grant_type_device_code: bool,
+    grant_type_token_exchange: bool,
     client_name: Option<String>,
     logo_uri: Option<String>,
     client_uri: Option<String>,
@@ -67,6 +71,23 @@ struct OAuth2ClientLookup {
     token_endpoint_auth_method: Option<String>,
     token_endpoint_auth_signing_alg: Option<String>,
     initiate_login_uri: Option<String>,
+    post_logout_redirect_uris: Vec<String>,
+    backchannel_logout_uri: Option<String>,
+    backchannel_logout_session_required: bool,
+    require_pushed_authorization_requests: bool,
+    jwt_bearer_subject: Option<String>,
+    request_object_signing_alg: Option<String>,
+    request_uris: Vec<String>,
+    subject_type: Option<String>,
+    sector_identifier_uri: Option<String>,
+    access_token_format: String,
+    introspection_signed_response_alg: Option<String>,
+    id_token_encrypted_response_alg: Option<String>,
+    id_token_encrypted_response_enc: Option<String>,
+    userinfo_encrypted_response_alg: Option<String>,
+    userinfo_encrypted_response_enc: Option<String>,
+    tls_client_auth_subject_dn: Option<String>,
+    encrypted_registration_access_token: Option<String>,
 }
 
 impl TryInto<Client> for OAuth2ClientLookup {
@@ -109,6 +130,12 @@ impl TryInto<Client> for OAuth2ClientLookup {
         if self.grant_type_device_code {
             grant_types.push(GrantType::DeviceCode);
         }
+        if self.grant_type_token_exchange {
+            grant_types.push(GrantType::TokenExchange);
+        }
+        if self.jwt_bearer_subject.is_some() {
+            grant_types.push(GrantType::JwtBearer);
+        }
 
         let logo_uri = self.logo_uri.map(|s| s.parse()).transpose().map_err(|e| {
             DatabaseInconsistencyError::on("oauth2_clients")
@@ -201,6 +228,133 @@ impl TryInto<Client> for OAuth2ClientLookup {
                     .source(e)
             })?;
 
+        let post_logout_redirect_uris: Result<Vec<Url>, _> = self
+            .post_logout_redirect_uris
+            .iter()
+            .map(|s| s.parse())
+            .collect();
+        let post_logout_redirect_uris = post_logout_redirect_uris.map_err(|e| {
+            DatabaseInconsistencyError::on("oauth2_clients")
+                .column("post_logout_redirect_uris")
+                .row(id)
+                .source(e)
+        })?;
+
+        let backchannel_logout_uri = self
+            .backchannel_logout_uri
+            .map(|s| s.parse())
+            .transpose()
+            .map_err(|e| {
+                DatabaseInconsistencyError::on("oauth2_clients")
+                    .column("backchannel_logout_uri")
+                    .row(id)
+                    .source(e)
+            })?;
+
+        let request_object_signing_alg = self
+            .request_object_signing_alg
+            .map(|s| s.parse())
+            .transpose()
+            .map_err(|e| {
+                DatabaseInconsistencyError::on("oauth2_clients")
+                    .column("request_object_signing_alg")
+                    .row(id)
+                    .source(e)
+            })?;
+
+        let request_uris: Result<Vec<Url>, _> =
+            self.request_uris.iter().map(|s| s.parse()).collect();
+        let request_uris = request_uris.map_err(|e| {
+            DatabaseInconsistencyError::on("oauth2_clients")
+                .column("request_uris")
+                .row(id)
+                .source(e)
+        })?;
+
+        let subject_type = self
+            .subject_type
+            .map(|s| s.parse())
+            .transpose()
+            .map_err(|e| {
+                DatabaseInconsistencyError::on("oauth2_clients")
+                    .column("subject_type")
+                    .row(id)
+                    .source(e)
+            })?;
+
+        let sector_identifier_uri = self
+            .sector_identifier_uri
+            .map(|s| s.parse())
+            .transpose()
+            .map_err(|e| {
+                DatabaseInconsistencyError::on("oauth2_clients")
+                    .column("sector_identifier_uri")
+                    .row(id)
+                    .source(e)
+            })?;
+
+        let access_token_format = self.access_token_format.parse().map_err(|e| {
+            DatabaseInconsistencyError::on("oauth2_clients")
+                .column("access_token_format")
+                .row(id)
+                .source(e)
+        })?;
+
+        let introspection_signed_response_alg = self
+            .introspection_signed_response_alg
+            .map(|s| s.parse())
+            .transpose()
+            .map_err(|e| {
+                DatabaseInconsistencyError::on("oauth2_clients")
+                    .column("introspection_signed_response_alg")
+                    .row(id)
+                    .source(e)
+            })?;
+
+        let id_token_encrypted_response_alg = self
+            .id_token_encrypted_response_alg
+            .map(|s| s.parse())
+            .transpose()
+            .map_err(|e| {
+                DatabaseInconsistencyError::on("oauth2_clients")
+                    .column("id_token_encrypted_response_alg")
+                    .row(id)
+                    .source(e)
+            })?;
+
+        let id_token_encrypted_response_enc = self
+            .id_token_encrypted_response_enc
+            .map(|s| s.parse())
+            .transpose()
+            .map_err(|e| {
+                DatabaseInconsistencyError::on("oauth2_clients")
+                    .column("id_token_encrypted_response_enc")
+                    .row(id)
+                    .source(e)
+            })?;
+
+        let userinfo_encrypted_response_alg = self
+            .userinfo_encrypted_response_alg
+            .map(|s| s.parse())
+            .transpose()
+            .map_err(|e| {
+                DatabaseInconsistencyError::on("oauth2_clients")
+                    .column("userinfo_encrypted_response_alg")
+                    .row(id)
+                    .source(e)
+            })?;
+
+        let userinfo_encrypted_response_enc = self
+            .userinfo_encrypted_response_enc
+            .map(|s| s.parse())
+            .transpose()
+            .map_err(|e| {
+                DatabaseInconsistencyError::on("oauth2_clients")
+                    .column("userinfo_encrypted_response_enc")
+                    .row(id)
+                    .source(e)
+            })?;
+
         let jwks = match (self.jwks, self.jwks_uri) {
             (None, None) => None,
             (Some(jwks), None) => {
@@ -248,6 +402,23 @@ impl TryInto<Client> for OAuth2ClientLookup {
             token_endpoint_auth_method,
             token_endpoint_auth_signing_alg,
             initiate_login_uri,
+            post_logout_redirect_uris,
+            backchannel_logout_uri,
+            backchannel_logout_session_required: self.backchannel_logout_session_required,
+            require_pushed_authorization_requests: self.require_pushed_authorization_requests,
+            jwt_bearer_subject: self.jwt_bearer_subject,
+            request_object_signing_alg,
+            request_uris,
+            subject_type,
+            sector_identifier_uri,
+            access_token_format,
+            introspection_signed_response_alg,
+            id_token_encrypted_response_alg,
+            id_token_encrypted_response_enc,
+            userinfo_encrypted_response_alg,
+            userinfo_encrypted_response_enc,
+            tls_client_auth_subject_dn: self.tls_client_auth_subject_dn,
+            encrypted_registration_access_token: self.encrypted_registration_access_token,
         })
     }
 }
@@ -278,6 +449,7 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
                      , grant_type_refresh_token
                      , grant_type_client_credentials
                      , grant_type_device_code
+                     , grant_type_token_exchange
                      , client_name
                      , logo_uri
                      , client_uri
@@ -290,6 +462,23 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
                      , token_endpoint_auth_method
                      , token_endpoint_auth_signing_alg
                      , initiate_login_uri
+                     , post_logout_redirect_uris
+                     , backchannel_logout_uri
+                     , backchannel_logout_session_required
+                     , require_pushed_authorization_requests
+                     , jwt_bearer_subject
+                     , request_object_signing_alg
+                     , request_uris
+                     , subject_type
+                     , sector_identifier_uri
+                     , access_token_format
+                     , introspection_signed_response_alg
+                     , id_token_encrypted_response_alg
+                     , id_token_encrypted_response_enc
+                     , userinfo_encrypted_response_alg
+                     , userinfo_encrypted_response_enc
+                     , tls_client_auth_subject_dn
+                     , encrypted_registration_access_token
                 FROM oauth2_clients c
 
                 WHERE oauth2_client_id = $1
@@ -329,6 +518,7 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
                     , grant_type_refresh_token
                     , grant_type_client_credentials
                     , grant_type_device_code
+                    , grant_type_token_exchange
                     , client_name
                     , logo_uri
                     , client_uri
@@ -341,6 +531,23 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
                     , token_endpoint_auth_method
                     , token_endpoint_auth_signing_alg
                     , initiate_login_uri
+                    , post_logout_redirect_uris
+                    , backchannel_logout_uri
+                    , backchannel_logout_session_required
+                    , require_pushed_authorization_requests
+                    , jwt_bearer_subject
+                    , request_object_signing_alg
+                    , request_uris
+                    , subject_type
+                    , sector_identifier_uri
+                    , access_token_format
+                    , introspection_signed_response_alg
+                    , id_token_encrypted_response_alg
+                    , id_token_encrypted_response_enc
+                    , userinfo_encrypted_response_alg
+                    , userinfo_encrypted_response_enc
+                    , tls_client_auth_subject_dn
+                    , encrypted_registration_access_token
                 FROM oauth2_clients
                 WHERE metadata_digest = $1
             "#,
@@ -380,6 +587,7 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
                      , grant_type_refresh_token
                      , grant_type_client_credentials
                      , grant_type_device_code
+                     , grant_type_token_exchange
                      , client_name
                      , logo_uri
                      , client_uri
@@ -392,6 +600,23 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
                      , token_endpoint_auth_method
                      , token_endpoint_auth_signing_alg
                      , initiate_login_uri
+                     , post_logout_redirect_uris
+                     , backchannel_logout_uri
+                     , backchannel_logout_session_required
+                     , require_pushed_authorization_requests
+                     , jwt_bearer_subject
+                     , request_object_signing_alg
+                     , request_uris
+                     , subject_type
+                     , sector_identifier_uri
+                     , access_token_format
+                     , introspection_signed_response_alg
+                     , id_token_encrypted_response_alg
+                     , id_token_encrypted_response_enc
+                     , userinfo_encrypted_response_alg
+                     , userinfo_encrypted_response_enc
+                     , tls_client_auth_subject_dn
+                     , encrypted_registration_access_token
                 FROM oauth2_clients c
 
                 WHERE oauth2_client_id = ANY($1::uuid[])
@@ -443,6 +668,20 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
         token_endpoint_auth_method: Option<OAuthClientAuthenticationMethod>,
         token_endpoint_auth_signing_alg: Option<JsonWebSignatureAlg>,
         initiate_login_uri: Option<Url>,
+        post_logout_redirect_uris: Vec<Url>,
+        backchannel_logout_uri: Option<Url>,
+        backchannel_logout_session_required: bool,
+        require_pushed_authorization_requests: bool,
+        request_object_signing_alg: Option<JsonWebSignatureAlg>,
+        request_uris: Vec<Url>,
+        subject_type: Option<SubjectType>,
+        sector_identifier_uri: Option<Url>,
+        introspection_signed_response_alg: Option<JsonWebSignatureAlg>,
+        id_token_encrypted_response_alg: Option<JsonWebEncryptionAlg>,
+        id_token_encrypted_response_enc: Option<JsonWebEncryptionEnc>,
+        userinfo_encrypted_response_alg: Option<JsonWebEncryptionAlg>,
+        userinfo_encrypted_response_enc: Option<JsonWebEncryptionEnc>,
+        encrypted_registration_access_token: Option<String>,
     ) -> Result<Client, Self::Error> {
         let now = clock.now();
         let id = Ulid::from_datetime_with_source(now.into(), rng);
@@ -455,6 +694,11 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
             .map_err(DatabaseError::to_invalid_operation)?;
 
         let redirect_uris_array = redirect_uris.iter().map(Url::to_string).collect::<Vec<_>>();
+        let post_logout_redirect_uris_array = post_logout_redirect_uris
+            .iter()
+            .map(Url::to_string)
+            .collect::<Vec<_>>();
+        let request_uris_array = request_uris.iter().map(Url::to_string).collect::<Vec<_>>();
 
         sqlx::query!(
             r#"
@@ -480,11 +724,27 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
                     , token_endpoint_auth_method
                     , token_endpoint_auth_signing_alg
                     , initiate_login_uri
+                    , post_logout_redirect_uris
+                    , backchannel_logout_uri
+                    , backchannel_logout_session_required
+                    , require_pushed_authorization_requests
+                    , grant_type_token_exchange
+                    , request_object_signing_alg
+                    , request_uris
+                    , subject_type
+                    , sector_identifier_uri
+                    , introspection_signed_response_alg
+                    , id_token_encrypted_response_alg
+                    , id_token_encrypted_response_enc
+                    , userinfo_encrypted_response_alg
+                    , userinfo_encrypted_response_enc
+                    , encrypted_registration_access_token
                     , is_static
                     )
                 VALUES
                     ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13,
-                    $14, $15, $16, $17, $18, $19, $20, $21, FALSE)
+                    $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24, $25, $26,
+                    $27, $28, $29, $30, $31, $32, $33, $34, $35, $36, FALSE)
             "#,
             Uuid::from(id),
             metadata_digest,
@@ -513,6 +773,31 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
                 .as_ref()
                 .map(ToString::to_string),
             initiate_login_uri.as_ref().map(Url::as_str),
+            &post_logout_redirect_uris_array,
+            backchannel_logout_uri.as_ref().map(Url::as_str),
+            backchannel_logout_session_required,
+            require_pushed_authorization_requests,
+            grant_types.contains(&GrantType::TokenExchange),
+            request_object_signing_alg.as_ref().map(ToString::to_string),
+            &request_uris_array,
+            subject_type.as_ref().map(ToString::to_string),
+            sector_identifier_uri.as_ref().map(Url::to_string),
+            introspection_signed_response_alg
+                .as_ref()
+                .map(ToString::to_string),
+            id_token_encrypted_response_alg
+                .as_ref()
+                .map(ToString::to_string),
+            id_token_encrypted_response_enc
+                .as_ref()
+                .map(ToString::to_string),
+            userinfo_encrypted_response_alg
+                .as_ref()
+                .map(ToString::to_string),
+            userinfo_encrypted_response_enc
+                .as_ref()
+                .map(ToString::to_string),
+            encrypted_registration_access_token,
         )
         .traced()
         .execute(&mut *self.conn)
@@ -544,6 +829,218 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
             token_endpoint_auth_method,
             token_endpoint_auth_signing_alg,
             initiate_login_uri,
+            post_logout_redirect_uris,
+            backchannel_logout_uri,
+            backchannel_logout_session_required,
+            require_pushed_authorization_requests,
+            jwt_bearer_subject: None,
+            request_object_signing_alg,
+            request_uris,
+            subject_type,
+            sector_identifier_uri,
+            access_token_format: AccessTokenFormat::Opaque,
+            introspection_signed_response_alg,
+            id_token_encrypted_response_alg,
+            id_token_encrypted_response_enc,
+            userinfo_encrypted_response_alg,
+            userinfo_encrypted_response_enc,
+            tls_client_auth_subject_dn: None,
+            encrypted_registration_access_token,
+        })
+    }
+
+    #[tracing::instrument(
+        name = "db.oauth2_client.update",
+        skip_all,
+        fields(
+            db.query.text,
+            %client.id,
+            client.name = client_name
+        ),
+        err,
+    )]
+    #[allow(clippy::too_many_lines)]
+    async fn update(
+        &mut self,
+        client: Client,
+        redirect_uris: Vec<Url>,
+        encrypted_client_secret: Option<String>,
+        application_type: Option<ApplicationType>,
+        grant_types: Vec<GrantType>,
+        client_name: Option<String>,
+        logo_uri: Option<Url>,
+        client_uri: Option<Url>,
+        policy_uri: Option<Url>,
+        tos_uri: Option<Url>,
+        jwks_uri: Option<Url>,
+        jwks: Option<PublicJsonWebKeySet>,
+        id_token_signed_response_alg: Option<JsonWebSignatureAlg>,
+        userinfo_signed_response_alg: Option<JsonWebSignatureAlg>,
+        token_endpoint_auth_method: Option<OAuthClientAuthenticationMethod>,
+        token_endpoint_auth_signing_alg: Option<JsonWebSignatureAlg>,
+        initiate_login_uri: Option<Url>,
+        post_logout_redirect_uris: Vec<Url>,
+        backchannel_logout_uri: Option<Url>,
+        backchannel_logout_session_required: bool,
+        require_pushed_authorization_requests: bool,
+        request_object_signing_alg: Option<JsonWebSignatureAlg>,
+        request_uris: Vec<Url>,
+        subject_type: Option<SubjectType>,
+        sector_identifier_uri: Option<Url>,
+        introspection_signed_response_alg: Option<JsonWebSignatureAlg>,
+        id_token_encrypted_response_alg: Option<JsonWebEncryptionAlg>,
+        id_token_encrypted_response_enc: Option<JsonWebEncryptionEnc>,
+        userinfo_encrypted_response_alg: Option<JsonWebEncryptionAlg>,
+        userinfo_encrypted_response_enc: Option<JsonWebEncryptionEnc>,
+    ) -> Result<Client, Self::Error> {
+        let jwks_json = jwks
+            .as_ref()
+            .map(serde_json::to_value)
+            .transpose()
+            .map_err(DatabaseError::to_invalid_operation)?;
+
+        let redirect_uris_array = redirect_uris.iter().map(Url::to_string).collect::<Vec<_>>();
+        let post_logout_redirect_uris_array = post_logout_redirect_uris
+            .iter()
+            .map(Url::to_string)
+            .collect::<Vec<_>>();
+        let request_uris_array = request_uris.iter().map(Url::to_string).collect::<Vec<_>>();
+
+        let res = sqlx::query!(
+            r#"
+                UPDATE oauth2_clients
+                SET metadata_digest = NULL
+                  , encrypted_client_secret = $2
+                  , application_type = $3
+                  , redirect_uris = $4
+                  , grant_type_authorization_code = $5
+                  , grant_type_refresh_token = $6
+                  , grant_type_client_credentials = $7
+                  , grant_type_device_code = $8
+                  , client_name = $9
+                  , logo_uri = $10
+                  , client_uri = $11
+                  , policy_uri = $12
+                  , tos_uri = $13
+                  , jwks_uri = $14
+                  , jwks = $15
+                  , id_token_signed_response_alg = $16
+                  , userinfo_signed_response_alg = $17
+                  , token_endpoint_auth_method = $18
+                  , token_endpoint_auth_signing_alg = $19
+                  , initiate_login_uri = $20
+                  , post_logout_redirect_uris = $21
+                  , backchannel_logout_uri = $22
+                  , backchannel_logout_session_required = $23
+                  , require_pushed_authorization_requests = $24
+                  , grant_type_token_exchange = $25
+                  , request_object_signing_alg = $26
+                  , request_uris = $27
+                  , subject_type = $28
+                  , sector_identifier_uri = $29
+                  , introspection_signed_response_alg = $30
+                  , id_token_encrypted_response_alg = $31
+                  , id_token_encrypted_response_enc = $32
+                  , userinfo_encrypted_response_alg = $33
+                  , userinfo_encrypted_response_enc = $34
+                WHERE oauth2_client_id = $1
+                  AND NOT is_static
+            "#,
+            Uuid::from(client.id),
+            encrypted_client_secret,
+            application_type.as_ref().map(ToString::to_string),
+            &redirect_uris_array,
+            grant_types.contains(&GrantType::AuthorizationCode),
+            grant_types.contains(&GrantType::RefreshToken),
+            grant_types.contains(&GrantType::ClientCredentials),
+            grant_types.contains(&GrantType::DeviceCode),
+            client_name,
+            logo_uri.as_ref().map(Url::as_str),
+            client_uri.as_ref().map(Url::as_str),
+            policy_uri.as_ref().map(Url::as_str),
+            tos_uri.as_ref().map(Url::as_str),
+            jwks_uri.as_ref().map(Url::as_str),
+            jwks_json,
+            id_token_signed_response_alg
+                .as_ref()
+                .map(ToString::to_string),
+            userinfo_signed_response_alg
+                .as_ref()
+                .map(ToString::to_string),
+            token_endpoint_auth_method.as_ref().map(ToString::to_string),
+            token_endpoint_auth_signing_alg
+                .as_ref()
+                .map(ToString::to_string),
+            initiate_login_uri.as_ref().map(Url::as_str),
+            &post_logout_redirect_uris_array,
+            backchannel_logout_uri.as_ref().map(Url::as_str),
+            backchannel_logout_session_required,
+            require_pushed_authorization_requests,
+            grant_types.contains(&GrantType::TokenExchange),
+            request_object_signing_alg.as_ref().map(ToString::to_string),
+            &request_uris_array,
+            subject_type.as_ref().map(ToString::to_string),
+            sector_identifier_uri.as_ref().map(Url::to_string),
+            introspection_signed_response_alg
+                .as_ref()
+                .map(ToString::to_string),
+            id_token_encrypted_response_alg
+                .as_ref()
+                .map(ToString::to_string),
+            id_token_encrypted_response_enc
+                .as_ref()
+                .map(ToString::to_string),
+            userinfo_encrypted_response_alg
+                .as_ref()
+                .map(ToString::to_string),
+            userinfo_encrypted_response_enc
+                .as_ref()
+                .map(ToString::to_string),
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        DatabaseError::ensure_affected_rows(&res, 1)?;
+
+        let jwks = match (jwks, jwks_uri) {
+            (None, None) => None,
+            (Some(jwks), None) => Some(JwksOrJwksUri::Jwks(jwks)),
+            (None, Some(jwks_uri)) => Some(JwksOrJwksUri::JwksUri(jwks_uri)),
+            _ => return Err(DatabaseError::invalid_operation()),
+        };
+
+        Ok(Client {
+            metadata_digest: None,
+            encrypted_client_secret,
+            application_type,
+            redirect_uris,
+            grant_types,
+            client_name,
+            logo_uri,
+            client_uri,
+            policy_uri,
+            tos_uri,
+            jwks,
+            id_token_signed_response_alg,
+            userinfo_signed_response_alg,
+            token_endpoint_auth_method,
+            token_endpoint_auth_signing_alg,
+            initiate_login_uri,
+            post_logout_redirect_uris,
+            backchannel_logout_uri,
+            backchannel_logout_session_required,
+            require_pushed_authorization_requests,
+            request_object_signing_alg,
+            request_uris,
+            subject_type,
+            sector_identifier_uri,
+            introspection_signed_response_alg,
+            id_token_encrypted_response_alg,
+            id_token_encrypted_response_enc,
+            userinfo_encrypted_response_alg,
+            userinfo_encrypted_response_enc,
+            ..client
         })
     }
 
@@ -564,6 +1061,13 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
         jwks: Option<PublicJsonWebKeySet>,
         jwks_uri: Option<Url>,
         redirect_uris: Vec<Url>,
+        post_logout_redirect_uris: Vec<Url>,
+        backchannel_logout_uri: Option<Url>,
+        backchannel_logout_session_required: bool,
+        require_pushed_authorization_requests: bool,
+        jwt_bearer_subject: Option<String>,
+        access_token_format: AccessTokenFormat,
+        tls_client_auth_subject_dn: Option<String>,
     ) -> Result<Client, Self::Error> {
         let jwks_json = jwks
             .as_ref()
@@ -573,6 +1077,10 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
 
         let client_auth_method = client_auth_method.to_string();
         let redirect_uris_array = redirect_uris.iter().map(Url::to_string).collect::<Vec<_>>();
+        let post_logout_redirect_uris_array = post_logout_redirect_uris
+            .iter()
+            .map(Url::to_string)
+            .collect::<Vec<_>>();
 
         sqlx::query!(
             r#"
@@ -587,10 +1095,18 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
                     , token_endpoint_auth_method
                     , jwks
                     , jwks_uri
+                    , post_logout_redirect_uris
+                    , backchannel_logout_uri
+                    , backchannel_logout_session_required
+                    , require_pushed_authorization_requests
+                    , grant_type_token_exchange
+                    , jwt_bearer_subject
+                    , access_token_format
+                    , tls_client_auth_subject_dn
                     , is_static
                     )
                 VALUES
-                    ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, TRUE)
+                    ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, TRUE)
                 ON CONFLICT (oauth2_client_id)
                 DO
                     UPDATE SET encrypted_client_secret = EXCLUDED.encrypted_client_secret
@@ -602,6 +1118,14 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
                              , token_endpoint_auth_method = EXCLUDED.token_endpoint_auth_method
                              , jwks = EXCLUDED.jwks
                              , jwks_uri = EXCLUDED.jwks_uri
+                             , post_logout_redirect_uris = EXCLUDED.post_logout_redirect_uris
+                             , backchannel_logout_uri = EXCLUDED.backchannel_logout_uri
+                             , backchannel_logout_session_required = EXCLUDED.backchannel_logout_session_required
+                             , require_pushed_authorization_requests = EXCLUDED.require_pushed_authorization_requests
+                             , grant_type_token_exchange = EXCLUDED.grant_type_token_exchange
+                             , jwt_bearer_subject = EXCLUDED.jwt_bearer_subject
+                             , access_token_format = EXCLUDED.access_token_format
+                             , tls_client_auth_subject_dn = EXCLUDED.tls_client_auth_subject_dn
                              , is_static = TRUE
             "#,
             Uuid::from(client_id),
@@ -614,6 +1138,14 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
             client_auth_method,
             jwks_json,
             jwks_uri.as_ref().map(Url::as_str),
+            &post_logout_redirect_uris_array,
+            backchannel_logout_uri.as_ref().map(Url::as_str),
+            backchannel_logout_session_required,
+            require_pushed_authorization_requests,
+            true,
+            jwt_bearer_subject.as_deref(),
+            access_token_format.as_str(),
+            tls_client_auth_subject_dn.as_deref(),
         )
         .traced()
         .execute(&mut *self.conn)
@@ -626,6 +1158,16 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
             _ => return Err(DatabaseError::invalid_operation()),
         };
 
+        let mut grant_types = vec![
+            GrantType::AuthorizationCode,
+            GrantType::RefreshToken,
+            GrantType::ClientCredentials,
+            GrantType::TokenExchange,
+        ];
+        if jwt_bearer_subject.is_some() {
+            grant_types.push(GrantType::JwtBearer);
+        }
+
         Ok(Client {
             id: client_id,
             client_id: client_id.to_string(),
@@ -633,11 +1175,7 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
             encrypted_client_secret,
             application_type: None,
             redirect_uris,
-            grant_types: vec![
-                GrantType::AuthorizationCode,
-                GrantType::RefreshToken,
-                GrantType::ClientCredentials,
-            ],
+            grant_types,
             client_name: None,
             logo_uri: None,
             client_uri: None,
@@ -649,6 +1187,23 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
             token_endpoint_auth_method: None,
             token_endpoint_auth_signing_alg: None,
             initiate_login_uri: None,
+            post_logout_redirect_uris,
+            backchannel_logout_uri,
+            backchannel_logout_session_required,
+            require_pushed_authorization_requests,
+            jwt_bearer_subject,
+            request_object_signing_alg: None,
+            request_uris: Vec::new(),
+            subject_type: None,
+            sector_identifier_uri: None,
+            access_token_format,
+            introspection_signed_response_alg: None,
+            id_token_encrypted_response_alg: None,
+            id_token_encrypted_response_enc: None,
+            userinfo_encrypted_response_alg: None,
+            userinfo_encrypted_response_enc: None,
+            tls_client_auth_subject_dn,
+            encrypted_registration_access_token: None,
         })
     }
 
@@ -673,6 +1228,7 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
                      , grant_type_refresh_token
                      , grant_type_client_credentials
                      , grant_type_device_code
+                     , grant_type_token_exchange
                      , client_name
                      , logo_uri
                      , client_uri
@@ -685,6 +1241,23 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
                      , token_endpoint_auth_method
                      , token_endpoint_auth_signing_alg
                      , initiate_login_uri
+                     , post_logout_redirect_uris
+                     , backchannel_logout_uri
+                     , backchannel_logout_session_required
+                     , require_pushed_authorization_requests
+                     , jwt_bearer_subject
+                     , request_object_signing_alg
+                     , request_uris
+                     , subject_type
+                     , sector_identifier_uri
+                     , access_token_format
+                     , introspection_signed_response_alg
+                     , id_token_encrypted_response_alg
+                     , id_token_encrypted_response_enc
+                     , userinfo_encrypted_response_alg
+                     , userinfo_encrypted_response_enc
+                     , tls_client_auth_subject_dn
+                     , encrypted_registration_access_token
                 FROM oauth2_clients c
                 WHERE is_static = TRUE
             "#,
@@ -839,6 +1412,26 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
             .await?;
         }
 
+        // Delete the device code grants
+        {
+            let span = info_span!(
+                "db.oauth2_client.delete_by_id.device_code_grants",
+                { DB_QUERY_TEXT } = tracing::field::Empty,
+            );
+
+            sqlx::query!(
+                r#"
+                    DELETE FROM oauth2_device_code_grant
+                    WHERE oauth2_client_id = $1
+                "#,
+                Uuid::from(id),
+            )
+            .record(&span)
+            .execute(&mut *self.conn)
+            .instrument(span)
+            .await?;
+        }
+
         // Delete the OAuth 2 sessions related data
         {
             let span = info_span!(
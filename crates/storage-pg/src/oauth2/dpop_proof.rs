@@ -0,0 +1,92 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use mas_storage::{Clock, oauth2::OAuth2DpopProofRepository};
+use sqlx::PgConnection;
+
+use crate::{DatabaseError, tracing::ExecuteExt};
+
+/// An implementation of [`OAuth2DpopProofRepository`] for a PostgreSQL
+/// connection
+pub struct PgOAuth2DpopProofRepository<'c> {
+    conn: &'c mut PgConnection,
+}
+
+impl<'c> PgOAuth2DpopProofRepository<'c> {
+    /// Create a new [`PgOAuth2DpopProofRepository`] from an active PostgreSQL
+    /// connection
+    pub fn new(conn: &'c mut PgConnection) -> Self {
+        Self { conn }
+    }
+}
+
+#[async_trait]
+impl OAuth2DpopProofRepository for PgOAuth2DpopProofRepository<'_> {
+    type Error = DatabaseError;
+
+    #[tracing::instrument(
+        name = "db.oauth2_dpop_proof.record",
+        skip_all,
+        fields(
+            db.query.text,
+            dpop_proof.jkt = jkt,
+            dpop_proof.jti = jti,
+        ),
+        err,
+    )]
+    async fn record(
+        &mut self,
+        clock: &dyn Clock,
+        jkt: &str,
+        jti: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<bool, Self::Error> {
+        let created_at = clock.now();
+
+        let res = sqlx::query!(
+            r#"
+                INSERT INTO oauth2_dpop_proofs
+                    (jkt, jti, created_at, expires_at)
+                VALUES
+                    ($1, $2, $3, $4)
+                ON CONFLICT (jkt, jti) DO NOTHING
+            "#,
+            jkt,
+            jti,
+            created_at,
+            expires_at,
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(res.rows_affected() == 1)
+    }
+
+    #[tracing::instrument(
+        name = "db.oauth2_dpop_proof.cleanup_expired",
+        skip_all,
+        fields(
+            db.query.text,
+        ),
+        err,
+    )]
+    async fn cleanup_expired(&mut self, clock: &dyn Clock) -> Result<usize, Self::Error> {
+        let res = sqlx::query!(
+            r#"
+                DELETE FROM oauth2_dpop_proofs
+                WHERE expires_at < $1
+            "#,
+            clock.now(),
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(res.rows_affected().try_into().unwrap_or(usize::MAX))
+    }
+}
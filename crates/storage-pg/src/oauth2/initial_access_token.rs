@@ -0,0 +1,459 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use mas_data_model::InitialAccessToken;
+use mas_storage::{
+    Clock, Page, Pagination,
+    oauth2::{OAuth2InitialAccessTokenFilter, OAuth2InitialAccessTokenRepository},
+};
+use rand::RngCore;
+use sea_query::{Expr, PostgresQueryBuilder, Query, enum_def};
+use sea_query_binder::SqlxBinder;
+use sqlx::PgConnection;
+use ulid::Ulid;
+use uuid::Uuid;
+
+use crate::{
+    DatabaseError, DatabaseInconsistencyError,
+    filter::{Filter, StatementExt},
+    iden::OAuth2InitialAccessTokens,
+    pagination::QueryBuilderExt,
+    tracing::ExecuteExt,
+};
+
+/// An implementation of [`OAuth2InitialAccessTokenRepository`] for a
+/// PostgreSQL connection
+pub struct PgOAuth2InitialAccessTokenRepository<'c> {
+    conn: &'c mut PgConnection,
+}
+
+impl<'c> PgOAuth2InitialAccessTokenRepository<'c> {
+    /// Create a new [`PgOAuth2InitialAccessTokenRepository`] from an active
+    /// PostgreSQL connection
+    pub fn new(conn: &'c mut PgConnection) -> Self {
+        Self { conn }
+    }
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+#[enum_def]
+struct OAuth2InitialAccessTokenLookup {
+    oauth2_initial_access_token_id: Uuid,
+    token: String,
+    max_uses: Option<i32>,
+    use_count: i32,
+    created_at: DateTime<Utc>,
+    expires_at: Option<DateTime<Utc>>,
+    last_used_at: Option<DateTime<Utc>>,
+    revoked_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<OAuth2InitialAccessTokenLookup> for InitialAccessToken {
+    type Error = DatabaseInconsistencyError;
+
+    fn try_from(value: OAuth2InitialAccessTokenLookup) -> Result<Self, Self::Error> {
+        let id = Ulid::from(value.oauth2_initial_access_token_id);
+
+        let max_uses = value.max_uses.map(u32::try_from).transpose().map_err(|e| {
+            DatabaseInconsistencyError::on("oauth2_initial_access_tokens")
+                .column("max_uses")
+                .row(id)
+                .source(e)
+        })?;
+
+        let use_count = u32::try_from(value.use_count).map_err(|e| {
+            DatabaseInconsistencyError::on("oauth2_initial_access_tokens")
+                .column("use_count")
+                .row(id)
+                .source(e)
+        })?;
+
+        Ok(InitialAccessToken {
+            id,
+            token: value.token,
+            max_uses,
+            use_count,
+            created_at: value.created_at,
+            expires_at: value.expires_at,
+            last_used_at: value.last_used_at,
+            revoked_at: value.revoked_at,
+        })
+    }
+}
+
+impl Filter for OAuth2InitialAccessTokenFilter {
+    fn generate_condition(&self, _has_joins: bool) -> impl sea_query::IntoCondition {
+        sea_query::Condition::all().add_option(self.revoked().map(|revoked| {
+            if revoked {
+                Expr::col((
+                    OAuth2InitialAccessTokens::Table,
+                    OAuth2InitialAccessTokens::RevokedAt,
+                ))
+                .is_not_null()
+            } else {
+                Expr::col((
+                    OAuth2InitialAccessTokens::Table,
+                    OAuth2InitialAccessTokens::RevokedAt,
+                ))
+                .is_null()
+            }
+        }))
+    }
+}
+
+#[async_trait]
+impl OAuth2InitialAccessTokenRepository for PgOAuth2InitialAccessTokenRepository<'_> {
+    type Error = DatabaseError;
+
+    #[tracing::instrument(
+        name = "db.oauth2_initial_access_token.lookup",
+        skip_all,
+        fields(
+            db.query.text,
+            oauth2_initial_access_token.id = %id,
+        ),
+        err,
+    )]
+    async fn lookup(&mut self, id: Ulid) -> Result<Option<InitialAccessToken>, Self::Error> {
+        let res = sqlx::query_as!(
+            OAuth2InitialAccessTokenLookup,
+            r#"
+                SELECT oauth2_initial_access_token_id
+                     , token
+                     , max_uses
+                     , use_count
+                     , created_at
+                     , expires_at
+                     , last_used_at
+                     , revoked_at
+                FROM oauth2_initial_access_tokens
+
+                WHERE oauth2_initial_access_token_id = $1
+            "#,
+            Uuid::from(id),
+        )
+        .traced()
+        .fetch_optional(&mut *self.conn)
+        .await?;
+
+        let Some(res) = res else { return Ok(None) };
+
+        Ok(Some(res.try_into()?))
+    }
+
+    #[tracing::instrument(
+        name = "db.oauth2_initial_access_token.find_by_token",
+        skip_all,
+        fields(
+            db.query.text,
+        ),
+        err,
+    )]
+    async fn find_by_token(
+        &mut self,
+        token: &str,
+    ) -> Result<Option<InitialAccessToken>, Self::Error> {
+        let res = sqlx::query_as!(
+            OAuth2InitialAccessTokenLookup,
+            r#"
+                SELECT oauth2_initial_access_token_id
+                     , token
+                     , max_uses
+                     , use_count
+                     , created_at
+                     , expires_at
+                     , last_used_at
+                     , revoked_at
+                FROM oauth2_initial_access_tokens
+
+                WHERE token = $1
+            "#,
+            token,
+        )
+        .traced()
+        .fetch_optional(&mut *self.conn)
+        .await?;
+
+        let Some(res) = res else { return Ok(None) };
+
+        Ok(Some(res.try_into()?))
+    }
+
+    #[tracing::instrument(
+        name = "db.oauth2_initial_access_token.add",
+        skip_all,
+        fields(
+            db.query.text,
+            oauth2_initial_access_token.id,
+        ),
+        err,
+    )]
+    async fn add(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        token: String,
+        expires_after: Option<Duration>,
+        max_uses: Option<u32>,
+    ) -> Result<InitialAccessToken, Self::Error> {
+        let created_at = clock.now();
+        let id = Ulid::from_datetime_with_source(created_at.into(), rng);
+        tracing::Span::current().record(
+            "oauth2_initial_access_token.id",
+            tracing::field::display(id),
+        );
+
+        let expires_at = expires_after.map(|expires_after| created_at + expires_after);
+        let max_uses_i32 = max_uses
+            .map(i32::try_from)
+            .transpose()
+            .map_err(DatabaseError::to_invalid_operation)?;
+
+        sqlx::query!(
+            r#"
+                INSERT INTO oauth2_initial_access_tokens
+                    ( oauth2_initial_access_token_id
+                    , token
+                    , max_uses
+                    , created_at
+                    , expires_at
+                    )
+                VALUES ($1, $2, $3, $4, $5)
+            "#,
+            Uuid::from(id),
+            &token,
+            max_uses_i32,
+            created_at,
+            expires_at,
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(InitialAccessToken {
+            id,
+            token,
+            max_uses,
+            use_count: 0,
+            created_at,
+            expires_at,
+            last_used_at: None,
+            revoked_at: None,
+        })
+    }
+
+    #[tracing::instrument(
+        name = "db.oauth2_initial_access_token.record_use",
+        skip_all,
+        fields(
+            db.query.text,
+            oauth2_initial_access_token.id = %initial_access_token.id,
+        ),
+        err,
+    )]
+    async fn record_use(
+        &mut self,
+        clock: &dyn Clock,
+        mut initial_access_token: InitialAccessToken,
+    ) -> Result<Option<InitialAccessToken>, Self::Error> {
+        let last_used_at = clock.now();
+
+        // Bump the counter in the database, so that concurrent registrations
+        // can't use the token more times than allowed
+        let use_count = sqlx::query_scalar!(
+            r#"
+                UPDATE oauth2_initial_access_tokens
+                SET use_count = use_count + 1
+                  , last_used_at = $1
+                WHERE oauth2_initial_access_token_id = $2
+                  AND revoked_at IS NULL
+                  AND (max_uses IS NULL OR use_count < max_uses)
+                RETURNING use_count
+            "#,
+            last_used_at,
+            Uuid::from(initial_access_token.id),
+        )
+        .traced()
+        .fetch_optional(&mut *self.conn)
+        .await?;
+
+        let Some(use_count) = use_count else {
+            return Ok(None);
+        };
+
+        initial_access_token.use_count =
+            u32::try_from(use_count).map_err(DatabaseError::to_invalid_operation)?;
+        initial_access_token.last_used_at = Some(last_used_at);
+
+        Ok(Some(initial_access_token))
+    }
+
+    #[tracing::instrument(
+        name = "db.oauth2_initial_access_token.revoke",
+        skip_all,
+        fields(
+            db.query.text,
+            oauth2_initial_access_token.id = %initial_access_token.id,
+        ),
+        err,
+    )]
+    async fn revoke(
+        &mut self,
+        clock: &dyn Clock,
+        initial_access_token: InitialAccessToken,
+    ) -> Result<InitialAccessToken, Self::Error> {
+        let revoked_at = clock.now();
+        let initial_access_token = initial_access_token
+            .revoke(revoked_at)
+            .map_err(DatabaseError::to_invalid_operation)?;
+
+        let res = sqlx::query!(
+            r#"
+                UPDATE oauth2_initial_access_tokens
+                SET revoked_at = $1
+                WHERE oauth2_initial_access_token_id = $2
+                  AND revoked_at IS NULL
+            "#,
+            revoked_at,
+            Uuid::from(initial_access_token.id),
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        DatabaseError::ensure_affected_rows(&res, 1)?;
+
+        Ok(initial_access_token)
+    }
+
+    #[tracing::instrument(
+        name = "db.oauth2_initial_access_token.list",
+        skip_all,
+        fields(
+            db.query.text,
+        ),
+        err,
+    )]
+    async fn list(
+        &mut self,
+        filter: OAuth2InitialAccessTokenFilter,
+        pagination: Pagination,
+    ) -> Result<Page<InitialAccessToken>, Self::Error> {
+        let (sql, arguments) = Query::select()
+            .expr_as(
+                Expr::col((
+                    OAuth2InitialAccessTokens::Table,
+                    OAuth2InitialAccessTokens::OAuth2InitialAccessTokenId,
+                )),
+                OAuth2InitialAccessTokenLookupIden::Oauth2InitialAccessTokenId,
+            )
+            .expr_as(
+                Expr::col((
+                    OAuth2InitialAccessTokens::Table,
+                    OAuth2InitialAccessTokens::Token,
+                )),
+                OAuth2InitialAccessTokenLookupIden::Token,
+            )
+            .expr_as(
+                Expr::col((
+                    OAuth2InitialAccessTokens::Table,
+                    OAuth2InitialAccessTokens::MaxUses,
+                )),
+                OAuth2InitialAccessTokenLookupIden::MaxUses,
+            )
+            .expr_as(
+                Expr::col((
+                    OAuth2InitialAccessTokens::Table,
+                    OAuth2InitialAccessTokens::UseCount,
+                )),
+                OAuth2InitialAccessTokenLookupIden::UseCount,
+            )
+            .expr_as(
+                Expr::col((
+                    OAuth2InitialAccessTokens::Table,
+                    OAuth2InitialAccessTokens::CreatedAt,
+                )),
+                OAuth2InitialAccessTokenLookupIden::CreatedAt,
+            )
+            .expr_as(
+                Expr::col((
+                    OAuth2InitialAccessTokens::Table,
+                    OAuth2InitialAccessTokens::ExpiresAt,
+                )),
+                OAuth2InitialAccessTokenLookupIden::ExpiresAt,
+            )
+            .expr_as(
+                Expr::col((
+                    OAuth2InitialAccessTokens::Table,
+                    OAuth2InitialAccessTokens::LastUsedAt,
+                )),
+                OAuth2InitialAccessTokenLookupIden::LastUsedAt,
+            )
+            .expr_as(
+                Expr::col((
+                    OAuth2InitialAccessTokens::Table,
+                    OAuth2InitialAccessTokens::RevokedAt,
+                )),
+                OAuth2InitialAccessTokenLookupIden::RevokedAt,
+            )
+            .from(OAuth2InitialAccessTokens::Table)
+            .apply_filter(filter)
+            .generate_pagination(
+                (
+                    OAuth2InitialAccessTokens::Table,
+                    OAuth2InitialAccessTokens::OAuth2InitialAccessTokenId,
+                ),
+                pagination,
+            )
+            .build_sqlx(PostgresQueryBuilder);
+
+        let edges: Vec<OAuth2InitialAccessTokenLookup> = sqlx::query_as_with(&sql, arguments)
+            .traced()
+            .fetch_all(&mut *self.conn)
+            .await?;
+
+        let page = pagination
+            .process(edges)
+            .try_map(InitialAccessToken::try_from)?;
+
+        Ok(page)
+    }
+
+    #[tracing::instrument(
+        name = "db.oauth2_initial_access_token.count",
+        skip_all,
+        fields(
+            db.query.text,
+        ),
+        err,
+    )]
+    async fn count(
+        &mut self,
+        filter: OAuth2InitialAccessTokenFilter,
+    ) -> Result<usize, Self::Error> {
+        let (sql, arguments) = Query::select()
+            .expr(
+                Expr::col((
+                    OAuth2InitialAccessTokens::Table,
+                    OAuth2InitialAccessTokens::OAuth2InitialAccessTokenId,
+                ))
+                .count(),
+            )
+            .from(OAuth2InitialAccessTokens::Table)
+            .apply_filter(filter)
+            .build_sqlx(PostgresQueryBuilder);
+
+        let count: i64 = sqlx::query_scalar_with(&sql, arguments)
+            .traced()
+            .fetch_one(&mut *self.conn)
+            .await?;
+
+        count
+            .try_into()
+            .map_err(DatabaseError::to_invalid_operation)
+    }
+}
@@ -11,27 +11,40 @@ mod access_token;
 mod authorization_grant;
 mod client;
 mod device_code_grant;
+mod dpop_proof;
+mod initial_access_token;
+mod jwt_bearer_assertion;
+mod pairwise_subject;
+mod pushed_authorization_request;
 mod refresh_token;
 mod session;
 
 pub use self::{
     access_token::PgOAuth2AccessTokenRepository,
     authorization_grant::PgOAuth2AuthorizationGrantRepository, client::PgOAuth2ClientRepository,
-    device_code_grant::PgOAuth2DeviceCodeGrantRepository,
+    device_code_grant::PgOAuth2DeviceCodeGrantRepository, dpop_proof::PgOAuth2DpopProofRepository,
+    initial_access_token::PgOAuth2InitialAccessTokenRepository,
+    jwt_bearer_assertion::PgOAuth2JwtBearerAssertionRepository,
+    pairwise_subject::PgOAuth2PairwiseSubjectRepository,
+    pushed_authorization_request::PgOAuth2PushedAuthorizationRequestRepository,
     refresh_token::PgOAuth2RefreshTokenRepository, session::PgOAuth2SessionRepository,
 };
 
 #[cfg(test)]
 mod tests {
     use chrono::Duration;
-    use mas_data_model::{AuthorizationCode, UserAgent};
+    use mas_data_model::{AuthorizationCode, Client, UserAgent};
+    use mas_iana::jose::{JsonWebEncryptionAlg, JsonWebEncryptionEnc};
     use mas_storage::{
         Clock, Pagination,
         clock::MockClock,
-        oauth2::{OAuth2DeviceCodeGrantParams, OAuth2SessionFilter, OAuth2SessionRepository},
+        oauth2::{
+            OAuth2DeviceCodeGrantParams, OAuth2InitialAccessTokenFilter, OAuth2SessionFilter,
+            OAuth2SessionRepository,
+        },
     };
     use oauth2_types::{
-        requests::{GrantType, ResponseMode},
+        requests::{ClaimsRequest, GrantType, ResponseMode},
         scope::{EMAIL, OPENID, PROFILE, Scope},
     };
     use rand::SeedableRng;
@@ -82,6 +95,20 @@ mod tests {
                 None,
                 None,
                 Some("https://example.com/login".parse().unwrap()),
+                vec!["https://example.com/logged-out".parse().unwrap()],
+                None,
+                false,
+                false,
+                None,
+                Vec::new(),
+                None,
+                None,
+                None,
+                Some(JsonWebEncryptionAlg::RsaOaep256),
+                Some(JsonWebEncryptionEnc::A128CbcHs256),
+                None,
+                None,
+                Some("encrypted-registration-access-token".to_owned()),
             )
             .await
             .unwrap();
@@ -94,6 +121,14 @@ mod tests {
             .unwrap()
             .expect("client not found");
         assert_eq!(client, client_lookup);
+        assert_eq!(
+            client_lookup.id_token_encrypted_response_alg,
+            Some(JsonWebEncryptionAlg::RsaOaep256)
+        );
+        assert_eq!(
+            client_lookup.id_token_encrypted_response_enc,
+            Some(JsonWebEncryptionEnc::A128CbcHs256)
+        );
 
         // Find the same client by client id
         let client_lookup = repo
@@ -104,6 +139,53 @@ mod tests {
             .expect("client not found");
         assert_eq!(client, client_lookup);
 
+        // Update the client metadata
+        let client = repo
+            .oauth2_client()
+            .update(
+                client,
+                vec!["https://example.com/new-redirect".parse().unwrap()],
+                None,
+                None,
+                vec![GrantType::AuthorizationCode, GrantType::RefreshToken],
+                Some("Updated client".to_owned()),
+                None,
+                Some("https://example.com/".parse().unwrap()),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                Vec::new(),
+                None,
+                false,
+                false,
+                None,
+                Vec::new(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(client.client_name.as_deref(), Some("Updated client"));
+
+        let client_lookup = repo
+            .oauth2_client()
+            .lookup(client.id)
+            .await
+            .unwrap()
+            .expect("client not found");
+        assert_eq!(client, client_lookup);
+
         // Lookup a non-existing grant
         let grant = repo
             .oauth2_authorization_grant()
@@ -140,6 +222,11 @@ mod tests {
                 true,
                 false,
                 None,
+                Some(ClaimsRequest {
+                    userinfo: Some([("name".to_owned(), None)].into()),
+                    id_token: None,
+                }),
+                vec!["https://api.example.com/".parse().unwrap()],
             )
             .await
             .unwrap();
@@ -214,6 +301,15 @@ mod tests {
             )
             .await
             .unwrap();
+        assert!(session.resources.is_empty());
+
+        // Restrict the session to the resources requested in the grant
+        let session = repo
+            .oauth2_session()
+            .set_resources(session, grant.resources.clone())
+            .await
+            .unwrap();
+        assert_eq!(session.resources, grant.resources);
 
         // Mark the grant as fulfilled
         let grant = repo
@@ -223,6 +319,15 @@ mod tests {
             .unwrap();
         assert!(grant.is_fulfilled());
 
+        // Find the grant by the session it created
+        let grant_lookup = repo
+            .oauth2_authorization_grant()
+            .find_by_session(&session)
+            .await
+            .unwrap()
+            .expect("grant not found");
+        assert_eq!(grant, grant_lookup);
+
         // Lookup the same session by id
         let session_lookup = repo
             .oauth2_session()
@@ -265,6 +370,8 @@ mod tests {
                 &session,
                 "aabbcc".to_owned(),
                 Some(Duration::try_minutes(5).unwrap()),
+                None,
+                Some("bwcK0esc3ACC3DB2Y5_lESsXE8o9ltc05O89jdN-dg2".to_owned()),
             )
             .await
             .unwrap();
@@ -450,6 +557,20 @@ mod tests {
                 None,
                 None,
                 Some("https://first.example.com/login".parse().unwrap()),
+                Vec::new(),
+                None,
+                false,
+                false,
+                None,
+                Vec::new(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -475,6 +596,20 @@ mod tests {
                 None,
                 None,
                 Some("https://second.example.com/login".parse().unwrap()),
+                Vec::new(),
+                None,
+                false,
+                false,
+                None,
+                Vec::new(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -775,6 +910,20 @@ mod tests {
                 None,
                 None,
                 Some("https://example.com/login".parse().unwrap()),
+                Vec::new(),
+                None,
+                false,
+                false,
+                None,
+                Vec::new(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -968,5 +1117,583 @@ mod tests {
             .exchange(&clock, grant, &session)
             .await;
         assert!(res.is_err());
+
+        // Deleting the client also deletes its device code grants
+        repo.oauth2_client().delete(client).await.unwrap();
+        assert!(
+            repo.oauth2_device_code_grant()
+                .lookup(id)
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    /// Test the [`OAuth2PushedAuthorizationRequestRepository`] implementation
+    #[sqlx::test(migrator = "crate::MIGRATOR")]
+    async fn test_pushed_authorization_request_repository(pool: PgPool) {
+        let mut rng = ChaChaRng::seed_from_u64(42);
+        let clock = MockClock::default();
+        let mut repo = PgRepository::from_pool(&pool).await.unwrap().boxed();
+
+        // Provision a client
+        let client = repo
+            .oauth2_client()
+            .add(
+                &mut rng,
+                &clock,
+                vec!["https://example.com/redirect".parse().unwrap()],
+                None,
+                None,
+                None,
+                vec![GrantType::AuthorizationCode],
+                Some("Example".to_owned()),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                Vec::new(),
+                None,
+                false,
+                true,
+                None,
+                Vec::new(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        assert!(client.require_pushed_authorization_requests);
+
+        let request_uri = "urn:ietf:params:oauth:request_uri:abcdef";
+        let parameters = vec![
+            ("response_type".to_owned(), "code".to_owned()),
+            ("scope".to_owned(), "openid".to_owned()),
+        ];
+
+        // Push an authorization request
+        let request = repo
+            .oauth2_pushed_authorization_request()
+            .add(
+                &mut rng,
+                &clock,
+                &client,
+                request_uri.to_owned(),
+                parameters.clone(),
+                Duration::try_seconds(60).unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(request.client_id, client.id);
+        assert_eq!(request.parameters, parameters);
+        assert!(request.is_valid(clock.now()));
+
+        // Check that we can find it by ID and by request URI
+        let lookup = repo
+            .oauth2_pushed_authorization_request()
+            .lookup(request.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(lookup, request);
+
+        let lookup = repo
+            .oauth2_pushed_authorization_request()
+            .find_by_request_uri(request_uri)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(lookup, request);
+
+        // An unknown request URI doesn't match anything
+        let lookup = repo
+            .oauth2_pushed_authorization_request()
+            .find_by_request_uri("urn:ietf:params:oauth:request_uri:unknown")
+            .await
+            .unwrap();
+        assert!(lookup.is_none());
+
+        // It expires after 60 seconds
+        clock.advance(Duration::try_seconds(61).unwrap());
+        assert!(!request.is_valid(clock.now()));
+
+        // Consume it
+        let consumed = repo
+            .oauth2_pushed_authorization_request()
+            .consume(&clock, request.clone())
+            .await
+            .unwrap();
+        assert_eq!(consumed.consumed_at, Some(clock.now()));
+
+        // It can't be consumed twice, even from a stale copy
+        let res = repo
+            .oauth2_pushed_authorization_request()
+            .consume(&clock, consumed)
+            .await;
+        assert!(res.is_err());
+
+        let res = repo
+            .oauth2_pushed_authorization_request()
+            .consume(&clock, request)
+            .await;
+        assert!(res.is_err());
+    }
+
+    /// Test the [`OAuth2DpopProofRepository`] implementation
+    #[sqlx::test(migrator = "crate::MIGRATOR")]
+    async fn test_dpop_proof_repository(pool: PgPool) {
+        let clock = MockClock::default();
+        let mut repo = PgRepository::from_pool(&pool).await.unwrap().boxed();
+
+        let expires_at = clock.now() + Duration::try_minutes(5).unwrap();
+
+        // The first time a proof is seen, it gets recorded
+        let recorded = repo
+            .oauth2_dpop_proof()
+            .record(&clock, "thumbprint", "jti", expires_at)
+            .await
+            .unwrap();
+        assert!(recorded);
+
+        // The second time, it is detected as a replay
+        let recorded = repo
+            .oauth2_dpop_proof()
+            .record(&clock, "thumbprint", "jti", expires_at)
+            .await
+            .unwrap();
+        assert!(!recorded);
+
+        // The same jti from another key is fine
+        let recorded = repo
+            .oauth2_dpop_proof()
+            .record(&clock, "other-thumbprint", "jti", expires_at)
+            .await
+            .unwrap();
+        assert!(recorded);
+
+        // Nothing to cleanup yet
+        let count = repo
+            .oauth2_dpop_proof()
+            .cleanup_expired(&clock)
+            .await
+            .unwrap();
+        assert_eq!(count, 0);
+
+        // Once expired, the proofs get cleaned up
+        clock.advance(Duration::try_minutes(6).unwrap());
+        let count = repo
+            .oauth2_dpop_proof()
+            .cleanup_expired(&clock)
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+    }
+
+    /// Test the [`OAuth2JwtBearerAssertionRepository`] implementation
+    #[sqlx::test(migrator = "crate::MIGRATOR")]
+    async fn test_jwt_bearer_assertion_repository(pool: PgPool) {
+        let mut rng = ChaChaRng::seed_from_u64(42);
+        let clock = MockClock::default();
+        let mut repo = PgRepository::from_pool(&pool).await.unwrap().boxed();
+
+        let [client, other_client] = Client::samples(clock.now(), &mut rng).try_into().unwrap();
+        let expires_at = clock.now() + Duration::try_minutes(5).unwrap();
+
+        // The first time an assertion is seen, it gets recorded
+        let recorded = repo
+            .oauth2_jwt_bearer_assertion()
+            .record(&clock, &client, "jti", expires_at)
+            .await
+            .unwrap();
+        assert!(recorded);
+
+        // The second time, it is detected as a replay
+        let recorded = repo
+            .oauth2_jwt_bearer_assertion()
+            .record(&clock, &client, "jti", expires_at)
+            .await
+            .unwrap();
+        assert!(!recorded);
+
+        // The same jti from another client is fine
+        let recorded = repo
+            .oauth2_jwt_bearer_assertion()
+            .record(&clock, &other_client, "jti", expires_at)
+            .await
+            .unwrap();
+        assert!(recorded);
+
+        // Nothing to cleanup yet
+        let count = repo
+            .oauth2_jwt_bearer_assertion()
+            .cleanup_expired(&clock)
+            .await
+            .unwrap();
+        assert_eq!(count, 0);
+
+        // Once expired, the assertions get cleaned up
+        clock.advance(Duration::try_minutes(6).unwrap());
+        let count = repo
+            .oauth2_jwt_bearer_assertion()
+            .cleanup_expired(&clock)
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+    }
+
+    /// Test the [`OAuth2InitialAccessTokenRepository`] implementation
+    #[sqlx::test(migrator = "crate::MIGRATOR")]
+    async fn test_initial_access_token_repository(pool: PgPool) {
+        let mut rng = ChaChaRng::seed_from_u64(42);
+        let clock = MockClock::default();
+        let mut repo = PgRepository::from_pool(&pool).await.unwrap().boxed();
+
+        let filter = OAuth2InitialAccessTokenFilter::new();
+        assert_eq!(
+            repo.oauth2_initial_access_token()
+                .count(filter)
+                .await
+                .unwrap(),
+            0
+        );
+
+        let token = repo
+            .oauth2_initial_access_token()
+            .add(
+                &mut rng,
+                &clock,
+                "mit_token".to_owned(),
+                Some(Duration::try_hours(1).unwrap()),
+                Some(1),
+            )
+            .await
+            .unwrap();
+        assert!(token.is_valid(clock.now()));
+
+        let lookup = repo
+            .oauth2_initial_access_token()
+            .find_by_token("mit_token")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(lookup, token);
+
+        // Using the token once exhausts it
+        let token = repo
+            .oauth2_initial_access_token()
+            .record_use(&clock, token)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(token.use_count, 1);
+        assert_eq!(token.last_used_at, Some(clock.now()));
+        assert!(!token.is_valid(clock.now()));
+
+        // It can't be used more times than allowed
+        let res = repo
+            .oauth2_initial_access_token()
+            .record_use(&clock, token.clone())
+            .await
+            .unwrap();
+        assert_eq!(res, None);
+
+        let unlimited = repo
+            .oauth2_initial_access_token()
+            .add(&mut rng, &clock, "mit_other".to_owned(), None, None)
+            .await
+            .unwrap();
+
+        // Expired tokens are not valid anymore
+        clock.advance(Duration::try_hours(2).unwrap());
+        let lookup = repo
+            .oauth2_initial_access_token()
+            .lookup(token.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(lookup, token);
+        assert!(unlimited.is_valid(clock.now()));
+
+        let unlimited = repo
+            .oauth2_initial_access_token()
+            .revoke(&clock, unlimited)
+            .await
+            .unwrap();
+        assert!(!unlimited.is_valid(clock.now()));
+
+        let all = filter;
+        let active = filter.active_only();
+        let revoked = filter.revoked_only();
+        let mut repo = repo.oauth2_initial_access_token();
+        assert_eq!(repo.count(all).await.unwrap(), 2);
+        assert_eq!(repo.count(active).await.unwrap(), 1);
+        assert_eq!(repo.count(revoked).await.unwrap(), 1);
+
+        let page = repo.list(revoked, Pagination::first(10)).await.unwrap();
+        assert_eq!(page.edges.len(), 1);
+        assert_eq!(page.edges[0], unlimited);
+        assert!(!page.has_next_page);
+    }
+
+    /// Test the [`OAuth2PairwiseSubjectRepository`] implementation
+    #[sqlx::test(migrator = "crate::MIGRATOR")]
+    async fn test_pairwise_subject_repository(pool: PgPool) {
+        let mut rng = ChaChaRng::seed_from_u64(42);
+        let clock = MockClock::default();
+        let mut repo = PgRepository::from_pool(&pool).await.unwrap().boxed();
+
+        let alice = repo
+            .user()
+            .add(&mut rng, &clock, "alice".to_owned())
+            .await
+            .unwrap();
+        let bob = repo
+            .user()
+            .add(&mut rng, &clock, "bob".to_owned())
+            .await
+            .unwrap();
+
+        // Nothing was given out yet
+        let user_id = repo
+            .oauth2_pairwise_subject()
+            .find_user_id("example.com", "unknown")
+            .await
+            .unwrap();
+        assert_eq!(user_id, None);
+
+        let alice_subject = repo
+            .oauth2_pairwise_subject()
+            .find_or_add(&mut rng, &clock, "example.com", &alice)
+            .await
+            .unwrap();
+        assert_ne!(alice_subject, alice.sub);
+
+        // Asking again gives the same subject
+        let subject = repo
+            .oauth2_pairwise_subject()
+            .find_or_add(&mut rng, &clock, "example.com", &alice)
+            .await
+            .unwrap();
+        assert_eq!(subject, alice_subject);
+
+        // Other users and other sectors get different subjects
+        let bob_subject = repo
+            .oauth2_pairwise_subject()
+            .find_or_add(&mut rng, &clock, "example.com", &bob)
+            .await
+            .unwrap();
+        assert_ne!(bob_subject, alice_subject);
+
+        let other_subject = repo
+            .oauth2_pairwise_subject()
+            .find_or_add(&mut rng, &clock, "example.org", &alice)
+            .await
+            .unwrap();
+        assert_ne!(other_subject, alice_subject);
+
+        // Subjects can be mapped back to the user, but only within their sector
+        let user_id = repo
+            .oauth2_pairwise_subject()
+            .find_user_id("example.com", &alice_subject)
+            .await
+            .unwrap();
+        assert_eq!(user_id, Some(alice.id));
+
+        let user_id = repo
+            .oauth2_pairwise_subject()
+            .find_user_id("example.org", &alice_subject)
+            .await
+            .unwrap();
+        assert_eq!(user_id, None);
+    }
+
+    /// Test the sessions derived through a token exchange
+    #[sqlx::test(migrator = "crate::MIGRATOR")]
+    async fn test_token_exchange_sessions(pool: PgPool) {
+        let mut rng = ChaChaRng::seed_from_u64(42);
+        let clock = MockClock::default();
+        let mut repo = PgRepository::from_pool(&pool).await.unwrap().boxed();
+
+        let user = repo
+            .user()
+            .add(&mut rng, &clock, "john".to_owned())
+            .await
+            .unwrap();
+        let user_session = repo
+            .browser_session()
+            .add(&mut rng, &clock, &user, None)
+            .await
+            .unwrap();
+
+        let client = repo
+            .oauth2_client()
+            .add(
+                &mut rng,
+                &clock,
+                vec!["https://example.com/redirect".parse().unwrap()],
+                None,
+                None,
+                None,
+                vec![GrantType::AuthorizationCode],
+                Some("Example".to_owned()),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                Vec::new(),
+                None,
+                false,
+                false,
+                None,
+                Vec::new(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let backend = repo
+            .oauth2_client()
+            .add(
+                &mut rng,
+                &clock,
+                Vec::new(),
+                None,
+                None,
+                None,
+                vec![GrantType::ClientCredentials, GrantType::TokenExchange],
+                Some("Backend".to_owned()),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                Vec::new(),
+                None,
+                false,
+                false,
+                None,
+                Vec::new(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        assert!(backend.grant_types.contains(&GrantType::TokenExchange));
+
+        let parent = repo
+            .oauth2_session()
+            .add_from_browser_session(
+                &mut rng,
+                &clock,
+                &client,
+                &user_session,
+                Scope::from_iter([OPENID, EMAIL]),
+            )
+            .await
+            .unwrap();
+
+        let child = repo
+            .oauth2_session()
+            .add_from_token_exchange(
+                &mut rng,
+                &clock,
+                &backend,
+                &parent,
+                Some(&backend),
+                Some("https://backend.example.com/".to_owned()),
+                Scope::from_iter([OPENID]),
+            )
+            .await
+            .unwrap();
+        assert_eq!(child.user_id, Some(user.id));
+        assert_eq!(child.user_session_id, Some(user_session.id));
+        assert_eq!(child.client_id, backend.id);
+
+        // Exchange the derived session again
+        let grandchild = repo
+            .oauth2_session()
+            .add_from_token_exchange(
+                &mut rng,
+                &clock,
+                &backend,
+                &child,
+                None,
+                None,
+                Scope::from_iter([OPENID]),
+            )
+            .await
+            .unwrap();
+
+        let child_lookup = repo
+            .oauth2_session()
+            .lookup(child.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(child_lookup, child);
+        assert_eq!(child_lookup.parent_session_id, Some(parent.id));
+        assert_eq!(child_lookup.actor_client_id, Some(backend.id));
+        assert_eq!(
+            child_lookup.audience.as_deref(),
+            Some("https://backend.example.com/")
+        );
+
+        // Finishing the parent session finishes the derived ones
+        repo.oauth2_session().finish(&clock, parent).await.unwrap();
+
+        let child = repo
+            .oauth2_session()
+            .lookup(child.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(child.is_finished());
+
+        let grandchild = repo
+            .oauth2_session()
+            .lookup(grandchild.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(grandchild.is_finished());
     }
 }
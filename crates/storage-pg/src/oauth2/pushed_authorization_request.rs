@@ -0,0 +1,236 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use mas_data_model::{Client, PushedAuthorizationRequest};
+use mas_storage::{Clock, oauth2::OAuth2PushedAuthorizationRequestRepository};
+use rand::RngCore;
+use sqlx::{PgConnection, types::Json};
+use ulid::Ulid;
+use uuid::Uuid;
+
+use crate::{DatabaseError, ExecuteExt};
+
+/// An implementation of [`OAuth2PushedAuthorizationRequestRepository`] for a
+/// PostgreSQL connection
+pub struct PgOAuth2PushedAuthorizationRequestRepository<'c> {
+    conn: &'c mut PgConnection,
+}
+
+impl<'c> PgOAuth2PushedAuthorizationRequestRepository<'c> {
+    /// Create a new [`PgOAuth2PushedAuthorizationRequestRepository`] from an
+    /// active PostgreSQL connection
+    pub fn new(conn: &'c mut PgConnection) -> Self {
+        Self { conn }
+    }
+}
+
+struct OAuth2PushedAuthorizationRequestLookup {
+    oauth2_pushed_authorization_request_id: Uuid,
+    oauth2_client_id: Uuid,
+    request_uri: String,
+    parameters: Json<Vec<(String, String)>>,
+    created_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+    consumed_at: Option<DateTime<Utc>>,
+}
+
+impl From<OAuth2PushedAuthorizationRequestLookup> for PushedAuthorizationRequest {
+    fn from(value: OAuth2PushedAuthorizationRequestLookup) -> Self {
+        Self {
+            id: value.oauth2_pushed_authorization_request_id.into(),
+            client_id: value.oauth2_client_id.into(),
+            request_uri: value.request_uri,
+            parameters: value.parameters.0,
+            created_at: value.created_at,
+            expires_at: value.expires_at,
+            consumed_at: value.consumed_at,
+        }
+    }
+}
+
+#[async_trait]
+impl OAuth2PushedAuthorizationRequestRepository
+    for PgOAuth2PushedAuthorizationRequestRepository<'_>
+{
+    type Error = DatabaseError;
+
+    #[tracing::instrument(
+        name = "db.oauth2_pushed_authorization_request.add",
+        skip_all,
+        fields(
+            db.query.text,
+            oauth2_pushed_authorization_request.id,
+            oauth2_client.id = %client.id,
+        ),
+        err,
+    )]
+    async fn add(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        client: &Client,
+        request_uri: String,
+        parameters: Vec<(String, String)>,
+        expires_in: Duration,
+    ) -> Result<PushedAuthorizationRequest, Self::Error> {
+        let created_at = clock.now();
+        let id = Ulid::from_datetime_with_source(created_at.into(), rng);
+        tracing::Span::current().record(
+            "oauth2_pushed_authorization_request.id",
+            tracing::field::display(id),
+        );
+
+        let expires_at = created_at + expires_in;
+
+        sqlx::query!(
+            r#"
+                INSERT INTO oauth2_pushed_authorization_requests
+                    ( oauth2_pushed_authorization_request_id
+                    , oauth2_client_id
+                    , request_uri
+                    , parameters
+                    , created_at
+                    , expires_at
+                    )
+                VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+            Uuid::from(id),
+            Uuid::from(client.id),
+            &request_uri,
+            Json(&parameters) as _,
+            created_at,
+            expires_at,
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(PushedAuthorizationRequest {
+            id,
+            client_id: client.id,
+            request_uri,
+            parameters,
+            created_at,
+            expires_at,
+            consumed_at: None,
+        })
+    }
+
+    #[tracing::instrument(
+        name = "db.oauth2_pushed_authorization_request.lookup",
+        skip_all,
+        fields(
+            db.query.text,
+            oauth2_pushed_authorization_request.id = %id,
+        ),
+        err,
+    )]
+    async fn lookup(
+        &mut self,
+        id: Ulid,
+    ) -> Result<Option<PushedAuthorizationRequest>, Self::Error> {
+        let res = sqlx::query_as!(
+            OAuth2PushedAuthorizationRequestLookup,
+            r#"
+                SELECT oauth2_pushed_authorization_request_id
+                     , oauth2_client_id
+                     , request_uri
+                     , parameters as "parameters: Json<Vec<(String, String)>>"
+                     , created_at
+                     , expires_at
+                     , consumed_at
+                FROM oauth2_pushed_authorization_requests
+
+                WHERE oauth2_pushed_authorization_request_id = $1
+            "#,
+            Uuid::from(id),
+        )
+        .traced()
+        .fetch_optional(&mut *self.conn)
+        .await?;
+
+        let Some(res) = res else { return Ok(None) };
+
+        Ok(Some(res.into()))
+    }
+
+    #[tracing::instrument(
+        name = "db.oauth2_pushed_authorization_request.find_by_request_uri",
+        skip_all,
+        fields(
+            db.query.text,
+        ),
+        err,
+    )]
+    async fn find_by_request_uri(
+        &mut self,
+        request_uri: &str,
+    ) -> Result<Option<PushedAuthorizationRequest>, Self::Error> {
+        let res = sqlx::query_as!(
+            OAuth2PushedAuthorizationRequestLookup,
+            r#"
+                SELECT oauth2_pushed_authorization_request_id
+                     , oauth2_client_id
+                     , request_uri
+                     , parameters as "parameters: Json<Vec<(String, String)>>"
+                     , created_at
+                     , expires_at
+                     , consumed_at
+                FROM oauth2_pushed_authorization_requests
+
+                WHERE request_uri = $1
+            "#,
+            request_uri,
+        )
+        .traced()
+        .fetch_optional(&mut *self.conn)
+        .await?;
+
+        let Some(res) = res else { return Ok(None) };
+
+        Ok(Some(res.into()))
+    }
+
+    #[tracing::instrument(
+        name = "db.oauth2_pushed_authorization_request.consume",
+        skip_all,
+        fields(
+            db.query.text,
+            oauth2_pushed_authorization_request.id = %pushed_authorization_request.id,
+            oauth2_client.id = %pushed_authorization_request.client_id,
+        ),
+        err,
+    )]
+    async fn consume(
+        &mut self,
+        clock: &dyn Clock,
+        pushed_authorization_request: PushedAuthorizationRequest,
+    ) -> Result<PushedAuthorizationRequest, Self::Error> {
+        let consumed_at = clock.now();
+        let pushed_authorization_request = pushed_authorization_request
+            .consume(consumed_at)
+            .map_err(DatabaseError::to_invalid_operation)?;
+
+        let res = sqlx::query!(
+            r#"
+                UPDATE oauth2_pushed_authorization_requests
+                SET consumed_at = $1
+                WHERE oauth2_pushed_authorization_request_id = $2
+                  AND consumed_at IS NULL
+            "#,
+            consumed_at,
+            Uuid::from(pushed_authorization_request.id),
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        DatabaseError::ensure_affected_rows(&res, 1)?;
+
+        Ok(pushed_authorization_request)
+    }
+}
@@ -19,6 +19,7 @@ use sea_query::{Expr, PgFunc, PostgresQueryBuilder, Query, enum_def, extension::
 use sea_query_binder::SqlxBinder;
 use sqlx::PgConnection;
 use ulid::Ulid;
+use url::Url;
 use uuid::Uuid;
 
 use crate::{
@@ -55,6 +56,10 @@ struct OAuthSessionLookup {
     user_agent: Option<String>,
     last_active_at: Option<DateTime<Utc>>,
     last_active_ip: Option<IpAddr>,
+    parent_oauth2_session_id: Option<Uuid>,
+    actor_oauth2_client_id: Option<Uuid>,
+    audience: Option<String>,
+    resources: Vec<String>,
 }
 
 impl TryFrom<OAuthSessionLookup> for Session {
@@ -74,6 +79,18 @@ impl TryFrom<OAuthSessionLookup> for Session {
                 .source(e)
         })?;
 
+        let resources = value
+            .resources
+            .iter()
+            .map(|resource| resource.parse())
+            .collect::<Result<_, _>>()
+            .map_err(|e| {
+                DatabaseInconsistencyError::on("oauth2_sessions")
+                    .column("resources")
+                    .row(id)
+                    .source(e)
+            })?;
+
         let state = match value.finished_at {
             None => SessionState::Valid,
             Some(finished_at) => SessionState::Finished { finished_at },
@@ -90,6 +107,10 @@ impl TryFrom<OAuthSessionLookup> for Session {
             user_agent: value.user_agent.map(UserAgent::parse),
             last_active_at: value.last_active_at,
             last_active_ip: value.last_active_ip,
+            parent_session_id: value.parent_oauth2_session_id.map(Ulid::from),
+            actor_client_id: value.actor_oauth2_client_id.map(Ulid::from),
+            audience: value.audience,
+            resources,
         })
     }
 }
@@ -195,6 +216,10 @@ impl OAuth2SessionRepository for PgOAuth2SessionRepository<'_> {
                      , user_agent
                      , last_active_at
                      , last_active_ip as "last_active_ip: IpAddr"
+                     , parent_oauth2_session_id
+                     , actor_oauth2_client_id
+                     , audience
+                     , resources
                 FROM oauth2_sessions
 
                 WHERE oauth2_session_id = $1
@@ -270,6 +295,85 @@ impl OAuth2SessionRepository for PgOAuth2SessionRepository<'_> {
             user_agent: None,
             last_active_at: None,
             last_active_ip: None,
+            parent_session_id: None,
+            actor_client_id: None,
+            audience: None,
+            resources: Vec::new(),
+        })
+    }
+
+    #[tracing::instrument(
+        name = "db.oauth2_session.add_from_token_exchange",
+        skip_all,
+        fields(
+            db.query.text,
+            %client.id,
+            %parent.id,
+            session.id,
+            session.scope = %scope,
+        ),
+        err,
+    )]
+    async fn add_from_token_exchange(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        client: &Client,
+        parent: &Session,
+        actor: Option<&Client>,
+        audience: Option<String>,
+        scope: Scope,
+    ) -> Result<Session, Self::Error> {
+        let created_at = clock.now();
+        let id = Ulid::from_datetime_with_source(created_at.into(), rng);
+        tracing::Span::current().record("session.id", tracing::field::display(id));
+
+        let scope_list: Vec<String> = scope.iter().map(|s| s.as_str().to_owned()).collect();
+
+        sqlx::query!(
+            r#"
+                INSERT INTO oauth2_sessions
+                    ( oauth2_session_id
+                    , user_id
+                    , user_session_id
+                    , oauth2_client_id
+                    , scope_list
+                    , created_at
+                    , parent_oauth2_session_id
+                    , actor_oauth2_client_id
+                    , audience
+                    )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+            Uuid::from(id),
+            parent.user_id.map(Uuid::from),
+            parent.user_session_id.map(Uuid::from),
+            Uuid::from(client.id),
+            &scope_list,
+            created_at,
+            Uuid::from(parent.id),
+            actor.map(|c| Uuid::from(c.id)),
+            audience.as_deref(),
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(Session {
+            id,
+            state: SessionState::Valid,
+            created_at,
+            user_id: parent.user_id,
+            user_session_id: parent.user_session_id,
+            client_id: client.id,
+            scope,
+            user_agent: None,
+            last_active_at: None,
+            last_active_ip: None,
+            parent_session_id: Some(parent.id),
+            actor_client_id: actor.map(|c| c.id),
+            audience,
+            resources: Vec::new(),
         })
     }
 
@@ -333,6 +437,32 @@ impl OAuth2SessionRepository for PgOAuth2SessionRepository<'_> {
 
         DatabaseError::ensure_affected_rows(&res, 1)?;
 
+        // Also finish the sessions which were derived from this one through token
+        // exchanges, recursively
+        sqlx::query!(
+            r#"
+                WITH RECURSIVE children AS (
+                    SELECT oauth2_session_id
+                    FROM oauth2_sessions
+                    WHERE parent_oauth2_session_id = $1
+                  UNION
+                    SELECT s.oauth2_session_id
+                    FROM oauth2_sessions s
+                    INNER JOIN children c
+                      ON s.parent_oauth2_session_id = c.oauth2_session_id
+                )
+                UPDATE oauth2_sessions
+                SET finished_at = $2
+                WHERE oauth2_session_id IN (SELECT oauth2_session_id FROM children)
+                  AND finished_at IS NULL
+            "#,
+            Uuid::from(session.id),
+            finished_at,
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
         session
             .finish(finished_at)
             .map_err(DatabaseError::to_invalid_operation)
@@ -392,6 +522,22 @@ impl OAuth2SessionRepository for PgOAuth2SessionRepository<'_> {
                 Expr::col((OAuth2Sessions::Table, OAuth2Sessions::LastActiveIp)),
                 OAuthSessionLookupIden::LastActiveIp,
             )
+            .expr_as(
+                Expr::col((OAuth2Sessions::Table, OAuth2Sessions::ParentOAuth2SessionId)),
+                OAuthSessionLookupIden::ParentOauth2SessionId,
+            )
+            .expr_as(
+                Expr::col((OAuth2Sessions::Table, OAuth2Sessions::ActorOAuth2ClientId)),
+                OAuthSessionLookupIden::ActorOauth2ClientId,
+            )
+            .expr_as(
+                Expr::col((OAuth2Sessions::Table, OAuth2Sessions::Audience)),
+                OAuthSessionLookupIden::Audience,
+            )
+            .expr_as(
+                Expr::col((OAuth2Sessions::Table, OAuth2Sessions::Resources)),
+                OAuthSessionLookupIden::Resources,
+            )
             .from(OAuth2Sessions::Table)
             .apply_filter(filter)
             .generate_pagination(
@@ -518,4 +664,41 @@ impl OAuth2SessionRepository for PgOAuth2SessionRepository<'_> {
 
         Ok(session)
     }
+
+    #[tracing::instrument(
+        name = "db.oauth2_session.set_resources",
+        skip_all,
+        fields(
+            db.query.text,
+            %session.id,
+            client.id = %session.client_id,
+        ),
+        err,
+    )]
+    async fn set_resources(
+        &mut self,
+        mut session: Session,
+        resources: Vec<Url>,
+    ) -> Result<Session, Self::Error> {
+        let resources_str: Vec<String> = resources.iter().map(ToString::to_string).collect();
+
+        let res = sqlx::query!(
+            r#"
+                UPDATE oauth2_sessions
+                SET resources = $2
+                WHERE oauth2_session_id = $1
+            "#,
+            Uuid::from(session.id),
+            &resources_str,
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        DatabaseError::ensure_affected_rows(&res, 1)?;
+
+        session.resources = resources;
+
+        Ok(session)
+    }
 }
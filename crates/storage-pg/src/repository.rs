@@ -16,15 +16,22 @@ use mas_storage::{
     },
     oauth2::{
         OAuth2AccessTokenRepository, OAuth2AuthorizationGrantRepository, OAuth2ClientRepository,
-        OAuth2DeviceCodeGrantRepository, OAuth2RefreshTokenRepository, OAuth2SessionRepository,
+        OAuth2DeviceCodeGrantRepository, OAuth2DpopProofRepository,
+        OAuth2InitialAccessTokenRepository, OAuth2JwtBearerAssertionRepository,
+        OAuth2PairwiseSubjectRepository, OAuth2PushedAuthorizationRequestRepository,
+        OAuth2RefreshTokenRepository, OAuth2SessionRepository,
     },
     policy_data::PolicyDataRepository,
     queue::{QueueJobRepository, QueueScheduleRepository, QueueWorkerRepository},
+    signing_key::SigningKeyRepository,
     upstream_oauth2::{
         UpstreamOAuthLinkRepository, UpstreamOAuthProviderRepository,
         UpstreamOAuthSessionRepository,
     },
-    user::{BrowserSessionRepository, UserEmailRepository, UserPasswordRepository, UserRepository},
+    user::{
+        BrowserSessionRepository, UserEmailRepository, UserPasskeyRepository,
+        UserPasswordRepository, UserRecoveryCodeRepository, UserRepository, UserTotpRepository,
+    },
 };
 use sqlx::{PgConnection, PgPool, Postgres, Transaction};
 use tracing::Instrument;
@@ -38,7 +45,9 @@ use crate::{
     },
     oauth2::{
         PgOAuth2AccessTokenRepository, PgOAuth2AuthorizationGrantRepository,
-        PgOAuth2ClientRepository, PgOAuth2DeviceCodeGrantRepository,
+        PgOAuth2ClientRepository, PgOAuth2DeviceCodeGrantRepository, PgOAuth2DpopProofRepository,
+        PgOAuth2InitialAccessTokenRepository, PgOAuth2JwtBearerAssertionRepository,
+        PgOAuth2PairwiseSubjectRepository, PgOAuth2PushedAuthorizationRequestRepository,
         PgOAuth2RefreshTokenRepository, PgOAuth2SessionRepository,
     },
     policy_data::PgPolicyDataRepository,
@@ -46,14 +55,16 @@ use crate::{
         job::PgQueueJobRepository, schedule::PgQueueScheduleRepository,
         worker::PgQueueWorkerRepository,
     },
+    signing_key::PgSigningKeyRepository,
     upstream_oauth2::{
         PgUpstreamOAuthLinkRepository, PgUpstreamOAuthProviderRepository,
         PgUpstreamOAuthSessionRepository,
     },
     user::{
-        PgBrowserSessionRepository, PgUserEmailRepository, PgUserPasswordRepository,
-        PgUserRecoveryRepository, PgUserRegistrationRepository, PgUserRepository,
-        PgUserTermsRepository,
+        PgBrowserSessionRepository, PgUserEmailRepository, PgUserPasskeyRepository,
+        PgUserPasswordRepository, PgUserRecoveryCodeRepository, PgUserRecoveryRepository,
+        PgUserRegistrationRepository, PgUserRepository, PgUserTermsRepository,
+        PgUserTotpRepository,
     },
 };
 
@@ -182,6 +193,20 @@ where
         Box::new(PgUserPasswordRepository::new(self.conn.as_mut()))
     }
 
+    fn user_totp<'c>(&'c mut self) -> Box<dyn UserTotpRepository<Error = Self::Error> + 'c> {
+        Box::new(PgUserTotpRepository::new(self.conn.as_mut()))
+    }
+
+    fn user_passkey<'c>(&'c mut self) -> Box<dyn UserPasskeyRepository<Error = Self::Error> + 'c> {
+        Box::new(PgUserPasskeyRepository::new(self.conn.as_mut()))
+    }
+
+    fn user_recovery_code<'c>(
+        &'c mut self,
+    ) -> Box<dyn UserRecoveryCodeRepository<Error = Self::Error> + 'c> {
+        Box::new(PgUserRecoveryCodeRepository::new(self.conn.as_mut()))
+    }
+
     fn user_recovery<'c>(
         &'c mut self,
     ) -> Box<dyn mas_storage::user::UserRecoveryRepository<Error = Self::Error> + 'c> {
@@ -248,6 +273,42 @@ where
         Box::new(PgOAuth2DeviceCodeGrantRepository::new(self.conn.as_mut()))
     }
 
+    fn oauth2_pushed_authorization_request<'c>(
+        &'c mut self,
+    ) -> Box<dyn OAuth2PushedAuthorizationRequestRepository<Error = Self::Error> + 'c> {
+        Box::new(PgOAuth2PushedAuthorizationRequestRepository::new(
+            self.conn.as_mut(),
+        ))
+    }
+
+    fn oauth2_dpop_proof<'c>(
+        &'c mut self,
+    ) -> Box<dyn OAuth2DpopProofRepository<Error = Self::Error> + 'c> {
+        Box::new(PgOAuth2DpopProofRepository::new(self.conn.as_mut()))
+    }
+
+    fn oauth2_jwt_bearer_assertion<'c>(
+        &'c mut self,
+    ) -> Box<dyn OAuth2JwtBearerAssertionRepository<Error = Self::Error> + 'c> {
+        Box::new(PgOAuth2JwtBearerAssertionRepository::new(
+            self.conn.as_mut(),
+        ))
+    }
+
+    fn oauth2_initial_access_token<'c>(
+        &'c mut self,
+    ) -> Box<dyn OAuth2InitialAccessTokenRepository<Error = Self::Error> + 'c> {
+        Box::new(PgOAuth2InitialAccessTokenRepository::new(
+            self.conn.as_mut(),
+        ))
+    }
+
+    fn oauth2_pairwise_subject<'c>(
+        &'c mut self,
+    ) -> Box<dyn OAuth2PairwiseSubjectRepository<Error = Self::Error> + 'c> {
+        Box::new(PgOAuth2PairwiseSubjectRepository::new(self.conn.as_mut()))
+    }
+
     fn compat_session<'c>(
         &'c mut self,
     ) -> Box<dyn CompatSessionRepository<Error = Self::Error> + 'c> {
@@ -289,4 +350,8 @@ where
     fn policy_data<'c>(&'c mut self) -> Box<dyn PolicyDataRepository<Error = Self::Error> + 'c> {
         Box::new(PgPolicyDataRepository::new(self.conn.as_mut()))
     }
+
+    fn signing_key<'c>(&'c mut self) -> Box<dyn SigningKeyRepository<Error = Self::Error> + 'c> {
+        Box::new(PgSigningKeyRepository::new(self.conn.as_mut()))
+    }
 }
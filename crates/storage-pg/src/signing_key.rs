@@ -0,0 +1,278 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+//! A module containing the PostgreSQL implementation of the signing keys
+//! storage.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use mas_data_model::SigningKey;
+use mas_storage::{Clock, signing_key::SigningKeyRepository};
+use rand::RngCore;
+use sqlx::PgConnection;
+use ulid::Ulid;
+use uuid::Uuid;
+
+use crate::{DatabaseError, ExecuteExt};
+
+/// An implementation of [`SigningKeyRepository`] for a PostgreSQL connection.
+pub struct PgSigningKeyRepository<'c> {
+    conn: &'c mut PgConnection,
+}
+
+impl<'c> PgSigningKeyRepository<'c> {
+    /// Create a new [`PgSigningKeyRepository`] from an active PostgreSQL
+    /// connection.
+    #[must_use]
+    pub fn new(conn: &'c mut PgConnection) -> Self {
+        Self { conn }
+    }
+}
+
+struct SigningKeyLookup {
+    signing_key_id: Uuid,
+    kid: String,
+    encrypted_key: String,
+    created_at: DateTime<Utc>,
+    activated_at: Option<DateTime<Utc>>,
+    retired_at: Option<DateTime<Utc>>,
+}
+
+impl From<SigningKeyLookup> for SigningKey {
+    fn from(value: SigningKeyLookup) -> Self {
+        SigningKey {
+            id: value.signing_key_id.into(),
+            kid: value.kid,
+            encrypted_key: value.encrypted_key,
+            created_at: value.created_at,
+            activated_at: value.activated_at,
+            retired_at: value.retired_at,
+        }
+    }
+}
+
+#[async_trait]
+impl SigningKeyRepository for PgSigningKeyRepository<'_> {
+    type Error = DatabaseError;
+
+    #[tracing::instrument(
+        name = "db.signing_key.all",
+        skip_all,
+        fields(
+            db.query.text,
+        ),
+        err,
+    )]
+    async fn all(&mut self) -> Result<Vec<SigningKey>, Self::Error> {
+        let rows = sqlx::query_as!(
+            SigningKeyLookup,
+            r#"
+            SELECT signing_key_id, kid, encrypted_key, created_at, activated_at, retired_at
+            FROM signing_keys
+            ORDER BY signing_key_id ASC
+            "#
+        )
+        .traced()
+        .fetch_all(&mut *self.conn)
+        .await?;
+
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    #[tracing::instrument(
+        name = "db.signing_key.add",
+        skip_all,
+        fields(
+            db.query.text,
+            signing_key.id,
+            signing_key.kid = %kid,
+        ),
+        err,
+    )]
+    async fn add(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        kid: String,
+        encrypted_key: String,
+    ) -> Result<SigningKey, Self::Error> {
+        let created_at = clock.now();
+        let id = Ulid::from_datetime_with_source(created_at.into(), rng);
+        tracing::Span::current().record("signing_key.id", tracing::field::display(id));
+
+        sqlx::query!(
+            r#"
+            INSERT INTO signing_keys (signing_key_id, kid, encrypted_key, created_at)
+            VALUES ($1, $2, $3, $4)
+            "#,
+            Uuid::from(id),
+            &kid,
+            &encrypted_key,
+            created_at,
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(SigningKey {
+            id,
+            kid,
+            encrypted_key,
+            created_at,
+            activated_at: None,
+            retired_at: None,
+        })
+    }
+
+    #[tracing::instrument(
+        name = "db.signing_key.activate",
+        skip_all,
+        fields(
+            db.query.text,
+            %signing_key.id,
+            %signing_key.kid,
+        ),
+        err,
+    )]
+    async fn activate(
+        &mut self,
+        clock: &dyn Clock,
+        mut signing_key: SigningKey,
+    ) -> Result<SigningKey, Self::Error> {
+        let activated_at = clock.now();
+        let res = sqlx::query!(
+            r#"
+            UPDATE signing_keys
+            SET activated_at = $2
+            WHERE signing_key_id = $1
+            "#,
+            Uuid::from(signing_key.id),
+            activated_at,
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        DatabaseError::ensure_affected_rows(&res, 1)?;
+
+        signing_key.activated_at = Some(activated_at);
+        Ok(signing_key)
+    }
+
+    #[tracing::instrument(
+        name = "db.signing_key.retire",
+        skip_all,
+        fields(
+            db.query.text,
+            %signing_key.id,
+            %signing_key.kid,
+        ),
+        err,
+    )]
+    async fn retire(
+        &mut self,
+        clock: &dyn Clock,
+        mut signing_key: SigningKey,
+    ) -> Result<SigningKey, Self::Error> {
+        let retired_at = clock.now();
+        let res = sqlx::query!(
+            r#"
+            UPDATE signing_keys
+            SET retired_at = $2
+            WHERE signing_key_id = $1
+            "#,
+            Uuid::from(signing_key.id),
+            retired_at,
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        DatabaseError::ensure_affected_rows(&res, 1)?;
+
+        signing_key.retired_at = Some(retired_at);
+        Ok(signing_key)
+    }
+
+    #[tracing::instrument(
+        name = "db.signing_key.remove",
+        skip_all,
+        fields(
+            db.query.text,
+            %signing_key.id,
+            %signing_key.kid,
+        ),
+        err,
+    )]
+    async fn remove(&mut self, signing_key: SigningKey) -> Result<(), Self::Error> {
+        let res = sqlx::query!(
+            r#"
+            DELETE FROM signing_keys
+            WHERE signing_key_id = $1
+            "#,
+            Uuid::from(signing_key.id),
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        DatabaseError::ensure_affected_rows(&res, 1)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mas_storage::{clock::MockClock, signing_key::SigningKeyRepository};
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+    use sqlx::PgPool;
+
+    use crate::signing_key::PgSigningKeyRepository;
+
+    #[sqlx::test(migrator = "crate::MIGRATOR")]
+    async fn test_signing_keys(pool: PgPool) {
+        let mut rng = ChaChaRng::seed_from_u64(42);
+        let clock = MockClock::default();
+        let mut conn = pool.acquire().await.unwrap();
+        let mut repo = PgSigningKeyRepository::new(&mut conn);
+
+        // There are no keys at first
+        assert!(repo.all().await.unwrap().is_empty());
+
+        // Add a key, which is pending at first
+        let key1 = repo
+            .add(&mut rng, &clock, "key1".to_owned(), "encrypted1".to_owned())
+            .await
+            .unwrap();
+        assert!(key1.is_pending());
+        assert_eq!(repo.all().await.unwrap(), vec![key1.clone()]);
+
+        // Activate it
+        clock.advance(chrono::Duration::seconds(1));
+        let key1 = repo.activate(&clock, key1).await.unwrap();
+        assert!(key1.is_active());
+
+        // Add a second key
+        clock.advance(chrono::Duration::seconds(1));
+        let key2 = repo
+            .add(&mut rng, &clock, "key2".to_owned(), "encrypted2".to_owned())
+            .await
+            .unwrap();
+        assert_eq!(repo.all().await.unwrap(), vec![key1.clone(), key2.clone()]);
+
+        // Retire the first one
+        clock.advance(chrono::Duration::seconds(1));
+        let key1 = repo.retire(&clock, key1).await.unwrap();
+        assert!(key1.is_retired());
+        assert!(!key1.is_active());
+        assert_eq!(repo.all().await.unwrap(), vec![key1.clone(), key2.clone()]);
+
+        // Remove it
+        repo.remove(key1).await.unwrap();
+        assert_eq!(repo.all().await.unwrap(), vec![key2]);
+    }
+}
@@ -66,6 +66,7 @@ struct UserEmailAuthenticationLookup {
     user_email_authentication_id: Uuid,
     user_session_id: Option<Uuid>,
     user_registration_id: Option<Uuid>,
+    user_id: Option<Uuid>,
     email: String,
     created_at: DateTime<Utc>,
     completed_at: Option<DateTime<Utc>>,
@@ -77,6 +78,7 @@ impl From<UserEmailAuthenticationLookup> for UserEmailAuthentication {
             id: value.user_email_authentication_id.into(),
             user_session_id: value.user_session_id.map(Ulid::from),
             user_registration_id: value.user_registration_id.map(Ulid::from),
+            user_id: value.user_id.map(Ulid::from),
             email: value.email,
             created_at: value.created_at,
             completed_at: value.completed_at,
@@ -470,6 +472,7 @@ impl UserEmailRepository for PgUserEmailRepository<'_> {
             id,
             user_session_id: Some(session.id),
             user_registration_id: None,
+            user_id: None,
             email,
             created_at,
             completed_at: None,
@@ -522,6 +525,61 @@ impl UserEmailRepository for PgUserEmailRepository<'_> {
             id,
             user_session_id: None,
             user_registration_id: Some(user_registration.id),
+            user_id: None,
+            email,
+            created_at,
+            completed_at: None,
+        })
+    }
+
+    #[tracing::instrument(
+        name = "db.user_email.add_authentication_for_login",
+        skip_all,
+        fields(
+            db.query.text,
+            user.id = user.map(|u| tracing::field::display(u.id)),
+            user_email_authentication.id,
+            user_email_authentication.email = email,
+        ),
+        err,
+    )]
+    async fn add_authentication_for_login(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        email: String,
+        user: Option<&User>,
+    ) -> Result<UserEmailAuthentication, Self::Error> {
+        let created_at = clock.now();
+        let id = Ulid::from_datetime_with_source(created_at.into(), rng);
+        tracing::Span::current()
+            .record("user_email_authentication.id", tracing::field::display(id));
+
+        let user_id = user.map(|u| u.id);
+        sqlx::query!(
+            r#"
+                INSERT INTO user_email_authentications
+                  ( user_email_authentication_id
+                  , user_id
+                  , email
+                  , created_at
+                  )
+                VALUES ($1, $2, $3, $4)
+            "#,
+            Uuid::from(id),
+            user_id.map(Uuid::from),
+            &email,
+            created_at,
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(UserEmailAuthentication {
+            id,
+            user_session_id: None,
+            user_registration_id: None,
+            user_id,
             email,
             created_at,
             completed_at: None,
@@ -605,6 +663,7 @@ impl UserEmailRepository for PgUserEmailRepository<'_> {
                 SELECT user_email_authentication_id
                      , user_session_id
                      , user_registration_id
+                     , user_id
                      , email
                      , created_at
                      , completed_at
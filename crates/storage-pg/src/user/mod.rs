@@ -29,19 +29,23 @@ use crate::{
 };
 
 mod email;
+mod passkey;
 mod password;
 mod recovery;
+mod recovery_code;
 mod registration;
 mod session;
 mod terms;
+mod totp;
 
 #[cfg(test)]
 mod tests;
 
 pub use self::{
-    email::PgUserEmailRepository, password::PgUserPasswordRepository,
-    recovery::PgUserRecoveryRepository, registration::PgUserRegistrationRepository,
-    session::PgBrowserSessionRepository, terms::PgUserTermsRepository,
+    email::PgUserEmailRepository, passkey::PgUserPasskeyRepository,
+    password::PgUserPasswordRepository, recovery::PgUserRecoveryRepository,
+    recovery_code::PgUserRecoveryCodeRepository, registration::PgUserRegistrationRepository,
+    session::PgBrowserSessionRepository, terms::PgUserTermsRepository, totp::PgUserTotpRepository,
 };
 
 /// An implementation of [`UserRepository`] for a PostgreSQL connection
@@ -74,6 +78,8 @@ mod priv_ {
         pub(super) locked_at: Option<DateTime<Utc>>,
         pub(super) deactivated_at: Option<DateTime<Utc>>,
         pub(super) can_request_admin: bool,
+        pub(super) locale: Option<String>,
+        pub(super) profile_updated_at: Option<DateTime<Utc>>,
     }
 }
 
@@ -90,6 +96,8 @@ impl From<UserLookup> for User {
             locked_at: value.locked_at,
             deactivated_at: value.deactivated_at,
             can_request_admin: value.can_request_admin,
+            locale: value.locale,
+            profile_updated_at: value.profile_updated_at,
         }
     }
 }
@@ -141,6 +149,8 @@ impl UserRepository for PgUserRepository<'_> {
                      , locked_at
                      , deactivated_at
                      , can_request_admin
+                     , locale
+                     , profile_updated_at
                 FROM users
                 WHERE user_id = $1
             "#,
@@ -174,6 +184,8 @@ impl UserRepository for PgUserRepository<'_> {
                      , locked_at
                      , deactivated_at
                      , can_request_admin
+                     , locale
+                     , profile_updated_at
                 FROM users
                 WHERE username = $1
             "#,
@@ -234,6 +246,8 @@ impl UserRepository for PgUserRepository<'_> {
             locked_at: None,
             deactivated_at: None,
             can_request_admin: false,
+            locale: None,
+            profile_updated_at: None,
         })
     }
 
@@ -401,6 +415,81 @@ impl UserRepository for PgUserRepository<'_> {
         Ok(user)
     }
 
+    #[tracing::instrument(
+        name = "db.user.set_locale",
+        skip_all,
+        fields(
+            db.query.text,
+            %user.id,
+            user.locale = locale,
+        ),
+        err,
+    )]
+    async fn set_locale(
+        &mut self,
+        clock: &dyn Clock,
+        mut user: User,
+        locale: String,
+    ) -> Result<User, Self::Error> {
+        let profile_updated_at = clock.now();
+        let res = sqlx::query!(
+            r#"
+                UPDATE users
+                SET locale = $2
+                  , profile_updated_at = $3
+                WHERE user_id = $1
+            "#,
+            Uuid::from(user.id),
+            &locale,
+            profile_updated_at,
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        DatabaseError::ensure_affected_rows(&res, 1)?;
+
+        user.locale = Some(locale);
+        user.profile_updated_at = Some(profile_updated_at);
+
+        Ok(user)
+    }
+
+    #[tracing::instrument(
+        name = "db.user.touch_profile",
+        skip_all,
+        fields(
+            db.query.text,
+            %user.id,
+        ),
+        err,
+    )]
+    async fn touch_profile(
+        &mut self,
+        clock: &dyn Clock,
+        mut user: User,
+    ) -> Result<User, Self::Error> {
+        let profile_updated_at = clock.now();
+        let res = sqlx::query!(
+            r#"
+                UPDATE users
+                SET profile_updated_at = $2
+                WHERE user_id = $1
+            "#,
+            Uuid::from(user.id),
+            profile_updated_at,
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        DatabaseError::ensure_affected_rows(&res, 1)?;
+
+        user.profile_updated_at = Some(profile_updated_at);
+
+        Ok(user)
+    }
+
     #[tracing::instrument(
         name = "db.user.list",
         skip_all,
@@ -439,6 +528,14 @@ impl UserRepository for PgUserRepository<'_> {
                 Expr::col((Users::Table, Users::CanRequestAdmin)),
                 UserLookupIden::CanRequestAdmin,
             )
+            .expr_as(
+                Expr::col((Users::Table, Users::Locale)),
+                UserLookupIden::Locale,
+            )
+            .expr_as(
+                Expr::col((Users::Table, Users::ProfileUpdatedAt)),
+                UserLookupIden::ProfileUpdatedAt,
+            )
             .from(Users::Table)
             .apply_filter(filter)
             .generate_pagination((Users::Table, Users::UserId), pagination)
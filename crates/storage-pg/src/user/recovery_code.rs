@@ -0,0 +1,274 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use mas_data_model::{User, UserRecoveryCode};
+use mas_storage::{Clock, user::UserRecoveryCodeRepository};
+use rand::RngCore;
+use sqlx::PgConnection;
+use ulid::Ulid;
+use uuid::Uuid;
+
+use crate::{DatabaseError, DatabaseInconsistencyError, tracing::ExecuteExt};
+
+/// An implementation of [`UserRecoveryCodeRepository`] for a PostgreSQL
+/// connection
+pub struct PgUserRecoveryCodeRepository<'c> {
+    conn: &'c mut PgConnection,
+}
+
+impl<'c> PgUserRecoveryCodeRepository<'c> {
+    /// Create a new [`PgUserRecoveryCodeRepository`] from an active
+    /// PostgreSQL connection
+    pub fn new(conn: &'c mut PgConnection) -> Self {
+        Self { conn }
+    }
+}
+
+struct UserRecoveryCodeLookup {
+    user_recovery_code_id: Uuid,
+    user_id: Uuid,
+    hashed_code: String,
+    version: i32,
+    created_at: DateTime<Utc>,
+    consumed_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<UserRecoveryCodeLookup> for UserRecoveryCode {
+    type Error = DatabaseInconsistencyError;
+
+    fn try_from(value: UserRecoveryCodeLookup) -> Result<Self, Self::Error> {
+        let id = Ulid::from(value.user_recovery_code_id);
+        let version = value.version.try_into().map_err(|e| {
+            DatabaseInconsistencyError::on("user_recovery_codes")
+                .column("version")
+                .row(id)
+                .source(e)
+        })?;
+
+        Ok(UserRecoveryCode {
+            id,
+            user_id: Ulid::from(value.user_id),
+            hashed_code: value.hashed_code,
+            version,
+            created_at: value.created_at,
+            consumed_at: value.consumed_at,
+        })
+    }
+}
+
+#[async_trait]
+impl UserRecoveryCodeRepository for PgUserRecoveryCodeRepository<'_> {
+    type Error = DatabaseError;
+
+    #[tracing::instrument(
+        name = "db.user_recovery_code.lookup",
+        skip_all,
+        fields(
+            db.query.text,
+            user_recovery_code.id = %id,
+        ),
+        err,
+    )]
+    async fn lookup(&mut self, id: Ulid) -> Result<Option<UserRecoveryCode>, Self::Error> {
+        let res = sqlx::query_as!(
+            UserRecoveryCodeLookup,
+            r#"
+                SELECT user_recovery_code_id
+                     , user_id
+                     , hashed_code
+                     , version
+                     , created_at
+                     , consumed_at
+                FROM user_recovery_codes
+                WHERE user_recovery_code_id = $1
+            "#,
+            Uuid::from(id),
+        )
+        .traced()
+        .fetch_optional(&mut *self.conn)
+        .await?;
+
+        let Some(res) = res else { return Ok(None) };
+
+        Ok(Some(res.try_into()?))
+    }
+
+    #[tracing::instrument(
+        name = "db.user_recovery_code.unused",
+        skip_all,
+        fields(
+            db.query.text,
+            %user.id,
+        ),
+        err,
+    )]
+    async fn unused(&mut self, user: &User) -> Result<Vec<UserRecoveryCode>, Self::Error> {
+        let res = sqlx::query_as!(
+            UserRecoveryCodeLookup,
+            r#"
+                SELECT user_recovery_code_id
+                     , user_id
+                     , hashed_code
+                     , version
+                     , created_at
+                     , consumed_at
+                FROM user_recovery_codes
+                WHERE user_id = $1
+                  AND consumed_at IS NULL
+                ORDER BY user_recovery_code_id ASC
+            "#,
+            Uuid::from(user.id),
+        )
+        .traced()
+        .fetch_all(&mut *self.conn)
+        .await?;
+
+        let codes = res
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<Result<_, _>>()?;
+
+        Ok(codes)
+    }
+
+    #[tracing::instrument(
+        name = "db.user_recovery_code.count_unused",
+        skip_all,
+        fields(
+            db.query.text,
+            %user.id,
+        ),
+        err,
+    )]
+    async fn count_unused(&mut self, user: &User) -> Result<usize, Self::Error> {
+        let count = sqlx::query_scalar!(
+            r#"
+                SELECT COUNT(*) AS "count!"
+                FROM user_recovery_codes
+                WHERE user_id = $1
+                  AND consumed_at IS NULL
+            "#,
+            Uuid::from(user.id),
+        )
+        .traced()
+        .fetch_one(&mut *self.conn)
+        .await?;
+
+        count
+            .try_into()
+            .map_err(DatabaseError::to_invalid_operation)
+    }
+
+    #[tracing::instrument(
+        name = "db.user_recovery_code.add",
+        skip_all,
+        fields(
+            db.query.text,
+            %user.id,
+            user_recovery_code.id,
+        ),
+        err,
+    )]
+    async fn add(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        user: &User,
+        version: u16,
+        hashed_code: String,
+    ) -> Result<UserRecoveryCode, Self::Error> {
+        let created_at = clock.now();
+        let id = Ulid::from_datetime_with_source(created_at.into(), rng);
+        tracing::Span::current().record("user_recovery_code.id", tracing::field::display(id));
+
+        sqlx::query!(
+            r#"
+                INSERT INTO user_recovery_codes
+                    (user_recovery_code_id, user_id, hashed_code, version, created_at)
+                VALUES ($1, $2, $3, $4, $5)
+            "#,
+            Uuid::from(id),
+            Uuid::from(user.id),
+            &hashed_code,
+            i32::from(version),
+            created_at,
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(UserRecoveryCode {
+            id,
+            user_id: user.id,
+            hashed_code,
+            version,
+            created_at,
+            consumed_at: None,
+        })
+    }
+
+    #[tracing::instrument(
+        name = "db.user_recovery_code.consume",
+        skip_all,
+        fields(
+            db.query.text,
+            %user_recovery_code.id,
+        ),
+        err,
+    )]
+    async fn consume(
+        &mut self,
+        clock: &dyn Clock,
+        mut user_recovery_code: UserRecoveryCode,
+    ) -> Result<UserRecoveryCode, Self::Error> {
+        let consumed_at = clock.now();
+        let res = sqlx::query!(
+            r#"
+                UPDATE user_recovery_codes
+                SET consumed_at = $2
+                WHERE user_recovery_code_id = $1
+                  AND consumed_at IS NULL
+            "#,
+            Uuid::from(user_recovery_code.id),
+            consumed_at,
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        DatabaseError::ensure_affected_rows(&res, 1)?;
+
+        user_recovery_code.consumed_at = Some(consumed_at);
+        Ok(user_recovery_code)
+    }
+
+    #[tracing::instrument(
+        name = "db.user_recovery_code.remove_all",
+        skip_all,
+        fields(
+            db.query.text,
+            %user.id,
+        ),
+        err,
+    )]
+    async fn remove_all(&mut self, user: &User) -> Result<usize, Self::Error> {
+        let res = sqlx::query!(
+            r#"
+                DELETE FROM user_recovery_codes
+                WHERE user_id = $1
+            "#,
+            Uuid::from(user.id),
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        res.rows_affected()
+            .try_into()
+            .map_err(DatabaseError::to_invalid_operation)
+    }
+}
@@ -10,7 +10,8 @@ use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use mas_data_model::{
     Authentication, AuthenticationMethod, BrowserSession, Password,
-    UpstreamOAuthAuthorizationSession, User, UserAgent,
+    UpstreamOAuthAuthorizationSession, User, UserAgent, UserEmailAuthentication, UserPasskey,
+    UserRecoveryCode, UserTotpAuthenticator,
 };
 use mas_storage::{
     Clock, Page, Pagination,
@@ -61,6 +62,8 @@ struct SessionLookup {
     user_locked_at: Option<DateTime<Utc>>,
     user_deactivated_at: Option<DateTime<Utc>>,
     user_can_request_admin: bool,
+    user_locale: Option<String>,
+    user_profile_updated_at: Option<DateTime<Utc>>,
 }
 
 impl TryFrom<SessionLookup> for BrowserSession {
@@ -76,6 +79,8 @@ impl TryFrom<SessionLookup> for BrowserSession {
             locked_at: value.user_locked_at,
             deactivated_at: value.user_deactivated_at,
             can_request_admin: value.user_can_request_admin,
+            locale: value.user_locale,
+            profile_updated_at: value.user_profile_updated_at,
         };
 
         Ok(BrowserSession {
@@ -95,6 +100,11 @@ struct AuthenticationLookup {
     created_at: DateTime<Utc>,
     user_password_id: Option<Uuid>,
     upstream_oauth_authorization_session_id: Option<Uuid>,
+    user_totp_authenticator_id: Option<Uuid>,
+    user_passkey_id: Option<Uuid>,
+    user_recovery_code_id: Option<Uuid>,
+    user_email_authentication_id: Option<Uuid>,
+    ldap_dn: Option<String>,
 }
 
 impl TryFrom<AuthenticationLookup> for Authentication {
@@ -107,12 +117,40 @@ impl TryFrom<AuthenticationLookup> for Authentication {
             value
                 .upstream_oauth_authorization_session_id
                 .map(Into::into),
+            value.user_totp_authenticator_id.map(Into::into),
+            value.user_passkey_id.map(Into::into),
+            value.user_recovery_code_id.map(Into::into),
+            value.user_email_authentication_id.map(Into::into),
+            value.ldap_dn,
         ) {
-            (Some(user_password_id), None) => AuthenticationMethod::Password { user_password_id },
-            (None, Some(upstream_oauth2_session_id)) => AuthenticationMethod::UpstreamOAuth2 {
-                upstream_oauth2_session_id,
-            },
-            (None, None) => AuthenticationMethod::Unknown,
+            (Some(user_password_id), None, None, None, None, None, None) => {
+                AuthenticationMethod::Password { user_password_id }
+            }
+            (None, Some(upstream_oauth2_session_id), None, None, None, None, None) => {
+                AuthenticationMethod::UpstreamOAuth2 {
+                    upstream_oauth2_session_id,
+                }
+            }
+            (None, None, Some(user_totp_authenticator_id), None, None, None, None) => {
+                AuthenticationMethod::Totp {
+                    user_totp_authenticator_id,
+                }
+            }
+            (None, None, None, Some(user_passkey_id), None, None, None) => {
+                AuthenticationMethod::Passkey { user_passkey_id }
+            }
+            (None, None, None, None, Some(user_recovery_code_id), None, None) => {
+                AuthenticationMethod::RecoveryCode {
+                    user_recovery_code_id,
+                }
+            }
+            (None, None, None, None, None, Some(user_email_authentication_id), None) => {
+                AuthenticationMethod::EmailCode {
+                    user_email_authentication_id,
+                }
+            }
+            (None, None, None, None, None, None, Some(dn)) => AuthenticationMethod::Ldap { dn },
+            (None, None, None, None, None, None, None) => AuthenticationMethod::Unknown,
             _ => {
                 return Err(DatabaseInconsistencyError::on("user_session_authentications").row(id));
             }
@@ -177,6 +215,8 @@ impl BrowserSessionRepository for PgBrowserSessionRepository<'_> {
                      , u.locked_at             AS "user_locked_at"
                      , u.deactivated_at        AS "user_deactivated_at"
                      , u.can_request_admin     AS "user_can_request_admin"
+                     , u.locale                AS "user_locale"
+                     , u.profile_updated_at    AS "user_profile_updated_at"
                 FROM user_sessions s
                 INNER JOIN users u
                     USING (user_id)
@@ -367,6 +407,14 @@ impl BrowserSessionRepository for PgBrowserSessionRepository<'_> {
                 Expr::col((Users::Table, Users::CanRequestAdmin)),
                 SessionLookupIden::UserCanRequestAdmin,
             )
+            .expr_as(
+                Expr::col((Users::Table, Users::Locale)),
+                SessionLookupIden::UserLocale,
+            )
+            .expr_as(
+                Expr::col((Users::Table, Users::ProfileUpdatedAt)),
+                SessionLookupIden::UserProfileUpdatedAt,
+            )
             .from(UserSessions::Table)
             .inner_join(
                 Users::Table,
@@ -515,6 +563,248 @@ impl BrowserSessionRepository for PgBrowserSessionRepository<'_> {
         })
     }
 
+    #[tracing::instrument(
+        name = "db.browser_session.authenticate_with_totp",
+        skip_all,
+        fields(
+            db.query.text,
+            %user_session.id,
+            %user_totp_authenticator.id,
+            user_session_authentication.id,
+        ),
+        err,
+    )]
+    async fn authenticate_with_totp(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        user_session: &BrowserSession,
+        user_totp_authenticator: &UserTotpAuthenticator,
+    ) -> Result<Authentication, Self::Error> {
+        let created_at = clock.now();
+        let id = Ulid::from_datetime_with_source(created_at.into(), rng);
+        tracing::Span::current().record(
+            "user_session_authentication.id",
+            tracing::field::display(id),
+        );
+
+        sqlx::query!(
+            r#"
+                INSERT INTO user_session_authentications
+                    (user_session_authentication_id, user_session_id, created_at, user_totp_authenticator_id)
+                VALUES ($1, $2, $3, $4)
+            "#,
+            Uuid::from(id),
+            Uuid::from(user_session.id),
+            created_at,
+            Uuid::from(user_totp_authenticator.id),
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(Authentication {
+            id,
+            created_at,
+            authentication_method: AuthenticationMethod::Totp {
+                user_totp_authenticator_id: user_totp_authenticator.id,
+            },
+        })
+    }
+
+    #[tracing::instrument(
+        name = "db.browser_session.authenticate_with_passkey",
+        skip_all,
+        fields(
+            db.query.text,
+            %user_session.id,
+            %user_passkey.id,
+            user_session_authentication.id,
+        ),
+        err,
+    )]
+    async fn authenticate_with_passkey(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        user_session: &BrowserSession,
+        user_passkey: &UserPasskey,
+    ) -> Result<Authentication, Self::Error> {
+        let created_at = clock.now();
+        let id = Ulid::from_datetime_with_source(created_at.into(), rng);
+        tracing::Span::current().record(
+            "user_session_authentication.id",
+            tracing::field::display(id),
+        );
+
+        sqlx::query!(
+            r#"
+                INSERT INTO user_session_authentications
+                    (user_session_authentication_id, user_session_id, created_at, user_passkey_id)
+                VALUES ($1, $2, $3, $4)
+            "#,
+            Uuid::from(id),
+            Uuid::from(user_session.id),
+            created_at,
+            Uuid::from(user_passkey.id),
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(Authentication {
+            id,
+            created_at,
+            authentication_method: AuthenticationMethod::Passkey {
+                user_passkey_id: user_passkey.id,
+            },
+        })
+    }
+
+    #[tracing::instrument(
+        name = "db.browser_session.authenticate_with_recovery_code",
+        skip_all,
+        fields(
+            db.query.text,
+            %user_session.id,
+            %user_recovery_code.id,
+            user_session_authentication.id,
+        ),
+        err,
+    )]
+    async fn authenticate_with_recovery_code(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        user_session: &BrowserSession,
+        user_recovery_code: &UserRecoveryCode,
+    ) -> Result<Authentication, Self::Error> {
+        let created_at = clock.now();
+        let id = Ulid::from_datetime_with_source(created_at.into(), rng);
+        tracing::Span::current().record(
+            "user_session_authentication.id",
+            tracing::field::display(id),
+        );
+
+        sqlx::query!(
+            r#"
+                INSERT INTO user_session_authentications
+                    (user_session_authentication_id, user_session_id, created_at, user_recovery_code_id)
+                VALUES ($1, $2, $3, $4)
+            "#,
+            Uuid::from(id),
+            Uuid::from(user_session.id),
+            created_at,
+            Uuid::from(user_recovery_code.id),
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(Authentication {
+            id,
+            created_at,
+            authentication_method: AuthenticationMethod::RecoveryCode {
+                user_recovery_code_id: user_recovery_code.id,
+            },
+        })
+    }
+
+    #[tracing::instrument(
+        name = "db.browser_session.authenticate_with_email_code",
+        skip_all,
+        fields(
+            db.query.text,
+            %user_session.id,
+            %user_email_authentication.id,
+            user_session_authentication.id,
+        ),
+        err,
+    )]
+    async fn authenticate_with_email_code(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        user_session: &BrowserSession,
+        user_email_authentication: &UserEmailAuthentication,
+    ) -> Result<Authentication, Self::Error> {
+        let created_at = clock.now();
+        let id = Ulid::from_datetime_with_source(created_at.into(), rng);
+        tracing::Span::current().record(
+            "user_session_authentication.id",
+            tracing::field::display(id),
+        );
+
+        sqlx::query!(
+            r#"
+                INSERT INTO user_session_authentications
+                    (user_session_authentication_id, user_session_id, created_at, user_email_authentication_id)
+                VALUES ($1, $2, $3, $4)
+            "#,
+            Uuid::from(id),
+            Uuid::from(user_session.id),
+            created_at,
+            Uuid::from(user_email_authentication.id),
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(Authentication {
+            id,
+            created_at,
+            authentication_method: AuthenticationMethod::EmailCode {
+                user_email_authentication_id: user_email_authentication.id,
+            },
+        })
+    }
+
+    #[tracing::instrument(
+        name = "db.browser_session.authenticate_with_ldap",
+        skip_all,
+        fields(
+            db.query.text,
+            %user_session.id,
+            user_session_authentication.id,
+        ),
+        err,
+    )]
+    async fn authenticate_with_ldap(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        user_session: &BrowserSession,
+        dn: String,
+    ) -> Result<Authentication, Self::Error> {
+        let created_at = clock.now();
+        let id = Ulid::from_datetime_with_source(created_at.into(), rng);
+        tracing::Span::current().record(
+            "user_session_authentication.id",
+            tracing::field::display(id),
+        );
+
+        sqlx::query!(
+            r#"
+                INSERT INTO user_session_authentications
+                    (user_session_authentication_id, user_session_id, created_at, ldap_dn)
+                VALUES ($1, $2, $3, $4)
+            "#,
+            Uuid::from(id),
+            Uuid::from(user_session.id),
+            created_at,
+            &dn,
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(Authentication {
+            id,
+            created_at,
+            authentication_method: AuthenticationMethod::Ldap { dn },
+        })
+    }
+
     #[tracing::instrument(
         name = "db.browser_session.get_last_authentication",
         skip_all,
@@ -535,6 +825,11 @@ impl BrowserSessionRepository for PgBrowserSessionRepository<'_> {
                      , created_at
                      , user_password_id
                      , upstream_oauth_authorization_session_id
+                     , user_totp_authenticator_id
+                     , user_passkey_id
+                     , user_recovery_code_id
+                     , user_email_authentication_id
+                     , ldap_dn
                 FROM user_session_authentications
                 WHERE user_session_id = $1
                 ORDER BY created_at DESC
@@ -554,6 +849,48 @@ impl BrowserSessionRepository for PgBrowserSessionRepository<'_> {
         Ok(Some(authentication))
     }
 
+    #[tracing::instrument(
+        name = "db.browser_session.list_authentications",
+        skip_all,
+        fields(
+            db.query.text,
+            %user_session.id,
+        ),
+        err,
+    )]
+    async fn list_authentications(
+        &mut self,
+        user_session: &BrowserSession,
+    ) -> Result<Vec<Authentication>, Self::Error> {
+        let authentications = sqlx::query_as!(
+            AuthenticationLookup,
+            r#"
+                SELECT user_session_authentication_id
+                     , created_at
+                     , user_password_id
+                     , upstream_oauth_authorization_session_id
+                     , user_totp_authenticator_id
+                     , user_passkey_id
+                     , user_recovery_code_id
+                     , user_email_authentication_id
+                     , ldap_dn
+                FROM user_session_authentications
+                WHERE user_session_id = $1
+                ORDER BY created_at ASC, user_session_authentication_id ASC
+            "#,
+            Uuid::from(user_session.id),
+        )
+        .traced()
+        .fetch_all(&mut *self.conn)
+        .await?;
+
+        authentications
+            .into_iter()
+            .map(Authentication::try_from)
+            .collect::<Result<_, _>>()
+            .map_err(Into::into)
+    }
+
     #[tracing::instrument(
         name = "db.browser_session.record_batch_activity",
         skip_all,
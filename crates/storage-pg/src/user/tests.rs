@@ -10,7 +10,8 @@ use mas_storage::{
     clock::MockClock,
     user::{
         BrowserSessionFilter, BrowserSessionRepository, UserEmailFilter, UserEmailRepository,
-        UserFilter, UserPasswordRepository, UserRepository,
+        UserFilter, UserPasswordRepository, UserRecoveryCodeRepository, UserRepository,
+        UserTotpRepository,
     },
 };
 use rand::SeedableRng;
@@ -145,6 +146,31 @@ async fn test_user_repo(pool: PgPool) {
     let user = repo.user().lookup(user.id).await.unwrap().unwrap();
     assert!(!user.can_request_admin);
 
+    // Set the locale
+    assert_eq!(user.locale, None);
+    assert_eq!(user.profile_updated_at, None);
+    assert_eq!(user.profile_updated_at(), user.created_at);
+    clock.advance(Duration::try_minutes(1).unwrap());
+    let user = repo
+        .user()
+        .set_locale(&clock, user, "fr-FR".to_owned())
+        .await
+        .unwrap();
+    assert_eq!(user.locale.as_deref(), Some("fr-FR"));
+    assert_eq!(user.profile_updated_at, Some(clock.now()));
+
+    // Check that the property is retrieved on lookup
+    let user = repo.user().lookup(user.id).await.unwrap().unwrap();
+    assert_eq!(user.locale.as_deref(), Some("fr-FR"));
+    assert_eq!(user.profile_updated_at, Some(clock.now()));
+
+    // Record another profile change
+    clock.advance(Duration::try_minutes(1).unwrap());
+    let user = repo.user().touch_profile(&clock, user).await.unwrap();
+    assert_eq!(user.profile_updated_at(), clock.now());
+    let user = repo.user().lookup(user.id).await.unwrap().unwrap();
+    assert_eq!(user.profile_updated_at(), clock.now());
+
     assert_eq!(repo.user().count(all).await.unwrap(), 1);
     assert_eq!(repo.user().count(admin).await.unwrap(), 0);
     assert_eq!(repo.user().count(non_admin).await.unwrap(), 1);
@@ -455,6 +481,150 @@ async fn test_user_email_repo_authentications(pool: PgPool) {
     assert!(res.is_err());
 }
 
+/// Test the email authentications used to log in without a password
+#[sqlx::test(migrator = "crate::MIGRATOR")]
+async fn test_user_email_repo_login_authentications(pool: PgPool) {
+    let mut repo = PgRepository::from_pool(&pool).await.unwrap().boxed();
+    let mut rng = ChaChaRng::seed_from_u64(42);
+    let clock = MockClock::default();
+
+    let user = repo
+        .user()
+        .add(&mut rng, &clock, "alice".to_owned())
+        .await
+        .unwrap();
+
+    // Create an authentication to log in as the user
+    let authentication = repo
+        .user_email()
+        .add_authentication_for_login(
+            &mut rng,
+            &clock,
+            "alice@example.com".to_owned(),
+            Some(&user),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(authentication.email, "alice@example.com");
+    assert_eq!(authentication.user_id, Some(user.id));
+    assert_eq!(authentication.user_session_id, None);
+    assert_eq!(authentication.user_registration_id, None);
+    assert_eq!(authentication.completed_at, None);
+
+    let lookup = repo
+        .user_email()
+        .lookup_authentication(authentication.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(lookup, authentication);
+
+    // An authentication for an unknown address has no user
+    let unknown = repo
+        .user_email()
+        .add_authentication_for_login(&mut rng, &clock, "bob@example.com".to_owned(), None)
+        .await
+        .unwrap();
+    let lookup = repo
+        .user_email()
+        .lookup_authentication(unknown.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(lookup.user_id, None);
+
+    // Complete the authentication and use it to authenticate a session
+    let code = repo
+        .user_email()
+        .add_authentication_code(
+            &mut rng,
+            &clock,
+            Duration::minutes(5),
+            &authentication,
+            "123456".to_owned(),
+        )
+        .await
+        .unwrap();
+    let authentication = repo
+        .user_email()
+        .complete_authentication(&clock, authentication, &code)
+        .await
+        .unwrap();
+
+    let session = repo
+        .browser_session()
+        .add(&mut rng, &clock, &user, None)
+        .await
+        .unwrap();
+    repo.browser_session()
+        .authenticate_with_email_code(&mut rng, &clock, &session, &authentication)
+        .await
+        .unwrap();
+
+    let last = repo
+        .browser_session()
+        .get_last_authentication(&session)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        last.authentication_method,
+        mas_data_model::AuthenticationMethod::EmailCode {
+            user_email_authentication_id: authentication.id,
+        }
+    );
+}
+
+/// Test authenticating browser sessions with a password checked against an
+/// LDAP directory
+#[sqlx::test(migrator = "crate::MIGRATOR")]
+async fn test_browser_session_ldap_authentication(pool: PgPool) {
+    const DN: &str = "uid=john,ou=people,dc=example,dc=com";
+
+    let mut repo = PgRepository::from_pool(&pool).await.unwrap().boxed();
+    let mut rng = ChaChaRng::seed_from_u64(42);
+    let clock = MockClock::default();
+
+    let user = repo
+        .user()
+        .add(&mut rng, &clock, "john".to_owned())
+        .await
+        .unwrap();
+    let session = repo
+        .browser_session()
+        .add(&mut rng, &clock, &user, None)
+        .await
+        .unwrap();
+
+    let authentication = repo
+        .browser_session()
+        .authenticate_with_ldap(&mut rng, &clock, &session, DN.to_owned())
+        .await
+        .unwrap();
+    assert_eq!(
+        authentication.authentication_method,
+        mas_data_model::AuthenticationMethod::Ldap { dn: DN.to_owned() }
+    );
+
+    let last = repo
+        .browser_session()
+        .get_last_authentication(&session)
+        .await
+        .unwrap()
+        .expect("session should have an authentication");
+    assert_eq!(last, authentication);
+
+    let all = repo
+        .browser_session()
+        .list_authentications(&session)
+        .await
+        .unwrap();
+    assert_eq!(all, vec![authentication]);
+
+    repo.save().await.unwrap();
+}
+
 /// Test the user password repository implementation.
 #[sqlx::test(migrator = "crate::MIGRATOR")]
 async fn test_user_password_repo(pool: PgPool) {
@@ -538,6 +708,387 @@ async fn test_user_password_repo(pool: PgPool) {
     repo.save().await.unwrap();
 }
 
+/// Test the user TOTP repository implementation, and authenticating browser
+/// sessions with a TOTP authenticator
+#[sqlx::test(migrator = "crate::MIGRATOR")]
+async fn test_user_totp_repo(pool: PgPool) {
+    let mut repo = PgRepository::from_pool(&pool).await.unwrap().boxed();
+    let mut rng = ChaChaRng::seed_from_u64(42);
+    let clock = MockClock::default();
+
+    let user = repo
+        .user()
+        .add(&mut rng, &clock, "john".to_owned())
+        .await
+        .unwrap();
+
+    // User should have no authenticator
+    assert!(repo.user_totp().find(&user).await.unwrap().is_none());
+
+    let authenticator = repo
+        .user_totp()
+        .add(&mut rng, &clock, &user, "encrypted".to_owned())
+        .await
+        .unwrap();
+    assert!(!authenticator.is_verified());
+    assert_eq!(authenticator.last_used_step, None);
+
+    // It can be looked up by ID and by user
+    let lookup = repo
+        .user_totp()
+        .lookup(authenticator.id)
+        .await
+        .unwrap()
+        .expect("authenticator should be found");
+    assert_eq!(lookup, authenticator);
+
+    let lookup = repo
+        .user_totp()
+        .find(&user)
+        .await
+        .unwrap()
+        .expect("authenticator should be found");
+    assert_eq!(lookup, authenticator);
+
+    // Users can only have one authenticator
+    let mut conflict_repo = PgRepository::from_pool(&pool).await.unwrap().boxed();
+    repo.save().await.unwrap();
+    assert!(
+        conflict_repo
+            .user_totp()
+            .add(&mut rng, &clock, &user, "other".to_owned())
+            .await
+            .is_err()
+    );
+    conflict_repo.cancel().await.unwrap();
+    let mut repo = PgRepository::from_pool(&pool).await.unwrap().boxed();
+
+    // Verify it and record a use
+    let authenticator = repo
+        .user_totp()
+        .verify(&clock, authenticator)
+        .await
+        .unwrap();
+    assert!(authenticator.is_verified());
+    let authenticator = repo
+        .user_totp()
+        .record_use(authenticator, 1234)
+        .await
+        .unwrap();
+    assert_eq!(authenticator.last_used_step, Some(1234));
+
+    let lookup = repo
+        .user_totp()
+        .find(&user)
+        .await
+        .unwrap()
+        .expect("authenticator should be found");
+    assert_eq!(lookup, authenticator);
+
+    // Authenticate a browser session with it
+    let session = repo
+        .browser_session()
+        .add(&mut rng, &clock, &user, None)
+        .await
+        .unwrap();
+    let password = repo
+        .user_password()
+        .add(&mut rng, &clock, &user, 1, "hash".to_owned(), None)
+        .await
+        .unwrap();
+    let password_authentication = repo
+        .browser_session()
+        .authenticate_with_password(&mut rng, &clock, &session, &password)
+        .await
+        .unwrap();
+    clock.advance(Duration::microseconds(1000 * 1000));
+    let totp_authentication = repo
+        .browser_session()
+        .authenticate_with_totp(&mut rng, &clock, &session, &authenticator)
+        .await
+        .unwrap();
+
+    let last = repo
+        .browser_session()
+        .get_last_authentication(&session)
+        .await
+        .unwrap()
+        .expect("session should have an authentication");
+    assert_eq!(last, totp_authentication);
+
+    let all = repo
+        .browser_session()
+        .list_authentications(&session)
+        .await
+        .unwrap();
+    assert_eq!(all, vec![password_authentication, totp_authentication]);
+
+    // Remove the authenticator
+    repo.user_totp().remove(authenticator).await.unwrap();
+    assert!(repo.user_totp().find(&user).await.unwrap().is_none());
+
+    // The authentication is still there, but we don't know how it happened
+    let last = repo
+        .browser_session()
+        .get_last_authentication(&session)
+        .await
+        .unwrap()
+        .expect("session should have an authentication");
+    assert_eq!(
+        last.authentication_method,
+        mas_data_model::AuthenticationMethod::Unknown
+    );
+
+    repo.save().await.unwrap();
+}
+
+/// Test the user passkey repository implementation, and authenticating browser
+/// sessions with a passkey
+#[sqlx::test(migrator = "crate::MIGRATOR")]
+async fn test_user_passkey_repo(pool: PgPool) {
+    let mut repo = PgRepository::from_pool(&pool).await.unwrap().boxed();
+    let mut rng = ChaChaRng::seed_from_u64(42);
+    let clock = MockClock::default();
+
+    let user = repo
+        .user()
+        .add(&mut rng, &clock, "john".to_owned())
+        .await
+        .unwrap();
+
+    // User should have no passkey
+    assert!(repo.user_passkey().all(&user).await.unwrap().is_empty());
+
+    let passkey = repo
+        .user_passkey()
+        .add(
+            &mut rng,
+            &clock,
+            &user,
+            "credential".to_owned(),
+            "My laptop".to_owned(),
+            vec![1, 2, 3],
+            0,
+            vec!["internal".to_owned(), "hybrid".to_owned()],
+        )
+        .await
+        .unwrap();
+    assert_eq!(passkey.last_used_at, None);
+
+    // It can be looked up by ID and by credential ID
+    let lookup = repo
+        .user_passkey()
+        .lookup(passkey.id)
+        .await
+        .unwrap()
+        .expect("passkey should be found");
+    assert_eq!(lookup, passkey);
+
+    let lookup = repo
+        .user_passkey()
+        .find_by_credential_id("credential")
+        .await
+        .unwrap()
+        .expect("passkey should be found");
+    assert_eq!(lookup, passkey);
+
+    assert!(
+        repo.user_passkey()
+            .find_by_credential_id("other")
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    // Rename it and record a use
+    let passkey = repo
+        .user_passkey()
+        .rename(passkey, "My phone".to_owned())
+        .await
+        .unwrap();
+    assert_eq!(passkey.name, "My phone");
+
+    let passkey = repo
+        .user_passkey()
+        .record_use(&clock, passkey, 42)
+        .await
+        .unwrap();
+    assert_eq!(passkey.sign_count, 42);
+    assert_eq!(passkey.last_used_at, Some(clock.now()));
+
+    let all = repo.user_passkey().all(&user).await.unwrap();
+    assert_eq!(all, vec![passkey.clone()]);
+
+    // Authenticate a browser session with it
+    let session = repo
+        .browser_session()
+        .add(&mut rng, &clock, &user, None)
+        .await
+        .unwrap();
+    let authentication = repo
+        .browser_session()
+        .authenticate_with_passkey(&mut rng, &clock, &session, &passkey)
+        .await
+        .unwrap();
+
+    let last = repo
+        .browser_session()
+        .get_last_authentication(&session)
+        .await
+        .unwrap()
+        .expect("session should have an authentication");
+    assert_eq!(last, authentication);
+
+    // Challenges can only be completed once
+    let challenge = repo
+        .user_passkey()
+        .add_challenge(&mut rng, &clock, Some(&session), "challenge".to_owned())
+        .await
+        .unwrap();
+    assert!(!challenge.is_completed());
+    assert_eq!(challenge.user_session_id, Some(session.id));
+
+    let lookup = repo
+        .user_passkey()
+        .lookup_challenge(challenge.id)
+        .await
+        .unwrap()
+        .expect("challenge should be found");
+    assert_eq!(lookup, challenge);
+
+    let completed = repo
+        .user_passkey()
+        .complete_challenge(&clock, challenge.clone())
+        .await
+        .unwrap();
+    assert!(completed.is_completed());
+    assert!(
+        repo.user_passkey()
+            .complete_challenge(&clock, challenge)
+            .await
+            .is_err()
+    );
+
+    // Challenges without a session are allowed for passwordless logins
+    let challenge = repo
+        .user_passkey()
+        .add_challenge(&mut rng, &clock, None, "challenge".to_owned())
+        .await
+        .unwrap();
+    assert_eq!(challenge.user_session_id, None);
+
+    // Remove the passkey
+    repo.user_passkey().remove(passkey).await.unwrap();
+    assert!(repo.user_passkey().all(&user).await.unwrap().is_empty());
+
+    repo.save().await.unwrap();
+}
+
+/// Test the user recovery code repository implementation, and authenticating
+/// browser sessions with a recovery code
+#[sqlx::test(migrator = "crate::MIGRATOR")]
+async fn test_user_recovery_code_repo(pool: PgPool) {
+    let mut repo = PgRepository::from_pool(&pool).await.unwrap().boxed();
+    let mut rng = ChaChaRng::seed_from_u64(42);
+    let clock = MockClock::default();
+
+    let user = repo
+        .user()
+        .add(&mut rng, &clock, "john".to_owned())
+        .await
+        .unwrap();
+
+    // User should have no recovery code
+    assert!(
+        repo.user_recovery_code()
+            .unused(&user)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    assert_eq!(
+        repo.user_recovery_code().count_unused(&user).await.unwrap(),
+        0
+    );
+
+    let first = repo
+        .user_recovery_code()
+        .add(&mut rng, &clock, &user, 1, "hash1".to_owned())
+        .await
+        .unwrap();
+    let second = repo
+        .user_recovery_code()
+        .add(&mut rng, &clock, &user, 1, "hash2".to_owned())
+        .await
+        .unwrap();
+    assert!(!first.is_consumed());
+    assert_eq!(
+        repo.user_recovery_code().count_unused(&user).await.unwrap(),
+        2
+    );
+
+    let lookup = repo
+        .user_recovery_code()
+        .lookup(first.id)
+        .await
+        .unwrap()
+        .expect("recovery code should be found");
+    assert_eq!(lookup, first);
+
+    // Codes can only be consumed once
+    let consumed = repo
+        .user_recovery_code()
+        .consume(&clock, first.clone())
+        .await
+        .unwrap();
+    assert_eq!(consumed.consumed_at, Some(clock.now()));
+    assert!(
+        repo.user_recovery_code()
+            .consume(&clock, first)
+            .await
+            .is_err()
+    );
+
+    let unused = repo.user_recovery_code().unused(&user).await.unwrap();
+    assert_eq!(unused, vec![second]);
+    assert_eq!(
+        repo.user_recovery_code().count_unused(&user).await.unwrap(),
+        1
+    );
+
+    // Authenticate a browser session with it
+    let session = repo
+        .browser_session()
+        .add(&mut rng, &clock, &user, None)
+        .await
+        .unwrap();
+    let authentication = repo
+        .browser_session()
+        .authenticate_with_recovery_code(&mut rng, &clock, &session, &consumed)
+        .await
+        .unwrap();
+
+    let last = repo
+        .browser_session()
+        .get_last_authentication(&session)
+        .await
+        .unwrap()
+        .expect("session should have an authentication");
+    assert_eq!(last, authentication);
+
+    // Remove all the codes, used or not
+    assert_eq!(
+        repo.user_recovery_code().remove_all(&user).await.unwrap(),
+        2
+    );
+    assert_eq!(
+        repo.user_recovery_code().count_unused(&user).await.unwrap(),
+        0
+    );
+
+    repo.save().await.unwrap();
+}
+
 #[sqlx::test(migrator = "crate::MIGRATOR")]
 async fn test_user_session(pool: PgPool) {
     let mut repo = PgRepository::from_pool(&pool).await.unwrap();
@@ -0,0 +1,277 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use mas_data_model::{User, UserTotpAuthenticator};
+use mas_storage::{Clock, user::UserTotpRepository};
+use rand::RngCore;
+use sqlx::PgConnection;
+use ulid::Ulid;
+use uuid::Uuid;
+
+use crate::{DatabaseError, DatabaseInconsistencyError, tracing::ExecuteExt};
+
+/// An implementation of [`UserTotpRepository`] for a PostgreSQL connection
+pub struct PgUserTotpRepository<'c> {
+    conn: &'c mut PgConnection,
+}
+
+impl<'c> PgUserTotpRepository<'c> {
+    /// Create a new [`PgUserTotpRepository`] from an active PostgreSQL
+    /// connection
+    pub fn new(conn: &'c mut PgConnection) -> Self {
+        Self { conn }
+    }
+}
+
+struct UserTotpAuthenticatorLookup {
+    user_totp_authenticator_id: Uuid,
+    user_id: Uuid,
+    encrypted_secret: String,
+    created_at: DateTime<Utc>,
+    verified_at: Option<DateTime<Utc>>,
+    last_used_step: Option<i64>,
+}
+
+impl TryFrom<UserTotpAuthenticatorLookup> for UserTotpAuthenticator {
+    type Error = DatabaseInconsistencyError;
+
+    fn try_from(value: UserTotpAuthenticatorLookup) -> Result<Self, Self::Error> {
+        let id = Ulid::from(value.user_totp_authenticator_id);
+        let last_used_step = value
+            .last_used_step
+            .map(u64::try_from)
+            .transpose()
+            .map_err(|e| {
+                DatabaseInconsistencyError::on("user_totp_authenticators")
+                    .column("last_used_step")
+                    .row(id)
+                    .source(e)
+            })?;
+
+        Ok(UserTotpAuthenticator {
+            id,
+            user_id: Ulid::from(value.user_id),
+            encrypted_secret: value.encrypted_secret,
+            created_at: value.created_at,
+            verified_at: value.verified_at,
+            last_used_step,
+        })
+    }
+}
+
+#[async_trait]
+impl UserTotpRepository for PgUserTotpRepository<'_> {
+    type Error = DatabaseError;
+
+    #[tracing::instrument(
+        name = "db.user_totp.lookup",
+        skip_all,
+        fields(
+            db.query.text,
+            user_totp_authenticator.id = %id,
+        ),
+        err,
+    )]
+    async fn lookup(&mut self, id: Ulid) -> Result<Option<UserTotpAuthenticator>, Self::Error> {
+        let res = sqlx::query_as!(
+            UserTotpAuthenticatorLookup,
+            r#"
+                SELECT user_totp_authenticator_id
+                     , user_id
+                     , encrypted_secret
+                     , created_at
+                     , verified_at
+                     , last_used_step
+                FROM user_totp_authenticators
+                WHERE user_totp_authenticator_id = $1
+            "#,
+            Uuid::from(id),
+        )
+        .traced()
+        .fetch_optional(&mut *self.conn)
+        .await?;
+
+        let Some(res) = res else { return Ok(None) };
+
+        Ok(Some(res.try_into()?))
+    }
+
+    #[tracing::instrument(
+        name = "db.user_totp.find",
+        skip_all,
+        fields(
+            db.query.text,
+            %user.id,
+        ),
+        err,
+    )]
+    async fn find(&mut self, user: &User) -> Result<Option<UserTotpAuthenticator>, Self::Error> {
+        let res = sqlx::query_as!(
+            UserTotpAuthenticatorLookup,
+            r#"
+                SELECT user_totp_authenticator_id
+                     , user_id
+                     , encrypted_secret
+                     , created_at
+                     , verified_at
+                     , last_used_step
+                FROM user_totp_authenticators
+                WHERE user_id = $1
+            "#,
+            Uuid::from(user.id),
+        )
+        .traced()
+        .fetch_optional(&mut *self.conn)
+        .await?;
+
+        let Some(res) = res else { return Ok(None) };
+
+        Ok(Some(res.try_into()?))
+    }
+
+    #[tracing::instrument(
+        name = "db.user_totp.add",
+        skip_all,
+        fields(
+            db.query.text,
+            %user.id,
+            user_totp_authenticator.id,
+        ),
+        err,
+    )]
+    async fn add(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        user: &User,
+        encrypted_secret: String,
+    ) -> Result<UserTotpAuthenticator, Self::Error> {
+        let created_at = clock.now();
+        let id = Ulid::from_datetime_with_source(created_at.into(), rng);
+        tracing::Span::current().record("user_totp_authenticator.id", tracing::field::display(id));
+
+        sqlx::query!(
+            r#"
+                INSERT INTO user_totp_authenticators
+                    (user_totp_authenticator_id, user_id, encrypted_secret, created_at)
+                VALUES ($1, $2, $3, $4)
+            "#,
+            Uuid::from(id),
+            Uuid::from(user.id),
+            &encrypted_secret,
+            created_at,
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(UserTotpAuthenticator {
+            id,
+            user_id: user.id,
+            encrypted_secret,
+            created_at,
+            verified_at: None,
+            last_used_step: None,
+        })
+    }
+
+    #[tracing::instrument(
+        name = "db.user_totp.verify",
+        skip_all,
+        fields(
+            db.query.text,
+            %user_totp_authenticator.id,
+        ),
+        err,
+    )]
+    async fn verify(
+        &mut self,
+        clock: &dyn Clock,
+        mut user_totp_authenticator: UserTotpAuthenticator,
+    ) -> Result<UserTotpAuthenticator, Self::Error> {
+        let verified_at = clock.now();
+        let res = sqlx::query!(
+            r#"
+                UPDATE user_totp_authenticators
+                SET verified_at = $2
+                WHERE user_totp_authenticator_id = $1
+            "#,
+            Uuid::from(user_totp_authenticator.id),
+            verified_at,
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        DatabaseError::ensure_affected_rows(&res, 1)?;
+
+        user_totp_authenticator.verified_at = Some(verified_at);
+        Ok(user_totp_authenticator)
+    }
+
+    #[tracing::instrument(
+        name = "db.user_totp.record_use",
+        skip_all,
+        fields(
+            db.query.text,
+            %user_totp_authenticator.id,
+        ),
+        err,
+    )]
+    async fn record_use(
+        &mut self,
+        mut user_totp_authenticator: UserTotpAuthenticator,
+        step: u64,
+    ) -> Result<UserTotpAuthenticator, Self::Error> {
+        let res = sqlx::query!(
+            r#"
+                UPDATE user_totp_authenticators
+                SET last_used_step = $2
+                WHERE user_totp_authenticator_id = $1
+            "#,
+            Uuid::from(user_totp_authenticator.id),
+            i64::try_from(step).map_err(DatabaseError::to_invalid_operation)?,
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        DatabaseError::ensure_affected_rows(&res, 1)?;
+
+        user_totp_authenticator.last_used_step = Some(step);
+        Ok(user_totp_authenticator)
+    }
+
+    #[tracing::instrument(
+        name = "db.user_totp.remove",
+        skip_all,
+        fields(
+            db.query.text,
+            %user_totp_authenticator.id,
+        ),
+        err,
+    )]
+    async fn remove(
+        &mut self,
+        user_totp_authenticator: UserTotpAuthenticator,
+    ) -> Result<(), Self::Error> {
+        let res = sqlx::query!(
+            r#"
+                DELETE FROM user_totp_authenticators
+                WHERE user_totp_authenticator_id = $1
+            "#,
+            Uuid::from(user_totp_authenticator.id),
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        DatabaseError::ensure_affected_rows(&res, 1)?;
+
+        Ok(())
+    }
+}
@@ -121,6 +121,7 @@ pub mod compat;
 pub mod oauth2;
 pub mod policy_data;
 pub mod queue;
+pub mod signing_key;
 pub mod upstream_oauth2;
 pub mod user;
 
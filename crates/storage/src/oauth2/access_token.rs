@@ -60,10 +60,15 @@ pub trait OAuth2AccessTokenRepository: Send + Sync {
     /// * `access_token`: The access token to add
     /// * `expires_after`: The duration after which the access token expires. If
     ///   [`None`] the access token never expires
+    /// * `dpop_jkt`: The JWK thumbprint of the `DPoP` key the access token is
+    ///   bound to, if any
+    /// * `x5t_s256`: The SHA-256 thumbprint of the client certificate the
+    ///   access token is bound to, if any
     ///
     /// # Errors
     ///
     /// Returns [`Self::Error`] if the underlying repository fails
+    #[allow(clippy::too_many_arguments)]
     async fn add(
         &mut self,
         rng: &mut (dyn RngCore + Send),
@@ -71,6 +76,8 @@ pub trait OAuth2AccessTokenRepository: Send + Sync {
         session: &Session,
         access_token: String,
         expires_after: Option<Duration>,
+        dpop_jkt: Option<String>,
+        x5t_s256: Option<String>,
     ) -> Result<AccessToken, Self::Error>;
 
     /// Revoke an access token
@@ -136,6 +143,8 @@ repository_impl!(OAuth2AccessTokenRepository:
         session: &Session,
         access_token: String,
         expires_after: Option<Duration>,
+        dpop_jkt: Option<String>,
+        x5t_s256: Option<String>,
     ) -> Result<AccessToken, Self::Error>;
 
     async fn revoke(
@@ -8,7 +8,10 @@ use std::num::NonZeroU32;
 
 use async_trait::async_trait;
 use mas_data_model::{AuthorizationCode, AuthorizationGrant, Client, Session};
-use oauth2_types::{requests::ResponseMode, scope::Scope};
+use oauth2_types::{
+    requests::{ClaimsRequest, ResponseMode},
+    scope::Scope,
+};
 use rand_core::RngCore;
 use ulid::Ulid;
 use url::Url;
@@ -44,6 +47,9 @@ pub trait OAuth2AuthorizationGrantRepository: Send + Sync {
     ///   requested
     /// * `requires_consent`: Whether the client explicitly requested consent
     /// * `login_hint`: The login_hint the client sent, if set
+    /// * `claims`: The individual claims the client requested, if set
+    /// * `resources`: The resources the client requested access to, through
+    ///   resource indicators
     ///
     /// # Errors
     ///
@@ -64,6 +70,8 @@ pub trait OAuth2AuthorizationGrantRepository: Send + Sync {
         response_type_id_token: bool,
         requires_consent: bool,
         login_hint: Option<String>,
+        claims: Option<ClaimsRequest>,
+        resources: Vec<Url>,
     ) -> Result<AuthorizationGrant, Self::Error>;
 
     /// Lookup an authorization grant by its ID
@@ -93,6 +101,22 @@ pub trait OAuth2AuthorizationGrantRepository: Send + Sync {
     async fn find_by_code(&mut self, code: &str)
     -> Result<Option<AuthorizationGrant>, Self::Error>;
 
+    /// Find the authorization grant which created a [`Session`]
+    ///
+    /// Returns the authorization grant if found, `None` otherwise
+    ///
+    /// # Parameters
+    ///
+    /// * `session`: The session created by the authorization grant
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn find_by_session(
+        &mut self,
+        session: &Session,
+    ) -> Result<Option<AuthorizationGrant>, Self::Error>;
+
     /// Fulfill an authorization grant, by giving the [`Session`] that it
     /// created
     ///
@@ -165,6 +189,8 @@ repository_impl!(OAuth2AuthorizationGrantRepository:
         response_type_id_token: bool,
         requires_consent: bool,
         login_hint: Option<String>,
+        claims: Option<ClaimsRequest>,
+        resources: Vec<Url>,
     ) -> Result<AuthorizationGrant, Self::Error>;
 
     async fn lookup(&mut self, id: Ulid) -> Result<Option<AuthorizationGrant>, Self::Error>;
@@ -172,6 +198,11 @@ repository_impl!(OAuth2AuthorizationGrantRepository:
     async fn find_by_code(&mut self, code: &str)
         -> Result<Option<AuthorizationGrant>, Self::Error>;
 
+    async fn find_by_session(
+        &mut self,
+        session: &Session,
+    ) -> Result<Option<AuthorizationGrant>, Self::Error>;
+
     async fn fulfill(
         &mut self,
         clock: &dyn Clock,
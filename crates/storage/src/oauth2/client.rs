@@ -7,10 +7,17 @@
 use std::collections::{BTreeMap, BTreeSet};
 
 use async_trait::async_trait;
-use mas_data_model::{Client, User};
-use mas_iana::{jose::JsonWebSignatureAlg, oauth::OAuthClientAuthenticationMethod};
+use mas_data_model::{AccessTokenFormat, Client, User};
+use mas_iana::{
+    jose::{JsonWebEncryptionAlg, JsonWebEncryptionEnc, JsonWebSignatureAlg},
+    oauth::OAuthClientAuthenticationMethod,
+};
 use mas_jose::jwk::PublicJsonWebKeySet;
-use oauth2_types::{oidc::ApplicationType, requests::GrantType, scope::Scope};
+use oauth2_types::{
+    oidc::{ApplicationType, SubjectType},
+    requests::GrantType,
+    scope::Scope,
+};
 use rand_core::RngCore;
 use ulid::Ulid;
 use url::Url;
@@ -109,6 +116,33 @@ pub trait OAuth2ClientRepository: Send + Sync {
     ///   when using the `client_secret_jwt` or `private_key_jwt` authentication
     ///   methods
     /// * `initiate_login_uri`: The URI used to initiate a login, if given
+    /// * `post_logout_redirect_uris`: The list of URIs the client can redirect
+    ///   to after an RP-initiated logout
+    /// * `backchannel_logout_uri`: The URI to which Logout Tokens are sent, if
+    ///   given
+    /// * `backchannel_logout_session_required`: Whether the client requires a
+    ///   `sid` Claim in Logout Tokens
+    /// * `require_pushed_authorization_requests`: Whether the client must use
+    ///   pushed authorization requests
+    /// * `request_object_signing_alg`: The algorithm request objects sent by
+    ///   this client must be signed with, if given
+    /// * `request_uris`: The list of `request_uri` values the client can use
+    ///   to pass request objects by reference
+    /// * `subject_type`: The subject type requested for this client, if given
+    /// * `sector_identifier_uri`: The URI used to determine the sector of
+    ///   pairwise subject identifiers, if given
+    /// * `introspection_signed_response_alg`: The algorithm used to sign JWT
+    ///   introspection responses returned to this client, if given
+    /// * `id_token_encrypted_response_alg`: The algorithm used to encrypt the
+    ///   content encryption key of ID tokens, if they are encrypted
+    /// * `id_token_encrypted_response_enc`: The algorithm used to encrypt the
+    ///   content of ID tokens, if they are encrypted
+    /// * `userinfo_encrypted_response_alg`: The algorithm used to encrypt the
+    ///   content encryption key of user info responses, if they are encrypted
+    /// * `userinfo_encrypted_response_enc`: The algorithm used to encrypt the
+    ///   content of user info responses, if they are encrypted
+    /// * `encrypted_registration_access_token`: The encrypted token the client
+    ///   uses to manage its registration, if any
     ///
     /// # Errors
     ///
@@ -135,6 +169,71 @@ pub trait OAuth2ClientRepository: Send + Sync {
         token_endpoint_auth_method: Option<OAuthClientAuthenticationMethod>,
         token_endpoint_auth_signing_alg: Option<JsonWebSignatureAlg>,
         initiate_login_uri: Option<Url>,
+        post_logout_redirect_uris: Vec<Url>,
+        backchannel_logout_uri: Option<Url>,
+        backchannel_logout_session_required: bool,
+        require_pushed_authorization_requests: bool,
+        request_object_signing_alg: Option<JsonWebSignatureAlg>,
+        request_uris: Vec<Url>,
+        subject_type: Option<SubjectType>,
+        sector_identifier_uri: Option<Url>,
+        introspection_signed_response_alg: Option<JsonWebSignatureAlg>,
+        id_token_encrypted_response_alg: Option<JsonWebEncryptionAlg>,
+        id_token_encrypted_response_enc: Option<JsonWebEncryptionEnc>,
+        userinfo_encrypted_response_alg: Option<JsonWebEncryptionAlg>,
+        userinfo_encrypted_response_enc: Option<JsonWebEncryptionEnc>,
+        encrypted_registration_access_token: Option<String>,
+    ) -> Result<Client, Self::Error>;
+
+    /// Replace the metadata of a dynamically registered client
+    ///
+    /// This also clears the metadata digest of the client, so that it is no
+    /// longer reused for new registrations with the same metadata.
+    ///
+    /// Returns the updated client
+    ///
+    /// # Parameters
+    ///
+    /// * `client`: The client to update
+    ///
+    /// The other parameters are the same as for [`Self::add`]
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    #[allow(clippy::too_many_arguments)]
+    async fn update(
+        &mut self,
+        client: Client,
+        redirect_uris: Vec<Url>,
+        encrypted_client_secret: Option<String>,
+        application_type: Option<ApplicationType>,
+        grant_types: Vec<GrantType>,
+        client_name: Option<String>,
+        logo_uri: Option<Url>,
+        client_uri: Option<Url>,
+        policy_uri: Option<Url>,
+        tos_uri: Option<Url>,
+        jwks_uri: Option<Url>,
+        jwks: Option<PublicJsonWebKeySet>,
+        id_token_signed_response_alg: Option<JsonWebSignatureAlg>,
+        userinfo_signed_response_alg: Option<JsonWebSignatureAlg>,
+        token_endpoint_auth_method: Option<OAuthClientAuthenticationMethod>,
+        token_endpoint_auth_signing_alg: Option<JsonWebSignatureAlg>,
+        initiate_login_uri: Option<Url>,
+        post_logout_redirect_uris: Vec<Url>,
+        backchannel_logout_uri: Option<Url>,
+        backchannel_logout_session_required: bool,
+        require_pushed_authorization_requests: bool,
+        request_object_signing_alg: Option<JsonWebSignatureAlg>,
+        request_uris: Vec<Url>,
+        subject_type: Option<SubjectType>,
+        sector_identifier_uri: Option<Url>,
+        introspection_signed_response_alg: Option<JsonWebSignatureAlg>,
+        id_token_encrypted_response_alg: Option<JsonWebEncryptionAlg>,
+        id_token_encrypted_response_enc: Option<JsonWebEncryptionEnc>,
+        userinfo_encrypted_response_alg: Option<JsonWebEncryptionAlg>,
+        userinfo_encrypted_response_enc: Option<JsonWebEncryptionEnc>,
     ) -> Result<Client, Self::Error>;
 
     /// Add or replace a static client
@@ -149,6 +248,21 @@ pub trait OAuth2ClientRepository: Send + Sync {
     /// * `jwks`: The client JWKS, if any
     /// * `jwks_uri`: The client JWKS URI, if any
     /// * `redirect_uris`: The list of redirect URIs used by this client
+    /// * `post_logout_redirect_uris`: The list of URIs the client can redirect
+    ///   to after an RP-initiated logout
+    /// * `backchannel_logout_uri`: The URI to which Logout Tokens are sent, if
+    ///   given
+    /// * `backchannel_logout_session_required`: Whether the client requires a
+    ///   `sid` Claim in Logout Tokens
+    /// * `require_pushed_authorization_requests`: Whether the client must use
+    ///   pushed authorization requests
+    /// * `jwt_bearer_subject`: The username of the user on behalf of which the
+    ///   client can use the JWT bearer grant, if any
+    /// * `access_token_format`: The format of the access tokens issued to this
+    ///   client
+    /// * `tls_client_auth_subject_dn`: The subject DN of the certificate the
+    ///   client must present when using the `tls_client_auth` authentication
+    ///   method, if any
     ///
     /// # Errors
     ///
@@ -162,6 +276,13 @@ pub trait OAuth2ClientRepository: Send + Sync {
         jwks: Option<PublicJsonWebKeySet>,
         jwks_uri: Option<Url>,
         redirect_uris: Vec<Url>,
+        post_logout_redirect_uris: Vec<Url>,
+        backchannel_logout_uri: Option<Url>,
+        backchannel_logout_session_required: bool,
+        require_pushed_authorization_requests: bool,
+        jwt_bearer_subject: Option<String>,
+        access_token_format: AccessTokenFormat,
+        tls_client_auth_subject_dn: Option<String>,
     ) -> Result<Client, Self::Error>;
 
     /// List all static clients
@@ -271,6 +392,54 @@ repository_impl!(OAuth2ClientRepository:
         token_endpoint_auth_method: Option<OAuthClientAuthenticationMethod>,
         token_endpoint_auth_signing_alg: Option<JsonWebSignatureAlg>,
         initiate_login_uri: Option<Url>,
+        post_logout_redirect_uris: Vec<Url>,
+        backchannel_logout_uri: Option<Url>,
+        backchannel_logout_session_required: bool,
+        require_pushed_authorization_requests: bool,
+        request_object_signing_alg: Option<JsonWebSignatureAlg>,
+        request_uris: Vec<Url>,
+        subject_type: Option<SubjectType>,
+        sector_identifier_uri: Option<Url>,
+        introspection_signed_response_alg: Option<JsonWebSignatureAlg>,
+        id_token_encrypted_response_alg: Option<JsonWebEncryptionAlg>,
+        id_token_encrypted_response_enc: Option<JsonWebEncryptionEnc>,
+        userinfo_encrypted_response_alg: Option<JsonWebEncryptionAlg>,
+        userinfo_encrypted_response_enc: Option<JsonWebEncryptionEnc>,
+        encrypted_registration_access_token: Option<String>,
+    ) -> Result<Client, Self::Error>;
+
+    async fn update(
+        &mut self,
+        client: Client,
+        redirect_uris: Vec<Url>,
+        encrypted_client_secret: Option<String>,
+        application_type: Option<ApplicationType>,
+        grant_types: Vec<GrantType>,
+        client_name: Option<String>,
+        logo_uri: Option<Url>,
+        client_uri: Option<Url>,
+        policy_uri: Option<Url>,
+        tos_uri: Option<Url>,
+        jwks_uri: Option<Url>,
+        jwks: Option<PublicJsonWebKeySet>,
+        id_token_signed_response_alg: Option<JsonWebSignatureAlg>,
+        userinfo_signed_response_alg: Option<JsonWebSignatureAlg>,
+        token_endpoint_auth_method: Option<OAuthClientAuthenticationMethod>,
+        token_endpoint_auth_signing_alg: Option<JsonWebSignatureAlg>,
+        initiate_login_uri: Option<Url>,
+        post_logout_redirect_uris: Vec<Url>,
+        backchannel_logout_uri: Option<Url>,
+        backchannel_logout_session_required: bool,
+        require_pushed_authorization_requests: bool,
+        request_object_signing_alg: Option<JsonWebSignatureAlg>,
+        request_uris: Vec<Url>,
+        subject_type: Option<SubjectType>,
+        sector_identifier_uri: Option<Url>,
+        introspection_signed_response_alg: Option<JsonWebSignatureAlg>,
+        id_token_encrypted_response_alg: Option<JsonWebEncryptionAlg>,
+        id_token_encrypted_response_enc: Option<JsonWebEncryptionEnc>,
+        userinfo_encrypted_response_alg: Option<JsonWebEncryptionAlg>,
+        userinfo_encrypted_response_enc: Option<JsonWebEncryptionEnc>,
     ) -> Result<Client, Self::Error>;
 
     async fn upsert_static(
@@ -281,6 +450,13 @@ repository_impl!(OAuth2ClientRepository:
         jwks: Option<PublicJsonWebKeySet>,
         jwks_uri: Option<Url>,
         redirect_uris: Vec<Url>,
+        post_logout_redirect_uris: Vec<Url>,
+        backchannel_logout_uri: Option<Url>,
+        backchannel_logout_session_required: bool,
+        require_pushed_authorization_requests: bool,
+        jwt_bearer_subject: Option<String>,
+        access_token_format: AccessTokenFormat,
+        tls_client_auth_subject_dn: Option<String>,
     ) -> Result<Client, Self::Error>;
 
     async fn all_static(&mut self) -> Result<Vec<Client>, Self::Error>;
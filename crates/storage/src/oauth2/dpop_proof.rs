@@ -0,0 +1,66 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use crate::{Clock, repository_impl};
+
+/// An [`OAuth2DpopProofRepository`] keeps track of the `DPoP` proofs which were
+/// already presented, to detect replays
+#[async_trait]
+pub trait OAuth2DpopProofRepository: Send + Sync {
+    /// The error type returned by the repository
+    type Error;
+
+    /// Record the use of a `DPoP` proof
+    ///
+    /// Returns `true` if the proof was not seen before, `false` if it is a
+    /// replay
+    ///
+    /// # Parameters
+    ///
+    /// * `clock`: The clock used to generate timestamps
+    /// * `jkt`: The JWK thumbprint of the key which signed the proof
+    /// * `jti`: The unique identifier of the proof
+    /// * `expires_at`: When the proof would be rejected anyway, after which
+    ///   it can be forgotten
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn record(
+        &mut self,
+        clock: &dyn Clock,
+        jkt: &str,
+        jti: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<bool, Self::Error>;
+
+    /// Cleanup the `DPoP` proofs which expired
+    ///
+    /// Returns the number of proofs which were removed
+    ///
+    /// # Parameters
+    ///
+    /// * `clock`: The clock used to get the current time
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn cleanup_expired(&mut self, clock: &dyn Clock) -> Result<usize, Self::Error>;
+}
+
+repository_impl!(OAuth2DpopProofRepository:
+    async fn record(
+        &mut self,
+        clock: &dyn Clock,
+        jkt: &str,
+        jti: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<bool, Self::Error>;
+
+    async fn cleanup_expired(&mut self, clock: &dyn Clock) -> Result<usize, Self::Error>;
+);
@@ -0,0 +1,214 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+use async_trait::async_trait;
+use chrono::Duration;
+use mas_data_model::InitialAccessToken;
+use rand_core::RngCore;
+use ulid::Ulid;
+
+use crate::{Clock, Pagination, pagination::Page, repository_impl};
+
+/// Filter parameters for listing initial access tokens
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct OAuth2InitialAccessTokenFilter {
+    revoked: Option<bool>,
+}
+
+impl OAuth2InitialAccessTokenFilter {
+    /// Create a new [`OAuth2InitialAccessTokenFilter`] with default values
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only return tokens which were not revoked
+    #[must_use]
+    pub fn active_only(mut self) -> Self {
+        self.revoked = Some(false);
+        self
+    }
+
+    /// Only return tokens which were revoked
+    #[must_use]
+    pub fn revoked_only(mut self) -> Self {
+        self.revoked = Some(true);
+        self
+    }
+
+    /// Get the revocation filter
+    ///
+    /// Returns [`None`] if no revocation filter was set
+    #[must_use]
+    pub fn revoked(&self) -> Option<bool> {
+        self.revoked
+    }
+}
+
+/// An [`OAuth2InitialAccessTokenRepository`] helps interacting with
+/// [`InitialAccessToken`] saved in the storage backend
+#[async_trait]
+pub trait OAuth2InitialAccessTokenRepository: Send + Sync {
+    /// The error type returned by the repository
+    type Error;
+
+    /// Lookup an initial access token by its ID
+    ///
+    /// Returns the initial access token if it exists, `None` otherwise
+    ///
+    /// # Parameters
+    ///
+    /// * `id`: The ID of the initial access token to lookup
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn lookup(&mut self, id: Ulid) -> Result<Option<InitialAccessToken>, Self::Error>;
+
+    /// Find an initial access token by its token
+    ///
+    /// Returns the initial access token if it exists, `None` otherwise
+    ///
+    /// # Parameters
+    ///
+    /// * `token`: The token to lookup
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn find_by_token(
+        &mut self,
+        token: &str,
+    ) -> Result<Option<InitialAccessToken>, Self::Error>;
+
+    /// Add a new initial access token
+    ///
+    /// Returns the newly created initial access token
+    ///
+    /// # Parameters
+    ///
+    /// * `rng`: A random number generator
+    /// * `clock`: The clock used to generate timestamps
+    /// * `token`: The token to add
+    /// * `expires_after`: The duration after which the token expires. If
+    ///   [`None`] the token never expires
+    /// * `max_uses`: The number of clients the token can be used to register.
+    ///   If [`None`] the number of uses is not limited
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn add(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        token: String,
+        expires_after: Option<Duration>,
+        max_uses: Option<u32>,
+    ) -> Result<InitialAccessToken, Self::Error>;
+
+    /// Record that an initial access token was used to register a client
+    ///
+    /// Returns the updated initial access token, or `None` if the token was
+    /// revoked or used up in the meantime
+    ///
+    /// # Parameters
+    ///
+    /// * `clock`: The clock used to generate timestamps
+    /// * `initial_access_token`: The initial access token which was used
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn record_use(
+        &mut self,
+        clock: &dyn Clock,
+        initial_access_token: InitialAccessToken,
+    ) -> Result<Option<InitialAccessToken>, Self::Error>;
+
+    /// Revoke an initial access token
+    ///
+    /// Returns the revoked initial access token
+    ///
+    /// # Parameters
+    ///
+    /// * `clock`: The clock used to generate timestamps
+    /// * `initial_access_token`: The initial access token to revoke
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails or if the
+    /// token was already revoked
+    async fn revoke(
+        &mut self,
+        clock: &dyn Clock,
+        initial_access_token: InitialAccessToken,
+    ) -> Result<InitialAccessToken, Self::Error>;
+
+    /// List initial access tokens matching the given filter
+    ///
+    /// # Parameters
+    ///
+    /// * `filter`: The filter parameters
+    /// * `pagination`: The pagination parameters
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn list(
+        &mut self,
+        filter: OAuth2InitialAccessTokenFilter,
+        pagination: Pagination,
+    ) -> Result<Page<InitialAccessToken>, Self::Error>;
+
+    /// Count the initial access tokens matching the given filter
+    ///
+    /// # Parameters
+    ///
+    /// * `filter`: The filter parameters
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn count(&mut self, filter: OAuth2InitialAccessTokenFilter)
+    -> Result<usize, Self::Error>;
+}
+
+repository_impl!(OAuth2InitialAccessTokenRepository:
+    async fn lookup(&mut self, id: Ulid) -> Result<Option<InitialAccessToken>, Self::Error>;
+
+    async fn find_by_token(&mut self, token: &str)
+    -> Result<Option<InitialAccessToken>, Self::Error>;
+
+    async fn add(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        token: String,
+        expires_after: Option<Duration>,
+        max_uses: Option<u32>,
+    ) -> Result<InitialAccessToken, Self::Error>;
+
+    async fn record_use(
+        &mut self,
+        clock: &dyn Clock,
+        initial_access_token: InitialAccessToken,
+    ) -> Result<Option<InitialAccessToken>, Self::Error>;
+
+    async fn revoke(
+        &mut self,
+        clock: &dyn Clock,
+        initial_access_token: InitialAccessToken,
+    ) -> Result<InitialAccessToken, Self::Error>;
+
+    async fn list(
+        &mut self,
+        filter: OAuth2InitialAccessTokenFilter,
+        pagination: Pagination,
+    ) -> Result<Page<InitialAccessToken>, Self::Error>;
+
+    async fn count(&mut self, filter: OAuth2InitialAccessTokenFilter)
+    -> Result<usize, Self::Error>;
+);
@@ -0,0 +1,68 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use mas_data_model::Client;
+
+use crate::{Clock, repository_impl};
+
+/// An [`OAuth2JwtBearerAssertionRepository`] keeps track of the JWT bearer
+/// assertions which were already used as authorization grants, to detect
+/// replays
+#[async_trait]
+pub trait OAuth2JwtBearerAssertionRepository: Send + Sync {
+    /// The error type returned by the repository
+    type Error;
+
+    /// Record the use of a JWT bearer assertion
+    ///
+    /// Returns `true` if the assertion was not seen before, `false` if it is a
+    /// replay
+    ///
+    /// # Parameters
+    ///
+    /// * `clock`: The clock used to generate timestamps
+    /// * `client`: The client which issued the assertion
+    /// * `jti`: The unique identifier of the assertion
+    /// * `expires_at`: When the assertion would be rejected anyway, after
+    ///   which it can be forgotten
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn record(
+        &mut self,
+        clock: &dyn Clock,
+        client: &Client,
+        jti: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<bool, Self::Error>;
+
+    /// Cleanup the JWT bearer assertions which expired
+    ///
+    /// Returns the number of assertions which were removed
+    ///
+    /// # Parameters
+    ///
+    /// * `clock`: The clock used to get the current time
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn cleanup_expired(&mut self, clock: &dyn Clock) -> Result<usize, Self::Error>;
+}
+
+repository_impl!(OAuth2JwtBearerAssertionRepository:
+    async fn record(
+        &mut self,
+        clock: &dyn Clock,
+        client: &Client,
+        jti: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<bool, Self::Error>;
+
+    async fn cleanup_expired(&mut self, clock: &dyn Clock) -> Result<usize, Self::Error>;
+);
@@ -10,6 +10,11 @@ mod access_token;
 mod authorization_grant;
 mod client;
 mod device_code_grant;
+mod dpop_proof;
+mod initial_access_token;
+mod jwt_bearer_assertion;
+mod pairwise_subject;
+mod pushed_authorization_request;
 mod refresh_token;
 mod session;
 
@@ -18,6 +23,11 @@ pub use self::{
     authorization_grant::OAuth2AuthorizationGrantRepository,
     client::OAuth2ClientRepository,
     device_code_grant::{OAuth2DeviceCodeGrantParams, OAuth2DeviceCodeGrantRepository},
+    dpop_proof::OAuth2DpopProofRepository,
+    initial_access_token::{OAuth2InitialAccessTokenFilter, OAuth2InitialAccessTokenRepository},
+    jwt_bearer_assertion::OAuth2JwtBearerAssertionRepository,
+    pairwise_subject::OAuth2PairwiseSubjectRepository,
+    pushed_authorization_request::OAuth2PushedAuthorizationRequestRepository,
     refresh_token::OAuth2RefreshTokenRepository,
     session::{OAuth2SessionFilter, OAuth2SessionRepository},
 };
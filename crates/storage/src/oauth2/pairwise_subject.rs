@@ -0,0 +1,75 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+use async_trait::async_trait;
+use mas_data_model::User;
+use rand_core::RngCore;
+use ulid::Ulid;
+
+use crate::{Clock, repository_impl};
+
+/// An [`OAuth2PairwiseSubjectRepository`] keeps track of the pairwise subject
+/// identifiers given out to each sector
+#[async_trait]
+pub trait OAuth2PairwiseSubjectRepository: Send + Sync {
+    /// The error type returned by the repository
+    type Error;
+
+    /// Get the pairwise subject identifier of a user for a sector, generating
+    /// one if none was given out yet
+    ///
+    /// # Parameters
+    ///
+    /// * `rng`: The random number generator to use
+    /// * `clock`: The clock used to generate timestamps
+    /// * `sector_identifier`: The sector identifier of the client
+    /// * `user`: The user to get the subject identifier for
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn find_or_add(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        sector_identifier: &str,
+        user: &User,
+    ) -> Result<String, Self::Error>;
+
+    /// Find the ID of the user a pairwise subject identifier refers to
+    ///
+    /// Returns `None` if no such subject identifier was given out for this
+    /// sector
+    ///
+    /// # Parameters
+    ///
+    /// * `sector_identifier`: The sector identifier of the client
+    /// * `subject`: The pairwise subject identifier to look up
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn find_user_id(
+        &mut self,
+        sector_identifier: &str,
+        subject: &str,
+    ) -> Result<Option<Ulid>, Self::Error>;
+}
+
+repository_impl!(OAuth2PairwiseSubjectRepository:
+    async fn find_or_add(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        sector_identifier: &str,
+        user: &User,
+    ) -> Result<String, Self::Error>;
+
+    async fn find_user_id(
+        &mut self,
+        sector_identifier: &str,
+        subject: &str,
+    ) -> Result<Option<Ulid>, Self::Error>;
+);
@@ -0,0 +1,122 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+use async_trait::async_trait;
+use chrono::Duration;
+use mas_data_model::{Client, PushedAuthorizationRequest};
+use rand_core::RngCore;
+use ulid::Ulid;
+
+use crate::{Clock, repository_impl};
+
+/// An [`OAuth2PushedAuthorizationRequestRepository`] helps interacting with
+/// [`PushedAuthorizationRequest`] saved in the storage backend.
+#[async_trait]
+pub trait OAuth2PushedAuthorizationRequestRepository: Send + Sync {
+    /// The error type returned by the repository
+    type Error;
+
+    /// Save a new pushed authorization request
+    ///
+    /// Returns the newly created pushed authorization request
+    ///
+    /// # Parameters
+    ///
+    /// * `rng`: A random number generator
+    /// * `clock`: The clock used to generate timestamps
+    /// * `client`: The client which pushed the authorization request
+    /// * `request_uri`: The `request_uri` referencing this request
+    /// * `parameters`: The authorization request parameters
+    /// * `expires_in`: After how long the request expires
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn add(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        client: &Client,
+        request_uri: String,
+        parameters: Vec<(String, String)>,
+        expires_in: Duration,
+    ) -> Result<PushedAuthorizationRequest, Self::Error>;
+
+    /// Lookup a pushed authorization request by its ID
+    ///
+    /// Returns the pushed authorization request if found, [`None`] otherwise
+    ///
+    /// # Parameters
+    ///
+    /// * `id`: The ID of the pushed authorization request
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn lookup(&mut self, id: Ulid)
+    -> Result<Option<PushedAuthorizationRequest>, Self::Error>;
+
+    /// Find a pushed authorization request by its `request_uri`
+    ///
+    /// Returns the pushed authorization request if found, [`None`] otherwise
+    ///
+    /// # Parameters
+    ///
+    /// * `request_uri`: The `request_uri` of the pushed authorization request
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn find_by_request_uri(
+        &mut self,
+        request_uri: &str,
+    ) -> Result<Option<PushedAuthorizationRequest>, Self::Error>;
+
+    /// Mark a pushed authorization request as consumed, so that it can't be
+    /// used again
+    ///
+    /// Returns the updated pushed authorization request
+    ///
+    /// # Parameters
+    ///
+    /// * `clock`: The clock used to generate timestamps
+    /// * `pushed_authorization_request`: The pushed authorization request to
+    ///   consume
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails or if the
+    /// request was already consumed
+    async fn consume(
+        &mut self,
+        clock: &dyn Clock,
+        pushed_authorization_request: PushedAuthorizationRequest,
+    ) -> Result<PushedAuthorizationRequest, Self::Error>;
+}
+
+repository_impl!(OAuth2PushedAuthorizationRequestRepository:
+    async fn add(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        client: &Client,
+        request_uri: String,
+        parameters: Vec<(String, String)>,
+        expires_in: Duration,
+    ) -> Result<PushedAuthorizationRequest, Self::Error>;
+
+    async fn lookup(&mut self, id: Ulid) -> Result<Option<PushedAuthorizationRequest>, Self::Error>;
+
+    async fn find_by_request_uri(
+        &mut self,
+        request_uri: &str,
+    ) -> Result<Option<PushedAuthorizationRequest>, Self::Error>;
+
+    async fn consume(
+        &mut self,
+        clock: &dyn Clock,
+        pushed_authorization_request: PushedAuthorizationRequest,
+    ) -> Result<PushedAuthorizationRequest, Self::Error>;
+);
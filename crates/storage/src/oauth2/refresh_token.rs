@@ -57,7 +57,8 @@ pub trait OAuth2RefreshTokenRepository: Send + Sync {
     /// * `clock`: The clock used to generate timestamps
     /// * `session`: The [`Session`] in which to create the [`RefreshToken`]
     /// * `access_token`: The [`AccessToken`] created alongside this
-    ///   [`RefreshToken`]
+    ///   [`RefreshToken`]. The refresh token is bound to the same `DPoP` key as
+    ///   this access token, if any
     /// * `refresh_token`: The refresh token to store
     ///
     /// # Errors
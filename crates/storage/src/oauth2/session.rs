@@ -12,6 +12,7 @@ use mas_data_model::{BrowserSession, Client, Device, Session, User, UserAgent};
 use oauth2_types::scope::Scope;
 use rand_core::RngCore;
 use ulid::Ulid;
+use url::Url;
 
 use crate::{Clock, Pagination, pagination::Page, repository_impl};
 
@@ -344,8 +345,44 @@ pub trait OAuth2SessionRepository: Send + Sync {
         self.add(rng, clock, client, None, None, scope).await
     }
 
+    /// Create a new [`Session`] for a [`Client`] out of another [`Session`],
+    /// using the token exchange flow
+    ///
+    /// The new [`Session`] is for the same user and browser session as the
+    /// parent one.
+    ///
+    /// Returns the newly created [`Session`]
+    ///
+    /// # Parameters
+    ///
+    /// * `rng`: The random number generator to use
+    /// * `clock`: The clock used to generate timestamps
+    /// * `client`: The [`Client`] which created the [`Session`]
+    /// * `parent`: The [`Session`] of the subject token which was exchanged
+    /// * `actor`: The [`Client`] acting on behalf of the user, if any
+    /// * `audience`: The audience the [`Session`] is restricted to, if any
+    /// * `scope`: The [`Scope`] of the [`Session`]
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    #[allow(clippy::too_many_arguments)]
+    async fn add_from_token_exchange(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        client: &Client,
+        parent: &Session,
+        actor: Option<&Client>,
+        audience: Option<String>,
+        scope: Scope,
+    ) -> Result<Session, Self::Error>;
+
     /// Mark a [`Session`] as finished
     ///
+    /// Sessions derived from it through a token exchange are also marked as
+    /// finished.
+    ///
     /// Returns the updated [`Session`]
     ///
     /// # Parameters
@@ -430,6 +467,25 @@ pub trait OAuth2SessionRepository: Send + Sync {
         session: Session,
         user_agent: UserAgent,
     ) -> Result<Session, Self::Error>;
+
+    /// Restrict the tokens of a [`Session`] to a set of resources, as
+    /// requested through resource indicators
+    ///
+    /// Returns the updated [`Session`]
+    ///
+    /// # Parameters
+    ///
+    /// * `session`: The [`Session`] to restrict
+    /// * `resources`: The resources the tokens of the session are restricted to
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn set_resources(
+        &mut self,
+        session: Session,
+        resources: Vec<Url>,
+    ) -> Result<Session, Self::Error>;
 }
 
 repository_impl!(OAuth2SessionRepository:
@@ -462,6 +518,17 @@ repository_impl!(OAuth2SessionRepository:
         scope: Scope,
     ) -> Result<Session, Self::Error>;
 
+    async fn add_from_token_exchange(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        client: &Client,
+        parent: &Session,
+        actor: Option<&Client>,
+        audience: Option<String>,
+        scope: Scope,
+    ) -> Result<Session, Self::Error>;
+
     async fn finish(&mut self, clock: &dyn Clock, session: Session)
         -> Result<Session, Self::Error>;
 
@@ -489,4 +556,10 @@ repository_impl!(OAuth2SessionRepository:
         session: Session,
         user_agent: UserAgent,
     ) -> Result<Session, Self::Error>;
+
+    async fn set_resources(
+        &mut self,
+        session: Session,
+        resources: Vec<Url>,
+    ) -> Result<Session, Self::Error>;
 );
@@ -6,7 +6,7 @@
 use chrono::{DateTime, Utc};
 use mas_data_model::{
     BrowserSession, CompatSession, Device, Session, User, UserEmailAuthentication,
-    UserRecoverySession,
+    UserRecoveryCode, UserRecoverySession,
 };
 use serde::{Deserialize, Serialize};
 use ulid::Ulid;
@@ -514,3 +514,184 @@ pub struct PruneStalePolicyDataJob;
 impl InsertableJob for PruneStalePolicyDataJob {
     const QUEUE_NAME: &'static str = "prune-stale-policy-data";
 }
+
+/// Rotate the signing keys, when the automatic key rotation is enabled
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RotateSigningKeysJob;
+
+impl InsertableJob for RotateSigningKeysJob {
+    const QUEUE_NAME: &'static str = "rotate-signing-keys";
+}
+
+/// A job to send an OpenID Connect back-channel logout notification to the
+/// client of an OAuth 2.0 session
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SendBackchannelLogoutJob {
+    oauth2_session_id: Ulid,
+}
+
+impl SendBackchannelLogoutJob {
+    /// Create a new job to notify the client of an OAuth 2.0 session that the
+    /// session ended
+    ///
+    /// # Parameters
+    ///
+    /// * `session` - The OAuth 2.0 session which ended
+    #[must_use]
+    pub fn new(session: &Session) -> Self {
+        Self {
+            oauth2_session_id: session.id,
+        }
+    }
+
+    /// The ID of the OAuth 2.0 session which ended
+    #[must_use]
+    pub fn oauth2_session_id(&self) -> Ulid {
+        self.oauth2_session_id
+    }
+}
+
+impl InsertableJob for SendBackchannelLogoutJob {
+    const QUEUE_NAME: &'static str = "send-backchannel-logout";
+}
+
+/// A job to notify a user by email that one of their OAuth 2.0 sessions was
+/// revoked because a refresh token was reused
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SendSessionRevokedEmailJob {
+    user_id: Ulid,
+    oauth2_session_id: Ulid,
+}
+
+impl SendSessionRevokedEmailJob {
+    /// Create a new job to notify a user that one of their sessions was
+    /// revoked
+    ///
+    /// # Parameters
+    ///
+    /// * `user` - The user to notify
+    /// * `session` - The OAuth 2.0 session which was revoked
+    #[must_use]
+    pub fn new(user: &User, session: &Session) -> Self {
+        Self {
+            user_id: user.id,
+            oauth2_session_id: session.id,
+        }
+    }
+
+    /// The ID of the user to notify
+    #[must_use]
+    pub fn user_id(&self) -> Ulid {
+        self.user_id
+    }
+
+    /// The ID of the OAuth 2.0 session which was revoked
+    #[must_use]
+    pub fn oauth2_session_id(&self) -> Ulid {
+        self.oauth2_session_id
+    }
+}
+
+impl InsertableJob for SendSessionRevokedEmailJob {
+    const QUEUE_NAME: &'static str = "send-session-revoked-email";
+}
+
+/// A job to notify a user by email that one of their recovery codes was used
+/// to log in
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SendRecoveryCodeUsedEmailJob {
+    user_id: Ulid,
+    user_recovery_code_id: Ulid,
+}
+
+impl SendRecoveryCodeUsedEmailJob {
+    /// Create a new job to notify a user that one of their recovery codes was
+    /// used
+    ///
+    /// # Parameters
+    ///
+    /// * `user` - The user to notify
+    /// * `user_recovery_code` - The recovery code which was used
+    #[must_use]
+    pub fn new(user: &User, user_recovery_code: &UserRecoveryCode) -> Self {
+        Self {
+            user_id: user.id,
+            user_recovery_code_id: user_recovery_code.id,
+        }
+    }
+
+    /// The ID of the user to notify
+    #[must_use]
+    pub fn user_id(&self) -> Ulid {
+        self.user_id
+    }
+
+    /// The ID of the recovery code which was used
+    #[must_use]
+    pub fn user_recovery_code_id(&self) -> Ulid {
+        self.user_recovery_code_id
+    }
+}
+
+impl InsertableJob for SendRecoveryCodeUsedEmailJob {
+    const QUEUE_NAME: &'static str = "send-recovery-code-used-email";
+}
+
+/// A job to send OpenID Connect back-channel logout notifications for all the
+/// OAuth 2.0 sessions started from a browser session which ended
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BrowserSessionBackchannelLogoutJob {
+    browser_session_id: Ulid,
+    after: Option<Ulid>,
+}
+
+impl BrowserSessionBackchannelLogoutJob {
+    /// Create a new job to notify the clients of the OAuth 2.0 sessions
+    /// started from a browser session
+    ///
+    /// # Parameters
+    ///
+    /// * `browser_session` - The browser session which ended
+    #[must_use]
+    pub fn new(browser_session: &BrowserSession) -> Self {
+        Self {
+            browser_session_id: browser_session.id,
+            after: None,
+        }
+    }
+
+    /// The ID of the browser session which ended
+    #[must_use]
+    pub fn browser_session_id(&self) -> Ulid {
+        self.browser_session_id
+    }
+
+    /// Get the pagination cursor
+    #[must_use]
+    pub fn pagination(&self, batch_size: usize) -> Pagination {
+        let pagination = Pagination::first(batch_size);
+        if let Some(after) = self.after {
+            pagination.after(after)
+        } else {
+            pagination
+        }
+    }
+
+    /// Get the next job given the page returned by the database
+    #[must_use]
+    pub fn next(&self, page: &Page<Session>) -> Option<Self> {
+        if !page.has_next_page {
+            return None;
+        }
+
+        let last_edge = page.edges.last()?;
+        Some(Self {
+            browser_session_id: self.browser_session_id,
+            after: Some(last_edge.id),
+        })
+    }
+}
+
+impl InsertableJob for BrowserSessionBackchannelLogoutJob {
+    const QUEUE_NAME: &'static str = "browser-session-backchannel-logout";
+}
@@ -15,17 +15,22 @@ use crate::{
     },
     oauth2::{
         OAuth2AccessTokenRepository, OAuth2AuthorizationGrantRepository, OAuth2ClientRepository,
-        OAuth2DeviceCodeGrantRepository, OAuth2RefreshTokenRepository, OAuth2SessionRepository,
+        OAuth2DeviceCodeGrantRepository, OAuth2DpopProofRepository,
+        OAuth2InitialAccessTokenRepository, OAuth2JwtBearerAssertionRepository,
+        OAuth2PairwiseSubjectRepository, OAuth2PushedAuthorizationRequestRepository,
+        OAuth2RefreshTokenRepository, OAuth2SessionRepository,
     },
     policy_data::PolicyDataRepository,
     queue::{QueueJobRepository, QueueScheduleRepository, QueueWorkerRepository},
+    signing_key::SigningKeyRepository,
     upstream_oauth2::{
         UpstreamOAuthLinkRepository, UpstreamOAuthProviderRepository,
         UpstreamOAuthSessionRepository,
     },
     user::{
-        BrowserSessionRepository, UserEmailRepository, UserPasswordRepository,
-        UserRecoveryRepository, UserRegistrationRepository, UserRepository, UserTermsRepository,
+        BrowserSessionRepository, UserEmailRepository, UserPasskeyRepository,
+        UserPasswordRepository, UserRecoveryCodeRepository, UserRecoveryRepository,
+        UserRegistrationRepository, UserRepository, UserTermsRepository, UserTotpRepository,
     },
 };
 
@@ -126,6 +131,17 @@ pub trait RepositoryAccess: Send {
     fn user_password<'c>(&'c mut self)
     -> Box<dyn UserPasswordRepository<Error = Self::Error> + 'c>;
 
+    /// Get an [`UserTotpRepository`]
+    fn user_totp<'c>(&'c mut self) -> Box<dyn UserTotpRepository<Error = Self::Error> + 'c>;
+
+    /// Get an [`UserPasskeyRepository`]
+    fn user_passkey<'c>(&'c mut self) -> Box<dyn UserPasskeyRepository<Error = Self::Error> + 'c>;
+
+    /// Get an [`UserRecoveryCodeRepository`]
+    fn user_recovery_code<'c>(
+        &'c mut self,
+    ) -> Box<dyn UserRecoveryCodeRepository<Error = Self::Error> + 'c>;
+
     /// Get an [`UserRecoveryRepository`]
     fn user_recovery<'c>(&'c mut self)
     -> Box<dyn UserRecoveryRepository<Error = Self::Error> + 'c>;
@@ -175,6 +191,31 @@ pub trait RepositoryAccess: Send {
         &'c mut self,
     ) -> Box<dyn OAuth2DeviceCodeGrantRepository<Error = Self::Error> + 'c>;
 
+    /// Get an [`OAuth2PushedAuthorizationRequestRepository`]
+    fn oauth2_pushed_authorization_request<'c>(
+        &'c mut self,
+    ) -> Box<dyn OAuth2PushedAuthorizationRequestRepository<Error = Self::Error> + 'c>;
+
+    /// Get an [`OAuth2DpopProofRepository`]
+    fn oauth2_dpop_proof<'c>(
+        &'c mut self,
+    ) -> Box<dyn OAuth2DpopProofRepository<Error = Self::Error> + 'c>;
+
+    /// Get an [`OAuth2JwtBearerAssertionRepository`]
+    fn oauth2_jwt_bearer_assertion<'c>(
+        &'c mut self,
+    ) -> Box<dyn OAuth2JwtBearerAssertionRepository<Error = Self::Error> + 'c>;
+
+    /// Get an [`OAuth2InitialAccessTokenRepository`]
+    fn oauth2_initial_access_token<'c>(
+        &'c mut self,
+    ) -> Box<dyn OAuth2InitialAccessTokenRepository<Error = Self::Error> + 'c>;
+
+    /// Get an [`OAuth2PairwiseSubjectRepository`]
+    fn oauth2_pairwise_subject<'c>(
+        &'c mut self,
+    ) -> Box<dyn OAuth2PairwiseSubjectRepository<Error = Self::Error> + 'c>;
+
     /// Get a [`CompatSessionRepository`]
     fn compat_session<'c>(
         &'c mut self,
@@ -208,6 +249,9 @@ pub trait RepositoryAccess: Send {
 
     /// Get a [`PolicyDataRepository`]
     fn policy_data<'c>(&'c mut self) -> Box<dyn PolicyDataRepository<Error = Self::Error> + 'c>;
+
+    /// Get a [`SigningKeyRepository`]
+    fn signing_key<'c>(&'c mut self) -> Box<dyn SigningKeyRepository<Error = Self::Error> + 'c>;
 }
 
 /// Implementations of the [`RepositoryAccess`], [`RepositoryTransaction`] and
@@ -225,18 +269,22 @@ mod impls {
         },
         oauth2::{
             OAuth2AccessTokenRepository, OAuth2AuthorizationGrantRepository,
-            OAuth2ClientRepository, OAuth2DeviceCodeGrantRepository, OAuth2RefreshTokenRepository,
-            OAuth2SessionRepository,
+            OAuth2ClientRepository, OAuth2DeviceCodeGrantRepository, OAuth2DpopProofRepository,
+            OAuth2InitialAccessTokenRepository, OAuth2JwtBearerAssertionRepository,
+            OAuth2PairwiseSubjectRepository, OAuth2PushedAuthorizationRequestRepository,
+            OAuth2RefreshTokenRepository, OAuth2SessionRepository,
         },
         policy_data::PolicyDataRepository,
         queue::{QueueJobRepository, QueueScheduleRepository, QueueWorkerRepository},
+        signing_key::SigningKeyRepository,
         upstream_oauth2::{
             UpstreamOAuthLinkRepository, UpstreamOAuthProviderRepository,
             UpstreamOAuthSessionRepository,
         },
         user::{
-            BrowserSessionRepository, UserEmailRepository, UserPasswordRepository,
-            UserRegistrationRepository, UserRepository, UserTermsRepository,
+            BrowserSessionRepository, UserEmailRepository, UserPasskeyRepository,
+            UserPasswordRepository, UserRecoveryCodeRepository, UserRegistrationRepository,
+            UserRepository, UserTermsRepository, UserTotpRepository,
         },
     };
 
@@ -320,6 +368,25 @@ mod impls {
             Box::new(MapErr::new(self.inner.user_password(), &mut self.mapper))
         }
 
+        fn user_totp<'c>(&'c mut self) -> Box<dyn UserTotpRepository<Error = Self::Error> + 'c> {
+            Box::new(MapErr::new(self.inner.user_totp(), &mut self.mapper))
+        }
+
+        fn user_passkey<'c>(
+            &'c mut self,
+        ) -> Box<dyn UserPasskeyRepository<Error = Self::Error> + 'c> {
+            Box::new(MapErr::new(self.inner.user_passkey(), &mut self.mapper))
+        }
+
+        fn user_recovery_code<'c>(
+            &'c mut self,
+        ) -> Box<dyn UserRecoveryCodeRepository<Error = Self::Error> + 'c> {
+            Box::new(MapErr::new(
+                self.inner.user_recovery_code(),
+                &mut self.mapper,
+            ))
+        }
+
         fn user_recovery<'c>(
             &'c mut self,
         ) -> Box<dyn crate::user::UserRecoveryRepository<Error = Self::Error> + 'c> {
@@ -399,6 +466,51 @@ mod impls {
             ))
         }
 
+        fn oauth2_pushed_authorization_request<'c>(
+            &'c mut self,
+        ) -> Box<dyn OAuth2PushedAuthorizationRequestRepository<Error = Self::Error> + 'c> {
+            Box::new(MapErr::new(
+                self.inner.oauth2_pushed_authorization_request(),
+                &mut self.mapper,
+            ))
+        }
+
+        fn oauth2_dpop_proof<'c>(
+            &'c mut self,
+        ) -> Box<dyn OAuth2DpopProofRepository<Error = Self::Error> + 'c> {
+            Box::new(MapErr::new(
+                self.inner.oauth2_dpop_proof(),
+                &mut self.mapper,
+            ))
+        }
+
+        fn oauth2_jwt_bearer_assertion<'c>(
+            &'c mut self,
+        ) -> Box<dyn OAuth2JwtBearerAssertionRepository<Error = Self::Error> + 'c> {
+            Box::new(MapErr::new(
+                self.inner.oauth2_jwt_bearer_assertion(),
+                &mut self.mapper,
+            ))
+        }
+
+        fn oauth2_initial_access_token<'c>(
+            &'c mut self,
+        ) -> Box<dyn OAuth2InitialAccessTokenRepository<Error = Self::Error> + 'c> {
+            Box::new(MapErr::new(
+                self.inner.oauth2_initial_access_token(),
+                &mut self.mapper,
+            ))
+        }
+
+        fn oauth2_pairwise_subject<'c>(
+            &'c mut self,
+        ) -> Box<dyn OAuth2PairwiseSubjectRepository<Error = Self::Error> + 'c> {
+            Box::new(MapErr::new(
+                self.inner.oauth2_pairwise_subject(),
+                &mut self.mapper,
+            ))
+        }
+
         fn compat_session<'c>(
             &'c mut self,
         ) -> Box<dyn CompatSessionRepository<Error = Self::Error> + 'c> {
@@ -450,6 +562,12 @@ mod impls {
         ) -> Box<dyn PolicyDataRepository<Error = Self::Error> + 'c> {
             Box::new(MapErr::new(self.inner.policy_data(), &mut self.mapper))
         }
+
+        fn signing_key<'c>(
+            &'c mut self,
+        ) -> Box<dyn SigningKeyRepository<Error = Self::Error> + 'c> {
+            Box::new(MapErr::new(self.inner.signing_key(), &mut self.mapper))
+        }
     }
 
     impl<R: RepositoryAccess + ?Sized> RepositoryAccess for Box<R> {
@@ -487,6 +605,22 @@ mod impls {
             (**self).user_password()
         }
 
+        fn user_totp<'c>(&'c mut self) -> Box<dyn UserTotpRepository<Error = Self::Error> + 'c> {
+            (**self).user_totp()
+        }
+
+        fn user_passkey<'c>(
+            &'c mut self,
+        ) -> Box<dyn UserPasskeyRepository<Error = Self::Error> + 'c> {
+            (**self).user_passkey()
+        }
+
+        fn user_recovery_code<'c>(
+            &'c mut self,
+        ) -> Box<dyn UserRecoveryCodeRepository<Error = Self::Error> + 'c> {
+            (**self).user_recovery_code()
+        }
+
         fn user_recovery<'c>(
             &'c mut self,
         ) -> Box<dyn crate::user::UserRecoveryRepository<Error = Self::Error> + 'c> {
@@ -551,6 +685,36 @@ mod impls {
             (**self).oauth2_device_code_grant()
         }
 
+        fn oauth2_pushed_authorization_request<'c>(
+            &'c mut self,
+        ) -> Box<dyn OAuth2PushedAuthorizationRequestRepository<Error = Self::Error> + 'c> {
+            (**self).oauth2_pushed_authorization_request()
+        }
+
+        fn oauth2_dpop_proof<'c>(
+            &'c mut self,
+        ) -> Box<dyn OAuth2DpopProofRepository<Error = Self::Error> + 'c> {
+            (**self).oauth2_dpop_proof()
+        }
+
+        fn oauth2_jwt_bearer_assertion<'c>(
+            &'c mut self,
+        ) -> Box<dyn OAuth2JwtBearerAssertionRepository<Error = Self::Error> + 'c> {
+            (**self).oauth2_jwt_bearer_assertion()
+        }
+
+        fn oauth2_initial_access_token<'c>(
+            &'c mut self,
+        ) -> Box<dyn OAuth2InitialAccessTokenRepository<Error = Self::Error> + 'c> {
+            (**self).oauth2_initial_access_token()
+        }
+
+        fn oauth2_pairwise_subject<'c>(
+            &'c mut self,
+        ) -> Box<dyn OAuth2PairwiseSubjectRepository<Error = Self::Error> + 'c> {
+            (**self).oauth2_pairwise_subject()
+        }
+
         fn compat_session<'c>(
             &'c mut self,
         ) -> Box<dyn CompatSessionRepository<Error = Self::Error> + 'c> {
@@ -596,5 +760,11 @@ mod impls {
         ) -> Box<dyn PolicyDataRepository<Error = Self::Error> + 'c> {
             (**self).policy_data()
         }
+
+        fn signing_key<'c>(
+            &'c mut self,
+        ) -> Box<dyn SigningKeyRepository<Error = Self::Error> + 'c> {
+            (**self).signing_key()
+        }
     }
 }
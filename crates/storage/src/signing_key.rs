@@ -0,0 +1,125 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+//! Repositories to interact with the signing keys generated by the automatic
+//! key rotation and saved in the storage backend.
+
+use async_trait::async_trait;
+use mas_data_model::SigningKey;
+use rand_core::RngCore;
+
+use crate::{Clock, repository_impl};
+
+/// A [`SigningKeyRepository`] helps interacting with the [`SigningKey`] saved
+/// in the storage backend.
+#[async_trait]
+pub trait SigningKeyRepository: Send + Sync {
+    /// The error type returned by the repository
+    type Error;
+
+    /// List all the signing keys, ordered by creation date
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn all(&mut self) -> Result<Vec<SigningKey>, Self::Error>;
+
+    /// Add a new signing key, which starts being published but isn't used for
+    /// signing yet
+    ///
+    /// Returns the newly created signing key
+    ///
+    /// # Parameters
+    ///
+    /// * `rng`: The random number generator to use
+    /// * `clock`: The clock used to generate the timestamps
+    /// * `kid`: The key ID of the key
+    /// * `encrypted_key`: The encrypted, PKCS#8 DER-encoded private key
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn add(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        kid: String,
+        encrypted_key: String,
+    ) -> Result<SigningKey, Self::Error>;
+
+    /// Mark a signing key as active, meaning it is now used for signing
+    ///
+    /// Returns the updated signing key
+    ///
+    /// # Parameters
+    ///
+    /// * `clock`: The clock used to generate the timestamps
+    /// * `signing_key`: The signing key to activate
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn activate(
+        &mut self,
+        clock: &dyn Clock,
+        signing_key: SigningKey,
+    ) -> Result<SigningKey, Self::Error>;
+
+    /// Mark a signing key as retired, meaning it is not used for signing
+    /// anymore, but still published
+    ///
+    /// Returns the updated signing key
+    ///
+    /// # Parameters
+    ///
+    /// * `clock`: The clock used to generate the timestamps
+    /// * `signing_key`: The signing key to retire
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn retire(
+        &mut self,
+        clock: &dyn Clock,
+        signing_key: SigningKey,
+    ) -> Result<SigningKey, Self::Error>;
+
+    /// Remove a signing key, so that it isn't published anymore
+    ///
+    /// # Parameters
+    ///
+    /// * `signing_key`: The signing key to remove
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn remove(&mut self, signing_key: SigningKey) -> Result<(), Self::Error>;
+}
+
+repository_impl!(SigningKeyRepository:
+    async fn all(&mut self) -> Result<Vec<SigningKey>, Self::Error>;
+
+    async fn add(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        kid: String,
+        encrypted_key: String,
+    ) -> Result<SigningKey, Self::Error>;
+
+    async fn activate(
+        &mut self,
+        clock: &dyn Clock,
+        signing_key: SigningKey,
+    ) -> Result<SigningKey, Self::Error>;
+
+    async fn retire(
+        &mut self,
+        clock: &dyn Clock,
+        signing_key: SigningKey,
+    ) -> Result<SigningKey, Self::Error>;
+
+    async fn remove(&mut self, signing_key: SigningKey) -> Result<(), Self::Error>;
+);
@@ -232,6 +232,29 @@ pub trait UserEmailRepository: Send + Sync {
         registration: &UserRegistration,
     ) -> Result<UserEmailAuthentication, Self::Error>;
 
+    /// Add a new [`UserEmailAuthentication`] to log in as a [`User`] without a
+    /// password
+    ///
+    /// # Parameters
+    ///
+    /// * `rng`: The random number generator to use
+    /// * `clock`: The clock to use
+    /// * `email`: The email address the code is sent to
+    /// * `user`: The [`User`] to log in as, or `None` if the email address
+    ///   doesn't belong to a user allowed to log in this way. Such an
+    ///   authentication can never be used to log in.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the underlying repository fails
+    async fn add_authentication_for_login(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        email: String,
+        user: Option<&User>,
+    ) -> Result<UserEmailAuthentication, Self::Error>;
+
     /// Add a new [`UserEmailAuthenticationCode`] for a
     /// [`UserEmailAuthentication`]
     ///
@@ -348,6 +371,14 @@ repository_impl!(UserEmailRepository:
         registration: &UserRegistration,
     ) -> Result<UserEmailAuthentication, Self::Error>;
 
+    async fn add_authentication_for_login(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        email: String,
+        user: Option<&User>,
+    ) -> Result<UserEmailAuthentication, Self::Error>;
+
     async fn add_authentication_code(
         &mut self,
         rng: &mut (dyn RngCore + Send),
@@ -14,19 +14,25 @@ use ulid::Ulid;
 use crate::{Clock, Page, Pagination, repository_impl};
 
 mod email;
+mod passkey;
 mod password;
 mod recovery;
+mod recovery_code;
 mod registration;
 mod session;
 mod terms;
+mod totp;
 
 pub use self::{
     email::{UserEmailFilter, UserEmailRepository},
+    passkey::UserPasskeyRepository,
     password::UserPasswordRepository,
     recovery::UserRecoveryRepository,
+    recovery_code::UserRecoveryCodeRepository,
     registration::UserRegistrationRepository,
     session::{BrowserSessionFilter, BrowserSessionRepository},
     terms::UserTermsRepository,
+    totp::UserTotpRepository,
 };
 
 /// The state of a user account
@@ -259,6 +265,42 @@ pub trait UserRepository: Send + Sync {
         can_request_admin: bool,
     ) -> Result<User, Self::Error>;
 
+    /// Set the locale of a [`User`]
+    ///
+    /// Returns the [`User`] with the new locale. This also records that the
+    /// profile of the user changed.
+    ///
+    /// # Parameters
+    ///
+    /// * `clock`: The clock used to generate timestamps
+    /// * `user`: The [`User`] to update
+    /// * `locale`: The new locale of the user, as a BCP 47 language tag
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn set_locale(
+        &mut self,
+        clock: &dyn Clock,
+        user: User,
+        locale: String,
+    ) -> Result<User, Self::Error>;
+
+    /// Record that the profile of a [`User`] changed, e.g. because their
+    /// display name or avatar was updated on the homeserver
+    ///
+    /// Returns the [`User`] with the new `profile_updated_at` value
+    ///
+    /// # Parameters
+    ///
+    /// * `clock`: The clock used to generate timestamps
+    /// * `user`: The [`User`] to update
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn touch_profile(&mut self, clock: &dyn Clock, user: User) -> Result<User, Self::Error>;
+
     /// List [`User`] with the given filter and pagination
     ///
     /// # Parameters
@@ -318,6 +360,13 @@ repository_impl!(UserRepository:
         user: User,
         can_request_admin: bool,
     ) -> Result<User, Self::Error>;
+    async fn set_locale(
+        &mut self,
+        clock: &dyn Clock,
+        user: User,
+        locale: String,
+    ) -> Result<User, Self::Error>;
+    async fn touch_profile(&mut self, clock: &dyn Clock, user: User) -> Result<User, Self::Error>;
     async fn list(
         &mut self,
         filter: UserFilter<'_>,
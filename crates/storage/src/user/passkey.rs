@@ -0,0 +1,244 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+use async_trait::async_trait;
+use mas_data_model::{BrowserSession, User, UserPasskey, UserPasskeyChallenge};
+use rand_core::RngCore;
+use ulid::Ulid;
+
+use crate::{Clock, repository_impl};
+
+/// A [`UserPasskeyRepository`] helps interacting with [`UserPasskey`] and
+/// [`UserPasskeyChallenge`] saved in the storage backend
+#[async_trait]
+pub trait UserPasskeyRepository: Send + Sync {
+    /// The error type returned by the repository
+    type Error;
+
+    /// Lookup a passkey by its ID
+    ///
+    /// Returns `None` if no passkey was found
+    ///
+    /// # Parameters
+    ///
+    /// * `id`: The ID of the passkey to lookup
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if underlying repository fails
+    async fn lookup(&mut self, id: Ulid) -> Result<Option<UserPasskey>, Self::Error>;
+
+    /// Find a passkey by its credential ID
+    ///
+    /// Returns `None` if no passkey was found
+    ///
+    /// # Parameters
+    ///
+    /// * `credential_id`: The base64url-encoded credential ID
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if underlying repository fails
+    async fn find_by_credential_id(
+        &mut self,
+        credential_id: &str,
+    ) -> Result<Option<UserPasskey>, Self::Error>;
+
+    /// Get all the passkeys of a user, oldest first
+    ///
+    /// # Parameters
+    ///
+    /// * `user`: The user to get the passkeys for
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if underlying repository fails
+    async fn all(&mut self, user: &User) -> Result<Vec<UserPasskey>, Self::Error>;
+
+    /// Add a new passkey for a user
+    ///
+    /// Returns the newly created [`UserPasskey`]
+    ///
+    /// # Parameters
+    ///
+    /// * `rng`: The random number generator to use
+    /// * `clock`: The clock used to generate timestamps
+    /// * `user`: The user to add the passkey for
+    /// * `credential_id`: The base64url-encoded credential ID
+    /// * `name`: The name of the passkey
+    /// * `public_key`: The credential public key, as a COSE key
+    /// * `sign_count`: The initial signature counter
+    /// * `transports`: The transports supported by the authenticator
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if underlying repository fails
+    #[allow(clippy::too_many_arguments)]
+    async fn add(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        user: &User,
+        credential_id: String,
+        name: String,
+        public_key: Vec<u8>,
+        sign_count: u32,
+        transports: Vec<String>,
+    ) -> Result<UserPasskey, Self::Error>;
+
+    /// Rename a passkey
+    ///
+    /// Returns the updated [`UserPasskey`]
+    ///
+    /// # Parameters
+    ///
+    /// * `user_passkey`: The passkey to rename
+    /// * `name`: The new name
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if underlying repository fails
+    async fn rename(
+        &mut self,
+        user_passkey: UserPasskey,
+        name: String,
+    ) -> Result<UserPasskey, Self::Error>;
+
+    /// Record that a passkey was used, with the new signature counter
+    ///
+    /// Returns the updated [`UserPasskey`]
+    ///
+    /// # Parameters
+    ///
+    /// * `clock`: The clock used to generate timestamps
+    /// * `user_passkey`: The passkey which was used
+    /// * `sign_count`: The signature counter reported by the authenticator
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if underlying repository fails
+    async fn record_use(
+        &mut self,
+        clock: &dyn Clock,
+        user_passkey: UserPasskey,
+        sign_count: u32,
+    ) -> Result<UserPasskey, Self::Error>;
+
+    /// Remove a passkey
+    ///
+    /// # Parameters
+    ///
+    /// * `user_passkey`: The passkey to remove
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if underlying repository fails
+    async fn remove(&mut self, user_passkey: UserPasskey) -> Result<(), Self::Error>;
+
+    /// Add a new challenge for a `WebAuthn` ceremony
+    ///
+    /// Returns the newly created [`UserPasskeyChallenge`]
+    ///
+    /// # Parameters
+    ///
+    /// * `rng`: The random number generator to use
+    /// * `clock`: The clock used to generate timestamps
+    /// * `browser_session`: The browser session which started the ceremony,
+    ///   if any
+    /// * `challenge`: The base64url-encoded challenge
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if underlying repository fails
+    async fn add_challenge(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        browser_session: Option<&BrowserSession>,
+        challenge: String,
+    ) -> Result<UserPasskeyChallenge, Self::Error>;
+
+    /// Lookup a challenge by its ID
+    ///
+    /// Returns `None` if no challenge was found
+    ///
+    /// # Parameters
+    ///
+    /// * `id`: The ID of the challenge to lookup
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if underlying repository fails
+    async fn lookup_challenge(
+        &mut self,
+        id: Ulid,
+    ) -> Result<Option<UserPasskeyChallenge>, Self::Error>;
+
+    /// Mark a challenge as completed, so that it can't be used again
+    ///
+    /// Returns the updated [`UserPasskeyChallenge`]
+    ///
+    /// # Parameters
+    ///
+    /// * `clock`: The clock used to generate timestamps
+    /// * `challenge`: The challenge to mark as completed
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if underlying repository fails
+    async fn complete_challenge(
+        &mut self,
+        clock: &dyn Clock,
+        challenge: UserPasskeyChallenge,
+    ) -> Result<UserPasskeyChallenge, Self::Error>;
+}
+
+repository_impl!(UserPasskeyRepository:
+    async fn lookup(&mut self, id: Ulid) -> Result<Option<UserPasskey>, Self::Error>;
+    async fn find_by_credential_id(
+        &mut self,
+        credential_id: &str,
+    ) -> Result<Option<UserPasskey>, Self::Error>;
+    async fn all(&mut self, user: &User) -> Result<Vec<UserPasskey>, Self::Error>;
+    async fn add(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        user: &User,
+        credential_id: String,
+        name: String,
+        public_key: Vec<u8>,
+        sign_count: u32,
+        transports: Vec<String>,
+    ) -> Result<UserPasskey, Self::Error>;
+    async fn rename(
+        &mut self,
+        user_passkey: UserPasskey,
+        name: String,
+    ) -> Result<UserPasskey, Self::Error>;
+    async fn record_use(
+        &mut self,
+        clock: &dyn Clock,
+        user_passkey: UserPasskey,
+        sign_count: u32,
+    ) -> Result<UserPasskey, Self::Error>;
+    async fn remove(&mut self, user_passkey: UserPasskey) -> Result<(), Self::Error>;
+    async fn add_challenge(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        browser_session: Option<&BrowserSession>,
+        challenge: String,
+    ) -> Result<UserPasskeyChallenge, Self::Error>;
+    async fn lookup_challenge(
+        &mut self,
+        id: Ulid,
+    ) -> Result<Option<UserPasskeyChallenge>, Self::Error>;
+    async fn complete_challenge(
+        &mut self,
+        clock: &dyn Clock,
+        challenge: UserPasskeyChallenge,
+    ) -> Result<UserPasskeyChallenge, Self::Error>;
+);
@@ -0,0 +1,130 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+use async_trait::async_trait;
+use mas_data_model::{User, UserRecoveryCode};
+use rand_core::RngCore;
+use ulid::Ulid;
+
+use crate::{Clock, repository_impl};
+
+/// A [`UserRecoveryCodeRepository`] helps interacting with
+/// [`UserRecoveryCode`] saved in the storage backend
+#[async_trait]
+pub trait UserRecoveryCodeRepository: Send + Sync {
+    /// The error type returned by the repository
+    type Error;
+
+    /// Lookup a recovery code by its ID
+    ///
+    /// Returns `None` if no recovery code was found
+    ///
+    /// # Parameters
+    ///
+    /// * `id`: The ID of the recovery code to lookup
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if underlying repository fails
+    async fn lookup(&mut self, id: Ulid) -> Result<Option<UserRecoveryCode>, Self::Error>;
+
+    /// Get all the recovery codes of a user which were not used yet
+    ///
+    /// # Parameters
+    ///
+    /// * `user`: The user to get the recovery codes for
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if underlying repository fails
+    async fn unused(&mut self, user: &User) -> Result<Vec<UserRecoveryCode>, Self::Error>;
+
+    /// Count the recovery codes of a user which were not used yet
+    ///
+    /// # Parameters
+    ///
+    /// * `user`: The user to count the recovery codes for
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if underlying repository fails
+    async fn count_unused(&mut self, user: &User) -> Result<usize, Self::Error>;
+
+    /// Add a new recovery code for a user
+    ///
+    /// Returns the newly created [`UserRecoveryCode`]
+    ///
+    /// # Parameters
+    ///
+    /// * `rng`: The random number generator to use
+    /// * `clock`: The clock used to generate timestamps
+    /// * `user`: The user to add the recovery code for
+    /// * `version`: The version of the hashing scheme used for the code
+    /// * `hashed_code`: The hashed code
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if underlying repository fails
+    async fn add(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        user: &User,
+        version: u16,
+        hashed_code: String,
+    ) -> Result<UserRecoveryCode, Self::Error>;
+
+    /// Mark a recovery code as used
+    ///
+    /// Returns the updated [`UserRecoveryCode`]
+    ///
+    /// # Parameters
+    ///
+    /// * `clock`: The clock used to generate timestamps
+    /// * `user_recovery_code`: The recovery code which was used
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if underlying repository fails, or if the code
+    /// was already used
+    async fn consume(
+        &mut self,
+        clock: &dyn Clock,
+        user_recovery_code: UserRecoveryCode,
+    ) -> Result<UserRecoveryCode, Self::Error>;
+
+    /// Remove all the recovery codes of a user, used or not
+    ///
+    /// Returns the number of recovery codes removed
+    ///
+    /// # Parameters
+    ///
+    /// * `user`: The user to remove the recovery codes of
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if underlying repository fails
+    async fn remove_all(&mut self, user: &User) -> Result<usize, Self::Error>;
+}
+
+repository_impl!(UserRecoveryCodeRepository:
+    async fn lookup(&mut self, id: Ulid) -> Result<Option<UserRecoveryCode>, Self::Error>;
+    async fn unused(&mut self, user: &User) -> Result<Vec<UserRecoveryCode>, Self::Error>;
+    async fn count_unused(&mut self, user: &User) -> Result<usize, Self::Error>;
+    async fn add(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        user: &User,
+        version: u16,
+        hashed_code: String,
+    ) -> Result<UserRecoveryCode, Self::Error>;
+    async fn consume(
+        &mut self,
+        clock: &dyn Clock,
+        user_recovery_code: UserRecoveryCode,
+    ) -> Result<UserRecoveryCode, Self::Error>;
+    async fn remove_all(&mut self, user: &User) -> Result<usize, Self::Error>;
+);
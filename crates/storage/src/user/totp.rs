@@ -0,0 +1,143 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+use async_trait::async_trait;
+use mas_data_model::{User, UserTotpAuthenticator};
+use rand_core::RngCore;
+use ulid::Ulid;
+
+use crate::{Clock, repository_impl};
+
+/// A [`UserTotpRepository`] helps interacting with [`UserTotpAuthenticator`]
+/// saved in the storage backend
+#[async_trait]
+pub trait UserTotpRepository: Send + Sync {
+    /// The error type returned by the repository
+    type Error;
+
+    /// Lookup a TOTP authenticator by its ID
+    ///
+    /// Returns `None` if no authenticator was found
+    ///
+    /// # Parameters
+    ///
+    /// * `id`: The ID of the authenticator to lookup
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if underlying repository fails
+    async fn lookup(&mut self, id: Ulid) -> Result<Option<UserTotpAuthenticator>, Self::Error>;
+
+    /// Find the TOTP authenticator of a user, verified or not
+    ///
+    /// Returns `None` if the user has no TOTP authenticator
+    ///
+    /// # Parameters
+    ///
+    /// * `user`: The user to find the authenticator for
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if underlying repository fails
+    async fn find(&mut self, user: &User) -> Result<Option<UserTotpAuthenticator>, Self::Error>;
+
+    /// Add a new, unverified, TOTP authenticator for a user
+    ///
+    /// A user can only have one authenticator at a time, so any existing one
+    /// must be removed first
+    ///
+    /// Returns the newly created [`UserTotpAuthenticator`]
+    ///
+    /// # Parameters
+    ///
+    /// * `rng`: The random number generator to use
+    /// * `clock`: The clock used to generate timestamps
+    /// * `user`: The user to add the authenticator for
+    /// * `encrypted_secret`: The shared secret, encrypted
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if underlying repository fails
+    async fn add(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        user: &User,
+        encrypted_secret: String,
+    ) -> Result<UserTotpAuthenticator, Self::Error>;
+
+    /// Mark a TOTP authenticator as verified
+    ///
+    /// Returns the updated [`UserTotpAuthenticator`]
+    ///
+    /// # Parameters
+    ///
+    /// * `clock`: The clock used to generate timestamps
+    /// * `user_totp_authenticator`: The authenticator to mark as verified
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if underlying repository fails
+    async fn verify(
+        &mut self,
+        clock: &dyn Clock,
+        user_totp_authenticator: UserTotpAuthenticator,
+    ) -> Result<UserTotpAuthenticator, Self::Error>;
+
+    /// Record that a code for the given time step was accepted
+    ///
+    /// Returns the updated [`UserTotpAuthenticator`]
+    ///
+    /// # Parameters
+    ///
+    /// * `user_totp_authenticator`: The authenticator which was used
+    /// * `step`: The time step of the accepted code
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if underlying repository fails
+    async fn record_use(
+        &mut self,
+        user_totp_authenticator: UserTotpAuthenticator,
+        step: u64,
+    ) -> Result<UserTotpAuthenticator, Self::Error>;
+
+    /// Remove a TOTP authenticator
+    ///
+    /// # Parameters
+    ///
+    /// * `user_totp_authenticator`: The authenticator to remove
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if underlying repository fails
+    async fn remove(
+        &mut self,
+        user_totp_authenticator: UserTotpAuthenticator,
+    ) -> Result<(), Self::Error>;
+}
+
+repository_impl!(UserTotpRepository:
+    async fn lookup(&mut self, id: Ulid) -> Result<Option<UserTotpAuthenticator>, Self::Error>;
+    async fn find(&mut self, user: &User) -> Result<Option<UserTotpAuthenticator>, Self::Error>;
+    async fn add(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        user: &User,
+        encrypted_secret: String,
+    ) -> Result<UserTotpAuthenticator, Self::Error>;
+    async fn verify(
+        &mut self,
+        clock: &dyn Clock,
+        user_totp_authenticator: UserTotpAuthenticator,
+    ) -> Result<UserTotpAuthenticator, Self::Error>;
+    async fn record_use(
+        &mut self,
+        user_totp_authenticator: UserTotpAuthenticator,
+        step: u64,
+    ) -> Result<UserTotpAuthenticator, Self::Error>;
+    async fn remove(&mut self, user_totp_authenticator: UserTotpAuthenticator) -> Result<(), Self::Error>;
+);
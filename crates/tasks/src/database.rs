@@ -27,6 +27,18 @@ impl RunnableJob for CleanupExpiredTokensJob {
             .cleanup_revoked(&clock)
             .await
             .map_err(JobError::retry)?;
+
+        let dpop_proofs_count = repo
+            .oauth2_dpop_proof()
+            .cleanup_expired(&clock)
+            .await
+            .map_err(JobError::retry)?;
+
+        let jwt_bearer_assertions_count = repo
+            .oauth2_jwt_bearer_assertion()
+            .cleanup_expired(&clock)
+            .await
+            .map_err(JobError::retry)?;
         repo.save().await.map_err(JobError::retry)?;
 
         if count == 0 {
@@ -35,6 +47,21 @@ impl RunnableJob for CleanupExpiredTokensJob {
             info!(count, "cleaned up revoked tokens");
         }
 
+        if dpop_proofs_count == 0 {
+            debug!("no DPoP proof to clean up");
+        } else {
+            info!(count = dpop_proofs_count, "cleaned up expired DPoP proofs");
+        }
+
+        if jwt_bearer_assertions_count == 0 {
+            debug!("no JWT bearer assertion to clean up");
+        } else {
+            info!(
+                count = jwt_bearer_assertions_count,
+                "cleaned up expired JWT bearer assertions"
+            );
+        }
+
         Ok(())
     }
 }
@@ -4,13 +4,24 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 // Please see LICENSE in the repository root for full details.
 
+use anyhow::Context as _;
 use async_trait::async_trait;
 use chrono::Duration;
 use mas_email::{Address, EmailVerificationContext, Mailbox};
-use mas_storage::queue::{SendEmailAuthenticationCodeJob, VerifyEmailJob};
-use mas_templates::TemplateContext as _;
+use mas_i18n::DataLocale;
+use mas_storage::{
+    Pagination,
+    queue::{
+        SendEmailAuthenticationCodeJob, SendRecoveryCodeUsedEmailJob, SendSessionRevokedEmailJob,
+        VerifyEmailJob,
+    },
+    user::UserEmailFilter,
+};
+use mas_templates::{
+    EmailRecoveryCodeUsedContext, EmailSessionRevokedContext, TemplateContext as _,
+};
 use rand::{Rng, distributions::Uniform};
-use tracing::info;
+use tracing::{error, info};
 
 use crate::{
     State,
@@ -94,6 +105,19 @@ impl RunnableJob for SendEmailAuthenticationCodeJob {
                 None
             };
 
+        // Load the user logging in, if any
+        let user = if let Some(user_id) = user_email_authentication.user_id {
+            Some(
+                repo.user()
+                    .lookup(user_id)
+                    .await
+                    .map_err(JobError::retry)?
+                    .ok_or(JobError::fail(anyhow::anyhow!("Failed to load user")))?,
+            )
+        } else {
+            None
+        };
+
         // Generate a new 6-digit authentication code
         let range = Uniform::<u32>::from(0..1_000_000);
         let code = rng.sample(range);
@@ -116,15 +140,21 @@ impl RunnableJob for SendEmailAuthenticationCodeJob {
             .map_err(JobError::fail)?;
         let username_from_session = browser_session.as_ref().map(|s| s.user.username.clone());
         let username_from_registration = registration.as_ref().map(|r| r.username.clone());
-        let username = username_from_registration.or(username_from_session);
+        let username_from_user = user.as_ref().map(|u| u.username.clone());
+        let username = username_from_registration
+            .or(username_from_session)
+            .or(username_from_user);
         let mailbox = Mailbox::new(username, address);
 
         info!("Sending email verification code to {}", mailbox);
 
         let language = self.language().parse().map_err(JobError::fail)?;
 
-        let context = EmailVerificationContext::new(code, browser_session, registration)
-            .with_language(language);
+        let mut context = EmailVerificationContext::new(code, browser_session, registration);
+        if let Some(user) = user {
+            context = context.with_user(user);
+        }
+        let context = context.with_language(language);
         mailer
             .send_verification_email(mailbox, &context)
             .await
@@ -135,3 +165,167 @@ impl RunnableJob for SendEmailAuthenticationCodeJob {
         Ok(())
     }
 }
+
+#[async_trait]
+impl RunnableJob for SendSessionRevokedEmailJob {
+    #[tracing::instrument(
+        name = "job.send_session_revoked_email",
+        fields(
+            user.id = %self.user_id(),
+            oauth2_session.id = %self.oauth2_session_id(),
+        ),
+        skip_all,
+        err,
+    )]
+    async fn run(&self, state: &State, _context: JobContext) -> Result<(), JobError> {
+        let mailer = state.mailer();
+        let mut repo = state.repository().await.map_err(JobError::retry)?;
+
+        let user = repo
+            .user()
+            .lookup(self.user_id())
+            .await
+            .map_err(JobError::retry)?
+            .context("User not found")
+            .map_err(JobError::fail)?;
+
+        let session = repo
+            .oauth2_session()
+            .lookup(self.oauth2_session_id())
+            .await
+            .map_err(JobError::retry)?
+            .context("OAuth 2.0 session not found")
+            .map_err(JobError::fail)?;
+
+        let client = repo
+            .oauth2_client()
+            .lookup(session.client_id)
+            .await
+            .map_err(JobError::retry)?
+            .context("OAuth 2.0 client not found")
+            .map_err(JobError::fail)?;
+
+        let lang: DataLocale = user
+            .locale
+            .as_deref()
+            .unwrap_or("en")
+            .parse()
+            .context("Invalid locale in database on user")
+            .map_err(JobError::fail)?;
+
+        let context = EmailSessionRevokedContext::new(user.clone(), client).with_language(lang);
+
+        let mut cursor = Pagination::first(50);
+        loop {
+            let page = repo
+                .user_email()
+                .list(UserEmailFilter::new().for_user(&user), cursor)
+                .await
+                .map_err(JobError::retry)?;
+
+            for email in page.edges {
+                let address: Address = email.email.parse().map_err(JobError::fail)?;
+                let mailbox = Mailbox::new(Some(user.username.clone()), address);
+
+                info!("Sending session revoked email to {}", mailbox);
+
+                // XXX: we only log if the email fails to send, to avoid stopping the loop
+                if let Err(e) = mailer.send_session_revoked_email(mailbox, &context).await {
+                    error!(
+                        error = &e as &dyn std::error::Error,
+                        "Failed to send session revoked email"
+                    );
+                }
+
+                cursor = cursor.after(email.id);
+            }
+
+            if !page.has_next_page {
+                break;
+            }
+        }
+
+        repo.cancel().await.map_err(JobError::retry)?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl RunnableJob for SendRecoveryCodeUsedEmailJob {
+    #[tracing::instrument(
+        name = "job.send_recovery_code_used_email",
+        fields(
+            user.id = %self.user_id(),
+            user_recovery_code.id = %self.user_recovery_code_id(),
+        ),
+        skip_all,
+        err,
+    )]
+    async fn run(&self, state: &State, _context: JobContext) -> Result<(), JobError> {
+        let mailer = state.mailer();
+        let mut repo = state.repository().await.map_err(JobError::retry)?;
+
+        let user = repo
+            .user()
+            .lookup(self.user_id())
+            .await
+            .map_err(JobError::retry)?
+            .context("User not found")
+            .map_err(JobError::fail)?;
+
+        let remaining = repo
+            .user_recovery_code()
+            .count_unused(&user)
+            .await
+            .map_err(JobError::retry)?;
+
+        let lang: DataLocale = user
+            .locale
+            .as_deref()
+            .unwrap_or("en")
+            .parse()
+            .context("Invalid locale in database on user")
+            .map_err(JobError::fail)?;
+
+        let context =
+            EmailRecoveryCodeUsedContext::new(user.clone(), remaining).with_language(lang);
+
+        let mut cursor = Pagination::first(50);
+        loop {
+            let page = repo
+                .user_email()
+                .list(UserEmailFilter::new().for_user(&user), cursor)
+                .await
+                .map_err(JobError::retry)?;
+
+            for email in page.edges {
+                let address: Address = email.email.parse().map_err(JobError::fail)?;
+                let mailbox = Mailbox::new(Some(user.username.clone()), address);
+
+                info!("Sending recovery code used email to {}", mailbox);
+
+                // XXX: we only log if the email fails to send, to avoid stopping the loop
+                if let Err(e) = mailer
+                    .send_recovery_code_used_email(mailbox, &context)
+                    .await
+                {
+                    error!(
+                        error = &e as &dyn std::error::Error,
+                        "Failed to send recovery code used email"
+                    );
+                }
+
+                cursor = cursor.after(email.id);
+            }
+
+            if !page.has_next_page {
+                break;
+            }
+        }
+
+        repo.cancel().await.map_err(JobError::retry)?;
+
+        Ok(())
+    }
+}
@@ -8,6 +8,7 @@ use std::sync::{Arc, LazyLock};
 
 use mas_data_model::SiteConfig;
 use mas_email::Mailer;
+use mas_keystore::{Encrypter, Keystore, SharedKeystore};
 use mas_matrix::HomeserverConnection;
 use mas_router::UrlBuilder;
 use mas_storage::{BoxClock, BoxRepository, RepositoryError, SystemClock};
@@ -24,6 +25,7 @@ mod matrix;
 mod new_queue;
 mod recovery;
 mod sessions;
+mod signing_keys;
 mod user;
 
 static METER: LazyLock<Meter> = LazyLock::new(|| {
@@ -43,9 +45,13 @@ struct State {
     homeserver: Arc<dyn HomeserverConnection>,
     url_builder: UrlBuilder,
     site_config: SiteConfig,
+    key_store: SharedKeystore,
+    encrypter: Encrypter,
+    http_client: reqwest::Client,
 }
 
 impl State {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         pool: Pool<Postgres>,
         clock: SystemClock,
@@ -53,6 +59,9 @@ impl State {
         homeserver: impl HomeserverConnection + 'static,
         url_builder: UrlBuilder,
         site_config: SiteConfig,
+        key_store: SharedKeystore,
+        encrypter: Encrypter,
+        http_client: reqwest::Client,
     ) -> Self {
         Self {
             pool,
@@ -61,6 +70,9 @@ impl State {
             homeserver: Arc::new(homeserver),
             url_builder,
             site_config,
+            key_store,
+            encrypter,
+            http_client,
         }
     }
 
@@ -102,6 +114,18 @@ impl State {
     pub fn site_config(&self) -> &SiteConfig {
         &self.site_config
     }
+
+    pub fn key_store(&self) -> Keystore {
+        self.key_store.load()
+    }
+
+    pub fn encrypter(&self) -> &Encrypter {
+        &self.encrypter
+    }
+
+    pub fn http_client(&self) -> &reqwest::Client {
+        &self.http_client
+    }
 }
 
 /// Initialise the workers.
@@ -109,12 +133,16 @@ impl State {
 /// # Errors
 ///
 /// This function can fail if the database connection fails.
+#[allow(clippy::too_many_arguments)]
 pub async fn init(
     pool: &Pool<Postgres>,
     mailer: &Mailer,
     homeserver: impl HomeserverConnection + 'static,
     url_builder: UrlBuilder,
     site_config: &SiteConfig,
+    key_store: &SharedKeystore,
+    encrypter: &Encrypter,
+    http_client: &reqwest::Client,
     cancellation_token: CancellationToken,
     task_tracker: &TaskTracker,
 ) -> Result<(), QueueRunnerError> {
@@ -125,6 +153,9 @@ pub async fn init(
         homeserver,
         url_builder,
         site_config.clone(),
+        key_store.clone(),
+        encrypter.clone(),
+        http_client.clone(),
     );
     let mut worker = self::new_queue::QueueWorker::new(state, cancellation_token).await?;
 
@@ -137,6 +168,8 @@ pub async fn init(
         .register_handler::<mas_storage::queue::ReactivateUserJob>()
         .register_handler::<mas_storage::queue::SendAccountRecoveryEmailsJob>()
         .register_handler::<mas_storage::queue::SendEmailAuthenticationCodeJob>()
+        .register_handler::<mas_storage::queue::SendRecoveryCodeUsedEmailJob>()
+        .register_handler::<mas_storage::queue::SendSessionRevokedEmailJob>()
         .register_handler::<mas_storage::queue::SyncDevicesJob>()
         .register_handler::<mas_storage::queue::VerifyEmailJob>()
         .register_handler::<mas_storage::queue::ExpireInactiveSessionsJob>()
@@ -144,6 +177,9 @@ pub async fn init(
         .register_handler::<mas_storage::queue::ExpireInactiveOAuthSessionsJob>()
         .register_handler::<mas_storage::queue::ExpireInactiveUserSessionsJob>()
         .register_handler::<mas_storage::queue::PruneStalePolicyDataJob>()
+        .register_handler::<mas_storage::queue::RotateSigningKeysJob>()
+        .register_handler::<mas_storage::queue::SendBackchannelLogoutJob>()
+        .register_handler::<mas_storage::queue::BrowserSessionBackchannelLogoutJob>()
         .add_schedule(
             "cleanup-expired-tokens",
             "0 0 * * * *".parse()?,
@@ -160,6 +196,12 @@ pub async fn init(
             // Run once a day
             "0 0 2 * * *".parse()?,
             mas_storage::queue::PruneStalePolicyDataJob,
+        )
+        .add_schedule(
+            "rotate-signing-keys",
+            // Run once an hour
+            "0 45 * * * *".parse()?,
+            mas_storage::queue::RotateSigningKeysJob,
         );
 
     task_tracker.spawn(worker.run());
@@ -3,19 +3,33 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 // Please see LICENSE in the repository root for full details.
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
+use anyhow::Context;
 use async_trait::async_trait;
-use chrono::Duration;
+use chrono::{DateTime, Duration, Utc};
+use mas_data_model::BrowserSession;
+use mas_http::RequestBuilderExt as _;
+use mas_iana::jose::JsonWebSignatureAlg;
+use mas_jose::{
+    claims::{self, ClaimError},
+    constraints::Constrainable,
+    jwt::{JsonWebSignatureHeader, Jwt},
+};
+use mas_keystore::Keystore;
 use mas_storage::{
+    BoxRepository, Clock, RepositoryError,
     compat::CompatSessionFilter,
     oauth2::OAuth2SessionFilter,
     queue::{
-        ExpireInactiveCompatSessionsJob, ExpireInactiveOAuthSessionsJob, ExpireInactiveSessionsJob,
-        ExpireInactiveUserSessionsJob, QueueJobRepositoryExt, SyncDevicesJob,
+        BrowserSessionBackchannelLogoutJob, ExpireInactiveCompatSessionsJob,
+        ExpireInactiveOAuthSessionsJob, ExpireInactiveSessionsJob, ExpireInactiveUserSessionsJob,
+        QueueJobRepositoryExt, SendBackchannelLogoutJob, SyncDevicesJob,
     },
     user::BrowserSessionFilter,
 };
+use rand::{CryptoRng, RngCore};
+use ulid::Ulid;
 
 use crate::{
     State,
@@ -127,6 +141,11 @@ impl RunnableJob for ExpireInactiveOAuthSessionsJob {
                 }
             }
 
+            repo.queue_job()
+                .schedule_job(&mut rng, &clock, SendBackchannelLogoutJob::new(&edge))
+                .await
+                .map_err(JobError::retry)?;
+
             repo.oauth2_session()
                 .finish(&clock, edge)
                 .await
@@ -229,6 +248,15 @@ impl RunnableJob for ExpireInactiveUserSessionsJob {
         }
 
         for edge in page.edges {
+            repo.queue_job()
+                .schedule_job(
+                    &mut rng,
+                    &clock,
+                    BrowserSessionBackchannelLogoutJob::new(&edge),
+                )
+                .await
+                .map_err(JobError::retry)?;
+
             repo.browser_session()
                 .finish(&clock, edge)
                 .await
@@ -240,3 +268,467 @@ impl RunnableJob for ExpireInactiveUserSessionsJob {
         Ok(())
     }
 }
+
+/// The event type signaling a back-channel logout in a logout token
+const BACKCHANNEL_LOGOUT_EVENT: &str = "http://schemas.openid.net/event/backchannel-logout";
+
+#[async_trait]
+impl RunnableJob for BrowserSessionBackchannelLogoutJob {
+    #[tracing::instrument(
+        name = "job.browser_session_backchannel_logout",
+        fields(user_session.id = %self.browser_session_id()),
+        skip_all,
+        err,
+    )]
+    async fn run(&self, state: &State, _context: JobContext) -> Result<(), JobError> {
+        let mut repo = state.repository().await.map_err(JobError::retry)?;
+        let clock = state.clock();
+        let mut rng = state.rng();
+
+        let browser_session = repo
+            .browser_session()
+            .lookup(self.browser_session_id())
+            .await
+            .map_err(JobError::retry)?
+            .context("Browser session not found")
+            .map_err(JobError::fail)?;
+
+        schedule_backchannel_logouts(&mut repo, &mut rng, &clock, self, &browser_session)
+            .await
+            .map_err(JobError::retry)?;
+
+        repo.save().await.map_err(JobError::retry)?;
+
+        Ok(())
+    }
+}
+
+/// Schedule a [`SendBackchannelLogoutJob`] for each active OAuth 2.0 session
+/// of a browser session, if the client registered a back-channel logout URI
+///
+/// Only one page of sessions is processed, and the job for the next page is
+/// scheduled if there are more.
+async fn schedule_backchannel_logouts(
+    repo: &mut BoxRepository,
+    rng: &mut (dyn RngCore + Send),
+    clock: &dyn Clock,
+    job: &BrowserSessionBackchannelLogoutJob,
+    browser_session: &BrowserSession,
+) -> Result<(), RepositoryError> {
+    let filter = OAuth2SessionFilter::new()
+        .for_browser_session(browser_session)
+        .active_only();
+
+    let pagination = job.pagination(100);
+
+    let page = repo.oauth2_session().list(filter, pagination).await?;
+
+    if let Some(job) = job.next(&page) {
+        repo.queue_job().schedule_job(rng, clock, job).await?;
+    }
+
+    // Sessions of the same client are likely to be on the same page, so we
+    // avoid looking up the same client multiple times
+    let mut has_backchannel_logout_uri = HashMap::new();
+    for edge in page.edges {
+        let notify = if let Some(notify) = has_backchannel_logout_uri.get(&edge.client_id) {
+            *notify
+        } else {
+            let notify = repo
+                .oauth2_client()
+                .lookup(edge.client_id)
+                .await?
+                .is_some_and(|client| client.backchannel_logout_uri.is_some());
+            has_backchannel_logout_uri.insert(edge.client_id, notify);
+            notify
+        };
+
+        if notify {
+            repo.queue_job()
+                .schedule_job(rng, clock, SendBackchannelLogoutJob::new(&edge))
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[async_trait]
+impl RunnableJob for SendBackchannelLogoutJob {
+    #[tracing::instrument(
+        name = "job.send_backchannel_logout",
+        fields(oauth2_session.id = %self.oauth2_session_id()),
+        skip_all,
+        err,
+    )]
+    async fn run(&self, state: &State, _context: JobContext) -> Result<(), JobError> {
+        let mut repo = state.repository().await.map_err(JobError::retry)?;
+        let clock = state.clock();
+        let mut rng = state.rng();
+
+        let session = repo
+            .oauth2_session()
+            .lookup(self.oauth2_session_id())
+            .await
+            .map_err(JobError::retry)?
+            .context("OAuth 2.0 session not found")
+            .map_err(JobError::fail)?;
+
+        let client = repo
+            .oauth2_client()
+            .lookup(session.client_id)
+            .await
+            .map_err(JobError::retry)?
+            .context("Client not found")
+            .map_err(JobError::fail)?;
+
+        let Some(backchannel_logout_uri) = client.backchannel_logout_uri.clone() else {
+            // The client did not register for back-channel logout notifications
+            return Ok(());
+        };
+
+        let Some(user_id) = session.user_id else {
+            // Sessions without a user (e.g. client credentials) have no subject to log out
+            return Ok(());
+        };
+
+        if client.backchannel_logout_session_required && session.user_session_id.is_none() {
+            tracing::warn!(
+                client.id = %client.id,
+                "Client requires a session ID in logout tokens, but the session has none"
+            );
+            return Ok(());
+        }
+
+        let user = repo
+            .user()
+            .lookup(user_id)
+            .await
+            .map_err(JobError::retry)?
+            .context("User not found")
+            .map_err(JobError::fail)?;
+
+        // The subject must be the one the client knows the user by
+        let subject = match client.sector_identifier() {
+            Some(sector_identifier) => repo
+                .oauth2_pairwise_subject()
+                .find_or_add(&mut rng, &clock, sector_identifier, &user)
+                .await
+                .map_err(JobError::retry)?,
+            None => user.sub.clone(),
+        };
+
+        // We don't need the database connection anymore. This is saved, as we may
+        // have given out a new pairwise subject identifier
+        repo.save().await.map_err(JobError::retry)?;
+
+        // The logout token is signed with the same algorithm as the ID tokens
+        let alg = client
+            .id_token_signed_response_alg
+            .clone()
+            .unwrap_or(JsonWebSignatureAlg::Rs256);
+        let claims = logout_token_claims(
+            &mut rng,
+            clock.now(),
+            state.url_builder().oidc_issuer().as_str(),
+            &client.client_id,
+            subject,
+            session.user_session_id,
+        )
+        .map_err(JobError::fail)?;
+        let logout_token = sign_logout_token(&mut rng, &state.key_store(), &alg, claims)
+            .map_err(JobError::fail)?;
+
+        let response = state
+            .http_client()
+            .post(backchannel_logout_uri)
+            .form(&[("logout_token", logout_token.as_str())])
+            .send_traced()
+            .await
+            .context("Failed to send the back-channel logout request")
+            .map_err(JobError::retry)?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(JobError::retry(anyhow::anyhow!(
+                "Client responded to the back-channel logout request with status {status}"
+            )));
+        }
+
+        tracing::info!(client.id = %client.id, "Sent back-channel logout notification");
+
+        Ok(())
+    }
+}
+
+/// Build the claims of the logout token sent to a client
+///
+/// The `sid` claim is only included if the OAuth 2.0 session was started from
+/// a browser session.
+fn logout_token_claims(
+    rng: &mut (impl RngCore + CryptoRng),
+    now: DateTime<Utc>,
+    issuer: &str,
+    client_id: &str,
+    subject: String,
+    user_session_id: Option<Ulid>,
+) -> Result<HashMap<String, serde_json::Value>, ClaimError> {
+    let mut claims = HashMap::new();
+    claims::ISS.insert(&mut claims, issuer.to_owned())?;
+    claims::SUB.insert(&mut claims, subject)?;
+    claims::AUD.insert(&mut claims, client_id.to_owned())?;
+    claims::IAT.insert(&mut claims, now)?;
+    claims::EXP.insert(&mut claims, now + Duration::minutes(2))?;
+    claims::JTI.insert(
+        &mut claims,
+        Ulid::from_datetime_with_source(now.into(), rng).to_string(),
+    )?;
+    claims::EVENTS.insert(
+        &mut claims,
+        serde_json::json!({ BACKCHANNEL_LOGOUT_EVENT: {} }),
+    )?;
+
+    if let Some(user_session_id) = user_session_id {
+        claims::SID.insert(&mut claims, user_session_id.to_string())?;
+    }
+
+    Ok(claims)
+}
+
+/// Sign a logout token with a key of the keystore suitable for the given
+/// algorithm
+fn sign_logout_token(
+    rng: &mut (impl RngCore + CryptoRng),
+    key_store: &Keystore,
+    alg: &JsonWebSignatureAlg,
+    claims: HashMap<String, serde_json::Value>,
+) -> anyhow::Result<Jwt<'static, HashMap<String, serde_json::Value>>> {
+    let key = key_store
+        .signing_key_for_algorithm(alg)
+        .context("No suitable signing key found")?;
+    let signer = key.params().signing_key_for_alg(alg)?;
+    let header = JsonWebSignatureHeader::new(alg.clone())
+        .with_kid(key.kid().context("Signing key has no key ID")?)
+        .with_typ("logout+jwt".to_owned());
+    let logout_token = Jwt::sign_with_rng(rng, header, claims, &signer)?;
+    Ok(logout_token)
+}
+
+#[cfg(test)]
+mod tests {
+    use mas_data_model::Client;
+    use mas_jose::jwk::{JsonWebKey, JsonWebKeySet};
+    use mas_keystore::PrivateKey;
+    use mas_storage::{
+        Clock, RepositoryAccess,
+        clock::MockClock,
+        queue::{BrowserSessionBackchannelLogoutJob, InsertableJob, SendBackchannelLogoutJob},
+    };
+    use mas_storage_pg::PgRepository;
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+    use sqlx::PgPool;
+
+    use super::*;
+
+    #[test]
+    fn test_logout_token() {
+        let mut rng = ChaChaRng::seed_from_u64(42);
+        let clock = MockClock::default();
+        let key = PrivateKey::generate_ec_p256(&mut rng);
+        let key_store = Keystore::new(JsonWebKeySet::new(vec![
+            JsonWebKey::new(key).with_kid("ec"),
+        ]));
+        let user_session_id = Ulid::from_datetime_with_source(clock.now().into(), &mut rng);
+
+        let claims = logout_token_claims(
+            &mut rng,
+            clock.now(),
+            "https://example.com/",
+            "client",
+            "subject".to_owned(),
+            Some(user_session_id),
+        )
+        .unwrap();
+        let logout_token =
+            sign_logout_token(&mut rng, &key_store, &JsonWebSignatureAlg::Es256, claims).unwrap();
+
+        // Decode the token again, and check its signature
+        let logout_token: Jwt<'_, HashMap<String, serde_json::Value>> =
+            Jwt::try_from(logout_token.as_str()).unwrap();
+        logout_token
+            .verify_with_jwks(&key_store.public_jwks())
+            .unwrap();
+
+        assert_eq!(logout_token.header().typ(), Some("logout+jwt"));
+        assert_eq!(logout_token.header().kid(), Some("ec"));
+
+        let claims = logout_token.payload();
+        assert_eq!(claims["iss"], "https://example.com/");
+        assert_eq!(claims["aud"], "client");
+        assert_eq!(claims["sub"], "subject");
+        assert_eq!(claims["sid"], user_session_id.to_string());
+        assert_eq!(
+            claims["events"],
+            serde_json::json!({ BACKCHANNEL_LOGOUT_EVENT: {} })
+        );
+        assert!(claims.contains_key("jti"));
+        // Logout tokens must never contain a nonce
+        assert!(!claims.contains_key("nonce"));
+
+        // Sessions without a browser session don't get a `sid` claim
+        let claims = logout_token_claims(
+            &mut rng,
+            clock.now(),
+            "https://example.com/",
+            "client",
+            "subject".to_owned(),
+            None,
+        )
+        .unwrap();
+        assert!(!claims.contains_key("sid"));
+    }
+
+    async fn add_client(
+        repo: &mut BoxRepository,
+        rng: &mut ChaChaRng,
+        clock: &MockClock,
+        backchannel_logout: bool,
+    ) -> Client {
+        let backchannel_logout_uri =
+            backchannel_logout.then(|| "https://example.com/logout".parse().unwrap());
+
+        repo.oauth2_client()
+            .add(
+                rng,
+                clock,
+                vec!["https://example.com/redirect".parse().unwrap()],
+                None,
+                None,
+                None,
+                Vec::new(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                Vec::new(),
+                backchannel_logout_uri,
+                false,
+                false,
+                None,
+                Vec::new(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_schedule_backchannel_logouts(pool: PgPool) {
+        let mut rng = ChaChaRng::seed_from_u64(42);
+        let clock = MockClock::default();
+        let mut repo = PgRepository::from_pool(&pool).await.unwrap().boxed();
+
+        let user = repo
+            .user()
+            .add(&mut rng, &clock, "john".to_owned())
+            .await
+            .unwrap();
+        let browser_session = repo
+            .browser_session()
+            .add(&mut rng, &clock, &user, None)
+            .await
+            .unwrap();
+        let other_browser_session = repo
+            .browser_session()
+            .add(&mut rng, &clock, &user, None)
+            .await
+            .unwrap();
+
+        // Two clients registered for back-channel logout notifications, one didn't
+        let first_client = add_client(&mut repo, &mut rng, &clock, true).await;
+        let second_client = add_client(&mut repo, &mut rng, &clock, true).await;
+        let third_client = add_client(&mut repo, &mut rng, &clock, false).await;
+
+        let first_session = repo
+            .oauth2_session()
+            .add_from_browser_session(
+                &mut rng,
+                &clock,
+                &first_client,
+                &browser_session,
+                "openid".parse().unwrap(),
+            )
+            .await
+            .unwrap();
+        let second_session = repo
+            .oauth2_session()
+            .add_from_browser_session(
+                &mut rng,
+                &clock,
+                &second_client,
+                &browser_session,
+                "openid".parse().unwrap(),
+            )
+            .await
+            .unwrap();
+        repo.oauth2_session()
+            .add_from_browser_session(
+                &mut rng,
+                &clock,
+                &third_client,
+                &browser_session,
+                "openid".parse().unwrap(),
+            )
+            .await
+            .unwrap();
+        // This session belongs to another browser session, so it is not notified
+        repo.oauth2_session()
+            .add_from_browser_session(
+                &mut rng,
+                &clock,
+                &first_client,
+                &other_browser_session,
+                "openid".parse().unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let job = BrowserSessionBackchannelLogoutJob::new(&browser_session);
+        schedule_backchannel_logouts(&mut repo, &mut rng, &clock, &job, &browser_session)
+            .await
+            .unwrap();
+        repo.save().await.unwrap();
+
+        let mut scheduled: Vec<serde_json::Value> =
+            sqlx::query_scalar("SELECT payload FROM queue_jobs WHERE queue_name = $1")
+                .bind(SendBackchannelLogoutJob::QUEUE_NAME)
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        scheduled.sort_by_key(ToString::to_string);
+
+        let mut expected = vec![
+            serde_json::json!({ "oauth2_session_id": first_session.id }),
+            serde_json::json!({ "oauth2_session_id": second_session.id }),
+        ];
+        expected.sort_by_key(ToString::to_string);
+
+        assert_eq!(scheduled, expected);
+    }
+}
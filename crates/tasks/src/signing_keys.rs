@@ -0,0 +1,132 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+//! Automatic rotation of the signing keys
+
+use anyhow::Context;
+use async_trait::async_trait;
+use mas_keystore::PrivateKey;
+use mas_storage::queue::RotateSigningKeysJob;
+use rand::{
+    SeedableRng,
+    distributions::{Alphanumeric, DistString},
+};
+use tracing::info;
+
+use crate::{
+    State,
+    new_queue::{JobContext, JobError, RunnableJob},
+};
+
+/// Generate a new set of keys, one for each supported key type
+async fn generate_keys(rng: &mut rand_chacha::ChaChaRng) -> Result<Vec<PrivateKey>, JobError> {
+    // Generating RSA keys is CPU-intensive, so do it in a blocking task
+    let key_rng = rand_chacha::ChaChaRng::from_rng(&mut *rng).map_err(JobError::fail)?;
+    let rsa = tokio::task::spawn_blocking(move || PrivateKey::generate_rsa(key_rng))
+        .await
+        .map_err(JobError::fail)?
+        .map_err(JobError::fail)?;
+
+    Ok(vec![
+        rsa,
+        PrivateKey::generate_ec_p256(&mut *rng),
+        PrivateKey::generate_ec_p384(&mut *rng),
+        PrivateKey::generate_ec_k256(&mut *rng),
+        PrivateKey::generate_ed25519(&mut *rng),
+    ])
+}
+
+#[async_trait]
+impl RunnableJob for RotateSigningKeysJob {
+    #[tracing::instrument(name = "job.rotate_signing_keys", skip_all, err)]
+    async fn run(&self, state: &State, _context: JobContext) -> Result<(), JobError> {
+        let Some(config) = state.site_config().signing_key_rotation.as_ref() else {
+            // Automatic key rotation is disabled
+            return Ok(());
+        };
+
+        let clock = state.clock();
+        let mut rng = state.rng();
+        let now = clock.now();
+        let mut repo = state.repository().await.map_err(JobError::retry)?;
+
+        let mut active = Vec::new();
+        let mut pending = Vec::new();
+        let keys = repo.signing_key().all().await.map_err(JobError::retry)?;
+        for key in keys {
+            if key.is_pending() {
+                pending.push(key);
+            } else if key.is_active() {
+                active.push(key);
+            } else if key
+                .retired_at
+                .is_some_and(|retired_at| retired_at + config.retention <= now)
+            {
+                // The key was retired long enough ago that tokens signed with it
+                // should have expired, stop publishing it
+                info!(signing_key.kid = %key.kid, "Removing retired signing key");
+                repo.signing_key()
+                    .remove(key)
+                    .await
+                    .map_err(JobError::retry)?;
+            }
+        }
+
+        // Generate the next set of keys ahead of time, so that they get
+        // published for long enough before the active ones are due to be
+        // replaced
+        let last_activation = active.iter().filter_map(|key| key.activated_at).max();
+        let due = last_activation.is_none_or(|activated_at| {
+            activated_at + config.rotation_interval - config.publication_delay <= now
+        });
+        if pending.is_empty() && due {
+            info!("Generating a new set of signing keys");
+            for key in generate_keys(&mut rng).await? {
+                let der = key.to_pkcs8_der().map_err(JobError::fail)?;
+                let encrypted_key = state
+                    .encrypter()
+                    .encrypt_to_string(&der)
+                    .context("Failed to encrypt the signing key")
+                    .map_err(JobError::fail)?;
+                let kid = Alphanumeric.sample_string(&mut rng, 10);
+                let key = repo
+                    .signing_key()
+                    .add(&mut rng, &clock, kid, encrypted_key)
+                    .await
+                    .map_err(JobError::retry)?;
+                pending.push(key);
+            }
+        }
+
+        // Start signing with the pending keys once they were published for long
+        // enough. If there is no key at all to sign with, there is no point in
+        // waiting, so they are used right away
+        let published = pending
+            .iter()
+            .all(|key| key.created_at + config.publication_delay <= now);
+        let nothing_to_sign_with = active.is_empty() && state.key_store().is_empty();
+        if !pending.is_empty() && (published || nothing_to_sign_with) {
+            for key in active {
+                info!(signing_key.kid = %key.kid, "Retiring signing key");
+                repo.signing_key()
+                    .retire(&clock, key)
+                    .await
+                    .map_err(JobError::retry)?;
+            }
+
+            for key in pending {
+                info!(signing_key.kid = %key.kid, "Activating signing key");
+                repo.signing_key()
+                    .activate(&clock, key)
+                    .await
+                    .map_err(JobError::retry)?;
+            }
+        }
+
+        repo.save().await.map_err(JobError::retry)?;
+
+        Ok(())
+    }
+}
@@ -7,10 +7,12 @@
 use anyhow::Context;
 use async_trait::async_trait;
 use mas_storage::{
-    RepositoryAccess,
+    Pagination, RepositoryAccess,
     compat::CompatSessionFilter,
     oauth2::OAuth2SessionFilter,
-    queue::{DeactivateUserJob, ReactivateUserJob},
+    queue::{
+        DeactivateUserJob, QueueJobRepositoryExt as _, ReactivateUserJob, SendBackchannelLogoutJob,
+    },
     user::{BrowserSessionFilter, UserEmailFilter, UserRepository},
 };
 use tracing::info;
@@ -31,6 +33,7 @@ impl RunnableJob for DeactivateUserJob {
     )]
     async fn run(&self, state: &State, _context: JobContext) -> Result<(), JobError> {
         let clock = state.clock();
+        let mut rng = state.rng();
         let matrix = state.matrix_connection();
         let mut repo = state.repository().await.map_err(JobError::retry)?;
 
@@ -68,6 +71,29 @@ impl RunnableJob for DeactivateUserJob {
             .map_err(JobError::retry)?;
         info!(affected = n, "Killed all browser sessions for user");
 
+        // Notify the clients of the OAuth 2.0 sessions we're about to kill
+        let filter = OAuth2SessionFilter::new().for_user(&user).active_only();
+        let mut pagination = Pagination::first(100);
+        loop {
+            let page = repo
+                .oauth2_session()
+                .list(filter, pagination)
+                .await
+                .map_err(JobError::retry)?;
+
+            for session in &page.edges {
+                repo.queue_job()
+                    .schedule_job(&mut rng, &clock, SendBackchannelLogoutJob::new(session))
+                    .await
+                    .map_err(JobError::retry)?;
+            }
+
+            match page.edges.last() {
+                Some(last) if page.has_next_page => pagination = pagination.after(last.id),
+                _ => break,
+            }
+        }
+
         let n = repo
             .oauth2_session()
             .finish_bulk(
@@ -40,7 +40,7 @@ use url::Url;
 pub use self::{
     branding::SiteBranding, captcha::WithCaptcha, ext::SiteConfigExt, features::SiteFeatures,
 };
-use crate::{FieldError, FormField, FormState};
+use crate::{FieldError, FormError, FormField, FormState};
 
 /// Helper trait to construct context wrappers
 pub trait TemplateContext: Serialize {
@@ -507,6 +507,308 @@ impl LoginContext {
     }
 }
 
+/// Fields of the TOTP login form
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LoginTotpFormField {
+    /// The code field
+    Code,
+}
+
+impl FormField for LoginTotpFormField {
+    fn keep(&self) -> bool {
+        match self {
+            Self::Code => false,
+        }
+    }
+}
+
+/// Context used by the `pages/login_totp.html` template
+#[derive(Serialize, Default)]
+pub struct LoginTotpContext {
+    form: FormState<LoginTotpFormField>,
+    next: Option<PostAuthContext>,
+    passkeys: bool,
+    recovery_codes: bool,
+}
+
+impl TemplateContext for LoginTotpContext {
+    fn sample(_now: chrono::DateTime<Utc>, _rng: &mut impl Rng) -> Vec<Self>
+    where
+        Self: Sized,
+    {
+        vec![
+            LoginTotpContext {
+                form: FormState::default(),
+                next: None,
+                passkeys: false,
+                recovery_codes: false,
+            },
+            LoginTotpContext {
+                form: FormState::default()
+                    .with_error_on_field(LoginTotpFormField::Code, FieldError::Invalid),
+                next: None,
+                passkeys: true,
+                recovery_codes: true,
+            },
+        ]
+    }
+}
+
+impl LoginTotpContext {
+    /// Set the form state
+    #[must_use]
+    pub fn with_form_state(self, form: FormState<LoginTotpFormField>) -> Self {
+        Self { form, ..self }
+    }
+
+    /// Set whether the user can use a passkey instead of a code
+    #[must_use]
+    pub fn with_passkeys(self, passkeys: bool) -> Self {
+        Self { passkeys, ..self }
+    }
+
+    /// Set whether the user can use a recovery code instead of a code
+    #[must_use]
+    pub fn with_recovery_codes(self, recovery_codes: bool) -> Self {
+        Self {
+            recovery_codes,
+            ..self
+        }
+    }
+
+    /// Add a post authentication action to the context
+    #[must_use]
+    pub fn with_post_action(self, context: PostAuthContext) -> Self {
+        Self {
+            next: Some(context),
+            ..self
+        }
+    }
+}
+
+/// Fields of the recovery code login form
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LoginRecoveryCodeFormField {
+    /// The code field
+    Code,
+}
+
+impl FormField for LoginRecoveryCodeFormField {
+    fn keep(&self) -> bool {
+        match self {
+            Self::Code => false,
+        }
+    }
+}
+
+/// Context used by the `pages/login_recovery_code.html` template
+#[derive(Serialize, Default)]
+pub struct LoginRecoveryCodeContext {
+    form: FormState<LoginRecoveryCodeFormField>,
+    next: Option<PostAuthContext>,
+}
+
+impl TemplateContext for LoginRecoveryCodeContext {
+    fn sample(_now: chrono::DateTime<Utc>, _rng: &mut impl Rng) -> Vec<Self>
+    where
+        Self: Sized,
+    {
+        vec![
+            LoginRecoveryCodeContext::default(),
+            LoginRecoveryCodeContext::default().with_form_state(
+                FormState::default()
+                    .with_error_on_field(LoginRecoveryCodeFormField::Code, FieldError::Invalid),
+            ),
+        ]
+    }
+}
+
+impl LoginRecoveryCodeContext {
+    /// Set the form state
+    #[must_use]
+    pub fn with_form_state(self, form: FormState<LoginRecoveryCodeFormField>) -> Self {
+        Self { form, ..self }
+    }
+
+    /// Add a post authentication action to the context
+    #[must_use]
+    pub fn with_post_action(self, context: PostAuthContext) -> Self {
+        Self {
+            next: Some(context),
+            ..self
+        }
+    }
+}
+
+/// Fields of the email code login form
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LoginEmailCodeFormField {
+    /// The code field
+    Code,
+}
+
+impl FormField for LoginEmailCodeFormField {
+    fn keep(&self) -> bool {
+        match self {
+            Self::Code => false,
+        }
+    }
+}
+
+/// Context used by the `pages/login_email_code.html` template
+#[derive(Serialize)]
+pub struct LoginEmailCodeContext {
+    form: FormState<LoginEmailCodeFormField>,
+    next: Option<PostAuthContext>,
+    authentication: UserEmailAuthentication,
+}
+
+impl TemplateContext for LoginEmailCodeContext {
+    fn sample(now: chrono::DateTime<Utc>, rng: &mut impl Rng) -> Vec<Self>
+    where
+        Self: Sized,
+    {
+        let authentication = UserEmailAuthentication {
+            id: Ulid::from_datetime_with_source(now.into(), rng),
+            user_session_id: None,
+            user_registration_id: None,
+            user_id: Some(Ulid::from_datetime_with_source(now.into(), rng)),
+            email: "foobar@example.com".to_owned(),
+            created_at: now,
+            completed_at: None,
+        };
+
+        vec![
+            Self::new(authentication.clone()),
+            Self::new(authentication).with_form_state(
+                FormState::default()
+                    .with_error_on_field(LoginEmailCodeFormField::Code, FieldError::Invalid),
+            ),
+        ]
+    }
+}
+
+impl LoginEmailCodeContext {
+    /// Constructs a context for the email code login page
+    #[must_use]
+    pub fn new(authentication: UserEmailAuthentication) -> Self {
+        Self {
+            form: FormState::default(),
+            next: None,
+            authentication,
+        }
+    }
+
+    /// Set the form state
+    #[must_use]
+    pub fn with_form_state(self, form: FormState<LoginEmailCodeFormField>) -> Self {
+        Self { form, ..self }
+    }
+
+    /// Add a post authentication action to the context
+    #[must_use]
+    pub fn with_post_action(self, context: PostAuthContext) -> Self {
+        Self {
+            next: Some(context),
+            ..self
+        }
+    }
+}
+
+/// Fields of the passkey login form
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LoginPasskeyFormField {
+    /// The credential returned by the browser
+    Response,
+}
+
+impl FormField for LoginPasskeyFormField {
+    fn keep(&self) -> bool {
+        match self {
+            Self::Response => false,
+        }
+    }
+}
+
+/// Context used by the `pages/login_passkey.html` template
+#[derive(Serialize)]
+pub struct LoginPasskeyContext {
+    form: FormState<LoginPasskeyFormField>,
+    next: Option<PostAuthContext>,
+    challenge_id: Ulid,
+    options: serde_json::Value,
+    recovery_codes: bool,
+}
+
+impl TemplateContext for LoginPasskeyContext {
+    fn sample(now: chrono::DateTime<Utc>, rng: &mut impl Rng) -> Vec<Self>
+    where
+        Self: Sized,
+    {
+        let options = serde_json::json!({
+            "rpId": "example.com",
+            "challenge": "c2FtcGxlLWNoYWxsZW5nZQ",
+            "allowCredentials": [],
+            "userVerification": "required",
+        });
+
+        vec![
+            LoginPasskeyContext::new(
+                Ulid::from_datetime_with_source(now.into(), rng),
+                options.clone(),
+            ),
+            LoginPasskeyContext::new(Ulid::from_datetime_with_source(now.into(), rng), options)
+                .with_form_state(
+                    FormState::default().with_error_on_form(FormError::InvalidCredentials),
+                )
+                .with_recovery_codes(true),
+        ]
+    }
+}
+
+impl LoginPasskeyContext {
+    /// Create a new context for the given challenge, with the options to pass
+    /// to `navigator.credentials.get()`
+    #[must_use]
+    pub fn new(challenge_id: Ulid, options: serde_json::Value) -> Self {
+        Self {
+            form: FormState::default(),
+            next: None,
+            challenge_id,
+            options,
+            recovery_codes: false,
+        }
+    }
+
+    /// Set the form state
+    #[must_use]
+    pub fn with_form_state(self, form: FormState<LoginPasskeyFormField>) -> Self {
+        Self { form, ..self }
+    }
+
+    /// Set whether the user can use a recovery code instead of a passkey
+    #[must_use]
+    pub fn with_recovery_codes(self, recovery_codes: bool) -> Self {
+        Self {
+            recovery_codes,
+            ..self
+        }
+    }
+
+    /// Add a post authentication action to the context
+    #[must_use]
+    pub fn with_post_action(self, context: PostAuthContext) -> Self {
+        Self {
+            next: Some(context),
+            ..self
+        }
+    }
+}
+
 /// Fields of the registration form
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, Hash, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
@@ -875,6 +1177,92 @@ impl TemplateContext for EmailRecoveryContext {
     }
 }
 
+/// Context used by the `emails/session_revoked.{txt,html,subject}` templates
+#[derive(Serialize)]
+pub struct EmailSessionRevokedContext {
+    user: User,
+    client: Client,
+}
+
+impl EmailSessionRevokedContext {
+    /// Constructs a context for the email notifying a user that one of their
+    /// sessions was revoked because its refresh token was reused
+    #[must_use]
+    pub fn new(user: User, client: Client) -> Self {
+        Self { user, client }
+    }
+
+    /// Returns the user associated with the email
+    #[must_use]
+    pub fn user(&self) -> &User {
+        &self.user
+    }
+
+    /// Returns the client of the revoked session
+    #[must_use]
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+}
+
+impl TemplateContext for EmailSessionRevokedContext {
+    fn sample(now: chrono::DateTime<Utc>, rng: &mut impl Rng) -> Vec<Self>
+    where
+        Self: Sized,
+    {
+        let clients = Client::samples(now, rng);
+        User::samples(now, rng)
+            .into_iter()
+            .flat_map(|user| {
+                clients
+                    .iter()
+                    .map(move |client| Self::new(user.clone(), client.clone()))
+            })
+            .collect()
+    }
+}
+
+/// Context used by the `emails/recovery_code_used.{txt,html,subject}`
+/// templates
+#[derive(Serialize)]
+pub struct EmailRecoveryCodeUsedContext {
+    user: User,
+    remaining: usize,
+}
+
+impl EmailRecoveryCodeUsedContext {
+    /// Constructs a context for the email notifying a user that one of their
+    /// recovery codes was used, with the number of codes they have left
+    #[must_use]
+    pub fn new(user: User, remaining: usize) -> Self {
+        Self { user, remaining }
+    }
+
+    /// Returns the user associated with the email
+    #[must_use]
+    pub fn user(&self) -> &User {
+        &self.user
+    }
+
+    /// Returns the number of recovery codes the user has left
+    #[must_use]
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+}
+
+impl TemplateContext for EmailRecoveryCodeUsedContext {
+    fn sample(now: chrono::DateTime<Utc>, rng: &mut impl Rng) -> Vec<Self>
+    where
+        Self: Sized,
+    {
+        User::samples(now, rng)
+            .into_iter()
+            .flat_map(|user| [Self::new(user.clone(), 9), Self::new(user, 0)])
+            .collect()
+    }
+}
+
 /// Context used by the `emails/verification.{txt,html,subject}` templates
 #[derive(Serialize)]
 pub struct EmailVerificationContext {
@@ -882,6 +1270,8 @@ pub struct EmailVerificationContext {
     browser_session: Option<BrowserSession>,
     #[serde(skip_serializing_if = "Option::is_none")]
     user_registration: Option<UserRegistration>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user: Option<User>,
     authentication_code: UserEmailAuthenticationCode,
 }
 
@@ -896,14 +1286,28 @@ impl EmailVerificationContext {
         Self {
             browser_session,
             user_registration,
+            user: None,
             authentication_code,
         }
     }
 
+    /// Set the user logging in with the code, when it is sent to log in
+    /// without a password
+    #[must_use]
+    pub fn with_user(self, user: User) -> Self {
+        Self {
+            user: Some(user),
+            ..self
+        }
+    }
+
     /// Get the user to which this email is being sent
     #[must_use]
     pub fn user(&self) -> Option<&User> {
-        self.browser_session.as_ref().map(|s| &s.user)
+        self.browser_session
+            .as_ref()
+            .map(|s| &s.user)
+            .or(self.user.as_ref())
     }
 
     /// Get the verification code being sent
@@ -920,7 +1324,7 @@ impl TemplateContext for EmailVerificationContext {
     {
         BrowserSession::samples(now, rng)
             .into_iter()
-            .map(|browser_session| {
+            .flat_map(|browser_session| {
                 let authentication_code = UserEmailAuthenticationCode {
                     id: Ulid::from_datetime_with_source(now.into(), rng),
                     user_email_authentication_id: Ulid::from_datetime_with_source(now.into(), rng),
@@ -929,11 +1333,14 @@ impl TemplateContext for EmailVerificationContext {
                     expires_at: now + Duration::try_minutes(25).unwrap(),
                 };
 
-                Self {
-                    browser_session: Some(browser_session),
-                    user_registration: None,
-                    authentication_code,
-                }
+                // One for a logged in user, and one to log in without a password
+                let login = Self::new(authentication_code.clone(), None, None)
+                    .with_user(browser_session.user.clone());
+
+                [
+                    Self::new(authentication_code, Some(browser_session), None),
+                    login,
+                ]
             })
             .collect()
     }
@@ -988,6 +1395,7 @@ impl TemplateContext for RegisterStepsVerifyEmailContext {
             id: Ulid::from_datetime_with_source(now.into(), rng),
             user_session_id: None,
             user_registration_id: None,
+            user_id: None,
             email: "foobar@example.com".to_owned(),
             created_at: now,
             completed_at: None,
@@ -1590,6 +1998,59 @@ impl TemplateContext for DeviceConsentContext {
     }
 }
 
+/// Context used by the `end_session.html` template
+#[derive(Serialize, Debug)]
+pub struct EndSessionContext {
+    client: Option<Client>,
+    post_logout_redirect_uri: Option<Url>,
+    state: Option<String>,
+}
+
+impl EndSessionContext {
+    /// Constructs a new context for a logout request, optionally coming from a
+    /// known client
+    #[must_use]
+    pub fn new(client: Option<Client>) -> Self {
+        Self {
+            client,
+            post_logout_redirect_uri: None,
+            state: None,
+        }
+    }
+
+    /// Set the URI to redirect to after the logout, with the optional `state`
+    /// to pass back to the client
+    #[must_use]
+    pub fn with_post_logout_redirect_uri(
+        self,
+        post_logout_redirect_uri: Url,
+        state: Option<String>,
+    ) -> Self {
+        Self {
+            post_logout_redirect_uri: Some(post_logout_redirect_uri),
+            state,
+            ..self
+        }
+    }
+}
+
+impl TemplateContext for EndSessionContext {
+    fn sample(now: chrono::DateTime<Utc>, rng: &mut impl Rng) -> Vec<Self>
+    where
+        Self: Sized,
+    {
+        let mut samples = vec![Self::new(None)];
+        samples.extend(Client::samples(now, rng).into_iter().map(|client| {
+            let ctx = Self::new(Some(client.clone()));
+            match client.post_logout_redirect_uris.first() {
+                Some(uri) => ctx.with_post_logout_redirect_uri(uri.clone(), Some("abc".to_owned())),
+                None => ctx,
+            }
+        }));
+        samples
+    }
+}
+
 /// Context used by the `account/deactivated.html` and `account/locked.html`
 /// templates
 #[derive(Serialize)]
@@ -48,6 +48,8 @@ impl SiteConfigExt for SiteConfig {
             password_login: self.password_login_enabled,
             account_recovery: self.account_recovery_allowed,
             login_with_email_allowed: self.login_with_email_allowed,
+            passkeys: self.passkeys_enabled,
+            email_code_login: self.email_code_login_enabled,
         }
     }
 }
@@ -26,6 +26,12 @@ pub struct SiteFeatures {
 
     /// Whether users can log in with their email address.
     pub login_with_email_allowed: bool,
+
+    /// Whether users can log in with a passkey.
+    pub passkeys: bool,
+
+    /// Whether users without a password can log in with a code sent by email.
+    pub email_code_login: bool,
 }
 
 impl Object for SiteFeatures {
@@ -35,6 +41,8 @@ impl Object for SiteFeatures {
             "password_login" => Some(Value::from(self.password_login)),
             "account_recovery" => Some(Value::from(self.account_recovery)),
             "login_with_email_allowed" => Some(Value::from(self.login_with_email_allowed)),
+            "passkeys" => Some(Value::from(self.passkeys)),
+            "email_code_login" => Some(Value::from(self.email_code_login)),
             _ => None,
         }
     }
@@ -45,6 +53,8 @@ impl Object for SiteFeatures {
             "password_login",
             "account_recovery",
             "login_with_email_allowed",
+            "passkeys",
+            "email_code_login",
         ])
     }
 }
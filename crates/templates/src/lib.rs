@@ -35,9 +35,12 @@ mod macros;
 pub use self::{
     context::{
         AccountInactiveContext, ApiDocContext, AppContext, CompatSsoContext, ConsentContext,
-        DeviceConsentContext, DeviceLinkContext, DeviceLinkFormField, EmailRecoveryContext,
-        EmailVerificationContext, EmptyContext, ErrorContext, FormPostContext, IndexContext,
-        LoginContext, LoginFormField, NotFoundContext, PasswordRegisterContext,
+        DeviceConsentContext, DeviceLinkContext, DeviceLinkFormField, EmailRecoveryCodeUsedContext,
+        EmailRecoveryContext, EmailSessionRevokedContext, EmailVerificationContext, EmptyContext,
+        EndSessionContext, ErrorContext, FormPostContext, IndexContext, LoginContext,
+        LoginEmailCodeContext, LoginEmailCodeFormField, LoginFormField, LoginPasskeyContext,
+        LoginPasskeyFormField, LoginRecoveryCodeContext, LoginRecoveryCodeFormField,
+        LoginTotpContext, LoginTotpFormField, NotFoundContext, PasswordRegisterContext,
         PolicyViolationContext, PostAuthContext, PostAuthContextInner, ReauthContext,
         ReauthFormField, RecoveryExpiredContext, RecoveryFinishContext, RecoveryFinishFormField,
         RecoveryProgressContext, RecoveryStartContext, RecoveryStartFormField, RegisterContext,
@@ -327,6 +330,18 @@ register_templates! {
     /// Render the login page
     pub fn render_login(WithLanguage<WithCsrf<LoginContext>>) { "pages/login.html" }
 
+    /// Render the TOTP code prompt shown after a password login
+    pub fn render_login_totp(WithLanguage<WithCsrf<LoginTotpContext>>) { "pages/login_totp.html" }
+
+    /// Render the passkey login page
+    pub fn render_login_passkey(WithLanguage<WithCsrf<LoginPasskeyContext>>) { "pages/login_passkey.html" }
+
+    /// Render the recovery code login page, used instead of the second factor
+    pub fn render_login_recovery_code(WithLanguage<WithCsrf<LoginRecoveryCodeContext>>) { "pages/login_recovery_code.html" }
+
+    /// Render the page asking for the code sent by email to log in without a password
+    pub fn render_login_email_code(WithLanguage<WithCsrf<LoginEmailCodeContext>>) { "pages/login_email_code.html" }
+
     /// Render the registration page
     pub fn render_register(WithLanguage<WithCsrf<RegisterContext>>) { "pages/register/index.html" }
 
@@ -390,6 +405,24 @@ register_templates! {
     /// Render the email recovery subject
     pub fn render_email_recovery_subject(WithLanguage<EmailRecoveryContext>) { "emails/recovery.subject" }
 
+    /// Render the recovery code used email (plain text variant)
+    pub fn render_email_recovery_code_used_txt(WithLanguage<EmailRecoveryCodeUsedContext>) { "emails/recovery_code_used.txt" }
+
+    /// Render the recovery code used email (HTML text variant)
+    pub fn render_email_recovery_code_used_html(WithLanguage<EmailRecoveryCodeUsedContext>) { "emails/recovery_code_used.html" }
+
+    /// Render the recovery code used email subject
+    pub fn render_email_recovery_code_used_subject(WithLanguage<EmailRecoveryCodeUsedContext>) { "emails/recovery_code_used.subject" }
+
+    /// Render the session revoked email (plain text variant)
+    pub fn render_email_session_revoked_txt(WithLanguage<EmailSessionRevokedContext>) { "emails/session_revoked.txt" }
+
+    /// Render the session revoked email (HTML text variant)
+    pub fn render_email_session_revoked_html(WithLanguage<EmailSessionRevokedContext>) { "emails/session_revoked.html" }
+
+    /// Render the session revoked email subject
+    pub fn render_email_session_revoked_subject(WithLanguage<EmailSessionRevokedContext>) { "emails/session_revoked.subject" }
+
     /// Render the email verification email (plain text variant)
     pub fn render_email_verification_txt(WithLanguage<EmailVerificationContext>) { "emails/verification.txt" }
 
@@ -414,6 +447,9 @@ register_templates! {
     /// Render the device code consent page
     pub fn render_device_consent(WithLanguage<WithCsrf<WithSession<DeviceConsentContext>>>) { "pages/device_consent.html" }
 
+    /// Render the end session confirmation page
+    pub fn render_end_session(WithLanguage<WithCsrf<WithSession<EndSessionContext>>>) { "pages/end_session.html" }
+
     /// Render the 'account deactivated' page
     pub fn render_account_deactivated(WithLanguage<WithCsrf<AccountInactiveContext>>) { "pages/account/deactivated.html" }
 
@@ -441,6 +477,10 @@ impl Templates {
         check::render_swagger(self, now, rng)?;
         check::render_swagger_callback(self, now, rng)?;
         check::render_login(self, now, rng)?;
+        check::render_login_totp(self, now, rng)?;
+        check::render_login_passkey(self, now, rng)?;
+        check::render_login_recovery_code(self, now, rng)?;
+        check::render_login_email_code(self, now, rng)?;
         check::render_register(self, now, rng)?;
         check::render_password_register(self, now, rng)?;
         check::render_register_steps_verify_email(self, now, rng)?;
@@ -459,12 +499,19 @@ impl Templates {
         check::render_reauth(self, now, rng)?;
         check::render_form_post::<EmptyContext>(self, now, rng)?;
         check::render_error(self, now, rng)?;
+        check::render_email_recovery_code_used_txt(self, now, rng)?;
+        check::render_email_recovery_code_used_html(self, now, rng)?;
+        check::render_email_recovery_code_used_subject(self, now, rng)?;
+        check::render_email_session_revoked_txt(self, now, rng)?;
+        check::render_email_session_revoked_html(self, now, rng)?;
+        check::render_email_session_revoked_subject(self, now, rng)?;
         check::render_email_verification_txt(self, now, rng)?;
         check::render_email_verification_html(self, now, rng)?;
         check::render_email_verification_subject(self, now, rng)?;
         check::render_upstream_oauth2_link_mismatch(self, now, rng)?;
         check::render_upstream_oauth2_suggest_link(self, now, rng)?;
         check::render_upstream_oauth2_do_register(self, now, rng)?;
+        check::render_end_session(self, now, rng)?;
         Ok(())
     }
 }
@@ -488,6 +535,8 @@ mod tests {
             password_registration: true,
             account_recovery: true,
             login_with_email_allowed: true,
+            passkeys: true,
+            email_code_login: true,
         };
         let vite_manifest_path =
             Utf8Path::new(env!("CARGO_MANIFEST_DIR")).join("../../frontend/dist/manifest.json");
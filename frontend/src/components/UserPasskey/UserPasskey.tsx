@@ -0,0 +1,302 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+import { useMutation, useQueryClient } from "@tanstack/react-query";
+import IconDelete from "@vector-im/compound-design-tokens/assets/web/icons/delete";
+import IconEdit from "@vector-im/compound-design-tokens/assets/web/icons/edit";
+import {
+  Button,
+  ErrorMessage,
+  Form,
+  IconButton,
+  Text,
+  Tooltip,
+} from "@vector-im/compound-web";
+import { parseISO } from "date-fns";
+import { useCallback, useState } from "react";
+import { useTranslation } from "react-i18next";
+import { type FragmentType, graphql, useFragment } from "../../gql";
+import { graphqlRequest } from "../../graphql";
+import { formatDate } from "../DateTime";
+import * as Dialog from "../Dialog";
+import LoadingSpinner from "../LoadingSpinner";
+import PasswordConfirmationModal, {
+  usePasswordConfirmation,
+} from "../PasswordConfirmation";
+import styles from "./UserPasskey.module.css";
+
+// This component shows a single passkey, with controls to rename and remove it
+
+export const FRAGMENT = graphql(/* GraphQL */ `
+  fragment UserPasskey_passkey on UserPasskey {
+    id
+    name
+    createdAt
+    lastUsedAt
+  }
+`);
+
+const RENAME_PASSKEY_MUTATION = graphql(/* GraphQL */ `
+  mutation RenamePasskey($id: ID!, $name: String!) {
+    renamePasskey(input: { id: $id, name: $name }) {
+      status
+      passkey {
+        id
+        name
+      }
+    }
+  }
+`);
+
+const REMOVE_PASSKEY_MUTATION = graphql(/* GraphQL */ `
+  mutation RemovePasskey($id: ID!, $password: String) {
+    removePasskey(input: { id: $id, password: $password }) {
+      status
+      user {
+        id
+      }
+    }
+  }
+`);
+
+const RenameButton: React.FC<{ id: string; name: string }> = ({ id, name }) => {
+  const { t } = useTranslation();
+  const [open, setOpen] = useState(false);
+  const queryClient = useQueryClient();
+
+  const renamePasskey = useMutation({
+    mutationFn: (name: string) =>
+      graphqlRequest({
+        query: RENAME_PASSKEY_MUTATION,
+        variables: { id, name },
+      }),
+
+    onSuccess: (data) => {
+      if (data.renamePasskey.status === "INVALID_NAME") {
+        return;
+      }
+
+      queryClient.invalidateQueries({ queryKey: ["userProfile"] });
+      setOpen(false);
+    },
+  });
+
+  const onSubmit = useCallback(
+    (e: React.FormEvent<HTMLFormElement>) => {
+      e.preventDefault();
+      const data = new FormData(e.currentTarget);
+      const name = data.get("name");
+      if (typeof name !== "string") {
+        throw new Error(); // This should never happen
+      }
+      renamePasskey.mutate(name);
+    },
+    [renamePasskey.mutate],
+  );
+
+  const onOpenChange = useCallback(
+    (open: boolean) => {
+      // Don't change the modal state if the mutation is pending
+      if (renamePasskey.isPending) return;
+      renamePasskey.reset();
+      setOpen(open);
+    },
+    [renamePasskey.isPending, renamePasskey.reset],
+  );
+
+  const status = renamePasskey.data?.renamePasskey.status ?? null;
+
+  return (
+    <Dialog.Dialog
+      trigger={
+        <Tooltip label={t("frontend.user_passkey.rename_button_title")}>
+          <IconButton type="button" size="var(--cpd-space-8x)">
+            <IconEdit />
+          </IconButton>
+        </Tooltip>
+      }
+      open={open}
+      onOpenChange={onOpenChange}
+    >
+      <Dialog.Title>
+        {t("frontend.user_passkey.rename_dialog.title")}
+      </Dialog.Title>
+
+      <Form.Root onSubmit={onSubmit}>
+        <Form.Field name="name" serverInvalid={status === "INVALID_NAME"}>
+          <Form.Label>{t("frontend.user_passkey.name_label")}</Form.Label>
+          <Form.TextControl required defaultValue={name} maxLength={64} />
+
+          <Form.ErrorMessage match="valueMissing">
+            {t("frontend.errors.field_required")}
+          </Form.ErrorMessage>
+
+          {status === "INVALID_NAME" && (
+            <Form.ErrorMessage>
+              {t("frontend.user_passkey.invalid_name")}
+            </Form.ErrorMessage>
+          )}
+        </Form.Field>
+
+        <Form.Submit disabled={renamePasskey.isPending}>
+          {renamePasskey.isPending && <LoadingSpinner inline />}
+          {t("action.save")}
+        </Form.Submit>
+      </Form.Root>
+
+      <Dialog.Close asChild>
+        <Button kind="tertiary" disabled={renamePasskey.isPending}>
+          {t("action.cancel")}
+        </Button>
+      </Dialog.Close>
+    </Dialog.Dialog>
+  );
+};
+
+const RemoveButton: React.FC<{
+  id: string;
+  name: string;
+  shouldPromptPassword?: boolean;
+}> = ({ id, name, shouldPromptPassword }) => {
+  const { t } = useTranslation();
+  const [open, setOpen] = useState(false);
+  const queryClient = useQueryClient();
+  const [promptPassword, passwordConfirmationRef] = usePasswordConfirmation();
+
+  const removePasskey = useMutation({
+    mutationFn: (password?: string) =>
+      graphqlRequest({
+        query: REMOVE_PASSKEY_MUTATION,
+        variables: { id, password },
+      }),
+
+    onSuccess: (data) => {
+      // Keep the modal open if the password was wrong
+      if (data.removePasskey.status === "INCORRECT_PASSWORD") {
+        return;
+      }
+
+      queryClient.invalidateQueries({ queryKey: ["userProfile"] });
+      setOpen(false);
+    },
+  });
+
+  const onRemoveClick = useCallback(async (): Promise<void> => {
+    let password = undefined;
+    if (shouldPromptPassword) {
+      password = await promptPassword();
+    }
+    removePasskey.mutate(password);
+  }, [shouldPromptPassword, promptPassword, removePasskey.mutate]);
+
+  const onOpenChange = useCallback(
+    (open: boolean) => {
+      // Don't change the modal state if the mutation is pending
+      if (removePasskey.isPending) return;
+      removePasskey.reset();
+      setOpen(open);
+    },
+    [removePasskey.isPending, removePasskey.reset],
+  );
+
+  const status = removePasskey.data?.removePasskey.status ?? null;
+
+  return (
+    <>
+      <PasswordConfirmationModal
+        title={t("frontend.user_passkey.remove_dialog.password_confirmation")}
+        destructive
+        ref={passwordConfirmationRef}
+      />
+
+      <Dialog.Dialog
+        trigger={
+          <Tooltip label={t("frontend.user_passkey.remove_button_title")}>
+            <IconButton type="button" size="var(--cpd-space-8x)">
+              <IconDelete className={styles.passkeyDeleteIcon} />
+            </IconButton>
+          </Tooltip>
+        }
+        open={open}
+        onOpenChange={onOpenChange}
+      >
+        <Dialog.Title>
+          {t("frontend.user_passkey.remove_dialog.title", { name })}
+        </Dialog.Title>
+
+        <Dialog.Description asChild>
+          <Text size="md" className="text-secondary">
+            {t("frontend.user_passkey.remove_dialog.description")}
+          </Text>
+        </Dialog.Description>
+
+        {status === "INCORRECT_PASSWORD" && (
+          <ErrorMessage>
+            {t("frontend.user_passkey.remove_dialog.incorrect_password")}
+          </ErrorMessage>
+        )}
+
+        <Button
+          kind="primary"
+          type="button"
+          destructive
+          onClick={onRemoveClick}
+          disabled={removePasskey.isPending}
+          Icon={removePasskey.isPending ? undefined : IconDelete}
+        >
+          {removePasskey.isPending && <LoadingSpinner inline />}
+          {t("frontend.user_passkey.remove_dialog.action")}
+        </Button>
+
+        <Dialog.Close asChild>
+          <Button kind="tertiary" disabled={removePasskey.isPending}>
+            {t("action.cancel")}
+          </Button>
+        </Dialog.Close>
+      </Dialog.Dialog>
+    </>
+  );
+};
+
+const UserPasskey: React.FC<{
+  passkey: FragmentType<typeof FRAGMENT>;
+  shouldPromptPassword?: boolean;
+}> = ({ passkey, shouldPromptPassword }) => {
+  const { t } = useTranslation();
+  const data = useFragment(FRAGMENT, passkey);
+
+  const createdAt = formatDate(parseISO(data.createdAt));
+  const lastUsedAt = data.lastUsedAt && formatDate(parseISO(data.lastUsedAt));
+
+  return (
+    <Form.Root>
+      <Form.Field name={`passkey-${data.id}`}>
+        <Form.Label>{t("frontend.user_passkey.name_label")}</Form.Label>
+
+        <div className="flex items-center gap-2">
+          <Form.TextControl
+            readOnly
+            value={data.name}
+            className={styles.passkeyField}
+          />
+          <RenameButton id={data.id} name={data.name} />
+          <RemoveButton
+            id={data.id}
+            name={data.name}
+            shouldPromptPassword={shouldPromptPassword}
+          />
+        </div>
+
+        <Form.HelpMessage>
+          {lastUsedAt
+            ? t("frontend.user_passkey.last_used_at", { createdAt, lastUsedAt })
+            : t("frontend.user_passkey.created_at", { createdAt })}
+        </Form.HelpMessage>
+      </Form.Field>
+    </Form.Root>
+  );
+};
+
+export default UserPasskey;
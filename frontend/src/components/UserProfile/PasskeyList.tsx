@@ -0,0 +1,292 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+import { useMutation, useQueryClient } from "@tanstack/react-query";
+import { Button, Form, Text } from "@vector-im/compound-web";
+import { useCallback, useState } from "react";
+import { useTranslation } from "react-i18next";
+import { type FragmentType, graphql, useFragment } from "../../gql";
+import { graphqlRequest } from "../../graphql";
+import {
+  parseCreationOptions,
+  serializeRegistration,
+} from "../../utils/webauthn";
+import * as Dialog from "../Dialog";
+import LoadingSpinner from "../LoadingSpinner";
+import PasswordConfirmationModal, {
+  usePasswordConfirmation,
+} from "../PasswordConfirmation";
+import UserPasskey from "../UserPasskey";
+
+export const USER_FRAGMENT = graphql(/* GraphQL */ `
+  fragment PasskeyList_user on User {
+    hasPassword
+    passkeys {
+      id
+      ...UserPasskey_passkey
+    }
+  }
+`);
+
+export const CONFIG_FRAGMENT = graphql(/* GraphQL */ `
+  fragment PasskeyList_siteConfig on SiteConfig {
+    passwordLoginEnabled
+  }
+`);
+
+const START_REGISTER_MUTATION = graphql(/* GraphQL */ `
+  mutation StartRegisterPasskey($password: String) {
+    startRegisterPasskey(input: { password: $password }) {
+      status
+      id
+      options
+    }
+  }
+`);
+
+const COMPLETE_REGISTER_MUTATION = graphql(/* GraphQL */ `
+  mutation CompleteRegisterPasskey(
+    $id: ID!
+    $name: String!
+    $response: String!
+  ) {
+    completeRegisterPasskey(
+      input: { id: $id, name: $name, response: $response }
+    ) {
+      status
+      passkey {
+        id
+      }
+    }
+  }
+`);
+
+// The outcome of a registration attempt: either the status returned by one of
+// the two mutations, or CANCELLED if the browser did not create a credential
+type RegisterStatus =
+  | "ADDED"
+  | "CANCELLED"
+  | "DISABLED"
+  | "EXISTS"
+  | "INCORRECT_PASSWORD"
+  | "INVALID_CHALLENGE"
+  | "INVALID_NAME"
+  | "INVALID_RESPONSE";
+
+const AddPasskeyButton: React.FC<{ shouldPromptPassword: boolean }> = ({
+  shouldPromptPassword,
+}) => {
+  const { t } = useTranslation();
+  const [open, setOpen] = useState(false);
+  const queryClient = useQueryClient();
+  const [promptPassword, passwordConfirmationRef] = usePasswordConfirmation();
+
+  const register = useMutation({
+    mutationFn: async ({
+      name,
+      password,
+    }: {
+      name: string;
+      password?: string;
+    }): Promise<RegisterStatus> => {
+      const start = await graphqlRequest({
+        query: START_REGISTER_MUTATION,
+        variables: { password },
+      });
+      const { status, id, options } = start.startRegisterPasskey;
+      if (status !== "STARTED" || !id || !options) {
+        return status === "STARTED" ? "INVALID_CHALLENGE" : status;
+      }
+
+      let credential: Credential | null;
+      try {
+        credential = await navigator.credentials.create({
+          publicKey: parseCreationOptions(options),
+        });
+      } catch (e) {
+        // The user dismissed the browser prompt, or the authenticator refused
+        // to create a credential, e.g. because it is already registered
+        if (e instanceof DOMException) {
+          return "CANCELLED";
+        }
+        throw e;
+      }
+
+      if (!(credential instanceof PublicKeyCredential)) {
+        return "CANCELLED";
+      }
+
+      const complete = await graphqlRequest({
+        query: COMPLETE_REGISTER_MUTATION,
+        variables: { id, name, response: serializeRegistration(credential) },
+      });
+      return complete.completeRegisterPasskey.status;
+    },
+
+    onSuccess: (status) => {
+      if (status !== "ADDED") {
+        return;
+      }
+
+      queryClient.invalidateQueries({ queryKey: ["userProfile"] });
+      setOpen(false);
+    },
+  });
+
+  const onSubmit = useCallback(
+    async (e: React.FormEvent<HTMLFormElement>): Promise<void> => {
+      e.preventDefault();
+      const data = new FormData(e.currentTarget);
+      const name = data.get("name");
+      if (typeof name !== "string") {
+        throw new Error(); // This should never happen
+      }
+
+      let password = undefined;
+      if (shouldPromptPassword) {
+        password = await promptPassword();
+      }
+      register.mutate({ name, password });
+    },
+    [shouldPromptPassword, promptPassword, register.mutate],
+  );
+
+  const onOpenChange = useCallback(
+    (open: boolean) => {
+      // Don't change the modal state if the mutation is pending
+      if (register.isPending) return;
+      register.reset();
+      setOpen(open);
+    },
+    [register.isPending, register.reset],
+  );
+
+  const status = register.data ?? null;
+
+  return (
+    <>
+      <PasswordConfirmationModal
+        title={t("frontend.passkey_list.password_confirmation")}
+        ref={passwordConfirmationRef}
+      />
+
+      <Dialog.Dialog
+        trigger={
+          <Button kind="secondary">
+            {t("frontend.passkey_list.add_button")}
+          </Button>
+        }
+        open={open}
+        onOpenChange={onOpenChange}
+      >
+        <Dialog.Title>
+          {t("frontend.passkey_list.add_dialog.title")}
+        </Dialog.Title>
+
+        <Dialog.Description asChild>
+          <Text size="md" className="text-secondary">
+            {t("frontend.passkey_list.add_dialog.description")}
+          </Text>
+        </Dialog.Description>
+
+        <Form.Root onSubmit={onSubmit}>
+          <Form.Field
+            name="name"
+            serverInvalid={status !== null && status !== "ADDED"}
+          >
+            <Form.Label>{t("frontend.user_passkey.name_label")}</Form.Label>
+            <Form.TextControl required autoFocus maxLength={64} />
+
+            <Form.ErrorMessage match="valueMissing">
+              {t("frontend.errors.field_required")}
+            </Form.ErrorMessage>
+
+            {status === "INVALID_NAME" && (
+              <Form.ErrorMessage>
+                {t("frontend.user_passkey.invalid_name")}
+              </Form.ErrorMessage>
+            )}
+
+            {status === "INCORRECT_PASSWORD" && (
+              <Form.ErrorMessage>
+                {t("frontend.passkey_list.add_dialog.incorrect_password")}
+              </Form.ErrorMessage>
+            )}
+
+            {status === "CANCELLED" && (
+              <Form.ErrorMessage>
+                {t("frontend.passkey_list.add_dialog.cancelled")}
+              </Form.ErrorMessage>
+            )}
+
+            {status === "EXISTS" && (
+              <Form.ErrorMessage>
+                {t("frontend.passkey_list.add_dialog.exists")}
+              </Form.ErrorMessage>
+            )}
+
+            {(status === "INVALID_CHALLENGE" ||
+              status === "INVALID_RESPONSE" ||
+              status === "DISABLED") && (
+              <Form.ErrorMessage>
+                {t("frontend.passkey_list.add_dialog.failed")}
+              </Form.ErrorMessage>
+            )}
+          </Form.Field>
+
+          <Form.Submit disabled={register.isPending}>
+            {register.isPending && <LoadingSpinner inline />}
+            {t("action.continue")}
+          </Form.Submit>
+        </Form.Root>
+
+        <Dialog.Close asChild>
+          <Button kind="tertiary" disabled={register.isPending}>
+            {t("action.cancel")}
+          </Button>
+        </Dialog.Close>
+      </Dialog.Dialog>
+    </>
+  );
+};
+
+const PasskeyList: React.FC<{
+  user: FragmentType<typeof USER_FRAGMENT>;
+  siteConfig: FragmentType<typeof CONFIG_FRAGMENT>;
+}> = ({ user, siteConfig }) => {
+  const { hasPassword, passkeys } = useFragment(USER_FRAGMENT, user);
+  const { passwordLoginEnabled } = useFragment(CONFIG_FRAGMENT, siteConfig);
+  const shouldPromptPassword = hasPassword && passwordLoginEnabled;
+  const { t } = useTranslation();
+
+  // Older browsers can't create passkeys, but can still manage existing ones
+  const canRegister = typeof window.PublicKeyCredential !== "undefined";
+
+  return (
+    <>
+      <Text className="text-secondary" size="md">
+        {t("frontend.passkey_list.description")}
+      </Text>
+
+      {passkeys.map((passkey) => (
+        <UserPasskey
+          key={passkey.id}
+          passkey={passkey}
+          shouldPromptPassword={shouldPromptPassword}
+        />
+      ))}
+
+      {canRegister ? (
+        <AddPasskeyButton shouldPromptPassword={shouldPromptPassword} />
+      ) : (
+        <Text className="text-secondary" size="sm">
+          {t("frontend.passkey_list.unsupported")}
+        </Text>
+      )}
+    </>
+  );
+};
+
+export default PasskeyList;
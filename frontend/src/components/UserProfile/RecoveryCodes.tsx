@@ -0,0 +1,182 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+import { useMutation, useQueryClient } from "@tanstack/react-query";
+import IconCopy from "@vector-im/compound-design-tokens/assets/web/icons/copy";
+import { Button, ErrorMessage, Text } from "@vector-im/compound-web";
+import { useCallback } from "react";
+import { useTranslation } from "react-i18next";
+import { type FragmentType, graphql, useFragment } from "../../gql";
+import { graphqlRequest } from "../../graphql";
+import * as Dialog from "../Dialog";
+import LoadingSpinner from "../LoadingSpinner";
+import PasswordConfirmationModal, {
+  usePasswordConfirmation,
+} from "../PasswordConfirmation";
+
+export const USER_FRAGMENT = graphql(/* GraphQL */ `
+  fragment RecoveryCodes_user on User {
+    hasPassword
+    recoveryCodesRemaining
+  }
+`);
+
+export const CONFIG_FRAGMENT = graphql(/* GraphQL */ `
+  fragment RecoveryCodes_siteConfig on SiteConfig {
+    passwordLoginEnabled
+  }
+`);
+
+const GENERATE_RECOVERY_CODES_MUTATION = graphql(/* GraphQL */ `
+  mutation GenerateRecoveryCodes($password: String) {
+    generateRecoveryCodes(input: { password: $password }) {
+      status
+      codes
+      user {
+        id
+        recoveryCodesRemaining
+      }
+    }
+  }
+`);
+
+// The server never shows the codes again, so this dialog is the only chance
+// the user has to write them down
+const CodesDialog: React.FC<{ codes: string[]; onClose: () => void }> = ({
+  codes,
+  onClose,
+}) => {
+  const { t } = useTranslation();
+
+  const onCopyClick = useCallback(
+    () => navigator.clipboard.writeText(codes.join("\n")),
+    [codes],
+  );
+
+  const onOpenChange = useCallback(
+    (open: boolean) => {
+      if (!open) {
+        onClose();
+      }
+    },
+    [onClose],
+  );
+
+  return (
+    <Dialog.Dialog open onOpenChange={onOpenChange}>
+      <Dialog.Title>
+        {t("frontend.recovery_codes.codes_dialog.title")}
+      </Dialog.Title>
+
+      <Dialog.Description asChild>
+        <Text size="md" className="text-secondary">
+          {t("frontend.recovery_codes.codes_dialog.description")}
+        </Text>
+      </Dialog.Description>
+
+      <ul className="grid grid-cols-2 gap-2 font-mono">
+        {codes.map((code) => (
+          <li key={code}>{code}</li>
+        ))}
+      </ul>
+
+      <Button kind="secondary" Icon={IconCopy} onClick={onCopyClick}>
+        {t("frontend.recovery_codes.codes_dialog.copy_button")}
+      </Button>
+
+      <Dialog.Close asChild>
+        <Button kind="primary">
+          {t("frontend.recovery_codes.codes_dialog.done_button")}
+        </Button>
+      </Dialog.Close>
+    </Dialog.Dialog>
+  );
+};
+
+const RecoveryCodes: React.FC<{
+  user: FragmentType<typeof USER_FRAGMENT>;
+  siteConfig: FragmentType<typeof CONFIG_FRAGMENT>;
+}> = ({ user, siteConfig }) => {
+  const { hasPassword, recoveryCodesRemaining } = useFragment(
+    USER_FRAGMENT,
+    user,
+  );
+  const { passwordLoginEnabled } = useFragment(CONFIG_FRAGMENT, siteConfig);
+  const shouldPromptPassword = hasPassword && passwordLoginEnabled;
+
+  const { t } = useTranslation();
+  const queryClient = useQueryClient();
+  const [promptPassword, passwordConfirmationRef] = usePasswordConfirmation();
+
+  const generateCodes = useMutation({
+    mutationFn: (password?: string) =>
+      graphqlRequest({
+        query: GENERATE_RECOVERY_CODES_MUTATION,
+        variables: { password },
+      }),
+
+    onSuccess: (data) => {
+      if (data.generateRecoveryCodes.status !== "INCORRECT_PASSWORD") {
+        queryClient.invalidateQueries({ queryKey: ["userProfile"] });
+      }
+    },
+  });
+
+  const onGenerateClick = useCallback(async (): Promise<void> => {
+    let password = undefined;
+    if (shouldPromptPassword) {
+      password = await promptPassword();
+    }
+    generateCodes.mutate(password);
+  }, [shouldPromptPassword, promptPassword, generateCodes.mutate]);
+
+  const result = generateCodes.data?.generateRecoveryCodes;
+
+  return (
+    <>
+      <PasswordConfirmationModal
+        title={t("frontend.recovery_codes.password_confirmation")}
+        ref={passwordConfirmationRef}
+      />
+
+      <Text className="text-secondary" size="md">
+        {recoveryCodesRemaining > 0
+          ? t("frontend.recovery_codes.remaining", {
+              count: recoveryCodesRemaining,
+            })
+          : t("frontend.recovery_codes.description")}
+      </Text>
+
+      {result?.status === "INCORRECT_PASSWORD" && (
+        <ErrorMessage>
+          {t("frontend.recovery_codes.incorrect_password")}
+        </ErrorMessage>
+      )}
+
+      {result?.status === "NO_SECOND_FACTOR" && (
+        <ErrorMessage>
+          {t("frontend.recovery_codes.no_second_factor")}
+        </ErrorMessage>
+      )}
+
+      <Button
+        kind="secondary"
+        onClick={onGenerateClick}
+        disabled={generateCodes.isPending}
+      >
+        {generateCodes.isPending && <LoadingSpinner inline />}
+        {recoveryCodesRemaining > 0
+          ? t("frontend.recovery_codes.regenerate_button")
+          : t("frontend.recovery_codes.generate_button")}
+      </Button>
+
+      {result?.codes && (
+        <CodesDialog codes={result.codes} onClose={generateCodes.reset} />
+      )}
+    </>
+  );
+};
+
+export default RecoveryCodes;
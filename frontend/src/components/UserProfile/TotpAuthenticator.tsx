@@ -0,0 +1,395 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+import { useMutation, useQueryClient } from "@tanstack/react-query";
+import IconDelete from "@vector-im/compound-design-tokens/assets/web/icons/delete";
+import {
+  Button,
+  ErrorMessage,
+  Form,
+  Link,
+  Text,
+} from "@vector-im/compound-web";
+import { useCallback, useState } from "react";
+import { useTranslation } from "react-i18next";
+import { type FragmentType, graphql, useFragment } from "../../gql";
+import { graphqlRequest } from "../../graphql";
+import * as Dialog from "../Dialog";
+import LoadingSpinner from "../LoadingSpinner";
+import PasswordConfirmationModal, {
+  usePasswordConfirmation,
+} from "../PasswordConfirmation";
+
+export const USER_FRAGMENT = graphql(/* GraphQL */ `
+  fragment TotpAuthenticator_user on User {
+    hasPassword
+    hasTotpAuthenticator
+  }
+`);
+
+export const CONFIG_FRAGMENT = graphql(/* GraphQL */ `
+  fragment TotpAuthenticator_siteConfig on SiteConfig {
+    passwordLoginEnabled
+  }
+`);
+
+const START_ENROLLMENT_MUTATION = graphql(/* GraphQL */ `
+  mutation StartTotpEnrollment($password: String) {
+    startTotpEnrollment(input: { password: $password }) {
+      status
+      secret
+      uri
+    }
+  }
+`);
+
+const VERIFY_ENROLLMENT_MUTATION = graphql(/* GraphQL */ `
+  mutation VerifyTotpEnrollment($code: String!) {
+    verifyTotpEnrollment(input: { code: $code }) {
+      status
+      user {
+        id
+        hasTotpAuthenticator
+      }
+    }
+  }
+`);
+
+const REMOVE_AUTHENTICATOR_MUTATION = graphql(/* GraphQL */ `
+  mutation RemoveTotpAuthenticator($password: String) {
+    removeTotpAuthenticator(input: { password: $password }) {
+      status
+      user {
+        id
+        hasTotpAuthenticator
+      }
+    }
+  }
+`);
+
+// This dialog is shown once an enrollment started: the user adds the secret to
+// their authenticator app, and enters the first code it generates
+const EnrollDialog: React.FC<{
+  secret: string;
+  uri: string;
+  onClose: () => void;
+}> = ({ secret, uri, onClose }) => {
+  const { t } = useTranslation();
+  const queryClient = useQueryClient();
+
+  const verifyEnrollment = useMutation({
+    mutationFn: (code: string) =>
+      graphqlRequest({
+        query: VERIFY_ENROLLMENT_MUTATION,
+        variables: { code },
+      }),
+
+    onSuccess: (data) => {
+      if (data.verifyTotpEnrollment.status !== "VERIFIED") {
+        return;
+      }
+
+      queryClient.invalidateQueries({ queryKey: ["userProfile"] });
+      onClose();
+    },
+  });
+
+  const onSubmit = useCallback(
+    (e: React.FormEvent<HTMLFormElement>) => {
+      e.preventDefault();
+      const data = new FormData(e.currentTarget);
+      const code = data.get("code");
+      if (typeof code !== "string") {
+        throw new Error(); // This should never happen
+      }
+      verifyEnrollment.mutate(code);
+    },
+    [verifyEnrollment.mutate],
+  );
+
+  const onOpenChange = useCallback(
+    (open: boolean) => {
+      // Don't close the modal if the mutation is pending
+      if (!open && !verifyEnrollment.isPending) {
+        onClose();
+      }
+    },
+    [verifyEnrollment.isPending, onClose],
+  );
+
+  const status = verifyEnrollment.data?.verifyTotpEnrollment.status ?? null;
+
+  return (
+    <Dialog.Dialog open onOpenChange={onOpenChange}>
+      <Dialog.Title>
+        {t("frontend.totp_authenticator.enroll_dialog.title")}
+      </Dialog.Title>
+
+      <Dialog.Description asChild>
+        <Text size="md" className="text-secondary">
+          {t("frontend.totp_authenticator.enroll_dialog.description")}
+        </Text>
+      </Dialog.Description>
+
+      <Form.Root onSubmit={onSubmit}>
+        <Form.Field name="secret">
+          <Form.Label>
+            {t("frontend.totp_authenticator.enroll_dialog.secret_label")}
+          </Form.Label>
+          <Form.TextControl readOnly value={secret} />
+          <Form.HelpMessage>
+            <Link href={uri}>
+              {t("frontend.totp_authenticator.enroll_dialog.open_app")}
+            </Link>
+          </Form.HelpMessage>
+        </Form.Field>
+
+        <Form.Field
+          name="code"
+          serverInvalid={status !== null && status !== "VERIFIED"}
+        >
+          <Form.Label>
+            {t("frontend.totp_authenticator.enroll_dialog.code_label")}
+          </Form.Label>
+          <Form.TextControl
+            required
+            autoFocus
+            inputMode="numeric"
+            autoComplete="one-time-code"
+            pattern="[0-9]{6}"
+          />
+
+          <Form.ErrorMessage match="valueMissing">
+            {t("frontend.errors.field_required")}
+          </Form.ErrorMessage>
+
+          <Form.ErrorMessage
+            match="patternMismatch"
+            forceMatch={status === "INVALID_CODE"}
+          >
+            {t("frontend.totp_authenticator.enroll_dialog.invalid_code")}
+          </Form.ErrorMessage>
+
+          {status === "NOT_STARTED" && (
+            <Form.ErrorMessage>
+              {t("frontend.totp_authenticator.enroll_dialog.not_started")}
+            </Form.ErrorMessage>
+          )}
+
+          {status === "RATE_LIMITED" && (
+            <Form.ErrorMessage>
+              {t("frontend.errors.rate_limit_exceeded")}
+            </Form.ErrorMessage>
+          )}
+        </Form.Field>
+
+        <Form.Submit disabled={verifyEnrollment.isPending}>
+          {verifyEnrollment.isPending && <LoadingSpinner inline />}
+          {t("frontend.totp_authenticator.enroll_dialog.verify_button")}
+        </Form.Submit>
+      </Form.Root>
+
+      <Dialog.Close asChild>
+        <Button kind="tertiary" disabled={verifyEnrollment.isPending}>
+          {t("action.cancel")}
+        </Button>
+      </Dialog.Close>
+    </Dialog.Dialog>
+  );
+};
+
+const RemoveButton: React.FC<{ shouldPromptPassword: boolean }> = ({
+  shouldPromptPassword,
+}) => {
+  const { t } = useTranslation();
+  const [open, setOpen] = useState(false);
+  const queryClient = useQueryClient();
+  const [promptPassword, passwordConfirmationRef] = usePasswordConfirmation();
+
+  const removeAuthenticator = useMutation({
+    mutationFn: (password?: string) =>
+      graphqlRequest({
+        query: REMOVE_AUTHENTICATOR_MUTATION,
+        variables: { password },
+      }),
+
+    onSuccess: (data) => {
+      // Keep the modal open if the password was wrong
+      if (data.removeTotpAuthenticator.status === "INCORRECT_PASSWORD") {
+        return;
+      }
+
+      queryClient.invalidateQueries({ queryKey: ["userProfile"] });
+      setOpen(false);
+    },
+  });
+
+  const onRemoveClick = useCallback(async (): Promise<void> => {
+    let password = undefined;
+    if (shouldPromptPassword) {
+      password = await promptPassword();
+    }
+    removeAuthenticator.mutate(password);
+  }, [shouldPromptPassword, promptPassword, removeAuthenticator.mutate]);
+
+  const onOpenChange = useCallback(
+    (open: boolean) => {
+      // Don't change the modal state if the mutation is pending
+      if (removeAuthenticator.isPending) return;
+      removeAuthenticator.reset();
+      setOpen(open);
+    },
+    [removeAuthenticator.isPending, removeAuthenticator.reset],
+  );
+
+  const status =
+    removeAuthenticator.data?.removeTotpAuthenticator.status ?? null;
+
+  return (
+    <>
+      <PasswordConfirmationModal
+        title={t(
+          "frontend.totp_authenticator.remove_dialog.password_confirmation",
+        )}
+        destructive
+        ref={passwordConfirmationRef}
+      />
+
+      <Dialog.Dialog
+        trigger={
+          <Button kind="secondary" destructive Icon={IconDelete}>
+            {t("frontend.totp_authenticator.remove_button")}
+          </Button>
+        }
+        open={open}
+        onOpenChange={onOpenChange}
+      >
+        <Dialog.Title>
+          {t("frontend.totp_authenticator.remove_dialog.title")}
+        </Dialog.Title>
+
+        <Dialog.Description asChild>
+          <Text size="md" className="text-secondary">
+            {t("frontend.totp_authenticator.remove_dialog.description")}
+          </Text>
+        </Dialog.Description>
+
+        {status === "INCORRECT_PASSWORD" && (
+          <ErrorMessage>
+            {t("frontend.totp_authenticator.incorrect_password")}
+          </ErrorMessage>
+        )}
+
+        <Button
+          kind="primary"
+          type="button"
+          destructive
+          onClick={onRemoveClick}
+          disabled={removeAuthenticator.isPending}
+          Icon={removeAuthenticator.isPending ? undefined : IconDelete}
+        >
+          {removeAuthenticator.isPending && <LoadingSpinner inline />}
+          {t("frontend.totp_authenticator.remove_dialog.action")}
+        </Button>
+
+        <Dialog.Close asChild>
+          <Button kind="tertiary" disabled={removeAuthenticator.isPending}>
+            {t("action.cancel")}
+          </Button>
+        </Dialog.Close>
+      </Dialog.Dialog>
+    </>
+  );
+};
+
+const TotpAuthenticator: React.FC<{
+  user: FragmentType<typeof USER_FRAGMENT>;
+  siteConfig: FragmentType<typeof CONFIG_FRAGMENT>;
+}> = ({ user, siteConfig }) => {
+  const { hasPassword, hasTotpAuthenticator } = useFragment(
+    USER_FRAGMENT,
+    user,
+  );
+  const { passwordLoginEnabled } = useFragment(CONFIG_FRAGMENT, siteConfig);
+  const shouldPromptPassword = hasPassword && passwordLoginEnabled;
+
+  const { t } = useTranslation();
+  const queryClient = useQueryClient();
+  const [promptPassword, passwordConfirmationRef] = usePasswordConfirmation();
+
+  const startEnrollment = useMutation({
+    mutationFn: (password?: string) =>
+      graphqlRequest({
+        query: START_ENROLLMENT_MUTATION,
+        variables: { password },
+      }),
+
+    onSuccess: (data) => {
+      // Another tab might have finished an enrollment in the meantime
+      if (data.startTotpEnrollment.status === "ALREADY_ENROLLED") {
+        queryClient.invalidateQueries({ queryKey: ["userProfile"] });
+      }
+    },
+  });
+
+  const onSetUpClick = useCallback(async (): Promise<void> => {
+    let password = undefined;
+    if (shouldPromptPassword) {
+      password = await promptPassword();
+    }
+    startEnrollment.mutate(password);
+  }, [shouldPromptPassword, promptPassword, startEnrollment.mutate]);
+
+  if (hasTotpAuthenticator) {
+    return (
+      <>
+        <Text className="text-secondary" size="md">
+          {t("frontend.totp_authenticator.enabled_description")}
+        </Text>
+        <RemoveButton shouldPromptPassword={shouldPromptPassword} />
+      </>
+    );
+  }
+
+  const enrollment = startEnrollment.data?.startTotpEnrollment;
+
+  return (
+    <>
+      <PasswordConfirmationModal
+        title={t("frontend.totp_authenticator.password_confirmation")}
+        ref={passwordConfirmationRef}
+      />
+
+      <Text className="text-secondary" size="md">
+        {t("frontend.totp_authenticator.description")}
+      </Text>
+
+      {enrollment?.status === "INCORRECT_PASSWORD" && (
+        <ErrorMessage>
+          {t("frontend.totp_authenticator.incorrect_password")}
+        </ErrorMessage>
+      )}
+
+      <Button
+        kind="secondary"
+        onClick={onSetUpClick}
+        disabled={startEnrollment.isPending}
+      >
+        {startEnrollment.isPending && <LoadingSpinner inline />}
+        {t("frontend.totp_authenticator.set_up_button")}
+      </Button>
+
+      {enrollment?.secret && enrollment.uri && (
+        <EnrollDialog
+          secret={enrollment.secret}
+          uri={enrollment.uri}
+          onClose={startEnrollment.reset}
+        />
+      )}
+    </>
+  );
+};
+
+export default TotpAuthenticator;
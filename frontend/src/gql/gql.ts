@@ -40,14 +40,29 @@ type Documents = {
     "\n  fragment UserGreeting_user on User {\n    id\n    matrix {\n      mxid\n      displayName\n    }\n  }\n": typeof types.UserGreeting_UserFragmentDoc,
     "\n  fragment UserGreeting_siteConfig on SiteConfig {\n    displayNameChangeAllowed\n  }\n": typeof types.UserGreeting_SiteConfigFragmentDoc,
     "\n  mutation SetDisplayName($userId: ID!, $displayName: String) {\n    setDisplayName(input: { userId: $userId, displayName: $displayName }) {\n      status\n    }\n  }\n": typeof types.SetDisplayNameDocument,
+    "\n  fragment UserPasskey_passkey on UserPasskey {\n    id\n    name\n    createdAt\n    lastUsedAt\n  }\n": typeof types.UserPasskey_PasskeyFragmentDoc,
+    "\n  mutation RenamePasskey($id: ID!, $name: String!) {\n    renamePasskey(input: { id: $id, name: $name }) {\n      status\n      passkey {\n        id\n        name\n      }\n    }\n  }\n": typeof types.RenamePasskeyDocument,
+    "\n  mutation RemovePasskey($id: ID!, $password: String) {\n    removePasskey(input: { id: $id, password: $password }) {\n      status\n      user {\n        id\n      }\n    }\n  }\n": typeof types.RemovePasskeyDocument,
     "\n  fragment AddEmailForm_user on User {\n    hasPassword\n  }\n": typeof types.AddEmailForm_UserFragmentDoc,
     "\n  fragment AddEmailForm_siteConfig on SiteConfig {\n    passwordLoginEnabled\n  }\n": typeof types.AddEmailForm_SiteConfigFragmentDoc,
     "\n  mutation AddEmail($email: String!, $password: String, $language: String!) {\n    startEmailAuthentication(input: {\n      email: $email,\n      password: $password,\n      language: $language\n    }) {\n      status\n      violations\n      authentication {\n        id\n      }\n    }\n  }\n": typeof types.AddEmailDocument,
+    "\n  fragment PasskeyList_user on User {\n    hasPassword\n    passkeys {\n      id\n      ...UserPasskey_passkey\n    }\n  }\n": typeof types.PasskeyList_UserFragmentDoc,
+    "\n  fragment PasskeyList_siteConfig on SiteConfig {\n    passwordLoginEnabled\n  }\n": typeof types.PasskeyList_SiteConfigFragmentDoc,
+    "\n  mutation StartRegisterPasskey($password: String) {\n    startRegisterPasskey(input: { password: $password }) {\n      status\n      id\n      options\n    }\n  }\n": typeof types.StartRegisterPasskeyDocument,
+    "\n  mutation CompleteRegisterPasskey(\n    $id: ID!\n    $name: String!\n    $response: String!\n  ) {\n    completeRegisterPasskey(\n      input: { id: $id, name: $name, response: $response }\n    ) {\n      status\n      passkey {\n        id\n      }\n    }\n  }\n": typeof types.CompleteRegisterPasskeyDocument,
+    "\n  fragment RecoveryCodes_user on User {\n    hasPassword\n    recoveryCodesRemaining\n  }\n": typeof types.RecoveryCodes_UserFragmentDoc,
+    "\n  fragment RecoveryCodes_siteConfig on SiteConfig {\n    passwordLoginEnabled\n  }\n": typeof types.RecoveryCodes_SiteConfigFragmentDoc,
+    "\n  mutation GenerateRecoveryCodes($password: String) {\n    generateRecoveryCodes(input: { password: $password }) {\n      status\n      codes\n      user {\n        id\n        recoveryCodesRemaining\n      }\n    }\n  }\n": typeof types.GenerateRecoveryCodesDocument,
+    "\n  fragment TotpAuthenticator_user on User {\n    hasPassword\n    hasTotpAuthenticator\n  }\n": typeof types.TotpAuthenticator_UserFragmentDoc,
+    "\n  fragment TotpAuthenticator_siteConfig on SiteConfig {\n    passwordLoginEnabled\n  }\n": typeof types.TotpAuthenticator_SiteConfigFragmentDoc,
+    "\n  mutation StartTotpEnrollment($password: String) {\n    startTotpEnrollment(input: { password: $password }) {\n      status\n      secret\n      uri\n    }\n  }\n": typeof types.StartTotpEnrollmentDocument,
+    "\n  mutation VerifyTotpEnrollment($code: String!) {\n    verifyTotpEnrollment(input: { code: $code }) {\n      status\n      user {\n        id\n        hasTotpAuthenticator\n      }\n    }\n  }\n": typeof types.VerifyTotpEnrollmentDocument,
+    "\n  mutation RemoveTotpAuthenticator($password: String) {\n    removeTotpAuthenticator(input: { password: $password }) {\n      status\n      user {\n        id\n        hasTotpAuthenticator\n      }\n    }\n  }\n": typeof types.RemoveTotpAuthenticatorDocument,
     "\n  query UserEmailList(\n    $first: Int\n    $after: String\n    $last: Int\n    $before: String\n  ) {\n    viewer {\n      __typename\n      ... on User {\n        emails(first: $first, after: $after, last: $last, before: $before) {\n          edges {\n            cursor\n            node {\n              ...UserEmail_email\n            }\n          }\n          totalCount\n          pageInfo {\n            hasNextPage\n            hasPreviousPage\n            startCursor\n            endCursor\n          }\n        }\n      }\n    }\n  }\n": typeof types.UserEmailListDocument,
     "\n  fragment UserEmailList_user on User {\n    hasPassword\n  }\n": typeof types.UserEmailList_UserFragmentDoc,
     "\n  fragment UserEmailList_siteConfig on SiteConfig {\n    emailChangeAllowed\n    passwordLoginEnabled\n  }\n": typeof types.UserEmailList_SiteConfigFragmentDoc,
     "\n  fragment BrowserSessionsOverview_user on User {\n    id\n\n    browserSessions(first: 0, state: ACTIVE) {\n      totalCount\n    }\n  }\n": typeof types.BrowserSessionsOverview_UserFragmentDoc,
-    "\n  query UserProfile {\n    viewerSession {\n      __typename\n      ... on BrowserSession {\n        id\n        user {\n          ...AddEmailForm_user\n          ...UserEmailList_user\n          ...AccountDeleteButton_user\n          hasPassword\n          emails(first: 0) {\n            totalCount\n          }\n        }\n      }\n    }\n\n    siteConfig {\n      emailChangeAllowed\n      passwordLoginEnabled\n      accountDeactivationAllowed\n      ...AddEmailForm_siteConfig\n      ...UserEmailList_siteConfig\n      ...PasswordChange_siteConfig\n      ...AccountDeleteButton_siteConfig\n    }\n  }\n": typeof types.UserProfileDocument,
+    "\n  query UserProfile {\n    viewerSession {\n      __typename\n      ... on BrowserSession {\n        id\n        user {\n          ...AddEmailForm_user\n          ...UserEmailList_user\n          ...AccountDeleteButton_user\n          ...TotpAuthenticator_user\n          ...PasskeyList_user\n          ...RecoveryCodes_user\n          hasPassword\n          hasTotpAuthenticator\n          passkeys {\n            id\n          }\n          emails(first: 0) {\n            totalCount\n          }\n        }\n      }\n    }\n\n    siteConfig {\n      emailChangeAllowed\n      passwordLoginEnabled\n      accountDeactivationAllowed\n      passkeysEnabled\n      ...AddEmailForm_siteConfig\n      ...UserEmailList_siteConfig\n      ...PasswordChange_siteConfig\n      ...AccountDeleteButton_siteConfig\n      ...TotpAuthenticator_siteConfig\n      ...PasskeyList_siteConfig\n      ...RecoveryCodes_siteConfig\n    }\n  }\n": typeof types.UserProfileDocument,
     "\n  query BrowserSessionList(\n    $first: Int\n    $after: String\n    $last: Int\n    $before: String\n    $lastActive: DateFilter\n  ) {\n    viewerSession {\n      __typename\n      ... on BrowserSession {\n        id\n\n        user {\n          id\n\n          browserSessions(\n            first: $first\n            after: $after\n            last: $last\n            before: $before\n            lastActive: $lastActive\n            state: ACTIVE\n          ) {\n            totalCount\n\n            edges {\n              cursor\n              node {\n                id\n                ...BrowserSession_session\n              }\n            }\n\n            pageInfo {\n              hasNextPage\n              hasPreviousPage\n              startCursor\n              endCursor\n            }\n          }\n        }\n      }\n    }\n  }\n": typeof types.BrowserSessionListDocument,
     "\n  query SessionsOverview {\n    viewer {\n      __typename\n\n      ... on User {\n        id\n        ...BrowserSessionsOverview_user\n      }\n    }\n  }\n": typeof types.SessionsOverviewDocument,
     "\n  query AppSessionsList(\n    $before: String\n    $after: String\n    $first: Int\n    $last: Int\n    $lastActive: DateFilter\n  ) {\n    viewer {\n      __typename\n\n      ... on User {\n        id\n        appSessions(\n          before: $before\n          after: $after\n          first: $first\n          last: $last\n          lastActive: $lastActive\n          state: ACTIVE\n        ) {\n          edges {\n            cursor\n            node {\n              __typename\n              ...CompatSession_session\n              ...OAuth2Session_session\n            }\n          }\n\n          totalCount\n          pageInfo {\n            startCursor\n            endCursor\n            hasNextPage\n            hasPreviousPage\n          }\n        }\n      }\n    }\n  }\n": typeof types.AppSessionsListDocument,
@@ -94,14 +109,29 @@ const documents: Documents = {
     "\n  fragment UserGreeting_user on User {\n    id\n    matrix {\n      mxid\n      displayName\n    }\n  }\n": types.UserGreeting_UserFragmentDoc,
     "\n  fragment UserGreeting_siteConfig on SiteConfig {\n    displayNameChangeAllowed\n  }\n": types.UserGreeting_SiteConfigFragmentDoc,
     "\n  mutation SetDisplayName($userId: ID!, $displayName: String) {\n    setDisplayName(input: { userId: $userId, displayName: $displayName }) {\n      status\n    }\n  }\n": types.SetDisplayNameDocument,
+    "\n  fragment UserPasskey_passkey on UserPasskey {\n    id\n    name\n    createdAt\n    lastUsedAt\n  }\n": types.UserPasskey_PasskeyFragmentDoc,
+    "\n  mutation RenamePasskey($id: ID!, $name: String!) {\n    renamePasskey(input: { id: $id, name: $name }) {\n      status\n      passkey {\n        id\n        name\n      }\n    }\n  }\n": types.RenamePasskeyDocument,
+    "\n  mutation RemovePasskey($id: ID!, $password: String) {\n    removePasskey(input: { id: $id, password: $password }) {\n      status\n      user {\n        id\n      }\n    }\n  }\n": types.RemovePasskeyDocument,
     "\n  fragment AddEmailForm_user on User {\n    hasPassword\n  }\n": types.AddEmailForm_UserFragmentDoc,
     "\n  fragment AddEmailForm_siteConfig on SiteConfig {\n    passwordLoginEnabled\n  }\n": types.AddEmailForm_SiteConfigFragmentDoc,
     "\n  mutation AddEmail($email: String!, $password: String, $language: String!) {\n    startEmailAuthentication(input: {\n      email: $email,\n      password: $password,\n      language: $language\n    }) {\n      status\n      violations\n      authentication {\n        id\n      }\n    }\n  }\n": types.AddEmailDocument,
+    "\n  fragment PasskeyList_user on User {\n    hasPassword\n    passkeys {\n      id\n      ...UserPasskey_passkey\n    }\n  }\n": types.PasskeyList_UserFragmentDoc,
+    "\n  fragment PasskeyList_siteConfig on SiteConfig {\n    passwordLoginEnabled\n  }\n": types.PasskeyList_SiteConfigFragmentDoc,
+    "\n  mutation StartRegisterPasskey($password: String) {\n    startRegisterPasskey(input: { password: $password }) {\n      status\n      id\n      options\n    }\n  }\n": types.StartRegisterPasskeyDocument,
+    "\n  mutation CompleteRegisterPasskey(\n    $id: ID!\n    $name: String!\n    $response: String!\n  ) {\n    completeRegisterPasskey(\n      input: { id: $id, name: $name, response: $response }\n    ) {\n      status\n      passkey {\n        id\n      }\n    }\n  }\n": types.CompleteRegisterPasskeyDocument,
+    "\n  fragment RecoveryCodes_user on User {\n    hasPassword\n    recoveryCodesRemaining\n  }\n": types.RecoveryCodes_UserFragmentDoc,
+    "\n  fragment RecoveryCodes_siteConfig on SiteConfig {\n    passwordLoginEnabled\n  }\n": types.RecoveryCodes_SiteConfigFragmentDoc,
+    "\n  mutation GenerateRecoveryCodes($password: String) {\n    generateRecoveryCodes(input: { password: $password }) {\n      status\n      codes\n      user {\n        id\n        recoveryCodesRemaining\n      }\n    }\n  }\n": types.GenerateRecoveryCodesDocument,
+    "\n  fragment TotpAuthenticator_user on User {\n    hasPassword\n    hasTotpAuthenticator\n  }\n": types.TotpAuthenticator_UserFragmentDoc,
+    "\n  fragment TotpAuthenticator_siteConfig on SiteConfig {\n    passwordLoginEnabled\n  }\n": types.TotpAuthenticator_SiteConfigFragmentDoc,
+    "\n  mutation StartTotpEnrollment($password: String) {\n    startTotpEnrollment(input: { password: $password }) {\n      status\n      secret\n      uri\n    }\n  }\n": types.StartTotpEnrollmentDocument,
+    "\n  mutation VerifyTotpEnrollment($code: String!) {\n    verifyTotpEnrollment(input: { code: $code }) {\n      status\n      user {\n        id\n        hasTotpAuthenticator\n      }\n    }\n  }\n": types.VerifyTotpEnrollmentDocument,
+    "\n  mutation RemoveTotpAuthenticator($password: String) {\n    removeTotpAuthenticator(input: { password: $password }) {\n      status\n      user {\n        id\n        hasTotpAuthenticator\n      }\n    }\n  }\n": types.RemoveTotpAuthenticatorDocument,
     "\n  query UserEmailList(\n    $first: Int\n    $after: String\n    $last: Int\n    $before: String\n  ) {\n    viewer {\n      __typename\n      ... on User {\n        emails(first: $first, after: $after, last: $last, before: $before) {\n          edges {\n            cursor\n            node {\n              ...UserEmail_email\n            }\n          }\n          totalCount\n          pageInfo {\n            hasNextPage\n            hasPreviousPage\n            startCursor\n            endCursor\n          }\n        }\n      }\n    }\n  }\n": types.UserEmailListDocument,
     "\n  fragment UserEmailList_user on User {\n    hasPassword\n  }\n": types.UserEmailList_UserFragmentDoc,
     "\n  fragment UserEmailList_siteConfig on SiteConfig {\n    emailChangeAllowed\n    passwordLoginEnabled\n  }\n": types.UserEmailList_SiteConfigFragmentDoc,
     "\n  fragment BrowserSessionsOverview_user on User {\n    id\n\n    browserSessions(first: 0, state: ACTIVE) {\n      totalCount\n    }\n  }\n": types.BrowserSessionsOverview_UserFragmentDoc,
-    "\n  query UserProfile {\n    viewerSession {\n      __typename\n      ... on BrowserSession {\n        id\n        user {\n          ...AddEmailForm_user\n          ...UserEmailList_user\n          ...AccountDeleteButton_user\n          hasPassword\n          emails(first: 0) {\n            totalCount\n          }\n        }\n      }\n    }\n\n    siteConfig {\n      emailChangeAllowed\n      passwordLoginEnabled\n      accountDeactivationAllowed\n      ...AddEmailForm_siteConfig\n      ...UserEmailList_siteConfig\n      ...PasswordChange_siteConfig\n      ...AccountDeleteButton_siteConfig\n    }\n  }\n": types.UserProfileDocument,
+    "\n  query UserProfile {\n    viewerSession {\n      __typename\n      ... on BrowserSession {\n        id\n        user {\n          ...AddEmailForm_user\n          ...UserEmailList_user\n          ...AccountDeleteButton_user\n          ...TotpAuthenticator_user\n          ...PasskeyList_user\n          ...RecoveryCodes_user\n          hasPassword\n          hasTotpAuthenticator\n          passkeys {\n            id\n          }\n          emails(first: 0) {\n            totalCount\n          }\n        }\n      }\n    }\n\n    siteConfig {\n      emailChangeAllowed\n      passwordLoginEnabled\n      accountDeactivationAllowed\n      passkeysEnabled\n      ...AddEmailForm_siteConfig\n      ...UserEmailList_siteConfig\n      ...PasswordChange_siteConfig\n      ...AccountDeleteButton_siteConfig\n      ...TotpAuthenticator_siteConfig\n      ...PasskeyList_siteConfig\n      ...RecoveryCodes_siteConfig\n    }\n  }\n": types.UserProfileDocument,
     "\n  query BrowserSessionList(\n    $first: Int\n    $after: String\n    $last: Int\n    $before: String\n    $lastActive: DateFilter\n  ) {\n    viewerSession {\n      __typename\n      ... on BrowserSession {\n        id\n\n        user {\n          id\n\n          browserSessions(\n            first: $first\n            after: $after\n            last: $last\n            before: $before\n            lastActive: $lastActive\n            state: ACTIVE\n          ) {\n            totalCount\n\n            edges {\n              cursor\n              node {\n                id\n                ...BrowserSession_session\n              }\n            }\n\n            pageInfo {\n              hasNextPage\n              hasPreviousPage\n              startCursor\n              endCursor\n            }\n          }\n        }\n      }\n    }\n  }\n": types.BrowserSessionListDocument,
     "\n  query SessionsOverview {\n    viewer {\n      __typename\n\n      ... on User {\n        id\n        ...BrowserSessionsOverview_user\n      }\n    }\n  }\n": types.SessionsOverviewDocument,
     "\n  query AppSessionsList(\n    $before: String\n    $after: String\n    $first: Int\n    $last: Int\n    $lastActive: DateFilter\n  ) {\n    viewer {\n      __typename\n\n      ... on User {\n        id\n        appSessions(\n          before: $before\n          after: $after\n          first: $first\n          last: $last\n          lastActive: $lastActive\n          state: ACTIVE\n        ) {\n          edges {\n            cursor\n            node {\n              __typename\n              ...CompatSession_session\n              ...OAuth2Session_session\n            }\n          }\n\n          totalCount\n          pageInfo {\n            startCursor\n            endCursor\n            hasNextPage\n            hasPreviousPage\n          }\n        }\n      }\n    }\n  }\n": types.AppSessionsListDocument,
@@ -223,6 +253,18 @@ export function graphql(source: "\n  fragment UserGreeting_siteConfig on SiteCon
  * The graphql function is used to parse GraphQL queries into a document that can be used by GraphQL clients.
  */
 export function graphql(source: "\n  mutation SetDisplayName($userId: ID!, $displayName: String) {\n    setDisplayName(input: { userId: $userId, displayName: $displayName }) {\n      status\n    }\n  }\n"): typeof import('./graphql').SetDisplayNameDocument;
+/**
+ * The graphql function is used to parse GraphQL queries into a document that can be used by GraphQL clients.
+ */
+export function graphql(source: "\n  fragment UserPasskey_passkey on UserPasskey {\n    id\n    name\n    createdAt\n    lastUsedAt\n  }\n"): typeof import('./graphql').UserPasskey_PasskeyFragmentDoc;
+/**
+ * The graphql function is used to parse GraphQL queries into a document that can be used by GraphQL clients.
+ */
+export function graphql(source: "\n  mutation RenamePasskey($id: ID!, $name: String!) {\n    renamePasskey(input: { id: $id, name: $name }) {\n      status\n      passkey {\n        id\n        name\n      }\n    }\n  }\n"): typeof import('./graphql').RenamePasskeyDocument;
+/**
+ * The graphql function is used to parse GraphQL queries into a document that can be used by GraphQL clients.
+ */
+export function graphql(source: "\n  mutation RemovePasskey($id: ID!, $password: String) {\n    removePasskey(input: { id: $id, password: $password }) {\n      status\n      user {\n        id\n      }\n    }\n  }\n"): typeof import('./graphql').RemovePasskeyDocument;
 /**
  * The graphql function is used to parse GraphQL queries into a document that can be used by GraphQL clients.
  */
@@ -235,6 +277,54 @@ export function graphql(source: "\n  fragment AddEmailForm_siteConfig on SiteCon
  * The graphql function is used to parse GraphQL queries into a document that can be used by GraphQL clients.
  */
 export function graphql(source: "\n  mutation AddEmail($email: String!, $password: String, $language: String!) {\n    startEmailAuthentication(input: {\n      email: $email,\n      password: $password,\n      language: $language\n    }) {\n      status\n      violations\n      authentication {\n        id\n      }\n    }\n  }\n"): typeof import('./graphql').AddEmailDocument;
+/**
+ * The graphql function is used to parse GraphQL queries into a document that can be used by GraphQL clients.
+ */
+export function graphql(source: "\n  fragment PasskeyList_user on User {\n    hasPassword\n    passkeys {\n      id\n      ...UserPasskey_passkey\n    }\n  }\n"): typeof import('./graphql').PasskeyList_UserFragmentDoc;
+/**
+ * The graphql function is used to parse GraphQL queries into a document that can be used by GraphQL clients.
+ */
+export function graphql(source: "\n  fragment PasskeyList_siteConfig on SiteConfig {\n    passwordLoginEnabled\n  }\n"): typeof import('./graphql').PasskeyList_SiteConfigFragmentDoc;
+/**
+ * The graphql function is used to parse GraphQL queries into a document that can be used by GraphQL clients.
+ */
+export function graphql(source: "\n  mutation StartRegisterPasskey($password: String) {\n    startRegisterPasskey(input: { password: $password }) {\n      status\n      id\n      options\n    }\n  }\n"): typeof import('./graphql').StartRegisterPasskeyDocument;
+/**
+ * The graphql function is used to parse GraphQL queries into a document that can be used by GraphQL clients.
+ */
+export function graphql(source: "\n  mutation CompleteRegisterPasskey(\n    $id: ID!\n    $name: String!\n    $response: String!\n  ) {\n    completeRegisterPasskey(\n      input: { id: $id, name: $name, response: $response }\n    ) {\n      status\n      passkey {\n        id\n      }\n    }\n  }\n"): typeof import('./graphql').CompleteRegisterPasskeyDocument;
+/**
+ * The graphql function is used to parse GraphQL queries into a document that can be used by GraphQL clients.
+ */
+export function graphql(source: "\n  fragment RecoveryCodes_user on User {\n    hasPassword\n    recoveryCodesRemaining\n  }\n"): typeof import('./graphql').RecoveryCodes_UserFragmentDoc;
+/**
+ * The graphql function is used to parse GraphQL queries into a document that can be used by GraphQL clients.
+ */
+export function graphql(source: "\n  fragment RecoveryCodes_siteConfig on SiteConfig {\n    passwordLoginEnabled\n  }\n"): typeof import('./graphql').RecoveryCodes_SiteConfigFragmentDoc;
+/**
+ * The graphql function is used to parse GraphQL queries into a document that can be used by GraphQL clients.
+ */
+export function graphql(source: "\n  mutation GenerateRecoveryCodes($password: String) {\n    generateRecoveryCodes(input: { password: $password }) {\n      status\n      codes\n      user {\n        id\n        recoveryCodesRemaining\n      }\n    }\n  }\n"): typeof import('./graphql').GenerateRecoveryCodesDocument;
+/**
+ * The graphql function is used to parse GraphQL queries into a document that can be used by GraphQL clients.
+ */
+export function graphql(source: "\n  fragment TotpAuthenticator_user on User {\n    hasPassword\n    hasTotpAuthenticator\n  }\n"): typeof import('./graphql').TotpAuthenticator_UserFragmentDoc;
+/**
+ * The graphql function is used to parse GraphQL queries into a document that can be used by GraphQL clients.
+ */
+export function graphql(source: "\n  fragment TotpAuthenticator_siteConfig on SiteConfig {\n    passwordLoginEnabled\n  }\n"): typeof import('./graphql').TotpAuthenticator_SiteConfigFragmentDoc;
+/**
+ * The graphql function is used to parse GraphQL queries into a document that can be used by GraphQL clients.
+ */
+export function graphql(source: "\n  mutation StartTotpEnrollment($password: String) {\n    startTotpEnrollment(input: { password: $password }) {\n      status\n      secret\n      uri\n    }\n  }\n"): typeof import('./graphql').StartTotpEnrollmentDocument;
+/**
+ * The graphql function is used to parse GraphQL queries into a document that can be used by GraphQL clients.
+ */
+export function graphql(source: "\n  mutation VerifyTotpEnrollment($code: String!) {\n    verifyTotpEnrollment(input: { code: $code }) {\n      status\n      user {\n        id\n        hasTotpAuthenticator\n      }\n    }\n  }\n"): typeof import('./graphql').VerifyTotpEnrollmentDocument;
+/**
+ * The graphql function is used to parse GraphQL queries into a document that can be used by GraphQL clients.
+ */
+export function graphql(source: "\n  mutation RemoveTotpAuthenticator($password: String) {\n    removeTotpAuthenticator(input: { password: $password }) {\n      status\n      user {\n        id\n        hasTotpAuthenticator\n      }\n    }\n  }\n"): typeof import('./graphql').RemoveTotpAuthenticatorDocument;
 /**
  * The graphql function is used to parse GraphQL queries into a document that can be used by GraphQL clients.
  */
@@ -254,7 +344,7 @@ export function graphql(source: "\n  fragment BrowserSessionsOverview_user on Us
 /**
  * The graphql function is used to parse GraphQL queries into a document that can be used by GraphQL clients.
  */
-export function graphql(source: "\n  query UserProfile {\n    viewerSession {\n      __typename\n      ... on BrowserSession {\n        id\n        user {\n          ...AddEmailForm_user\n          ...UserEmailList_user\n          ...AccountDeleteButton_user\n          hasPassword\n          emails(first: 0) {\n            totalCount\n          }\n        }\n      }\n    }\n\n    siteConfig {\n      emailChangeAllowed\n      passwordLoginEnabled\n      accountDeactivationAllowed\n      ...AddEmailForm_siteConfig\n      ...UserEmailList_siteConfig\n      ...PasswordChange_siteConfig\n      ...AccountDeleteButton_siteConfig\n    }\n  }\n"): typeof import('./graphql').UserProfileDocument;
+export function graphql(source: "\n  query UserProfile {\n    viewerSession {\n      __typename\n      ... on BrowserSession {\n        id\n        user {\n          ...AddEmailForm_user\n          ...UserEmailList_user\n          ...AccountDeleteButton_user\n          ...TotpAuthenticator_user\n          ...PasskeyList_user\n          ...RecoveryCodes_user\n          hasPassword\n          hasTotpAuthenticator\n          passkeys {\n            id\n          }\n          emails(first: 0) {\n            totalCount\n          }\n        }\n      }\n    }\n\n    siteConfig {\n      emailChangeAllowed\n      passwordLoginEnabled\n      accountDeactivationAllowed\n      passkeysEnabled\n      ...AddEmailForm_siteConfig\n      ...UserEmailList_siteConfig\n      ...PasswordChange_siteConfig\n      ...AccountDeleteButton_siteConfig\n      ...TotpAuthenticator_siteConfig\n      ...PasskeyList_siteConfig\n      ...RecoveryCodes_siteConfig\n    }\n  }\n"): typeof import('./graphql').UserProfileDocument;
 /**
  * The graphql function is used to parse GraphQL queries into a document that can be used by GraphQL clients.
  */
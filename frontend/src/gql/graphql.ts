@@ -354,6 +354,43 @@ export type CompleteEmailAuthenticationStatus =
   /** Too many attempts to complete an email authentication */
   | 'RATE_LIMITED';
 
+/** The input for the `completeRegisterPasskey` mutation */
+export type CompleteRegisterPasskeyInput = {
+  /** The ID of the registration, as returned by `startRegisterPasskey` */
+  id: Scalars['ID']['input'];
+  /** The name to give to the passkey */
+  name: Scalars['String']['input'];
+  /**
+   * The credential returned by `navigator.credentials.create()`, serialized
+   * as JSON with `PublicKeyCredential.toJSON()`
+   */
+  response: Scalars['String']['input'];
+};
+
+/** The payload of the `completeRegisterPasskey` mutation */
+export type CompleteRegisterPasskeyPayload = {
+  __typename?: 'CompleteRegisterPasskeyPayload';
+  /** The passkey which was added */
+  passkey?: Maybe<UserPasskey>;
+  /** Status of the operation */
+  status: CompleteRegisterPasskeyStatus;
+};
+
+/** The status of the `completeRegisterPasskey` mutation */
+export type CompleteRegisterPasskeyStatus =
+  /** The passkey was added */
+  | 'ADDED'
+  /** Passkeys are disabled on this server */
+  | 'DISABLED'
+  /** The passkey is already registered */
+  | 'EXISTS'
+  /** The registration is unknown, expired or was already completed */
+  | 'INVALID_CHALLENGE'
+  /** The name is empty or too long */
+  | 'INVALID_NAME'
+  /** The credential is invalid */
+  | 'INVALID_RESPONSE';
+
 /** The input of the `createOauth2Session` mutation. */
 export type CreateOAuth2SessionInput = {
   /** Whether the session should issue a never-expiring access token */
@@ -495,6 +532,38 @@ export type EndOAuth2SessionStatus =
   /** The session was not found. */
   | 'NOT_FOUND';
 
+/** The input for the `generateRecoveryCodes` mutation */
+export type GenerateRecoveryCodesInput = {
+  /**
+   * The user's current password. This is required if the user has a
+   * password on its account.
+   */
+  password?: InputMaybe<Scalars['String']['input']>;
+};
+
+/** The payload of the `generateRecoveryCodes` mutation */
+export type GenerateRecoveryCodesPayload = {
+  __typename?: 'GenerateRecoveryCodesPayload';
+  /**
+   * The new recovery codes. They are only shown once, and each of them can
+   * be used once.
+   */
+  codes?: Maybe<Array<Scalars['String']['output']>>;
+  /** Status of the operation */
+  status: GenerateRecoveryCodesStatus;
+  /** The user who generated the codes */
+  user?: Maybe<User>;
+};
+
+/** The status of the `generateRecoveryCodes` mutation */
+export type GenerateRecoveryCodesStatus =
+  /** New recovery codes were generated, replacing any previous ones */
+  | 'GENERATED'
+  /** The password provided is incorrect */
+  | 'INCORRECT_PASSWORD'
+  /** The user has no second factor, so recovery codes would be useless */
+  | 'NO_SECOND_FACTOR';
+
 /** The input for the `lockUser` mutation. */
 export type LockUserInput = {
   /** Permanently lock the user. */
@@ -545,6 +614,11 @@ export type Mutation = {
   allowUserCrossSigningReset: AllowUserCrossSigningResetPayload;
   /** Complete the email authentication flow */
   completeEmailAuthentication: CompleteEmailAuthenticationPayload;
+  /**
+   * Finish registering a passkey, by checking the credential created by
+   * the browser
+   */
+  completeRegisterPasskey: CompleteRegisterPasskeyPayload;
   /**
    * Create a new arbitrary OAuth 2.0 Session.
    *
@@ -561,10 +635,21 @@ export type Mutation = {
   endBrowserSession: EndBrowserSessionPayload;
   endCompatSession: EndCompatSessionPayload;
   endOauth2Session: EndOAuth2SessionPayload;
+  /**
+   * Generate a new set of recovery codes for the current user. Any
+   * previous codes, used or not, stop working.
+   */
+  generateRecoveryCodes: GenerateRecoveryCodesPayload;
   /** Lock a user. This is only available to administrators. */
   lockUser: LockUserPayload;
   /** Remove an email address */
   removeEmail: RemoveEmailPayload;
+  /** Remove a passkey of the current user */
+  removePasskey: RemovePasskeyPayload;
+  /** Remove the TOTP authenticator of the current user */
+  removeTotpAuthenticator: RemoveTotpAuthenticatorPayload;
+  /** Rename a passkey of the current user */
+  renamePasskey: RenamePasskeyPayload;
   /** Resend the email authentication code */
   resendEmailAuthenticationCode: ResendEmailAuthenticationCodePayload;
   /**
@@ -600,8 +685,20 @@ export type Mutation = {
   setPrimaryEmail: SetPrimaryEmailPayload;
   /** Start a new email authentication flow */
   startEmailAuthentication: StartEmailAuthenticationPayload;
+  /** Start registering a new passkey for the current user */
+  startRegisterPasskey: StartRegisterPasskeyPayload;
+  /**
+   * Start enrolling a TOTP authenticator for the current user. Any pending
+   * enrollment is replaced.
+   */
+  startTotpEnrollment: StartTotpEnrollmentPayload;
   /** Unlock a user. This is only available to administrators. */
   unlockUser: UnlockUserPayload;
+  /**
+   * Finish enrolling a TOTP authenticator, by checking a code generated by
+   * the authenticator app
+   */
+  verifyTotpEnrollment: VerifyTotpEnrollmentPayload;
 };
 
 
@@ -629,6 +726,12 @@ export type MutationCompleteEmailAuthenticationArgs = {
 };
 
 
+/** The mutations root of the GraphQL interface. */
+export type MutationCompleteRegisterPasskeyArgs = {
+  input: CompleteRegisterPasskeyInput;
+};
+
+
 /** The mutations root of the GraphQL interface. */
 export type MutationCreateOauth2SessionArgs = {
   input: CreateOAuth2SessionInput;
@@ -659,6 +762,12 @@ export type MutationEndOauth2SessionArgs = {
 };
 
 
+/** The mutations root of the GraphQL interface. */
+export type MutationGenerateRecoveryCodesArgs = {
+  input: GenerateRecoveryCodesInput;
+};
+
+
 /** The mutations root of the GraphQL interface. */
 export type MutationLockUserArgs = {
   input: LockUserInput;
@@ -671,6 +780,24 @@ export type MutationRemoveEmailArgs = {
 };
 
 
+/** The mutations root of the GraphQL interface. */
+export type MutationRemovePasskeyArgs = {
+  input: RemovePasskeyInput;
+};
+
+
+/** The mutations root of the GraphQL interface. */
+export type MutationRemoveTotpAuthenticatorArgs = {
+  input: RemoveTotpAuthenticatorInput;
+};
+
+
+/** The mutations root of the GraphQL interface. */
+export type MutationRenamePasskeyArgs = {
+  input: RenamePasskeyInput;
+};
+
+
 /** The mutations root of the GraphQL interface. */
 export type MutationResendEmailAuthenticationCodeArgs = {
   input: ResendEmailAuthenticationCodeInput;
@@ -719,11 +846,29 @@ export type MutationStartEmailAuthenticationArgs = {
 };
 
 
+/** The mutations root of the GraphQL interface. */
+export type MutationStartRegisterPasskeyArgs = {
+  input: StartRegisterPasskeyInput;
+};
+
+
+/** The mutations root of the GraphQL interface. */
+export type MutationStartTotpEnrollmentArgs = {
+  input: StartTotpEnrollmentInput;
+};
+
+
 /** The mutations root of the GraphQL interface. */
 export type MutationUnlockUserArgs = {
   input: UnlockUserInput;
 };
 
+
+/** The mutations root of the GraphQL interface. */
+export type MutationVerifyTotpEnrollmentArgs = {
+  input: VerifyTotpEnrollmentInput;
+};
+
 /** An object with an ID. */
 export type Node = {
   /** ID of the object. */
@@ -865,6 +1010,8 @@ export type Query = {
   userEmail?: Maybe<UserEmail>;
   /** Fetch a user email authentication session */
   userEmailAuthentication?: Maybe<UserEmailAuthentication>;
+  /** Fetch a user passkey by its ID. */
+  userPasskey?: Maybe<UserPasskey>;
   /** Fetch a user recovery ticket. */
   userRecoveryTicket?: Maybe<UserRecoveryTicket>;
   /**
@@ -962,6 +1109,12 @@ export type QueryUserEmailAuthenticationArgs = {
 };
 
 
+/** The query root of the GraphQL interface. */
+export type QueryUserPasskeyArgs = {
+  id: Scalars['ID']['input'];
+};
+
+
 /** The query root of the GraphQL interface. */
 export type QueryUserRecoveryTicketArgs = {
   ticket: Scalars['String']['input'];
@@ -1009,6 +1162,88 @@ export type RemoveEmailStatus =
   /** The email address was removed */
   | 'REMOVED';
 
+/** The input for the `removePasskey` mutation */
+export type RemovePasskeyInput = {
+  /** The ID of the passkey to remove */
+  id: Scalars['ID']['input'];
+  /**
+   * The user's current password. This is required if the user has a
+   * password on its account.
+   */
+  password?: InputMaybe<Scalars['String']['input']>;
+};
+
+/** The payload of the `removePasskey` mutation */
+export type RemovePasskeyPayload = {
+  __typename?: 'RemovePasskeyPayload';
+  /** Status of the operation */
+  status: RemovePasskeyStatus;
+  /** The user who owned the passkey */
+  user?: Maybe<User>;
+};
+
+/** The status of the `removePasskey` mutation */
+export type RemovePasskeyStatus =
+  /** The password provided is incorrect */
+  | 'INCORRECT_PASSWORD'
+  /** The passkey was not found */
+  | 'NOT_FOUND'
+  /** The passkey was removed */
+  | 'REMOVED';
+
+/** The input for the `removeTotpAuthenticator` mutation */
+export type RemoveTotpAuthenticatorInput = {
+  /**
+   * The user's current password. This is required if the user has a
+   * password on its account.
+   */
+  password?: InputMaybe<Scalars['String']['input']>;
+};
+
+/** The payload of the `removeTotpAuthenticator` mutation */
+export type RemoveTotpAuthenticatorPayload = {
+  __typename?: 'RemoveTotpAuthenticatorPayload';
+  /** Status of the operation */
+  status: RemoveTotpAuthenticatorStatus;
+  /** The user whose authenticator was removed */
+  user?: Maybe<User>;
+};
+
+/** The status of the `removeTotpAuthenticator` mutation */
+export type RemoveTotpAuthenticatorStatus =
+  /** The password provided is incorrect */
+  | 'INCORRECT_PASSWORD'
+  /** The user has no TOTP authenticator */
+  | 'NOT_FOUND'
+  /** The authenticator was removed */
+  | 'REMOVED';
+
+/** The input for the `renamePasskey` mutation */
+export type RenamePasskeyInput = {
+  /** The ID of the passkey to rename */
+  id: Scalars['ID']['input'];
+  /** The new name */
+  name: Scalars['String']['input'];
+};
+
+/** The payload of the `renamePasskey` mutation */
+export type RenamePasskeyPayload = {
+  __typename?: 'RenamePasskeyPayload';
+  /** The passkey which was renamed */
+  passkey?: Maybe<UserPasskey>;
+  /** Status of the operation */
+  status: RenamePasskeyStatus;
+};
+
+/** The status of the `renamePasskey` mutation */
+export type RenamePasskeyStatus =
+  /** The name is empty or too long */
+  | 'INVALID_NAME'
+  /** The passkey was not found */
+  | 'NOT_FOUND'
+  /** The passkey was renamed */
+  | 'RENAMED';
+
 /** The input for the `resendEmailAuthenticationCode` mutation */
 export type ResendEmailAuthenticationCodeInput = {
   /** The ID of the authentication session to resend the code for */
@@ -1226,6 +1461,8 @@ export type SiteConfig = Node & {
    * in use is <https://crates.io/crates/zxcvbn>.
    */
   minimumPasswordComplexity: Scalars['Int']['output'];
+  /** Whether users can register passkeys and use them to log in. */
+  passkeysEnabled: Scalars['Boolean']['output'];
   /** Whether passwords are enabled and users can change their own passwords. */
   passwordChangeAllowed: Scalars['Boolean']['output'];
   /** Whether passwords are enabled for login. */
@@ -1279,6 +1516,77 @@ export type StartEmailAuthenticationStatus =
   /** The email address was started */
   | 'STARTED';
 
+/** The input for the `startRegisterPasskey` mutation */
+export type StartRegisterPasskeyInput = {
+  /**
+   * The user's current password. This is required if the user has a
+   * password on its account.
+   */
+  password?: InputMaybe<Scalars['String']['input']>;
+};
+
+/** The payload of the `startRegisterPasskey` mutation */
+export type StartRegisterPasskeyPayload = {
+  __typename?: 'StartRegisterPasskeyPayload';
+  /** The ID of the registration, to pass to `completeRegisterPasskey` */
+  id?: Maybe<Scalars['ID']['output']>;
+  /**
+   * The options to pass to `navigator.credentials.create()`, as a JSON
+   * string in the format accepted by
+   * `PublicKeyCredential.parseCreationOptionsFromJSON()`
+   */
+  options?: Maybe<Scalars['String']['output']>;
+  /** Status of the operation */
+  status: StartRegisterPasskeyStatus;
+};
+
+/** The status of the `startRegisterPasskey` mutation */
+export type StartRegisterPasskeyStatus =
+  /** Passkeys are disabled on this server */
+  | 'DISABLED'
+  /** The password provided is incorrect */
+  | 'INCORRECT_PASSWORD'
+  /**
+   * The registration started, the options must now be passed to
+   * `navigator.credentials.create()`
+   */
+  | 'STARTED';
+
+/** The input for the `startTotpEnrollment` mutation */
+export type StartTotpEnrollmentInput = {
+  /**
+   * The user's current password. This is required if the user has a
+   * password on its account.
+   */
+  password?: InputMaybe<Scalars['String']['input']>;
+};
+
+/** The payload of the `startTotpEnrollment` mutation */
+export type StartTotpEnrollmentPayload = {
+  __typename?: 'StartTotpEnrollmentPayload';
+  /**
+   * The shared secret, base32-encoded, for manual entry in an
+   * authenticator app
+   */
+  secret?: Maybe<Scalars['String']['output']>;
+  /** Status of the operation */
+  status: StartTotpEnrollmentStatus;
+  /** The `otpauth://` URI to display as a QR code */
+  uri?: Maybe<Scalars['String']['output']>;
+};
+
+/** The status of the `startTotpEnrollment` mutation */
+export type StartTotpEnrollmentStatus =
+  /** The user already has a verified TOTP authenticator */
+  | 'ALREADY_ENROLLED'
+  /** The password provided is incorrect */
+  | 'INCORRECT_PASSWORD'
+  /**
+   * The enrollment started, the secret must now be added to an
+   * authenticator app and verified
+   */
+  | 'STARTED';
+
 /** The input for the `unlockUser` mutation. */
 export type UnlockUserInput = {
   /** The ID of the user to unlock */
@@ -1403,6 +1711,11 @@ export type User = Node & {
   emails: UserEmailConnection;
   /** Check if the user has a password set. */
   hasPassword: Scalars['Boolean']['output'];
+  /**
+   * Check if the user has a verified TOTP authenticator, used as a second
+   * factor on password logins.
+   */
+  hasTotpAuthenticator: Scalars['Boolean']['output'];
   /** ID of the object. */
   id: Scalars['ID']['output'];
   /** When the user was locked out. */
@@ -1411,6 +1724,13 @@ export type User = Node & {
   matrix: MatrixUser;
   /** Get the list of OAuth 2.0 sessions, chronologically sorted */
   oauth2Sessions: Oauth2SessionConnection;
+  /** Get the list of passkeys registered by the user, oldest first. */
+  passkeys: Array<UserPasskey>;
+  /**
+   * Get the number of recovery codes the user can still use instead of
+   * their second factor.
+   */
+  recoveryCodesRemaining: Scalars['Int']['output'];
   /** Get the list of upstream OAuth 2.0 links */
   upstreamOauth2Links: UpstreamOAuth2LinkConnection;
   /** Username chosen by the user. */
@@ -1485,6 +1805,24 @@ export type UserOauth2SessionsArgs = {
 };
 
 
+/** A `WebAuthn` passkey registered by a user */
+export type UserPasskey = CreationEvent & Node & {
+  __typename?: 'UserPasskey';
+  /** When the object was created. */
+  createdAt: Scalars['DateTime']['output'];
+  /** ID of the object. */
+  id: Scalars['ID']['output'];
+  /** When the passkey was last used to authenticate */
+  lastUsedAt?: Maybe<Scalars['DateTime']['output']>;
+  /** The name given to the passkey by the user */
+  name: Scalars['String']['output'];
+  /**
+   * The transports supported by the authenticator, as reported by the
+   * browser during registration
+   */
+  transports: Array<Scalars['String']['output']>;
+};
+
 /** A user is an individual's account. */
 export type UserUpstreamOauth2LinksArgs = {
   after?: InputMaybe<Scalars['String']['input']>;
@@ -1622,6 +1960,35 @@ export type UserState =
   /** The user is locked. */
   | 'LOCKED';
 
+/** The input for the `verifyTotpEnrollment` mutation */
+export type VerifyTotpEnrollmentInput = {
+  /** The code generated by the authenticator app */
+  code: Scalars['String']['input'];
+};
+
+/** The payload of the `verifyTotpEnrollment` mutation */
+export type VerifyTotpEnrollmentPayload = {
+  __typename?: 'VerifyTotpEnrollmentPayload';
+  /** Status of the operation */
+  status: VerifyTotpEnrollmentStatus;
+  /** The user who enrolled the authenticator */
+  user?: Maybe<User>;
+};
+
+/** The status of the `verifyTotpEnrollment` mutation */
+export type VerifyTotpEnrollmentStatus =
+  /** The code is invalid */
+  | 'INVALID_CODE'
+  /** There is no pending enrollment */
+  | 'NOT_STARTED'
+  /** Too many attempts, try again later */
+  | 'RATE_LIMITED'
+  /**
+   * The authenticator was verified, and will now be required on password
+   * logins
+   */
+  | 'VERIFIED';
+
 /** Represents the current viewer */
 export type Viewer = Anonymous | User;
 
@@ -1735,6 +2102,24 @@ export type SetDisplayNameMutationVariables = Exact<{
 
 export type SetDisplayNameMutation = { __typename?: 'Mutation', setDisplayName: { __typename?: 'SetDisplayNamePayload', status: SetDisplayNameStatus } };
 
+export type UserPasskey_PasskeyFragment = { __typename?: 'UserPasskey', id: string, name: string, createdAt: string, lastUsedAt?: string | null } & { ' $fragmentName'?: 'UserPasskey_PasskeyFragment' };
+
+export type RenamePasskeyMutationVariables = Exact<{
+  id: Scalars['ID']['input'];
+  name: Scalars['String']['input'];
+}>;
+
+
+export type RenamePasskeyMutation = { __typename?: 'Mutation', renamePasskey: { __typename?: 'RenamePasskeyPayload', status: RenamePasskeyStatus, passkey?: { __typename?: 'UserPasskey', id: string, name: string } | null } };
+
+export type RemovePasskeyMutationVariables = Exact<{
+  id: Scalars['ID']['input'];
+  password?: InputMaybe<Scalars['String']['input']>;
+}>;
+
+
+export type RemovePasskeyMutation = { __typename?: 'Mutation', removePasskey: { __typename?: 'RemovePasskeyPayload', status: RemovePasskeyStatus, user?: { __typename?: 'User', id: string } | null } };
+
 export type AddEmailForm_UserFragment = { __typename?: 'User', hasPassword: boolean } & { ' $fragmentName'?: 'AddEmailForm_UserFragment' };
 
 export type AddEmailForm_SiteConfigFragment = { __typename?: 'SiteConfig', passwordLoginEnabled: boolean } & { ' $fragmentName'?: 'AddEmailForm_SiteConfigFragment' };
@@ -1748,6 +2133,65 @@ export type AddEmailMutationVariables = Exact<{
 
 export type AddEmailMutation = { __typename?: 'Mutation', startEmailAuthentication: { __typename?: 'StartEmailAuthenticationPayload', status: StartEmailAuthenticationStatus, violations?: Array<string> | null, authentication?: { __typename?: 'UserEmailAuthentication', id: string } | null } };
 
+export type PasskeyList_UserFragment = { __typename?: 'User', hasPassword: boolean, passkeys: Array<(
+    { __typename?: 'UserPasskey', id: string }
+    & { ' $fragmentRefs'?: { 'UserPasskey_PasskeyFragment': UserPasskey_PasskeyFragment } }
+  )> } & { ' $fragmentName'?: 'PasskeyList_UserFragment' };
+
+export type PasskeyList_SiteConfigFragment = { __typename?: 'SiteConfig', passwordLoginEnabled: boolean } & { ' $fragmentName'?: 'PasskeyList_SiteConfigFragment' };
+
+export type StartRegisterPasskeyMutationVariables = Exact<{
+  password?: InputMaybe<Scalars['String']['input']>;
+}>;
+
+
+export type StartRegisterPasskeyMutation = { __typename?: 'Mutation', startRegisterPasskey: { __typename?: 'StartRegisterPasskeyPayload', status: StartRegisterPasskeyStatus, id?: string | null, options?: string | null } };
+
+export type CompleteRegisterPasskeyMutationVariables = Exact<{
+  id: Scalars['ID']['input'];
+  name: Scalars['String']['input'];
+  response: Scalars['String']['input'];
+}>;
+
+
+export type CompleteRegisterPasskeyMutation = { __typename?: 'Mutation', completeRegisterPasskey: { __typename?: 'CompleteRegisterPasskeyPayload', status: CompleteRegisterPasskeyStatus, passkey?: { __typename?: 'UserPasskey', id: string } | null } };
+
+export type RecoveryCodes_UserFragment = { __typename?: 'User', hasPassword: boolean, recoveryCodesRemaining: number } & { ' $fragmentName'?: 'RecoveryCodes_UserFragment' };
+
+export type RecoveryCodes_SiteConfigFragment = { __typename?: 'SiteConfig', passwordLoginEnabled: boolean } & { ' $fragmentName'?: 'RecoveryCodes_SiteConfigFragment' };
+
+export type GenerateRecoveryCodesMutationVariables = Exact<{
+  password?: InputMaybe<Scalars['String']['input']>;
+}>;
+
+
+export type GenerateRecoveryCodesMutation = { __typename?: 'Mutation', generateRecoveryCodes: { __typename?: 'GenerateRecoveryCodesPayload', status: GenerateRecoveryCodesStatus, codes?: Array<string> | null, user?: { __typename?: 'User', id: string, recoveryCodesRemaining: number } | null } };
+
+export type TotpAuthenticator_UserFragment = { __typename?: 'User', hasPassword: boolean, hasTotpAuthenticator: boolean } & { ' $fragmentName'?: 'TotpAuthenticator_UserFragment' };
+
+export type TotpAuthenticator_SiteConfigFragment = { __typename?: 'SiteConfig', passwordLoginEnabled: boolean } & { ' $fragmentName'?: 'TotpAuthenticator_SiteConfigFragment' };
+
+export type StartTotpEnrollmentMutationVariables = Exact<{
+  password?: InputMaybe<Scalars['String']['input']>;
+}>;
+
+
+export type StartTotpEnrollmentMutation = { __typename?: 'Mutation', startTotpEnrollment: { __typename?: 'StartTotpEnrollmentPayload', status: StartTotpEnrollmentStatus, secret?: string | null, uri?: string | null } };
+
+export type VerifyTotpEnrollmentMutationVariables = Exact<{
+  code: Scalars['String']['input'];
+}>;
+
+
+export type VerifyTotpEnrollmentMutation = { __typename?: 'Mutation', verifyTotpEnrollment: { __typename?: 'VerifyTotpEnrollmentPayload', status: VerifyTotpEnrollmentStatus, user?: { __typename?: 'User', id: string, hasTotpAuthenticator: boolean } | null } };
+
+export type RemoveTotpAuthenticatorMutationVariables = Exact<{
+  password?: InputMaybe<Scalars['String']['input']>;
+}>;
+
+
+export type RemoveTotpAuthenticatorMutation = { __typename?: 'Mutation', removeTotpAuthenticator: { __typename?: 'RemoveTotpAuthenticatorPayload', status: RemoveTotpAuthenticatorStatus, user?: { __typename?: 'User', id: string, hasTotpAuthenticator: boolean } | null } };
+
 export type UserEmailListQueryVariables = Exact<{
   first?: InputMaybe<Scalars['Int']['input']>;
   after?: InputMaybe<Scalars['String']['input']>;
@@ -1771,11 +2215,11 @@ export type UserProfileQueryVariables = Exact<{ [key: string]: never; }>;
 
 
 export type UserProfileQuery = { __typename?: 'Query', viewerSession: { __typename: 'Anonymous' } | { __typename: 'BrowserSession', id: string, user: (
-      { __typename?: 'User', hasPassword: boolean, emails: { __typename?: 'UserEmailConnection', totalCount: number } }
-      & { ' $fragmentRefs'?: { 'AddEmailForm_UserFragment': AddEmailForm_UserFragment;'UserEmailList_UserFragment': UserEmailList_UserFragment;'AccountDeleteButton_UserFragment': AccountDeleteButton_UserFragment } }
+      { __typename?: 'User', hasPassword: boolean, hasTotpAuthenticator: boolean, passkeys: Array<{ __typename?: 'UserPasskey', id: string }>, emails: { __typename?: 'UserEmailConnection', totalCount: number } }
+      & { ' $fragmentRefs'?: { 'AddEmailForm_UserFragment': AddEmailForm_UserFragment;'UserEmailList_UserFragment': UserEmailList_UserFragment;'AccountDeleteButton_UserFragment': AccountDeleteButton_UserFragment;'TotpAuthenticator_UserFragment': TotpAuthenticator_UserFragment;'PasskeyList_UserFragment': PasskeyList_UserFragment;'RecoveryCodes_UserFragment': RecoveryCodes_UserFragment } }
     ) } | { __typename: 'Oauth2Session' }, siteConfig: (
-    { __typename?: 'SiteConfig', emailChangeAllowed: boolean, passwordLoginEnabled: boolean, accountDeactivationAllowed: boolean }
-    & { ' $fragmentRefs'?: { 'AddEmailForm_SiteConfigFragment': AddEmailForm_SiteConfigFragment;'UserEmailList_SiteConfigFragment': UserEmailList_SiteConfigFragment;'PasswordChange_SiteConfigFragment': PasswordChange_SiteConfigFragment;'AccountDeleteButton_SiteConfigFragment': AccountDeleteButton_SiteConfigFragment } }
+    { __typename?: 'SiteConfig', emailChangeAllowed: boolean, passwordLoginEnabled: boolean, accountDeactivationAllowed: boolean, passkeysEnabled: boolean }
+    & { ' $fragmentRefs'?: { 'AddEmailForm_SiteConfigFragment': AddEmailForm_SiteConfigFragment;'UserEmailList_SiteConfigFragment': UserEmailList_SiteConfigFragment;'PasswordChange_SiteConfigFragment': PasswordChange_SiteConfigFragment;'AccountDeleteButton_SiteConfigFragment': AccountDeleteButton_SiteConfigFragment;'TotpAuthenticator_SiteConfigFragment': TotpAuthenticator_SiteConfigFragment;'PasskeyList_SiteConfigFragment': PasskeyList_SiteConfigFragment;'RecoveryCodes_SiteConfigFragment': RecoveryCodes_SiteConfigFragment } }
   ) };
 
 export type BrowserSessionListQueryVariables = Exact<{
@@ -1947,7 +2391,7 @@ export type SessionDetailQuery = { __typename?: 'Query', viewerSession: { __type
   ) | { __typename: 'CompatSsoLogin', id: string } | { __typename: 'Oauth2Client', id: string } | (
     { __typename: 'Oauth2Session', id: string }
     & { ' $fragmentRefs'?: { 'OAuth2Session_DetailFragment': OAuth2Session_DetailFragment } }
-  ) | { __typename: 'SiteConfig', id: string } | { __typename: 'UpstreamOAuth2Link', id: string } | { __typename: 'UpstreamOAuth2Provider', id: string } | { __typename: 'User', id: string } | { __typename: 'UserEmail', id: string } | { __typename: 'UserEmailAuthentication', id: string } | { __typename: 'UserRecoveryTicket', id: string } | null };
+  ) | { __typename: 'SiteConfig', id: string } | { __typename: 'UpstreamOAuth2Link', id: string } | { __typename: 'UpstreamOAuth2Provider', id: string } | { __typename: 'User', id: string } | { __typename: 'UserEmail', id: string } | { __typename: 'UserEmailAuthentication', id: string } | { __typename: 'UserPasskey', id: string } | { __typename: 'UserRecoveryTicket', id: string } | null };
 
 export class TypedDocumentString<TResult, TVariables>
   extends String
@@ -2264,6 +2708,14 @@ export const UserGreeting_SiteConfigFragmentDoc = new TypedDocumentString(`
   displayNameChangeAllowed
 }
     `, {"fragmentName":"UserGreeting_siteConfig"}) as unknown as TypedDocumentString<UserGreeting_SiteConfigFragment, unknown>;
+export const UserPasskey_PasskeyFragmentDoc = new TypedDocumentString(`
+    fragment UserPasskey_passkey on UserPasskey {
+  id
+  name
+  createdAt
+  lastUsedAt
+}
+    `, {"fragmentName":"UserPasskey_passkey"}) as unknown as TypedDocumentString<UserPasskey_PasskeyFragment, unknown>;
 export const AddEmailForm_UserFragmentDoc = new TypedDocumentString(`
     fragment AddEmailForm_user on User {
   hasPassword
@@ -2274,6 +2726,47 @@ export const AddEmailForm_SiteConfigFragmentDoc = new TypedDocumentString(`
   passwordLoginEnabled
 }
     `, {"fragmentName":"AddEmailForm_siteConfig"}) as unknown as TypedDocumentString<AddEmailForm_SiteConfigFragment, unknown>;
+export const PasskeyList_UserFragmentDoc = new TypedDocumentString(`
+    fragment PasskeyList_user on User {
+  hasPassword
+  passkeys {
+    id
+    ...UserPasskey_passkey
+  }
+}
+    fragment UserPasskey_passkey on UserPasskey {
+  id
+  name
+  createdAt
+  lastUsedAt
+}`, {"fragmentName":"PasskeyList_user"}) as unknown as TypedDocumentString<PasskeyList_UserFragment, unknown>;
+export const PasskeyList_SiteConfigFragmentDoc = new TypedDocumentString(`
+    fragment PasskeyList_siteConfig on SiteConfig {
+  passwordLoginEnabled
+}
+    `, {"fragmentName":"PasskeyList_siteConfig"}) as unknown as TypedDocumentString<PasskeyList_SiteConfigFragment, unknown>;
+export const RecoveryCodes_UserFragmentDoc = new TypedDocumentString(`
+    fragment RecoveryCodes_user on User {
+  hasPassword
+  recoveryCodesRemaining
+}
+    `, {"fragmentName":"RecoveryCodes_user"}) as unknown as TypedDocumentString<RecoveryCodes_UserFragment, unknown>;
+export const RecoveryCodes_SiteConfigFragmentDoc = new TypedDocumentString(`
+    fragment RecoveryCodes_siteConfig on SiteConfig {
+  passwordLoginEnabled
+}
+    `, {"fragmentName":"RecoveryCodes_siteConfig"}) as unknown as TypedDocumentString<RecoveryCodes_SiteConfigFragment, unknown>;
+export const TotpAuthenticator_UserFragmentDoc = new TypedDocumentString(`
+    fragment TotpAuthenticator_user on User {
+  hasPassword
+  hasTotpAuthenticator
+}
+    `, {"fragmentName":"TotpAuthenticator_user"}) as unknown as TypedDocumentString<TotpAuthenticator_UserFragment, unknown>;
+export const TotpAuthenticator_SiteConfigFragmentDoc = new TypedDocumentString(`
+    fragment TotpAuthenticator_siteConfig on SiteConfig {
+  passwordLoginEnabled
+}
+    `, {"fragmentName":"TotpAuthenticator_siteConfig"}) as unknown as TypedDocumentString<TotpAuthenticator_SiteConfigFragment, unknown>;
 export const UserEmailList_UserFragmentDoc = new TypedDocumentString(`
     fragment UserEmailList_user on User {
   hasPassword
@@ -2380,6 +2873,27 @@ export const SetDisplayNameDocument = new TypedDocumentString(`
   }
 }
     `) as unknown as TypedDocumentString<SetDisplayNameMutation, SetDisplayNameMutationVariables>;
+export const RenamePasskeyDocument = new TypedDocumentString(`
+    mutation RenamePasskey($id: ID!, $name: String!) {
+  renamePasskey(input: {id: $id, name: $name}) {
+    status
+    passkey {
+      id
+      name
+    }
+  }
+}
+    `) as unknown as TypedDocumentString<RenamePasskeyMutation, RenamePasskeyMutationVariables>;
+export const RemovePasskeyDocument = new TypedDocumentString(`
+    mutation RemovePasskey($id: ID!, $password: String) {
+  removePasskey(input: {id: $id, password: $password}) {
+    status
+    user {
+      id
+    }
+  }
+}
+    `) as unknown as TypedDocumentString<RemovePasskeyMutation, RemovePasskeyMutationVariables>;
 export const AddEmailDocument = new TypedDocumentString(`
     mutation AddEmail($email: String!, $password: String, $language: String!) {
   startEmailAuthentication(
@@ -2393,6 +2907,68 @@ export const AddEmailDocument = new TypedDocumentString(`
   }
 }
     `) as unknown as TypedDocumentString<AddEmailMutation, AddEmailMutationVariables>;
+export const StartRegisterPasskeyDocument = new TypedDocumentString(`
+    mutation StartRegisterPasskey($password: String) {
+  startRegisterPasskey(input: {password: $password}) {
+    status
+    id
+    options
+  }
+}
+    `) as unknown as TypedDocumentString<StartRegisterPasskeyMutation, StartRegisterPasskeyMutationVariables>;
+export const CompleteRegisterPasskeyDocument = new TypedDocumentString(`
+    mutation CompleteRegisterPasskey($id: ID!, $name: String!, $response: String!) {
+  completeRegisterPasskey(input: {id: $id, name: $name, response: $response}) {
+    status
+    passkey {
+      id
+    }
+  }
+}
+    `) as unknown as TypedDocumentString<CompleteRegisterPasskeyMutation, CompleteRegisterPasskeyMutationVariables>;
+export const GenerateRecoveryCodesDocument = new TypedDocumentString(`
+    mutation GenerateRecoveryCodes($password: String) {
+  generateRecoveryCodes(input: {password: $password}) {
+    status
+    codes
+    user {
+      id
+      recoveryCodesRemaining
+    }
+  }
+}
+    `) as unknown as TypedDocumentString<GenerateRecoveryCodesMutation, GenerateRecoveryCodesMutationVariables>;
+export const StartTotpEnrollmentDocument = new TypedDocumentString(`
+    mutation StartTotpEnrollment($password: String) {
+  startTotpEnrollment(input: {password: $password}) {
+    status
+    secret
+    uri
+  }
+}
+    `) as unknown as TypedDocumentString<StartTotpEnrollmentMutation, StartTotpEnrollmentMutationVariables>;
+export const VerifyTotpEnrollmentDocument = new TypedDocumentString(`
+    mutation VerifyTotpEnrollment($code: String!) {
+  verifyTotpEnrollment(input: {code: $code}) {
+    status
+    user {
+      id
+      hasTotpAuthenticator
+    }
+  }
+}
+    `) as unknown as TypedDocumentString<VerifyTotpEnrollmentMutation, VerifyTotpEnrollmentMutationVariables>;
+export const RemoveTotpAuthenticatorDocument = new TypedDocumentString(`
+    mutation RemoveTotpAuthenticator($password: String) {
+  removeTotpAuthenticator(input: {password: $password}) {
+    status
+    user {
+      id
+      hasTotpAuthenticator
+    }
+  }
+}
+    `) as unknown as TypedDocumentString<RemoveTotpAuthenticatorMutation, RemoveTotpAuthenticatorMutationVariables>;
 export const UserEmailListDocument = new TypedDocumentString(`
     query UserEmailList($first: Int, $after: String, $last: Int, $before: String) {
   viewer {
@@ -2430,7 +3006,14 @@ export const UserProfileDocument = new TypedDocumentString(`
         ...AddEmailForm_user
         ...UserEmailList_user
         ...AccountDeleteButton_user
+        ...TotpAuthenticator_user
+        ...PasskeyList_user
+        ...RecoveryCodes_user
         hasPassword
+        hasTotpAuthenticator
+        passkeys {
+          id
+        }
         emails(first: 0) {
           totalCount
         }
@@ -2441,10 +3024,14 @@ export const UserProfileDocument = new TypedDocumentString(`
     emailChangeAllowed
     passwordLoginEnabled
     accountDeactivationAllowed
+    passkeysEnabled
     ...AddEmailForm_siteConfig
     ...UserEmailList_siteConfig
     ...PasswordChange_siteConfig
     ...AccountDeleteButton_siteConfig
+    ...TotpAuthenticator_siteConfig
+    ...PasskeyList_siteConfig
+    ...RecoveryCodes_siteConfig
   }
 }
     fragment AccountDeleteButton_user on User {
@@ -2461,12 +3048,42 @@ fragment AccountDeleteButton_siteConfig on SiteConfig {
 fragment PasswordChange_siteConfig on SiteConfig {
   passwordChangeAllowed
 }
+fragment UserPasskey_passkey on UserPasskey {
+  id
+  name
+  createdAt
+  lastUsedAt
+}
 fragment AddEmailForm_user on User {
   hasPassword
 }
 fragment AddEmailForm_siteConfig on SiteConfig {
   passwordLoginEnabled
 }
+fragment PasskeyList_user on User {
+  hasPassword
+  passkeys {
+    id
+    ...UserPasskey_passkey
+  }
+}
+fragment PasskeyList_siteConfig on SiteConfig {
+  passwordLoginEnabled
+}
+fragment RecoveryCodes_user on User {
+  hasPassword
+  recoveryCodesRemaining
+}
+fragment RecoveryCodes_siteConfig on SiteConfig {
+  passwordLoginEnabled
+}
+fragment TotpAuthenticator_user on User {
+  hasPassword
+  hasTotpAuthenticator
+}
+fragment TotpAuthenticator_siteConfig on SiteConfig {
+  passwordLoginEnabled
+}
 fragment UserEmailList_user on User {
   hasPassword
 }
@@ -3066,6 +3683,50 @@ export const mockSetDisplayNameMutation = (resolver: GraphQLResponseResolver<Set
     options
   )
 
+/**
+ * @param resolver A function that accepts [resolver arguments](https://mswjs.io/docs/api/graphql#resolver-argument) and must always return the instruction on what to do with the intercepted request. ([see more](https://mswjs.io/docs/concepts/response-resolver#resolver-instructions))
+ * @param options Options object to customize the behavior of the mock. ([see more](https://mswjs.io/docs/api/graphql#handler-options))
+ * @see https://mswjs.io/docs/basics/response-resolver
+ * @example
+ * mockRenamePasskeyMutation(
+ *   ({ query, variables }) => {
+ *     const { id, name } = variables;
+ *     return HttpResponse.json({
+ *       data: { renamePasskey }
+ *     })
+ *   },
+ *   requestOptions
+ * )
+ */
+export const mockRenamePasskeyMutation = (resolver: GraphQLResponseResolver<RenamePasskeyMutation, RenamePasskeyMutationVariables>, options?: RequestHandlerOptions) =>
+  graphql.mutation<RenamePasskeyMutation, RenamePasskeyMutationVariables>(
+    'RenamePasskey',
+    resolver,
+    options
+  )
+
+/**
+ * @param resolver A function that accepts [resolver arguments](https://mswjs.io/docs/api/graphql#resolver-argument) and must always return the instruction on what to do with the intercepted request. ([see more](https://mswjs.io/docs/concepts/response-resolver#resolver-instructions))
+ * @param options Options object to customize the behavior of the mock. ([see more](https://mswjs.io/docs/api/graphql#handler-options))
+ * @see https://mswjs.io/docs/basics/response-resolver
+ * @example
+ * mockRemovePasskeyMutation(
+ *   ({ query, variables }) => {
+ *     const { id, password } = variables;
+ *     return HttpResponse.json({
+ *       data: { removePasskey }
+ *     })
+ *   },
+ *   requestOptions
+ * )
+ */
+export const mockRemovePasskeyMutation = (resolver: GraphQLResponseResolver<RemovePasskeyMutation, RemovePasskeyMutationVariables>, options?: RequestHandlerOptions) =>
+  graphql.mutation<RemovePasskeyMutation, RemovePasskeyMutationVariables>(
+    'RemovePasskey',
+    resolver,
+    options
+  )
+
 /**
  * @param resolver A function that accepts [resolver arguments](https://mswjs.io/docs/api/graphql#resolver-argument) and must always return the instruction on what to do with the intercepted request. ([see more](https://mswjs.io/docs/concepts/response-resolver#resolver-instructions))
  * @param options Options object to customize the behavior of the mock. ([see more](https://mswjs.io/docs/api/graphql#handler-options))
@@ -3088,6 +3749,138 @@ export const mockAddEmailMutation = (resolver: GraphQLResponseResolver<AddEmailM
     options
   )
 
+/**
+ * @param resolver A function that accepts [resolver arguments](https://mswjs.io/docs/api/graphql#resolver-argument) and must always return the instruction on what to do with the intercepted request. ([see more](https://mswjs.io/docs/concepts/response-resolver#resolver-instructions))
+ * @param options Options object to customize the behavior of the mock. ([see more](https://mswjs.io/docs/api/graphql#handler-options))
+ * @see https://mswjs.io/docs/basics/response-resolver
+ * @example
+ * mockStartRegisterPasskeyMutation(
+ *   ({ query, variables }) => {
+ *     const { password } = variables;
+ *     return HttpResponse.json({
+ *       data: { startRegisterPasskey }
+ *     })
+ *   },
+ *   requestOptions
+ * )
+ */
+export const mockStartRegisterPasskeyMutation = (resolver: GraphQLResponseResolver<StartRegisterPasskeyMutation, StartRegisterPasskeyMutationVariables>, options?: RequestHandlerOptions) =>
+  graphql.mutation<StartRegisterPasskeyMutation, StartRegisterPasskeyMutationVariables>(
+    'StartRegisterPasskey',
+    resolver,
+    options
+  )
+
+/**
+ * @param resolver A function that accepts [resolver arguments](https://mswjs.io/docs/api/graphql#resolver-argument) and must always return the instruction on what to do with the intercepted request. ([see more](https://mswjs.io/docs/concepts/response-resolver#resolver-instructions))
+ * @param options Options object to customize the behavior of the mock. ([see more](https://mswjs.io/docs/api/graphql#handler-options))
+ * @see https://mswjs.io/docs/basics/response-resolver
+ * @example
+ * mockCompleteRegisterPasskeyMutation(
+ *   ({ query, variables }) => {
+ *     const { id, name, response } = variables;
+ *     return HttpResponse.json({
+ *       data: { completeRegisterPasskey }
+ *     })
+ *   },
+ *   requestOptions
+ * )
+ */
+export const mockCompleteRegisterPasskeyMutation = (resolver: GraphQLResponseResolver<CompleteRegisterPasskeyMutation, CompleteRegisterPasskeyMutationVariables>, options?: RequestHandlerOptions) =>
+  graphql.mutation<CompleteRegisterPasskeyMutation, CompleteRegisterPasskeyMutationVariables>(
+    'CompleteRegisterPasskey',
+    resolver,
+    options
+  )
+
+/**
+ * @param resolver A function that accepts [resolver arguments](https://mswjs.io/docs/api/graphql#resolver-argument) and must always return the instruction on what to do with the intercepted request. ([see more](https://mswjs.io/docs/concepts/response-resolver#resolver-instructions))
+ * @param options Options object to customize the behavior of the mock. ([see more](https://mswjs.io/docs/api/graphql#handler-options))
+ * @see https://mswjs.io/docs/basics/response-resolver
+ * @example
+ * mockGenerateRecoveryCodesMutation(
+ *   ({ query, variables }) => {
+ *     const { password } = variables;
+ *     return HttpResponse.json({
+ *       data: { generateRecoveryCodes }
+ *     })
+ *   },
+ *   requestOptions
+ * )
+ */
+export const mockGenerateRecoveryCodesMutation = (resolver: GraphQLResponseResolver<GenerateRecoveryCodesMutation, GenerateRecoveryCodesMutationVariables>, options?: RequestHandlerOptions) =>
+  graphql.mutation<GenerateRecoveryCodesMutation, GenerateRecoveryCodesMutationVariables>(
+    'GenerateRecoveryCodes',
+    resolver,
+    options
+  )
+
+/**
+ * @param resolver A function that accepts [resolver arguments](https://mswjs.io/docs/api/graphql#resolver-argument) and must always return the instruction on what to do with the intercepted request. ([see more](https://mswjs.io/docs/concepts/response-resolver#resolver-instructions))
+ * @param options Options object to customize the behavior of the mock. ([see more](https://mswjs.io/docs/api/graphql#handler-options))
+ * @see https://mswjs.io/docs/basics/response-resolver
+ * @example
+ * mockStartTotpEnrollmentMutation(
+ *   ({ query, variables }) => {
+ *     const { password } = variables;
+ *     return HttpResponse.json({
+ *       data: { startTotpEnrollment }
+ *     })
+ *   },
+ *   requestOptions
+ * )
+ */
+export const mockStartTotpEnrollmentMutation = (resolver: GraphQLResponseResolver<StartTotpEnrollmentMutation, StartTotpEnrollmentMutationVariables>, options?: RequestHandlerOptions) =>
+  graphql.mutation<StartTotpEnrollmentMutation, StartTotpEnrollmentMutationVariables>(
+    'StartTotpEnrollment',
+    resolver,
+    options
+  )
+
+/**
+ * @param resolver A function that accepts [resolver arguments](https://mswjs.io/docs/api/graphql#resolver-argument) and must always return the instruction on what to do with the intercepted request. ([see more](https://mswjs.io/docs/concepts/response-resolver#resolver-instructions))
+ * @param options Options object to customize the behavior of the mock. ([see more](https://mswjs.io/docs/api/graphql#handler-options))
+ * @see https://mswjs.io/docs/basics/response-resolver
+ * @example
+ * mockVerifyTotpEnrollmentMutation(
+ *   ({ query, variables }) => {
+ *     const { code } = variables;
+ *     return HttpResponse.json({
+ *       data: { verifyTotpEnrollment }
+ *     })
+ *   },
+ *   requestOptions
+ * )
+ */
+export const mockVerifyTotpEnrollmentMutation = (resolver: GraphQLResponseResolver<VerifyTotpEnrollmentMutation, VerifyTotpEnrollmentMutationVariables>, options?: RequestHandlerOptions) =>
+  graphql.mutation<VerifyTotpEnrollmentMutation, VerifyTotpEnrollmentMutationVariables>(
+    'VerifyTotpEnrollment',
+    resolver,
+    options
+  )
+
+/**
+ * @param resolver A function that accepts [resolver arguments](https://mswjs.io/docs/api/graphql#resolver-argument) and must always return the instruction on what to do with the intercepted request. ([see more](https://mswjs.io/docs/concepts/response-resolver#resolver-instructions))
+ * @param options Options object to customize the behavior of the mock. ([see more](https://mswjs.io/docs/api/graphql#handler-options))
+ * @see https://mswjs.io/docs/basics/response-resolver
+ * @example
+ * mockRemoveTotpAuthenticatorMutation(
+ *   ({ query, variables }) => {
+ *     const { password } = variables;
+ *     return HttpResponse.json({
+ *       data: { removeTotpAuthenticator }
+ *     })
+ *   },
+ *   requestOptions
+ * )
+ */
+export const mockRemoveTotpAuthenticatorMutation = (resolver: GraphQLResponseResolver<RemoveTotpAuthenticatorMutation, RemoveTotpAuthenticatorMutationVariables>, options?: RequestHandlerOptions) =>
+  graphql.mutation<RemoveTotpAuthenticatorMutation, RemoveTotpAuthenticatorMutationVariables>(
+    'RemoveTotpAuthenticator',
+    resolver,
+    options
+  )
+
 /**
  * @param resolver A function that accepts [resolver arguments](https://mswjs.io/docs/api/graphql#resolver-argument) and must always return the instruction on what to do with the intercepted request. ([see more](https://mswjs.io/docs/concepts/response-resolver#resolver-instructions))
  * @param options Options object to customize the behavior of the mock. ([see more](https://mswjs.io/docs/api/graphql#handler-options))
@@ -24,6 +24,9 @@ import LoadingSpinner from "../components/LoadingSpinner";
 import Separator from "../components/Separator";
 import { useEndBrowserSession } from "../components/Session/EndBrowserSessionButton";
 import AddEmailForm from "../components/UserProfile/AddEmailForm";
+import PasskeyList from "../components/UserProfile/PasskeyList";
+import RecoveryCodes from "../components/UserProfile/RecoveryCodes";
+import TotpAuthenticator from "../components/UserProfile/TotpAuthenticator";
 import UserEmailList, {
   query as userEmailListQuery,
 } from "../components/UserProfile/UserEmailList";
@@ -40,7 +43,14 @@ const QUERY = graphql(/* GraphQL */ `
           ...AddEmailForm_user
           ...UserEmailList_user
           ...AccountDeleteButton_user
+          ...TotpAuthenticator_user
+          ...PasskeyList_user
+          ...RecoveryCodes_user
           hasPassword
+          hasTotpAuthenticator
+          passkeys {
+            id
+          }
           emails(first: 0) {
             totalCount
           }
@@ -52,10 +62,14 @@ const QUERY = graphql(/* GraphQL */ `
       emailChangeAllowed
       passwordLoginEnabled
       accountDeactivationAllowed
+      passkeysEnabled
       ...AddEmailForm_siteConfig
       ...UserEmailList_siteConfig
       ...PasswordChange_siteConfig
       ...AccountDeleteButton_siteConfig
+      ...TotpAuthenticator_siteConfig
+      ...PasskeyList_siteConfig
+      ...RecoveryCodes_siteConfig
     }
   }
 `);
@@ -224,6 +238,44 @@ function Index(): React.ReactElement {
             </Collapsible.Section>
 
             <Separator kind="section" />
+
+            <Collapsible.Section
+              title={t("frontend.account.authenticator_app")}
+            >
+              <TotpAuthenticator
+                user={viewerSession.user}
+                siteConfig={siteConfig}
+              />
+            </Collapsible.Section>
+
+            <Separator kind="section" />
+          </>
+        )}
+
+        {siteConfig.passkeysEnabled && (
+          <>
+            <Collapsible.Section title={t("frontend.account.passkeys")}>
+              <PasskeyList user={viewerSession.user} siteConfig={siteConfig} />
+            </Collapsible.Section>
+
+            <Separator kind="section" />
+          </>
+        )}
+
+        {/* Recovery codes are only useful if there is a second factor to
+          recover from */}
+        {(viewerSession.user.hasTotpAuthenticator ||
+          (siteConfig.passkeysEnabled &&
+            viewerSession.user.passkeys.length > 0)) && (
+          <>
+            <Collapsible.Section title={t("frontend.account.recovery_codes")}>
+              <RecoveryCodes
+                user={viewerSession.user}
+                siteConfig={siteConfig}
+              />
+            </Collapsible.Section>
+
+            <Separator kind="section" />
           </>
         )}
 
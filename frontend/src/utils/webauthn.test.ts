@@ -0,0 +1,57 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+import { describe, expect, it } from "vitest";
+
+import {
+  base64UrlDecode,
+  base64UrlEncode,
+  parseCreationOptions,
+} from "./webauthn";
+
+const bytes = (data: ArrayBuffer | BufferSource | undefined): number[] =>
+  Array.from(new Uint8Array(data as ArrayBuffer));
+
+describe("base64url", () => {
+  it("round-trips binary data", () => {
+    const data = new Uint8Array([0xfb, 0xff, 0xbf, 0x00, 0x01]).buffer;
+    const encoded = base64UrlEncode(data);
+    expect(encoded).toEqual("-_-_AAE");
+    expect(bytes(base64UrlDecode(encoded))).toEqual(bytes(data));
+  });
+
+  it("decodes strings of any length", () => {
+    expect(bytes(base64UrlDecode(""))).toEqual([]);
+    expect(bytes(base64UrlDecode("YQ"))).toEqual([0x61]);
+    expect(bytes(base64UrlDecode("YWI"))).toEqual([0x61, 0x62]);
+    expect(bytes(base64UrlDecode("YWJj"))).toEqual([0x61, 0x62, 0x63]);
+  });
+});
+
+describe("parseCreationOptions()", () => {
+  it("decodes the binary fields", () => {
+    const options = parseCreationOptions(
+      JSON.stringify({
+        rp: { id: "example.com", name: "example.com" },
+        user: { id: "AQID", name: "alice", displayName: "alice" },
+        challenge: "BAUG",
+        pubKeyCredParams: [{ type: "public-key", alg: -7 }],
+        excludeCredentials: [
+          { type: "public-key", id: "BwgJ", transports: ["usb"] },
+        ],
+        attestation: "none",
+      }),
+    );
+
+    expect(options.rp).toEqual({ id: "example.com", name: "example.com" });
+    expect(options.user.name).toEqual("alice");
+    expect(bytes(options.user.id)).toEqual([1, 2, 3]);
+    expect(bytes(options.challenge)).toEqual([4, 5, 6]);
+    expect(options.excludeCredentials).toHaveLength(1);
+    expect(bytes(options.excludeCredentials?.[0].id)).toEqual([7, 8, 9]);
+    expect(options.excludeCredentials?.[0].transports).toEqual(["usb"]);
+    expect(options.attestation).toEqual("none");
+  });
+});
@@ -0,0 +1,104 @@
+// Copyright 2025 New Vector Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only
+// Please see LICENSE in the repository root for full details.
+
+// Helpers to go between the JSON representation of WebAuthn options and
+// credentials used by the server, and the binary one used by the browser.
+// Those do what `PublicKeyCredential.parseCreationOptionsFromJSON()` and
+// `PublicKeyCredential.prototype.toJSON()` do, but these aren't available in
+// all browsers yet.
+
+/**
+ * Decode an unpadded base64url string
+ *
+ * @param data The base64url-encoded string
+ * @returns The decoded bytes
+ */
+export const base64UrlDecode = (data: string): ArrayBuffer => {
+  const base64 = data.replace(/-/g, "+").replace(/_/g, "/");
+  const binary = atob(base64.padEnd(Math.ceil(base64.length / 4) * 4, "="));
+  const bytes = new Uint8Array(binary.length);
+  for (let i = 0; i < binary.length; i++) {
+    bytes[i] = binary.charCodeAt(i);
+  }
+  return bytes.buffer;
+};
+
+/**
+ * Encode bytes as an unpadded base64url string
+ *
+ * @param data The bytes to encode
+ * @returns The base64url-encoded string
+ */
+export const base64UrlEncode = (data: ArrayBuffer): string => {
+  const bytes = new Uint8Array(data);
+  let binary = "";
+  for (const byte of bytes) {
+    binary += String.fromCharCode(byte);
+  }
+  return btoa(binary)
+    .replace(/\+/g, "-")
+    .replace(/\//g, "_")
+    .replace(/=+$/, "");
+};
+
+type CredentialDescriptorJSON = {
+  type: PublicKeyCredentialType;
+  id: string;
+  transports?: AuthenticatorTransport[];
+};
+
+type CreationOptionsJSON = Omit<
+  PublicKeyCredentialCreationOptions,
+  "challenge" | "user" | "excludeCredentials"
+> & {
+  challenge: string;
+  user: Omit<PublicKeyCredentialUserEntity, "id"> & { id: string };
+  excludeCredentials?: CredentialDescriptorJSON[];
+};
+
+/**
+ * Parse the options returned by the server to pass to
+ * `navigator.credentials.create()`
+ *
+ * @param json The options, as a JSON string
+ * @returns The options, with the binary fields decoded
+ */
+export const parseCreationOptions = (
+  json: string,
+): PublicKeyCredentialCreationOptions => {
+  const options: CreationOptionsJSON = JSON.parse(json);
+  return {
+    ...options,
+    challenge: base64UrlDecode(options.challenge),
+    user: { ...options.user, id: base64UrlDecode(options.user.id) },
+    excludeCredentials: options.excludeCredentials?.map((credential) => ({
+      ...credential,
+      id: base64UrlDecode(credential.id),
+    })),
+  };
+};
+
+/**
+ * Serialize the credential created by `navigator.credentials.create()` to
+ * send it back to the server
+ *
+ * @param credential The newly created credential
+ * @returns The credential, as a JSON string
+ */
+export const serializeRegistration = (
+  credential: PublicKeyCredential,
+): string => {
+  const response = credential.response as AuthenticatorAttestationResponse;
+  return JSON.stringify({
+    id: credential.id,
+    rawId: base64UrlEncode(credential.rawId),
+    type: credential.type,
+    response: {
+      clientDataJSON: base64UrlEncode(response.clientDataJSON),
+      attestationObject: base64UrlEncode(response.attestationObject),
+      transports: response.getTransports?.() ?? [],
+    },
+  });
+};
@@ -19,6 +19,18 @@ import {
   CONFIG_FRAGMENT as ADD_USER_EMAIL_CONFIG_FRAGMENT,
   USER_FRAGMENT as ADD_USER_EMAIL_USER_FRAGMENT,
 } from "../../src/components/UserProfile/AddEmailForm";
+import {
+  CONFIG_FRAGMENT as PASSKEY_LIST_CONFIG_FRAGMENT,
+  USER_FRAGMENT as PASSKEY_LIST_USER_FRAGMENT,
+} from "../../src/components/UserProfile/PasskeyList";
+import {
+  CONFIG_FRAGMENT as RECOVERY_CODES_CONFIG_FRAGMENT,
+  USER_FRAGMENT as RECOVERY_CODES_USER_FRAGMENT,
+} from "../../src/components/UserProfile/RecoveryCodes";
+import {
+  CONFIG_FRAGMENT as TOTP_AUTHENTICATOR_CONFIG_FRAGMENT,
+  USER_FRAGMENT as TOTP_AUTHENTICATOR_USER_FRAGMENT,
+} from "../../src/components/UserProfile/TotpAuthenticator";
 import {
   CONFIG_FRAGMENT as USER_EMAIL_LIST_CONFIG_FRAGMENT,
   USER_FRAGMENT as USER_EMAIL_LIST_USER_FRAGMENT,
@@ -101,6 +113,8 @@ export const handlers = [
           user: Object.assign(
             {
               hasPassword: true,
+              hasTotpAuthenticator: false,
+              passkeys: [],
               emails: {
                 totalCount: 1,
               },
@@ -128,6 +142,27 @@ export const handlers = [
               },
               ACCOUNT_DELETE_BUTTON_USER_FRAGMENT,
             ),
+            makeFragmentData(
+              {
+                hasPassword: true,
+                hasTotpAuthenticator: false,
+              },
+              TOTP_AUTHENTICATOR_USER_FRAGMENT,
+            ),
+            makeFragmentData(
+              {
+                hasPassword: true,
+                passkeys: [],
+              },
+              PASSKEY_LIST_USER_FRAGMENT,
+            ),
+            makeFragmentData(
+              {
+                hasPassword: true,
+                recoveryCodesRemaining: 0,
+              },
+              RECOVERY_CODES_USER_FRAGMENT,
+            ),
           ),
         },
 
@@ -136,6 +171,7 @@ export const handlers = [
             emailChangeAllowed: true,
             passwordLoginEnabled: true,
             accountDeactivationAllowed: true,
+            passkeysEnabled: false,
           },
           makeFragmentData(
             {
@@ -163,6 +199,24 @@ export const handlers = [
             },
             ACCOUNT_DELETE_BUTTON_CONFIG_FRAGMENT,
           ),
+          makeFragmentData(
+            {
+              passwordLoginEnabled: true,
+            },
+            TOTP_AUTHENTICATOR_CONFIG_FRAGMENT,
+          ),
+          makeFragmentData(
+            {
+              passwordLoginEnabled: true,
+            },
+            PASSKEY_LIST_CONFIG_FRAGMENT,
+          ),
+          makeFragmentData(
+            {
+              passwordLoginEnabled: true,
+            },
+            RECOVERY_CODES_CONFIG_FRAGMENT,
+          ),
         ),
       },
     }),
@@ -5,11 +5,15 @@
 
 // @vitest-environment happy-dom
 
-import { act, screen, waitFor } from "@testing-library/react";
+import { act, screen, waitFor, within } from "@testing-library/react";
 import userEvent from "@testing-library/user-event";
 import { HttpResponse } from "msw";
 import { describe, expect, it } from "vitest";
-import { mockSetDisplayNameMutation } from "../../../src/gql/graphql";
+import {
+  mockSetDisplayNameMutation,
+  mockStartTotpEnrollmentMutation,
+  mockVerifyTotpEnrollmentMutation,
+} from "../../../src/gql/graphql";
 import { renderPage, server } from "../render";
 
 describe("Account home page", () => {
@@ -135,4 +139,79 @@ describe("Account home page", () => {
       expect(dialog).toMatchSnapshot();
     });
   });
+
+  describe("authenticator app", () => {
+    it("sets up an authenticator app", async () => {
+      server.use(
+        mockStartTotpEnrollmentMutation(({ variables: { password } }) => {
+          // Double check that the password was asked for
+          if (password !== "hunter2") {
+            throw new Error("Invalid password");
+          }
+
+          return HttpResponse.json({
+            data: {
+              startTotpEnrollment: {
+                __typename: "StartTotpEnrollmentPayload",
+                status: "STARTED",
+                secret: "JBSWY3DPEHPK3PXP",
+                uri: "otpauth://totp/example.com:alice?secret=JBSWY3DPEHPK3PXP",
+              },
+            },
+          });
+        }),
+        mockVerifyTotpEnrollmentMutation(({ variables: { code } }) =>
+          HttpResponse.json({
+            data: {
+              verifyTotpEnrollment: {
+                __typename: "VerifyTotpEnrollmentPayload",
+                status: code === "123456" ? "VERIFIED" : "INVALID_CODE",
+                user: null,
+              },
+            },
+          }),
+        ),
+      );
+
+      const user = userEvent.setup();
+      await renderPage("/");
+
+      await user.click(
+        screen.getByRole("heading", { name: "Authenticator app" }),
+      );
+      await user.click(
+        screen.getByRole("button", { name: "Set up authenticator app" }),
+      );
+
+      const passwordDialog = await screen.findByRole("dialog", {
+        name: "Confirm your password to set up an authenticator app",
+      });
+      await user.type(
+        within(passwordDialog).getByLabelText("Password"),
+        "hunter2",
+      );
+      await user.click(
+        within(passwordDialog).getByRole("button", { name: "Confirm" }),
+      );
+
+      const dialog = await screen.findByRole("dialog", {
+        name: "Set up an authenticator app",
+      });
+      expect(
+        within(dialog).getByDisplayValue("JBSWY3DPEHPK3PXP"),
+      ).toBeInTheDocument();
+
+      const codeInput = within(dialog).getByLabelText(
+        "Code from your authenticator app",
+      );
+      await user.type(codeInput, "654321");
+      await user.click(within(dialog).getByRole("button", { name: "Verify" }));
+      await waitFor(() => expect(codeInput).toBeInvalid());
+
+      await user.clear(codeInput);
+      await user.type(codeInput, "123456");
+      await user.click(within(dialog).getByRole("button", { name: "Verify" }));
+      await waitFor(() => expect(dialog).not.toBeInTheDocument());
+    });
+  });
 });
@@ -29,11 +29,20 @@ interactive_grant_type("authorization_code") := true
 
 interactive_grant_type("urn:ietf:params:oauth:grant-type:device_code") := true
 
+# Grants which issue tokens on behalf of a user, without them being present
+user_grant_type("urn:ietf:params:oauth:grant-type:jwt-bearer") := true
+
+user_grant_type(grant_type) if {
+	interactive_grant_type(grant_type)
+}
+
 # Special case to make empty scope work
 allowed_scope("") := true
 
 allowed_scope("openid") := true
 
+allowed_scope("profile") := true
+
 allowed_scope("email") := true
 
 # This grants access to Synapse's admin API endpoints
@@ -63,13 +72,24 @@ allowed_scope("urn:mas:admin") if {
 
 allowed_scope(scope) if {
 	# Grant access to the C-S API only if there is a user
-	interactive_grant_type(input.grant_type)
+	user_grant_type(input.grant_type)
 	regex.match(`^urn:matrix:org.matrix.msc2967.client:device:[A-Za-z0-9._~!$&'()*+,;=:@/-]{10,}$`, scope)
 }
 
 allowed_scope("urn:matrix:org.matrix.msc2967.client:api:*") if {
 	# Grant access to the C-S API only if there is a user
-	interactive_grant_type(input.grant_type)
+	user_grant_type(input.grant_type)
+}
+
+# If no resources are configured, clients can request any resource
+allowed_resource(_) if {
+	not data.resources
+}
+
+# Otherwise, only the clients listed for a resource can request it
+allowed_resource(resource) if {
+	some client in data.resources[resource]
+	input.client.id == client
 }
 
 # METADATA
@@ -80,6 +100,34 @@ violation contains {"msg": msg} if {
 	msg := sprintf("scope '%s' not allowed", [scope])
 }
 
+violation contains {
+	"msg": sprintf("resource '%s' not allowed", [resource]),
+	"field": "resource",
+} if {
+	some resource in input.resources
+	not allowed_resource(resource)
+}
+
+# If configured, users who logged in with a password must also have used a
+# second factor before granting access
+violation contains {"msg": "a second factor is required"} if {
+	data.require_second_factor
+	interactive_grant_type(input.grant_type)
+	password_used
+	not second_factor_used
+}
+
+password_used if "password" in input.authentication_methods
+
+# Passwords checked against an LDAP directory
+password_used if "ldap" in input.authentication_methods
+
+second_factor_used if "totp" in input.authentication_methods
+
+second_factor_used if "passkey" in input.authentication_methods
+
+second_factor_used if "recovery-code" in input.authentication_methods
+
 violation contains {"msg": "only one device scope is allowed at a time"} if {
 	scope_list := split(input.scope, " ")
 	count({scope | some scope in scope_list; startswith(scope, "urn:matrix:org.matrix.msc2967.client:device:")}) > 1
@@ -24,15 +24,14 @@ test_standard_scopes if {
 		with input.client as client
 		with input.scope as "openid email"
 
-	# Not supported yet
-	not authorization_grant.allow with input.user as user
+	authorization_grant.allow with input.user as user
 		with input.client as client
-		with input.scope as "phone"
+		with input.scope as "openid profile"
 
 	# Not supported yet
 	not authorization_grant.allow with input.user as user
 		with input.client as client
-		with input.scope as "profile"
+		with input.scope as "phone"
 }
 
 test_matrix_scopes if {
@@ -46,6 +45,11 @@ test_matrix_scopes if {
 		with input.grant_type as "urn:ietf:params:oauth:grant-type:device_code"
 		with input.scope as "urn:matrix:org.matrix.msc2967.client:api:*"
 
+	authorization_grant.allow with input.user as user
+		with input.client as client
+		with input.grant_type as "urn:ietf:params:oauth:grant-type:jwt-bearer"
+		with input.scope as "urn:matrix:org.matrix.msc2967.client:api:*"
+
 	not authorization_grant.allow with input.user as user
 		with input.client as client
 		with input.grant_type as "client_credentials"
@@ -81,6 +85,12 @@ test_device_scopes if {
 		with input.grant_type as "urn:ietf:params:oauth:grant-type:device_code"
 		with input.scope as "urn:matrix:org.matrix.msc2967.client:device:AAbbCCdd01"
 
+	# Allowed with the JWT bearer grant
+	authorization_grant.allow with input.user as user
+		with input.client as client
+		with input.grant_type as "urn:ietf:params:oauth:grant-type:jwt-bearer"
+		with input.scope as "urn:matrix:org.matrix.msc2967.client:device:AAbbCCdd01"
+
 	# Not authorization_grant.allowed for the client credentials grant
 	not authorization_grant.allow with input.client as client
 		with input.grant_type as "client_credentials"
@@ -117,6 +127,21 @@ test_synapse_admin_scopes if {
 		with input.scope as "urn:synapse:admin:*"
 }
 
+test_synapse_admin_scopes_jwt_bearer if {
+	# The user isn't present with the JWT bearer grant
+	not authorization_grant.allow with input.user as user
+		with input.client as client
+		with data.admin_users as ["john"]
+		with input.grant_type as "urn:ietf:params:oauth:grant-type:jwt-bearer"
+		with input.scope as "urn:synapse:admin:*"
+
+	not authorization_grant.allow with input.user as user
+		with input.client as client
+		with data.admin_users as ["john"]
+		with input.grant_type as "urn:ietf:params:oauth:grant-type:jwt-bearer"
+		with input.scope as "urn:mas:admin"
+}
+
 test_mas_scopes if {
 	authorization_grant.allow with input.user as user
 		with input.client as client
@@ -134,3 +159,122 @@ test_mas_scopes if {
 		with input.grant_type as "authorization_code"
 		with input.scope as "urn:mas:admin"
 }
+
+resource_client := {"id": "01H8PKNWKKRPCBW4YGH1RWV279", "client_id": "client"}
+
+test_resources if {
+	# Any resource is allowed if none are configured
+	authorization_grant.allow with input.user as user
+		with input.client as resource_client
+		with input.scope as "openid"
+		with input.resources as ["https://api.example.com/"]
+
+	authorization_grant.allow with input.user as user
+		with input.client as resource_client
+		with input.scope as "openid"
+		with input.resources as ["https://api.example.com/"]
+		with data.resources as {"https://api.example.com/": [resource_client.id]}
+
+	not authorization_grant.allow with input.user as user
+		with input.client as resource_client
+		with input.scope as "openid"
+		with input.resources as ["https://api.example.com/"]
+		with data.resources as {"https://api.example.com/": ["other-client"]}
+
+	not authorization_grant.allow with input.user as user
+		with input.client as resource_client
+		with input.scope as "openid"
+		with input.resources as ["https://other.example.com/"]
+		with data.resources as {"https://api.example.com/": [resource_client.id]}
+}
+
+test_second_factor if {
+	# Not required by default
+	authorization_grant.allow with input.user as user
+		with input.client as client
+		with input.grant_type as "authorization_code"
+		with input.scope as "openid"
+		with input.authentication_methods as ["password"]
+
+	not authorization_grant.allow with input.user as user
+		with input.client as client
+		with input.grant_type as "authorization_code"
+		with input.scope as "openid"
+		with input.authentication_methods as ["password"]
+		with data.require_second_factor as true
+
+	not authorization_grant.allow with input.user as user
+		with input.client as client
+		with input.grant_type as "urn:ietf:params:oauth:grant-type:device_code"
+		with input.scope as "openid"
+		with input.authentication_methods as ["password"]
+		with data.require_second_factor as true
+
+	authorization_grant.allow with input.user as user
+		with input.client as client
+		with input.grant_type as "authorization_code"
+		with input.scope as "openid"
+		with input.authentication_methods as ["password", "totp"]
+		with data.require_second_factor as true
+
+	authorization_grant.allow with input.user as user
+		with input.client as client
+		with input.grant_type as "authorization_code"
+		with input.scope as "openid"
+		with input.authentication_methods as ["password", "passkey"]
+		with data.require_second_factor as true
+
+	not authorization_grant.allow with input.user as user
+		with input.client as client
+		with input.grant_type as "authorization_code"
+		with input.scope as "openid"
+		with input.authentication_methods as ["ldap"]
+		with data.require_second_factor as true
+
+	authorization_grant.allow with input.user as user
+		with input.client as client
+		with input.grant_type as "authorization_code"
+		with input.scope as "openid"
+		with input.authentication_methods as ["ldap", "totp"]
+		with data.require_second_factor as true
+
+	# Recovery codes stand in for a lost second factor
+	authorization_grant.allow with input.user as user
+		with input.client as client
+		with input.grant_type as "authorization_code"
+		with input.scope as "openid"
+		with input.authentication_methods as ["password", "recovery-code"]
+		with data.require_second_factor as true
+
+	# Passwordless passkey logins already verify the user
+	authorization_grant.allow with input.user as user
+		with input.client as client
+		with input.grant_type as "authorization_code"
+		with input.scope as "openid"
+		with input.authentication_methods as ["passkey"]
+		with data.require_second_factor as true
+
+	# Email code logins are only offered to users without a password
+	authorization_grant.allow with input.user as user
+		with input.client as client
+		with input.grant_type as "authorization_code"
+		with input.scope as "openid"
+		with input.authentication_methods as ["email-code"]
+		with data.require_second_factor as true
+
+	# Upstream logins are left to the upstream provider
+	authorization_grant.allow with input.user as user
+		with input.client as client
+		with input.grant_type as "authorization_code"
+		with input.scope as "openid"
+		with input.authentication_methods as ["upstream-oauth2"]
+		with data.require_second_factor as true
+
+	# Non-interactive grants don't have a browser session
+	authorization_grant.allow with input.user as user
+		with input.client as client
+		with input.grant_type as "urn:ietf:params:oauth:grant-type:jwt-bearer"
+		with input.scope as "openid"
+		with input.authentication_methods as []
+		with data.require_second_factor as true
+}
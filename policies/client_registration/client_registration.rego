@@ -185,6 +185,11 @@ violation contains {"msg": "client_credentials grant_type requires some form of
 	is_public_client
 }
 
+violation contains {"msg": "token-exchange grant_type requires some form of client authentication"} if {
+	uses_grant_type("urn:ietf:params:oauth:grant-type:token-exchange", input.client_metadata)
+	is_public_client
+}
+
 violation contains {"msg": "missing redirect_uris"} if {
 	requires_redirect_uris
 	not input.client_metadata.redirect_uris
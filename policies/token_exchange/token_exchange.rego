@@ -0,0 +1,56 @@
+# METADATA
+# schemas:
+#   - input: schema["token_exchange_input"]
+package token_exchange
+
+import rego.v1
+
+import data.common
+
+default allow := false
+
+allow if {
+	count(violation) == 0
+}
+
+# Only clients explicitly listed are allowed to exchange tokens
+allowed_client(client) if {
+	some allowed_client in data.token_exchange.allowed_clients
+	client.id == allowed_client
+}
+
+# If no list of audiences is configured, any audience is allowed
+allowed_audience(_) if {
+	not data.token_exchange.allowed_audiences
+}
+
+allowed_audience(audience) if {
+	some allowed_audience in data.token_exchange.allowed_audiences
+	audience == allowed_audience
+}
+
+# METADATA
+# entrypoint: true
+violation contains {"msg": "client is not allowed to exchange tokens"} if {
+	not allowed_client(input.client)
+}
+
+violation contains {"msg": "actor token must have been issued to the client"} if {
+	input.actor_client
+	input.actor_client.id != input.client.id
+}
+
+violation contains {
+	"msg": sprintf("audience '%s' not allowed", [input.audience]),
+	"field": "audience",
+} if {
+	input.audience
+	not allowed_audience(input.audience)
+}
+
+violation contains {"msg": sprintf(
+	"Requester [%s] isn't allowed to do this action",
+	[common.format_requester(input.requester)],
+)} if {
+	common.requester_banned(input.requester, data.requester)
+}
@@ -0,0 +1,66 @@
+package token_exchange_test
+
+import data.token_exchange
+import rego.v1
+
+user := {"username": "john"}
+
+client := {"id": "01JRVQ6N3C7S2K8VZ4X1MEW9PB"}
+
+other_client := {"id": "01JRVQ7AXJ0P9KGW3T5D6YCHNQ"}
+
+test_allowed_clients if {
+	token_exchange.allow with input.user as user
+		with input.client as client
+		with input.scope as "openid"
+		with data.token_exchange.allowed_clients as [client.id]
+
+	not token_exchange.allow with input.user as user
+		with input.client as other_client
+		with input.scope as "openid"
+		with data.token_exchange.allowed_clients as [client.id]
+
+	# No client is allowed by default
+	not token_exchange.allow with input.user as user
+		with input.client as client
+		with input.scope as "openid"
+}
+
+test_allowed_audiences if {
+	token_exchange.allow with input.user as user
+		with input.client as client
+		with input.audience as "https://backend.example.com/"
+		with data.token_exchange.allowed_clients as [client.id]
+
+	token_exchange.allow with input.user as user
+		with input.client as client
+		with input.audience as "https://backend.example.com/"
+		with data.token_exchange.allowed_clients as [client.id]
+		with data.token_exchange.allowed_audiences as ["https://backend.example.com/"]
+
+	not token_exchange.allow with input.user as user
+		with input.client as client
+		with input.audience as "https://other.example.com/"
+		with data.token_exchange.allowed_clients as [client.id]
+		with data.token_exchange.allowed_audiences as ["https://backend.example.com/"]
+}
+
+test_actor if {
+	token_exchange.allow with input.user as user
+		with input.client as client
+		with input.actor_client as client
+		with data.token_exchange.allowed_clients as [client.id]
+
+	not token_exchange.allow with input.user as user
+		with input.client as client
+		with input.actor_client as other_client
+		with data.token_exchange.allowed_clients as [client.id]
+}
+
+test_requester_banned if {
+	not token_exchange.allow with input.user as user
+		with input.client as client
+		with input.requester as {"ip_address": "1.2.3.4"}
+		with data.token_exchange.allowed_clients as [client.id]
+		with data.requester.banned_ips as ["1.2.3.4"]
+}